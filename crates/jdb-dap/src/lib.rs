@@ -0,0 +1,11 @@
+//! # `jdb-dap`
+//!
+//! A [Debug Adapter Protocol](https://microsoft.github.io/debug-adapter-protocol/) server for java
+//! virtual machines, built on [jdi-rs](jdi_rs).
+//!
+//! Editors speaking the protocol [launch or attach](server::serve) to a VM, set breakpoints, and
+//! inspect threads, frames and variables through it. Its messages are [framed](protocol) by a
+//! `Content-Length` header.
+
+pub mod protocol;
+pub mod server;
@@ -0,0 +1,31 @@
+//! The `jdb-dap` debug adapter
+
+use clap::Parser;
+use tokio::net::TcpListener;
+use tracing::warn;
+
+/// A Debug Adapter Protocol server for java virtual machines
+#[derive(Debug, Parser)]
+#[command(name = "jdb-dap", version)]
+struct Options {
+    /// Serves clients connecting to a port, one at a time, rather than over stdin and stdout
+    #[arg(long)]
+    port: Option<u16>,
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    let options = Options::parse();
+    let Some(port) = options.port else {
+        jdb_dap::server::serve(tokio::io::stdin(), tokio::io::stdout()).await?;
+        return Ok(());
+    };
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let (reader, writer) = stream.into_split();
+        if let Err(e) = jdb_dap::server::serve(reader, writer).await {
+            warn!("session ended with an error: {e}");
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! The framing of Debug Adapter Protocol messages, each a JSON body preceded by a
+//! `Content-Length` header
+
+use serde_json::Value;
+use std::io;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The header giving the length of a message's body in bytes
+const CONTENT_LENGTH: &str = "Content-Length";
+
+/// The longest body accepted, so that a peer can't make the adapter allocate without bound
+const MAX_CONTENT_LENGTH: usize = 16 * 1024 * 1024;
+
+/// Reads the next message, or `None` if the stream ends between messages
+pub async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return match length {
+                None => Ok(None),
+                Some(_) => Err(io::ErrorKind::UnexpectedEof.into()),
+            };
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case(CONTENT_LENGTH) {
+                length = Some(value.trim().parse::<usize>().map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid content length {value:?}"),
+                    )
+                })?);
+            }
+        }
+    }
+    let length = length.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message has no {CONTENT_LENGTH} header"),
+        )
+    })?;
+    if length > MAX_CONTENT_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{CONTENT_LENGTH} {length} is longer than the limit of {MAX_CONTENT_LENGTH}"),
+        ));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// Writes a message, flushing it
+pub async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &Value,
+) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    writer
+        .write_all(format!("{CONTENT_LENGTH}: {}\r\n\r\n", body.len()).as_bytes())
+        .await?;
+    writer.write_all(&body).await?;
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let mut buffer = vec![];
+        let first = json!({"seq": 1, "type": "request", "command": "initialize"});
+        let second = json!({"seq": 2, "type": "event", "event": "output", "body": {"output": "é"}});
+        write_message(&mut buffer, &first).await.unwrap();
+        write_message(&mut buffer, &second).await.unwrap();
+
+        let mut reader = buffer.as_slice();
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(first));
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(second));
+        assert_eq!(read_message(&mut reader).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_messages() {
+        let mut reader = "Content-Type: application/json\r\n\r\n{}".as_bytes();
+        assert!(read_message(&mut reader).await.is_err());
+        let mut reader = "Content-Length: 10\r\n\r\n{}".as_bytes();
+        assert!(read_message(&mut reader).await.is_err());
+        let mut reader = "content-length: 2\r\n\r\n{}".as_bytes();
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(json!({})));
+        let mut reader = "Content-Length: 99999999999\r\n\r\n{}".as_bytes();
+        let error = read_message(&mut reader).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
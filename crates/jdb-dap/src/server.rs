@@ -0,0 +1,797 @@
+//! Serves a Debug Adapter Protocol session, mapping its requests onto a connected
+//! [VirtualMachine]
+
+use crate::protocol::{read_message, write_message};
+use futures::StreamExt;
+use jdb::format::format_value;
+use jdb::launch::Launched;
+use jdi_rs::event::{Event, EventSet};
+use jdi_rs::expr::{EvalError, Expression, ParseError};
+use jdi_rs::request::{
+    BreakpointOptions, EventRequestError, ExceptionBreakpoint, HitCondition, LineBreakpoint,
+    LogMessage, StepOptions,
+};
+use jdi_rs::{
+    ObjectReference, StackFrame, ThreadReference, Value, VirtualMachine, VirtualMachineManager,
+};
+use jdwp_types::{ObjectId, StepDepth, StepSize, SuspendPolicy, Tag};
+use serde_json::{json, Value as Json};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite, BufReader};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::{debug, warn};
+
+/// A request couldn't be carried out, which is reported in its response
+#[derive(Debug, Error)]
+pub enum DapError {
+    /// A required argument of the request is missing or has the wrong type
+    #[error("missing argument {0}")]
+    MissingArgument(&'static str),
+    /// The request needs a connected virtual machine
+    #[error("not connected to a java virtual machine, launch or attach first")]
+    NotConnected,
+    /// The request isn't supported
+    #[error("unsupported request {0}")]
+    Unsupported(String),
+    /// No thread has the given id
+    #[error("unknown thread {0}")]
+    UnknownThread(i64),
+    /// No frame has the given id, which happens once the threads are resumed
+    #[error("unknown frame {0}")]
+    UnknownFrame(i64),
+    /// No variables have the given reference, which happens once the threads are resumed
+    #[error("unknown variables reference {0}")]
+    UnknownReference(i64),
+    #[error(transparent)]
+    Eval(#[from] EvalError),
+    #[error(transparent)]
+    Request(#[from] EventRequestError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl From<ParseError> for DapError {
+    fn from(value: ParseError) -> Self {
+        DapError::Eval(value.into())
+    }
+}
+
+/// Sends responses and events to the client, numbering them in the order they're sent
+#[derive(Debug, Clone)]
+struct Client {
+    messages: UnboundedSender<Json>,
+}
+
+impl Client {
+    /// Responds to a request with the body of its result, or the message of its error
+    fn respond(&self, request: &Json, result: Result<Json, DapError>) {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(body) => response["body"] = body,
+            Err(e) => {
+                debug!("{} failed: {e}", request["command"]);
+                response["message"] = json!(e.to_string());
+            }
+        }
+        let _ = self.messages.send(response);
+    }
+
+    /// Sends an event
+    fn event(&self, event: &str, body: Json) {
+        let _ = self.messages.send(json!({
+            "type": "event",
+            "event": event,
+            "body": body,
+        }));
+    }
+
+    /// Sends an `output` event
+    fn output(&self, category: &str, output: impl Into<String>) {
+        let mut output = output.into();
+        if !output.ends_with('\n') {
+            output.push('\n');
+        }
+        self.event("output", json!({"category": category, "output": output}));
+    }
+}
+
+/// What a `variablesReference` refers to
+enum Reference<VM: VirtualMachine + ?Sized> {
+    /// The local variables of a frame, and `this`
+    Locals(StackFrame<VM>),
+    /// The fields of an object, or the components of an array
+    Object(ObjectReference<VM>),
+}
+
+/// Serves a debugging session to a client, until it disconnects.
+///
+/// The client launches or attaches to a virtual machine first, after which the `initialized`
+/// event is sent and breakpoints can be set. Once the client is done configuring, the virtual
+/// machine is resumed.
+pub async fn serve<R, W>(reader: R, writer: W) -> io::Result<()>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (request_sender, mut requests) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(reader);
+        loop {
+            match read_message(&mut reader).await {
+                Ok(Some(request)) => {
+                    if request_sender.send(request).is_err() {
+                        break;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("could not read a message: {e}");
+                    break;
+                }
+            }
+        }
+    });
+    let (messages, mut outgoing) = mpsc::unbounded_channel::<Json>();
+    let writing = tokio::spawn(async move {
+        let mut writer = writer;
+        let mut seq = 1;
+        while let Some(mut message) = outgoing.recv().await {
+            message["seq"] = json!(seq);
+            seq += 1;
+            write_message(&mut writer, &message).await?;
+        }
+        Ok::<_, io::Error>(())
+    });
+    let client = Client { messages };
+
+    while let Some(request) = requests.recv().await {
+        let arguments = &request["arguments"];
+        match request["command"].as_str().unwrap_or_default() {
+            "initialize" => client.respond(&request, Ok(capabilities())),
+            "launch" | "attach" => match connect(&request, &client).await {
+                Ok((vm, launched)) => {
+                    let source_paths = arguments["sourcePaths"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|path| path.as_str().map(PathBuf::from))
+                        .collect();
+                    client.respond(&request, Ok(json!({})));
+                    client.event("initialized", json!({}));
+                    let debugger = Debugger::new(vm, client.clone(), source_paths, launched);
+                    debugger.run(&mut requests).await;
+                    break;
+                }
+                Err(e) => client.respond(&request, Err(e)),
+            },
+            "disconnect" => {
+                client.respond(&request, Ok(json!({})));
+                break;
+            }
+            _ => client.respond(&request, Err(DapError::NotConnected)),
+        }
+    }
+    drop(client);
+    writing.await.map_err(io::Error::other)?
+}
+
+/// The capabilities of this debug adapter, sent in response to `initialize`
+fn capabilities() -> Json {
+    json!({
+        "supportsConfigurationDoneRequest": true,
+        "supportsConditionalBreakpoints": true,
+        "supportsHitConditionalBreakpoints": true,
+        "supportsLogPoints": true,
+        "supportsEvaluateForHovers": true,
+        "supportTerminateDebuggee": true,
+        "exceptionBreakpointFilters": [
+            {"filter": "caught", "label": "Caught Exceptions", "default": false},
+            {"filter": "uncaught", "label": "Uncaught Exceptions", "default": true},
+        ],
+    })
+}
+
+/// Connects to the virtual machine a `launch` or `attach` request asks for, launching it first
+/// for `launch`
+async fn connect(
+    request: &Json,
+    client: &Client,
+) -> Result<(Arc<impl VirtualMachine>, Option<Launched>), DapError> {
+    let arguments = &request["arguments"];
+    if request["command"] == "attach" {
+        let host = arguments["hostName"].as_str().unwrap_or("localhost");
+        let port = arguments["port"]
+            .as_u64()
+            .ok_or(DapError::MissingArgument("port"))?;
+        let vm = VirtualMachineManager::attach(format!("{host}:{port}")).await?;
+        return Ok((vm, None));
+    }
+
+    let main_class = arguments["mainClass"]
+        .as_str()
+        .ok_or(DapError::MissingArgument("mainClass"))?;
+    let class_path = match (&arguments["classPaths"], &arguments["classPath"]) {
+        (Json::Array(paths), _) => Some(
+            std::env::join_paths(paths.iter().filter_map(Json::as_str))
+                .map_err(io::Error::other)?,
+        ),
+        (_, Json::String(path)) => Some(OsString::from(path)),
+        _ => None,
+    };
+    let program_arguments = match &arguments["args"] {
+        Json::Array(args) => args
+            .iter()
+            .filter_map(|arg| arg.as_str().map(str::to_string))
+            .collect(),
+        Json::String(args) => args.split_whitespace().map(str::to_string).collect(),
+        _ => vec![],
+    };
+    let (output, mut lines) = mpsc::unbounded_channel::<String>();
+    let launched =
+        Launched::new(class_path.as_ref(), main_class, &program_arguments, output).await?;
+    let client = client.clone();
+    tokio::spawn(async move {
+        while let Some(line) = lines.recv().await {
+            client.output("stdout", line);
+        }
+    });
+    let vm = VirtualMachineManager::attach(format!("127.0.0.1:{}", launched.port())).await?;
+    Ok((vm, Some(launched)))
+}
+
+/// Carries out the requests of a client once connected to a virtual machine
+struct Debugger<VM: VirtualMachine + ?Sized> {
+    vm: Arc<VM>,
+    client: Client,
+    source_paths: Vec<PathBuf>,
+    launched: Option<Launched>,
+    /// The line breakpoints set in each source file, by its path relative to the source root
+    breakpoints: HashMap<String, Vec<LineBreakpoint<VM>>>,
+    /// The absolute paths of the source files breakpoints were set in, by their relative paths
+    sources: HashMap<String, PathBuf>,
+    exception_breakpoints: Vec<ExceptionBreakpoint<VM>>,
+    /// The frames listed since the threads were suspended, whose ids are their indices plus one
+    frames: Vec<StackFrame<VM>>,
+    /// What each `variablesReference` refers to, at its index minus one
+    references: Vec<Reference<VM>>,
+    objects: HashMap<ObjectId, i64>,
+    /// The event set that started the virtual machine, until the client is done configuring
+    start: Option<EventSet<VM>>,
+    configured: bool,
+    next_breakpoint_id: i64,
+    disconnected: bool,
+}
+
+impl<VM: VirtualMachine + ?Sized> Debugger<VM> {
+    fn new(
+        vm: Arc<VM>,
+        client: Client,
+        source_paths: Vec<PathBuf>,
+        launched: Option<Launched>,
+    ) -> Self {
+        Self {
+            vm,
+            client,
+            source_paths,
+            launched,
+            breakpoints: HashMap::new(),
+            sources: HashMap::new(),
+            exception_breakpoints: vec![],
+            frames: vec![],
+            references: vec![],
+            objects: HashMap::new(),
+            start: None,
+            configured: false,
+            next_breakpoint_id: 1,
+            disconnected: false,
+        }
+    }
+
+    /// Handles requests and events until the client or the virtual machine disconnects
+    async fn run(mut self, requests: &mut UnboundedReceiver<Json>) {
+        let (event_sender, mut events) = mpsc::unbounded_channel();
+        let mut queue = self.vm.event_queue();
+        tokio::spawn(async move {
+            while let Some(set) = queue.next().await {
+                if event_sender.send(set).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut logpoints = self.vm.event_request_manager().logpoint_messages();
+
+        while !self.disconnected {
+            tokio::select! {
+                request = requests.recv() => {
+                    let Some(request) = request else {
+                        break;
+                    };
+                    let result = self.handle(&request).await;
+                    self.client.respond(&request, result);
+                }
+                Some(set) = events.recv() => {
+                    if let Err(e) = self.handle_event_set(set).await {
+                        warn!("could not handle an event: {e}");
+                    }
+                }
+                message = logpoints.recv() => match message {
+                    Ok(message) => self.client.output("console", message.message()),
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+    }
+
+    async fn handle(&mut self, request: &Json) -> Result<Json, DapError> {
+        let arguments = &request["arguments"];
+        match request["command"].as_str().unwrap_or_default() {
+            "initialize" => Ok(capabilities()),
+            "setBreakpoints" => self.set_breakpoints(arguments).await,
+            "setExceptionBreakpoints" => self.set_exception_breakpoints(arguments).await,
+            "configurationDone" => {
+                self.configured = true;
+                if let Some(start) = self.start.take() {
+                    start.resume().await?;
+                }
+                Ok(json!({}))
+            }
+            "threads" => {
+                let mut threads = vec![];
+                for thread in self.vm.all_threads().await? {
+                    threads.push(json!({
+                        "id": thread.id().get(),
+                        "name": thread.name().await?,
+                    }));
+                }
+                Ok(json!({ "threads": threads }))
+            }
+            "stackTrace" => self.stack_trace(arguments).await,
+            "scopes" => {
+                let frame = self.frame(arguments)?.clone();
+                Ok(json!({
+                    "scopes": [{
+                        "name": "Locals",
+                        "presentationHint": "locals",
+                        "variablesReference": self.reference(Reference::Locals(frame)),
+                        "expensive": false,
+                    }],
+                }))
+            }
+            "variables" => self.variables(arguments).await,
+            "evaluate" => {
+                let expression = arguments["expression"]
+                    .as_str()
+                    .ok_or(DapError::MissingArgument("expression"))?;
+                let frame = self.frame(arguments)?.clone();
+                let value = Expression::parse(expression)?.evaluate(&frame).await?;
+                let (result, type_name, reference) = self.describe(&value).await?;
+                Ok(json!({
+                    "result": result,
+                    "type": type_name,
+                    "variablesReference": reference,
+                }))
+            }
+            "continue" => {
+                self.resume().await?;
+                Ok(json!({ "allThreadsContinued": true }))
+            }
+            "next" => self.step(arguments, StepDepth::Over).await,
+            "stepIn" => self.step(arguments, StepDepth::Into).await,
+            "stepOut" => self.step(arguments, StepDepth::Out).await,
+            "pause" => {
+                let thread = self.thread(arguments).await?;
+                self.vm.suspend().await?;
+                self.client.event(
+                    "stopped",
+                    json!({
+                        "reason": "pause",
+                        "threadId": thread.id().get(),
+                        "allThreadsStopped": true,
+                    }),
+                );
+                Ok(json!({}))
+            }
+            "disconnect" => {
+                let terminate = arguments["terminateDebuggee"]
+                    .as_bool()
+                    .unwrap_or(self.launched.is_some());
+                if terminate {
+                    self.launched = None;
+                } else {
+                    self.vm
+                        .event_request_manager()
+                        .delete_all_breakpoints()
+                        .await?;
+                    self.vm.resume().await?;
+                }
+                self.disconnected = true;
+                Ok(json!({}))
+            }
+            command => Err(DapError::Unsupported(command.to_string())),
+        }
+    }
+
+    /// Replaces the line breakpoints of a source file
+    async fn set_breakpoints(&mut self, arguments: &Json) -> Result<Json, DapError> {
+        let path = arguments["source"]["path"]
+            .as_str()
+            .ok_or(DapError::MissingArgument("source.path"))?;
+        let source_path = self.relative_source_path(Path::new(path));
+        self.sources
+            .insert(source_path.clone(), PathBuf::from(path));
+        let manager = self.vm.event_request_manager();
+        for breakpoint in self.breakpoints.remove(&source_path).unwrap_or_default() {
+            manager.delete_line_breakpoint(&breakpoint).await?;
+        }
+
+        let mut set = vec![];
+        let mut breakpoints = vec![];
+        for requested in arguments["breakpoints"].as_array().into_iter().flatten() {
+            let line = requested["line"]
+                .as_i64()
+                .ok_or(DapError::MissingArgument("breakpoints.line"))?;
+            let id = self.next_breakpoint_id;
+            self.next_breakpoint_id += 1;
+            let breakpoint = match breakpoint_options(requested) {
+                Ok(options) => {
+                    manager
+                        .set_breakpoint_with(&source_path, line as i32, options)
+                        .await?
+                }
+                Err(e) => {
+                    breakpoints.push(json!({
+                        "id": id,
+                        "verified": false,
+                        "line": line,
+                        "message": e.to_string(),
+                    }));
+                    continue;
+                }
+            };
+            breakpoints.push(json!({
+                "id": id,
+                "verified": breakpoint.is_resolved(),
+                "line": line,
+            }));
+            set.push(breakpoint);
+        }
+        self.breakpoints.insert(source_path, set);
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    /// Gets the path of a source file relative to its source root, such as
+    /// `com/acme/Foo.java`, from one of the source paths or else the file's package declaration
+    fn relative_source_path(&self, path: &Path) -> String {
+        for root in &self.source_paths {
+            if let Ok(relative) = path.strip_prefix(root) {
+                return relative
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+            }
+        }
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let package = std::fs::read_to_string(path).ok().and_then(|source| {
+            source.lines().find_map(|line| {
+                let package = line.trim().strip_prefix("package ")?;
+                Some(package.trim_end_matches(';').trim().replace('.', "/"))
+            })
+        });
+        match package {
+            Some(package) => format!("{package}/{file_name}"),
+            None => file_name,
+        }
+    }
+
+    /// Finds the source file a class is compiled from
+    fn find_source(&self, class: &str) -> Option<PathBuf> {
+        let source_path = jdb::format::source_path(class);
+        if let Some(path) = self.sources.get(&source_path) {
+            return Some(path.clone());
+        }
+        self.source_paths
+            .iter()
+            .map(|root| root.join(&source_path))
+            .find(|path| path.is_file())
+    }
+
+    /// Replaces the exception breakpoints with the filters that are enabled
+    async fn set_exception_breakpoints(&mut self, arguments: &Json) -> Result<Json, DapError> {
+        let manager = self.vm.event_request_manager();
+        for breakpoint in std::mem::take(&mut self.exception_breakpoints) {
+            manager.delete_exception_breakpoint(&breakpoint).await?;
+        }
+        let filters = arguments["filters"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Json::as_str)
+            .collect::<Vec<_>>();
+        let caught = filters.contains(&"caught");
+        let uncaught = filters.contains(&"uncaught");
+        if caught || uncaught {
+            let breakpoint = manager.break_on_exception("*", caught, uncaught).await?;
+            self.exception_breakpoints.push(breakpoint);
+        }
+        Ok(json!({}))
+    }
+
+    /// Finds the thread a request's `threadId` refers to
+    async fn thread(&self, arguments: &Json) -> Result<ThreadReference<VM>, DapError> {
+        let id = arguments["threadId"]
+            .as_i64()
+            .ok_or(DapError::MissingArgument("threadId"))?;
+        self.vm
+            .all_threads()
+            .await?
+            .into_iter()
+            .find(|thread| thread.id().get() as i64 == id)
+            .ok_or(DapError::UnknownThread(id))
+    }
+
+    /// Finds the frame a request's `frameId` refers to
+    fn frame(&self, arguments: &Json) -> Result<&StackFrame<VM>, DapError> {
+        let id = arguments["frameId"]
+            .as_i64()
+            .ok_or(DapError::MissingArgument("frameId"))?;
+        usize::try_from(id - 1)
+            .ok()
+            .and_then(|index| self.frames.get(index))
+            .ok_or(DapError::UnknownFrame(id))
+    }
+
+    async fn stack_trace(&mut self, arguments: &Json) -> Result<Json, DapError> {
+        let thread = self.thread(arguments).await?;
+        let frames = thread.frames().await?;
+        let total = frames.len();
+        let start = arguments["startFrame"].as_u64().unwrap_or(0) as usize;
+        let levels = match arguments["levels"].as_u64().unwrap_or(0) as usize {
+            0 => total,
+            levels => levels,
+        };
+        let mut stack_frames = vec![];
+        for frame in frames.into_iter().skip(start).take(levels) {
+            let location = frame.location();
+            let ref_type = location.declaring_type().await?;
+            let method = location.method().await?;
+            let class = ref_type.name();
+            let mut stack_frame = json!({
+                "id": self.frames.len() + 1,
+                "name": format!("{class}.{}", method.name()),
+                "line": location.line_number().await?.unwrap_or(0),
+                "column": 1,
+            });
+            if let Some(source_name) = ref_type.source_name().await? {
+                let mut source = json!({ "name": source_name });
+                if let Some(path) = self.find_source(&class) {
+                    source["path"] = json!(path);
+                }
+                stack_frame["source"] = source;
+            }
+            stack_frames.push(stack_frame);
+            self.frames.push(frame);
+        }
+        Ok(json!({
+            "stackFrames": stack_frames,
+            "totalFrames": total,
+        }))
+    }
+
+    /// Gets a new `variablesReference`
+    fn reference(&mut self, reference: Reference<VM>) -> i64 {
+        self.references.push(reference);
+        self.references.len() as i64
+    }
+
+    /// Gets the `variablesReference` of an object, which is the same every time until the
+    /// threads are resumed
+    fn object_reference(&mut self, object: &ObjectReference<VM>) -> i64 {
+        if let Some(reference) = self.objects.get(&object.id()) {
+            return *reference;
+        }
+        let reference = self.reference(Reference::Object(object.clone()));
+        self.objects.insert(object.id(), reference);
+        reference
+    }
+
+    /// Describes a value as its formatted text, its type's name, and the `variablesReference`
+    /// of its fields or components, if it has any
+    async fn describe(&mut self, value: &Value<VM>) -> io::Result<(String, String, i64)> {
+        let text = format_value(value).await?;
+        let (type_name, reference) = match value {
+            Value::Boolean(_) => ("boolean".to_string(), 0),
+            Value::Byte(_) => ("byte".to_string(), 0),
+            Value::Char(_) => ("char".to_string(), 0),
+            Value::Short(_) => ("short".to_string(), 0),
+            Value::Int(_) => ("int".to_string(), 0),
+            Value::Long(_) => ("long".to_string(), 0),
+            Value::Float(_) => ("float".to_string(), 0),
+            Value::Double(_) => ("double".to_string(), 0),
+            Value::Void => ("void".to_string(), 0),
+            Value::Object(None) => ("null".to_string(), 0),
+            Value::Object(Some(object)) => {
+                let type_name = object.reference_type().await?.name();
+                let reference = match object.tag() {
+                    Tag::String => 0,
+                    _ => self.object_reference(object),
+                };
+                (type_name, reference)
+            }
+        };
+        Ok((text, type_name, reference))
+    }
+
+    async fn variables(&mut self, arguments: &Json) -> Result<Json, DapError> {
+        let id = arguments["variablesReference"]
+            .as_i64()
+            .ok_or(DapError::MissingArgument("variablesReference"))?;
+        let reference = usize::try_from(id - 1)
+            .ok()
+            .and_then(|index| self.references.get(index))
+            .ok_or(DapError::UnknownReference(id))?;
+        let mut named = vec![];
+        match reference {
+            Reference::Locals(frame) => {
+                let frame = frame.clone();
+                if let Some(this) = frame.this_object().await? {
+                    named.push(("this".to_string(), Value::Object(Some(this))));
+                }
+                let variables = frame.visible_variables().await?;
+                let values = frame.get_values(&variables).await?;
+                for (variable, value) in variables.iter().zip(values) {
+                    named.push((variable.name().to_string(), value));
+                }
+            }
+            Reference::Object(object) if object.tag() == Tag::Array => {
+                let object = object.clone();
+                let length = object.array_length().await?;
+                for (index, value) in object
+                    .array_values(0, length)
+                    .await?
+                    .into_iter()
+                    .enumerate()
+                {
+                    named.push((format!("[{index}]"), value));
+                }
+            }
+            Reference::Object(object) => {
+                let object = object.clone();
+                let fields = object
+                    .reference_type()
+                    .await?
+                    .all_fields()
+                    .await?
+                    .into_iter()
+                    .filter(|field| !field.is_static())
+                    .collect::<Vec<_>>();
+                let values = object.get_values(&fields).await?;
+                for (field, value) in fields.iter().zip(values) {
+                    named.push((field.name().to_string(), value));
+                }
+            }
+        }
+        let mut variables = vec![];
+        for (name, value) in named {
+            let (text, type_name, reference) = self.describe(&value).await?;
+            variables.push(json!({
+                "name": name,
+                "value": text,
+                "type": type_name,
+                "variablesReference": reference,
+            }));
+        }
+        Ok(json!({ "variables": variables }))
+    }
+
+    /// Resumes every thread, forgetting the frames and variables of the suspended threads
+    async fn resume(&mut self) -> io::Result<()> {
+        self.frames.clear();
+        self.references.clear();
+        self.objects.clear();
+        self.vm.resume().await
+    }
+
+    /// Steps a thread by a line, resuming every thread until the step completes. The step's
+    /// event is sent as a `stopped` event once it's removed from the event queue.
+    async fn step(&mut self, arguments: &Json, depth: StepDepth) -> Result<Json, DapError> {
+        let thread = self.thread(arguments).await?;
+        let request = thread
+            .prepare_step(StepSize::Line, depth, &StepOptions::default())
+            .await?;
+        request.enable().await?;
+        self.resume().await?;
+        Ok(json!({}))
+    }
+
+    /// Sends the events of an event set to the client, resuming it unless it stopped a thread
+    async fn handle_event_set(&mut self, set: EventSet<VM>) -> Result<(), DapError> {
+        let all_stopped = set.suspend_policy() == SuspendPolicy::All;
+        let mut stopped = false;
+        for event in set.events() {
+            let (reason, thread, text) = match event {
+                Event::VmStart(_) if self.configured => continue,
+                Event::VmStart(_) => {
+                    stopped = true;
+                    continue;
+                }
+                Event::VmDeath(_) => {
+                    self.client.event("terminated", json!({}));
+                    continue;
+                }
+                Event::VmDisconnect(_) => {
+                    self.client.event("terminated", json!({}));
+                    self.disconnected = true;
+                    continue;
+                }
+                Event::Breakpoint(event) => ("breakpoint", event.thread(), None),
+                Event::Step(event) => {
+                    if let Some(request) = event.request() {
+                        let manager = self.vm.event_request_manager();
+                        manager.delete_event_request(request.clone()).await?;
+                    }
+                    ("step", event.thread(), None)
+                }
+                Event::Exception(event) => {
+                    let name = event.exception().reference_type().await?.name();
+                    let text = match event.message().await? {
+                        Some(message) => format!("{name}: {message}"),
+                        None => name,
+                    };
+                    ("exception", event.thread(), Some(text))
+                }
+                _ => continue,
+            };
+            stopped = true;
+            let mut body = json!({
+                "reason": reason,
+                "threadId": thread.id().get(),
+                "allThreadsStopped": all_stopped,
+            });
+            if let Some(text) = text {
+                body["text"] = json!(text);
+            }
+            self.client.event("stopped", body);
+        }
+        let starting = set
+            .events()
+            .iter()
+            .any(|event| matches!(event, Event::VmStart(_)));
+        if starting && !self.configured {
+            self.start = Some(set);
+        } else if !stopped && set.suspend_policy() != SuspendPolicy::None {
+            set.resume().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Gets the options of a breakpoint requested by `setBreakpoints`
+fn breakpoint_options(requested: &Json) -> Result<BreakpointOptions, ParseError> {
+    let mut options = BreakpointOptions::new();
+    if let Some(condition) = requested["condition"].as_str() {
+        options = options.when(Expression::parse(condition)?);
+    }
+    if let Some(hit_condition) = requested["hitCondition"].as_str() {
+        options = options.on_hit(hit_condition.parse::<HitCondition>()?);
+    }
+    if let Some(log_message) = requested["logMessage"].as_str() {
+        options = options.log(LogMessage::parse(log_message)?);
+    }
+    Ok(options)
+}
@@ -0,0 +1,283 @@
+use jdb_dap::protocol::{read_message, write_message};
+use jdb_dap::server::serve;
+use jdb_test_fixtures::{class_path, JavaInstance};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::Duration;
+use test_log::test;
+use tokio::io::{BufReader, DuplexStream, ReadHalf, WriteHalf};
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A scripted client of a debug adapter served in the background
+struct Client {
+    reader: BufReader<ReadHalf<DuplexStream>>,
+    writer: WriteHalf<DuplexStream>,
+    seq: i64,
+    /// Events received while waiting for a response
+    events: VecDeque<Value>,
+    server: JoinHandle<std::io::Result<()>>,
+}
+
+impl Client {
+    fn new() -> Self {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let (server_reader, server_writer) = tokio::io::split(server);
+        // the futures of jdi-rs aren't `Send`, so the server runs on a runtime of its own
+        let server = tokio::task::spawn_blocking(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?
+                .block_on(serve(server_reader, server_writer))
+        });
+        let (reader, writer) = tokio::io::split(client);
+        Self {
+            reader: BufReader::new(reader),
+            writer,
+            seq: 0,
+            events: VecDeque::new(),
+            server,
+        }
+    }
+
+    async fn next_message(&mut self) -> eyre::Result<Value> {
+        let message = timeout(TIMEOUT, read_message(&mut self.reader)).await??;
+        message.ok_or_else(|| eyre::eyre!("the server hung up"))
+    }
+
+    /// Sends a request and waits for its response
+    async fn request(&mut self, command: &str, arguments: Value) -> eyre::Result<Value> {
+        self.seq += 1;
+        let request = json!({
+            "seq": self.seq,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        });
+        write_message(&mut self.writer, &request).await?;
+        loop {
+            let message = self.next_message().await?;
+            if message["type"] == "event" {
+                self.events.push_back(message);
+            } else if message["request_seq"] == self.seq {
+                assert_eq!(message["command"], command);
+                return Ok(message);
+            }
+        }
+    }
+
+    /// Sends a request that must succeed, and gets the body of its response
+    async fn success(&mut self, command: &str, arguments: Value) -> eyre::Result<Value> {
+        let response = self.request(command, arguments).await?;
+        assert_eq!(response["success"], true, "{response}");
+        Ok(response["body"].clone())
+    }
+
+    /// Waits for an event, skipping any others before it
+    async fn event(&mut self, event: &str) -> eyre::Result<Value> {
+        loop {
+            let message = match self.events.pop_front() {
+                Some(message) => message,
+                None => self.next_message().await?,
+            };
+            if message["type"] == "event" && message["event"] == event {
+                return Ok(message["body"].clone());
+            }
+        }
+    }
+
+    /// Disconnects, waiting for the server to finish
+    async fn disconnect(mut self, arguments: Value) -> eyre::Result<()> {
+        self.success("disconnect", arguments).await?;
+        timeout(TIMEOUT, self.server).await???;
+        Ok(())
+    }
+}
+
+fn sources() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../jdb-test-fixtures/testFixtures")
+}
+
+/// Finds a variable by name in the body of a `variables` response
+fn variable<'a>(variables: &'a Value, name: &str) -> &'a Value {
+    variables["variables"]
+        .as_array()
+        .expect("no variables")
+        .iter()
+        .find(|variable| variable["name"] == name)
+        .unwrap_or_else(|| panic!("no variable {name} in {variables}"))
+}
+
+#[test(tokio::test)]
+async fn test_attach() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Orders").await?;
+    let mut client = Client::new();
+    let capabilities = client
+        .success("initialize", json!({"adapterID": "java"}))
+        .await?;
+    assert_eq!(capabilities["supportsConfigurationDoneRequest"], true);
+    assert!(!client.request("threads", json!({})).await?["success"]
+        .as_bool()
+        .unwrap());
+
+    client
+        .success(
+            "attach",
+            json!({"port": jvm_instance.port(), "sourcePaths": [sources()]}),
+        )
+        .await?;
+    client.event("initialized").await?;
+    let source = sources().join("com/acme/Orders.java");
+    let breakpoints = client
+        .success(
+            "setBreakpoints",
+            json!({"source": {"path": source}, "breakpoints": [{"line": 75}]}),
+        )
+        .await?;
+    assert_eq!(breakpoints["breakpoints"][0]["line"], 75);
+    client
+        .success("setExceptionBreakpoints", json!({"filters": ["uncaught"]}))
+        .await?;
+    client.success("configurationDone", json!({})).await?;
+
+    let stopped = client.event("stopped").await?;
+    assert_eq!(stopped["reason"], "breakpoint");
+    let thread_id = stopped["threadId"].clone();
+    let threads = client.success("threads", json!({})).await?;
+    assert!(threads["threads"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|thread| thread["id"] == thread_id && thread["name"] == "main"));
+
+    let trace = client
+        .success("stackTrace", json!({"threadId": thread_id}))
+        .await?;
+    assert_eq!(trace["totalFrames"], 2);
+    let frame = &trace["stackFrames"][0];
+    assert_eq!(frame["name"], "com.acme.Orders.process");
+    assert_eq!(frame["line"], 75);
+    assert_eq!(frame["source"]["name"], "Orders.java");
+    assert_eq!(frame["source"]["path"], json!(source));
+    assert_eq!(trace["stackFrames"][1]["name"], "com.acme.Orders.main");
+    assert_eq!(trace["stackFrames"][1]["line"], 86);
+    let frame_id = frame["id"].clone();
+
+    let scopes = client
+        .success("scopes", json!({"frameId": frame_id}))
+        .await?;
+    let locals = client
+        .success(
+            "variables",
+            json!({"variablesReference": scopes["scopes"][0]["variablesReference"]}),
+        )
+        .await?;
+    assert_eq!(variable(&locals, "count")["value"], "4");
+    assert_eq!(variable(&locals, "count")["type"], "int");
+    let order = variable(&locals, "order");
+    assert_eq!(order["type"], "com.acme.Orders$Order");
+    let fields = client
+        .success(
+            "variables",
+            json!({"variablesReference": order["variablesReference"]}),
+        )
+        .await?;
+    assert_eq!(variable(&fields, "gift")["value"], "null");
+    let ratings = variable(&fields, "ratings");
+    assert_eq!(ratings["type"], "int[]");
+    let elements = client
+        .success(
+            "variables",
+            json!({"variablesReference": ratings["variablesReference"]}),
+        )
+        .await?;
+    assert_eq!(variable(&elements, "[0]")["value"], "5");
+    assert_eq!(variable(&elements, "[2]")["value"], "4");
+
+    let evaluated = client
+        .success(
+            "evaluate",
+            json!({"expression": "count * 2", "frameId": frame_id}),
+        )
+        .await?;
+    assert_eq!(evaluated["result"], "8");
+    assert_eq!(evaluated["variablesReference"], 0);
+    let response = client
+        .request("evaluate", json!({"expression": "count"}))
+        .await?;
+    assert_eq!(response["success"], false);
+
+    client
+        .success("next", json!({"threadId": thread_id}))
+        .await?;
+    let stopped = client.event("stopped").await?;
+    assert_eq!(stopped["reason"], "step");
+    let trace = client
+        .success("stackTrace", json!({"threadId": thread_id, "levels": 1}))
+        .await?;
+    assert_eq!(trace["stackFrames"][0]["line"], 76);
+    assert_eq!(trace["stackFrames"].as_array().unwrap().len(), 1);
+    // frames are forgotten once the threads are resumed, so only the frame listed since exists
+    let response = client.request("scopes", json!({"frameId": 2})).await?;
+    assert_eq!(response["success"], false);
+
+    client
+        .success(
+            "setBreakpoints",
+            json!({"source": {"path": source}, "breakpoints": []}),
+        )
+        .await?;
+    let resumed = client
+        .success("continue", json!({"threadId": thread_id}))
+        .await?;
+    assert_eq!(resumed["allThreadsContinued"], true);
+    client
+        .success("pause", json!({"threadId": thread_id}))
+        .await?;
+    assert_eq!(client.event("stopped").await?["reason"], "pause");
+
+    client.disconnect(json!({})).await
+}
+
+#[test(tokio::test)]
+async fn test_launch() -> eyre::Result<()> {
+    let mut client = Client::new();
+    client.success("initialize", json!({})).await?;
+    let response = client.request("launch", json!({})).await?;
+    assert_eq!(response["success"], false);
+
+    client
+        .success(
+            "launch",
+            json!({
+                "mainClass": "com.acme.Orders",
+                "classPaths": [class_path()],
+                "sourcePaths": [sources()],
+            }),
+        )
+        .await?;
+    client.event("initialized").await?;
+    let breakpoints = client
+        .success(
+            "setBreakpoints",
+            json!({
+                "source": {"path": sources().join("com/acme/Orders.java")},
+                "breakpoints": [{"line": 75, "logMessage": "count is {count}"}],
+            }),
+        )
+        .await?;
+    assert_eq!(breakpoints["breakpoints"].as_array().unwrap().len(), 1);
+    client.success("configurationDone", json!({})).await?;
+
+    loop {
+        let output = client.event("output").await?;
+        if output["category"] == "console" {
+            assert_eq!(output["output"], "count is 4\n");
+            break;
+        }
+    }
+    client.disconnect(json!({"terminateDebuggee": true})).await
+}
@@ -1,21 +1,43 @@
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
-    println!("cargo:rerun-if-changed=testFixtures/BusyBeaver.java");
+    println!("cargo:rerun-if-changed=testFixtures");
 
     let out_dir =
         PathBuf::from(env::var("OUT_DIR").expect("$OUT_DIR not set. Please build with cargo"));
 
+    let mut sources = vec![];
+    find_sources(Path::new("testFixtures"), &mut sources);
+    for source in &sources {
+        println!("cargo:rerun-if-changed={}", source.display());
+    }
+
     let status = Command::new("javac")
+        .arg("-g")
         .arg("-d")
         .arg(&out_dir)
-        .arg("testFixtures/BusyBeaver.java")
+        .args(&sources)
         .status()
         .expect("Failed to execute java");
     if !status.success() {
         panic!("Failed to execute java");
     }
 }
+
+/// Finds every java source file within a directory
+fn find_sources(dir: &Path, sources: &mut Vec<PathBuf>) {
+    for entry in dir.read_dir().expect("could not read test fixtures") {
+        let path = entry.expect("could not read test fixture").path();
+        if path.is_dir() {
+            find_sources(&path, sources);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "java")
+        {
+            sources.push(path);
+        }
+    }
+}
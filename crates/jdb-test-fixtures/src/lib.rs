@@ -1,4 +1,3 @@
-use jdwp_client::JdwpClient;
 use regex::Regex;
 use std::io;
 use std::num::NonZeroU16;
@@ -6,14 +5,19 @@ use std::path::Path;
 use std::process::Stdio;
 use std::str::FromStr;
 use std::sync::LazyLock;
-use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader};
-use tokio::net::TcpStream;
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::{Child, ChildStderr, ChildStdout, Command};
 
 static DEBUG_STARTUP_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^Listening for transport dt_socket at address: (?<port>\d{1,5})$").unwrap()
 });
 
+/// Gets the directory the test fixtures are compiled into, to run them with a `java` command of
+/// their own
+pub fn class_path() -> &'static Path {
+    Path::new(env!("OUT_DIR"))
+}
+
 /// A running java instance
 #[derive(Debug)]
 pub struct JavaInstance {
@@ -26,18 +30,19 @@ pub struct JavaInstance {
 impl JavaInstance {
     /// Starts a new running java instance, with debug enabled at a given port
     pub async fn new(debug_port: u16, main: impl AsRef<Path>) -> io::Result<Self> {
-        let path = Path::new(env!("OUT_DIR")).join(main);
         let mut child = Command::new("java")
             .arg(format!(
                 "-agentlib:jdwp=transport=dt_socket,server=y,address={debug_port},suspend=y"
             ))
-            .arg(path)
+            .arg("-cp")
+            .arg(class_path())
+            .arg(main.as_ref())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()?;
 
         let mut stdout = BufReader::new(child.stdout.take().expect("no stdout"));
-        let mut stderr = BufReader::new(child.stderr.take().expect("no stderr"));
+        let stderr = BufReader::new(child.stderr.take().expect("no stderr"));
 
         let mut port: Option<NonZeroU16> = None;
 
@@ -62,10 +67,20 @@ impl JavaInstance {
     pub fn port(&self) -> u16 {
         self.port.get()
     }
+
+    /// Gets the standard output of the java process
+    pub fn stdout(&mut self) -> &mut BufReader<ChildStdout> {
+        &mut self.stdout
+    }
+
+    /// Gets the standard error of the java process
+    pub fn stderr(&mut self) -> &mut BufReader<ChildStderr> {
+        &mut self.stderr
+    }
 }
 
 impl Drop for JavaInstance {
     fn drop(&mut self) {
-        let _ = self.child.kill();
+        let _ = self.child.start_kill();
     }
 }
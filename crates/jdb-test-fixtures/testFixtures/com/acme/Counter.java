@@ -0,0 +1,23 @@
+package com.acme;
+
+public class Counter {
+    static final String DEFAULT_NAME = "clicks";
+    private final String name;
+    private int count;
+
+    Counter(String name) {
+        this.name = name;
+    }
+
+    void increment(int by) {
+        count += by;
+    }
+
+    public static void main(String[] args) throws InterruptedException {
+        Counter counter = new Counter(DEFAULT_NAME);
+        for (int i = 0; ; i++) {
+            counter.increment(i % 3);
+            Thread.sleep(10);
+        }
+    }
+}
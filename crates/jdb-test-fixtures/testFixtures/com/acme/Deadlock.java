@@ -0,0 +1,52 @@
+package com.acme;
+
+public class Deadlock {
+    static class Account {
+        final String name;
+
+        Account(String name) {
+            this.name = name;
+        }
+    }
+
+    static final Account savings = new Account("savings");
+    static final Account checking = new Account("checking");
+    static final Object signal = new Object();
+
+    static void transfer(Account from, Account to) {
+        synchronized (from) {
+            pause();
+            synchronized (to) {
+                System.out.println("transferred from " + from.name + " to " + to.name);
+            }
+        }
+    }
+
+    static void pause() {
+        try {
+            Thread.sleep(200);
+        } catch (InterruptedException e) {
+            // ignored
+        }
+    }
+
+    static void await() {
+        synchronized (signal) {
+            try {
+                signal.wait();
+            } catch (InterruptedException e) {
+                // ignored
+            }
+        }
+    }
+
+    public static void main(String[] args) throws InterruptedException {
+        Thread waiter = new Thread(Deadlock::await, "waiter");
+        Thread left = new Thread(() -> transfer(savings, checking), "left");
+        Thread right = new Thread(() -> transfer(checking, savings), "right");
+        waiter.start();
+        left.start();
+        right.start();
+        left.join();
+    }
+}
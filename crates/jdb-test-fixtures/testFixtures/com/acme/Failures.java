@@ -0,0 +1,48 @@
+package com.acme;
+
+public class Failures {
+    static class AppException extends RuntimeException {
+        AppException(String message) {
+            super(message);
+        }
+    }
+
+    static class RetryException extends AppException {
+        RetryException(String message) {
+            super(message);
+        }
+    }
+
+    static void check(int attempt) {
+        if (attempt % 2 == 0) {
+            throw new RetryException("attempt " + attempt);
+        }
+        throw new IllegalStateException("odd attempt " + attempt);
+    }
+
+    static void attempt(int attempt) {
+        try {
+            check(attempt);
+        } catch (AppException e) {
+            // retried on the next attempt
+        }
+    }
+
+    public static void main(String[] args) throws InterruptedException {
+        Thread worker = new Thread(Failures::fail, "worker");
+        worker.start();
+        worker.join();
+        for (int i = 0; ; i++) {
+            try {
+                attempt(i);
+            } catch (IllegalStateException e) {
+                // ignored
+            }
+            Thread.sleep(10);
+        }
+    }
+
+    static void fail() {
+        throw new IllegalArgumentException("worker failed");
+    }
+}
@@ -0,0 +1,28 @@
+package com.acme;
+
+public class Gauges {
+    static int readings;
+    private final String name;
+    private int level;
+
+    Gauges(String name) {
+        this.name = name;
+    }
+
+    void set(int value) {
+        if (value != level) {
+            level = value;
+        }
+        readings++;
+    }
+
+    public static void main(String[] args) throws InterruptedException {
+        Gauges low = new Gauges("low");
+        Gauges high = new Gauges("high");
+        for (int i = 1; ; i++) {
+            low.set(i);
+            high.set(i * 100);
+            Thread.sleep(10);
+        }
+    }
+}
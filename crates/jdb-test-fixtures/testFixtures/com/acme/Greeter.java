@@ -0,0 +1,14 @@
+package com.acme;
+
+public class Greeter {
+    static String greeting() {
+        return "hello";
+    }
+
+    public static void main(String[] args) throws InterruptedException {
+        while (true) {
+            System.out.println(greeting());
+            Thread.sleep(20);
+        }
+    }
+}
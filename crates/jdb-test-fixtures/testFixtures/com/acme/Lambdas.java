@@ -0,0 +1,16 @@
+package com.acme;
+
+import java.util.List;
+
+public class Lambdas {
+    static int total;
+
+    public static void main(String[] args) throws InterruptedException {
+        while (true) {
+            List.of(1, 2, 3).forEach(value -> {
+                total += value;
+            });
+            Thread.sleep(10);
+        }
+    }
+}
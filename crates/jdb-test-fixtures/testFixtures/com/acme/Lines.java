@@ -0,0 +1,22 @@
+package com.acme;
+
+public class Lines {
+    static int greeted;
+
+    public static void main(String[] args) throws InterruptedException {
+        while (true) {
+            greet();
+            Thread.sleep(10);
+        }
+    }
+
+    static void greet() {
+        Runnable greeter = new Runnable() {
+            @Override
+            public void run() {
+                greeted += 1;
+            }
+        };
+        greeter.run();
+    }
+}
@@ -0,0 +1,90 @@
+package com.acme;
+
+import java.util.ArrayList;
+import java.util.List;
+
+public class Orders {
+    static int created;
+
+    static class Item {
+        final String name;
+        final int quantity;
+
+        Item(String name, int quantity) {
+            this.name = name;
+            this.quantity = quantity;
+        }
+
+        @Override
+        public String toString() {
+            return quantity + " x " + name;
+        }
+    }
+
+    static class Order {
+        private final List<Item> items = new ArrayList<>();
+        private final int[] ratings = {5, 3, 4};
+        private final Long discount = 10L;
+        private Item gift;
+
+        Order add(String name, int quantity) {
+            items.add(new Item(name, quantity));
+            return this;
+        }
+
+        List<Item> getItems() {
+            return items;
+        }
+
+        String describe(int value) {
+            return "int";
+        }
+
+        String describe(long value) {
+            return "long";
+        }
+
+        String describe(Integer value) {
+            return "integer";
+        }
+
+        String describe(String value) {
+            return "string";
+        }
+
+        String describe(Object value) {
+            return "object";
+        }
+
+        void fail(String message) {
+            throw new IllegalStateException(message);
+        }
+    }
+
+    static String label(String prefix) {
+        return prefix + created;
+    }
+
+    static int twice(Integer value) {
+        return value * 2;
+    }
+
+    static void process(Order order) {
+        Object first = order.getItems().get(0);
+        int count = order.getItems().size();
+        String summary = first + " of " + count;
+    }
+
+    public static void main(String[] args) throws InterruptedException {
+        Order order = new Order()
+                .add("apple", 3)
+                .add("pear", 1)
+                .add("plum", 6)
+                .add("fig", 2);
+        created++;
+        while (true) {
+            process(order);
+            Thread.sleep(10);
+        }
+    }
+}
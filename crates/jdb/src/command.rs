@@ -0,0 +1,533 @@
+//! The commands typed at the prompt, parsed with the same syntax as the JDK's `jdb`
+
+use jdwp_types::Int;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Every command name, as completed at the prompt
+pub const COMMANDS: &[&str] = &[
+    "attach", "catch", "classes", "clear", "cont", "down", "dump", "eval", "exit", "fields",
+    "help", "ignore", "locals", "methods", "next", "print", "quit", "redefine", "resume", "run",
+    "set", "step", "stop", "suspend", "thread", "threads", "unwatch", "up", "watch", "where",
+];
+
+/// The help printed by the `help` command
+pub const HELP: &str = "\
+** command list **
+attach <address>          -- attaches to a VM listening at [host:]port
+run [class [args]]        -- starts execution of a class
+threads                   -- lists threads
+thread <thread id>        -- sets the current thread
+where [<thread id> | all] -- dumps a thread's stack
+up [n frames]             -- moves up a thread's stack
+down [n frames]           -- moves down a thread's stack
+suspend [thread id(s)]    -- suspends threads (default: all)
+resume [thread id(s)]     -- resumes threads (default: all)
+print <expr>              -- prints the value of an expression
+eval <expr>               -- evaluates an expression (same as print)
+dump <expr>               -- prints all of an object's fields
+locals                    -- prints all local variables in the current stack frame
+set <lvalue> = <expr>     -- assigns a new value to a variable, field or array component
+classes                   -- lists the loaded classes
+methods <class>           -- lists a class's methods
+fields <class>            -- lists a class's fields
+stop                      -- lists the breakpoints
+stop at <class>:<line>    -- sets a breakpoint at a line
+stop in <class>.<method>[(argument types)] -- sets a breakpoint in a method
+clear <class>:<line>      -- clears a breakpoint at a line
+clear <class>.<method>[(argument types)]   -- clears a breakpoint in a method
+clear                     -- lists the breakpoints
+catch [uncaught|caught|all] <class pattern> -- breaks when an exception is thrown
+ignore [uncaught|caught|all] <class pattern> -- stops breaking on an exception
+watch [access|all] <class>.<field>   -- watches a field for modification, or access too
+unwatch [access|all] <class>.<field> -- stops watching a field
+step                      -- executes the current line
+step up                   -- executes until the current method returns
+next                      -- steps one line, over calls
+cont                      -- continues execution from a breakpoint, exception or step
+redefine <class> <class file> -- redefines a class from a class file
+help (or ?)               -- lists the commands
+exit (or quit)            -- exits the debugger";
+
+/// A command typed at the prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Attaches to a VM listening at an address, such as `localhost:8000` or `8000`
+    Attach(String),
+    /// Launches a class, or the one given on the command line, and runs it
+    Run {
+        class: Option<String>,
+        arguments: Vec<String>,
+    },
+    Threads,
+    /// Makes a thread the current one
+    Thread(String),
+    Where(WhereTarget),
+    /// Moves up a number of frames, towards the callers
+    Up(Int),
+    /// Moves down a number of frames, towards the top of the stack
+    Down(Int),
+    /// Suspends the given threads, or every thread if there are none
+    Suspend(Vec<String>),
+    /// Resumes the given threads, or every thread if there are none
+    Resume(Vec<String>),
+    Print(String),
+    Dump(String),
+    Locals,
+    Set {
+        variable: String,
+        value: String,
+    },
+    Classes,
+    Methods(String),
+    Fields(String),
+    /// Sets a breakpoint, or lists them when there isn't one
+    Stop(Option<BreakpointSpec>),
+    /// Clears a breakpoint, or lists them when there isn't one
+    Clear(Option<BreakpointSpec>),
+    /// Breaks on exceptions, or lists the ones broken on when there isn't a pattern
+    Catch(Option<CatchSpec>),
+    Ignore(CatchSpec),
+    Watch(WatchSpec),
+    Unwatch(WatchSpec),
+    /// Steps into the next line
+    Step,
+    /// Steps out of the current method
+    StepUp,
+    /// Steps over the next line
+    Next,
+    Cont,
+    Redefine {
+        class: String,
+        class_file: PathBuf,
+    },
+    Help,
+    Exit,
+}
+
+/// The threads `where` dumps the stack of
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WhereTarget {
+    Current,
+    All,
+    Thread(String),
+}
+
+/// Where a breakpoint is set
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BreakpointSpec {
+    /// A line of a class, such as `com.acme.Foo:12`
+    Line { class: String, line: Int },
+    /// The start of a method, such as `com.acme.Foo.bar(int)`. Overloads are told apart by the
+    /// names of their argument types, and every overload is meant if they aren't given.
+    Method {
+        class: String,
+        method: String,
+        arguments: Option<Vec<String>>,
+    },
+}
+
+impl BreakpointSpec {
+    /// Gets the name of the class the breakpoint is in
+    pub fn class(&self) -> &str {
+        match self {
+            BreakpointSpec::Line { class, .. } | BreakpointSpec::Method { class, .. } => class,
+        }
+    }
+}
+
+impl Display for BreakpointSpec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BreakpointSpec::Line { class, line } => write!(f, "{class}:{line}"),
+            BreakpointSpec::Method {
+                class,
+                method,
+                arguments,
+            } => {
+                write!(f, "{class}.{method}")?;
+                if let Some(arguments) = arguments {
+                    write!(f, "({})", arguments.join(", "))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The exceptions `catch` breaks on
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CatchSpec {
+    /// A restricted regular expression matched against exception class names, such as
+    /// `java.io.*`
+    pub pattern: String,
+    pub caught: bool,
+    pub uncaught: bool,
+}
+
+impl Display for CatchSpec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let kind = match (self.caught, self.uncaught) {
+            (true, false) => "caught",
+            (false, true) => "uncaught",
+            _ => "all",
+        };
+        write!(f, "{kind} {}", self.pattern)
+    }
+}
+
+/// The field `watch` watches
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WatchSpec {
+    pub class: String,
+    pub field: String,
+    /// Whether reads are watched, as well as modifications
+    pub access: bool,
+    /// Whether modifications are watched
+    pub modification: bool,
+}
+
+impl Display for WatchSpec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.access, self.modification) {
+            (true, true) => write!(f, "all {}.{}", self.class, self.field),
+            (true, false) => write!(f, "access {}.{}", self.class, self.field),
+            _ => write!(f, "modification of {}.{}", self.class, self.field),
+        }
+    }
+}
+
+/// A line typed at the prompt isn't a valid command
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseCommandError {
+    #[error("Unrecognized command: '{0}'.  Try help...")]
+    Unrecognized(String),
+    /// A command was given the wrong arguments, with the usage of the command
+    #[error("Usage: {0}")]
+    Usage(&'static str),
+}
+
+impl Command {
+    /// Parses a line typed at the prompt, or returns `None` if it's blank
+    pub fn parse(line: &str) -> Result<Option<Command>, ParseCommandError> {
+        let line = line.trim();
+        let (name, rest) = line
+            .split_once(char::is_whitespace)
+            .map_or((line, ""), |(name, rest)| (name, rest.trim()));
+        let words = rest.split_whitespace().collect::<Vec<_>>();
+        let command = match name {
+            "" => return Ok(None),
+            "attach" => match words[..] {
+                [address] => Command::Attach(address.to_string()),
+                _ => return Err(ParseCommandError::Usage("attach <address>")),
+            },
+            "run" => Command::Run {
+                class: words.first().map(|class| class.to_string()),
+                arguments: words.iter().skip(1).map(|word| word.to_string()).collect(),
+            },
+            "threads" => Command::Threads,
+            "thread" => match words[..] {
+                [thread] => Command::Thread(thread.to_string()),
+                _ => return Err(ParseCommandError::Usage("thread <thread id>")),
+            },
+            "where" => match words[..] {
+                [] => Command::Where(WhereTarget::Current),
+                ["all"] => Command::Where(WhereTarget::All),
+                [thread] => Command::Where(WhereTarget::Thread(thread.to_string())),
+                _ => return Err(ParseCommandError::Usage("where [<thread id> | all]")),
+            },
+            "up" | "down" => {
+                let frames = match words[..] {
+                    [] => 1,
+                    [frames] => frames
+                        .parse::<Int>()
+                        .ok()
+                        .filter(|frames| *frames > 0)
+                        .ok_or(ParseCommandError::Usage("up|down [n frames]"))?,
+                    _ => return Err(ParseCommandError::Usage("up|down [n frames]")),
+                };
+                if name == "up" {
+                    Command::Up(frames)
+                } else {
+                    Command::Down(frames)
+                }
+            }
+            "suspend" => Command::Suspend(words.iter().map(|word| word.to_string()).collect()),
+            "resume" => Command::Resume(words.iter().map(|word| word.to_string()).collect()),
+            "print" | "eval" if !rest.is_empty() => Command::Print(rest.to_string()),
+            "print" | "eval" => return Err(ParseCommandError::Usage("print <expr>")),
+            "dump" if !rest.is_empty() => Command::Dump(rest.to_string()),
+            "dump" => return Err(ParseCommandError::Usage("dump <expr>")),
+            "locals" => Command::Locals,
+            "set" => match rest.split_once('=') {
+                Some((variable, value))
+                    if !variable.trim().is_empty() && !value.trim().is_empty() =>
+                {
+                    Command::Set {
+                        variable: variable.trim().to_string(),
+                        value: value.trim().to_string(),
+                    }
+                }
+                _ => return Err(ParseCommandError::Usage("set <lvalue> = <expr>")),
+            },
+            "classes" => Command::Classes,
+            "methods" => match words[..] {
+                [class] => Command::Methods(class.to_string()),
+                _ => return Err(ParseCommandError::Usage("methods <class>")),
+            },
+            "fields" => match words[..] {
+                [class] => Command::Fields(class.to_string()),
+                _ => return Err(ParseCommandError::Usage("fields <class>")),
+            },
+            "stop" => match rest {
+                "" => Command::Stop(None),
+                _ => Command::Stop(Some(parse_stop(rest).ok_or(ParseCommandError::Usage(
+                    "stop at <class>:<line> or stop in <class>.<method>[(argument types)]",
+                ))?)),
+            },
+            "clear" => match rest {
+                "" => Command::Clear(None),
+                _ => Command::Clear(Some(parse_breakpoint(rest).ok_or(
+                    ParseCommandError::Usage(
+                        "clear <class>:<line> or clear <class>.<method>[(argument types)]",
+                    ),
+                )?)),
+            },
+            "catch" => match rest {
+                "" => Command::Catch(None),
+                _ => Command::Catch(Some(parse_catch(&words).ok_or(
+                    ParseCommandError::Usage("catch [uncaught|caught|all] <class pattern>"),
+                )?)),
+            },
+            "ignore" => Command::Ignore(parse_catch(&words).ok_or(ParseCommandError::Usage(
+                "ignore [uncaught|caught|all] <class pattern>",
+            ))?),
+            "watch" => Command::Watch(parse_watch(&words).ok_or(ParseCommandError::Usage(
+                "watch [access|all] <class>.<field>",
+            ))?),
+            "unwatch" => Command::Unwatch(parse_watch(&words).ok_or(ParseCommandError::Usage(
+                "unwatch [access|all] <class>.<field>",
+            ))?),
+            "step" => match words[..] {
+                [] => Command::Step,
+                ["up"] => Command::StepUp,
+                _ => return Err(ParseCommandError::Usage("step [up]")),
+            },
+            "next" => Command::Next,
+            "cont" => Command::Cont,
+            "redefine" => match words[..] {
+                [class, class_file] => Command::Redefine {
+                    class: class.to_string(),
+                    class_file: PathBuf::from(class_file),
+                },
+                _ => return Err(ParseCommandError::Usage("redefine <class> <class file>")),
+            },
+            "help" | "?" => Command::Help,
+            "exit" | "quit" => Command::Exit,
+            other => return Err(ParseCommandError::Unrecognized(other.to_string())),
+        };
+        Ok(Some(command))
+    }
+}
+
+/// Parses `at <class>:<line>` or `in <class>.<method>[(argument types)]`
+fn parse_stop(rest: &str) -> Option<BreakpointSpec> {
+    let (kind, spec) = rest.split_once(char::is_whitespace)?;
+    let spec = parse_breakpoint(spec.trim())?;
+    match (kind, &spec) {
+        ("at", BreakpointSpec::Line { .. }) | ("in", BreakpointSpec::Method { .. }) => Some(spec),
+        _ => None,
+    }
+}
+
+/// Parses `<class>:<line>` or `<class>.<method>[(argument types)]`
+fn parse_breakpoint(spec: &str) -> Option<BreakpointSpec> {
+    if let Some((class, line)) = spec.rsplit_once(':') {
+        return Some(BreakpointSpec::Line {
+            class: class.to_string(),
+            line: line.parse().ok().filter(|line| *line > 0)?,
+        });
+    }
+    let (name, arguments) = match spec.split_once('(') {
+        Some((name, arguments)) => {
+            let arguments = arguments.strip_suffix(')')?;
+            let arguments = arguments
+                .split(',')
+                .map(|argument| argument.trim().to_string())
+                .filter(|argument| !argument.is_empty())
+                .collect();
+            (name, Some(arguments))
+        }
+        None => (spec, None),
+    };
+    let (class, method) = name.rsplit_once('.')?;
+    if class.is_empty() || method.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(BreakpointSpec::Method {
+        class: class.to_string(),
+        method: method.to_string(),
+        arguments,
+    })
+}
+
+/// Parses `[uncaught|caught|all] <class pattern>`
+fn parse_catch(words: &[&str]) -> Option<CatchSpec> {
+    let (caught, uncaught, pattern) = match words {
+        [pattern] => (true, true, pattern),
+        ["all", pattern] => (true, true, pattern),
+        ["caught", pattern] => (true, false, pattern),
+        ["uncaught", pattern] => (false, true, pattern),
+        _ => return None,
+    };
+    Some(CatchSpec {
+        pattern: pattern.to_string(),
+        caught,
+        uncaught,
+    })
+}
+
+/// Parses `[access|all] <class>.<field>`
+fn parse_watch(words: &[&str]) -> Option<WatchSpec> {
+    let (access, modification, name) = match words {
+        [name] => (false, true, name),
+        ["access", name] => (true, false, name),
+        ["all", name] => (true, true, name),
+        _ => return None,
+    };
+    let (class, field) = name.rsplit_once('.')?;
+    if class.is_empty() || field.is_empty() {
+        return None;
+    }
+    Some(WatchSpec {
+        class: class.to_string(),
+        field: field.to_string(),
+        access,
+        modification,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Command {
+        Command::parse(line)
+            .expect("could not parse")
+            .expect("blank line")
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        assert_eq!(
+            parse("stop at com.acme.Foo:12"),
+            Command::Stop(Some(BreakpointSpec::Line {
+                class: "com.acme.Foo".to_string(),
+                line: 12,
+            }))
+        );
+        assert_eq!(
+            parse("stop in com.acme.Foo$Bar.baz(int, java.lang.String)"),
+            Command::Stop(Some(BreakpointSpec::Method {
+                class: "com.acme.Foo$Bar".to_string(),
+                method: "baz".to_string(),
+                arguments: Some(vec!["int".to_string(), "java.lang.String".to_string()]),
+            }))
+        );
+        assert_eq!(
+            parse("clear com.acme.Foo.<init>").to_string_spec(),
+            "com.acme.Foo.<init>"
+        );
+        assert_eq!(parse("stop"), Command::Stop(None));
+        assert_eq!(parse("clear"), Command::Clear(None));
+        assert!(matches!(
+            Command::parse("stop in com.acme.Foo:12"),
+            Err(ParseCommandError::Usage(_))
+        ));
+        assert!(matches!(
+            Command::parse("stop at com.acme.Foo:zero"),
+            Err(ParseCommandError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn test_catch_and_watch() {
+        assert_eq!(
+            parse("catch java.io.*"),
+            Command::Catch(Some(CatchSpec {
+                pattern: "java.io.*".to_string(),
+                caught: true,
+                uncaught: true,
+            }))
+        );
+        assert_eq!(
+            parse("ignore uncaught java.lang.Error").to_string_spec(),
+            "uncaught java.lang.Error"
+        );
+        assert_eq!(
+            parse("watch access com.acme.Foo.count"),
+            Command::Watch(WatchSpec {
+                class: "com.acme.Foo".to_string(),
+                field: "count".to_string(),
+                access: true,
+                modification: false,
+            })
+        );
+        assert!(matches!(
+            Command::parse("watch count"),
+            Err(ParseCommandError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(Command::parse("   "), Ok(None));
+        assert_eq!(
+            parse("set order.items[0] = \"a = b\""),
+            Command::Set {
+                variable: "order.items[0]".to_string(),
+                value: "\"a = b\"".to_string(),
+            }
+        );
+        assert_eq!(
+            parse("print  count + 1"),
+            Command::Print("count + 1".to_string())
+        );
+        assert_eq!(parse("eval x"), Command::Print("x".to_string()));
+        assert_eq!(parse("step up"), Command::StepUp);
+        assert_eq!(parse("up 3"), Command::Up(3));
+        assert_eq!(parse("where all"), Command::Where(WhereTarget::All));
+        assert_eq!(
+            parse("run com.acme.Foo a b"),
+            Command::Run {
+                class: Some("com.acme.Foo".to_string()),
+                arguments: vec!["a".to_string(), "b".to_string()],
+            }
+        );
+        assert_eq!(
+            parse("suspend 0x1 main"),
+            Command::Suspend(vec!["0x1".to_string(), "main".to_string()])
+        );
+        assert_eq!(
+            Command::parse("frobnicate"),
+            Err(ParseCommandError::Unrecognized("frobnicate".to_string()))
+        );
+        assert!(matches!(
+            Command::parse("up 0"),
+            Err(ParseCommandError::Usage(_))
+        ));
+    }
+
+    impl Command {
+        /// Formats the breakpoint, exception or field a command is given
+        fn to_string_spec(&self) -> String {
+            match self {
+                Command::Stop(Some(spec)) | Command::Clear(Some(spec)) => spec.to_string(),
+                Command::Catch(Some(spec)) | Command::Ignore(spec) => spec.to_string(),
+                Command::Watch(spec) | Command::Unwatch(spec) => spec.to_string(),
+                other => panic!("{other:?} isn't given a breakpoint, exception or field"),
+            }
+        }
+    }
+}
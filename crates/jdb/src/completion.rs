@@ -0,0 +1,200 @@
+//! Tab completion of command names, and of the names of the classes and methods loaded in the
+//! target VM
+
+use crate::command::COMMANDS;
+use jdi_rs::VirtualMachine;
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+use std::sync::{Arc, Mutex, Weak};
+use tokio::runtime::Handle;
+
+/// The commands whose arguments name classes, or methods of a class
+const CLASS_COMMANDS: &[&str] = &[
+    "stop", "clear", "methods", "fields", "watch", "unwatch", "catch", "ignore", "redefine",
+];
+
+/// The names of the types loaded in the target VM, as completed at the prompt
+pub trait LoadedTypes: Send + Sync {
+    /// Gets the fully qualified names of every loaded class
+    fn class_names(&self) -> Vec<String>;
+
+    /// Gets the names of the methods of the loaded classes with a name
+    fn method_names(&self, class: &str) -> Vec<String>;
+}
+
+/// The types loaded in a connected virtual machine, queried with [AllClasses] from the thread
+/// reading the prompt.
+///
+/// [AllClasses]: jdi_rs::VirtualMachine::all_classes
+pub struct VmTypes<VM: VirtualMachine> {
+    vm: Weak<VM>,
+    runtime: Handle,
+}
+
+impl<VM: VirtualMachine> VmTypes<VM> {
+    /// Creates the loaded types of a virtual machine, queried on a runtime. They must be queried
+    /// outside of the runtime.
+    pub fn new(vm: &Arc<VM>, runtime: Handle) -> Self {
+        Self {
+            vm: Arc::downgrade(vm),
+            runtime,
+        }
+    }
+}
+
+impl<VM: VirtualMachine> LoadedTypes for VmTypes<VM> {
+    fn class_names(&self) -> Vec<String> {
+        let Some(vm) = self.vm.upgrade() else {
+            return vec![];
+        };
+        self.runtime
+            .block_on(async { vm.all_classes().await })
+            .map(|classes| classes.into_iter().map(|class| class.name()).collect())
+            .unwrap_or_default()
+    }
+
+    fn method_names(&self, class: &str) -> Vec<String> {
+        let Some(vm) = self.vm.upgrade() else {
+            return vec![];
+        };
+        self.runtime.block_on(async {
+            let mut names = vec![];
+            for class in vm.classes_by_name(class).await.unwrap_or_default() {
+                for method in class.methods().await.unwrap_or_default() {
+                    names.push(method.name().to_string());
+                }
+            }
+            names
+        })
+    }
+}
+
+/// Completes the words typed at the prompt. Class and method names are only completed once the
+/// [loaded types](LoadedTypes) are set, after connecting to the target VM.
+#[derive(Clone, Default)]
+pub struct CommandCompleter {
+    types: Arc<Mutex<Option<Box<dyn LoadedTypes>>>>,
+}
+
+impl CommandCompleter {
+    /// Creates a completer that only completes command names until the loaded types are set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the types whose names are completed
+    pub fn set_types(&self, types: impl LoadedTypes + 'static) {
+        *self.types.lock().unwrap() = Some(Box::new(types));
+    }
+}
+
+impl Completer for CommandCompleter {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let types = self.types.lock().unwrap();
+        Ok(candidates(&line[..pos], types.as_deref()))
+    }
+}
+
+impl Hinter for CommandCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for CommandCompleter {}
+
+impl Validator for CommandCompleter {}
+
+impl Helper for CommandCompleter {}
+
+/// Finds the candidates for the last word of a line, returning where the word starts
+fn candidates(line: &str, types: Option<&dyn LoadedTypes>) -> (usize, Vec<String>) {
+    let start = line
+        .rfind(char::is_whitespace)
+        .map_or(0, |position| position + 1);
+    let word = &line[start..];
+    let command = line.split_whitespace().next().unwrap_or_default();
+    let mut candidates = if start == 0 {
+        COMMANDS
+            .iter()
+            .filter(|name| name.starts_with(word))
+            .map(|name| name.to_string())
+            .collect()
+    } else if let (true, Some(types)) = (CLASS_COMMANDS.contains(&command), types) {
+        let mut candidates = types
+            .class_names()
+            .into_iter()
+            .filter(|name| name.starts_with(word))
+            .collect::<Vec<_>>();
+        if let Some((class, method)) = word.rsplit_once('.') {
+            candidates.extend(
+                types
+                    .method_names(class)
+                    .into_iter()
+                    .filter(|name| name.starts_with(method))
+                    .map(|name| format!("{class}.{name}")),
+            );
+        }
+        candidates
+    } else {
+        vec![]
+    };
+    candidates.sort();
+    candidates.dedup();
+    (start, candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Types;
+
+    impl LoadedTypes for Types {
+        fn class_names(&self) -> Vec<String> {
+            vec![
+                "com.acme.Orders".to_string(),
+                "com.acme.Orders$Item".to_string(),
+            ]
+        }
+
+        fn method_names(&self, class: &str) -> Vec<String> {
+            match class {
+                "com.acme.Orders" => vec!["process".to_string(), "main".to_string()],
+                _ => vec![],
+            }
+        }
+    }
+
+    #[test]
+    fn test_candidates() {
+        assert_eq!(
+            candidates("st", Some(&Types)),
+            (0, vec!["step".to_string(), "stop".to_string()])
+        );
+        assert_eq!(
+            candidates("stop in com.acme.Or", Some(&Types)),
+            (
+                8,
+                vec![
+                    "com.acme.Orders".to_string(),
+                    "com.acme.Orders$Item".to_string()
+                ]
+            )
+        );
+        assert_eq!(
+            candidates("stop in com.acme.Orders.p", Some(&Types)),
+            (8, vec!["com.acme.Orders.process".to_string()])
+        );
+        assert_eq!(candidates("print com.acme.Or", Some(&Types)), (6, vec![]));
+        assert_eq!(candidates("methods com.acme.Or", None), (8, vec![]));
+    }
+}
@@ -0,0 +1,53 @@
+//! Formatting of values and names shared by the commands
+
+use jdi_rs::{ObjectReference, Value, VirtualMachine};
+use jdwp_types::Tag;
+use std::io;
+
+/// Gets the relative path of the source file a class is compiled from, such as
+/// `com/acme/Foo.java` for `com.acme.Foo$Bar`
+pub fn source_path(class: &str) -> String {
+    let outer = class.split('$').next().unwrap_or(class);
+    format!("{}.java", outer.replace('.', "/"))
+}
+
+/// Formats a value the way `print` does, quoting strings and characters and describing objects
+pub async fn format_value<VM: VirtualMachine + ?Sized>(value: &Value<VM>) -> io::Result<String> {
+    Ok(match value {
+        Value::Boolean(b) => b.to_string(),
+        Value::Byte(b) => (*b as i8).to_string(),
+        Value::Char(c) => format!(
+            "'{}'",
+            char::from_u32(*c as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+        ),
+        Value::Short(s) => s.to_string(),
+        Value::Int(i) => i.to_string(),
+        Value::Long(l) => l.to_string(),
+        Value::Float(f) => format!("{f:?}"),
+        Value::Double(d) => format!("{d:?}"),
+        Value::Void => "<void value>".to_string(),
+        Value::Object(None) => "null".to_string(),
+        Value::Object(Some(object)) => format_object(object).await?,
+    })
+}
+
+async fn format_object<VM: VirtualMachine + ?Sized>(
+    object: &ObjectReference<VM>,
+) -> io::Result<String> {
+    let id = object.id().get();
+    Ok(match object.tag() {
+        Tag::String => format!("\"{}\"", object.string_value().await?),
+        Tag::Array => {
+            let name = object.reference_type().await?.name();
+            let length = object.array_length().await?;
+            format!(
+                "instance of {} (id={id})",
+                name.replacen("[]", &format!("[{length}]"), 1)
+            )
+        }
+        _ => format!(
+            "instance of {}(id={id})",
+            object.reference_type().await?.name()
+        ),
+    })
+}
@@ -0,0 +1,88 @@
+//! Launches a class in a new java virtual machine, waiting for a debugger to attach
+
+use std::ffi::OsString;
+use std::io;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// The line the jdwp agent prints once it's listening, followed by the port
+const LISTENING: &str = "Listening for transport dt_socket at address: ";
+
+/// A java virtual machine launched by `run`, which is killed when dropped
+#[derive(Debug)]
+pub struct Launched {
+    child: Child,
+    port: u16,
+}
+
+impl Launched {
+    /// Launches a class with the `java` command, suspended until a debugger attaches to the
+    /// returned port. Once the jdwp agent is listening, every line the virtual machine writes is
+    /// sent to `output`.
+    pub async fn new(
+        classpath: Option<&OsString>,
+        class: &str,
+        arguments: &[String],
+        output: UnboundedSender<String>,
+    ) -> io::Result<Self> {
+        let mut command = Command::new("java");
+        command.arg("-agentlib:jdwp=transport=dt_socket,server=y,address=127.0.0.1:0,suspend=y");
+        if let Some(classpath) = classpath {
+            command.arg("-cp").arg(classpath);
+        }
+        let mut child = command
+            .arg(class)
+            .args(arguments)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let mut stdout = BufReader::new(child.stdout.take().expect("no stdout"));
+        let stderr = BufReader::new(child.stderr.take().expect("no stderr"));
+        let port = loop {
+            let mut line = String::new();
+            if stdout.read_line(&mut line).await? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "java exited before listening for a debugger: {:?}",
+                        child.wait().await?
+                    ),
+                ));
+            }
+            if let Some(port) = line.trim().strip_prefix(LISTENING) {
+                let port = port.rsplit(':').next().unwrap_or(port);
+                break port.parse().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, format!("invalid port {port}"))
+                })?;
+            }
+        };
+        tokio::spawn(forward(stdout, output.clone()));
+        tokio::spawn(forward(stderr, output));
+        Ok(Self { child, port })
+    }
+
+    /// Gets the port the virtual machine is waiting for a debugger on
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for Launched {
+    fn drop(&mut self) {
+        let _ = self.child.start_kill();
+    }
+}
+
+/// Sends every line read to `output` until the end of the stream
+async fn forward(reader: BufReader<impl AsyncRead + Unpin>, output: UnboundedSender<String>) {
+    let mut lines = reader.lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if output.send(line).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,13 @@
+//! # `jdb`
+//!
+//! A 'jdb'-compatible command-line debugger for java virtual machines, built on [jdi-rs](jdi_rs).
+//!
+//! The familiar commands of the JDK's `jdb`, such as `stop at`, `where`, `print` and `cont`, are
+//! [parsed](command::Command::parse) from the prompt and executed by a [Session](session::Session)
+//! connected to the target VM.
+
+pub mod command;
+pub mod completion;
+pub mod format;
+pub mod launch;
+pub mod session;
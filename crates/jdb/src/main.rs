@@ -0,0 +1,272 @@
+//! The `jdb` command-line debugger
+
+use clap::Parser;
+use futures::StreamExt;
+use jdb::command::Command;
+use jdb::completion::{CommandCompleter, VmTypes};
+use jdb::launch::Launched;
+use jdb::session::Session;
+use jdi_rs::{VirtualMachine, VirtualMachineManager};
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::{Editor, ExternalPrinter};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::thread;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+/// The options `jdb` accepts with a single dash, which clap expects two dashes for
+const SINGLE_DASH_OPTIONS: &[&str] = &["-attach", "-sourcepath", "-classpath", "-cp"];
+
+/// A 'jdb'-compatible debugger for java virtual machines
+#[derive(Debug, Parser)]
+#[command(name = "jdb", version)]
+struct Options {
+    /// Attaches to a running VM listening at an address, such as `localhost:8000` or `8000`
+    #[arg(long)]
+    attach: Option<String>,
+    /// The directories source files are looked for in, separated like `PATH`
+    #[arg(long)]
+    sourcepath: Option<OsString>,
+    /// The class path of a VM launched by the debugger
+    #[arg(long, visible_alias = "cp")]
+    classpath: Option<OsString>,
+    /// A class to launch, which starts running with the `run` command
+    class: Option<String>,
+    /// The arguments of the launched class
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    arguments: Vec<String>,
+}
+
+/// The prompt, read with line editing on a thread of its own so events can be printed while a
+/// line is being typed
+struct Prompt {
+    prompts: std::sync::mpsc::Sender<String>,
+    lines: UnboundedReceiver<Option<String>>,
+    output: UnboundedSender<String>,
+}
+
+impl Prompt {
+    fn new(completer: CommandCompleter) -> eyre::Result<Self> {
+        let mut editor = Editor::<CommandCompleter, DefaultHistory>::new()?;
+        editor.set_helper(Some(completer));
+        let history = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".jdb_history"));
+        if let Some(history) = &history {
+            // there's no history the first time
+            let _ = editor.load_history(history);
+        }
+
+        let (output, mut outputs) = mpsc::unbounded_channel::<String>();
+        match editor.create_external_printer() {
+            Ok(mut printer) => thread::spawn(move || {
+                while let Some(text) = outputs.blocking_recv() {
+                    if printer.print(text).is_err() {
+                        break;
+                    }
+                }
+            }),
+            Err(_) => thread::spawn(move || {
+                while let Some(text) = outputs.blocking_recv() {
+                    println!("{text}");
+                }
+            }),
+        };
+
+        let (prompts, prompt_receiver) = std::sync::mpsc::channel::<String>();
+        let (line_sender, lines) = mpsc::unbounded_channel();
+        thread::spawn(move || {
+            while let Ok(prompt) = prompt_receiver.recv() {
+                let line = match editor.readline(&prompt) {
+                    Ok(line) => {
+                        if !line.trim().is_empty() {
+                            let _ = editor.add_history_entry(line.as_str());
+                            if let Some(history) = &history {
+                                let _ = editor.save_history(history);
+                            }
+                        }
+                        Some(line)
+                    }
+                    Err(ReadlineError::Interrupted) => Some(String::new()),
+                    Err(_) => None,
+                };
+                if line_sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            prompts,
+            lines,
+            output,
+        })
+    }
+
+    /// Starts reading a line after printing a prompt
+    fn ask(&self, prompt: String) {
+        let _ = self.prompts.send(prompt);
+    }
+
+    /// Waits for the line being read, or `None` once the input ends. Nothing is lost if this is
+    /// cancelled.
+    async fn next_line(&mut self) -> Option<String> {
+        self.lines.recv().await.flatten()
+    }
+
+    /// Reads a line, or `None` once the input ends
+    async fn read_line(&mut self, prompt: String) -> Option<String> {
+        self.ask(prompt);
+        self.next_line().await
+    }
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    let options = Options::parse_from(std::env::args_os().map(|arg| match arg.to_str() {
+        Some(option) if SINGLE_DASH_OPTIONS.contains(&option) => format!("-{option}").into(),
+        _ => arg,
+    }));
+    let sourcepath = match &options.sourcepath {
+        Some(sourcepath) => std::env::split_paths(sourcepath).collect(),
+        None => vec![PathBuf::from(".")],
+    };
+    let completer = CommandCompleter::new();
+    let mut prompt = Prompt::new(completer.clone())?;
+    println!("Initializing jdb ...");
+
+    let mut launched = None;
+    let mut run = false;
+    let mut deferred = vec![];
+    let address = if let Some(address) = &options.attach {
+        address.clone()
+    } else if let Some(class) = &options.class {
+        let vm = Launched::new(
+            options.classpath.as_ref(),
+            class,
+            &options.arguments,
+            prompt.output.clone(),
+        )
+        .await?;
+        let address = format!("127.0.0.1:{}", vm.port());
+        launched = Some(vm);
+        address
+    } else {
+        loop {
+            let Some(line) = prompt.read_line("> ".to_string()).await else {
+                return Ok(());
+            };
+            match Command::parse(&line) {
+                Ok(None) => {}
+                Ok(Some(Command::Attach(address))) => break address,
+                Ok(Some(Command::Run { class, arguments })) => {
+                    let Some(class) = class else {
+                        println!("No class specified.");
+                        continue;
+                    };
+                    let vm = Launched::new(
+                        options.classpath.as_ref(),
+                        &class,
+                        &arguments,
+                        prompt.output.clone(),
+                    )
+                    .await?;
+                    run = true;
+                    let address = format!("127.0.0.1:{}", vm.port());
+                    launched = Some(vm);
+                    break address;
+                }
+                Ok(Some(Command::Exit)) => return Ok(()),
+                Ok(Some(Command::Help)) => println!("{}", jdb::command::HELP),
+                Ok(Some(command @ Command::Stop(Some(_))))
+                | Ok(Some(command @ Command::Catch(Some(_))))
+                | Ok(Some(command @ Command::Watch(_))) => {
+                    println!("Deferring {}.", describe_deferred(&command));
+                    println!("It will be set after the VM is started.");
+                    deferred.push(command);
+                }
+                Ok(Some(_)) => {
+                    println!("Command '{line}' is not valid until the VM is started with the 'run' command")
+                }
+                Err(e) => println!("{e}"),
+            }
+        }
+    };
+    let address = match address.parse::<u16>() {
+        Ok(port) => format!("localhost:{port}"),
+        Err(_) => address,
+    };
+    let vm = VirtualMachineManager::attach(address).await?;
+    completer.set_types(VmTypes::new(&vm, Handle::current()));
+    let mut session = Session::new(vm, sourcepath);
+    if run {
+        session.resume_on_start();
+    }
+    debug(&mut session, &mut prompt, deferred).await?;
+    drop(launched);
+    Ok(())
+}
+
+/// Describes a command deferred until the VM is started
+fn describe_deferred(command: &Command) -> String {
+    match command {
+        Command::Stop(Some(spec)) => format!("breakpoint {spec}"),
+        Command::Catch(Some(spec)) => spec.to_string(),
+        Command::Watch(spec) => format!("watch {spec}"),
+        _ => String::new(),
+    }
+}
+
+/// Executes the commands typed at the prompt and prints the events of the virtual machine until
+/// it disconnects or the debugger exits
+async fn debug<VM: VirtualMachine>(
+    session: &mut Session<VM>,
+    prompt: &mut Prompt,
+    deferred: Vec<Command>,
+) -> eyre::Result<()> {
+    for command in deferred {
+        if let Err(e) = session.execute(command).await {
+            println!("{e}");
+        }
+    }
+    let (event_sender, mut events) = mpsc::unbounded_channel();
+    let mut queue = session.virtual_machine().event_queue();
+    tokio::spawn(async move {
+        while let Some(set) = queue.next().await {
+            if event_sender.send(set).is_err() {
+                break;
+            }
+        }
+    });
+
+    prompt.ask(session.prompt());
+    loop {
+        tokio::select! {
+            read = prompt.next_line() => {
+                let Some(read) = read else {
+                    break;
+                };
+                match Command::parse(&read) {
+                    Ok(None) => {}
+                    Ok(Some(Command::Exit)) => break,
+                    Ok(Some(command)) => match session.execute(command).await {
+                        Ok(output) if output.is_empty() => {}
+                        Ok(output) => println!("{}", output.trim_end()),
+                        Err(e) => println!("{e}"),
+                    },
+                    Err(e) => println!("{e}"),
+                }
+                prompt.ask(session.prompt());
+            }
+            Some(set) = events.recv() => {
+                let output = session.handle_event_set(set).await?;
+                if !output.is_empty() {
+                    let _ = prompt.output.send(output);
+                }
+                if session.is_disconnected() {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,940 @@
+//! A debugging session with a connected virtual machine, executing commands and describing events
+//! the way `jdb` does
+
+use crate::command::{BreakpointSpec, CatchSpec, Command, WatchSpec, WhereTarget, HELP};
+use crate::format::{format_value, source_path};
+use jdi_rs::event::{Event, EventSet};
+use jdi_rs::expr::{EvalError, Expression, ParseError};
+use jdi_rs::redefine::RedefineError;
+use jdi_rs::request::{
+    AccessWatchpointRequest, BreakpointRequest, ClassPrepareRequest, EventRequestError,
+    ExceptionBreakpoint, LineBreakpoint, ModificationWatchpointRequest, StepOptions,
+};
+use jdi_rs::{Location, ReferenceType, StackFrame, ThreadReference, Value, VirtualMachine};
+use jdwp_types::{Int, StepDepth, StepSize, SuspendPolicy, Tag, ThreadStatus};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// A command couldn't be executed
+#[derive(Debug, Error)]
+pub enum CommandError {
+    /// The command needs a current thread, and none has been set
+    #[error("No thread specified.")]
+    NoCurrentThread,
+    /// The command needs the current thread to be suspended
+    #[error("Current thread isn't suspended.")]
+    NotSuspended,
+    /// No thread has the given id or name
+    #[error("\"{0}\" is not a valid thread id.")]
+    UnknownThread(String),
+    /// No class with the given name is loaded
+    #[error("\"{0}\" is not a valid class name, or the class isn't loaded.")]
+    UnknownClass(String),
+    /// A loaded class has no method with the given name and argument types
+    #[error("Unable to set breakpoint {0}: no such method")]
+    UnknownMethod(BreakpointSpec),
+    /// A loaded class has no field with the given name
+    #[error("Class {class} has no field {field}")]
+    UnknownField { class: String, field: String },
+    /// The current frame is already the outermost one
+    #[error("End of stack.")]
+    EndOfStack,
+    /// The current frame is already the innermost one
+    #[error("Current frame is at top of stack.")]
+    TopOfStack,
+    /// No breakpoint, catch or watch matches the one to remove
+    #[error("Not found: {0}")]
+    NotFound(String),
+    /// The command can't be used once connected
+    #[error("The VM is already connected.")]
+    AlreadyConnected,
+    #[error(transparent)]
+    Eval(#[from] EvalError),
+    #[error(transparent)]
+    Request(#[from] EventRequestError),
+    #[error(transparent)]
+    Redefine(#[from] RedefineError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl From<ParseError> for CommandError {
+    fn from(value: ParseError) -> Self {
+        CommandError::Eval(value.into())
+    }
+}
+
+/// The thread commands such as `where` and `print` apply to, and the frame within it
+struct Current<VM: VirtualMachine + ?Sized> {
+    thread: ThreadReference<VM>,
+    name: String,
+    frame: Int,
+}
+
+/// A breakpoint set by `stop`
+enum Breakpoint<VM: VirtualMachine + ?Sized> {
+    Line(LineBreakpoint<VM>),
+    /// The breakpoint requests at the start of every matching method, which are empty until the
+    /// class is loaded
+    Method(Vec<BreakpointRequest<VM>>),
+}
+
+/// A field watched by `watch`, whose requests are empty until the class is loaded
+struct Watch<VM: VirtualMachine + ?Sized> {
+    access: Option<AccessWatchpointRequest<VM>>,
+    modification: Option<ModificationWatchpointRequest<VM>>,
+}
+
+/// A debugging session with a connected virtual machine.
+///
+/// Commands are [executed](Self::execute) and the event sets removed from the virtual machine's
+/// [EventQueue](jdi_rs::event::EventQueue) are [handled](Self::handle_event_set) here, both
+/// returning the text to print.
+pub struct Session<VM: VirtualMachine + ?Sized> {
+    vm: Arc<VM>,
+    sourcepath: Vec<PathBuf>,
+    current: Option<Current<VM>>,
+    breakpoints: Vec<(BreakpointSpec, Breakpoint<VM>)>,
+    catches: Vec<(CatchSpec, ExceptionBreakpoint<VM>)>,
+    watches: Vec<(WatchSpec, Watch<VM>)>,
+    /// The requests for the classes that deferred breakpoints and watches are waiting for
+    class_prepares: HashMap<String, ClassPrepareRequest<VM>>,
+    /// Whether the virtual machine is resumed once it starts, rather than waiting for `run`
+    resume_on_start: bool,
+    started: bool,
+    disconnected: bool,
+}
+
+impl<VM: VirtualMachine + ?Sized> Session<VM> {
+    /// Creates a session with a virtual machine, whose sources are looked for in the given
+    /// directories
+    pub fn new(vm: Arc<VM>, sourcepath: Vec<PathBuf>) -> Self {
+        Self {
+            vm,
+            sourcepath,
+            current: None,
+            breakpoints: vec![],
+            catches: vec![],
+            watches: vec![],
+            class_prepares: HashMap::new(),
+            resume_on_start: false,
+            started: false,
+            disconnected: false,
+        }
+    }
+
+    /// Resumes the virtual machine as soon as it starts, like `run` does for a launched one
+    pub fn resume_on_start(&mut self) {
+        self.resume_on_start = true;
+    }
+
+    /// Gets the connected virtual machine
+    pub fn virtual_machine(&self) -> &Arc<VM> {
+        &self.vm
+    }
+
+    /// Whether the virtual machine has disconnected, after which the session is over
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    /// Gets the prompt, which names the current thread and frame once there is one, such as
+    /// `main[1] `
+    pub fn prompt(&self) -> String {
+        match &self.current {
+            Some(current) => format!("{}[{}] ", current.name, current.frame + 1),
+            None => "> ".to_string(),
+        }
+    }
+
+    /// Executes a command, returning the text to print
+    pub async fn execute(&mut self, command: Command) -> Result<String, CommandError> {
+        match command {
+            Command::Attach(_) => Err(CommandError::AlreadyConnected),
+            Command::Run { .. } if self.started => Err(CommandError::AlreadyConnected),
+            Command::Run { .. } => {
+                self.started = true;
+                self.vm.resume().await?;
+                Ok(String::new())
+            }
+            Command::Threads => self.threads().await,
+            Command::Thread(id) => {
+                let thread = self.find_thread(&id).await?;
+                self.set_current(thread).await?;
+                Ok(String::new())
+            }
+            Command::Where(target) => self.where_(target).await,
+            Command::Up(frames) => self.move_frame(frames).await,
+            Command::Down(frames) => self.move_frame(-frames).await,
+            Command::Suspend(ids) if ids.is_empty() => {
+                self.vm.suspend().await?;
+                Ok("All threads suspended.".to_string())
+            }
+            Command::Suspend(ids) => {
+                let mut output = String::new();
+                for id in ids {
+                    self.find_thread(&id).await?.suspend().await?;
+                    writeln!(output, "Thread {id} suspended.").unwrap();
+                }
+                Ok(output)
+            }
+            Command::Resume(ids) if ids.is_empty() => {
+                self.vm.resume().await?;
+                Ok("All threads resumed.".to_string())
+            }
+            Command::Resume(ids) => {
+                let mut output = String::new();
+                for id in ids {
+                    self.find_thread(&id).await?.resume().await?;
+                    writeln!(output, "Thread {id} resumed.").unwrap();
+                }
+                Ok(output)
+            }
+            Command::Print(expression) => {
+                let frame = self.frame().await?;
+                let value = Expression::parse(&expression)?.evaluate(&frame).await?;
+                Ok(format!(" {expression} = {}", format_value(&value).await?))
+            }
+            Command::Dump(expression) => {
+                let frame = self.frame().await?;
+                let value = Expression::parse(&expression)?.evaluate(&frame).await?;
+                Ok(format!(" {expression} = {}", dump_value(&value).await?))
+            }
+            Command::Locals => self.locals().await,
+            Command::Set { variable, value } => {
+                let frame = self.frame().await?;
+                let value = Expression::parse(&variable)?
+                    .assign(&frame, &Expression::parse(&value)?)
+                    .await?;
+                Ok(format!(" {variable} = {}", format_value(&value).await?))
+            }
+            Command::Classes => {
+                let mut names = self
+                    .vm
+                    .all_classes()
+                    .await?
+                    .into_iter()
+                    .map(|class| class.name())
+                    .collect::<Vec<_>>();
+                names.sort();
+                Ok(format!("** classes list **\n{}", names.join("\n")))
+            }
+            Command::Methods(class) => self.methods(&class).await,
+            Command::Fields(class) => self.fields(&class).await,
+            Command::Stop(None) | Command::Clear(None) => Ok(self.list_breakpoints()),
+            Command::Stop(Some(spec)) => self.stop(spec).await,
+            Command::Clear(Some(spec)) => self.clear(spec).await,
+            Command::Catch(None) => Ok(self
+                .catches
+                .iter()
+                .map(|(spec, _)| format!("catch {spec}"))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            Command::Catch(Some(spec)) => self.catch(spec).await,
+            Command::Ignore(spec) => self.ignore(spec).await,
+            Command::Watch(spec) => self.watch(spec).await,
+            Command::Unwatch(spec) => self.unwatch(spec).await,
+            Command::Step => self.step(StepSize::Line, StepDepth::Into).await,
+            Command::StepUp => self.step(StepSize::Line, StepDepth::Out).await,
+            Command::Next => self.step(StepSize::Line, StepDepth::Over).await,
+            Command::Cont => {
+                self.started = true;
+                self.vm.resume().await?;
+                Ok(String::new())
+            }
+            Command::Redefine { class, class_file } => {
+                let class_file = std::fs::read(class_file)?;
+                let classes = self
+                    .classes(&class)
+                    .await?
+                    .into_iter()
+                    .map(|ref_type| (ref_type, class_file.clone()))
+                    .collect::<Vec<_>>();
+                self.vm.redefine_classes(&classes).await?;
+                Ok(format!("Redefined {class}"))
+            }
+            Command::Help => Ok(HELP.to_string()),
+            Command::Exit => Ok(String::new()),
+        }
+    }
+
+    /// Handles an event set removed from the virtual machine's event queue, returning the text to
+    /// print. Sets that don't stop the virtual machine for the user, such as a class being loaded,
+    /// are resumed here.
+    pub async fn handle_event_set(&mut self, set: EventSet<VM>) -> Result<String, CommandError> {
+        let mut output = String::new();
+        let mut stopped = false;
+        for event in set.events() {
+            match event {
+                Event::VmStart(_) if self.resume_on_start => {
+                    self.started = true;
+                    stopped = true;
+                    set.resume().await?;
+                    output.push_str("VM Started:");
+                }
+                Event::VmStart(event) => {
+                    stopped = true;
+                    writeln!(output, "VM Started: No frames on the current call stack").unwrap();
+                    self.set_current(event.thread().clone()).await?;
+                }
+                Event::VmDeath(_) => output.push_str("\nThe application exited"),
+                Event::VmDisconnect(_) => {
+                    self.disconnected = true;
+                    self.current = None;
+                }
+                Event::Breakpoint(event) => {
+                    stopped = true;
+                    let description = self.describe(event.thread(), event.location()).await?;
+                    output.push_str(&format!("Breakpoint hit: {description}"));
+                }
+                Event::Step(event) => {
+                    stopped = true;
+                    if let Some(request) = event.request() {
+                        let manager = self.vm.event_request_manager();
+                        manager.delete_event_request(request.clone()).await?;
+                    }
+                    let description = self.describe(event.thread(), event.location()).await?;
+                    output.push_str(&format!("Step completed: {description}"));
+                }
+                Event::Exception(event) => {
+                    stopped = true;
+                    let exception = event.exception().reference_type().await?.name();
+                    let caught = match event.catch_location() {
+                        Some(location) => {
+                            format!("to be caught at: {}", location_name(location).await?)
+                        }
+                        None => "uncaught".to_string(),
+                    };
+                    let description = self.describe(event.thread(), event.location()).await?;
+                    output.push_str(&format!(
+                        "Exception occurred: {exception} ({caught}){description}"
+                    ));
+                }
+                Event::AccessWatchpoint(event) => {
+                    stopped = true;
+                    let field = self.watched_field(|watch| {
+                        watch.access.is_some() && watch.access.as_ref() == event.request()
+                    });
+                    let value = match event.current_value() {
+                        Some(value) => format_value(value).await?,
+                        None => "<unknown>".to_string(),
+                    };
+                    let description = self.describe(event.thread(), event.location()).await?;
+                    output.push_str(&format!("Field ({field}) is {value}: {description}"));
+                }
+                Event::ModificationWatchpoint(event) => {
+                    stopped = true;
+                    let field = self.watched_field(|watch| {
+                        watch.modification.is_some()
+                            && watch.modification.as_ref() == event.request()
+                    });
+                    let value = match event.current_value() {
+                        Some(value) => format_value(value).await?,
+                        None => "<unknown>".to_string(),
+                    };
+                    let value_to_be = format_value(event.value_to_be()).await?;
+                    let description = self.describe(event.thread(), event.location()).await?;
+                    output.push_str(&format!(
+                        "Field ({field}) is {value}, will be {value_to_be}: {description}"
+                    ));
+                }
+                Event::ClassPrepare(event) => {
+                    let class = event.reference_type().name();
+                    if let Some(request) = self.class_prepares.remove(&class) {
+                        self.vm
+                            .event_request_manager()
+                            .delete_event_request(request)
+                            .await?;
+                        output.push_str(&self.resolve_deferred(event.reference_type()).await?);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !stopped && set.suspend_policy() != SuspendPolicy::None {
+            set.resume().await?;
+        }
+        Ok(output.trim_end().to_string())
+    }
+
+    /// Describes where a thread stopped, such as
+    /// `"thread=main", com.acme.Foo.bar(), line=12 bci=3`, followed by the source line if it can
+    /// be found. The thread becomes the current one.
+    async fn describe(
+        &mut self,
+        thread: &ThreadReference<VM>,
+        location: &Location<VM>,
+    ) -> io::Result<String> {
+        self.set_current(thread.clone()).await?;
+        let name = self.current.as_ref().map_or("", |current| &current.name);
+        let mut description = format!("\"thread={name}\", {}", location_name(location).await?);
+        if let Some(source) = self.source_line(location).await? {
+            write!(description, "\n{source}").unwrap();
+        }
+        Ok(description)
+    }
+
+    /// Reads the line of source code a location is at, prefixed by its number
+    async fn source_line(&self, location: &Location<VM>) -> io::Result<Option<String>> {
+        let Some(line) = location.line_number().await? else {
+            return Ok(None);
+        };
+        let ref_type = location.declaring_type().await?;
+        let Some(source_name) = ref_type.source_name().await? else {
+            return Ok(None);
+        };
+        let name = ref_type.name();
+        let path = match name.rsplit_once('.') {
+            Some((package, _)) => PathBuf::from(package.replace('.', "/")).join(source_name),
+            None => PathBuf::from(source_name),
+        };
+        for root in &self.sourcepath {
+            if let Ok(source) = std::fs::read_to_string(root.join(&path)) {
+                return Ok(source
+                    .lines()
+                    .nth(line as usize - 1)
+                    .map(|text| format!("{line:<8}{text}")));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Makes a thread the current one, at its top frame
+    async fn set_current(&mut self, thread: ThreadReference<VM>) -> io::Result<()> {
+        let name = thread.name().await?;
+        self.current = Some(Current {
+            thread,
+            name,
+            frame: 0,
+        });
+        Ok(())
+    }
+
+    /// Finds a thread by its id, in hex as listed by `threads` or in decimal, or by its name
+    async fn find_thread(&self, id: &str) -> Result<ThreadReference<VM>, CommandError> {
+        let parsed = match id.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => id.parse().ok(),
+        };
+        for thread in self.vm.all_threads().await? {
+            if Some(thread.id().get()) == parsed || thread.name().await? == id {
+                return Ok(thread);
+            }
+        }
+        Err(CommandError::UnknownThread(id.to_string()))
+    }
+
+    /// Gets the current frame of the current thread, which must be suspended
+    async fn frame(&self) -> Result<StackFrame<VM>, CommandError> {
+        let current = self.current.as_ref().ok_or(CommandError::NoCurrentThread)?;
+        if !current.thread.is_suspended().await? {
+            return Err(CommandError::NotSuspended);
+        }
+        Ok(current.thread.frame(current.frame).await?)
+    }
+
+    async fn threads(&self) -> Result<String, CommandError> {
+        let mut output = String::new();
+        for thread in self.vm.all_threads().await? {
+            let status = match thread.status().await? {
+                ThreadStatus::Zombie => "zombie",
+                ThreadStatus::Running => "running",
+                ThreadStatus::Sleeping => "sleeping",
+                ThreadStatus::Monitor => "waiting in a monitor",
+                ThreadStatus::Wait => "cond. waiting",
+            };
+            let suspended = if thread.is_suspended().await? {
+                " (suspended)"
+            } else {
+                ""
+            };
+            writeln!(
+                output,
+                "  0x{:x} {} {status}{suspended}",
+                thread.id().get(),
+                thread.name().await?
+            )
+            .unwrap();
+        }
+        Ok(output)
+    }
+
+    async fn where_(&self, target: WhereTarget) -> Result<String, CommandError> {
+        let threads = match target {
+            WhereTarget::Current => {
+                let current = self.current.as_ref().ok_or(CommandError::NoCurrentThread)?;
+                vec![current.thread.clone()]
+            }
+            WhereTarget::All => self.vm.all_threads().await?,
+            WhereTarget::Thread(id) => vec![self.find_thread(&id).await?],
+        };
+        let all = threads.len() > 1;
+        let mut output = String::new();
+        for thread in threads {
+            if all {
+                writeln!(output, "{}:", thread.name().await?).unwrap();
+            }
+            if !thread.is_suspended().await? {
+                writeln!(output, "  Thread is not suspended").unwrap();
+                continue;
+            }
+            for (index, frame) in thread.frames().await?.iter().enumerate() {
+                writeln!(
+                    output,
+                    "  [{}] {}",
+                    index + 1,
+                    frame_name(frame.location()).await?
+                )
+                .unwrap();
+            }
+        }
+        Ok(output)
+    }
+
+    /// Moves the current frame up, towards the callers, or down if the number is negative
+    async fn move_frame(&mut self, frames: Int) -> Result<String, CommandError> {
+        let current = self.current.as_ref().ok_or(CommandError::NoCurrentThread)?;
+        if !current.thread.is_suspended().await? {
+            return Err(CommandError::NotSuspended);
+        }
+        let frame = current.frame + frames;
+        if frame < 0 {
+            return Err(CommandError::TopOfStack);
+        }
+        if frame >= current.thread.frame_count().await? {
+            return Err(CommandError::EndOfStack);
+        }
+        if let Some(current) = &mut self.current {
+            current.frame = frame;
+        }
+        Ok(String::new())
+    }
+
+    async fn locals(&self) -> Result<String, CommandError> {
+        let frame = self.frame().await?;
+        let variables = frame.visible_variables().await?;
+        let values = frame.get_values(&variables).await?;
+        let mut arguments = String::from("Method arguments:\n");
+        let mut locals = String::from("Local variables:\n");
+        for (variable, value) in variables.iter().zip(&values) {
+            let output = if variable.is_argument() {
+                &mut arguments
+            } else {
+                &mut locals
+            };
+            writeln!(
+                output,
+                "{} = {}",
+                variable.name(),
+                format_value(value).await?
+            )
+            .unwrap();
+        }
+        Ok(arguments + &locals)
+    }
+
+    /// Gets the loaded classes with a name, failing if there aren't any
+    async fn classes(&self, name: &str) -> Result<Vec<ReferenceType<VM>>, CommandError> {
+        let classes = self.vm.classes_by_name(name).await?;
+        if classes.is_empty() {
+            return Err(CommandError::UnknownClass(name.to_string()));
+        }
+        Ok(classes)
+    }
+
+    async fn methods(&self, class: &str) -> Result<String, CommandError> {
+        let ref_type = self.classes(class).await?.remove(0);
+        let mut output = String::from("** methods list **\n");
+        let types = std::iter::once(ref_type.clone()).chain(ref_type.all_supertypes().await?);
+        for ref_type in types {
+            let name = ref_type.name();
+            for method in ref_type.methods().await? {
+                writeln!(
+                    output,
+                    "{name} {}({})",
+                    method.name(),
+                    method.argument_type_names().join(", ")
+                )
+                .unwrap();
+            }
+        }
+        Ok(output)
+    }
+
+    async fn fields(&self, class: &str) -> Result<String, CommandError> {
+        let ref_type = self.classes(class).await?.remove(0);
+        let mut output = String::from("** fields list **\n");
+        for field in ref_type.fields().await? {
+            writeln!(output, "{} {}", field.type_name(), field.name()).unwrap();
+        }
+        for supertype in ref_type.all_supertypes().await? {
+            for field in supertype.fields().await? {
+                writeln!(
+                    output,
+                    "{} {} (inherited from {})",
+                    field.type_name(),
+                    field.name(),
+                    supertype.name()
+                )
+                .unwrap();
+            }
+        }
+        Ok(output)
+    }
+
+    /// Gets the qualified name of the watched field whose requests match, such as
+    /// `com.acme.Foo.count`
+    fn watched_field(&self, matches: impl Fn(&Watch<VM>) -> bool) -> String {
+        self.watches
+            .iter()
+            .find(|(_, watch)| matches(watch))
+            .map_or("<unwatched>".to_string(), |(spec, _)| {
+                format!("{}.{}", spec.class, spec.field)
+            })
+    }
+
+    fn list_breakpoints(&self) -> String {
+        if self.breakpoints.is_empty() {
+            return "No breakpoints set.".to_string();
+        }
+        let mut output = String::from("Breakpoints set:");
+        for (spec, breakpoint) in &self.breakpoints {
+            let resolved = match breakpoint {
+                Breakpoint::Line(breakpoint) => breakpoint.is_resolved(),
+                Breakpoint::Method(requests) => !requests.is_empty(),
+            };
+            let deferred = if resolved { "" } else { " (deferred)" };
+            write!(output, "\n\tbreakpoint {spec}{deferred}").unwrap();
+        }
+        output
+    }
+
+    async fn stop(&mut self, spec: BreakpointSpec) -> Result<String, CommandError> {
+        if self.breakpoints.iter().any(|(other, _)| *other == spec) {
+            return Ok(format!("Breakpoint {spec} is already set"));
+        }
+        let (breakpoint, resolved) = match &spec {
+            BreakpointSpec::Line { class, line } => {
+                let breakpoint = self
+                    .vm
+                    .event_request_manager()
+                    .set_breakpoint(&source_path(class), *line)
+                    .await?;
+                let resolved = breakpoint.is_resolved();
+                (Breakpoint::Line(breakpoint), resolved)
+            }
+            BreakpointSpec::Method { class, .. } => {
+                let classes = self.vm.classes_by_name(class).await?;
+                let requests = self.method_breakpoints(&spec, &classes).await?;
+                if !classes.is_empty() && requests.is_empty() {
+                    return Err(CommandError::UnknownMethod(spec));
+                }
+                if classes.is_empty() {
+                    self.defer(class).await?;
+                }
+                let resolved = !requests.is_empty();
+                (Breakpoint::Method(requests), resolved)
+            }
+        };
+        self.breakpoints.push((spec.clone(), breakpoint));
+        Ok(if resolved {
+            format!("Set breakpoint {spec}")
+        } else {
+            format!("Deferring breakpoint {spec}.\nIt will be set after the class is loaded.")
+        })
+    }
+
+    /// Sets breakpoints at the start of every method matching a spec in the given classes
+    async fn method_breakpoints(
+        &self,
+        spec: &BreakpointSpec,
+        classes: &[ReferenceType<VM>],
+    ) -> Result<Vec<BreakpointRequest<VM>>, CommandError> {
+        let BreakpointSpec::Method {
+            method, arguments, ..
+        } = spec
+        else {
+            return Ok(vec![]);
+        };
+        let manager = self.vm.event_request_manager();
+        let mut requests = vec![];
+        for class in classes {
+            for candidate in class.methods_by_name(method).await? {
+                if let Some(arguments) = arguments {
+                    let names = candidate.argument_type_names();
+                    let matches = names.len() == arguments.len()
+                        && names.iter().zip(arguments).all(|(name, argument)| {
+                            name == argument || name.rsplit('.').next() == Some(argument)
+                        });
+                    if !matches {
+                        continue;
+                    }
+                }
+                let Some(location) = candidate.location().await? else {
+                    continue;
+                };
+                let request = manager.create_breakpoint_request(&location);
+                request.enable().await?;
+                requests.push(request);
+            }
+        }
+        Ok(requests)
+    }
+
+    async fn clear(&mut self, spec: BreakpointSpec) -> Result<String, CommandError> {
+        let index = self
+            .breakpoints
+            .iter()
+            .position(|(other, _)| *other == spec)
+            .ok_or_else(|| CommandError::NotFound(spec.to_string()))?;
+        let manager = self.vm.event_request_manager();
+        match self.breakpoints.remove(index).1 {
+            Breakpoint::Line(breakpoint) => manager.delete_line_breakpoint(&breakpoint).await?,
+            Breakpoint::Method(requests) => manager.delete_event_requests(requests).await?,
+        }
+        Ok(format!("Removed: {spec}"))
+    }
+
+    async fn catch(&mut self, spec: CatchSpec) -> Result<String, CommandError> {
+        let breakpoint = self
+            .vm
+            .event_request_manager()
+            .break_on_exception(&spec.pattern, spec.caught, spec.uncaught)
+            .await?;
+        let resolved = breakpoint.is_resolved();
+        self.catches.push((spec.clone(), breakpoint));
+        Ok(if resolved {
+            format!("Set {spec}")
+        } else {
+            format!("Deferring {spec}.\nIt will be set after the class is loaded.")
+        })
+    }
+
+    async fn ignore(&mut self, spec: CatchSpec) -> Result<String, CommandError> {
+        let index = self
+            .catches
+            .iter()
+            .position(|(other, _)| *other == spec)
+            .ok_or_else(|| CommandError::NotFound(spec.to_string()))?;
+        let (_, breakpoint) = self.catches.remove(index);
+        self.vm
+            .event_request_manager()
+            .delete_exception_breakpoint(&breakpoint)
+            .await?;
+        Ok(format!("Removed: {spec}"))
+    }
+
+    async fn watch(&mut self, spec: WatchSpec) -> Result<String, CommandError> {
+        let classes = self.vm.classes_by_name(&spec.class).await?;
+        let watch = match classes.first() {
+            Some(class) => self.watch_field(&spec, class).await?,
+            None => {
+                self.defer(&spec.class).await?;
+                Watch {
+                    access: None,
+                    modification: None,
+                }
+            }
+        };
+        let resolved = !classes.is_empty();
+        self.watches.push((spec.clone(), watch));
+        Ok(if resolved {
+            format!("Set watch {spec}")
+        } else {
+            format!("Deferring watch {spec}.\nIt will be set after the class is loaded.")
+        })
+    }
+
+    /// Watches the field a spec names in a loaded class
+    async fn watch_field(
+        &self,
+        spec: &WatchSpec,
+        class: &ReferenceType<VM>,
+    ) -> Result<Watch<VM>, CommandError> {
+        let field =
+            class
+                .field_by_name(&spec.field)
+                .await?
+                .ok_or_else(|| CommandError::UnknownField {
+                    class: spec.class.clone(),
+                    field: spec.field.clone(),
+                })?;
+        let access = match spec.access {
+            true => Some(field.watch_access(None).await?),
+            false => None,
+        };
+        let modification = match spec.modification {
+            true => Some(field.watch_modification(None).await?),
+            false => None,
+        };
+        Ok(Watch {
+            access,
+            modification,
+        })
+    }
+
+    async fn unwatch(&mut self, spec: WatchSpec) -> Result<String, CommandError> {
+        let index = self
+            .watches
+            .iter()
+            .position(|(other, _)| *other == spec)
+            .ok_or_else(|| CommandError::NotFound(format!("watch {spec}")))?;
+        let (_, watch) = self.watches.remove(index);
+        let manager = self.vm.event_request_manager();
+        manager.delete_event_requests(watch.access).await?;
+        manager.delete_event_requests(watch.modification).await?;
+        Ok(format!("Removed: watch {spec}"))
+    }
+
+    /// Waits for a class to be loaded, to resolve the breakpoints and watches deferred until then
+    async fn defer(&mut self, class: &str) -> Result<(), CommandError> {
+        if self.class_prepares.contains_key(class) {
+            return Ok(());
+        }
+        let request = self
+            .vm
+            .event_request_manager()
+            .create_class_prepare_request();
+        request.add_class_pattern_filter(class)?;
+        request.enable().await?;
+        self.class_prepares.insert(class.to_string(), request);
+        Ok(())
+    }
+
+    /// Resolves the breakpoints and watches that were deferred until a class was loaded
+    async fn resolve_deferred(&mut self, class: &ReferenceType<VM>) -> io::Result<String> {
+        let name = class.name();
+        let mut output = String::new();
+        let mut breakpoints = std::mem::take(&mut self.breakpoints);
+        for (spec, breakpoint) in &mut breakpoints {
+            let Breakpoint::Method(requests) = breakpoint else {
+                continue;
+            };
+            if spec.class() != name || !requests.is_empty() {
+                continue;
+            }
+            match self
+                .method_breakpoints(spec, std::slice::from_ref(class))
+                .await
+            {
+                Ok(resolved) if !resolved.is_empty() => {
+                    *requests = resolved;
+                    writeln!(output, "Set deferred breakpoint {spec}").unwrap();
+                }
+                Ok(_) => writeln!(output, "Unable to set deferred breakpoint {spec}").unwrap(),
+                Err(e) => {
+                    writeln!(output, "Unable to set deferred breakpoint {spec}: {e}").unwrap()
+                }
+            }
+        }
+        self.breakpoints = breakpoints;
+        let mut watches = std::mem::take(&mut self.watches);
+        for (spec, watch) in &mut watches {
+            if spec.class != name || watch.access.is_some() || watch.modification.is_some() {
+                continue;
+            }
+            match self.watch_field(spec, class).await {
+                Ok(resolved) => {
+                    *watch = resolved;
+                    writeln!(output, "Set deferred watch {spec}").unwrap();
+                }
+                Err(e) => writeln!(output, "Unable to set deferred watch {spec}: {e}").unwrap(),
+            }
+        }
+        self.watches = watches;
+        Ok(output)
+    }
+
+    /// Steps the current thread, resuming the virtual machine until the step completes. The
+    /// step's event is handled like any other once it's removed from the event queue.
+    async fn step(&mut self, size: StepSize, depth: StepDepth) -> Result<String, CommandError> {
+        let current = self.current.as_ref().ok_or(CommandError::NoCurrentThread)?;
+        if !current.thread.is_suspended().await? {
+            return Err(CommandError::NotSuspended);
+        }
+        let request = current
+            .thread
+            .prepare_step(size, depth, &StepOptions::default())
+            .await?;
+        request.enable().await?;
+        self.vm.resume().await?;
+        Ok(String::new())
+    }
+}
+
+/// Names a location the way events describe it, such as `com.acme.Foo.bar(), line=12 bci=3`
+async fn location_name<VM: VirtualMachine + ?Sized>(location: &Location<VM>) -> io::Result<String> {
+    let class = location.declaring_type().await?.name();
+    let method = location.method().await?;
+    let line = location.line_number().await?.unwrap_or(-1);
+    Ok(format!(
+        "{class}.{}(), line={line} bci={}",
+        method.name(),
+        location.code_index()
+    ))
+}
+
+/// Names a frame's location the way `where` lists it, such as `com.acme.Foo.bar (Foo.java:12)`
+async fn frame_name<VM: VirtualMachine + ?Sized>(location: &Location<VM>) -> io::Result<String> {
+    let ref_type = location.declaring_type().await?;
+    let method = location.method().await?;
+    let position = match (ref_type.source_name().await?, location.line_number().await?) {
+        _ if method.modifiers() & NATIVE != 0 => "native method".to_string(),
+        (Some(source_name), Some(line)) => format!("{source_name}:{line}"),
+        _ => format!("pc {}", location.code_index()),
+    };
+    Ok(format!(
+        "{}.{} ({position})",
+        ref_type.name(),
+        method.name()
+    ))
+}
+
+/// The modifier of a method implemented in native code
+const NATIVE: Int = 0x0100;
+
+/// Formats a value the way `dump` does, listing the instance fields of objects and the
+/// components of arrays
+async fn dump_value<VM: VirtualMachine + ?Sized>(value: &Value<VM>) -> io::Result<String> {
+    let Value::Object(Some(object)) = value else {
+        return format_value(value).await;
+    };
+    match object.tag() {
+        Tag::String => format_value(value).await,
+        Tag::Array => {
+            let length = object.array_length().await?;
+            let mut components = vec![];
+            for component in object.array_values(0, length).await? {
+                components.push(format_value(&component).await?);
+            }
+            Ok(format!("{{{}}}", components.join(", ")))
+        }
+        _ => {
+            let fields = object
+                .reference_type()
+                .await?
+                .all_fields()
+                .await?
+                .into_iter()
+                .filter(|field| !field.is_static())
+                .collect::<Vec<_>>();
+            let values = object.get_values(&fields).await?;
+            let mut output = String::from("{\n");
+            for (field, value) in fields.iter().zip(&values) {
+                writeln!(
+                    output,
+                    "    {}: {}",
+                    field.name(),
+                    format_value(value).await?
+                )
+                .unwrap();
+            }
+            output.push('}');
+            Ok(output)
+        }
+    }
+}
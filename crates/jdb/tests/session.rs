@@ -0,0 +1,212 @@
+use jdb::command::Command;
+use jdb::session::{CommandError, Session};
+use jdb_test_fixtures::JavaInstance;
+use jdi_rs::*;
+use std::path::PathBuf;
+use std::time::Duration;
+use test_log::test;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Executes a line typed at the prompt
+async fn execute<VM: VirtualMachine>(
+    session: &mut Session<VM>,
+    line: &str,
+) -> Result<String, CommandError> {
+    let command = Command::parse(line)
+        .expect("could not parse")
+        .expect("blank line");
+    session.execute(command).await
+}
+
+/// Handles event sets until one is described
+async fn next_output<VM: VirtualMachine>(session: &mut Session<VM>) -> eyre::Result<String> {
+    loop {
+        let set = session
+            .virtual_machine()
+            .event_queue()
+            .remove_timeout(TIMEOUT)
+            .await?
+            .expect("no event");
+        let output = session.handle_event_set(set).await?;
+        if !output.is_empty() {
+            return Ok(output);
+        }
+    }
+}
+
+#[test(tokio::test)]
+async fn test_session() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Orders").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let sources =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../jdb-test-fixtures/testFixtures");
+    let mut session = Session::new(vm, vec![sources]);
+    assert_eq!(session.prompt(), "> ");
+    assert!(next_output(&mut session).await?.starts_with("VM Started:"));
+
+    assert_eq!(
+        execute(&mut session, "stop at com.acme.Orders:75").await?,
+        "Deferring breakpoint com.acme.Orders:75.\nIt will be set after the class is loaded."
+    );
+    assert_eq!(
+        execute(&mut session, "stop in com.acme.Orders$Order.getItems").await?,
+        "Deferring breakpoint com.acme.Orders$Order.getItems.\nIt will be set after the class is loaded."
+    );
+    execute(&mut session, "cont").await?;
+    assert_eq!(
+        next_output(&mut session).await?,
+        "Set deferred breakpoint com.acme.Orders$Order.getItems"
+    );
+    let hit = next_output(&mut session).await?;
+    assert!(
+        hit.starts_with(
+            "Breakpoint hit: \"thread=main\", com.acme.Orders$Order.getItems(), line=36"
+        ),
+        "{hit}"
+    );
+    assert_eq!(session.prompt(), "main[1] ");
+    assert_eq!(
+        execute(&mut session, "clear com.acme.Orders$Order.getItems").await?,
+        "Removed: com.acme.Orders$Order.getItems"
+    );
+    assert_eq!(
+        execute(&mut session, "stop").await?,
+        "Breakpoints set:\n\tbreakpoint com.acme.Orders:75"
+    );
+
+    execute(&mut session, "cont").await?;
+    let hit = next_output(&mut session).await?;
+    assert!(
+        hit.starts_with("Breakpoint hit: \"thread=main\", com.acme.Orders.process(), line=75 bci="),
+        "{hit}"
+    );
+    assert!(
+        hit.ends_with("\n75              String summary = first + \" of \" + count;"),
+        "{hit}"
+    );
+
+    assert_eq!(execute(&mut session, "print count").await?, " count = 4");
+    assert_eq!(
+        execute(&mut session, "print first.name").await?,
+        " first.name = \"apple\""
+    );
+    assert_eq!(
+        execute(&mut session, "dump order.ratings").await?,
+        " order.ratings = {5, 3, 4}"
+    );
+    let dump = execute(&mut session, "dump order").await?;
+    assert!(
+        dump.contains("\n    ratings: instance of int[3] (id="),
+        "{dump}"
+    );
+    assert!(dump.contains("\n    gift: null\n"), "{dump}");
+    let locals = execute(&mut session, "locals").await?;
+    assert!(
+        locals.starts_with("Method arguments:\norder = instance of com.acme.Orders$Order(id="),
+        "{locals}"
+    );
+    assert!(
+        locals.contains("\nLocal variables:\nfirst = instance of com.acme.Orders$Item(id="),
+        "{locals}"
+    );
+    assert!(locals.ends_with("\ncount = 4\n"), "{locals}");
+    assert_eq!(
+        execute(&mut session, "where").await?,
+        "  [1] com.acme.Orders.process (Orders.java:75)\n  [2] com.acme.Orders.main (Orders.java:86)\n"
+    );
+
+    execute(&mut session, "up").await?;
+    assert_eq!(session.prompt(), "main[2] ");
+    assert!(execute(&mut session, "print count").await.is_err());
+    assert!(matches!(
+        execute(&mut session, "up").await,
+        Err(CommandError::EndOfStack)
+    ));
+    execute(&mut session, "down").await?;
+    assert!(matches!(
+        execute(&mut session, "down").await,
+        Err(CommandError::TopOfStack)
+    ));
+
+    assert_eq!(
+        execute(&mut session, "set count = count * 2").await?,
+        " count = 8"
+    );
+    assert_eq!(execute(&mut session, "next").await?, "");
+    let step = next_output(&mut session).await?;
+    assert!(
+        step.starts_with("Step completed: \"thread=main\", com.acme.Orders.process(), line=76"),
+        "{step}"
+    );
+    assert_eq!(
+        execute(&mut session, "print summary").await?,
+        " summary = \"3 x apple of 8\""
+    );
+
+    let methods = execute(&mut session, "methods com.acme.Orders").await?;
+    assert!(methods.starts_with("** methods list **\n"), "{methods}");
+    assert!(
+        methods.contains("\ncom.acme.Orders process(com.acme.Orders$Order)\n"),
+        "{methods}"
+    );
+    assert!(
+        methods.contains("\njava.lang.Object hashCode()\n"),
+        "{methods}"
+    );
+    let fields = execute(&mut session, "fields com.acme.Orders$Order").await?;
+    assert!(fields.contains("\nint[] ratings\n"), "{fields}");
+    let classes = execute(&mut session, "classes").await?;
+    assert!(classes.contains("\ncom.acme.Orders\n"), "{classes}");
+    let threads = execute(&mut session, "threads").await?;
+    assert!(threads.contains(" main running (suspended)\n"), "{threads}");
+    assert!(matches!(
+        execute(&mut session, "methods com.acme.Missing").await,
+        Err(CommandError::UnknownClass(_))
+    ));
+
+    assert_eq!(
+        execute(&mut session, "watch com.acme.Orders.created").await?,
+        "Set watch modification of com.acme.Orders.created"
+    );
+    assert_eq!(
+        execute(&mut session, "unwatch com.acme.Orders.created").await?,
+        "Removed: watch modification of com.acme.Orders.created"
+    );
+    assert!(matches!(
+        execute(&mut session, "watch com.acme.Orders.missing").await,
+        Err(CommandError::UnknownField { .. })
+    ));
+    execute(
+        &mut session,
+        "catch uncaught java.lang.IllegalStateException",
+    )
+    .await?;
+    assert_eq!(
+        execute(&mut session, "catch").await?,
+        "catch uncaught java.lang.IllegalStateException"
+    );
+    assert_eq!(
+        execute(
+            &mut session,
+            "ignore uncaught java.lang.IllegalStateException"
+        )
+        .await?,
+        "Removed: uncaught java.lang.IllegalStateException"
+    );
+
+    assert_eq!(
+        execute(&mut session, "clear com.acme.Orders:75").await?,
+        "Removed: com.acme.Orders:75"
+    );
+    assert_eq!(execute(&mut session, "clear").await?, "No breakpoints set.");
+    assert!(matches!(
+        execute(&mut session, "clear com.acme.Orders:75").await,
+        Err(CommandError::NotFound(_))
+    ));
+    assert!(matches!(
+        execute(&mut session, "thread 0xdeadbeef").await,
+        Err(CommandError::UnknownThread(_))
+    ));
+    Ok(())
+}
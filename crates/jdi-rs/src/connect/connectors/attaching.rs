@@ -75,10 +75,7 @@ impl TransportService for AttachingTcpStreamTransportService {
     async fn connect(&self) -> io::Result<JdwpClient<Self::Transport>> {
         for addr in &self.addresses {
             trace!("trying to connect to JDWP client at {addr:?}");
-            if let Ok(client) = TcpStream::connect(addr)
-                .and_then(|stream| JdwpClient::create(stream))
-                .await
-            {
+            if let Ok(client) = TcpStream::connect(addr).and_then(JdwpClient::create).await {
                 return Ok(client);
             }
         }
@@ -112,12 +109,10 @@ impl TransportCapabilities for AttachingTcpStreamTransportCapabilities {
 #[cfg(test)]
 mod tests {
     use crate::connect::connectors::attaching::TcpAttachingConnector;
-    use crate::connect::spi::TransportService;
-    use crate::connect::{Connector, Transport};
 
     #[tokio::test]
     async fn test_create_attaching_service() {
-        let create_service = TcpAttachingConnector::tcp("localhost:5005")
+        let _create_service = TcpAttachingConnector::tcp("localhost:5005")
             .await
             .expect("could not create service");
     }
@@ -2,10 +2,25 @@
 
 mod manager;
 mod mirror;
-mod objects;
-mod reference_type;
+pub(crate) mod objects;
+mod value;
 mod virtual_machine;
 
 pub(crate) mod private;
 
-pub use self::{manager::VirtualMachineManager, mirror::Mirror, virtual_machine::VirtualMachine};
+pub use self::{
+    manager::VirtualMachineManager,
+    mirror::Mirror,
+    objects::{
+        field::Field,
+        local_variable::LocalVariable,
+        location::Location,
+        method::Method,
+        object_reference::{InvokeError, MonitorInfo, ObjectReference},
+        reference_type::ReferenceType,
+        stack_frame::StackFrame,
+        thread_reference::{OwnedMonitor, ThreadReference},
+    },
+    value::Value,
+    virtual_machine::VirtualMachine,
+};
@@ -1,10 +1,16 @@
 //! futures
 
-use jdwp_client::connect::JdwpTransport;
 use std::future::Future;
 use std::pin::Pin;
 
 pub mod all_classes;
 pub mod class;
+pub mod field;
+pub mod local_variable;
+pub mod location;
+pub mod method;
+pub mod object_reference;
 pub mod reference_type;
+pub mod stack_frame;
+pub mod thread_reference;
 type BoxedFuture<T> = Pin<Box<dyn Future<Output = T>>>;
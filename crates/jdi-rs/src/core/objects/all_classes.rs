@@ -3,7 +3,6 @@ use crate::core::objects::BoxedFuture;
 use crate::VirtualMachine;
 use jdwp_client::commands;
 use jdwp_client::commands::{AllClassesReply, ClassReferenceWithSignature};
-use jdwp_client::connect::JdwpTransport;
 use pin_project::pin_project;
 use std::future::Future;
 use std::io;
@@ -25,7 +24,7 @@ impl<VM: VirtualMachine + ?Sized> Future for AllClasses<VM> {
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let mut me = self.project();
         loop {
-            if let Some(future) = me.future.as_mut().as_pin_mut().take() {
+            if let Some(future) = me.future.as_mut().as_pin_mut() {
                 match future.poll(cx) {
                     Poll::Ready(ready) => {
                         me.future.take();
@@ -40,11 +39,7 @@ impl<VM: VirtualMachine + ?Sized> Future for AllClasses<VM> {
                                         status,
                                     } = class;
                                     vector.push(ReferenceType::new(
-                                        type_tag,
-                                        id,
-                                        signature,
-                                        status,
-                                        &me.vm,
+                                        type_tag, id, signature, status, me.vm,
                                     ));
                                 }
                                 return Poll::Ready(Ok(vector));
@@ -61,7 +56,7 @@ impl<VM: VirtualMachine + ?Sized> Future for AllClasses<VM> {
             } else {
                 let vm = me.vm.upgrade().expect("vm is dead");
                 let future = Box::pin(async move { vm.client().send(commands::AllClasses).await });
-                let _ = me.future.insert(future);
+                me.future.set(Some(future));
             }
         }
     }
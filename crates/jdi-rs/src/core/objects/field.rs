@@ -0,0 +1,106 @@
+use crate::core::objects::reference_type::signature_name;
+use crate::{Mirror, VirtualMachine};
+use jdwp_types::{FieldId, Int, ReferenceTypeId};
+use std::hash::{Hash, Hasher};
+use std::sync::Weak;
+
+/// A class or instance variable in the target VM
+#[derive(Debug)]
+pub struct Field<VM: VirtualMachine + ?Sized> {
+    declaring_type: ReferenceTypeId,
+    id: FieldId,
+    name: String,
+    signature: String,
+    modifiers: Int,
+    vm: Weak<VM>,
+}
+
+impl<VM: VirtualMachine + ?Sized> Field<VM> {
+    /// Creates a new field
+    pub fn new(
+        declaring_type: ReferenceTypeId,
+        id: FieldId,
+        name: String,
+        signature: String,
+        modifiers: Int,
+        vm: &Weak<VM>,
+    ) -> Self {
+        Self {
+            declaring_type,
+            id,
+            name,
+            signature,
+            modifiers,
+            vm: vm.clone(),
+        }
+    }
+
+    /// Gets the id of this field
+    pub fn id(&self) -> FieldId {
+        self.id
+    }
+
+    /// Gets the id of the type this field is declared in
+    pub fn declaring_type_id(&self) -> ReferenceTypeId {
+        self.declaring_type
+    }
+
+    /// Gets the name of this field
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the JNI signature of this field's type, such as `Ljava/lang/String;`
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    /// Gets the name of this field's type, such as `java.lang.String`
+    pub fn type_name(&self) -> String {
+        signature_name(&self.signature)
+    }
+
+    /// Gets the modifier bits of this field, as defined in the JVM specification
+    pub fn modifiers(&self) -> Int {
+        self.modifiers
+    }
+
+    /// Whether this field is static
+    pub fn is_static(&self) -> bool {
+        self.modifiers & 0x0008 != 0
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for Field<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            declaring_type: self.declaring_type,
+            id: self.id,
+            name: self.name.clone(),
+            signature: self.signature.clone(),
+            modifiers: self.modifiers,
+            vm: self.vm.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> PartialEq for Field<VM> {
+    fn eq(&self, other: &Self) -> bool {
+        self.declaring_type == other.declaring_type && self.id == other.id
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Eq for Field<VM> {}
+
+impl<VM: VirtualMachine + ?Sized> Hash for Field<VM> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.declaring_type.hash(state);
+        self.id.hash(state);
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Mirror<VM> for Field<VM> {
+    fn virtual_machine(&self) -> Weak<VM> {
+        self.vm.clone()
+    }
+}
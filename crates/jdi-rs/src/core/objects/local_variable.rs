@@ -0,0 +1,138 @@
+use crate::core::objects::reference_type::signature_name;
+use crate::{Location, Mirror, VirtualMachine};
+use jdwp_types::{Int, MethodId, ReferenceTypeId, Tag};
+use std::hash::{Hash, Hasher};
+use std::sync::Weak;
+
+/// A local variable or argument of a method in the target VM
+#[derive(Debug)]
+pub struct LocalVariable<VM: VirtualMachine + ?Sized> {
+    declaring_type: ReferenceTypeId,
+    method: MethodId,
+    name: String,
+    signature: String,
+    code_index: u64,
+    length: u64,
+    slot: Int,
+    argument: bool,
+    vm: Weak<VM>,
+}
+
+impl<VM: VirtualMachine + ?Sized> LocalVariable<VM> {
+    /// Creates a new local variable, in scope for `length` code indices from `code_index`
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        declaring_type: ReferenceTypeId,
+        method: MethodId,
+        name: String,
+        signature: String,
+        code_index: u64,
+        length: u64,
+        slot: Int,
+        argument: bool,
+        vm: &Weak<VM>,
+    ) -> Self {
+        Self {
+            declaring_type,
+            method,
+            name,
+            signature,
+            code_index,
+            length,
+            slot,
+            argument,
+            vm: vm.clone(),
+        }
+    }
+
+    /// Gets the name of this variable
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the JNI signature of this variable's type, such as `Ljava/lang/String;`
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    /// Gets the name of this variable's type, such as `java.lang.String`
+    pub fn type_name(&self) -> String {
+        signature_name(&self.signature)
+    }
+
+    /// Gets the tag of this variable's type, as needed to read its value
+    pub fn tag(&self) -> Tag {
+        match self.signature.as_bytes().first() {
+            Some(b'Z') => Tag::Boolean,
+            Some(b'B') => Tag::Byte,
+            Some(b'C') => Tag::Char,
+            Some(b'S') => Tag::Short,
+            Some(b'I') => Tag::Int,
+            Some(b'J') => Tag::Long,
+            Some(b'F') => Tag::Float,
+            Some(b'D') => Tag::Double,
+            Some(b'[') => Tag::Array,
+            _ => Tag::Object,
+        }
+    }
+
+    /// Gets the slot of this variable within its frame
+    pub fn slot(&self) -> Int {
+        self.slot
+    }
+
+    /// Whether this variable is an argument of its method
+    pub fn is_argument(&self) -> bool {
+        self.argument
+    }
+
+    /// Whether this variable is in scope at a location
+    pub fn is_visible(&self, location: &Location<VM>) -> bool {
+        location.declaring_type_id() == self.declaring_type
+            && location.method_id() == self.method
+            && (self.code_index..self.code_index + self.length).contains(&location.code_index())
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for LocalVariable<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            declaring_type: self.declaring_type,
+            method: self.method,
+            name: self.name.clone(),
+            signature: self.signature.clone(),
+            code_index: self.code_index,
+            length: self.length,
+            slot: self.slot,
+            argument: self.argument,
+            vm: self.vm.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> PartialEq for LocalVariable<VM> {
+    fn eq(&self, other: &Self) -> bool {
+        self.declaring_type == other.declaring_type
+            && self.method == other.method
+            && self.slot == other.slot
+            && self.code_index == other.code_index
+            && self.name == other.name
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Eq for LocalVariable<VM> {}
+
+impl<VM: VirtualMachine + ?Sized> Hash for LocalVariable<VM> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.declaring_type.hash(state);
+        self.method.hash(state);
+        self.slot.hash(state);
+        self.code_index.hash(state);
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Mirror<VM> for LocalVariable<VM> {
+    fn virtual_machine(&self) -> Weak<VM> {
+        self.vm.clone()
+    }
+}
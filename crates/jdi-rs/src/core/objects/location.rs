@@ -0,0 +1,124 @@
+use crate::core::objects::reference_type::is_absent_information;
+use crate::core::private::upgrade;
+use crate::{Method, Mirror, ReferenceType, VirtualMachine};
+use jdwp_client::commands::method::LineTable;
+use jdwp_types::{Int, MethodId, ReferenceTypeId, TypeTag};
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::sync::Weak;
+
+/// A point within the executing code of the target VM
+#[derive(Debug)]
+pub struct Location<VM: VirtualMachine + ?Sized> {
+    location: jdwp_types::Location,
+    vm: Weak<VM>,
+}
+
+impl<VM: VirtualMachine + ?Sized> Location<VM> {
+    /// Creates a new location mirror from a raw jdwp location
+    pub fn new(location: jdwp_types::Location, vm: &Weak<VM>) -> Self {
+        Self {
+            location,
+            vm: vm.clone(),
+        }
+    }
+
+    /// Gets whether the declaring type of this location is a class or an interface
+    pub fn type_tag(&self) -> TypeTag {
+        self.location.tag
+    }
+
+    /// Gets the id of the type this location is declared in
+    pub fn declaring_type_id(&self) -> ReferenceTypeId {
+        ReferenceTypeId::new(self.location.class.get())
+    }
+
+    /// Gets the id of the method containing this location
+    pub fn method_id(&self) -> MethodId {
+        self.location.method
+    }
+
+    /// Gets the code position of this location within its method
+    pub fn code_index(&self) -> u64 {
+        self.location.offset
+    }
+
+    /// Gets the raw jdwp location this mirrors
+    pub fn raw(&self) -> jdwp_types::Location {
+        self.location
+    }
+
+    /// Gets the type this location is declared in
+    pub async fn declaring_type(&self) -> io::Result<ReferenceType<VM>> {
+        ReferenceType::from_id(self.type_tag(), self.declaring_type_id(), &self.vm).await
+    }
+
+    /// Gets the method containing this location
+    pub async fn method(&self) -> io::Result<Method<VM>> {
+        self.declaring_type()
+            .await?
+            .methods()
+            .await?
+            .into_iter()
+            .find(|method| method.id() == self.method_id())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "the method of this location no longer exists",
+                )
+            })
+    }
+
+    /// Gets the line of source code containing this location, if the class file recorded line
+    /// numbers
+    pub async fn line_number(&self) -> io::Result<Option<Int>> {
+        let vm = upgrade(&self.vm)?;
+        let line_table = match vm
+            .client()
+            .send(LineTable {
+                ref_type: self.declaring_type_id(),
+                method_id: self.method_id(),
+            })
+            .await
+        {
+            Ok(line_table) => line_table,
+            Err(e) if is_absent_information(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        Ok(line_table
+            .lines
+            .iter()
+            .filter(|line| line.line_code_index as u64 <= self.code_index())
+            .max_by_key(|line| line.line_code_index)
+            .map(|line| line.line_number))
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for Location<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            location: self.location,
+            vm: self.vm.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> PartialEq for Location<VM> {
+    fn eq(&self, other: &Self) -> bool {
+        self.location == other.location
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Eq for Location<VM> {}
+
+impl<VM: VirtualMachine + ?Sized> Hash for Location<VM> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.location.hash(state)
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Mirror<VM> for Location<VM> {
+    fn virtual_machine(&self) -> Weak<VM> {
+        self.vm.clone()
+    }
+}
@@ -0,0 +1,260 @@
+use crate::core::objects::reference_type::signature_name;
+use crate::core::private::upgrade;
+use crate::{LocalVariable, Location, Mirror, VirtualMachine};
+use jdwp_client::commands::method::{LineTable, VariableTable};
+use jdwp_types::{ClassId, Int, MethodId, ReferenceTypeId, TypeTag};
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::sync::Weak;
+
+/// A static or instance method in the target VM
+#[derive(Debug)]
+pub struct Method<VM: VirtualMachine + ?Sized> {
+    type_tag: TypeTag,
+    declaring_type: ReferenceTypeId,
+    id: MethodId,
+    name: String,
+    signature: String,
+    modifiers: Int,
+    vm: Weak<VM>,
+}
+
+impl<VM: VirtualMachine + ?Sized> Method<VM> {
+    /// Creates a new method
+    pub fn new(
+        type_tag: TypeTag,
+        declaring_type: ReferenceTypeId,
+        id: MethodId,
+        name: String,
+        signature: String,
+        modifiers: Int,
+        vm: &Weak<VM>,
+    ) -> Self {
+        Self {
+            type_tag,
+            declaring_type,
+            id,
+            name,
+            signature,
+            modifiers,
+            vm: vm.clone(),
+        }
+    }
+
+    /// Gets the id of this method
+    pub fn id(&self) -> MethodId {
+        self.id
+    }
+
+    /// Gets whether the type this method is declared in is a class or an interface
+    pub fn type_tag(&self) -> TypeTag {
+        self.type_tag
+    }
+
+    /// Gets the id of the type this method is declared in
+    pub fn declaring_type_id(&self) -> ReferenceTypeId {
+        self.declaring_type
+    }
+
+    /// Gets the name of this method
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the JNI signature of this method, such as `(ILjava/lang/String;)V`
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    /// Gets the modifier bits of this method, as defined in the JVM specification
+    pub fn modifiers(&self) -> Int {
+        self.modifiers
+    }
+
+    /// Whether this method is static
+    pub fn is_static(&self) -> bool {
+        self.modifiers & 0x0008 != 0
+    }
+
+    /// Gets the JNI signatures of the types of this method's arguments, such as `["I",
+    /// "Ljava/lang/String;"]` for `(ILjava/lang/String;)V`
+    pub fn argument_type_signatures(&self) -> Vec<&str> {
+        let Some(arguments) = self
+            .signature
+            .strip_prefix('(')
+            .and_then(|s| s.split_once(')'))
+            .map(|(arguments, _)| arguments)
+        else {
+            return vec![];
+        };
+        let mut signatures = vec![];
+        let mut start = 0;
+        let bytes = arguments.as_bytes();
+        while start < bytes.len() {
+            let mut end = start;
+            while end + 1 < bytes.len() && bytes[end] == b'[' {
+                end += 1;
+            }
+            if bytes[end] == b'L' {
+                end += arguments[end..]
+                    .find(';')
+                    .unwrap_or(arguments.len() - end - 1);
+            }
+            signatures.push(&arguments[start..=end]);
+            start = end + 1;
+        }
+        signatures
+    }
+
+    /// Gets the JNI signature of this method's return type, such as `V` for `void`
+    pub fn return_type_signature(&self) -> &str {
+        self.signature
+            .split_once(')')
+            .map_or("V", |(_, return_type)| return_type)
+    }
+
+    /// Gets the names of the types of this method's arguments, such as `["int",
+    /// "java.lang.String"]` for `(ILjava/lang/String;)V`
+    pub fn argument_type_names(&self) -> Vec<String> {
+        self.argument_type_signatures()
+            .into_iter()
+            .map(signature_name)
+            .collect()
+    }
+
+    /// Gets the name of this method's return type, such as `void`
+    pub fn return_type_name(&self) -> String {
+        signature_name(self.return_type_signature())
+    }
+
+    /// Gets the location of the first executable instruction in this method, if line number
+    /// information is available
+    pub async fn location(&self) -> io::Result<Option<Location<VM>>> {
+        let vm = upgrade(&self.vm)?;
+        let line_table = vm
+            .client()
+            .send(LineTable {
+                ref_type: self.declaring_type,
+                method_id: self.id,
+            })
+            .await?;
+        if line_table.start < 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.location_at(line_table.start as u64)))
+    }
+
+    /// Gets the location of the first code index of every line in this method
+    pub async fn all_line_locations(&self) -> io::Result<Vec<Location<VM>>> {
+        let vm = upgrade(&self.vm)?;
+        let line_table = vm
+            .client()
+            .send(LineTable {
+                ref_type: self.declaring_type,
+                method_id: self.id,
+            })
+            .await?;
+        Ok(line_table
+            .lines
+            .iter()
+            .map(|line| self.location_at(line.line_code_index as u64))
+            .collect())
+    }
+
+    /// Gets the locations in this method that begin the given line
+    pub async fn locations_of_line(&self, line_number: Int) -> io::Result<Vec<Location<VM>>> {
+        let vm = upgrade(&self.vm)?;
+        let line_table = vm
+            .client()
+            .send(LineTable {
+                ref_type: self.declaring_type,
+                method_id: self.id,
+            })
+            .await?;
+        Ok(line_table
+            .lines
+            .iter()
+            .filter(|line| line.line_number == line_number)
+            .map(|line| self.location_at(line.line_code_index as u64))
+            .collect())
+    }
+
+    /// Gets the local variables of this method, including its arguments. Fails with
+    /// [AbsentInformation](jdwp_types::ErrorConstant::AbsentInformation) if the class was compiled
+    /// without them.
+    pub async fn variables(&self) -> io::Result<Vec<LocalVariable<VM>>> {
+        let vm = upgrade(&self.vm)?;
+        let table = vm
+            .client()
+            .send(VariableTable {
+                ref_type: self.declaring_type,
+                method_id: self.id,
+            })
+            .await?;
+        Ok(table
+            .slots
+            .into_iter()
+            .map(|variable| {
+                LocalVariable::new(
+                    self.declaring_type,
+                    self.id,
+                    variable.name,
+                    variable.signature,
+                    variable.code_index as u64,
+                    variable.length as u64,
+                    variable.slot,
+                    variable.slot < table.arg_cnt,
+                    &self.vm,
+                )
+            })
+            .collect())
+    }
+
+    /// Creates a location within this method at a given code index
+    pub fn location_at(&self, code_index: u64) -> Location<VM> {
+        Location::new(
+            jdwp_types::Location {
+                tag: self.type_tag,
+                class: ClassId::new(self.declaring_type.get()),
+                method: self.id,
+                offset: code_index,
+            },
+            &self.vm,
+        )
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for Method<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            type_tag: self.type_tag,
+            declaring_type: self.declaring_type,
+            id: self.id,
+            name: self.name.clone(),
+            signature: self.signature.clone(),
+            modifiers: self.modifiers,
+            vm: self.vm.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> PartialEq for Method<VM> {
+    fn eq(&self, other: &Self) -> bool {
+        self.declaring_type == other.declaring_type && self.id == other.id
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Eq for Method<VM> {}
+
+impl<VM: VirtualMachine + ?Sized> Hash for Method<VM> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.declaring_type.hash(state);
+        self.id.hash(state);
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Mirror<VM> for Method<VM> {
+    fn virtual_machine(&self) -> Weak<VM> {
+        self.vm.clone()
+    }
+}
@@ -0,0 +1,327 @@
+use crate::core::private::upgrade;
+use crate::{Field, Method, Mirror, ReferenceType, ThreadReference, Value, VirtualMachine};
+use jdwp_client::codec::UntaggedValue;
+use jdwp_client::commands::array_reference::{
+    GetValues as GetArrayValues, Length, SetValues as SetArrayValues,
+};
+use jdwp_client::commands::object_reference::{
+    FieldValue, GetValues, InvokeMethod, MonitorInfo as GetMonitorInfo,
+    ReferenceType as GetReferenceType, SetValues,
+};
+use jdwp_client::commands::string_reference::Value as StringValue;
+use jdwp_types::{ArrayId, ClassId, Int, ObjectId, Tag, TaggedObjectId, TypeTag};
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::sync::Weak;
+use thiserror::Error;
+
+/// A method invoked in the target VM didn't return normally
+#[derive(Debug, Error)]
+pub enum InvokeError<VM: VirtualMachine + ?Sized> {
+    /// The method threw an exception
+    #[error("the invoked method threw an exception (id={})", .0.id().get())]
+    Exception(ObjectReference<VM>),
+    /// Communicating with the target VM failed, or the invocation was rejected, such as because
+    /// the thread wasn't suspended by an event
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl<VM: VirtualMachine + ?Sized> InvokeError<VM> {
+    /// Converts the reply to an invocation into its return value, or the exception it threw
+    pub(crate) fn from_reply(
+        return_value: jdwp_types::Value,
+        exception: TaggedObjectId,
+        vm: &Weak<VM>,
+    ) -> Result<Value<VM>, Self> {
+        if exception.id().get() != 0 {
+            return Err(InvokeError::Exception(ObjectReference::from_tagged(
+                exception, vm,
+            )));
+        }
+        Ok(Value::new(return_value, vm))
+    }
+}
+
+/// An object that currently exists in the target VM
+#[derive(Debug)]
+pub struct ObjectReference<VM: VirtualMachine + ?Sized> {
+    id: ObjectId,
+    tag: Tag,
+    vm: Weak<VM>,
+}
+
+impl<VM: VirtualMachine + ?Sized> ObjectReference<VM> {
+    /// Creates a new object reference
+    pub fn new(id: ObjectId, vm: &Weak<VM>) -> Self {
+        Self {
+            id,
+            tag: Tag::Object,
+            vm: vm.clone(),
+        }
+    }
+
+    /// Creates a new object reference from a tagged object id, keeping track of what kind of
+    /// object it refers to
+    pub fn from_tagged(tagged: TaggedObjectId, vm: &Weak<VM>) -> Self {
+        Self {
+            id: ObjectId::new(tagged.id().get()),
+            tag: tagged.tag(),
+            vm: vm.clone(),
+        }
+    }
+
+    /// Gets the id of this object
+    pub fn id(&self) -> ObjectId {
+        self.id
+    }
+
+    /// Gets the tag describing what kind of object this is, such as a string, thread or array.
+    ///
+    /// Objects created from an untagged id are always tagged as [Tag::Object].
+    pub fn tag(&self) -> Tag {
+        self.tag
+    }
+
+    /// Gets the runtime type of this object
+    pub async fn reference_type(&self) -> io::Result<ReferenceType<VM>> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm
+            .client()
+            .send(GetReferenceType { object: self.id })
+            .await?;
+        ReferenceType::from_id(reply.ref_type_tag, reply.type_id, &self.vm).await
+    }
+
+    /// Gets the value of a field of this object. Static fields should be read from their
+    /// [ReferenceType] instead.
+    pub async fn get_value(&self, field: &Field<VM>) -> io::Result<Value<VM>> {
+        let mut values = self.get_values(std::slice::from_ref(field)).await?;
+        Ok(values.remove(0))
+    }
+
+    /// Gets the values of fields of this object, in the same order as the fields
+    pub async fn get_values(&self, fields: &[Field<VM>]) -> io::Result<Vec<Value<VM>>> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm
+            .client()
+            .send(GetValues {
+                object: self.id,
+                fields: fields.iter().map(Field::id).collect(),
+            })
+            .await?;
+        Ok(reply
+            .values
+            .into_iter()
+            .map(|value| Value::new(value, &self.vm))
+            .collect())
+    }
+
+    /// Sets the value of a field of this object. The value must already have the field's exact
+    /// type if it's a primitive, or a type assignable to it otherwise. Static fields should be set
+    /// through their [ReferenceType] instead.
+    pub async fn set_value(&self, field: &Field<VM>, value: &Value<VM>) -> io::Result<()> {
+        let vm = upgrade(&self.vm)?;
+        vm.client()
+            .send(SetValues {
+                object: self.id,
+                values: vec![FieldValue {
+                    field: field.id(),
+                    value: UntaggedValue(value.raw()),
+                }],
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Invokes an instance method on this object in a thread, which must have been suspended by an
+    /// event. The method is looked up in this object's runtime type unless
+    /// [INVOKE_NONVIRTUAL](jdwp_client::commands::class_type::INVOKE_NONVIRTUAL) is set in the
+    /// options.
+    ///
+    /// Every thread is resumed while the method runs, unless
+    /// [INVOKE_SINGLE_THREADED](jdwp_client::commands::class_type::INVOKE_SINGLE_THREADED) is set,
+    /// and the thread's frames must be fetched again afterwards.
+    pub async fn invoke_method(
+        &self,
+        thread: &ThreadReference<VM>,
+        method: &Method<VM>,
+        arguments: &[Value<VM>],
+        options: Int,
+    ) -> Result<Value<VM>, InvokeError<VM>> {
+        let vm = upgrade(&self.vm)?;
+        // interface methods have to be invoked through the class of the object implementing them
+        let clazz = if method.type_tag() == TypeTag::Class {
+            ClassId::new(method.declaring_type_id().get())
+        } else {
+            ClassId::new(self.reference_type().await?.id().get())
+        };
+        let reply = vm
+            .client()
+            .send(InvokeMethod {
+                object: self.id,
+                thread: thread.id(),
+                clazz,
+                method_id: method.id(),
+                arguments: arguments.iter().map(Value::raw).collect(),
+                options,
+            })
+            .await?;
+        InvokeError::from_reply(reply.return_value, reply.exception, &self.vm)
+    }
+
+    /// Gets the state of this object's monitor, which needs every thread to be suspended for it to
+    /// be consistent
+    pub async fn monitor_info(&self) -> io::Result<MonitorInfo<VM>> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm.client().send(GetMonitorInfo { object: self.id }).await?;
+        Ok(MonitorInfo {
+            owner: (reply.owner.get() != 0).then(|| ThreadReference::new(reply.owner, &self.vm)),
+            entry_count: reply.entry_count,
+            waiters: reply
+                .waiters
+                .into_iter()
+                .map(|waiter| ThreadReference::new(waiter, &self.vm))
+                .collect(),
+        })
+    }
+
+    /// Gets the number of components of this object, which must be an array
+    pub async fn array_length(&self) -> io::Result<Int> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm
+            .client()
+            .send(Length {
+                array_object: ArrayId::new(self.id.get()),
+            })
+            .await?;
+        Ok(reply.array_length)
+    }
+
+    /// Gets a range of components of this object, which must be an array
+    pub async fn array_values(&self, first_index: Int, length: Int) -> io::Result<Vec<Value<VM>>> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm
+            .client()
+            .send(GetArrayValues {
+                array_object: ArrayId::new(self.id.get()),
+                first_index,
+                length,
+            })
+            .await?;
+        Ok(reply
+            .values
+            .values
+            .into_iter()
+            .map(|value| Value::new(value, &self.vm))
+            .collect())
+    }
+
+    /// Sets a range of components of this object, which must be an array, starting at the first
+    /// index. The values must already have the component type if it's a primitive.
+    pub async fn set_array_values(&self, first_index: Int, values: &[Value<VM>]) -> io::Result<()> {
+        let vm = upgrade(&self.vm)?;
+        vm.client()
+            .send(SetArrayValues {
+                array_object: ArrayId::new(self.id.get()),
+                first_index,
+                values: values
+                    .iter()
+                    .map(|value| UntaggedValue(value.raw()))
+                    .collect(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Gets the message of this object, which must be a `java.lang.Throwable`, from its
+    /// `detailMessage` field so that nothing has to be invoked. Overrides of `getMessage` are
+    /// ignored.
+    pub(crate) async fn detail_message(&self) -> io::Result<Option<String>> {
+        let ref_type = self.reference_type().await?;
+        let Some(field) = ref_type.visible_field_by_name("detailMessage").await? else {
+            return Ok(None);
+        };
+        match self.get_value(&field).await? {
+            Value::Object(Some(message)) => Ok(Some(message.string_value().await?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Gets the characters of this object, which must be a `java.lang.String`
+    pub async fn string_value(&self) -> io::Result<String> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm
+            .client()
+            .send(StringValue {
+                string_object: self.id,
+            })
+            .await?;
+        Ok(reply.string_value)
+    }
+}
+
+/// The state of an object's monitor
+#[derive(Debug)]
+pub struct MonitorInfo<VM: VirtualMachine + ?Sized> {
+    owner: Option<ThreadReference<VM>>,
+    entry_count: Int,
+    waiters: Vec<ThreadReference<VM>>,
+}
+
+impl<VM: VirtualMachine + ?Sized> MonitorInfo<VM> {
+    /// Gets the thread owning the monitor, if any
+    pub fn owner(&self) -> Option<&ThreadReference<VM>> {
+        self.owner.as_ref()
+    }
+
+    /// Gets how many times the owner has entered the monitor
+    pub fn entry_count(&self) -> Int {
+        self.entry_count
+    }
+
+    /// Gets the threads waiting on the monitor in `Object.wait`
+    pub fn waiters(&self) -> &[ThreadReference<VM>] {
+        &self.waiters
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for MonitorInfo<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            owner: self.owner.clone(),
+            entry_count: self.entry_count,
+            waiters: self.waiters.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for ObjectReference<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            tag: self.tag,
+            vm: self.vm.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> PartialEq for ObjectReference<VM> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Eq for ObjectReference<VM> {}
+
+impl<VM: VirtualMachine + ?Sized> Hash for ObjectReference<VM> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Mirror<VM> for ObjectReference<VM> {
+    fn virtual_machine(&self) -> Weak<VM> {
+        self.vm.clone()
+    }
+}
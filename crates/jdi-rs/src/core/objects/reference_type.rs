@@ -1,7 +1,43 @@
-use crate::{Mirror, VirtualMachine};
-use jdwp_types::{ClassStatus, ReferenceTypeId, TypeTag};
+use crate::core::private::upgrade;
+use crate::{Field, InvokeError, Location, Method, Mirror, ThreadReference, Value, VirtualMachine};
+use jdwp_client::codec::UntaggedValue;
+use jdwp_client::commands::class_type::{InvokeMethod, SetValues, Superclass};
+use jdwp_client::commands::interface_type::InvokeMethod as InvokeInterfaceMethod;
+use jdwp_client::commands::object_reference::FieldValue;
+use jdwp_client::commands::reference_type::{
+    Fields, GetValues, Interfaces, Methods, Signature, SourceFile, Status,
+};
+use jdwp_client::commands::ClassesBySignatures;
+use jdwp_client::packet::ReplyError;
+use jdwp_types::signature::JavaType;
+use jdwp_types::{ClassId, ClassStatus, ErrorConstant, Int, InterfaceId, ReferenceTypeId, TypeTag};
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::io;
 use std::sync::Weak;
 
+const BRIDGE: Int = 0x0040;
+const NATIVE: Int = 0x0100;
+const ABSTRACT: Int = 0x0400;
+const SYNTHETIC: Int = 0x1000;
+
+/// Whether a request failed because the class file doesn't contain the debug information needed
+pub(crate) fn is_absent_information(error: &io::Error) -> bool {
+    ReplyError::from_io_error(error).and_then(ReplyError::error_constant)
+        == Some(ErrorConstant::AbsentInformation)
+}
+
+/// Gets the name of a type from its JNI signature, such as `int[]` for `[I` or `java.lang.String`
+/// for `Ljava/lang/String;`
+pub(crate) fn signature_name(signature: &str) -> String {
+    match signature.parse::<JavaType>() {
+        Ok(java_type) => java_type.source_name(),
+        Err(_) if signature == "V" => "void".to_string(),
+        Err(_) => signature.to_string(),
+    }
+}
+
+/// The type of an object in a target VM
 #[derive(Debug)]
 pub struct ReferenceType<VM: VirtualMachine + ?Sized> {
     type_tag: TypeTag,
@@ -29,15 +65,383 @@ impl<VM: VirtualMachine + ?Sized> ReferenceType<VM> {
         }
     }
 
+    /// Creates a reference type from its id, getting its signature and status from the target VM
+    pub(crate) async fn from_id(
+        type_tag: TypeTag,
+        id: ReferenceTypeId,
+        vm: &Weak<VM>,
+    ) -> io::Result<Self> {
+        let client = upgrade(vm)?;
+        let signature = client
+            .client()
+            .send(Signature { ref_type: id })
+            .await?
+            .signature;
+        let status = client.client().send(Status { ref_type: id }).await?.status;
+        Ok(Self::new(type_tag, id, signature, status, vm))
+    }
+
+    /// Gets the loaded types with a given JNI signature, such as `Ljava/lang/String;`. There can be
+    /// more than one if several class loaders loaded the same class.
+    pub(crate) async fn by_signature(signature: &str, vm: &Weak<VM>) -> io::Result<Vec<Self>> {
+        let client = upgrade(vm)?;
+        let reply = client
+            .client()
+            .send(ClassesBySignatures {
+                signature: signature.to_string(),
+            })
+            .await?;
+        Ok(reply
+            .classes
+            .into_iter()
+            .map(|class| {
+                Self::new(
+                    class.type_tag,
+                    class.id,
+                    signature.to_string(),
+                    class.status,
+                    vm,
+                )
+            })
+            .collect())
+    }
+
+    /// Gets the id of the reference type
+    pub fn id(&self) -> ReferenceTypeId {
+        self.id
+    }
+
+    /// Gets whether this reference type is a class, an interface or an array
+    pub fn type_tag(&self) -> TypeTag {
+        self.type_tag
+    }
+
     /// Gets the signature of the reference type
     pub fn signature(&self) -> &str {
         &self.signature
     }
 
+    /// Gets the fully qualified name of this type, such as `java.lang.String`
+    pub fn name(&self) -> String {
+        signature_name(&self.signature)
+    }
+
     /// Gets the class status of the signature
     pub fn status(&self) -> ClassStatus {
         self.status
     }
+
+    /// Gets the name of the source file this type was declared in, such as `Foo.java`, if the
+    /// class file recorded it
+    pub async fn source_name(&self) -> io::Result<Option<String>> {
+        let vm = upgrade(&self.vm)?;
+        match vm.client().send(SourceFile { ref_type: self.id }).await {
+            Ok(reply) => Ok(Some(reply.source_file)),
+            Err(e) if is_absent_information(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Gets the locations that begin the given line in every method declared directly in this
+    /// type, in the order the methods appear in the class file. Methods without line number
+    /// information are skipped.
+    pub async fn locations_of_line(&self, line_number: Int) -> io::Result<Vec<Location<VM>>> {
+        let mut locations = vec![];
+        for method in self.methods().await? {
+            if method.modifiers() & (ABSTRACT | NATIVE) != 0 {
+                continue;
+            }
+            match method.locations_of_line(line_number).await {
+                Ok(found) => locations.extend(found),
+                Err(e) if is_absent_information(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(locations)
+    }
+
+    /// Gets the methods declared directly in this type, in the order they appear in the class file
+    pub async fn methods(&self) -> io::Result<Vec<Method<VM>>> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm.client().send(Methods { ref_type: self.id }).await?;
+        Ok(reply
+            .declared
+            .into_iter()
+            .map(|info| {
+                Method::new(
+                    self.type_tag,
+                    self.id,
+                    info.method_id,
+                    info.name,
+                    info.signature,
+                    info.mod_bits,
+                    &self.vm,
+                )
+            })
+            .collect())
+    }
+
+    /// Gets the methods declared directly in this type with a given name
+    pub async fn methods_by_name(&self, name: &str) -> io::Result<Vec<Method<VM>>> {
+        Ok(self
+            .methods()
+            .await?
+            .into_iter()
+            .filter(|method| method.name() == name)
+            .collect())
+    }
+
+    /// Gets the fields declared directly in this type, in the order they appear in the class file
+    pub async fn fields(&self) -> io::Result<Vec<Field<VM>>> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm.client().send(Fields { ref_type: self.id }).await?;
+        Ok(reply
+            .declared
+            .into_iter()
+            .map(|info| {
+                Field::new(
+                    self.id,
+                    info.field_id,
+                    info.name,
+                    info.signature,
+                    info.mod_bits,
+                    &self.vm,
+                )
+            })
+            .collect())
+    }
+
+    /// Gets a field declared directly in this type by its name
+    pub async fn field_by_name(&self, name: &str) -> io::Result<Option<Field<VM>>> {
+        Ok(self
+            .fields()
+            .await?
+            .into_iter()
+            .find(|field| field.name() == name))
+    }
+
+    /// Gets the direct superclass of this class, or `None` for interfaces, arrays and
+    /// `java.lang.Object`
+    pub async fn superclass(&self) -> io::Result<Option<ReferenceType<VM>>> {
+        if self.type_tag != TypeTag::Class {
+            return Ok(None);
+        }
+        let vm = upgrade(&self.vm)?;
+        let superclass = vm
+            .client()
+            .send(Superclass {
+                clazz: ClassId::new(self.id.get()),
+            })
+            .await?
+            .superclass;
+        if superclass.get() == 0 {
+            return Ok(None);
+        }
+        Self::from_id(
+            TypeTag::Class,
+            ReferenceTypeId::new(superclass.get()),
+            &self.vm,
+        )
+        .await
+        .map(Some)
+    }
+
+    /// Gets the interfaces directly implemented by this class, or directly extended by this
+    /// interface
+    pub async fn interfaces(&self) -> io::Result<Vec<ReferenceType<VM>>> {
+        if self.type_tag == TypeTag::Array {
+            return Ok(vec![]);
+        }
+        let vm = upgrade(&self.vm)?;
+        let reply = vm.client().send(Interfaces { ref_type: self.id }).await?;
+        let mut interfaces = Vec::with_capacity(reply.interfaces.len());
+        for interface in reply.interfaces {
+            interfaces.push(
+                Self::from_id(
+                    TypeTag::Interface,
+                    ReferenceTypeId::new(interface.get()),
+                    &self.vm,
+                )
+                .await?,
+            );
+        }
+        Ok(interfaces)
+    }
+
+    /// Gets this type, followed by every one of its superclasses and the interfaces they
+    /// implement, nearest first. Each type is only included once.
+    pub async fn all_supertypes(&self) -> io::Result<Vec<ReferenceType<VM>>> {
+        let mut supertypes = vec![self.clone()];
+        let mut seen = HashSet::from([self.id]);
+        let mut next = 0;
+        while next < supertypes.len() {
+            let current = supertypes[next].clone();
+            next += 1;
+            for supertype in current
+                .superclass()
+                .await?
+                .into_iter()
+                .chain(current.interfaces().await?)
+            {
+                if seen.insert(supertype.id) {
+                    supertypes.push(supertype);
+                }
+            }
+        }
+        Ok(supertypes)
+    }
+
+    /// Gets the methods that can be invoked on this type, which are declared in it or inherited
+    /// from one of its supertypes. Overridden methods, constructors, static initializers and
+    /// compiler generated bridge methods are left out.
+    pub async fn visible_methods(&self) -> io::Result<Vec<Method<VM>>> {
+        let mut methods: Vec<Method<VM>> = vec![];
+        for supertype in self.all_supertypes().await? {
+            for method in supertype.methods().await? {
+                if method.name().starts_with('<')
+                    || method.modifiers() & (BRIDGE | SYNTHETIC) != 0
+                    || methods.iter().any(|visible| {
+                        visible.name() == method.name() && visible.signature() == method.signature()
+                    })
+                {
+                    continue;
+                }
+                methods.push(method);
+            }
+        }
+        Ok(methods)
+    }
+
+    /// Invokes a static method declared in this type or one of its supertypes in a thread, which
+    /// must have been suspended by an event.
+    ///
+    /// Every thread is resumed while the method runs, unless
+    /// [INVOKE_SINGLE_THREADED](jdwp_client::commands::class_type::INVOKE_SINGLE_THREADED) is set,
+    /// and the thread's frames must be fetched again afterwards.
+    pub async fn invoke_method(
+        &self,
+        thread: &ThreadReference<VM>,
+        method: &Method<VM>,
+        arguments: &[Value<VM>],
+        options: Int,
+    ) -> Result<Value<VM>, InvokeError<VM>> {
+        let vm = upgrade(&self.vm)?;
+        let arguments = arguments.iter().map(Value::raw).collect();
+        let (return_value, exception) = if self.type_tag == TypeTag::Interface {
+            let reply = vm
+                .client()
+                .send(InvokeInterfaceMethod {
+                    clazz: InterfaceId::new(self.id.get()),
+                    thread: thread.id(),
+                    method_id: method.id(),
+                    arguments,
+                    options,
+                })
+                .await?;
+            (reply.return_value, reply.exception)
+        } else {
+            let reply = vm
+                .client()
+                .send(InvokeMethod {
+                    clazz: ClassId::new(self.id.get()),
+                    thread: thread.id(),
+                    method_id: method.id(),
+                    arguments,
+                    options,
+                })
+                .await?;
+            (reply.return_value, reply.exception)
+        };
+        InvokeError::from_reply(return_value, exception, &self.vm)
+    }
+
+    /// Gets the fields declared in this type and every one of its superclasses, starting with
+    /// the ones declared in this type
+    pub async fn all_fields(&self) -> io::Result<Vec<Field<VM>>> {
+        let mut fields = self.fields().await?;
+        let mut superclass = self.superclass().await?;
+        while let Some(class) = superclass {
+            fields.extend(class.fields().await?);
+            superclass = class.superclass().await?;
+        }
+        Ok(fields)
+    }
+
+    /// Gets the field with a given name visible in this type, which is either declared in it or
+    /// inherited from one of its superclasses
+    pub async fn visible_field_by_name(&self, name: &str) -> io::Result<Option<Field<VM>>> {
+        Ok(self
+            .all_fields()
+            .await?
+            .into_iter()
+            .find(|field| field.name() == name))
+    }
+
+    /// Gets the value of a static field declared in this type or one of its supertypes
+    pub async fn get_value(&self, field: &Field<VM>) -> io::Result<Value<VM>> {
+        let mut values = self.get_values(std::slice::from_ref(field)).await?;
+        Ok(values.remove(0))
+    }
+
+    /// Gets the values of static fields declared in this type or its supertypes, in the same
+    /// order as the fields
+    pub async fn get_values(&self, fields: &[Field<VM>]) -> io::Result<Vec<Value<VM>>> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm
+            .client()
+            .send(GetValues {
+                ref_type: self.id,
+                fields: fields.iter().map(Field::id).collect(),
+            })
+            .await?;
+        Ok(reply
+            .values
+            .into_iter()
+            .map(|value| Value::new(value, &self.vm))
+            .collect())
+    }
+    /// Sets the value of a static field of this type, which must be a class, or of one of its
+    /// supertypes. The value must already have the field's exact type if it's a primitive, or a
+    /// type assignable to it otherwise.
+    pub async fn set_value(&self, field: &Field<VM>, value: &Value<VM>) -> io::Result<()> {
+        let vm = upgrade(&self.vm)?;
+        vm.client()
+            .send(SetValues {
+                clazz: ClassId::new(self.id.get()),
+                values: vec![FieldValue {
+                    field: field.id(),
+                    value: UntaggedValue(value.raw()),
+                }],
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for ReferenceType<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            type_tag: self.type_tag,
+            id: self.id,
+            signature: self.signature.clone(),
+            status: self.status,
+            vm: self.vm.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> PartialEq for ReferenceType<VM> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Eq for ReferenceType<VM> {}
+
+impl<VM: VirtualMachine + ?Sized> Hash for ReferenceType<VM> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
 }
 
 impl<Vm: VirtualMachine + ?Sized> Mirror<Vm> for ReferenceType<Vm> {
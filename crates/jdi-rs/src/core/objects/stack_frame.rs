@@ -0,0 +1,191 @@
+use crate::core::private::upgrade;
+use crate::expr::{EvalError, Expression};
+use crate::{
+    LocalVariable, Location, Mirror, ObjectReference, ThreadReference, Value, VirtualMachine,
+};
+use jdwp_client::commands::stack_frame::{
+    GetValues, SetValues, SlotRequest, SlotValue, ThisObject,
+};
+use jdwp_types::FrameId;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::sync::Weak;
+
+/// The state of a method invocation on a thread's call stack.
+///
+/// Frames are only valid while their thread stays suspended.
+#[derive(Debug)]
+pub struct StackFrame<VM: VirtualMachine + ?Sized> {
+    thread: ThreadReference<VM>,
+    id: FrameId,
+    location: Location<VM>,
+    vm: Weak<VM>,
+}
+
+impl<VM: VirtualMachine + ?Sized> StackFrame<VM> {
+    /// Creates a new stack frame
+    pub fn new(
+        thread: ThreadReference<VM>,
+        id: FrameId,
+        location: Location<VM>,
+        vm: &Weak<VM>,
+    ) -> Self {
+        Self {
+            thread,
+            id,
+            location,
+            vm: vm.clone(),
+        }
+    }
+
+    /// Gets the id of this frame
+    pub fn id(&self) -> FrameId {
+        self.id
+    }
+
+    /// Gets the thread this frame is on
+    pub fn thread(&self) -> &ThreadReference<VM> {
+        &self.thread
+    }
+
+    /// Gets the location currently executing in this frame
+    pub fn location(&self) -> &Location<VM> {
+        &self.location
+    }
+
+    /// Gets the object executing this frame's method, or `None` if the method is static or
+    /// native
+    pub async fn this_object(&self) -> io::Result<Option<ObjectReference<VM>>> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm
+            .client()
+            .send(ThisObject {
+                thread: self.thread.id(),
+                frame: self.id,
+            })
+            .await?;
+        if reply.object_this.id().get() == 0 {
+            return Ok(None);
+        }
+        Ok(Some(ObjectReference::from_tagged(
+            reply.object_this,
+            &self.vm,
+        )))
+    }
+
+    /// Gets the local variables, including arguments, in scope at this frame's location. Fails
+    /// with [AbsentInformation](jdwp_types::ErrorConstant::AbsentInformation) if the class was
+    /// compiled without them.
+    pub async fn visible_variables(&self) -> io::Result<Vec<LocalVariable<VM>>> {
+        let variables = self.location.method().await?.variables().await?;
+        Ok(variables
+            .into_iter()
+            .filter(|variable| variable.is_visible(&self.location))
+            .collect())
+    }
+
+    /// Gets the local variable in scope at this frame's location with a given name
+    pub async fn visible_variable_by_name(
+        &self,
+        name: &str,
+    ) -> io::Result<Option<LocalVariable<VM>>> {
+        Ok(self
+            .visible_variables()
+            .await?
+            .into_iter()
+            .find(|variable| variable.name() == name))
+    }
+
+    /// Gets the value of a local variable in this frame
+    pub async fn get_value(&self, variable: &LocalVariable<VM>) -> io::Result<Value<VM>> {
+        let mut values = self.get_values(std::slice::from_ref(variable)).await?;
+        Ok(values.remove(0))
+    }
+
+    /// Sets the value of a local variable in this frame. The value must already have the
+    /// variable's exact type if it's a primitive, or a type assignable to it otherwise.
+    pub async fn set_value(
+        &self,
+        variable: &LocalVariable<VM>,
+        value: &Value<VM>,
+    ) -> io::Result<()> {
+        let vm = upgrade(&self.vm)?;
+        vm.client()
+            .send(SetValues {
+                thread: self.thread.id(),
+                frame: self.id,
+                slot_values: vec![SlotValue {
+                    slot: variable.slot(),
+                    value: value.raw(),
+                }],
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Evaluates a Java expression in this frame, such as `order.getItems().size() > 3`. See
+    /// [expr](crate::expr) for what expressions can contain.
+    ///
+    /// Methods the expression invokes run in this frame's thread while the others stay
+    /// suspended, which deadlocks if they need a lock another thread holds. Invoking a method
+    /// resumes the thread, so this frame must be fetched from its thread again afterwards.
+    pub async fn evaluate(&self, expression: &str) -> Result<Value<VM>, EvalError> {
+        Expression::parse(expression)?.evaluate(self).await
+    }
+
+    /// Gets the values of local variables in this frame, in the same order as the variables
+    pub async fn get_values(&self, variables: &[LocalVariable<VM>]) -> io::Result<Vec<Value<VM>>> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm
+            .client()
+            .send(GetValues {
+                thread: self.thread.id(),
+                frame: self.id,
+                slots: variables
+                    .iter()
+                    .map(|variable| SlotRequest {
+                        slot: variable.slot(),
+                        sig_byte: variable.tag(),
+                    })
+                    .collect(),
+            })
+            .await?;
+        Ok(reply
+            .values
+            .into_iter()
+            .map(|value| Value::new(value, &self.vm))
+            .collect())
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for StackFrame<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            thread: self.thread.clone(),
+            id: self.id,
+            location: self.location.clone(),
+            vm: self.vm.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> PartialEq for StackFrame<VM> {
+    fn eq(&self, other: &Self) -> bool {
+        self.thread == other.thread && self.id == other.id
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Eq for StackFrame<VM> {}
+
+impl<VM: VirtualMachine + ?Sized> Hash for StackFrame<VM> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.thread.hash(state);
+        self.id.hash(state);
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Mirror<VM> for StackFrame<VM> {
+    fn virtual_machine(&self) -> Weak<VM> {
+        self.vm.clone()
+    }
+}
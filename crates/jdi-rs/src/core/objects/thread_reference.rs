@@ -0,0 +1,210 @@
+use crate::core::private::upgrade;
+use crate::{Location, Mirror, ObjectReference, StackFrame, VirtualMachine};
+use jdwp_client::commands::thread_reference::{
+    CurrentContendedMonitor, FrameCount, Frames, Name, OwnedMonitorsStackDepthInfo, Resume, Status,
+    Suspend, SuspendCount, SUSPEND_STATUS_SUSPENDED,
+};
+use jdwp_types::{Int, ThreadId, ThreadStatus};
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::sync::Weak;
+
+/// A thread object from the target VM
+#[derive(Debug)]
+pub struct ThreadReference<VM: VirtualMachine + ?Sized> {
+    id: ThreadId,
+    vm: Weak<VM>,
+}
+
+impl<VM: VirtualMachine + ?Sized> ThreadReference<VM> {
+    /// Creates a new thread reference
+    pub fn new(id: ThreadId, vm: &Weak<VM>) -> Self {
+        Self { id, vm: vm.clone() }
+    }
+
+    /// Gets the id of this thread
+    pub fn id(&self) -> ThreadId {
+        self.id
+    }
+
+    /// Gets the name of this thread
+    pub async fn name(&self) -> io::Result<String> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm.client().send(Name { thread: self.id }).await?;
+        Ok(reply.thread_name)
+    }
+
+    /// Suspends this thread. Suspensions are counted, so the thread must be resumed as many times
+    /// as it has been suspended before it runs again.
+    pub async fn suspend(&self) -> io::Result<()> {
+        let vm = upgrade(&self.vm)?;
+        vm.client().send(Suspend { thread: self.id }).await?;
+        Ok(())
+    }
+
+    /// Resumes this thread, decrementing its suspend count
+    pub async fn resume(&self) -> io::Result<()> {
+        let vm = upgrade(&self.vm)?;
+        vm.client().send(Resume { thread: self.id }).await?;
+        Ok(())
+    }
+
+    /// Gets the execution status of this thread
+    pub async fn status(&self) -> io::Result<ThreadStatus> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm.client().send(Status { thread: self.id }).await?;
+        Ok(reply.thread_status)
+    }
+
+    /// Whether this thread is suspended, by an event or a suspend command
+    pub async fn is_suspended(&self) -> io::Result<bool> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm.client().send(Status { thread: self.id }).await?;
+        Ok(reply.suspend_status & SUSPEND_STATUS_SUSPENDED != 0)
+    }
+
+    /// Gets how many times this thread has been suspended without being resumed
+    pub async fn suspend_count(&self) -> io::Result<Int> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm.client().send(SuspendCount { thread: self.id }).await?;
+        Ok(reply.suspend_count)
+    }
+
+    /// Gets the objects whose monitors this thread owns, along with the frame each was entered
+    /// in. The thread must be suspended.
+    pub async fn owned_monitors_and_frames(&self) -> io::Result<Vec<OwnedMonitor<VM>>> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm
+            .client()
+            .send(OwnedMonitorsStackDepthInfo { thread: self.id })
+            .await?;
+        Ok(reply
+            .owned
+            .into_iter()
+            .map(|owned| OwnedMonitor {
+                monitor: ObjectReference::from_tagged(owned.monitor, &self.vm),
+                stack_depth: (owned.stack_depth >= 0).then_some(owned.stack_depth),
+            })
+            .collect())
+    }
+
+    /// Gets the object whose monitor this thread is waiting to enter, or to re-enter in
+    /// `Object.wait`, if any. The thread must be suspended.
+    pub async fn current_contended_monitor(&self) -> io::Result<Option<ObjectReference<VM>>> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm
+            .client()
+            .send(CurrentContendedMonitor { thread: self.id })
+            .await?;
+        if reply.monitor.id().get() == 0 {
+            return Ok(None);
+        }
+        Ok(Some(ObjectReference::from_tagged(reply.monitor, &self.vm)))
+    }
+
+    /// Gets the number of frames on this thread's call stack. The thread must be suspended.
+    pub async fn frame_count(&self) -> io::Result<Int> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm.client().send(FrameCount { thread: self.id }).await?;
+        Ok(reply.frame_count)
+    }
+
+    /// Gets every frame on this thread's call stack, starting from the currently executing one.
+    /// The thread must be suspended.
+    pub async fn frames(&self) -> io::Result<Vec<StackFrame<VM>>> {
+        self.frames_in(0, -1).await
+    }
+
+    /// Gets a frame on this thread's call stack, where the currently executing frame is `0`. The
+    /// thread must be suspended.
+    pub async fn frame(&self, index: Int) -> io::Result<StackFrame<VM>> {
+        self.frames_in(index, 1).await?.pop().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("thread has no frame {index}"),
+            )
+        })
+    }
+
+    async fn frames_in(&self, start_frame: Int, length: Int) -> io::Result<Vec<StackFrame<VM>>> {
+        let vm = upgrade(&self.vm)?;
+        let reply = vm
+            .client()
+            .send(Frames {
+                thread: self.id,
+                start_frame,
+                length,
+            })
+            .await?;
+        Ok(reply
+            .frames
+            .into_iter()
+            .map(|frame| {
+                StackFrame::new(
+                    self.clone(),
+                    frame.frame_id,
+                    Location::new(frame.location, &self.vm),
+                    &self.vm,
+                )
+            })
+            .collect())
+    }
+}
+
+/// A monitor owned by a thread
+#[derive(Debug)]
+pub struct OwnedMonitor<VM: VirtualMachine + ?Sized> {
+    monitor: ObjectReference<VM>,
+    stack_depth: Option<Int>,
+}
+
+impl<VM: VirtualMachine + ?Sized> OwnedMonitor<VM> {
+    /// Gets the object whose monitor is owned
+    pub fn monitor(&self) -> &ObjectReference<VM> {
+        &self.monitor
+    }
+
+    /// Gets the index of the frame the monitor was entered in, where the currently executing
+    /// frame is `0`, or `None` if it isn't known, such as when it was entered by JNI code
+    pub fn stack_depth(&self) -> Option<Int> {
+        self.stack_depth
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for OwnedMonitor<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            monitor: self.monitor.clone(),
+            stack_depth: self.stack_depth,
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for ThreadReference<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            vm: self.vm.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> PartialEq for ThreadReference<VM> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Eq for ThreadReference<VM> {}
+
+impl<VM: VirtualMachine + ?Sized> Hash for ThreadReference<VM> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Mirror<VM> for ThreadReference<VM> {
+    fn virtual_machine(&self) -> Weak<VM> {
+        self.vm.clone()
+    }
+}
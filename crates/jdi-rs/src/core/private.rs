@@ -3,9 +3,22 @@
 use crate::VirtualMachine;
 use jdwp_client::connect::JdwpTransport;
 use jdwp_client::JdwpClient;
+use std::io;
+use std::sync::{Arc, Weak};
 
 /// An extension of a virtual machine, only accessible within this crate
 pub trait VirtualMachineExt {
     /// Get access to the underlying transport used by this
     fn client(&self) -> &JdwpClient<impl JdwpTransport>;
 }
+
+/// Upgrades a weak reference to a virtual machine held by a mirror, failing if the virtual machine
+/// has since been dropped
+pub(crate) fn upgrade<VM: VirtualMachine + ?Sized>(vm: &Weak<VM>) -> io::Result<Arc<VM>> {
+    vm.upgrade().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotConnected,
+            "the virtual machine is no longer available",
+        )
+    })
+}
@@ -0,0 +1,152 @@
+use crate::{ObjectReference, VirtualMachine};
+use jdwp_types::{
+    ArrayId, Byte, ClassLoaderId, ClassObjectId, Id, ObjectId, StringId, Tag, TaggedObjectId,
+    ThreadGroupId, ThreadId,
+};
+use std::sync::Weak;
+
+/// A value within the target VM, either a primitive or a reference to an object.
+#[derive(Debug)]
+pub enum Value<VM: VirtualMachine + ?Sized> {
+    Boolean(bool),
+    Byte(Byte),
+    Char(u16),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    /// The value returned by a method declared `void`
+    Void,
+    /// An object reference, or `None` for `null`
+    Object(Option<ObjectReference<VM>>),
+}
+
+impl<VM: VirtualMachine + ?Sized> Value<VM> {
+    /// Creates a value mirror from a raw jdwp value
+    pub fn new(value: jdwp_types::Value, vm: &Weak<VM>) -> Self {
+        use jdwp_types::Value as Raw;
+        let object = |tag: Tag, id: u64| {
+            if id == 0 {
+                Value::Object(None)
+            } else {
+                Value::Object(Some(ObjectReference::from_tagged(
+                    TaggedObjectId::new(tag, Id::new(id)),
+                    vm,
+                )))
+            }
+        };
+        match value {
+            Raw::Boolean(b) => Value::Boolean(b),
+            Raw::Byte(b) => Value::Byte(b),
+            Raw::Char(c) => Value::Char(c),
+            Raw::Short(s) => Value::Short(s),
+            Raw::Int(i) => Value::Int(i),
+            Raw::Long(l) => Value::Long(l),
+            Raw::Float(f) => Value::Float(f),
+            Raw::Double(d) => Value::Double(d),
+            Raw::Void => Value::Void,
+            Raw::Object(id) => object(Tag::Object, id.get()),
+            Raw::Array(id) => object(Tag::Array, id.get()),
+            Raw::String(id) => object(Tag::String, id.get()),
+            Raw::Thread(id) => object(Tag::Thread, id.get()),
+            Raw::ThreadGroup(id) => object(Tag::ThreadGroup, id.get()),
+            Raw::ClassLoader(id) => object(Tag::ClassLoader, id.get()),
+            Raw::ClassObject(id) => object(Tag::ClassObject, id.get()),
+        }
+    }
+
+    /// Converts this value back into a raw jdwp value, such as to send it as an argument
+    pub fn raw(&self) -> jdwp_types::Value {
+        use jdwp_types::Value as Raw;
+        match self {
+            Value::Boolean(b) => Raw::Boolean(*b),
+            Value::Byte(b) => Raw::Byte(*b),
+            Value::Char(c) => Raw::Char(*c),
+            Value::Short(s) => Raw::Short(*s),
+            Value::Int(i) => Raw::Int(*i),
+            Value::Long(l) => Raw::Long(*l),
+            Value::Float(f) => Raw::Float(*f),
+            Value::Double(d) => Raw::Double(*d),
+            Value::Void => Raw::Void,
+            Value::Object(None) => Raw::Object(ObjectId::new(0)),
+            Value::Object(Some(object)) => {
+                let id = object.id().get();
+                match object.tag() {
+                    Tag::Array => Raw::Array(ArrayId::new(id)),
+                    Tag::String => Raw::String(StringId::new(id)),
+                    Tag::Thread => Raw::Thread(ThreadId::new(id)),
+                    Tag::ThreadGroup => Raw::ThreadGroup(ThreadGroupId::new(id)),
+                    Tag::ClassLoader => Raw::ClassLoader(ClassLoaderId::new(id)),
+                    Tag::ClassObject => Raw::ClassObject(ClassObjectId::new(id)),
+                    _ => Raw::Object(ObjectId::new(id)),
+                }
+            }
+        }
+    }
+
+    /// Gets the tag of this value
+    pub fn tag(&self) -> Tag {
+        match self {
+            Value::Boolean(_) => Tag::Boolean,
+            Value::Byte(_) => Tag::Byte,
+            Value::Char(_) => Tag::Char,
+            Value::Short(_) => Tag::Short,
+            Value::Int(_) => Tag::Int,
+            Value::Long(_) => Tag::Long,
+            Value::Float(_) => Tag::Float,
+            Value::Double(_) => Tag::Double,
+            Value::Void => Tag::Void,
+            Value::Object(Some(object)) => object.tag(),
+            Value::Object(None) => Tag::Object,
+        }
+    }
+
+    /// Gets the object this value refers to, if it's a non-null reference
+    pub fn as_object(&self) -> Option<&ObjectReference<VM>> {
+        match self {
+            Value::Object(object) => object.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Gets whether this value is a `null` reference
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Object(None))
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for Value<VM> {
+    fn clone(&self) -> Self {
+        match self {
+            Value::Boolean(b) => Value::Boolean(*b),
+            Value::Byte(b) => Value::Byte(*b),
+            Value::Char(c) => Value::Char(*c),
+            Value::Short(s) => Value::Short(*s),
+            Value::Int(i) => Value::Int(*i),
+            Value::Long(l) => Value::Long(*l),
+            Value::Float(f) => Value::Float(*f),
+            Value::Double(d) => Value::Double(*d),
+            Value::Void => Value::Void,
+            Value::Object(object) => Value::Object(object.clone()),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> PartialEq for Value<VM> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Boolean(l), Value::Boolean(r)) => l == r,
+            (Value::Byte(l), Value::Byte(r)) => l == r,
+            (Value::Char(l), Value::Char(r)) => l == r,
+            (Value::Short(l), Value::Short(r)) => l == r,
+            (Value::Int(l), Value::Int(r)) => l == r,
+            (Value::Long(l), Value::Long(r)) => l == r,
+            (Value::Float(l), Value::Float(r)) => l == r,
+            (Value::Double(l), Value::Double(r)) => l == r,
+            (Value::Void, Value::Void) => true,
+            (Value::Object(l), Value::Object(r)) => l == r,
+            _ => false,
+        }
+    }
+}
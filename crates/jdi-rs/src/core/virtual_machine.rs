@@ -1,14 +1,100 @@
 use crate::connect::Transport;
 use crate::core::objects::all_classes::AllClasses;
 use crate::core::private::VirtualMachineExt;
-use crate::Mirror;
-use jdwp_client::connect::JdwpTransport;
+use crate::event::EventQueue;
+use crate::expr::TypeName;
+use crate::redefine::RedefineError;
+use crate::request::EventRequestManager;
+use crate::thread_dump::ThreadDump;
+use crate::{Mirror, ReferenceType, ThreadReference};
+use jdwp_client::commands::{AllThreads, CapabilitiesNew, CapabilitiesNewReply, Resume, Suspend};
+use std::future::Future;
+use std::io;
 
 pub mod attaching_vm;
 
 /// A virtual machine
-pub trait VirtualMachine: VirtualMachineExt + Mirror<Self> + 'static {
+pub trait VirtualMachine: VirtualMachineExt + Mirror<Self> + Send + Sync + 'static {
     type Transport: Transport;
 
     fn all_classes(&self) -> AllClasses<Self>;
+
+    /// Gets the loaded types with a fully qualified name, such as `com.acme.Foo$Bar` or
+    /// `java.lang.String[]`. There's more than one if the type was loaded by different class
+    /// loaders.
+    fn classes_by_name(
+        &self,
+        name: &str,
+    ) -> impl Future<Output = io::Result<Vec<ReferenceType<Self>>>> {
+        let dimensions = name.matches("[]").count();
+        let element = name.trim_end_matches("[]");
+        let signature = "[".repeat(dimensions)
+            + &match TypeName::primitive_signature(element) {
+                Some(primitive) => primitive.to_string(),
+                None => format!("L{};", element.replace('.', "/")),
+            };
+        async move { ReferenceType::by_signature(&signature, &self.virtual_machine()).await }
+    }
+
+    /// Gets every running thread in this virtual machine
+    fn all_threads(&self) -> impl Future<Output = io::Result<Vec<ThreadReference<Self>>>> {
+        async move {
+            let reply = self.client().send(AllThreads).await?;
+            let vm = self.virtual_machine();
+            Ok(reply
+                .threads
+                .into_iter()
+                .map(|thread| ThreadReference::new(thread, &vm))
+                .collect())
+        }
+    }
+
+    /// Suspends every thread in this virtual machine
+    fn suspend(&self) -> impl Future<Output = io::Result<()>> {
+        async move {
+            self.client().send(Suspend).await?;
+            Ok(())
+        }
+    }
+
+    /// Resumes every thread in this virtual machine, undoing a [suspend](Self::suspend) or an
+    /// event that suspended everything
+    fn resume(&self) -> impl Future<Output = io::Result<()>> {
+        async move {
+            self.client().send(Resume).await?;
+            Ok(())
+        }
+    }
+
+    /// Gets the optional capabilities of this virtual machine, such as whether fields can be
+    /// watched
+    fn capabilities(&self) -> impl Future<Output = io::Result<CapabilitiesNewReply>> {
+        async move { self.client().send(CapabilitiesNew).await }
+    }
+
+    /// Dumps every thread in this virtual machine, with its call stack and the monitors it owns
+    /// or is waiting for, and finds the deadlocks between them. The virtual machine is suspended
+    /// while it's dumped.
+    fn thread_dump(&self) -> impl Future<Output = io::Result<ThreadDump<Self>>> {
+        ThreadDump::capture(self)
+    }
+
+    /// Replaces the code of loaded classes with new class files, all at once. Threads keep
+    /// executing the old versions of the methods they're in, and later calls use the new ones.
+    ///
+    /// Needs the `canRedefineClasses` capability. Adding methods also needs `canAddMethod`, and
+    /// any other change than to method bodies needs `canUnrestrictedlyRedefineClasses`, otherwise
+    /// the classes are [rejected](RedefineError::Rejected).
+    fn redefine_classes(
+        &self,
+        classes: &[(ReferenceType<Self>, Vec<u8>)],
+    ) -> impl Future<Output = Result<(), RedefineError>> {
+        crate::redefine::redefine_classes(self, classes)
+    }
+
+    /// Gets the queue events sent by this virtual machine are removed from
+    fn event_queue(&self) -> EventQueue<Self>;
+
+    /// Gets the manager used to create and delete event requests for this virtual machine
+    fn event_request_manager(&self) -> &EventRequestManager<Self>;
 }
@@ -2,10 +2,11 @@ use crate::connect::spi::TransportService;
 use crate::connect::Transport;
 use crate::core::objects::all_classes::AllClasses;
 use crate::core::private::VirtualMachineExt;
+use crate::event::{EventQueue, EventSource};
+use crate::request::EventRequestManager;
 use crate::{Mirror, VirtualMachine};
-use jdwp_client::connect::JdwpTransport;
 use jdwp_client::JdwpClient;
-use std::fmt::{Debug, Formatter, Pointer};
+use std::fmt::{Debug, Formatter};
 use std::sync::{Arc, Weak};
 
 /// An attaching vm
@@ -15,6 +16,8 @@ where
 {
     this: Weak<Self>,
     jdwp_client: Arc<JdwpClient<<T::TransportService as TransportService>::Transport>>,
+    event_source: Arc<EventSource>,
+    event_request_manager: EventRequestManager<Self>,
 }
 
 impl<T: Transport> AttachingVm<T>
@@ -25,9 +28,12 @@ where
     pub fn new(
         jdwp_client: JdwpClient<<T::TransportService as TransportService>::Transport>,
     ) -> Arc<Self> {
+        let event_source = EventSource::new(jdwp_client.events());
         Arc::new_cyclic(|weak| Self {
             this: weak.clone(),
             jdwp_client: Arc::new(jdwp_client),
+            event_source,
+            event_request_manager: EventRequestManager::new(weak),
         })
     }
 }
@@ -48,6 +54,8 @@ where
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AttachingVm")
             .field("jdwp_client", &self.jdwp_client)
+            .field("event_source", &self.event_source)
+            .field("event_request_manager", &self.event_request_manager)
             .finish()
     }
 }
@@ -70,4 +78,12 @@ where
     fn all_classes(&self) -> AllClasses<Self> {
         AllClasses::new(&self.this)
     }
+
+    fn event_queue(&self) -> EventQueue<Self> {
+        EventQueue::new(&self.event_source, &self.this)
+    }
+
+    fn event_request_manager(&self) -> &EventRequestManager<Self> {
+        &self.event_request_manager
+    }
 }
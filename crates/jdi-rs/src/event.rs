@@ -1 +1,14 @@
 //! Defines JDI events and event processing
+//!
+//! Events sent by the target VM are removed from a virtual machine's [EventQueue] as
+//! [EventSet]s, which hold every [Event] that occurred at the same time along with what was
+//! suspended because of them.
+
+pub use event_queue::EventQueue;
+pub(crate) use event_queue::EventSource;
+pub use event_set::EventSet;
+pub use events::*;
+
+mod event_queue;
+mod event_set;
+mod events;
@@ -0,0 +1,176 @@
+use crate::core::private::upgrade;
+use crate::event::{Event, EventSet};
+use crate::VirtualMachine;
+use futures::{Stream, StreamExt};
+use jdwp_client::events::{EventStream, Events};
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+type NextSet<VM> = Pin<Box<dyn Future<Output = io::Result<EventSet<VM>>> + Send>>;
+
+/// The composite events received from a virtual machine, shared between every [EventQueue] of
+/// that virtual machine
+#[derive(Debug)]
+pub(crate) struct EventSource {
+    stream: Mutex<EventStream>,
+    disconnect_delivered: AtomicBool,
+}
+
+impl EventSource {
+    /// Creates a new event source from the events of a jdwp client
+    pub(crate) fn new(stream: EventStream) -> Arc<Self> {
+        Arc::new(Self {
+            stream: Mutex::new(stream),
+            disconnect_delivered: AtomicBool::new(false),
+        })
+    }
+
+    async fn next(&self) -> Option<Events> {
+        self.stream.lock().await.next().await
+    }
+}
+
+/// A queue of [EventSet]s sent by the target VM.
+///
+/// Every queue of a virtual machine shares the same events, so each event set is only removed
+/// by one of them. Once the target VM disconnects, a final set containing a
+/// [VmDisconnect](Event::VmDisconnect) event is removed, after which removing fails.
+///
+/// Queues are also a [Stream] of event sets, ending once the target VM disconnects.
+pub struct EventQueue<VM: VirtualMachine + ?Sized> {
+    source: Arc<EventSource>,
+    // only ever accessed through `&mut self`, the mutex just lets the queue be shared
+    next: Option<std::sync::Mutex<NextSet<VM>>>,
+    vm: Weak<VM>,
+}
+
+impl<VM: VirtualMachine + ?Sized> EventQueue<VM> {
+    /// Creates a new event queue
+    pub(crate) fn new(source: &Arc<EventSource>, vm: &Weak<VM>) -> Self {
+        Self {
+            source: source.clone(),
+            next: None,
+            vm: vm.clone(),
+        }
+    }
+
+    /// Waits for the next available event set.
+    ///
+    /// Fails with [NotConnected](io::ErrorKind::NotConnected) once the
+    /// [VmDisconnect](Event::VmDisconnect) event has been removed.
+    pub async fn remove(&self) -> io::Result<EventSet<VM>> {
+        next_set(&self.source, &self.vm).await
+    }
+
+    /// Waits for the next available event set, giving up after the timeout has elapsed
+    pub async fn remove_timeout(&self, timeout: Duration) -> io::Result<Option<EventSet<VM>>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let Ok(events) = tokio::time::timeout_at(deadline, self.source.next()).await else {
+                return Ok(None);
+            };
+            if let Some(set) = to_event_set(&self.source, &self.vm, events).await? {
+                return Ok(Some(set));
+            }
+        }
+    }
+}
+
+/// Waits for the next event set meant for the application
+async fn next_set<VM: VirtualMachine + ?Sized>(
+    source: &EventSource,
+    vm: &Weak<VM>,
+) -> io::Result<EventSet<VM>> {
+    loop {
+        let events = source.next().await;
+        if let Some(set) = to_event_set(source, vm, events).await? {
+            return Ok(set);
+        }
+    }
+}
+
+/// Converts a composite into an event set, or `None` if every event in it was requested by the
+/// virtual machine itself and has already been handled
+async fn to_event_set<VM: VirtualMachine + ?Sized>(
+    source: &EventSource,
+    vm: &Weak<VM>,
+    events: Option<Events>,
+) -> io::Result<Option<EventSet<VM>>> {
+    match events {
+        Some(events) => {
+            let vm_ref = upgrade(vm)?;
+            let manager = vm_ref.event_request_manager();
+            let Some(events) = manager.remove_awaited(events) else {
+                return Ok(None);
+            };
+            let Some(events) = manager.prepare_breakpoints(events).await? else {
+                return Ok(None);
+            };
+            let Some(events) = manager.evaluate_breakpoints(events).await? else {
+                return Ok(None);
+            };
+            let mut converted = Vec::with_capacity(events.events.len());
+            for event in events.events {
+                if let Some(mut event) = Event::from_jdwp(event, manager, vm) {
+                    event.read_current_value().await;
+                    converted.push(event);
+                }
+            }
+            Ok(Some(EventSet::new(events.policy, converted, vm)))
+        }
+        None if !source.disconnect_delivered.swap(true, Ordering::SeqCst) => {
+            Ok(Some(EventSet::new(
+                jdwp_types::SuspendPolicy::None,
+                vec![Event::disconnect(vm)],
+                vm,
+            )))
+        }
+        None => Err(io::Error::new(
+            io::ErrorKind::NotConnected,
+            "the target VM has disconnected",
+        )),
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Stream for EventQueue<VM> {
+    type Item = EventSet<VM>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let me = &mut *self;
+        let next = me.next.get_or_insert_with(|| {
+            let source = me.source.clone();
+            let vm = me.vm.clone();
+            std::sync::Mutex::new(Box::pin(async move { next_set(&source, &vm).await }))
+        });
+        let next = next.get_mut().expect("event queue poisoned");
+        match next.as_mut().poll(cx) {
+            Poll::Ready(set) => {
+                me.next = None;
+                Poll::Ready(set.ok())
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for EventQueue<VM> {
+    fn clone(&self) -> Self {
+        Self::new(&self.source, &self.vm)
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Debug for EventQueue<VM> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventQueue")
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
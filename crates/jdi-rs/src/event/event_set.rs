@@ -0,0 +1,109 @@
+use crate::core::private::upgrade;
+use crate::event::Event;
+use crate::{Mirror, VirtualMachine};
+use jdwp_types::SuspendPolicy;
+use std::io;
+use std::sync::Weak;
+
+/// Several [Event]s that occurred at the same time in the target VM, removed together from the
+/// [EventQueue](crate::event::EventQueue).
+///
+/// The suspend policy of the set is the most suspending policy of the requests that generated
+/// its events. Once the events have been processed, the set should be [resumed](Self::resume) to
+/// undo whatever suspension it caused.
+#[derive(Debug)]
+pub struct EventSet<VM: VirtualMachine + ?Sized> {
+    suspend_policy: SuspendPolicy,
+    events: Vec<Event<VM>>,
+    vm: Weak<VM>,
+}
+
+impl<VM: VirtualMachine + ?Sized> EventSet<VM> {
+    /// Creates a new event set
+    pub(crate) fn new(
+        suspend_policy: SuspendPolicy,
+        events: Vec<Event<VM>>,
+        vm: &Weak<VM>,
+    ) -> Self {
+        Self {
+            suspend_policy,
+            events,
+            vm: vm.clone(),
+        }
+    }
+
+    /// Gets what was suspended when these events occurred
+    pub fn suspend_policy(&self) -> SuspendPolicy {
+        self.suspend_policy
+    }
+
+    /// Gets the events within this set
+    pub fn events(&self) -> &[Event<VM>] {
+        &self.events
+    }
+
+    /// Iterates over the events within this set
+    pub fn iter(&self) -> std::slice::Iter<'_, Event<VM>> {
+        self.events.iter()
+    }
+
+    /// Gets the number of events within this set
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Gets whether this set contains no events
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Resumes whatever was suspended when these events occurred.
+    ///
+    /// - [SuspendPolicy::None]: nothing was suspended, so nothing is resumed
+    /// - [SuspendPolicy::EventThread]: the thread the events occurred in is resumed
+    /// - [SuspendPolicy::All]: the entire virtual machine is resumed
+    pub async fn resume(&self) -> io::Result<()> {
+        match self.suspend_policy {
+            SuspendPolicy::None => Ok(()),
+            SuspendPolicy::EventThread => match self.events.iter().find_map(Event::thread) {
+                Some(thread) => thread.resume().await,
+                None => Ok(()),
+            },
+            SuspendPolicy::All => upgrade(&self.vm)?.resume().await,
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for EventSet<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            suspend_policy: self.suspend_policy,
+            events: self.events.clone(),
+            vm: self.vm.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Mirror<VM> for EventSet<VM> {
+    fn virtual_machine(&self) -> Weak<VM> {
+        self.vm.clone()
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> IntoIterator for EventSet<VM> {
+    type Item = Event<VM>;
+    type IntoIter = std::vec::IntoIter<Event<VM>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.events.into_iter()
+    }
+}
+
+impl<'a, VM: VirtualMachine + ?Sized> IntoIterator for &'a EventSet<VM> {
+    type Item = &'a Event<VM>;
+    type IntoIter = std::slice::Iter<'a, Event<VM>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.events.iter()
+    }
+}
@@ -0,0 +1,796 @@
+use crate::core::private::upgrade;
+use crate::request::{
+    AccessWatchpointRequest, BreakpointRequest, ClassPrepareRequest, ClassUnloadRequest,
+    EventRequest, EventRequestManager, ExceptionRequest, MethodEntryRequest, MethodExitRequest,
+    ModificationWatchpointRequest, MonitorContendedEnterRequest, MonitorContendedEnteredRequest,
+    MonitorWaitRequest, MonitorWaitedRequest, StepRequest, ThreadDeathRequest, ThreadStartRequest,
+    VmDeathRequest,
+};
+use crate::{
+    Field, InvokeError, Location, Mirror, ObjectReference, ReferenceType, ThreadReference, Value,
+    VirtualMachine,
+};
+use jdwp_client::commands::class_type::INVOKE_SINGLE_THREADED;
+use jdwp_client::commands::{object_reference, reference_type};
+use jdwp_client::events::Event as JdwpEvent;
+use jdwp_types::{EventKind, FieldId, Int, Long, ReferenceTypeId, TaggedObjectId};
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::sync::Weak;
+use tracing::{debug, trace};
+
+macro_rules! events {
+    (
+        $(
+            $(#[$meta:meta])*
+            $variant:ident($name:ident): $request:ident {
+                $(
+                    $(#[$field_meta:meta])*
+                    $field:ident: $field_ty:ty
+                ),* $(,)?
+            } $(copy {
+                $(
+                    $(#[$copy_meta:meta])*
+                    $copy_field:ident: $copy_ty:ty
+                ),* $(,)?
+            })?;
+        )*
+    ) => {
+        /// An occurrence in the target VM that is of interest to a debugger
+        #[derive(Debug)]
+        pub enum Event<VM: VirtualMachine + ?Sized> {
+            $(
+                $(#[$meta])*
+                $variant($name<VM>),
+            )*
+        }
+
+        impl<VM: VirtualMachine + ?Sized> Event<VM> {
+            /// Gets the request that generated this event.
+            ///
+            /// This is `None` for events the target VM sends automatically, such as
+            /// [VmStart](Event::VmStart), and for requests that were deleted before the event was
+            /// removed from the [EventQueue](crate::event::EventQueue).
+            pub fn request(&self) -> Option<&EventRequest<VM>> {
+                match self {
+                    $(
+                        Event::$variant(event) => event.request().map(AsRef::as_ref),
+                    )*
+                }
+            }
+        }
+
+        impl<VM: VirtualMachine + ?Sized> Clone for Event<VM> {
+            fn clone(&self) -> Self {
+                match self {
+                    $(
+                        Event::$variant(event) => Event::$variant(event.clone()),
+                    )*
+                }
+            }
+        }
+
+        impl<VM: VirtualMachine + ?Sized> Mirror<VM> for Event<VM> {
+            fn virtual_machine(&self) -> Weak<VM> {
+                match self {
+                    $(
+                        Event::$variant(event) => event.virtual_machine(),
+                    )*
+                }
+            }
+        }
+
+        $(
+            $(#[$meta])*
+            #[derive(Debug)]
+            pub struct $name<VM: VirtualMachine + ?Sized> {
+                request: Option<$request<VM>>,
+                $($field: $field_ty,)*
+                $($($copy_field: $copy_ty,)*)?
+                vm: Weak<VM>,
+            }
+
+            impl<VM: VirtualMachine + ?Sized> $name<VM> {
+                /// Gets the request that generated this event, if it still exists
+                pub fn request(&self) -> Option<&$request<VM>> {
+                    self.request.as_ref()
+                }
+
+                $(
+                    $(#[$field_meta])*
+                    pub fn $field(&self) -> &$field_ty {
+                        &self.$field
+                    }
+                )*
+
+                $($(
+                    $(#[$copy_meta])*
+                    pub fn $copy_field(&self) -> $copy_ty {
+                        self.$copy_field
+                    }
+                )*)?
+            }
+
+            impl<VM: VirtualMachine + ?Sized> Clone for $name<VM> {
+                fn clone(&self) -> Self {
+                    Self {
+                        request: self.request.clone(),
+                        $($field: self.$field.clone(),)*
+                        $($($copy_field: self.$copy_field,)*)?
+                        vm: self.vm.clone(),
+                    }
+                }
+            }
+
+            impl<VM: VirtualMachine + ?Sized> Mirror<VM> for $name<VM> {
+                fn virtual_machine(&self) -> Weak<VM> {
+                    self.vm.clone()
+                }
+            }
+
+            impl<VM: VirtualMachine + ?Sized> From<$name<VM>> for Event<VM> {
+                fn from(value: $name<VM>) -> Self {
+                    Event::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+events! {
+    /// Notification of initialization of the target VM, sent before any application code runs
+    VmStart(VmStartEvent): EventRequest {
+        /// Gets the initial thread of the target VM
+        thread: ThreadReference<VM>,
+    };
+    /// Notification of the target VM's termination
+    VmDeath(VmDeathEvent): VmDeathRequest {};
+    /// Notification that the connection to the target VM has been closed.
+    ///
+    /// This is never sent by the target VM, and is instead the last event placed in the
+    /// [EventQueue](crate::event::EventQueue).
+    VmDisconnect(VmDisconnectEvent): EventRequest {};
+    /// Notification that a breakpoint has been reached
+    Breakpoint(BreakpointEvent): BreakpointRequest {
+        /// Gets the thread that hit the breakpoint
+        thread: ThreadReference<VM>,
+        /// Gets the location of the breakpoint
+        location: Location<VM>,
+    };
+    /// Notification that a step has completed
+    Step(StepEvent): StepRequest {
+        /// Gets the thread that stepped
+        thread: ThreadReference<VM>,
+        /// Gets the location the step completed at
+        location: Location<VM>,
+    };
+    /// Notification that an exception has been thrown
+    Exception(ExceptionEvent): ExceptionRequest {
+        /// Gets the thread that threw the exception
+        thread: ThreadReference<VM>,
+        /// Gets the location the exception was thrown from
+        location: Location<VM>,
+        /// Gets the thrown exception object
+        exception: ObjectReference<VM>,
+        /// Gets the location the exception will be caught at, or `None` if it's uncaught
+        catch_location: Option<Location<VM>>,
+    };
+    /// Notification that a thread has started
+    ThreadStart(ThreadStartEvent): ThreadStartRequest {
+        /// Gets the thread that started
+        thread: ThreadReference<VM>,
+    };
+    /// Notification that a thread has completed
+    ThreadDeath(ThreadDeathEvent): ThreadDeathRequest {
+        /// Gets the thread that completed
+        thread: ThreadReference<VM>,
+    };
+    /// Notification that a class has been prepared
+    ClassPrepare(ClassPrepareEvent): ClassPrepareRequest {
+        /// Gets the thread that prepared the class
+        thread: ThreadReference<VM>,
+        /// Gets the type that was prepared
+        reference_type: ReferenceType<VM>,
+    };
+    /// Notification that a class has been unloaded
+    ClassUnload(ClassUnloadEvent): ClassUnloadRequest {
+        /// Gets the JNI signature of the class that was unloaded
+        signature: String,
+    };
+    /// Notification that a method has been invoked
+    MethodEntry(MethodEntryEvent): MethodEntryRequest {
+        /// Gets the thread that invoked the method
+        thread: ThreadReference<VM>,
+        /// Gets the location of the start of the invoked method
+        location: Location<VM>,
+    };
+    /// Notification that a method is about to return
+    MethodExit(MethodExitEvent): MethodExitRequest {
+        /// Gets the thread the method is returning in
+        thread: ThreadReference<VM>,
+        /// Gets the location of the return
+        location: Location<VM>,
+        /// Gets the value the method is returning, if it was requested
+        return_value: Option<Value<VM>>,
+    };
+    /// Notification that a watched field is about to be read
+    AccessWatchpoint(AccessWatchpointEvent): AccessWatchpointRequest {
+        /// Gets the thread accessing the field
+        thread: ThreadReference<VM>,
+        /// Gets the location of the access
+        location: Location<VM>,
+        /// Gets the object the field is accessed on, or `None` for a static field
+        object: Option<ObjectReference<VM>>,
+        /// Gets the value of the field when the event was removed from the queue, or `None` if
+        /// it couldn't be read
+        current_value: Option<Value<VM>>,
+    } copy {
+        /// Gets the id of the type declaring the field
+        declaring_type_id: ReferenceTypeId,
+        /// Gets the id of the accessed field
+        field_id: FieldId,
+    };
+    /// Notification that a watched field is about to be modified
+    ModificationWatchpoint(ModificationWatchpointEvent): ModificationWatchpointRequest {
+        /// Gets the thread modifying the field
+        thread: ThreadReference<VM>,
+        /// Gets the location of the modification
+        location: Location<VM>,
+        /// Gets the object the field is modified on, or `None` for a static field
+        object: Option<ObjectReference<VM>>,
+        /// Gets the value of the field before the modification, read when the event was removed
+        /// from the queue, or `None` if it couldn't be read
+        current_value: Option<Value<VM>>,
+        /// Gets the value the field is about to be set to
+        value_to_be: Value<VM>,
+    } copy {
+        /// Gets the id of the type declaring the field
+        declaring_type_id: ReferenceTypeId,
+        /// Gets the id of the modified field
+        field_id: FieldId,
+    };
+    /// Notification that a thread is attempting to enter a monitor already held by another thread
+    MonitorContendedEnter(MonitorContendedEnterEvent): MonitorContendedEnterRequest {
+        /// Gets the thread attempting to enter the monitor
+        thread: ThreadReference<VM>,
+        /// Gets the monitor object
+        monitor: ObjectReference<VM>,
+        /// Gets the location of the attempt
+        location: Location<VM>,
+    };
+    /// Notification that a thread has entered a monitor after waiting for another thread to
+    /// release it
+    MonitorContendedEntered(MonitorContendedEnteredEvent): MonitorContendedEnteredRequest {
+        /// Gets the thread that entered the monitor
+        thread: ThreadReference<VM>,
+        /// Gets the monitor object
+        monitor: ObjectReference<VM>,
+        /// Gets the location the monitor was entered at
+        location: Location<VM>,
+    };
+    /// Notification that a thread is about to wait on a monitor object
+    MonitorWait(MonitorWaitEvent): MonitorWaitRequest {
+        /// Gets the thread about to wait
+        thread: ThreadReference<VM>,
+        /// Gets the monitor object
+        monitor: ObjectReference<VM>,
+        /// Gets the location of the wait
+        location: Location<VM>,
+    } copy {
+        /// Gets the number of milliseconds the thread will wait for
+        timeout: Long,
+    };
+    /// Notification that a thread has finished waiting on a monitor object
+    MonitorWaited(MonitorWaitedEvent): MonitorWaitedRequest {
+        /// Gets the thread that waited
+        thread: ThreadReference<VM>,
+        /// Gets the monitor object
+        monitor: ObjectReference<VM>,
+        /// Gets the location of the wait
+        location: Location<VM>,
+    } copy {
+        /// Gets whether the wait timed out
+        timed_out: bool,
+    };
+}
+
+impl<VM: VirtualMachine + ?Sized> Event<VM> {
+    /// Gets the thread this event occurred in, if any
+    pub fn thread(&self) -> Option<&ThreadReference<VM>> {
+        match self {
+            Event::VmStart(event) => Some(event.thread()),
+            Event::Breakpoint(event) => Some(event.thread()),
+            Event::Step(event) => Some(event.thread()),
+            Event::Exception(event) => Some(event.thread()),
+            Event::ThreadStart(event) => Some(event.thread()),
+            Event::ThreadDeath(event) => Some(event.thread()),
+            Event::ClassPrepare(event) => Some(event.thread()),
+            Event::MethodEntry(event) => Some(event.thread()),
+            Event::MethodExit(event) => Some(event.thread()),
+            Event::AccessWatchpoint(event) => Some(event.thread()),
+            Event::ModificationWatchpoint(event) => Some(event.thread()),
+            Event::MonitorContendedEnter(event) => Some(event.thread()),
+            Event::MonitorContendedEntered(event) => Some(event.thread()),
+            Event::MonitorWait(event) => Some(event.thread()),
+            Event::MonitorWaited(event) => Some(event.thread()),
+            Event::VmDeath(_) | Event::VmDisconnect(_) | Event::ClassUnload(_) => None,
+        }
+    }
+
+    /// Gets the location this event occurred at, if any
+    pub fn location(&self) -> Option<&Location<VM>> {
+        match self {
+            Event::Breakpoint(event) => Some(event.location()),
+            Event::Step(event) => Some(event.location()),
+            Event::Exception(event) => Some(event.location()),
+            Event::MethodEntry(event) => Some(event.location()),
+            Event::MethodExit(event) => Some(event.location()),
+            Event::AccessWatchpoint(event) => Some(event.location()),
+            Event::ModificationWatchpoint(event) => Some(event.location()),
+            Event::MonitorContendedEnter(event) => Some(event.location()),
+            Event::MonitorContendedEntered(event) => Some(event.location()),
+            Event::MonitorWait(event) => Some(event.location()),
+            Event::MonitorWaited(event) => Some(event.location()),
+            _ => None,
+        }
+    }
+
+    /// Creates the event that's placed in the queue once the target VM disconnects
+    pub(crate) fn disconnect(vm: &Weak<VM>) -> Self {
+        Event::VmDisconnect(VmDisconnectEvent {
+            request: None,
+            vm: vm.clone(),
+        })
+    }
+
+    /// Creates a JDI event from an event received over jdwp, resolving the request that
+    /// generated it. Returns `None` for events that have no JDI equivalent.
+    pub(crate) fn from_jdwp(
+        event: JdwpEvent,
+        manager: &EventRequestManager<VM>,
+        vm: &Weak<VM>,
+    ) -> Option<Self> {
+        let thread = |thread| ThreadReference::new(thread, vm);
+        let location = |location| Location::new(location, vm);
+        let object_ref = |object: TaggedObjectId| ObjectReference::from_tagged(object, vm);
+        let nullable =
+            |object: TaggedObjectId| (object.id().get() != 0).then(|| object_ref(object));
+        let event = match event {
+            JdwpEvent::VmStart {
+                thread: thread_id, ..
+            } => Event::VmStart(VmStartEvent {
+                request: None,
+                thread: thread(thread_id),
+                vm: vm.clone(),
+            }),
+            JdwpEvent::VmDeath { request_id } => Event::VmDeath(VmDeathEvent {
+                request: request(manager, EventKind::VmDeath, request_id),
+                vm: vm.clone(),
+            }),
+            JdwpEvent::VmDisconnected => Event::disconnect(vm),
+            JdwpEvent::Breakpoint {
+                request_id,
+                thread: thread_id,
+                location: loc,
+            } => Event::Breakpoint(BreakpointEvent {
+                request: request(manager, EventKind::Breakpoint, request_id),
+                thread: thread(thread_id),
+                location: location(loc),
+                vm: vm.clone(),
+            }),
+            JdwpEvent::SingleStep {
+                request_id,
+                thread: thread_id,
+                location: loc,
+            } => Event::Step(StepEvent {
+                request: request(manager, EventKind::SingleStep, request_id),
+                thread: thread(thread_id),
+                location: location(loc),
+                vm: vm.clone(),
+            }),
+            JdwpEvent::Exception {
+                request_id,
+                thread: thread_id,
+                location: loc,
+                exception,
+                catch_location,
+            } => Event::Exception(ExceptionEvent {
+                request: request(manager, EventKind::Exception, request_id),
+                thread: thread(thread_id),
+                location: location(loc),
+                exception: object_ref(exception),
+                catch_location: (catch_location.class.get() != 0).then(|| location(catch_location)),
+                vm: vm.clone(),
+            }),
+            JdwpEvent::ThreadStart {
+                request_id,
+                thread: thread_id,
+            } => Event::ThreadStart(ThreadStartEvent {
+                request: request(manager, EventKind::ThreadStart, request_id),
+                thread: thread(thread_id),
+                vm: vm.clone(),
+            }),
+            JdwpEvent::ThreadDeath {
+                request_id,
+                thread: thread_id,
+            } => Event::ThreadDeath(ThreadDeathEvent {
+                request: request(manager, EventKind::ThreadDeath, request_id),
+                thread: thread(thread_id),
+                vm: vm.clone(),
+            }),
+            JdwpEvent::ClassPrepare {
+                request_id,
+                thread: thread_id,
+                ref_type_tag,
+                type_id,
+                signature,
+                status,
+            } => Event::ClassPrepare(ClassPrepareEvent {
+                request: request(manager, EventKind::ClassPrepare, request_id),
+                thread: thread(thread_id),
+                reference_type: ReferenceType::new(ref_type_tag, type_id, signature, status, vm),
+                vm: vm.clone(),
+            }),
+            JdwpEvent::ClassUnload {
+                request_id,
+                signature,
+            } => Event::ClassUnload(ClassUnloadEvent {
+                request: request(manager, EventKind::ClassUnload, request_id),
+                signature,
+                vm: vm.clone(),
+            }),
+            JdwpEvent::MethodEntry {
+                request_id,
+                thread: thread_id,
+                location: loc,
+            } => Event::MethodEntry(MethodEntryEvent {
+                request: request(manager, EventKind::MethodEntry, request_id),
+                thread: thread(thread_id),
+                location: location(loc),
+                vm: vm.clone(),
+            }),
+            JdwpEvent::MethodExit {
+                request_id,
+                thread: thread_id,
+                location: loc,
+            } => Event::MethodExit(MethodExitEvent {
+                request: request(manager, EventKind::MethodExit, request_id),
+                thread: thread(thread_id),
+                location: location(loc),
+                return_value: None,
+                vm: vm.clone(),
+            }),
+            JdwpEvent::MethodExitWithReturnValue {
+                request_id,
+                thread: thread_id,
+                location: loc,
+                value,
+            } => Event::MethodExit(MethodExitEvent {
+                request: request(manager, EventKind::MethodExitWithReturnValue, request_id),
+                thread: thread(thread_id),
+                location: location(loc),
+                return_value: Some(Value::new(value, vm)),
+                vm: vm.clone(),
+            }),
+            JdwpEvent::FieldAccess {
+                request_id,
+                thread: thread_id,
+                location: loc,
+                type_id,
+                field_id,
+                object,
+                ..
+            } => Event::AccessWatchpoint(AccessWatchpointEvent {
+                request: request(manager, EventKind::FieldAccess, request_id),
+                thread: thread(thread_id),
+                location: location(loc),
+                object: nullable(object),
+                current_value: None,
+                declaring_type_id: type_id,
+                field_id,
+                vm: vm.clone(),
+            }),
+            JdwpEvent::FieldModification {
+                request_id,
+                thread: thread_id,
+                location: loc,
+                type_id,
+                field_id,
+                object,
+                value_to_be,
+                ..
+            } => Event::ModificationWatchpoint(ModificationWatchpointEvent {
+                request: request(manager, EventKind::FieldModification, request_id),
+                thread: thread(thread_id),
+                location: location(loc),
+                object: nullable(object),
+                current_value: None,
+                value_to_be: Value::new(value_to_be, vm),
+                declaring_type_id: type_id,
+                field_id,
+                vm: vm.clone(),
+            }),
+            JdwpEvent::MonitorContendedEnter {
+                request_id,
+                thread: thread_id,
+                object: monitor,
+                location: loc,
+            } => Event::MonitorContendedEnter(MonitorContendedEnterEvent {
+                request: request(manager, EventKind::MonitorContendedEnter, request_id),
+                thread: thread(thread_id),
+                monitor: object_ref(monitor),
+                location: location(loc),
+                vm: vm.clone(),
+            }),
+            JdwpEvent::MonitorContendedEntered {
+                request_id,
+                thread: thread_id,
+                object: monitor,
+                location: loc,
+            } => Event::MonitorContendedEntered(MonitorContendedEnteredEvent {
+                request: request(manager, EventKind::MonitorContendedEntered, request_id),
+                thread: thread(thread_id),
+                monitor: object_ref(monitor),
+                location: location(loc),
+                vm: vm.clone(),
+            }),
+            JdwpEvent::MonitorWait {
+                request_id,
+                thread: thread_id,
+                object: monitor,
+                location: loc,
+                timeout,
+            } => Event::MonitorWait(MonitorWaitEvent {
+                request: request(manager, EventKind::MonitorWait, request_id),
+                thread: thread(thread_id),
+                monitor: object_ref(monitor),
+                location: location(loc),
+                timeout,
+                vm: vm.clone(),
+            }),
+            JdwpEvent::MonitorWaited {
+                request_id,
+                thread: thread_id,
+                object: monitor,
+                location: loc,
+                timed_out,
+            } => Event::MonitorWaited(MonitorWaitedEvent {
+                request: request(manager, EventKind::MonitorWaited, request_id),
+                thread: thread(thread_id),
+                monitor: object_ref(monitor),
+                location: location(loc),
+                timed_out: timed_out != 0,
+                vm: vm.clone(),
+            }),
+            other @ (JdwpEvent::FramePop
+            | JdwpEvent::UserDefined
+            | JdwpEvent::ClassLoad
+            | JdwpEvent::ExceptionCatch) => {
+                trace!("ignoring event without a JDI equivalent: {other:?}");
+                return None;
+            }
+        };
+        Some(event)
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Event<VM> {
+    /// Reads the current value of the field a watchpoint event is for, which jdwp doesn't send.
+    /// Other events are left alone.
+    pub(crate) async fn read_current_value(&mut self) {
+        let (object, declaring_type_id, field_id, current_value, vm) = match self {
+            Event::AccessWatchpoint(event) => (
+                &event.object,
+                event.declaring_type_id,
+                event.field_id,
+                &mut event.current_value,
+                &event.vm,
+            ),
+            Event::ModificationWatchpoint(event) => (
+                &event.object,
+                event.declaring_type_id,
+                event.field_id,
+                &mut event.current_value,
+                &event.vm,
+            ),
+            _ => return,
+        };
+        match read_field(object.as_ref(), declaring_type_id, field_id, vm).await {
+            Ok(value) => *current_value = value,
+            Err(e) => debug!("failed to read the current value of field {field_id:?}: {e}"),
+        }
+    }
+}
+
+/// Reads a field of an object, or a static field of its declaring type if there's no object
+async fn read_field<VM: VirtualMachine + ?Sized>(
+    object: Option<&ObjectReference<VM>>,
+    declaring_type_id: ReferenceTypeId,
+    field_id: FieldId,
+    vm: &Weak<VM>,
+) -> io::Result<Option<Value<VM>>> {
+    let vm_ref = upgrade(vm)?;
+    let client = vm_ref.client();
+    let values = match object {
+        Some(object) => {
+            client
+                .send(object_reference::GetValues {
+                    object: object.id(),
+                    fields: vec![field_id],
+                })
+                .await?
+                .values
+        }
+        None => {
+            client
+                .send(reference_type::GetValues {
+                    ref_type: declaring_type_id,
+                    fields: vec![field_id],
+                })
+                .await?
+                .values
+        }
+    };
+    Ok(values.into_iter().next().map(|value| Value::new(value, vm)))
+}
+
+impl<VM: VirtualMachine + ?Sized> ExceptionEvent<VM> {
+    /// Gets the message of the exception, as read from its `detailMessage` field
+    pub async fn message(&self) -> io::Result<Option<String>> {
+        self.exception.detail_message().await
+    }
+
+    /// Gets the stack trace of the exception, by invoking its `getStackTrace` method in the
+    /// thread that threw it. The thread must have been suspended by this event, and its frames
+    /// must be fetched again afterwards.
+    pub async fn stack_trace(&self) -> Result<Vec<StackTraceElement>, InvokeError<VM>> {
+        let ref_type = self.exception.reference_type().await?;
+        let get_stack_trace = ref_type
+            .visible_methods()
+            .await?
+            .into_iter()
+            .find(|method| {
+                method.name() == "getStackTrace"
+                    && method.signature() == "()[Ljava/lang/StackTraceElement;"
+            })
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} has no getStackTrace method", ref_type.name()),
+                )
+            })?;
+        let Value::Object(Some(array)) = self
+            .exception
+            .invoke_method(&self.thread, &get_stack_trace, &[], INVOKE_SINGLE_THREADED)
+            .await?
+        else {
+            return Ok(vec![]);
+        };
+        let length = array.array_length().await?;
+        let mut stack_trace = Vec::with_capacity(length as usize);
+        for element in array.array_values(0, length).await? {
+            if let Value::Object(Some(element)) = element {
+                stack_trace.push(StackTraceElement::read(&element).await?);
+            }
+        }
+        Ok(stack_trace)
+    }
+}
+
+/// A frame of an exception's stack trace, copied from a `java.lang.StackTraceElement`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StackTraceElement {
+    class_name: String,
+    method_name: String,
+    file_name: Option<String>,
+    line_number: Int,
+}
+
+impl StackTraceElement {
+    /// Reads the fields of a `java.lang.StackTraceElement`
+    async fn read<VM: VirtualMachine + ?Sized>(element: &ObjectReference<VM>) -> io::Result<Self> {
+        let ref_type = element.reference_type().await?;
+        let mut fields = vec![];
+        for name in ["declaringClass", "methodName", "fileName", "lineNumber"] {
+            let field = ref_type.field_by_name(name).await?.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} has no {name} field", ref_type.name()),
+                )
+            })?;
+            fields.push(field);
+        }
+        let mut values = element.get_values(&fields).await?.into_iter();
+        let class_name = string(values.next()).await?.unwrap_or_default();
+        let method_name = string(values.next()).await?.unwrap_or_default();
+        let file_name = string(values.next()).await?;
+        let line_number = match values.next() {
+            Some(Value::Int(line_number)) => line_number,
+            _ => -1,
+        };
+        Ok(Self {
+            class_name,
+            method_name,
+            file_name,
+            line_number,
+        })
+    }
+
+    /// Gets the fully qualified name of the class of the frame's method
+    pub fn class_name(&self) -> &str {
+        &self.class_name
+    }
+
+    /// Gets the name of the frame's method
+    pub fn method_name(&self) -> &str {
+        &self.method_name
+    }
+
+    /// Gets the name of the source file of the frame's method, if it's known
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// Gets the line the frame is at, which is negative if it isn't known, and `-2` for native
+    /// methods
+    pub fn line_number(&self) -> Int {
+        self.line_number
+    }
+
+    /// Whether the frame's method is native
+    pub fn is_native_method(&self) -> bool {
+        self.line_number == -2
+    }
+}
+
+/// Gets the characters of a value if it's a string
+async fn string<VM: VirtualMachine + ?Sized>(
+    value: Option<Value<VM>>,
+) -> io::Result<Option<String>> {
+    match value {
+        Some(Value::Object(Some(string))) => Ok(Some(string.string_value().await?)),
+        _ => Ok(None),
+    }
+}
+
+/// Formats the frame the way `java.lang.StackTraceElement` does, such as
+/// `com.acme.Foo.bar(Foo.java:12)`
+impl Display for StackTraceElement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}(", self.class_name, self.method_name)?;
+        match &self.file_name {
+            _ if self.is_native_method() => write!(f, "Native Method")?,
+            Some(file_name) if self.line_number >= 0 => {
+                write!(f, "{file_name}:{}", self.line_number)?
+            }
+            Some(file_name) => write!(f, "{file_name}")?,
+            None => write!(f, "Unknown Source")?,
+        }
+        write!(f, ")")
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> AccessWatchpointEvent<VM> {
+    /// Gets the watched field, if the request that generated this event still exists
+    pub fn field(&self) -> Option<Field<VM>> {
+        self.request.as_ref().map(|request| request.field())
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> ModificationWatchpointEvent<VM> {
+    /// Gets the watched field, if the request that generated this event still exists
+    pub fn field(&self) -> Option<Field<VM>> {
+        self.request.as_ref().map(|request| request.field())
+    }
+}
+
+/// Finds the live request of the expected type that generated an event
+fn request<VM, R>(manager: &EventRequestManager<VM>, kind: EventKind, request_id: Int) -> Option<R>
+where
+    VM: VirtualMachine + ?Sized,
+    R: TryFrom<EventRequest<VM>>,
+{
+    manager
+        .event_request(kind, request_id)
+        .and_then(|request| R::try_from(request).ok())
+}
@@ -0,0 +1,27 @@
+//! Java-like expressions, evaluated by the debugger against a suspended [StackFrame](crate::StackFrame).
+//!
+//! Expressions are a practical subset of Java's: literals, local variables and arguments, `this`,
+//! fields, static fields, method calls, array components, arithmetic, comparisons, string
+//! concatenation, casts, `instanceof` and `?:`, as in `order.getItems().size() > 3`. They're used
+//! for [breakpoint conditions](crate::request::BreakpointOptions) and logpoints, since the
+//! `Conditional` modifier of jdwp is reserved and can't be used to have the target VM evaluate
+//! them, and by [StackFrame::evaluate](crate::StackFrame::evaluate).
+//!
+//! Names are looked up like the Java compiler would, except that imports aren't known: classes
+//! must be fully qualified unless they're nested in the frame's class, in its package or in
+//! `java.lang`, and only loaded classes can be found. Overloaded methods are chosen like the
+//! compiler does, but variable arity methods must be passed an array.
+//!
+//! Methods are invoked in the frame's thread with every other thread left suspended. Comparing a
+//! string with a string literal compares their characters, unlike Java.
+
+mod ast;
+mod eval;
+mod invoke;
+mod parser;
+mod types;
+
+pub use ast::{BinaryOp, Expression, Literal, TypeName, UnaryOp};
+pub use eval::EvalError;
+pub(crate) use eval::{display_value, Context};
+pub use parser::ParseError;
@@ -0,0 +1,272 @@
+use crate::expr::parser::{ParseError, Parser};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A parsed expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    /// A constant value
+    Literal(Literal),
+    /// The object executing the frame's method
+    This,
+    /// A local variable, or a field of `this` or the frame's class
+    Name(String),
+    /// A field of the object or class a target expression evaluates to
+    Field {
+        target: Box<Expression>,
+        name: String,
+    },
+    /// An operator applied to a single operand
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expression>,
+    },
+    /// An operator applied to two operands
+    Binary {
+        op: BinaryOp,
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    /// A method invoked on the object or class a target expression evaluates to, or on `this` or
+    /// the frame's class without a target
+    MethodCall {
+        target: Option<Box<Expression>>,
+        name: String,
+        arguments: Vec<Expression>,
+    },
+    /// A component of an array
+    Index {
+        array: Box<Expression>,
+        index: Box<Expression>,
+    },
+    /// A conversion of an operand to another type, such as `(int) 1.5` or `(String) value`
+    Cast {
+        type_name: TypeName,
+        operand: Box<Expression>,
+    },
+    /// Whether an operand is an instance of a reference type
+    InstanceOf {
+        operand: Box<Expression>,
+        type_name: TypeName,
+    },
+    /// `condition ? then : otherwise`, which only evaluates one of its branches
+    Conditional {
+        condition: Box<Expression>,
+        then: Box<Expression>,
+        otherwise: Box<Expression>,
+    },
+}
+
+impl Expression {
+    /// Parses an expression
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        Parser::new(source)?.parse_expression()
+    }
+}
+
+impl FromStr for Expression {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expression::Literal(literal) => write!(f, "{literal}"),
+            Expression::This => write!(f, "this"),
+            Expression::Name(name) => write!(f, "{name}"),
+            Expression::Field { target, name } => write!(f, "{target}.{name}"),
+            Expression::Unary { op, operand } => write!(f, "{op}{operand}"),
+            Expression::Binary { op, left, right } => write!(f, "({left} {op} {right})"),
+            Expression::MethodCall {
+                target,
+                name,
+                arguments,
+            } => {
+                if let Some(target) = target {
+                    write!(f, "{target}.")?;
+                }
+                write!(f, "{name}(")?;
+                for (i, argument) in arguments.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{argument}")?;
+                }
+                write!(f, ")")
+            }
+            Expression::Index { array, index } => write!(f, "{array}[{index}]"),
+            Expression::Cast { type_name, operand } => write!(f, "(({type_name}) {operand})"),
+            Expression::InstanceOf { operand, type_name } => {
+                write!(f, "({operand} instanceof {type_name})")
+            }
+            Expression::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => write!(f, "({condition} ? {then} : {otherwise})"),
+        }
+    }
+}
+
+/// The name of a type, as written in a cast or `instanceof`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypeName {
+    /// The name of the element type, such as `int`, `String` or `java.util.List`
+    pub name: String,
+    /// The number of array dimensions, such as 2 for `int[][]`
+    pub dimensions: usize,
+}
+
+impl TypeName {
+    /// Gets the JNI signature of a primitive type by its name, such as `I` for `int`
+    pub fn primitive_signature(name: &str) -> Option<&'static str> {
+        Some(match name {
+            "boolean" => "Z",
+            "byte" => "B",
+            "char" => "C",
+            "short" => "S",
+            "int" => "I",
+            "long" => "J",
+            "float" => "F",
+            "double" => "D",
+            _ => return None,
+        })
+    }
+
+    /// Whether this is a primitive type rather than a reference type
+    pub fn is_primitive(&self) -> bool {
+        self.dimensions == 0 && Self::primitive_signature(&self.name).is_some()
+    }
+}
+
+impl Display for TypeName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.name, "[]".repeat(self.dimensions))
+    }
+}
+
+/// A constant value written in an expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Null,
+    Boolean(bool),
+    Char(u16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+}
+
+impl Display for Literal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Literal::Null => write!(f, "null"),
+            Literal::Boolean(b) => write!(f, "{b}"),
+            Literal::Char(c) => match char::from_u32(*c as u32) {
+                Some(c) => write!(f, "{c:?}"),
+                None => write!(f, "'\\u{c:04x}'"),
+            },
+            Literal::Int(i) => write!(f, "{i}"),
+            Literal::Long(l) => write!(f, "{l}L"),
+            Literal::Float(v) => write!(f, "{v:?}f"),
+            Literal::Double(d) => write!(f, "{d:?}"),
+            Literal::String(s) => write!(f, "{s:?}"),
+        }
+    }
+}
+
+/// An operator with a single operand
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnaryOp {
+    /// `!`
+    Not,
+    /// `-`
+    Negate,
+}
+
+impl Display for UnaryOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            UnaryOp::Not => "!",
+            UnaryOp::Negate => "-",
+        })
+    }
+}
+
+/// An operator with two operands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    /// `||`, which only evaluates its right operand if the left is false
+    Or,
+    /// `&&`, which only evaluates its right operand if the left is true
+    And,
+    /// `==`
+    Equal,
+    /// `!=`
+    NotEqual,
+    /// `<`
+    Less,
+    /// `<=`
+    LessEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterEqual,
+    /// `+`, which concatenates if either operand is a string
+    Add,
+    /// `-`
+    Subtract,
+    /// `*`
+    Multiply,
+    /// `/`
+    Divide,
+    /// `%`
+    Remainder,
+}
+
+impl BinaryOp {
+    /// Gets the operator written as a token, and how tightly it binds
+    pub(crate) fn from_token(token: &str) -> Option<(Self, u8)> {
+        Some(match token {
+            "||" => (BinaryOp::Or, 1),
+            "&&" => (BinaryOp::And, 2),
+            "==" => (BinaryOp::Equal, 3),
+            "!=" => (BinaryOp::NotEqual, 3),
+            "<" => (BinaryOp::Less, 4),
+            "<=" => (BinaryOp::LessEqual, 4),
+            ">" => (BinaryOp::Greater, 4),
+            ">=" => (BinaryOp::GreaterEqual, 4),
+            "+" => (BinaryOp::Add, 5),
+            "-" => (BinaryOp::Subtract, 5),
+            "*" => (BinaryOp::Multiply, 6),
+            "/" => (BinaryOp::Divide, 6),
+            "%" => (BinaryOp::Remainder, 6),
+            _ => return None,
+        })
+    }
+}
+
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BinaryOp::Or => "||",
+            BinaryOp::And => "&&",
+            BinaryOp::Equal => "==",
+            BinaryOp::NotEqual => "!=",
+            BinaryOp::Less => "<",
+            BinaryOp::LessEqual => "<=",
+            BinaryOp::Greater => ">",
+            BinaryOp::GreaterEqual => ">=",
+            BinaryOp::Add => "+",
+            BinaryOp::Subtract => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::Remainder => "%",
+        })
+    }
+}
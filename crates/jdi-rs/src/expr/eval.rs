@@ -0,0 +1,1142 @@
+use crate::core::objects::reference_type::{is_absent_information, signature_name};
+use crate::core::private::upgrade;
+use crate::expr::ast::{BinaryOp, Expression, Literal, TypeName, UnaryOp};
+use crate::expr::invoke::{box_value, invoke, select_method, to_string, unbox};
+use crate::expr::parser::ParseError;
+use crate::expr::types::{
+    convert_primitive, find_class, format_floating, is_assignable, is_primitive,
+    primitive_signature, resolve_type_name, widens, STRING,
+};
+use crate::{
+    Field, LocalVariable, Method, Mirror, ObjectReference, ReferenceType, StackFrame, Value,
+    VirtualMachine,
+};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use jdwp_client::commands::CreateString;
+use jdwp_types::{Int, Tag, TaggedObjectId};
+use std::cmp::Ordering;
+use std::io;
+use std::ops::{Add, Div, Mul, Rem, Sub};
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// An expression couldn't be evaluated
+#[derive(Debug, Error)]
+pub enum EvalError {
+    /// The expression couldn't be parsed
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    /// A name isn't a local variable, or a field of `this` or the frame's class
+    #[error("cannot find {0} in this frame")]
+    UnknownName(String),
+    /// A type has no field with the given name
+    #[error("{type_name} has no field {field}")]
+    NoSuchField { type_name: String, field: String },
+    /// A type has no method with the given name that can be invoked with the arguments
+    #[error("{type_name} has no method {method}")]
+    NoSuchMethod { type_name: String, method: String },
+    /// More than one method with the given name can be invoked with the arguments, and none of
+    /// them is more specific than the others
+    #[error("{method} is ambiguous in {type_name}")]
+    AmbiguousMethod { type_name: String, method: String },
+    /// A field, method or component of `null` was accessed, given the expression that was `null`
+    #[error("{0} is null")]
+    NullPointer(String),
+    /// An operand has the wrong type for its operator
+    #[error("{0}")]
+    Type(String),
+    /// An object was cast to a type it isn't an instance of
+    #[error("{from} cannot be cast to {to}")]
+    ClassCast { from: String, to: String },
+    /// An integer was divided by zero
+    #[error("/ by zero")]
+    DivideByZero,
+    /// An array was indexed outside of its bounds
+    #[error("index {index} out of bounds for length {length}")]
+    IndexOutOfBounds { index: i32, length: i32 },
+    /// An invoked method threw an exception, given its type and message
+    #[error("{exception} was thrown{}", message.as_ref().map(|message| format!(": {message}")).unwrap_or_default())]
+    Thrown {
+        exception: String,
+        message: Option<String>,
+    },
+    /// Communicating with the target VM failed
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// The modifier of a field that can't be assigned
+const FINAL: Int = 0x0010;
+
+/// The result of evaluating part of an expression
+enum Operand<VM: VirtualMachine + ?Sized> {
+    Value(Value<VM>),
+    /// A string that only exists in the debugger, which is only created in the target VM if it
+    /// has to be
+    String(String),
+}
+
+/// The frame an expression is evaluated in. Invoking a method resumes the frame's thread, which
+/// invalidates its frames, so the frame is fetched again at the same depth before it's used after
+/// an invocation.
+pub(crate) struct Context<VM: VirtualMachine + ?Sized> {
+    frame: StackFrame<VM>,
+    current: Mutex<Current<VM>>,
+}
+
+struct Current<VM: VirtualMachine + ?Sized> {
+    frame: StackFrame<VM>,
+    depth: Option<Int>,
+    stale: bool,
+}
+
+impl<VM: VirtualMachine + ?Sized> Context<VM> {
+    pub(crate) fn new(frame: &StackFrame<VM>) -> Self {
+        Self {
+            frame: frame.clone(),
+            current: Mutex::new(Current {
+                frame: frame.clone(),
+                depth: None,
+                stale: false,
+            }),
+        }
+    }
+
+    /// Gets the frame evaluation started in, whose thread and location stay valid, but whose id
+    /// can't be used after a method was invoked
+    pub(super) fn frame(&self) -> &StackFrame<VM> {
+        &self.frame
+    }
+
+    /// Gets a valid frame, fetching it again if a method was invoked since it was last fetched
+    pub(super) async fn current(&self) -> io::Result<StackFrame<VM>> {
+        let depth = {
+            let current = self.current.lock().expect("poisoned");
+            match (current.stale, current.depth) {
+                (true, Some(depth)) => depth,
+                _ => return Ok(current.frame.clone()),
+            }
+        };
+        let frame = self.frame.thread().frame(depth).await?;
+        let mut current = self.current.lock().expect("poisoned");
+        current.frame = frame.clone();
+        current.stale = false;
+        Ok(frame)
+    }
+
+    /// Prepares for a method to be invoked in the frame's thread, by finding the frame's depth
+    /// while it's still valid
+    pub(super) async fn invalidate(&self) -> io::Result<()> {
+        let frame = self.current().await?;
+        if self.current.lock().expect("poisoned").depth.is_none() {
+            let frames = self.frame.thread().frames().await?;
+            let depth = frames
+                .iter()
+                .position(|other| other.id() == frame.id())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        "the frame is no longer on its thread",
+                    )
+                })?;
+            self.current.lock().expect("poisoned").depth = Some(depth as Int);
+        }
+        self.current.lock().expect("poisoned").stale = true;
+        Ok(())
+    }
+}
+
+/// What a name, or a name qualified by other names, refers to
+enum Target<VM: VirtualMachine + ?Sized> {
+    Operand(Operand<VM>),
+    /// A class, whose static fields and methods can be accessed
+    Type(ReferenceType<VM>),
+    /// The start of a fully qualified class name
+    Package(String),
+}
+
+/// Something a value can be assigned to
+enum Variable<VM: VirtualMachine + ?Sized> {
+    Local(LocalVariable<VM>),
+    /// A field, of an object unless it's static
+    Field {
+        ref_type: ReferenceType<VM>,
+        object: Option<ObjectReference<VM>>,
+        field: Field<VM>,
+    },
+    /// A component of an array
+    Component {
+        array: ObjectReference<VM>,
+        index: i32,
+    },
+}
+
+impl<VM: VirtualMachine + ?Sized> Variable<VM> {
+    /// Gets the current value of this variable
+    async fn value(&self, cx: &Context<VM>) -> Result<Value<VM>, EvalError> {
+        match self {
+            Variable::Local(variable) => Ok(cx.current().await?.get_value(variable).await?),
+            Variable::Field {
+                ref_type,
+                object,
+                field,
+            } => read_field(ref_type, object.as_ref(), field).await,
+            Variable::Component { array, index } => {
+                let mut values = array.array_values(*index, 1).await?;
+                Ok(values.remove(0))
+            }
+        }
+    }
+
+    /// Gets the signature of the type of this variable
+    async fn signature(&self) -> io::Result<String> {
+        Ok(match self {
+            Variable::Local(variable) => variable.signature().to_string(),
+            Variable::Field { field, .. } => field.signature().to_string(),
+            Variable::Component { array, .. } => {
+                let ref_type = array.reference_type().await?;
+                ref_type.signature()[1..].to_string()
+            }
+        })
+    }
+
+    /// Sets this variable to a value that already has its type
+    async fn set(&self, cx: &Context<VM>, value: &Value<VM>) -> Result<(), EvalError> {
+        match self {
+            Variable::Local(variable) => cx.current().await?.set_value(variable, value).await?,
+            Variable::Field { field, .. } if field.modifiers() & FINAL != 0 => {
+                return Err(EvalError::Type(format!(
+                    "cannot assign a value to final field {}",
+                    field.name()
+                )))
+            }
+            Variable::Field {
+                object: Some(object),
+                field,
+                ..
+            } if !field.is_static() => object.set_value(field, value).await?,
+            Variable::Field {
+                ref_type, field, ..
+            } => ref_type.set_value(field, value).await?,
+            Variable::Component { array, index } => {
+                array
+                    .set_array_values(*index, std::slice::from_ref(value))
+                    .await?
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Expression {
+    /// Evaluates this expression in a frame, whose thread must be suspended.
+    ///
+    /// A string literal evaluated on its own is created in the target VM, where it may be garbage
+    /// collected at any time. If a method was invoked, the frame must be fetched from its thread
+    /// again before it's used, since invoking a method resumes the thread.
+    pub async fn evaluate<VM: VirtualMachine + ?Sized>(
+        &self,
+        frame: &StackFrame<VM>,
+    ) -> Result<Value<VM>, EvalError> {
+        let cx = Context::new(frame);
+        let operand = self.operand(&cx).await?;
+        materialize(&cx, operand).await
+    }
+
+    /// Assigns the value of another expression to the local variable, field or array component
+    /// this expression refers to in a frame, whose thread must be suspended, and returns the
+    /// assigned value.
+    ///
+    /// The value is converted the way an assignment in Java would, boxing, unboxing and widening
+    /// it, and narrowing an `int` to a `byte`, `short` or `char` that can hold it. Final fields
+    /// can't be assigned.
+    pub async fn assign<VM: VirtualMachine + ?Sized>(
+        &self,
+        frame: &StackFrame<VM>,
+        value: &Expression,
+    ) -> Result<Value<VM>, EvalError> {
+        let cx = Context::new(frame);
+        let variable = self.variable(&cx).await?;
+        let operand = value.operand(&cx).await?;
+        let value = assigned(&cx, operand, &variable.signature().await?).await?;
+        variable.set(&cx, &value).await?;
+        Ok(value)
+    }
+
+    /// Evaluates this expression in a frame, failing unless it's a `boolean`
+    pub async fn evaluate_condition<VM: VirtualMachine + ?Sized>(
+        &self,
+        frame: &StackFrame<VM>,
+    ) -> Result<bool, EvalError> {
+        self.evaluate_condition_in(&Context::new(frame)).await
+    }
+
+    pub(crate) async fn evaluate_condition_in<VM: VirtualMachine + ?Sized>(
+        &self,
+        cx: &Context<VM>,
+    ) -> Result<bool, EvalError> {
+        let operand = unboxed(self.operand(cx).await?).await?;
+        boolean(&operand, "a condition")
+    }
+
+    /// Evaluates this expression in a frame, and formats the result the way string concatenation
+    /// would, except that objects other than strings are described rather than having their
+    /// `toString` method invoked
+    pub async fn evaluate_to_string<VM: VirtualMachine + ?Sized>(
+        &self,
+        frame: &StackFrame<VM>,
+    ) -> Result<String, EvalError> {
+        self.evaluate_to_string_in(&Context::new(frame)).await
+    }
+
+    pub(crate) async fn evaluate_to_string_in<VM: VirtualMachine + ?Sized>(
+        &self,
+        cx: &Context<VM>,
+    ) -> Result<String, EvalError> {
+        match self.operand(cx).await? {
+            Operand::Value(value) => Ok(display_value(&value).await?),
+            Operand::String(string) => Ok(string),
+        }
+    }
+
+    fn operand<'a, VM: VirtualMachine + ?Sized>(
+        &'a self,
+        cx: &'a Context<VM>,
+    ) -> BoxFuture<'a, Result<Operand<VM>, EvalError>> {
+        async move {
+            match self {
+                Expression::Literal(literal) => Ok(literal_operand(literal)),
+                Expression::This => Ok(Operand::Value(Value::Object(
+                    cx.current().await?.this_object().await?,
+                ))),
+                Expression::Name(name) => name_operand(cx, name).await.map(Operand::Value),
+                Expression::Field { .. } => match self.target(cx).await? {
+                    Target::Operand(operand) => Ok(operand),
+                    Target::Type(ref_type) => Err(EvalError::Type(format!(
+                        "{} is a type, not a value",
+                        ref_type.name()
+                    ))),
+                    Target::Package(name) => Err(EvalError::UnknownName(name)),
+                },
+                Expression::Unary { op, operand } => {
+                    let operand = unboxed(operand.operand(cx).await?).await?;
+                    unary(*op, &operand).map(Operand::Value)
+                }
+                Expression::Binary { op, left, right } => {
+                    let left = left.operand(cx).await?;
+                    match op {
+                        BinaryOp::Or | BinaryOp::And => {
+                            let short_circuit = *op == BinaryOp::Or;
+                            let left = unboxed(left).await?;
+                            if boolean(&left, &op.to_string())? == short_circuit {
+                                return Ok(Operand::Value(Value::Boolean(short_circuit)));
+                            }
+                            let right = unboxed(right.operand(cx).await?).await?;
+                            Ok(Operand::Value(Value::Boolean(boolean(
+                                &right,
+                                &op.to_string(),
+                            )?)))
+                        }
+                        _ => {
+                            let right = right.operand(cx).await?;
+                            binary(cx, *op, left, right).await
+                        }
+                    }
+                }
+                Expression::MethodCall {
+                    target,
+                    name,
+                    arguments,
+                } => {
+                    self.method_call(cx, target.as_deref(), name, arguments)
+                        .await
+                }
+                Expression::Index { array, index } => component(cx, array, index)
+                    .await?
+                    .value(cx)
+                    .await
+                    .map(Operand::Value),
+                Expression::Cast { type_name, operand } => {
+                    let operand = operand.operand(cx).await?;
+                    cast(cx, type_name, operand).await
+                }
+                Expression::InstanceOf { operand, type_name } => {
+                    let operand = operand.operand(cx).await?;
+                    instance_of(cx, type_name, &operand)
+                        .await
+                        .map(|is_instance| Operand::Value(Value::Boolean(is_instance)))
+                }
+                Expression::Conditional {
+                    condition,
+                    then,
+                    otherwise,
+                } => {
+                    let condition = unboxed(condition.operand(cx).await?).await?;
+                    if boolean(&condition, "?:")? {
+                        then.operand(cx).await
+                    } else {
+                        otherwise.operand(cx).await
+                    }
+                }
+            }
+        }
+        .boxed()
+    }
+
+    /// Evaluates a name, or a name qualified by other names, which can also refer to a class or
+    /// a package
+    fn target<'a, VM: VirtualMachine + ?Sized>(
+        &'a self,
+        cx: &'a Context<VM>,
+    ) -> BoxFuture<'a, Result<Target<VM>, EvalError>> {
+        async move {
+            match self {
+                Expression::Name(name) => match name_operand(cx, name).await {
+                    Ok(value) => Ok(Target::Operand(Operand::Value(value))),
+                    Err(EvalError::UnknownName(_)) => match find_class(cx.frame(), name).await? {
+                        Some(class) => Ok(Target::Type(class)),
+                        None => Ok(Target::Package(name.clone())),
+                    },
+                    Err(e) => Err(e),
+                },
+                Expression::Field { target, name } => match target.target(cx).await? {
+                    Target::Package(package) => {
+                        let qualified = format!("{package}.{name}");
+                        match find_class(cx.frame(), &qualified).await? {
+                            Some(class) => Ok(Target::Type(class)),
+                            None => Ok(Target::Package(qualified)),
+                        }
+                    }
+                    Target::Type(ref_type) => {
+                        if let Some(field) = ref_type.visible_field_by_name(name).await? {
+                            return read_field(&ref_type, None, &field)
+                                .await
+                                .map(|value| Target::Operand(Operand::Value(value)));
+                        }
+                        let nested = format!("{}.{name}", ref_type.name());
+                        match find_class(cx.frame(), &nested).await? {
+                            Some(class) => Ok(Target::Type(class)),
+                            None => Err(EvalError::NoSuchField {
+                                type_name: ref_type.name(),
+                                field: name.clone(),
+                            }),
+                        }
+                    }
+                    Target::Operand(operand) => field_operand(cx, target, operand, name)
+                        .await
+                        .map(Target::Operand),
+                },
+                _ => self.operand(cx).await.map(Target::Operand),
+            }
+        }
+        .boxed()
+    }
+
+    /// Finds the variable this expression refers to, which must be a name, a field access or an
+    /// array access
+    async fn variable<VM: VirtualMachine + ?Sized>(
+        &self,
+        cx: &Context<VM>,
+    ) -> Result<Variable<VM>, EvalError> {
+        match self {
+            Expression::Name(name) => name_variable(cx, name).await,
+            Expression::Field { target, name } => match target.target(cx).await? {
+                Target::Operand(operand) => instance_field(cx, target, operand, name).await,
+                Target::Type(ref_type) => match ref_type.visible_field_by_name(name).await? {
+                    Some(field) if field.is_static() => Ok(Variable::Field {
+                        ref_type,
+                        object: None,
+                        field,
+                    }),
+                    Some(field) => Err(EvalError::Type(format!(
+                        "cannot access instance field {} without an object",
+                        field.name()
+                    ))),
+                    None => Err(EvalError::NoSuchField {
+                        type_name: ref_type.name(),
+                        field: name.clone(),
+                    }),
+                },
+                Target::Package(package) => {
+                    Err(EvalError::UnknownName(format!("{package}.{name}")))
+                }
+            },
+            Expression::Index { array, index } => component(cx, array, index).await,
+            other => Err(EvalError::Type(format!("cannot assign a value to {other}"))),
+        }
+    }
+
+    async fn method_call<VM: VirtualMachine + ?Sized>(
+        &self,
+        cx: &Context<VM>,
+        target: Option<&Expression>,
+        name: &str,
+        arguments: &[Expression],
+    ) -> Result<Operand<VM>, EvalError> {
+        // methods without a target are looked up in the frame's class, and invoked on `this`
+        let (object, ref_type) = match target {
+            None => (
+                cx.current().await?.this_object().await?,
+                cx.frame().location().declaring_type().await?,
+            ),
+            Some(target) => match target.target(cx).await? {
+                Target::Type(ref_type) => (None, ref_type),
+                Target::Package(package) => return Err(EvalError::UnknownName(package)),
+                Target::Operand(operand) => match materialize(cx, operand).await? {
+                    Value::Object(Some(object)) => {
+                        let ref_type = object.reference_type().await?;
+                        (Some(object), ref_type)
+                    }
+                    Value::Object(None) => return Err(EvalError::NullPointer(target.to_string())),
+                    other => {
+                        return Err(EvalError::Type(format!(
+                            "cannot invoke {name} on {}",
+                            type_name(&Operand::Value(other))
+                        )))
+                    }
+                },
+            },
+        };
+        let mut values = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            let operand = argument.operand(cx).await?;
+            values.push(materialize(cx, operand).await?);
+        }
+        let candidates = ref_type
+            .visible_methods()
+            .await?
+            .into_iter()
+            .filter(|method| method.name() == name)
+            .collect::<Vec<Method<VM>>>();
+        let method = select_method(cx.frame(), &ref_type.name(), name, candidates, &values).await?;
+        if object.is_none() && !method.is_static() {
+            return Err(EvalError::Type(format!(
+                "cannot invoke instance method {name} without an object"
+            )));
+        }
+        invoke(cx, object.as_ref(), &method, values)
+            .await
+            .map(Operand::Value)
+    }
+}
+
+fn literal_operand<VM: VirtualMachine + ?Sized>(literal: &Literal) -> Operand<VM> {
+    Operand::Value(match literal {
+        Literal::Null => Value::Object(None),
+        Literal::Boolean(b) => Value::Boolean(*b),
+        Literal::Char(c) => Value::Char(*c),
+        Literal::Int(i) => Value::Int(*i),
+        Literal::Long(l) => Value::Long(*l),
+        Literal::Float(f) => Value::Float(*f),
+        Literal::Double(d) => Value::Double(*d),
+        Literal::String(s) => return Operand::String(s.clone()),
+    })
+}
+
+/// Looks a name up as a local variable, then a field of `this`, then a static field of the
+/// frame's class, and gets its value
+async fn name_operand<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    name: &str,
+) -> Result<Value<VM>, EvalError> {
+    name_variable(cx, name).await?.value(cx).await
+}
+
+/// Looks a name up as a local variable, then a field of `this`, then a static field of the
+/// frame's class
+async fn name_variable<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    name: &str,
+) -> Result<Variable<VM>, EvalError> {
+    let frame = cx.current().await?;
+    match frame.visible_variable_by_name(name).await {
+        Ok(Some(variable)) => return Ok(Variable::Local(variable)),
+        Ok(None) => {}
+        Err(e) if is_absent_information(&e) => {}
+        Err(e) => return Err(e.into()),
+    }
+    if let Some(this) = frame.this_object().await? {
+        let ref_type = this.reference_type().await?;
+        if let Some(field) = ref_type.visible_field_by_name(name).await? {
+            return Ok(Variable::Field {
+                ref_type,
+                object: Some(this),
+                field,
+            });
+        }
+    }
+    let ref_type = cx.frame().location().declaring_type().await?;
+    match ref_type.visible_field_by_name(name).await? {
+        Some(field) if field.is_static() => Ok(Variable::Field {
+            ref_type,
+            object: None,
+            field,
+        }),
+        _ => Err(EvalError::UnknownName(name.to_string())),
+    }
+}
+
+/// Reads a field of the object an operand evaluates to, or the length of an array
+async fn field_operand<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    target: &Expression,
+    operand: Operand<VM>,
+    name: &str,
+) -> Result<Operand<VM>, EvalError> {
+    let object = object_operand(cx, target, operand, &format!("access field {name} of")).await?;
+    if object.tag() == Tag::Array && name == "length" {
+        return Ok(Operand::Value(Value::Int(object.array_length().await?)));
+    }
+    let ref_type = object.reference_type().await?;
+    match ref_type.visible_field_by_name(name).await? {
+        Some(field) => read_field(&ref_type, Some(&object), &field)
+            .await
+            .map(Operand::Value),
+        None => Err(EvalError::NoSuchField {
+            type_name: ref_type.name(),
+            field: name.to_string(),
+        }),
+    }
+}
+
+/// Finds a field of the object an operand evaluates to
+async fn instance_field<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    target: &Expression,
+    operand: Operand<VM>,
+    name: &str,
+) -> Result<Variable<VM>, EvalError> {
+    let object = object_operand(cx, target, operand, &format!("access field {name} of")).await?;
+    let ref_type = object.reference_type().await?;
+    match ref_type.visible_field_by_name(name).await? {
+        Some(field) => Ok(Variable::Field {
+            ref_type,
+            object: Some(object),
+            field,
+        }),
+        None => Err(EvalError::NoSuchField {
+            type_name: ref_type.name(),
+            field: name.to_string(),
+        }),
+    }
+}
+
+/// Gets the object an operand evaluates to, failing if it's `null` or a primitive that can't be
+/// used the way described
+async fn object_operand<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    target: &Expression,
+    operand: Operand<VM>,
+    usage: &str,
+) -> Result<ObjectReference<VM>, EvalError> {
+    match materialize(cx, operand).await? {
+        Value::Object(Some(object)) => Ok(object),
+        Value::Object(None) => Err(EvalError::NullPointer(target.to_string())),
+        other => Err(EvalError::Type(format!(
+            "cannot {usage} {}",
+            type_name(&Operand::Value(other))
+        ))),
+    }
+}
+
+/// Finds a component of an array, checking that the index is within its bounds
+async fn component<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    array: &Expression,
+    index: &Expression,
+) -> Result<Variable<VM>, EvalError> {
+    let object = match array.operand(cx).await? {
+        Operand::Value(Value::Object(Some(object))) if object.tag() == Tag::Array => object,
+        Operand::Value(Value::Object(None)) => {
+            return Err(EvalError::NullPointer(array.to_string()))
+        }
+        other => {
+            return Err(EvalError::Type(format!(
+                "cannot index {}",
+                type_name(&other)
+            )))
+        }
+    };
+    let index = match unboxed(index.operand(cx).await?).await? {
+        Operand::Value(Value::Byte(b)) => b as i8 as i32,
+        Operand::Value(Value::Char(c)) => c as i32,
+        Operand::Value(Value::Short(s)) => s as i32,
+        Operand::Value(Value::Int(i)) => i,
+        other => {
+            return Err(EvalError::Type(format!(
+                "an array index needs an int, found {}",
+                type_name(&other)
+            )))
+        }
+    };
+    let length = object.array_length().await?;
+    if !(0..length).contains(&index) {
+        return Err(EvalError::IndexOutOfBounds { index, length });
+    }
+    Ok(Variable::Component {
+        array: object,
+        index,
+    })
+}
+
+/// Converts an operand to the type of the variable it's assigned to, given by its signature
+async fn assigned<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    operand: Operand<VM>,
+    signature: &str,
+) -> Result<Value<VM>, EvalError> {
+    let mismatch = |operand: &Operand<VM>| {
+        EvalError::Type(format!(
+            "cannot assign {} to {}",
+            type_name(operand),
+            signature_name(signature)
+        ))
+    };
+    if is_primitive(signature) {
+        let to = signature.as_bytes()[0] as char;
+        let operand = unboxed(operand).await?;
+        if let Operand::Value(value) = &operand {
+            if let Some(from) = primitive_signature(value) {
+                let converted = convert_primitive(value, to);
+                // an int can be narrowed if the narrower type holds the same value
+                let narrowed = from == 'I'
+                    && matches!(to, 'B' | 'S' | 'C')
+                    && converted
+                        .as_ref()
+                        .and_then(|value| convert_primitive(value, 'I'))
+                        == Some(value.clone());
+                if widens(from, to) || narrowed {
+                    return converted.ok_or_else(|| mismatch(&operand));
+                }
+            }
+        }
+        return Err(mismatch(&operand));
+    }
+    let value = match materialize(cx, operand).await? {
+        value @ Value::Object(None) => return Ok(value),
+        value if primitive_signature(&value).is_some() => box_value(cx, &value).await?,
+        value => value,
+    };
+    match &value {
+        Value::Object(Some(object))
+            if is_assignable(&object.reference_type().await?, signature).await? =>
+        {
+            Ok(value)
+        }
+        _ => Err(mismatch(&Operand::Value(value))),
+    }
+}
+
+/// Gets the value of an operand, creating it in the target VM if it's a string
+async fn materialize<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    operand: Operand<VM>,
+) -> Result<Value<VM>, EvalError> {
+    match operand {
+        Operand::Value(value) => Ok(value),
+        Operand::String(string) => {
+            let vm = upgrade(&cx.frame().virtual_machine())?;
+            let reply = vm.client().send(CreateString { utf: string }).await?;
+            Ok(Value::Object(Some(ObjectReference::from_tagged(
+                TaggedObjectId::from(reply.string_object),
+                &cx.frame().virtual_machine(),
+            ))))
+        }
+    }
+}
+
+/// Unboxes an operand if it's a box, such as a `java.lang.Integer`
+async fn unboxed<VM: VirtualMachine + ?Sized>(
+    operand: Operand<VM>,
+) -> Result<Operand<VM>, EvalError> {
+    if let Operand::Value(Value::Object(Some(object))) = &operand {
+        if let Some(value) = unbox(object).await? {
+            return Ok(Operand::Value(value));
+        }
+    }
+    Ok(operand)
+}
+
+/// Whether an operand is a string, either in the debugger or in the target VM
+fn is_string<VM: VirtualMachine + ?Sized>(operand: &Operand<VM>) -> bool {
+    match operand {
+        Operand::String(_) => true,
+        Operand::Value(Value::Object(Some(object))) => object.tag() == Tag::String,
+        _ => false,
+    }
+}
+
+/// Converts an operand to a string the way string concatenation does, invoking `toString` on
+/// objects other than strings
+async fn concatenated<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    operand: Operand<VM>,
+) -> Result<String, EvalError> {
+    match operand {
+        Operand::String(string) => Ok(string),
+        Operand::Value(Value::Object(Some(object))) if object.tag() != Tag::String => {
+            to_string(cx, &object).await
+        }
+        Operand::Value(value) => Ok(display_value(&value).await?),
+    }
+}
+
+async fn cast<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    type_name: &TypeName,
+    operand: Operand<VM>,
+) -> Result<Operand<VM>, EvalError> {
+    if type_name.is_primitive() {
+        let signature = TypeName::primitive_signature(&type_name.name).unwrap_or("V");
+        let operand = unboxed(operand).await?;
+        let converted = match &operand {
+            Operand::Value(value) => convert_primitive(value, signature.as_bytes()[0] as char),
+            Operand::String(_) => None,
+        };
+        return converted.map(Operand::Value).ok_or_else(|| {
+            EvalError::Type(format!(
+                "cannot cast {} to {type_name}",
+                self::type_name(&operand)
+            ))
+        });
+    }
+    let operand = match operand {
+        Operand::Value(value) if primitive_signature(&value).is_some() => {
+            Operand::Value(box_value(cx, &value).await?)
+        }
+        operand => operand,
+    };
+    if matches!(operand, Operand::Value(Value::Object(None)))
+        || instance_of(cx, type_name, &operand).await?
+    {
+        return Ok(operand);
+    }
+    let from = match &operand {
+        Operand::Value(Value::Object(Some(object))) => object.reference_type().await?.name(),
+        other => self::type_name(other),
+    };
+    Err(EvalError::ClassCast {
+        from,
+        to: type_name.to_string(),
+    })
+}
+
+async fn instance_of<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    type_name: &TypeName,
+    operand: &Operand<VM>,
+) -> Result<bool, EvalError> {
+    if type_name.is_primitive() {
+        return Err(EvalError::Type(format!(
+            "{type_name} is not a reference type"
+        )));
+    }
+    let signature = resolve_type_name(cx.frame(), type_name)
+        .await?
+        .ok_or_else(|| EvalError::UnknownName(type_name.to_string()))?;
+    match operand {
+        Operand::String(_) => {
+            let string = ReferenceType::by_signature(STRING, &cx.frame().virtual_machine()).await?;
+            match string.first() {
+                Some(string) => Ok(is_assignable(string, &signature).await?),
+                None => Ok(false),
+            }
+        }
+        Operand::Value(Value::Object(None)) => Ok(false),
+        Operand::Value(Value::Object(Some(object))) => {
+            let ref_type = object.reference_type().await?;
+            Ok(is_assignable(&ref_type, &signature).await?)
+        }
+        other => Err(EvalError::Type(format!(
+            "instanceof needs a reference, found {}",
+            self::type_name(other)
+        ))),
+    }
+}
+
+async fn read_field<VM: VirtualMachine + ?Sized>(
+    ref_type: &ReferenceType<VM>,
+    object: Option<&ObjectReference<VM>>,
+    field: &Field<VM>,
+) -> Result<Value<VM>, EvalError> {
+    match object {
+        Some(object) if !field.is_static() => Ok(object.get_value(field).await?),
+        _ if field.is_static() => Ok(ref_type.get_value(field).await?),
+        _ => Err(EvalError::Type(format!(
+            "cannot access instance field {} without an object",
+            field.name()
+        ))),
+    }
+}
+
+fn type_name<VM: VirtualMachine + ?Sized>(operand: &Operand<VM>) -> String {
+    match operand {
+        Operand::Value(Value::Object(None)) => "null".to_string(),
+        Operand::Value(value) => format!("{:?}", value.tag()).to_lowercase(),
+        Operand::String(_) => "string".to_string(),
+    }
+}
+
+fn boolean<VM: VirtualMachine + ?Sized>(
+    operand: &Operand<VM>,
+    usage: &str,
+) -> Result<bool, EvalError> {
+    match operand {
+        Operand::Value(Value::Boolean(b)) => Ok(*b),
+        other => Err(EvalError::Type(format!(
+            "{usage} needs a boolean, found {}",
+            type_name(other)
+        ))),
+    }
+}
+
+/// A value of a numeric type, after binary numeric promotion
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Integral(i64),
+    Floating(f64),
+}
+
+fn number<VM: VirtualMachine + ?Sized>(operand: &Operand<VM>) -> Option<Number> {
+    let Operand::Value(value) = operand else {
+        return None;
+    };
+    Some(match value {
+        Value::Byte(b) => Number::Integral(*b as i8 as i64),
+        Value::Char(c) => Number::Integral(*c as i64),
+        Value::Short(s) => Number::Integral(*s as i64),
+        Value::Int(i) => Number::Integral(*i as i64),
+        Value::Long(l) => Number::Integral(*l),
+        Value::Float(f) => Number::Floating(*f as f64),
+        Value::Double(d) => Number::Floating(*d),
+        _ => return None,
+    })
+}
+
+impl Number {
+    fn as_i64(self) -> i64 {
+        match self {
+            Number::Integral(i) => i,
+            Number::Floating(f) => f as i64,
+        }
+    }
+
+    fn as_f32(self) -> f32 {
+        match self {
+            Number::Integral(i) => i as f32,
+            Number::Floating(f) => f as f32,
+        }
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Integral(i) => i as f64,
+            Number::Floating(f) => f,
+        }
+    }
+}
+
+fn compare_numbers(left: Number, right: Number) -> Option<Ordering> {
+    match (left, right) {
+        (Number::Integral(l), Number::Integral(r)) => Some(l.cmp(&r)),
+        (Number::Integral(l), Number::Floating(r)) => (l as f64).partial_cmp(&r),
+        (Number::Floating(l), Number::Integral(r)) => l.partial_cmp(&(r as f64)),
+        (Number::Floating(l), Number::Floating(r)) => l.partial_cmp(&r),
+    }
+}
+
+fn unary<VM: VirtualMachine + ?Sized>(
+    op: UnaryOp,
+    operand: &Operand<VM>,
+) -> Result<Value<VM>, EvalError> {
+    match (op, operand) {
+        (UnaryOp::Not, operand) => Ok(Value::Boolean(!boolean(operand, "!")?)),
+        (UnaryOp::Negate, Operand::Value(Value::Long(l))) => Ok(Value::Long(l.wrapping_neg())),
+        (UnaryOp::Negate, Operand::Value(Value::Float(f))) => Ok(Value::Float(-f)),
+        (UnaryOp::Negate, Operand::Value(Value::Double(d))) => Ok(Value::Double(-d)),
+        (UnaryOp::Negate, operand) => match number(operand) {
+            // byte, short, char and int are all promoted to int
+            Some(Number::Integral(i)) => Ok(Value::Int((i as i32).wrapping_neg())),
+            _ => Err(EvalError::Type(format!(
+                "cannot negate {}",
+                type_name(operand)
+            ))),
+        },
+    }
+}
+
+async fn binary<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    op: BinaryOp,
+    left: Operand<VM>,
+    right: Operand<VM>,
+) -> Result<Operand<VM>, EvalError> {
+    if op == BinaryOp::Add && (is_string(&left) || is_string(&right)) {
+        let mut string = concatenated(cx, left).await?;
+        string.push_str(&concatenated(cx, right).await?);
+        return Ok(Operand::String(string));
+    }
+    // boxes are compared by reference, unless they're compared with a primitive
+    let is_primitive = |operand: &Operand<VM>| matches!(operand, Operand::Value(value) if primitive_signature(value).is_some());
+    let (left, right) = match op {
+        BinaryOp::Equal | BinaryOp::NotEqual if !is_primitive(&left) && !is_primitive(&right) => {
+            (left, right)
+        }
+        _ => (unboxed(left).await?, unboxed(right).await?),
+    };
+    let (left, right) = (&left, &right);
+    let incompatible = || {
+        EvalError::Type(format!(
+            "cannot apply {op} to {} and {}",
+            type_name(left),
+            type_name(right)
+        ))
+    };
+    let result = match op {
+        BinaryOp::Equal => equals(left, right).await?.ok_or_else(incompatible)?,
+        BinaryOp::NotEqual => !equals(left, right).await?.ok_or_else(incompatible)?,
+        BinaryOp::Less | BinaryOp::LessEqual | BinaryOp::Greater | BinaryOp::GreaterEqual => {
+            let (Some(l), Some(r)) = (number(left), number(right)) else {
+                return Err(incompatible());
+            };
+            // comparisons with NaN are always false
+            compare_numbers(l, r).is_some_and(|ordering| match op {
+                BinaryOp::Less => ordering.is_lt(),
+                BinaryOp::LessEqual => ordering.is_le(),
+                BinaryOp::Greater => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            })
+        }
+        BinaryOp::Or | BinaryOp::And => {
+            let l = boolean(left, &op.to_string())?;
+            let r = boolean(right, &op.to_string())?;
+            if op == BinaryOp::Or {
+                l || r
+            } else {
+                l && r
+            }
+        }
+        BinaryOp::Add
+        | BinaryOp::Subtract
+        | BinaryOp::Multiply
+        | BinaryOp::Divide
+        | BinaryOp::Remainder => {
+            return arithmetic(op, left, right)?
+                .map(Operand::Value)
+                .ok_or_else(incompatible)
+        }
+    };
+    Ok(Operand::Value(Value::Boolean(result)))
+}
+
+/// Applies an arithmetic operator after binary numeric promotion, or `None` if either operand
+/// isn't a number
+fn arithmetic<VM: VirtualMachine + ?Sized>(
+    op: BinaryOp,
+    left: &Operand<VM>,
+    right: &Operand<VM>,
+) -> Result<Option<Value<VM>>, EvalError> {
+    let (Some(l), Some(r)) = (number(left), number(right)) else {
+        return Ok(None);
+    };
+    let rank = |operand: &Operand<VM>| match operand {
+        Operand::Value(Value::Double(_)) => 3,
+        Operand::Value(Value::Float(_)) => 2,
+        Operand::Value(Value::Long(_)) => 1,
+        _ => 0,
+    };
+    Ok(Some(match rank(left).max(rank(right)) {
+        3 => Value::Double(floating_arithmetic(op, l.as_f64(), r.as_f64())),
+        2 => Value::Float(floating_arithmetic(op, l.as_f32(), r.as_f32())),
+        1 => Value::Long(long_arithmetic(op, l.as_i64(), r.as_i64())?),
+        _ => Value::Int(int_arithmetic(op, l.as_i64() as i32, r.as_i64() as i32)?),
+    }))
+}
+
+fn floating_arithmetic<T>(op: BinaryOp, left: T, right: T) -> T
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Rem<Output = T>,
+{
+    match op {
+        BinaryOp::Add => left + right,
+        BinaryOp::Subtract => left - right,
+        BinaryOp::Multiply => left * right,
+        BinaryOp::Divide => left / right,
+        _ => left % right,
+    }
+}
+
+fn int_arithmetic(op: BinaryOp, left: i32, right: i32) -> Result<i32, EvalError> {
+    Ok(match op {
+        BinaryOp::Add => left.wrapping_add(right),
+        BinaryOp::Subtract => left.wrapping_sub(right),
+        BinaryOp::Multiply => left.wrapping_mul(right),
+        _ if right == 0 => return Err(EvalError::DivideByZero),
+        BinaryOp::Divide => left.wrapping_div(right),
+        _ => left.wrapping_rem(right),
+    })
+}
+
+fn long_arithmetic(op: BinaryOp, left: i64, right: i64) -> Result<i64, EvalError> {
+    Ok(match op {
+        BinaryOp::Add => left.wrapping_add(right),
+        BinaryOp::Subtract => left.wrapping_sub(right),
+        BinaryOp::Multiply => left.wrapping_mul(right),
+        _ if right == 0 => return Err(EvalError::DivideByZero),
+        BinaryOp::Divide => left.wrapping_div(right),
+        _ => left.wrapping_rem(right),
+    })
+}
+
+/// Compares two operands with `==`, or `None` if they can't be compared
+async fn equals<VM: VirtualMachine + ?Sized>(
+    left: &Operand<VM>,
+    right: &Operand<VM>,
+) -> Result<Option<bool>, EvalError> {
+    if let (Some(l), Some(r)) = (number(left), number(right)) {
+        return Ok(Some(compare_numbers(l, r) == Some(Ordering::Equal)));
+    }
+    Ok(match (left, right) {
+        (Operand::String(l), Operand::String(r)) => Some(l == r),
+        (Operand::String(string), Operand::Value(value))
+        | (Operand::Value(value), Operand::String(string)) => match value {
+            Value::Object(None) => Some(false),
+            Value::Object(Some(object)) if object.tag() == Tag::String => {
+                Some(object.string_value().await? == *string)
+            }
+            Value::Object(Some(_)) => Some(false),
+            _ => None,
+        },
+        (Operand::Value(Value::Boolean(l)), Operand::Value(Value::Boolean(r))) => Some(l == r),
+        (Operand::Value(Value::Object(l)), Operand::Value(Value::Object(r))) => Some(l == r),
+        _ => None,
+    })
+}
+
+/// Formats a value the way string concatenation would, describing objects other than strings
+/// rather than invoking their `toString` method
+pub(crate) async fn display_value<VM: VirtualMachine + ?Sized>(
+    value: &Value<VM>,
+) -> io::Result<String> {
+    Ok(match value {
+        Value::Boolean(b) => b.to_string(),
+        Value::Byte(b) => (*b as i8).to_string(),
+        Value::Char(c) => char::from_u32(*c as u32)
+            .unwrap_or(char::REPLACEMENT_CHARACTER)
+            .to_string(),
+        Value::Short(s) => s.to_string(),
+        Value::Int(i) => i.to_string(),
+        Value::Long(l) => l.to_string(),
+        Value::Float(f) => format_floating(*f as f64, format!("{f:?}"), format!("{f:e}")),
+        Value::Double(d) => format_floating(*d, format!("{d:?}"), format!("{d:e}")),
+        Value::Void => "void".to_string(),
+        Value::Object(None) => "null".to_string(),
+        Value::Object(Some(object)) if object.tag() == Tag::String => object.string_value().await?,
+        Value::Object(Some(object)) => format!(
+            "instance of {}(id={})",
+            object.reference_type().await?.name(),
+            object.id().get()
+        ),
+    })
+}
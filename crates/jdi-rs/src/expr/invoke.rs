@@ -0,0 +1,324 @@
+//! Choosing the method an invocation in an expression refers to, and invoking it
+
+use crate::core::objects::reference_type::signature_name;
+use crate::expr::eval::Context;
+use crate::expr::types::{
+    box_signature, convert_primitive, is_assignable, is_primitive, is_signature_assignable,
+    primitive_signature, unbox_signature, widens, OBJECT,
+};
+use crate::expr::EvalError;
+use crate::{
+    InvokeError, Method, Mirror, ObjectReference, ReferenceType, StackFrame, Value, VirtualMachine,
+};
+use jdwp_client::commands::class_type::INVOKE_SINGLE_THREADED;
+use jdwp_types::Tag;
+use std::io;
+
+/// The type of an argument, as far as choosing an overload is concerned
+enum ArgumentType<VM: VirtualMachine + ?Sized> {
+    Primitive(char),
+    Null,
+    Reference(ReferenceType<VM>),
+}
+
+impl<VM: VirtualMachine + ?Sized> ArgumentType<VM> {
+    async fn of(value: &Value<VM>) -> io::Result<Self> {
+        Ok(match value {
+            Value::Object(None) => ArgumentType::Null,
+            Value::Object(Some(object)) => ArgumentType::Reference(object.reference_type().await?),
+            value => ArgumentType::Primitive(primitive_signature(value).unwrap_or('V')),
+        })
+    }
+
+    fn name(&self) -> String {
+        match self {
+            ArgumentType::Primitive(primitive) => signature_name(&primitive.to_string()),
+            ArgumentType::Null => "null".to_string(),
+            ArgumentType::Reference(ref_type) => ref_type.name(),
+        }
+    }
+
+    /// Whether an argument of this type can be passed as a parameter with a given signature,
+    /// optionally boxing or unboxing it
+    async fn is_applicable(
+        &self,
+        parameter: &str,
+        boxing: bool,
+        frame: &StackFrame<VM>,
+    ) -> io::Result<bool> {
+        if is_primitive(parameter) {
+            let to = parameter.as_bytes()[0] as char;
+            return Ok(match self {
+                ArgumentType::Primitive(from) => widens(*from, to),
+                ArgumentType::Reference(ref_type) if boxing => {
+                    unbox_signature(ref_type.signature()).is_some_and(|from| widens(from, to))
+                }
+                _ => false,
+            });
+        }
+        match self {
+            ArgumentType::Null => Ok(true),
+            ArgumentType::Reference(ref_type) => is_assignable(ref_type, parameter).await,
+            ArgumentType::Primitive(primitive) if boxing => {
+                let Some(boxed) = box_signature(*primitive) else {
+                    return Ok(false);
+                };
+                is_signature_assignable(boxed, parameter, frame).await
+            }
+            ArgumentType::Primitive(_) => Ok(false),
+        }
+    }
+}
+
+/// Chooses the method an invocation with the given arguments refers to among the methods with
+/// its name, like the Java compiler would, except that variable arity methods can only be passed
+/// an array.
+///
+/// Methods that only need their arguments to be widened are preferred over ones that need them
+/// boxed or unboxed, and the most specific of those is chosen.
+pub(super) async fn select_method<VM: VirtualMachine + ?Sized>(
+    frame: &StackFrame<VM>,
+    type_name: &str,
+    name: &str,
+    candidates: Vec<Method<VM>>,
+    arguments: &[Value<VM>],
+) -> Result<Method<VM>, EvalError> {
+    let mut argument_types = Vec::with_capacity(arguments.len());
+    for argument in arguments {
+        argument_types.push(ArgumentType::of(argument).await?);
+    }
+    let description = || {
+        let types = argument_types
+            .iter()
+            .map(ArgumentType::name)
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{name}({types})")
+    };
+    let candidates = candidates
+        .into_iter()
+        .filter(|method| method.argument_type_signatures().len() == arguments.len())
+        .collect::<Vec<_>>();
+    for boxing in [false, true] {
+        let mut applicable = vec![];
+        'candidates: for method in &candidates {
+            for (parameter, argument) in method
+                .argument_type_signatures()
+                .iter()
+                .zip(&argument_types)
+            {
+                if !argument.is_applicable(parameter, boxing, frame).await? {
+                    continue 'candidates;
+                }
+            }
+            applicable.push(method);
+        }
+        if applicable.is_empty() {
+            continue;
+        }
+        let mut most_specific = vec![];
+        for method in &applicable {
+            let mut is_most_specific = true;
+            for other in &applicable {
+                if !is_more_specific(method, other, frame).await? {
+                    is_most_specific = false;
+                    break;
+                }
+            }
+            if is_most_specific {
+                most_specific.push(*method);
+            }
+        }
+        return match most_specific.as_slice() {
+            [method] => Ok((*method).clone()),
+            _ => Err(EvalError::AmbiguousMethod {
+                type_name: type_name.to_string(),
+                method: description(),
+            }),
+        };
+    }
+    Err(EvalError::NoSuchMethod {
+        type_name: type_name.to_string(),
+        method: description(),
+    })
+}
+
+/// Whether every parameter of a method could be passed as the corresponding parameter of another
+async fn is_more_specific<VM: VirtualMachine + ?Sized>(
+    method: &Method<VM>,
+    other: &Method<VM>,
+    frame: &StackFrame<VM>,
+) -> io::Result<bool> {
+    for (parameter, other_parameter) in method
+        .argument_type_signatures()
+        .iter()
+        .zip(other.argument_type_signatures())
+    {
+        if !is_signature_assignable(parameter, other_parameter, frame).await? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Invokes a method on an object, or a static method if there's no object, in the frame's
+/// thread. The other threads stay suspended while it runs.
+pub(super) async fn invoke<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    object: Option<&ObjectReference<VM>>,
+    method: &Method<VM>,
+    arguments: Vec<Value<VM>>,
+) -> Result<Value<VM>, EvalError> {
+    let mut converted = Vec::with_capacity(arguments.len());
+    for (parameter, argument) in method.argument_type_signatures().iter().zip(arguments) {
+        converted.push(convert_argument(cx, parameter, argument).await?);
+    }
+    let thread = cx.frame().thread();
+    let declaring_type = ReferenceType::from_id(
+        method.type_tag(),
+        method.declaring_type_id(),
+        &cx.frame().virtual_machine(),
+    )
+    .await?;
+    cx.invalidate().await?;
+    let result = match object {
+        Some(object) if !method.is_static() => {
+            object
+                .invoke_method(thread, method, &converted, INVOKE_SINGLE_THREADED)
+                .await
+        }
+        _ => {
+            declaring_type
+                .invoke_method(thread, method, &converted, INVOKE_SINGLE_THREADED)
+                .await
+        }
+    };
+    returned(result).await
+}
+
+/// Converts the result of an invocation, describing the exception it threw if it didn't return
+async fn returned<VM: VirtualMachine + ?Sized>(
+    result: Result<Value<VM>, InvokeError<VM>>,
+) -> Result<Value<VM>, EvalError> {
+    match result {
+        Ok(value) => Ok(value),
+        Err(InvokeError::Io(e)) => Err(e.into()),
+        Err(InvokeError::Exception(exception)) => Err(thrown(&exception).await?),
+    }
+}
+
+/// Converts an argument to the exact type of its parameter, which jdwp requires
+async fn convert_argument<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    parameter: &str,
+    argument: Value<VM>,
+) -> Result<Value<VM>, EvalError> {
+    if is_primitive(parameter) {
+        let argument = match argument {
+            Value::Object(Some(object)) => unbox(&object).await?.ok_or_else(|| {
+                EvalError::Type(format!(
+                    "cannot pass an object as {}",
+                    signature_name(parameter)
+                ))
+            })?,
+            argument => argument,
+        };
+        let primitive = parameter.as_bytes()[0] as char;
+        return convert_primitive(&argument, primitive).ok_or_else(|| {
+            EvalError::Type(format!(
+                "cannot pass {:?} as {}",
+                argument.tag(),
+                signature_name(parameter)
+            ))
+        });
+    }
+    match primitive_signature(&argument) {
+        Some(_) => box_value(cx, &argument).await,
+        None => Ok(argument),
+    }
+}
+
+/// Boxes a primitive value, by invoking the `valueOf` method of its box class
+pub(super) async fn box_value<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    value: &Value<VM>,
+) -> Result<Value<VM>, EvalError> {
+    let Some(primitive) = primitive_signature(value) else {
+        return Ok(value.clone());
+    };
+    let boxed = box_signature(primitive).unwrap_or(OBJECT);
+    let class = ReferenceType::by_signature(boxed, &cx.frame().virtual_machine())
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| EvalError::UnknownName(signature_name(boxed)))?;
+    let signature = format!("({primitive}){boxed}");
+    let value_of = class
+        .methods()
+        .await?
+        .into_iter()
+        .find(|method| method.name() == "valueOf" && method.signature() == signature)
+        .ok_or_else(|| EvalError::NoSuchMethod {
+            type_name: class.name(),
+            method: format!("valueOf({})", signature_name(&primitive.to_string())),
+        })?;
+    cx.invalidate().await?;
+    let result = class
+        .invoke_method(
+            cx.frame().thread(),
+            &value_of,
+            std::slice::from_ref(value),
+            INVOKE_SINGLE_THREADED,
+        )
+        .await;
+    returned(result).await
+}
+
+/// Gets the primitive value held by a box, such as a `java.lang.Integer`, or `None` for other
+/// objects
+pub(super) async fn unbox<VM: VirtualMachine + ?Sized>(
+    object: &ObjectReference<VM>,
+) -> io::Result<Option<Value<VM>>> {
+    if object.tag() != Tag::Object {
+        return Ok(None);
+    }
+    let ref_type = object.reference_type().await?;
+    if unbox_signature(ref_type.signature()).is_none() {
+        return Ok(None);
+    }
+    match ref_type.field_by_name("value").await? {
+        Some(field) => Ok(Some(object.get_value(&field).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Invokes `toString` on an object
+pub(super) async fn to_string<VM: VirtualMachine + ?Sized>(
+    cx: &Context<VM>,
+    object: &ObjectReference<VM>,
+) -> Result<String, EvalError> {
+    let ref_type = object.reference_type().await?;
+    let method = ref_type
+        .visible_methods()
+        .await?
+        .into_iter()
+        .find(|method| method.name() == "toString" && method.signature() == "()Ljava/lang/String;")
+        .ok_or_else(|| EvalError::NoSuchMethod {
+            type_name: ref_type.name(),
+            method: "toString()".to_string(),
+        })?;
+    match invoke(cx, Some(object), &method, vec![]).await? {
+        Value::Object(Some(string)) => Ok(string.string_value().await?),
+        _ => Ok("null".to_string()),
+    }
+}
+
+/// Describes an exception thrown by an invoked method, with its message
+async fn thrown<VM: VirtualMachine + ?Sized>(
+    exception: &ObjectReference<VM>,
+) -> io::Result<EvalError> {
+    Ok(EvalError::Thrown {
+        exception: exception.reference_type().await?.name(),
+        message: exception.detail_message().await?,
+    })
+}
@@ -0,0 +1,590 @@
+use crate::expr::ast::{BinaryOp, Expression, Literal, TypeName, UnaryOp};
+use thiserror::Error;
+
+/// An expression couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{message} at position {position}")]
+pub struct ParseError {
+    message: String,
+    position: usize,
+}
+
+impl ParseError {
+    pub(crate) fn new(message: impl Into<String>, position: usize) -> Self {
+        Self {
+            message: message.into(),
+            position,
+        }
+    }
+
+    /// Gets what went wrong
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Gets the byte offset into the source where it went wrong
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    Literal(Literal),
+    Punct(&'static str),
+    End,
+}
+
+/// Punctuation, longest first so that `<=` isn't read as `<` and `=`
+const PUNCTUATION: &[&str] = &[
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "(", ")", ".", "-", "+", "*", "/", "%", "[",
+    "]", ",", "?", ":",
+];
+
+/// Splits an expression into tokens, each with the byte offset it starts at
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = vec![];
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            let mut identifier = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_' || c == '$') {
+                    break;
+                }
+                identifier.push(c);
+                chars.next();
+            }
+            tokens.push((Token::Identifier(identifier), start));
+        } else if c.is_ascii_digit() {
+            let mut number = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '.' || c == '_') {
+                    break;
+                }
+                number.push(c);
+                chars.next();
+            }
+            tokens.push((Token::Literal(number_literal(&number, start)?), start));
+        } else if c == '"' {
+            chars.next();
+            let mut string = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((at, '\\')) => string.push(escape(chars.next(), at)?),
+                    Some((_, c)) => string.push(c),
+                    None => return Err(ParseError::new("unterminated string", start)),
+                }
+            }
+            tokens.push((Token::Literal(Literal::String(string)), start));
+        } else if c == '\'' {
+            chars.next();
+            let c = match chars.next() {
+                Some((at, '\\')) => escape(chars.next(), at)?,
+                Some((_, '\'')) | None => {
+                    return Err(ParseError::new("empty character literal", start))
+                }
+                Some((_, c)) => c,
+            };
+            if !matches!(chars.next(), Some((_, '\''))) {
+                return Err(ParseError::new("unterminated character literal", start));
+            }
+            let mut units = [0; 2];
+            match c.encode_utf16(&mut units) {
+                [unit] => tokens.push((Token::Literal(Literal::Char(*unit)), start)),
+                _ => {
+                    return Err(ParseError::new(
+                        "character literal doesn't fit in a char",
+                        start,
+                    ))
+                }
+            }
+        } else {
+            let rest = &source[start..];
+            let Some(punct) = PUNCTUATION.iter().find(|punct| rest.starts_with(**punct)) else {
+                return Err(ParseError::new(
+                    format!("unexpected character {c:?}"),
+                    start,
+                ));
+            };
+            for _ in 0..punct.len() {
+                chars.next();
+            }
+            tokens.push((Token::Punct(punct), start));
+        }
+    }
+    tokens.push((Token::End, source.len()));
+    Ok(tokens)
+}
+
+fn escape(escaped: Option<(usize, char)>, at: usize) -> Result<char, ParseError> {
+    Ok(match escaped {
+        Some((_, 'n')) => '\n',
+        Some((_, 't')) => '\t',
+        Some((_, 'r')) => '\r',
+        Some((_, 'b')) => '\u{8}',
+        Some((_, 'f')) => '\u{c}',
+        Some((_, '0')) => '\0',
+        Some((_, c @ ('\\' | '\'' | '"'))) => c,
+        _ => return Err(ParseError::new("invalid escape sequence", at)),
+    })
+}
+
+fn number_literal(number: &str, at: usize) -> Result<Literal, ParseError> {
+    let invalid = || ParseError::new(format!("invalid number {number}"), at);
+    let digits = number.replace('_', "");
+    let is_hex = digits.starts_with("0x") || digits.starts_with("0X");
+    if let Some(long) = digits.strip_suffix(['l', 'L']) {
+        return parse_integer(long, is_hex)
+            .map(Literal::Long)
+            .ok_or_else(invalid);
+    }
+    if !is_hex {
+        if let Some(float) = digits.strip_suffix(['f', 'F']) {
+            return float.parse().map(Literal::Float).map_err(|_| invalid());
+        }
+        let double = digits.strip_suffix(['d', 'D']);
+        if double.is_some() || digits.contains(['.', 'e', 'E']) {
+            return double
+                .unwrap_or(&digits)
+                .parse()
+                .map(Literal::Double)
+                .map_err(|_| invalid());
+        }
+    }
+    // like Java, int literals may be written up to 2^31 so that they can be negated
+    let int = parse_integer(&digits, is_hex).ok_or_else(invalid)?;
+    if is_hex {
+        u32::try_from(int)
+            .map(|int| Literal::Int(int as i32))
+            .map_err(|_| invalid())
+    } else if int == 1 << 31 {
+        Ok(Literal::Int(i32::MIN))
+    } else {
+        i32::try_from(int).map(Literal::Int).map_err(|_| invalid())
+    }
+}
+
+fn parse_integer(digits: &str, is_hex: bool) -> Option<i64> {
+    if is_hex {
+        u64::from_str_radix(&digits[2..], 16).ok().map(|v| v as i64)
+    } else {
+        digits.parse().ok()
+    }
+}
+
+/// How tightly `instanceof` binds, the same as the relational operators
+const INSTANCEOF_PRECEDENCE: u8 = 4;
+
+/// Whether an identifier is reserved, rather than a name
+fn is_keyword(identifier: &str) -> bool {
+    matches!(
+        identifier,
+        "this" | "null" | "true" | "false" | "instanceof"
+    )
+}
+
+/// A recursive descent parser over the tokens of an expression
+pub(crate) struct Parser {
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+}
+
+impl Parser {
+    pub(crate) fn new(source: &str) -> Result<Self, ParseError> {
+        Ok(Self {
+            tokens: tokenize(source)?,
+            next: 0,
+        })
+    }
+
+    /// Parses the whole source as a single expression
+    pub(crate) fn parse_expression(mut self) -> Result<Expression, ParseError> {
+        let expression = self.expression()?;
+        match self.peek() {
+            Token::End => Ok(expression),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.next].0
+    }
+
+    fn position(&self) -> usize {
+        self.tokens[self.next].1
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.next].0.clone();
+        if token != Token::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Token::Punct(p) if *p == punct) {
+            self.next += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), ParseError> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(ParseError::new(
+                format!("expected `{punct}`"),
+                self.position(),
+            ))
+        }
+    }
+
+    fn unexpected(&self) -> ParseError {
+        let message = match self.peek() {
+            Token::End => "unexpected end of expression".to_string(),
+            Token::Identifier(identifier) => format!("unexpected `{identifier}`"),
+            Token::Literal(literal) => format!("unexpected {literal}"),
+            Token::Punct(punct) => format!("unexpected `{punct}`"),
+        };
+        ParseError::new(message, self.position())
+    }
+
+    fn expression(&mut self) -> Result<Expression, ParseError> {
+        let condition = self.binary(1)?;
+        if !self.eat("?") {
+            return Ok(condition);
+        }
+        let then = self.expression()?;
+        self.expect(":")?;
+        let otherwise = self.expression()?;
+        Ok(Expression::Conditional {
+            condition: Box::new(condition),
+            then: Box::new(then),
+            otherwise: Box::new(otherwise),
+        })
+    }
+
+    /// Parses operators binding at least as tightly as `min_precedence`, all left associative
+    fn binary(&mut self, min_precedence: u8) -> Result<Expression, ParseError> {
+        let mut left = self.unary()?;
+        loop {
+            if min_precedence <= INSTANCEOF_PRECEDENCE && self.eat_keyword("instanceof") {
+                left = Expression::InstanceOf {
+                    operand: Box::new(left),
+                    type_name: self.type_name()?,
+                };
+                continue;
+            }
+            let Token::Punct(punct) = self.peek() else {
+                break;
+            };
+            let Some((op, precedence)) = BinaryOp::from_token(punct) else {
+                break;
+            };
+            if precedence < min_precedence {
+                break;
+            }
+            self.advance();
+            let right = self.binary(precedence + 1)?;
+            left = Expression::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expression, ParseError> {
+        let op = if self.eat("!") {
+            UnaryOp::Not
+        } else if self.eat("-") {
+            UnaryOp::Negate
+        } else if let Some(type_name) = self.cast()? {
+            return Ok(Expression::Cast {
+                type_name,
+                operand: Box::new(self.unary()?),
+            });
+        } else {
+            return self.postfix();
+        };
+        Ok(Expression::Unary {
+            op,
+            operand: Box::new(self.unary()?),
+        })
+    }
+
+    /// Parses the type of a cast if one is next, telling it apart from a parenthesized expression
+    /// like Java does: a reference type cast must be followed by something other than `+` or `-`
+    fn cast(&mut self) -> Result<Option<TypeName>, ParseError> {
+        if !matches!(self.peek(), Token::Punct("(")) {
+            return Ok(None);
+        }
+        let start = self.next;
+        self.advance();
+        if let Ok(type_name) = self.type_name() {
+            if self.eat(")") {
+                let operand_follows = match self.peek() {
+                    Token::Identifier(identifier) => identifier != "instanceof",
+                    Token::Literal(_) => true,
+                    Token::Punct(punct) => matches!(*punct, "(" | "!"),
+                    Token::End => false,
+                };
+                if operand_follows
+                    || (type_name.is_primitive() && matches!(self.peek(), Token::Punct("-")))
+                {
+                    return Ok(Some(type_name));
+                }
+            }
+        }
+        self.next = start;
+        Ok(None)
+    }
+
+    /// Parses a possibly qualified type name, followed by any number of `[]`
+    fn type_name(&mut self) -> Result<TypeName, ParseError> {
+        let mut name = self.identifier("expected a type")?;
+        while matches!(self.peek(), Token::Punct(".")) {
+            self.advance();
+            name.push('.');
+            name.push_str(&self.identifier("expected a type")?);
+        }
+        let mut dimensions = 0;
+        while self.eat("[") {
+            self.expect("]")?;
+            dimensions += 1;
+        }
+        Ok(TypeName { name, dimensions })
+    }
+
+    fn identifier(&mut self, expected: &str) -> Result<String, ParseError> {
+        match self.peek().clone() {
+            Token::Identifier(identifier) if !is_keyword(&identifier) => {
+                self.advance();
+                Ok(identifier)
+            }
+            _ => Err(ParseError::new(expected, self.position())),
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Token::Identifier(identifier) if identifier == keyword) {
+            self.next += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Expression>, ParseError> {
+        let mut arguments = vec![];
+        if self.eat(")") {
+            return Ok(arguments);
+        }
+        loop {
+            arguments.push(self.expression()?);
+            if self.eat(")") {
+                return Ok(arguments);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expression, ParseError> {
+        let mut expression = self.primary()?;
+        loop {
+            if self.eat(".") {
+                let name = self.identifier("expected a field or method name")?;
+                expression = if self.eat("(") {
+                    Expression::MethodCall {
+                        target: Some(Box::new(expression)),
+                        name,
+                        arguments: self.arguments()?,
+                    }
+                } else {
+                    Expression::Field {
+                        target: Box::new(expression),
+                        name,
+                    }
+                };
+            } else if self.eat("[") {
+                let index = self.expression()?;
+                self.expect("]")?;
+                expression = Expression::Index {
+                    array: Box::new(expression),
+                    index: Box::new(index),
+                };
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expression, ParseError> {
+        match self.peek().clone() {
+            Token::Literal(literal) => {
+                self.advance();
+                Ok(Expression::Literal(literal))
+            }
+            Token::Identifier(identifier) => {
+                self.advance();
+                Ok(match identifier.as_str() {
+                    "this" => Expression::This,
+                    "null" => Expression::Literal(Literal::Null),
+                    "true" => Expression::Literal(Literal::Boolean(true)),
+                    "false" => Expression::Literal(Literal::Boolean(false)),
+                    _ if is_keyword(&identifier) => {
+                        return Err(ParseError::new(
+                            format!("unexpected `{identifier}`"),
+                            self.tokens[self.next - 1].1,
+                        ))
+                    }
+                    _ if self.eat("(") => Expression::MethodCall {
+                        target: None,
+                        name: identifier,
+                        arguments: self.arguments()?,
+                    },
+                    _ => Expression::Name(identifier),
+                })
+            }
+            Token::Punct("(") => {
+                self.advance();
+                let expression = self.expression()?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(name: &str) -> Box<Expression> {
+        Box::new(Expression::Name(name.to_string()))
+    }
+
+    fn int(value: i32) -> Box<Expression> {
+        Box::new(Expression::Literal(Literal::Int(value)))
+    }
+
+    #[test]
+    fn test_precedence() {
+        let parsed = Expression::parse("a < 1 || b == 2 && !c").unwrap();
+        assert_eq!(
+            parsed,
+            Expression::Binary {
+                op: BinaryOp::Or,
+                left: Box::new(Expression::Binary {
+                    op: BinaryOp::Less,
+                    left: name("a"),
+                    right: int(1),
+                }),
+                right: Box::new(Expression::Binary {
+                    op: BinaryOp::And,
+                    left: Box::new(Expression::Binary {
+                        op: BinaryOp::Equal,
+                        left: name("b"),
+                        right: int(2),
+                    }),
+                    right: Box::new(Expression::Unary {
+                        op: UnaryOp::Not,
+                        operand: name("c"),
+                    }),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_field_access_and_literals() {
+        let parsed = Expression::parse(r#"(this.name == "a \"b\"") != false"#).unwrap();
+        assert_eq!(parsed.to_string(), r#"((this.name == "a \"b\"") != false)"#);
+        assert_eq!(
+            Expression::parse("'x'").unwrap(),
+            Expression::Literal(Literal::Char('x' as u16))
+        );
+        assert_eq!(
+            Expression::parse("0x7fL").unwrap(),
+            Expression::Literal(Literal::Long(0x7f))
+        );
+        assert_eq!(
+            Expression::parse("-2147483648").unwrap(),
+            Expression::Unary {
+                op: UnaryOp::Negate,
+                operand: int(i32::MIN),
+            }
+        );
+        assert_eq!(
+            Expression::parse("1.5").unwrap(),
+            Expression::Literal(Literal::Double(1.5))
+        );
+    }
+
+    #[test]
+    fn test_calls_and_arithmetic() {
+        let parse = |source: &str| Expression::parse(source).unwrap().to_string();
+        assert_eq!(
+            parse("order.getItems().size() > 3"),
+            "(order.getItems().size() > 3)"
+        );
+        assert_eq!(parse("max(a, b[i + 1])"), "max(a, b[(i + 1)])");
+        assert_eq!(parse("a + b * c - d % 2"), "((a + (b * c)) - (d % 2))");
+        assert_eq!(
+            parse("a == b ? c : d ? e : f"),
+            "((a == b) ? c : (d ? e : f))"
+        );
+        assert_eq!(
+            parse("x instanceof java.util.List && y instanceof int[][]"),
+            "((x instanceof java.util.List) && (y instanceof int[][]))"
+        );
+    }
+
+    #[test]
+    fn test_casts() {
+        let parse = |source: &str| Expression::parse(source).unwrap().to_string();
+        assert_eq!(parse("(int) a.b + 1"), "(((int) a.b) + 1)");
+        assert_eq!(parse("(int) -a"), "((int) -a)");
+        assert_eq!(parse("(String) (Object) s"), "((String) ((Object) s))");
+        assert_eq!(parse("(java.util.List) items"), "((java.util.List) items)");
+        // a parenthesized name followed by an operator is not a cast
+        assert_eq!(parse("(a) - b"), "(a - b)");
+        assert_eq!(parse("(a).b"), "a.b");
+        assert_eq!(
+            Expression::parse("(int[]) x").unwrap(),
+            Expression::Cast {
+                type_name: TypeName {
+                    name: "int".to_string(),
+                    dimensions: 1,
+                },
+                operand: name("x"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = Expression::parse("a == ").unwrap_err();
+        assert_eq!(error.position(), 5);
+        assert_eq!(error.message(), "unexpected end of expression");
+        assert_eq!(Expression::parse("a b").unwrap_err().position(), 2);
+        assert_eq!(Expression::parse("\"abc").unwrap_err().position(), 0);
+        assert!(Expression::parse("a.").is_err());
+        assert!(Expression::parse("3000000000").is_err());
+        assert!(Expression::parse("a # b").is_err());
+        assert!(Expression::parse("f(a,)").is_err());
+        assert!(Expression::parse("a ? b").is_err());
+        assert!(Expression::parse("a instanceof").is_err());
+        assert!(Expression::parse("null.instanceof").is_err());
+    }
+}
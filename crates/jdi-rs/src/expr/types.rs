@@ -0,0 +1,247 @@
+//! Looking up types by the names written in expressions, and the conversions between them
+
+use crate::expr::ast::TypeName;
+use crate::{Mirror, ReferenceType, StackFrame, Value, VirtualMachine};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use std::io;
+
+pub(super) const OBJECT: &str = "Ljava/lang/Object;";
+pub(super) const STRING: &str = "Ljava/lang/String;";
+
+/// The primitive types, with the signatures of the classes they're boxed into
+const BOXES: [(char, &str); 8] = [
+    ('Z', "Ljava/lang/Boolean;"),
+    ('B', "Ljava/lang/Byte;"),
+    ('C', "Ljava/lang/Character;"),
+    ('S', "Ljava/lang/Short;"),
+    ('I', "Ljava/lang/Integer;"),
+    ('J', "Ljava/lang/Long;"),
+    ('F', "Ljava/lang/Float;"),
+    ('D', "Ljava/lang/Double;"),
+];
+
+/// Gets the signature of the class a primitive type is boxed into
+pub(super) fn box_signature(primitive: char) -> Option<&'static str> {
+    BOXES
+        .iter()
+        .find(|(p, _)| *p == primitive)
+        .map(|(_, signature)| *signature)
+}
+
+/// Gets the primitive type a class is unboxed into, if it's one of the boxes
+pub(super) fn unbox_signature(signature: &str) -> Option<char> {
+    BOXES
+        .iter()
+        .find(|(_, b)| *b == signature)
+        .map(|(primitive, _)| *primitive)
+}
+
+/// Gets the signature of a primitive value's type, or `None` for references and `void`
+pub(super) fn primitive_signature<VM: VirtualMachine + ?Sized>(value: &Value<VM>) -> Option<char> {
+    Some(match value {
+        Value::Boolean(_) => 'Z',
+        Value::Byte(_) => 'B',
+        Value::Char(_) => 'C',
+        Value::Short(_) => 'S',
+        Value::Int(_) => 'I',
+        Value::Long(_) => 'J',
+        Value::Float(_) => 'F',
+        Value::Double(_) => 'D',
+        Value::Void | Value::Object(_) => return None,
+    })
+}
+
+/// Whether a signature is of a primitive type
+pub(super) fn is_primitive(signature: &str) -> bool {
+    signature.len() == 1 && signature != "V"
+}
+
+/// Whether a primitive type can be converted to another without a cast
+pub(super) fn widens(from: char, to: char) -> bool {
+    from == to
+        || match from {
+            'B' => matches!(to, 'S' | 'I' | 'J' | 'F' | 'D'),
+            'S' | 'C' => matches!(to, 'I' | 'J' | 'F' | 'D'),
+            'I' => matches!(to, 'J' | 'F' | 'D'),
+            'J' => matches!(to, 'F' | 'D'),
+            'F' => to == 'D',
+            _ => false,
+        }
+}
+
+/// Converts a primitive value to another primitive type the way a cast would, or `None` if
+/// booleans and numbers are mixed
+pub(super) fn convert_primitive<VM: VirtualMachine + ?Sized>(
+    value: &Value<VM>,
+    to: char,
+) -> Option<Value<VM>> {
+    if let Value::Boolean(b) = value {
+        return (to == 'Z').then_some(Value::Boolean(*b));
+    }
+    let (integral, floating) = match value {
+        Value::Byte(b) => (*b as i8 as i64, None),
+        Value::Char(c) => (*c as i64, None),
+        Value::Short(s) => (*s as i64, None),
+        Value::Int(i) => (*i as i64, None),
+        Value::Long(l) => (*l, None),
+        Value::Float(f) => (0, Some(*f as f64)),
+        Value::Double(d) => (0, Some(*d)),
+        _ => return None,
+    };
+    // floating point values are narrowed to int before they're narrowed further, and saturate
+    // like they do in Java
+    let int = floating.map_or(integral as i32, |f| f as i32);
+    Some(match to {
+        'B' => Value::Byte(int as i8 as u8),
+        'C' => Value::Char(int as u16),
+        'S' => Value::Short(int as i16),
+        'I' => Value::Int(int),
+        'J' => Value::Long(floating.map_or(integral, |f| f as i64)),
+        'F' => match value {
+            Value::Float(f) => Value::Float(*f),
+            _ => Value::Float(floating.map_or(integral as f32, |f| f as f32)),
+        },
+        'D' => Value::Double(floating.unwrap_or(integral as f64)),
+        _ => return None,
+    })
+}
+
+/// Finds a loaded class by the name it would be referred to in the frame's source, which can be
+/// fully qualified, nested in the frame's class, in the same package or in `java.lang`. Imports
+/// aren't known, so other classes must be fully qualified.
+pub(super) async fn find_class<VM: VirtualMachine + ?Sized>(
+    frame: &StackFrame<VM>,
+    name: &str,
+) -> io::Result<Option<ReferenceType<VM>>> {
+    let mut candidates = vec![];
+    let (first, nested) = match name.split_once('.') {
+        Some((first, rest)) => {
+            // any of the trailing names could be nested classes
+            let mut binary_name = name.replace('.', "/");
+            candidates.push(binary_name.clone());
+            while let Some(last) = binary_name.rfind('/') {
+                binary_name.replace_range(last..=last, "$");
+                candidates.push(binary_name.clone());
+            }
+            (first, format!("${}", rest.replace('.', "$")))
+        }
+        None => (name, String::new()),
+    };
+    let declaring_type = frame.location().declaring_type().await?;
+    let class = declaring_type
+        .signature()
+        .trim_start_matches('L')
+        .trim_end_matches(';');
+    let mut enclosing = Some(class);
+    while let Some(outer) = enclosing {
+        candidates.push(format!("{outer}${first}{nested}"));
+        enclosing = outer.rsplit_once('$').map(|(outer, _)| outer);
+    }
+    match class.rsplit_once('/') {
+        Some((package, _)) => candidates.push(format!("{package}/{first}{nested}")),
+        None => candidates.push(format!("{first}{nested}")),
+    }
+    candidates.push(format!("java/lang/{first}{nested}"));
+    for candidate in candidates {
+        let signature = format!("L{candidate};");
+        let found = ReferenceType::by_signature(&signature, &frame.virtual_machine()).await?;
+        if let Some(class) = found.into_iter().next() {
+            return Ok(Some(class));
+        }
+    }
+    Ok(None)
+}
+
+/// Gets the signature of a type written in a cast or `instanceof`, or `None` if it's a class
+/// that isn't loaded
+pub(super) async fn resolve_type_name<VM: VirtualMachine + ?Sized>(
+    frame: &StackFrame<VM>,
+    type_name: &TypeName,
+) -> io::Result<Option<String>> {
+    let element = match TypeName::primitive_signature(&type_name.name) {
+        Some(primitive) => primitive.to_string(),
+        None => match find_class(frame, &type_name.name).await? {
+            Some(class) => class.signature().to_string(),
+            None => return Ok(None),
+        },
+    };
+    Ok(Some("[".repeat(type_name.dimensions) + &element))
+}
+
+/// Whether a value of a type can be assigned to a variable of the type with a given signature,
+/// which is what `instanceof` and casts check
+pub(super) fn is_assignable<'a, VM: VirtualMachine + ?Sized>(
+    from: &'a ReferenceType<VM>,
+    to: &'a str,
+) -> BoxFuture<'a, io::Result<bool>> {
+    async move {
+        if from.signature() == to || to == OBJECT {
+            return Ok(true);
+        }
+        let Some(from_element) = from.signature().strip_prefix('[') else {
+            let supertypes = from.all_supertypes().await?;
+            return Ok(supertypes
+                .iter()
+                .any(|supertype| supertype.signature() == to));
+        };
+        let Some(to_element) = to.strip_prefix('[') else {
+            return Ok(matches!(
+                to,
+                "Ljava/lang/Cloneable;" | "Ljava/io/Serializable;"
+            ));
+        };
+        if is_primitive(from_element) || is_primitive(to_element) {
+            return Ok(from_element == to_element);
+        }
+        match ReferenceType::by_signature(from_element, &from.virtual_machine())
+            .await?
+            .first()
+        {
+            Some(element) => is_assignable(element, to_element).await,
+            None => Ok(false),
+        }
+    }
+    .boxed()
+}
+
+/// Whether a value of the type with a given signature can be assigned to a variable of another,
+/// either by widening a primitive or because it's a subtype
+pub(super) async fn is_signature_assignable<VM: VirtualMachine + ?Sized>(
+    from: &str,
+    to: &str,
+    frame: &StackFrame<VM>,
+) -> io::Result<bool> {
+    match (is_primitive(from), is_primitive(to)) {
+        (true, true) => return Ok(widens(from.as_bytes()[0] as char, to.as_bytes()[0] as char)),
+        (false, false) => {}
+        _ => return Ok(false),
+    }
+    // a type that isn't loaded can't have any loaded subtypes
+    match ReferenceType::by_signature(from, &frame.virtual_machine())
+        .await?
+        .first()
+    {
+        Some(from) => is_assignable(from, to).await,
+        None => Ok(from == to),
+    }
+}
+
+/// Formats a `double` the way `Double.toString` does, given its shortest decimal and exponential
+/// representations
+pub(super) fn format_floating(value: f64, decimal: String, exponential: String) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else if value == 0.0 || (1e-3..1e7).contains(&value.abs()) {
+        decimal
+    } else {
+        let (mantissa, exponent) = exponential.split_once('e').unwrap_or((&exponential, "0"));
+        if mantissa.contains('.') {
+            format!("{mantissa}E{exponent}")
+        } else {
+            format!("{mantissa}.0E{exponent}")
+        }
+    }
+}
@@ -17,4 +17,8 @@ mod core;
 
 pub mod connect;
 pub mod event;
+pub mod expr;
+pub mod redefine;
 pub mod request;
+pub mod thread_dump;
+pub mod trace;
@@ -0,0 +1,379 @@
+//! Replacing the code of loaded classes with new class files
+//!
+//! Classes are redefined by [VirtualMachine::redefine_classes], given the bytes of their new class
+//! files. A [ClassFileWatcher] watches a directory of compiled classes, such as `target/classes`,
+//! and redefines the loaded classes whose class files change, so that recompiling is enough for
+//! a running application to pick up the changes.
+//!
+//! Threads keep executing the old versions of the methods they're in, and only later calls use
+//! the new versions. Most virtual machines only allow method bodies to change: adding, removing or
+//! changing the signatures of fields and methods is rejected with a [Rejection].
+
+use crate::core::private::upgrade;
+use crate::{ReferenceType, VirtualMachine};
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use jdwp_client::commands::{
+    CapabilitiesNew, CapabilitiesNewReply, ClassDefinition, RedefineClasses,
+};
+use jdwp_client::packet::ReplyError;
+use jdwp_types::ErrorConstant;
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Weak;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+/// How long a directory must go without changes before its changed class files are redefined,
+/// since compilers write many class files one after the other
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// Redefining classes failed
+#[derive(Debug, Error)]
+pub enum RedefineError {
+    /// The target VM lacks a capability redefining classes needs
+    #[error("the target VM can not {0}")]
+    Unsupported(&'static str),
+    /// The target VM rejected the new class files, and none of the classes were redefined
+    #[error(
+        "could not redefine {}: {reason}{}",
+        .classes.join(", "),
+        .missing_capability.map(|capability| format!(" (it lacks `{capability}`)")).unwrap_or_default()
+    )]
+    Rejected {
+        /// The names of the classes that were being redefined
+        classes: Vec<String>,
+        /// Why they were rejected
+        reason: Rejection,
+        /// The capability the target VM lacks to make the rejected change, such as
+        /// `canAddMethod`, if that's why it was rejected
+        missing_capability: Option<&'static str>,
+    },
+    /// The directory of class files couldn't be watched
+    #[error("could not watch for class file changes: {0}")]
+    Watch(#[from] notify::Error),
+    /// Communicating with the target VM failed
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Why the target VM rejected new class files
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rejection {
+    /// A class file is malformed
+    #[error("a class file is malformed")]
+    InvalidClassFormat,
+    /// A class would be its own superclass
+    #[error("a class would be its own superclass")]
+    CircularClassDefinition,
+    /// A class file failed verification
+    #[error("a class file failed verification")]
+    FailsVerification,
+    /// A class file's version isn't supported by the target VM
+    #[error("a class file's version isn't supported by the target VM")]
+    UnsupportedVersion,
+    /// A class file defines a different class than the one being redefined
+    #[error("a class file defines a different class than the one being redefined")]
+    NamesDontMatch,
+    /// A method was added, which needs the `canAddMethod` capability
+    #[error("the target VM can not add methods")]
+    AddMethod,
+    /// A method was removed, which needs the `canUnrestrictedlyRedefineClasses` capability
+    #[error("the target VM can not remove methods")]
+    DeleteMethod,
+    /// Fields were added, removed or changed, which needs the
+    /// `canUnrestrictedlyRedefineClasses` capability
+    #[error("the target VM can not add, remove or change fields")]
+    SchemaChange,
+    /// The superclass or interfaces of a class changed, which needs the
+    /// `canUnrestrictedlyRedefineClasses` capability
+    #[error("the target VM can not change the superclass or interfaces of a class")]
+    HierarchyChange,
+    /// The modifiers of a class changed, which needs the `canUnrestrictedlyRedefineClasses`
+    /// capability
+    #[error("the target VM can not change the modifiers of a class")]
+    ClassModifiersChange,
+    /// The modifiers of a method changed, which needs the `canUnrestrictedlyRedefineClasses`
+    /// capability
+    #[error("the target VM can not change the modifiers of a method")]
+    MethodModifiersChange,
+}
+
+impl Rejection {
+    /// Gets the rejection an error constant sent back by the target VM stands for, if any
+    pub fn from_error_constant(constant: ErrorConstant) -> Option<Self> {
+        Some(match constant {
+            ErrorConstant::InvalidClassFormat => Rejection::InvalidClassFormat,
+            ErrorConstant::CircularClassDefinition => Rejection::CircularClassDefinition,
+            ErrorConstant::FailsVerification => Rejection::FailsVerification,
+            ErrorConstant::UnsupportedVersion => Rejection::UnsupportedVersion,
+            ErrorConstant::NamesDontMatch => Rejection::NamesDontMatch,
+            ErrorConstant::AddMethodNotImplemented => Rejection::AddMethod,
+            ErrorConstant::DeleteMethodNotImplemented => Rejection::DeleteMethod,
+            ErrorConstant::SchemaChangeNotImplemented => Rejection::SchemaChange,
+            ErrorConstant::HierarchyChangeNotImplemented => Rejection::HierarchyChange,
+            ErrorConstant::ClassModifiersChangeNotImplemented => Rejection::ClassModifiersChange,
+            ErrorConstant::MethodModifiersChangeNotImplemented => Rejection::MethodModifiersChange,
+            _ => return None,
+        })
+    }
+
+    /// Gets the capability a target VM with the given capabilities lacks to make the rejected
+    /// change, or `None` if it isn't a change a capability allows or the target VM has it
+    pub fn missing_capability(self, capabilities: &CapabilitiesNewReply) -> Option<&'static str> {
+        match self {
+            Rejection::AddMethod
+                if !capabilities.can_add_method
+                    && !capabilities.can_unrestrictedly_redefine_classes =>
+            {
+                Some("canAddMethod")
+            }
+            Rejection::DeleteMethod
+            | Rejection::SchemaChange
+            | Rejection::HierarchyChange
+            | Rejection::ClassModifiersChange
+            | Rejection::MethodModifiersChange
+                if !capabilities.can_unrestrictedly_redefine_classes =>
+            {
+                Some("canUnrestrictedlyRedefineClasses")
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Redefines classes, given the bytes of their new class files
+pub(crate) async fn redefine_classes<VM: VirtualMachine + ?Sized>(
+    vm: &VM,
+    classes: &[(ReferenceType<VM>, Vec<u8>)],
+) -> Result<(), RedefineError> {
+    let capabilities = vm.client().send(CapabilitiesNew).await?;
+    if !capabilities.can_redefine_classes {
+        return Err(RedefineError::Unsupported("redefine classes"));
+    }
+    let definitions = classes
+        .iter()
+        .map(|(ref_type, class_file)| ClassDefinition {
+            ref_type: ref_type.id(),
+            class_file: class_file.clone(),
+        })
+        .collect();
+    let result = vm
+        .client()
+        .send(RedefineClasses {
+            classes: definitions,
+        })
+        .await;
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let rejection = ReplyError::from_io_error(&e)
+                .and_then(ReplyError::error_constant)
+                .and_then(Rejection::from_error_constant);
+            match rejection {
+                Some(reason) => Err(RedefineError::Rejected {
+                    classes: classes
+                        .iter()
+                        .map(|(ref_type, _)| ref_type.name())
+                        .collect(),
+                    reason,
+                    missing_capability: reason.missing_capability(&capabilities),
+                }),
+                None => Err(e.into()),
+            }
+        }
+    }
+}
+
+/// Classes a [ClassFileWatcher] redefined together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redefinition {
+    classes: Vec<String>,
+    class_files: Vec<PathBuf>,
+}
+
+impl Redefinition {
+    /// Gets the names of the redefined classes
+    pub fn classes(&self) -> &[String] {
+        &self.classes
+    }
+
+    /// Gets the class files the classes were redefined from
+    pub fn class_files(&self) -> &[PathBuf] {
+        &self.class_files
+    }
+}
+
+/// Watches a directory of compiled classes, redefining the loaded classes whose class files
+/// change. It's a [Stream] of the redefinitions made, or of why they failed, and must be polled
+/// for classes to be redefined.
+///
+/// Class files are expected at the path matching their binary name, such as
+/// `com/acme/Foo$Bar.class` for `com.acme.Foo$Bar`. Changed class files of classes that haven't
+/// been loaded yet are ignored, since they'll be loaded from the new class file. Changes made
+/// close together are redefined at once, so that classes depending on each other can change
+/// together.
+pub struct ClassFileWatcher<VM: VirtualMachine + ?Sized> {
+    directory: PathBuf,
+    redefinitions: BoxStream<'static, Result<Redefinition, RedefineError>>,
+    // dropping the watcher stops watching
+    _watcher: RecommendedWatcher,
+    _vm: std::marker::PhantomData<fn() -> VM>,
+}
+
+impl<VM: VirtualMachine + ?Sized> ClassFileWatcher<VM> {
+    /// Starts watching a directory of compiled classes, and its subdirectories
+    pub fn new(vm: &VM, directory: impl AsRef<Path>) -> Result<Self, RedefineError> {
+        let directory = directory.as_ref().canonicalize()?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if is_change(&event.kind) => {
+                    for path in event.paths {
+                        let _ = sender.send(path);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => warn!("could not watch for class file changes: {e}"),
+            })?;
+        watcher.watch(&directory, RecursiveMode::Recursive)?;
+        let state = WatchState {
+            directory: directory.clone(),
+            receiver,
+            vm: vm.virtual_machine(),
+        };
+        let redefinitions = futures::stream::unfold(state, |mut state| async move {
+            let redefinition = state.next().await?;
+            Some((redefinition, state))
+        })
+        .boxed();
+        Ok(Self {
+            directory,
+            redefinitions,
+            _watcher: watcher,
+            _vm: std::marker::PhantomData,
+        })
+    }
+
+    /// Gets the directory being watched
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Stream for ClassFileWatcher<VM> {
+    type Item = Result<Redefinition, RedefineError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.redefinitions.poll_next_unpin(cx)
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Debug for ClassFileWatcher<VM> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClassFileWatcher")
+            .field("directory", &self.directory)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Whether a file system event could have changed the contents of a file
+fn is_change(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::Create(_) => true,
+        EventKind::Modify(modify) => !matches!(modify, ModifyKind::Metadata(_)),
+        _ => false,
+    }
+}
+
+struct WatchState<VM: VirtualMachine + ?Sized> {
+    directory: PathBuf,
+    receiver: mpsc::UnboundedReceiver<PathBuf>,
+    vm: Weak<VM>,
+}
+
+impl<VM: VirtualMachine + ?Sized> WatchState<VM> {
+    /// Waits for class files to change and redefines them, or gets `None` once the watcher or
+    /// the target VM is gone
+    async fn next(&mut self) -> Option<Result<Redefinition, RedefineError>> {
+        loop {
+            let mut changed = BTreeSet::new();
+            changed.insert(self.receiver.recv().await?);
+            while let Ok(Some(path)) = tokio::time::timeout(SETTLE_TIME, self.receiver.recv()).await
+            {
+                changed.insert(path);
+            }
+            let vm = upgrade(&self.vm).ok()?;
+            match self.redefine(vm.as_ref(), changed).await {
+                Ok(Some(redefinition)) => return Some(Ok(redefinition)),
+                Ok(None) => continue,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    /// Redefines the loaded classes among changed files, or gets `None` if none were loaded
+    async fn redefine(
+        &self,
+        vm: &VM,
+        changed: BTreeSet<PathBuf>,
+    ) -> Result<Option<Redefinition>, RedefineError> {
+        let mut classes = vec![];
+        let mut class_files = vec![];
+        for path in changed {
+            let Some(signature) = class_file_signature(&self.directory, &path) else {
+                continue;
+            };
+            let loaded = ReferenceType::by_signature(&signature, &vm.virtual_machine()).await?;
+            if loaded.is_empty() {
+                continue;
+            }
+            let class_file = match tokio::fs::read(&path).await {
+                Ok(class_file) => class_file,
+                // the file was removed again, such as a compiler's temporary file
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            // every class loader that loaded the class gets the new version
+            for ref_type in loaded {
+                classes.push((ref_type, class_file.clone()));
+            }
+            class_files.push(path);
+        }
+        if classes.is_empty() {
+            return Ok(None);
+        }
+        redefine_classes(vm, &classes).await?;
+        let redefinition = Redefinition {
+            classes: classes
+                .iter()
+                .map(|(ref_type, _)| ref_type.name())
+                .collect(),
+            class_files,
+        };
+        debug!("redefined {:?}", redefinition.classes);
+        Ok(Some(redefinition))
+    }
+}
+
+/// Gets the JNI signature of the class a class file within a directory of compiled classes
+/// defines, such as `Lcom/acme/Foo$Bar;` for `com/acme/Foo$Bar.class`
+fn class_file_signature(directory: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(directory).ok()?;
+    if relative.extension()? != "class" {
+        return None;
+    }
+    let mut binary_name = vec![];
+    for component in relative.with_extension("").components() {
+        binary_name.push(component.as_os_str().to_str()?.to_string());
+    }
+    Some(format!("L{};", binary_name.join("/")))
+}
@@ -1 +1,1110 @@
 //! Request that a JDI event be sent under specified conditions
+//!
+//! Event requests are created through the [EventRequestManager] of a [VirtualMachine], and are
+//! created *disabled*. Filters can only be added while a request is disabled, and are sent to
+//! the target VM as `EventRequest.Set` modifiers once [enabled](EventRequest::enable).
+
+use crate::core::private::upgrade;
+use crate::request::breakpoint_options::AppliedOptions;
+use crate::{Field, Location, ObjectReference, ReferenceType, ThreadReference, VirtualMachine};
+use jdwp_client::commands::event_request::{Clear, ClearAllBreakpoints, Modifier, Set};
+use jdwp_client::commands::Resume;
+use jdwp_client::events::{Event as JdwpEvent, Events};
+use jdwp_types::{EventKind, Int, StepDepth, StepSize, SuspendPolicy, ThreadId};
+use std::collections::HashSet;
+use std::fmt::{Debug, Formatter};
+use std::io;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use thiserror::Error;
+use tracing::warn;
+
+pub use breakpoint_options::{BreakpointOptions, HitCondition, LogMessage, LogpointMessage};
+pub use exception_breakpoint::ExceptionBreakpoint;
+pub use line_breakpoint::LineBreakpoint;
+pub use step::{StepOptions, DEFAULT_STEP_EXCLUSIONS};
+
+mod breakpoint_options;
+mod exception_breakpoint;
+mod line_breakpoint;
+mod step;
+mod watchpoint;
+
+/// An error occurred while modifying an event request
+#[derive(Debug, Error)]
+pub enum EventRequestError {
+    /// The request can't be modified while it's enabled
+    #[error("event request can not be modified while it is enabled")]
+    Enabled,
+    /// The request has been deleted
+    #[error("event request has been deleted")]
+    Deleted,
+    /// A step request already exists for the given thread
+    #[error("a step request already exists for thread {0:?}")]
+    DuplicateStep(ThreadId),
+    /// The target VM lacks a capability the request needs
+    #[error("the target VM can not {0}")]
+    Unsupported(&'static str),
+    /// Communicating with the target VM failed
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Manages the creation and deletion of [EventRequest]s for a virtual machine.
+pub struct EventRequestManager<VM: VirtualMachine + ?Sized> {
+    vm: Weak<VM>,
+    requests: Mutex<Vec<EventRequest<VM>>>,
+    line_breakpoints: Mutex<Vec<LineBreakpoint<VM>>>,
+    exception_breakpoints: Mutex<Vec<ExceptionBreakpoint<VM>>>,
+    /// Requests whose events are awaited by whatever created them, rather than removed from an
+    /// event queue
+    awaited: Mutex<HashSet<(EventKind, Int)>>,
+    /// Requests whose events are all handled by whatever created them, which also resumes what
+    /// they suspend
+    handled: Mutex<HashSet<(EventKind, Int)>>,
+    logpoints: tokio::sync::broadcast::Sender<LogpointMessage<VM>>,
+}
+
+impl<VM: VirtualMachine + ?Sized> EventRequestManager<VM> {
+    /// Creates a new event request manager for a virtual machine
+    pub(crate) fn new(vm: &Weak<VM>) -> Self {
+        Self {
+            vm: vm.clone(),
+            requests: Mutex::new(vec![]),
+            line_breakpoints: Mutex::new(vec![]),
+            exception_breakpoints: Mutex::new(vec![]),
+            awaited: Mutex::new(HashSet::new()),
+            handled: Mutex::new(HashSet::new()),
+            logpoints: Self::logpoint_channel(),
+        }
+    }
+
+    fn requests(&self) -> MutexGuard<'_, Vec<EventRequest<VM>>> {
+        self.requests.lock().expect("event requests poisoned")
+    }
+
+    fn line_breakpoint_list(&self) -> MutexGuard<'_, Vec<LineBreakpoint<VM>>> {
+        self.line_breakpoints
+            .lock()
+            .expect("line breakpoints poisoned")
+    }
+
+    fn exception_breakpoint_list(&self) -> MutexGuard<'_, Vec<ExceptionBreakpoint<VM>>> {
+        self.exception_breakpoints
+            .lock()
+            .expect("exception breakpoints poisoned")
+    }
+
+    /// Resumes whatever was suspended by a composite whose events have all been handled
+    /// internally
+    async fn resume_events(
+        &self,
+        policy: SuspendPolicy,
+        thread: &ThreadReference<VM>,
+    ) -> io::Result<()> {
+        match policy {
+            SuspendPolicy::None => {}
+            SuspendPolicy::EventThread => thread.resume().await?,
+            SuspendPolicy::All => {
+                upgrade(&self.vm)?.client().send(Resume).await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn create(&self, kind: EventKind, detail: RequestDetail<VM>) -> EventRequest<VM> {
+        let request = EventRequest::new(kind, detail, &self.vm);
+        self.requests().push(request.clone());
+        request
+    }
+
+    /// Creates a request for a breakpoint at the given location
+    pub fn create_breakpoint_request(&self, location: &Location<VM>) -> BreakpointRequest<VM> {
+        BreakpointRequest(self.create(
+            EventKind::Breakpoint,
+            RequestDetail::Location(location.clone()),
+        ))
+    }
+
+    /// Creates a request for a single step in the given thread. Only one step request may exist
+    /// for a thread at any time.
+    pub fn create_step_request(
+        &self,
+        thread: &ThreadReference<VM>,
+        size: StepSize,
+        depth: StepDepth,
+    ) -> Result<StepRequest<VM>, EventRequestError> {
+        if self
+            .step_requests()
+            .iter()
+            .any(|request| request.thread() == *thread)
+        {
+            return Err(EventRequestError::DuplicateStep(thread.id()));
+        }
+        Ok(StepRequest(self.create(
+            EventKind::SingleStep,
+            RequestDetail::Step {
+                thread: thread.clone(),
+                size,
+                depth,
+            },
+        )))
+    }
+
+    /// Creates a request for notification of thrown exceptions of the given type (or any of its
+    /// subtypes), or of any exception if no type is given.
+    pub fn create_exception_request(
+        &self,
+        ref_type: Option<&ReferenceType<VM>>,
+        notify_caught: bool,
+        notify_uncaught: bool,
+    ) -> ExceptionRequest<VM> {
+        ExceptionRequest(self.create(
+            EventKind::Exception,
+            RequestDetail::Exception {
+                exception: ref_type.cloned(),
+                caught: notify_caught,
+                uncaught: notify_uncaught,
+            },
+        ))
+    }
+
+    /// Creates a request for notification when a class is prepared
+    pub fn create_class_prepare_request(&self) -> ClassPrepareRequest<VM> {
+        ClassPrepareRequest(self.create(EventKind::ClassPrepare, RequestDetail::None))
+    }
+
+    /// Creates a request for notification when a class is unloaded
+    pub fn create_class_unload_request(&self) -> ClassUnloadRequest<VM> {
+        ClassUnloadRequest(self.create(EventKind::ClassUnload, RequestDetail::None))
+    }
+
+    /// Creates a request for notification when a method is invoked
+    pub fn create_method_entry_request(&self) -> MethodEntryRequest<VM> {
+        MethodEntryRequest(self.create(EventKind::MethodEntry, RequestDetail::None))
+    }
+
+    /// Creates a request for notification when a method returns
+    pub fn create_method_exit_request(&self) -> MethodExitRequest<VM> {
+        MethodExitRequest(self.create(EventKind::MethodExit, RequestDetail::None))
+    }
+
+    /// Creates a request for notification when a method returns, whose events include the value
+    /// it returned
+    pub fn create_method_exit_with_return_value_request(&self) -> MethodExitRequest<VM> {
+        MethodExitRequest(self.create(EventKind::MethodExitWithReturnValue, RequestDetail::None))
+    }
+
+    /// Creates a request for notification when the contents of a field are accessed
+    pub fn create_access_watchpoint_request(
+        &self,
+        field: &Field<VM>,
+    ) -> AccessWatchpointRequest<VM> {
+        AccessWatchpointRequest(
+            self.create(EventKind::FieldAccess, RequestDetail::Field(field.clone())),
+        )
+    }
+
+    /// Creates a request for notification when the contents of a field are set
+    pub fn create_modification_watchpoint_request(
+        &self,
+        field: &Field<VM>,
+    ) -> ModificationWatchpointRequest<VM> {
+        ModificationWatchpointRequest(self.create(
+            EventKind::FieldModification,
+            RequestDetail::Field(field.clone()),
+        ))
+    }
+
+    /// Creates a request for notification when a thread starts
+    pub fn create_thread_start_request(&self) -> ThreadStartRequest<VM> {
+        ThreadStartRequest(self.create(EventKind::ThreadStart, RequestDetail::None))
+    }
+
+    /// Creates a request for notification when a thread terminates
+    pub fn create_thread_death_request(&self) -> ThreadDeathRequest<VM> {
+        ThreadDeathRequest(self.create(EventKind::ThreadDeath, RequestDetail::None))
+    }
+
+    /// Creates a request for notification when a thread attempts to enter a monitor already
+    /// acquired by another thread
+    pub fn create_monitor_contended_enter_request(&self) -> MonitorContendedEnterRequest<VM> {
+        MonitorContendedEnterRequest(
+            self.create(EventKind::MonitorContendedEnter, RequestDetail::None),
+        )
+    }
+
+    /// Creates a request for notification when a thread enters a monitor after waiting for it
+    /// to be released by another thread
+    pub fn create_monitor_contended_entered_request(&self) -> MonitorContendedEnteredRequest<VM> {
+        MonitorContendedEnteredRequest(
+            self.create(EventKind::MonitorContendedEntered, RequestDetail::None),
+        )
+    }
+
+    /// Creates a request for notification when a thread is about to wait on a monitor
+    pub fn create_monitor_wait_request(&self) -> MonitorWaitRequest<VM> {
+        MonitorWaitRequest(self.create(EventKind::MonitorWait, RequestDetail::None))
+    }
+
+    /// Creates a request for notification when a thread has finished waiting on a monitor
+    pub fn create_monitor_waited_request(&self) -> MonitorWaitedRequest<VM> {
+        MonitorWaitedRequest(self.create(EventKind::MonitorWaited, RequestDetail::None))
+    }
+
+    /// Creates a request for notification when the target VM terminates
+    pub fn create_vm_death_request(&self) -> VmDeathRequest<VM> {
+        VmDeathRequest(self.create(EventKind::VmDeath, RequestDetail::None))
+    }
+
+    /// Gets every event request that has been created and not yet deleted
+    pub fn event_requests(&self) -> Vec<EventRequest<VM>> {
+        self.requests().clone()
+    }
+
+    /// Gets the live request that was assigned the given request id by the target VM
+    pub fn event_request(&self, kind: EventKind, request_id: Int) -> Option<EventRequest<VM>> {
+        self.requests()
+            .iter()
+            .find(|request| request.kind() == kind && request.request_id() == Some(request_id))
+            .cloned()
+    }
+
+    /// Deletes an event request, disabling it first if necessary
+    pub async fn delete_event_request(
+        &self,
+        request: impl Into<EventRequest<VM>>,
+    ) -> Result<(), EventRequestError> {
+        let request = request.into();
+        if let Some(request_id) = request.request_id() {
+            self.handled().remove(&(request.kind(), request_id));
+        }
+        match request.disable().await {
+            Ok(()) | Err(EventRequestError::Deleted) => {}
+            Err(e) => return Err(e),
+        }
+        request.state().deleted = true;
+        self.requests()
+            .retain(|other| !Arc::ptr_eq(&other.inner, &request.inner));
+        Ok(())
+    }
+
+    /// Deletes every given event request
+    pub async fn delete_event_requests<R: Into<EventRequest<VM>>>(
+        &self,
+        requests: impl IntoIterator<Item = R>,
+    ) -> Result<(), EventRequestError> {
+        for request in requests {
+            self.delete_event_request(request).await?;
+        }
+        Ok(())
+    }
+
+    /// Deletes every breakpoint request, along with every line breakpoint
+    pub async fn delete_all_breakpoints(&self) -> Result<(), EventRequestError> {
+        let line_breakpoints = std::mem::take(&mut *self.line_breakpoint_list());
+        self.delete_event_requests(
+            line_breakpoints
+                .iter()
+                .map(|breakpoint| breakpoint.class_prepare_request().clone()),
+        )
+        .await?;
+        let vm = upgrade(&self.vm)?;
+        vm.client().send(ClearAllBreakpoints).await?;
+        let breakpoints = self.breakpoint_requests();
+        for breakpoint in &breakpoints {
+            let mut state = breakpoint.state();
+            state.request_id = None;
+            state.deleted = true;
+        }
+        self.requests()
+            .retain(|request| request.kind() != EventKind::Breakpoint);
+        Ok(())
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> EventRequestManager<VM> {
+    fn awaited(&self) -> MutexGuard<'_, HashSet<(EventKind, Int)>> {
+        self.awaited.lock().expect("awaited requests poisoned")
+    }
+
+    /// Marks the next event of an enabled request as awaited by whatever created it, so that it's
+    /// never removed from an event queue
+    pub(crate) fn await_event(&self, request: &EventRequest<VM>) {
+        if let Some(request_id) = request.request_id() {
+            self.awaited().insert((request.kind(), request_id));
+        }
+    }
+
+    /// Installs the requests of line and exception breakpoints in the classes prepared by a
+    /// composite, and removes the class prepare events requested for them.
+    ///
+    /// Returns `None` if nothing remains of the composite, in which case whatever it suspended
+    /// has been resumed.
+    pub(crate) async fn prepare_breakpoints(&self, events: Events) -> io::Result<Option<Events>> {
+        let line_breakpoints = self.line_breakpoints();
+        let exception_breakpoints = self.exception_breakpoints();
+        if line_breakpoints.is_empty() && exception_breakpoints.is_empty() {
+            return Ok(Some(events));
+        }
+        let Events { policy, events } = events;
+        let mut remaining = vec![];
+        let mut prepared_thread = None;
+        for event in events {
+            let JdwpEvent::ClassPrepare {
+                request_id,
+                thread,
+                ref_type_tag,
+                type_id,
+                signature,
+                status,
+            } = &event
+            else {
+                remaining.push(event);
+                continue;
+            };
+            let is_preparing = |request: Option<&ClassPrepareRequest<VM>>| {
+                request.is_some_and(|request| request.request_id() == Some(*request_id))
+            };
+            let preparing_lines = line_breakpoints
+                .iter()
+                .filter(|breakpoint| is_preparing(Some(breakpoint.class_prepare_request())))
+                .collect::<Vec<_>>();
+            let preparing_exceptions = exception_breakpoints
+                .iter()
+                .filter(|breakpoint| is_preparing(breakpoint.class_prepare_request()))
+                .collect::<Vec<_>>();
+            if preparing_lines.is_empty() && preparing_exceptions.is_empty() {
+                remaining.push(event);
+                continue;
+            }
+            let ref_type = ReferenceType::new(
+                *ref_type_tag,
+                *type_id,
+                signature.clone(),
+                *status,
+                &self.vm,
+            );
+            for breakpoint in preparing_lines {
+                if let Err(e) = breakpoint.resolve(self, &ref_type).await {
+                    warn!(
+                        "could not set breakpoint {}:{} in {}: {e}",
+                        breakpoint.source_path(),
+                        breakpoint.line(),
+                        ref_type.name()
+                    );
+                }
+            }
+            for breakpoint in preparing_exceptions {
+                if let Err(e) = breakpoint.resolve(self, &ref_type).await {
+                    warn!(
+                        "could not break on exception {} for {}: {e}",
+                        breakpoint.pattern(),
+                        ref_type.name()
+                    );
+                }
+            }
+            prepared_thread = Some(*thread);
+        }
+
+        match prepared_thread {
+            Some(thread) if remaining.is_empty() => {
+                self.resume_events(policy, &ThreadReference::new(thread, &self.vm))
+                    .await?;
+                Ok(None)
+            }
+            _ => Ok(Some(Events {
+                policy,
+                events: remaining,
+            })),
+        }
+    }
+
+    fn handled(&self) -> MutexGuard<'_, HashSet<(EventKind, Int)>> {
+        self.handled.lock().expect("handled requests poisoned")
+    }
+
+    /// Marks every event of an enabled request as handled by whatever created it, so that none
+    /// are removed from an event queue until the request is deleted
+    pub(crate) fn handle_events(&self, request: &EventRequest<VM>) {
+        if let Some(request_id) = request.request_id() {
+            self.handled().insert((request.kind(), request_id));
+        }
+    }
+
+    /// Removes the awaited and handled events from a composite, returning `None` if nothing
+    /// remains of it
+    pub(crate) fn remove_awaited(&self, events: Events) -> Option<Events> {
+        let mut awaited = self.awaited();
+        let handled = self.handled();
+        if awaited.is_empty() && handled.is_empty() {
+            return Some(events);
+        }
+        let Events { policy, events } = events;
+        let remaining = events
+            .into_iter()
+            .filter(|event| match event.request_id() {
+                Some(request_id) => {
+                    let key = (event.kind(), request_id);
+                    !handled.contains(&key) && !awaited.remove(&key)
+                }
+                None => true,
+            })
+            .collect::<Vec<_>>();
+        (!remaining.is_empty()).then_some(Events {
+            policy,
+            events: remaining,
+        })
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Debug for EventRequestManager<VM> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventRequestManager")
+            .field("requests", &*self.requests())
+            .field("line_breakpoints", &*self.line_breakpoint_list())
+            .field("exception_breakpoints", &*self.exception_breakpoint_list())
+            .finish()
+    }
+}
+
+/// Whether a class name matches a restricted regular expression, which is either a whole name or
+/// begins or ends with `*`, like the patterns of class filters
+pub(crate) fn matches_class_pattern(pattern: &str, class_name: &str) -> bool {
+    if let Some(suffix) = pattern.strip_prefix('*') {
+        class_name.ends_with(suffix)
+    } else if let Some(prefix) = pattern.strip_suffix('*') {
+        class_name.starts_with(prefix)
+    } else {
+        class_name == pattern
+    }
+}
+
+/// The data a request was created with, which is always sent as the first modifier
+enum RequestDetail<VM: VirtualMachine + ?Sized> {
+    None,
+    Location(Location<VM>),
+    Step {
+        thread: ThreadReference<VM>,
+        size: StepSize,
+        depth: StepDepth,
+    },
+    Exception {
+        exception: Option<ReferenceType<VM>>,
+        caught: bool,
+        uncaught: bool,
+    },
+    Field(Field<VM>),
+}
+
+impl<VM: VirtualMachine + ?Sized> RequestDetail<VM> {
+    fn modifier(&self) -> Option<Modifier> {
+        match self {
+            RequestDetail::None => None,
+            RequestDetail::Location(location) => Some(Modifier::LocationOnly(location.raw())),
+            RequestDetail::Step {
+                thread,
+                size,
+                depth,
+            } => Some(Modifier::Step {
+                thread: thread.id(),
+                size: *size,
+                depth: *depth,
+            }),
+            RequestDetail::Exception {
+                exception,
+                caught,
+                uncaught,
+            } => Some(Modifier::ExceptionOnly {
+                exception: exception.as_ref().map(|e| e.id()),
+                caught: *caught,
+                uncaught: *uncaught,
+            }),
+            RequestDetail::Field(field) => Some(Modifier::FieldOnly {
+                declaring: field.declaring_type_id(),
+                field: field.id(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct EventRequestState {
+    suspend_policy: SuspendPolicy,
+    filters: Vec<Modifier>,
+    request_id: Option<Int>,
+    deleted: bool,
+    breakpoint_options: Option<Arc<AppliedOptions>>,
+}
+
+struct EventRequestInner<VM: VirtualMachine + ?Sized> {
+    kind: EventKind,
+    detail: RequestDetail<VM>,
+    state: Mutex<EventRequestState>,
+    vm: Weak<VM>,
+}
+
+/// A request for notification of an event in the target VM.
+///
+/// This is a handle, and clones of it refer to the same request. Every typed request, such as
+/// [BreakpointRequest], dereferences to this.
+pub struct EventRequest<VM: VirtualMachine + ?Sized> {
+    inner: Arc<EventRequestInner<VM>>,
+}
+
+impl<VM: VirtualMachine + ?Sized> EventRequest<VM> {
+    fn new(kind: EventKind, detail: RequestDetail<VM>, vm: &Weak<VM>) -> Self {
+        Self {
+            inner: Arc::new(EventRequestInner {
+                kind,
+                detail,
+                state: Mutex::new(EventRequestState {
+                    suspend_policy: SuspendPolicy::All,
+                    filters: vec![],
+                    request_id: None,
+                    deleted: false,
+                    breakpoint_options: None,
+                }),
+                vm: vm.clone(),
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, EventRequestState> {
+        self.inner
+            .state
+            .lock()
+            .expect("event request state poisoned")
+    }
+
+    /// Gets the kind of event this request is for
+    pub fn kind(&self) -> EventKind {
+        self.inner.kind
+    }
+
+    /// Whether this request is currently enabled
+    pub fn is_enabled(&self) -> bool {
+        self.state().request_id.is_some()
+    }
+
+    /// Gets the id assigned to this request by the target VM, if it's enabled
+    pub fn request_id(&self) -> Option<Int> {
+        self.state().request_id
+    }
+
+    /// Gets the threads suspended when this request generates an event
+    pub fn suspend_policy(&self) -> SuspendPolicy {
+        self.state().suspend_policy
+    }
+
+    /// Sets which threads are suspended when this request generates an event. Defaults to
+    /// [SuspendPolicy::All].
+    pub fn set_suspend_policy(&self, policy: SuspendPolicy) -> Result<(), EventRequestError> {
+        let mut state = self.modifiable_state()?;
+        state.suspend_policy = policy;
+        Ok(())
+    }
+
+    /// Limits this request to generating an event only once, after it has occurred `count` times
+    pub fn add_count_filter(&self, count: Int) -> Result<(), EventRequestError> {
+        self.add_filter(Modifier::Count(count))
+    }
+
+    /// Gets every modifier sent to the target VM for this request, in order
+    pub fn modifiers(&self) -> Vec<Modifier> {
+        self.inner
+            .detail
+            .modifier()
+            .into_iter()
+            .chain(self.state().filters.iter().cloned())
+            .collect()
+    }
+
+    pub(crate) fn add_filter(&self, modifier: Modifier) -> Result<(), EventRequestError> {
+        self.modifiable_state()?.filters.push(modifier);
+        Ok(())
+    }
+
+    fn modifiable_state(&self) -> Result<MutexGuard<'_, EventRequestState>, EventRequestError> {
+        let state = self.state();
+        if state.deleted {
+            Err(EventRequestError::Deleted)
+        } else if state.request_id.is_some() {
+            Err(EventRequestError::Enabled)
+        } else {
+            Ok(state)
+        }
+    }
+
+    /// Enables this request, so that the target VM starts generating events for it
+    pub async fn enable(&self) -> Result<(), EventRequestError> {
+        let set = {
+            let state = self.state();
+            if state.deleted {
+                return Err(EventRequestError::Deleted);
+            }
+            if state.request_id.is_some() {
+                return Ok(());
+            }
+            Set {
+                event_kind: self.inner.kind,
+                suspend_policy: state.suspend_policy,
+                modifiers: self
+                    .inner
+                    .detail
+                    .modifier()
+                    .into_iter()
+                    .chain(state.filters.iter().cloned())
+                    .collect(),
+            }
+        };
+        let vm = upgrade(&self.inner.vm)?;
+        let reply = vm.client().send(set).await?;
+        self.state().request_id = Some(reply.request_id);
+        Ok(())
+    }
+
+    /// Disables this request, so that the target VM stops generating events for it
+    pub async fn disable(&self) -> Result<(), EventRequestError> {
+        let request_id = {
+            let state = self.state();
+            if state.deleted {
+                return Err(EventRequestError::Deleted);
+            }
+            match state.request_id {
+                Some(request_id) => request_id,
+                None => return Ok(()),
+            }
+        };
+        let vm = upgrade(&self.inner.vm)?;
+        vm.client()
+            .send(Clear {
+                event_kind: self.inner.kind,
+                request_id,
+            })
+            .await?;
+        self.state().request_id = None;
+        Ok(())
+    }
+
+    /// Enables or disables this request
+    pub async fn set_enabled(&self, enabled: bool) -> Result<(), EventRequestError> {
+        if enabled {
+            self.enable().await
+        } else {
+            self.disable().await
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for EventRequest<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> PartialEq for EventRequest<VM> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Eq for EventRequest<VM> {}
+
+impl<VM: VirtualMachine + ?Sized> AsRef<EventRequest<VM>> for EventRequest<VM> {
+    fn as_ref(&self) -> &EventRequest<VM> {
+        self
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Debug for EventRequest<VM> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventRequest")
+            .field("kind", &self.inner.kind)
+            .field("modifiers", &self.modifiers())
+            .field("state", &*self.state())
+            .finish()
+    }
+}
+
+/// An event request was not of the expected kind
+#[derive(Debug, Error)]
+#[error("expected a {expected:?} request but got a {got:?} request")]
+pub struct WrongRequestKindError {
+    expected: EventKind,
+    got: EventKind,
+}
+
+macro_rules! typed_requests {
+    (
+        $(
+            $(#[$meta:meta])*
+            $name:ident($kind:ident $(| $other_kind:ident)*) => $list:ident
+        );* $(;)?
+    ) => {
+        $(
+            $(#[$meta])*
+            pub struct $name<VM: VirtualMachine + ?Sized>(EventRequest<VM>);
+
+            impl<VM: VirtualMachine + ?Sized> Clone for $name<VM> {
+                fn clone(&self) -> Self {
+                    Self(self.0.clone())
+                }
+            }
+
+            impl<VM: VirtualMachine + ?Sized> PartialEq for $name<VM> {
+                fn eq(&self, other: &Self) -> bool {
+                    self.0 == other.0
+                }
+            }
+
+            impl<VM: VirtualMachine + ?Sized> Eq for $name<VM> {}
+
+            impl<VM: VirtualMachine + ?Sized> Debug for $name<VM> {
+                fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                    f.debug_tuple(stringify!($name)).field(&self.0).finish()
+                }
+            }
+
+            impl<VM: VirtualMachine + ?Sized> Deref for $name<VM> {
+                type Target = EventRequest<VM>;
+
+                fn deref(&self) -> &Self::Target {
+                    &self.0
+                }
+            }
+
+            impl<VM: VirtualMachine + ?Sized> AsRef<EventRequest<VM>> for $name<VM> {
+                fn as_ref(&self) -> &EventRequest<VM> {
+                    &self.0
+                }
+            }
+
+            impl<VM: VirtualMachine + ?Sized> From<$name<VM>> for EventRequest<VM> {
+                fn from(value: $name<VM>) -> Self {
+                    value.0
+                }
+            }
+
+            impl<VM: VirtualMachine + ?Sized> TryFrom<EventRequest<VM>> for $name<VM> {
+                type Error = WrongRequestKindError;
+
+                fn try_from(value: EventRequest<VM>) -> Result<Self, Self::Error> {
+                    if matches!(value.kind(), EventKind::$kind $(| EventKind::$other_kind)*) {
+                        Ok(Self(value))
+                    } else {
+                        Err(WrongRequestKindError {
+                            expected: EventKind::$kind,
+                            got: value.kind(),
+                        })
+                    }
+                }
+            }
+
+            impl<VM: VirtualMachine + ?Sized> EventRequestManager<VM> {
+                #[doc = concat!("Gets every live [", stringify!($name), "]")]
+                pub fn $list(&self) -> Vec<$name<VM>> {
+                    self.requests()
+                        .iter()
+                        .filter(|request| {
+                            matches!(request.kind(), EventKind::$kind $(| EventKind::$other_kind)*)
+                        })
+                        .cloned()
+                        .map($name)
+                        .collect()
+                }
+            }
+        )*
+    };
+}
+
+typed_requests! {
+    /// A request for notification when a thread reaches a location
+    BreakpointRequest(Breakpoint) => breakpoint_requests;
+    /// A request for notification when a step occurs in a thread
+    StepRequest(SingleStep) => step_requests;
+    /// A request for notification when an exception occurs
+    ExceptionRequest(Exception) => exception_requests;
+    /// A request for notification when a class is prepared
+    ClassPrepareRequest(ClassPrepare) => class_prepare_requests;
+    /// A request for notification when a class is unloaded
+    ClassUnloadRequest(ClassUnload) => class_unload_requests;
+    /// A request for notification when a method is invoked
+    MethodEntryRequest(MethodEntry) => method_entry_requests;
+    /// A request for notification when a method returns, optionally with its return value
+    MethodExitRequest(MethodExit | MethodExitWithReturnValue) => method_exit_requests;
+    /// A request for notification when a field is accessed
+    AccessWatchpointRequest(FieldAccess) => access_watchpoint_requests;
+    /// A request for notification when a field is set
+    ModificationWatchpointRequest(FieldModification) => modification_watchpoint_requests;
+    /// A request for notification when a thread starts
+    ThreadStartRequest(ThreadStart) => thread_start_requests;
+    /// A request for notification when a thread terminates
+    ThreadDeathRequest(ThreadDeath) => thread_death_requests;
+    /// A request for notification when a thread attempts to enter a contended monitor
+    MonitorContendedEnterRequest(MonitorContendedEnter) => monitor_contended_enter_requests;
+    /// A request for notification when a thread enters a monitor after contending for it
+    MonitorContendedEnteredRequest(MonitorContendedEntered) => monitor_contended_entered_requests;
+    /// A request for notification when a thread is about to wait on a monitor
+    MonitorWaitRequest(MonitorWait) => monitor_wait_requests;
+    /// A request for notification when a thread has finished waiting on a monitor
+    MonitorWaitedRequest(MonitorWaited) => monitor_waited_requests;
+    /// A request for notification when the target VM terminates
+    VmDeathRequest(VmDeath) => vm_death_requests;
+}
+
+macro_rules! thread_filter {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl<VM: VirtualMachine + ?Sized> $name<VM> {
+                /// Restricts the events generated by this request to those in the given thread
+                pub fn add_thread_filter(
+                    &self,
+                    thread: &ThreadReference<VM>,
+                ) -> Result<(), EventRequestError> {
+                    self.add_filter(Modifier::ThreadOnly(thread.id()))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! class_filter {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl<VM: VirtualMachine + ?Sized> $name<VM> {
+                /// Restricts the events generated by this request to those whose location is in
+                /// the given reference type or any of its subtypes
+                pub fn add_class_filter(
+                    &self,
+                    ref_type: &ReferenceType<VM>,
+                ) -> Result<(), EventRequestError> {
+                    self.add_filter(Modifier::ClassOnly(ref_type.id()))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! class_pattern_filter {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl<VM: VirtualMachine + ?Sized> $name<VM> {
+                /// Restricts the events generated by this request to those for classes whose name
+                /// matches a restricted regular expression. Only simple patterns that begin or end
+                /// with `*` are allowed, such as `java.*` or `*.Foo`.
+                pub fn add_class_pattern_filter(
+                    &self,
+                    pattern: impl Into<String>,
+                ) -> Result<(), EventRequestError> {
+                    self.add_filter(Modifier::ClassMatch(pattern.into()))
+                }
+
+                /// Restricts the events generated by this request to those for classes whose name
+                /// does not match a restricted regular expression
+                pub fn add_class_exclusion_filter(
+                    &self,
+                    pattern: impl Into<String>,
+                ) -> Result<(), EventRequestError> {
+                    self.add_filter(Modifier::ClassExclude(pattern.into()))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! instance_filter {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl<VM: VirtualMachine + ?Sized> $name<VM> {
+                /// Restricts the events generated by this request to those in which the currently
+                /// executing instance is the given object
+                pub fn add_instance_filter(
+                    &self,
+                    instance: &ObjectReference<VM>,
+                ) -> Result<(), EventRequestError> {
+                    self.add_filter(Modifier::InstanceOnly(instance.id()))
+                }
+            }
+        )*
+    };
+}
+
+thread_filter!(
+    BreakpointRequest,
+    ExceptionRequest,
+    MethodEntryRequest,
+    MethodExitRequest,
+    AccessWatchpointRequest,
+    ModificationWatchpointRequest,
+    ThreadStartRequest,
+    ThreadDeathRequest,
+    MonitorContendedEnterRequest,
+    MonitorContendedEnteredRequest,
+    MonitorWaitRequest,
+    MonitorWaitedRequest,
+);
+
+class_filter!(
+    StepRequest,
+    ExceptionRequest,
+    ClassPrepareRequest,
+    MethodEntryRequest,
+    MethodExitRequest,
+    AccessWatchpointRequest,
+    ModificationWatchpointRequest,
+    MonitorContendedEnterRequest,
+    MonitorContendedEnteredRequest,
+    MonitorWaitRequest,
+    MonitorWaitedRequest,
+);
+
+class_pattern_filter!(
+    StepRequest,
+    ExceptionRequest,
+    ClassPrepareRequest,
+    ClassUnloadRequest,
+    MethodEntryRequest,
+    MethodExitRequest,
+    AccessWatchpointRequest,
+    ModificationWatchpointRequest,
+    MonitorContendedEnterRequest,
+    MonitorContendedEnteredRequest,
+    MonitorWaitRequest,
+    MonitorWaitedRequest,
+);
+
+instance_filter!(
+    BreakpointRequest,
+    StepRequest,
+    ExceptionRequest,
+    MethodEntryRequest,
+    MethodExitRequest,
+    AccessWatchpointRequest,
+    ModificationWatchpointRequest,
+    MonitorContendedEnterRequest,
+    MonitorContendedEnteredRequest,
+    MonitorWaitRequest,
+    MonitorWaitedRequest,
+);
+
+impl<VM: VirtualMachine + ?Sized> BreakpointRequest<VM> {
+    /// Gets the location of this breakpoint
+    pub fn location(&self) -> Location<VM> {
+        match &self.inner.detail {
+            RequestDetail::Location(location) => location.clone(),
+            _ => unreachable!("breakpoint requests are always created with a location"),
+        }
+    }
+
+    /// Gets the options deciding what this breakpoint does when it's hit
+    pub fn options(&self) -> BreakpointOptions {
+        self.state()
+            .breakpoint_options
+            .as_ref()
+            .map(|applied| applied.options().clone())
+            .unwrap_or_default()
+    }
+
+    /// Sets the options deciding what this breakpoint does when it's hit, restarting its hit
+    /// count. Unlike filters, options can be changed while the request is enabled.
+    pub fn set_options(&self, options: BreakpointOptions) {
+        self.apply_options(AppliedOptions::new(options));
+    }
+
+    /// Gets the number of times this breakpoint has been hit while its condition was true, if it
+    /// has any options. Hits of a breakpoint without options aren't counted.
+    pub fn hit_count(&self) -> u64 {
+        self.state()
+            .breakpoint_options
+            .as_ref()
+            .map_or(0, |applied| applied.hits())
+    }
+
+    pub(crate) fn apply_options(&self, applied: Option<Arc<AppliedOptions>>) {
+        self.state().breakpoint_options = applied;
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> StepRequest<VM> {
+    /// Gets the thread this step occurs in
+    pub fn thread(&self) -> ThreadReference<VM> {
+        match &self.inner.detail {
+            RequestDetail::Step { thread, .. } => thread.clone(),
+            _ => unreachable!("step requests are always created with a step"),
+        }
+    }
+
+    /// Gets the size of this step
+    pub fn size(&self) -> StepSize {
+        match &self.inner.detail {
+            RequestDetail::Step { size, .. } => *size,
+            _ => unreachable!("step requests are always created with a step"),
+        }
+    }
+
+    /// Gets the depth of this step
+    pub fn depth(&self) -> StepDepth {
+        match &self.inner.detail {
+            RequestDetail::Step { depth, .. } => *depth,
+            _ => unreachable!("step requests are always created with a step"),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> ExceptionRequest<VM> {
+    /// Gets the exception type this request is for, or [None] if it's for any exception
+    pub fn exception(&self) -> Option<ReferenceType<VM>> {
+        match &self.inner.detail {
+            RequestDetail::Exception { exception, .. } => exception.clone(),
+            _ => unreachable!("exception requests are always created with an exception"),
+        }
+    }
+
+    /// Whether caught exceptions are reported
+    pub fn notify_caught(&self) -> bool {
+        match &self.inner.detail {
+            RequestDetail::Exception { caught, .. } => *caught,
+            _ => unreachable!("exception requests are always created with an exception"),
+        }
+    }
+
+    /// Whether uncaught exceptions are reported
+    pub fn notify_uncaught(&self) -> bool {
+        match &self.inner.detail {
+            RequestDetail::Exception { uncaught, .. } => *uncaught,
+            _ => unreachable!("exception requests are always created with an exception"),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> ClassPrepareRequest<VM> {
+    /// Restricts the events generated by this request to the preparation of reference types
+    /// whose source name matches a restricted regular expression, such as `*Foo.java`
+    pub fn add_source_name_filter(
+        &self,
+        pattern: impl Into<String>,
+    ) -> Result<(), EventRequestError> {
+        self.add_filter(Modifier::SourceNameMatch(pattern.into()))
+    }
+}
+
+macro_rules! watchpoint_field {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl<VM: VirtualMachine + ?Sized> $name<VM> {
+                /// Gets the field being watched
+                pub fn field(&self) -> Field<VM> {
+                    match &self.inner.detail {
+                        RequestDetail::Field(field) => field.clone(),
+                        _ => unreachable!("watchpoint requests are always created with a field"),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+watchpoint_field!(AccessWatchpointRequest, ModificationWatchpointRequest);
@@ -0,0 +1,457 @@
+use crate::expr::{Context, Expression, ParseError};
+use crate::request::{BreakpointRequest, EventRequestManager};
+use crate::{Location, StackFrame, ThreadReference, VirtualMachine};
+use jdwp_client::events::{Event as JdwpEvent, Events};
+use jdwp_types::EventKind;
+use std::fmt::{Debug, Display, Formatter};
+use std::io;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// How many messages from logpoints are kept for receivers that fall behind
+const LOGPOINT_CAPACITY: usize = 256;
+
+/// Decides what a breakpoint does when it's hit, beyond what the target VM can filter.
+///
+/// jdwp reserves the `Conditional` modifier without ever implementing it, so conditions are
+/// evaluated by the debugger as breakpoint events are removed from an
+/// [EventQueue](crate::event::EventQueue). Hits that shouldn't stop are removed from their event
+/// set, and whatever the breakpoint suspended is resumed if nothing else remains of the set.
+///
+/// Each hit is handled in order:
+/// 1. The [condition](Self::when) is evaluated, and the hit is ignored if it's false. A
+///    condition that can't be evaluated stops, so that the problem gets noticed.
+/// 2. The hit is counted, and ignored unless the count satisfies the
+///    [hit condition](Self::on_hit).
+/// 3. If there's a [log message](Self::log), it's formatted and sent to
+///    [logpoint_messages](EventRequestManager::logpoint_messages) instead of stopping.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BreakpointOptions {
+    condition: Option<Expression>,
+    hit_condition: Option<HitCondition>,
+    log_message: Option<LogMessage>,
+}
+
+impl BreakpointOptions {
+    /// Creates options for a breakpoint that stops on every hit
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only stops when a `boolean` expression is true
+    pub fn when(mut self, condition: Expression) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    /// Only stops on the hits matching a hit condition
+    pub fn on_hit(mut self, hit_condition: HitCondition) -> Self {
+        self.hit_condition = Some(hit_condition);
+        self
+    }
+
+    /// Logs a message instead of stopping, making the breakpoint a logpoint
+    pub fn log(mut self, message: LogMessage) -> Self {
+        self.log_message = Some(message);
+        self
+    }
+
+    /// Gets the condition that must be true to stop
+    pub fn condition(&self) -> Option<&Expression> {
+        self.condition.as_ref()
+    }
+
+    /// Gets the condition the hit count must satisfy to stop
+    pub fn hit_condition(&self) -> Option<HitCondition> {
+        self.hit_condition
+    }
+
+    /// Gets the message logged instead of stopping
+    pub fn log_message(&self) -> Option<&LogMessage> {
+        self.log_message.as_ref()
+    }
+
+    /// Whether this logs a message instead of ever stopping
+    pub fn is_logpoint(&self) -> bool {
+        self.log_message.is_some()
+    }
+
+    /// Whether these options stop on every hit, and so don't need to be evaluated
+    fn is_unconditional(&self) -> bool {
+        self.condition.is_none() && self.hit_condition.is_none() && self.log_message.is_none()
+    }
+}
+
+/// Which hits of a breakpoint stop, by the number of times it has been hit counting from 1.
+///
+/// Parses from the forms used by editors: `5` or `== 5`, `>= 5` or `> 4`, and `% 5`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HitCondition {
+    /// Only the given hit stops
+    Equal(u64),
+    /// The given hit and every one after it stops
+    AtLeast(u64),
+    /// Every hit that's a multiple of the given number stops
+    Multiple(u64),
+}
+
+impl HitCondition {
+    /// Whether the given hit stops
+    pub fn matches(&self, hits: u64) -> bool {
+        match *self {
+            HitCondition::Equal(n) => hits == n,
+            HitCondition::AtLeast(n) => hits >= n,
+            HitCondition::Multiple(n) => n != 0 && hits % n == 0,
+        }
+    }
+}
+
+impl FromStr for HitCondition {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let (operator, count) = ["==", ">=", ">", "%", "="]
+            .into_iter()
+            .find_map(|operator| Some((operator, trimmed.strip_prefix(operator)?)))
+            .unwrap_or(("==", trimmed));
+        let count = count.trim().parse::<u64>().map_err(|_| {
+            ParseError::new(
+                format!("invalid hit condition {s:?}"),
+                s.len() - s.trim_start().len(),
+            )
+        })?;
+        Ok(match operator {
+            ">=" => HitCondition::AtLeast(count),
+            ">" => HitCondition::AtLeast(count + 1),
+            "%" => HitCondition::Multiple(count),
+            _ => HitCondition::Equal(count),
+        })
+    }
+}
+
+impl Display for HitCondition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HitCondition::Equal(n) => write!(f, "== {n}"),
+            HitCondition::AtLeast(n) => write!(f, ">= {n}"),
+            HitCondition::Multiple(n) => write!(f, "% {n}"),
+        }
+    }
+}
+
+/// A message logged by a logpoint, with expressions between braces such as
+/// `count = {count}, name = {this.name}`. Braces are written as `{{` and `}}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogMessage {
+    template: String,
+    parts: Vec<LogMessagePart>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum LogMessagePart {
+    Text(String),
+    Expression(Expression),
+}
+
+impl LogMessage {
+    /// Parses a message, and every expression in it
+    pub fn parse(template: &str) -> Result<Self, ParseError> {
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut chars = template.char_indices().peekable();
+        while let Some((at, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|&(_, c)| c == '{').is_some() => text.push('{'),
+                '}' if chars.next_if(|&(_, c)| c == '}').is_some() => text.push('}'),
+                '{' => {
+                    let start = at + 1;
+                    let end = loop {
+                        match chars.next() {
+                            Some((end, '}')) => break end,
+                            Some(_) => {}
+                            None => return Err(ParseError::new("unclosed `{`", at)),
+                        }
+                    };
+                    let expression = Expression::parse(&template[start..end])
+                        .map_err(|e| ParseError::new(e.message(), start + e.position()))?;
+                    if !text.is_empty() {
+                        parts.push(LogMessagePart::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(LogMessagePart::Expression(expression));
+                }
+                '}' => return Err(ParseError::new("unmatched `}`", at)),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(LogMessagePart::Text(text));
+        }
+        Ok(Self {
+            template: template.to_string(),
+            parts,
+        })
+    }
+
+    /// Gets the message as it was written
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// Formats this message by evaluating its expressions in a frame. Expressions that can't be
+    /// evaluated are replaced by their error.
+    pub async fn format<VM: VirtualMachine + ?Sized>(&self, frame: &StackFrame<VM>) -> String {
+        self.format_in(&Context::new(frame)).await
+    }
+
+    async fn format_in<VM: VirtualMachine + ?Sized>(&self, cx: &Context<VM>) -> String {
+        let mut message = String::new();
+        for part in &self.parts {
+            match part {
+                LogMessagePart::Text(text) => message.push_str(text),
+                LogMessagePart::Expression(expression) => {
+                    match expression.evaluate_to_string_in(cx).await {
+                        Ok(value) => message.push_str(&value),
+                        Err(e) => message.push_str(&format!("<{e}>")),
+                    }
+                }
+            }
+        }
+        message
+    }
+}
+
+impl FromStr for LogMessage {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Display for LogMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+/// A message logged by a logpoint when it was hit
+pub struct LogpointMessage<VM: VirtualMachine + ?Sized> {
+    request: BreakpointRequest<VM>,
+    thread: ThreadReference<VM>,
+    location: Location<VM>,
+    message: String,
+}
+
+impl<VM: VirtualMachine + ?Sized> LogpointMessage<VM> {
+    /// Gets the breakpoint request that was hit
+    pub fn request(&self) -> &BreakpointRequest<VM> {
+        &self.request
+    }
+
+    /// Gets the thread that hit the logpoint
+    pub fn thread(&self) -> &ThreadReference<VM> {
+        &self.thread
+    }
+
+    /// Gets the location of the logpoint
+    pub fn location(&self) -> &Location<VM> {
+        &self.location
+    }
+
+    /// Gets the formatted message
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for LogpointMessage<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            request: self.request.clone(),
+            thread: self.thread.clone(),
+            location: self.location.clone(),
+            message: self.message.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Debug for LogpointMessage<VM> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogpointMessage")
+            .field("thread", &self.thread)
+            .field("location", &self.location)
+            .field("message", &self.message)
+            .finish()
+    }
+}
+
+/// Breakpoint options applied to one or more breakpoint requests, which share a hit count
+#[derive(Debug)]
+pub(crate) struct AppliedOptions {
+    options: BreakpointOptions,
+    hits: AtomicU64,
+}
+
+impl AppliedOptions {
+    /// Applies options with a new hit count, or `None` if they always stop
+    pub(crate) fn new(options: BreakpointOptions) -> Option<Arc<Self>> {
+        (!options.is_unconditional()).then(|| {
+            Arc::new(Self {
+                options,
+                hits: AtomicU64::new(0),
+            })
+        })
+    }
+
+    pub(crate) fn options(&self) -> &BreakpointOptions {
+        &self.options
+    }
+
+    pub(crate) fn hits(&self) -> u64 {
+        self.hits.load(Ordering::SeqCst)
+    }
+
+    /// Decides whether a hit stops, logging the message of a logpoint
+    async fn should_stop<VM: VirtualMachine + ?Sized>(
+        &self,
+        manager: &EventRequestManager<VM>,
+        request: BreakpointRequest<VM>,
+        thread: ThreadReference<VM>,
+    ) -> bool {
+        let frame = match thread.frame(0).await {
+            Ok(frame) => frame,
+            Err(e) => {
+                warn!("could not get the frame of a conditional breakpoint: {e}");
+                return true;
+            }
+        };
+        // the condition and the message share a context, since invoking methods invalidates the
+        // frame
+        let cx = Context::new(&frame);
+        if let Some(condition) = &self.options.condition {
+            match condition.evaluate_condition_in(&cx).await {
+                Ok(true) => {}
+                Ok(false) => return false,
+                Err(e) => warn!("could not evaluate breakpoint condition `{condition}`: {e}"),
+            }
+        }
+        let hits = self.hits.fetch_add(1, Ordering::SeqCst) + 1;
+        if let Some(hit_condition) = &self.options.hit_condition {
+            if !hit_condition.matches(hits) {
+                return false;
+            }
+        }
+        let Some(log_message) = &self.options.log_message else {
+            return true;
+        };
+        let message = log_message.format_in(&cx).await;
+        info!(target: "jdi_rs::logpoint", "{message}");
+        // no one listening is fine, the message was still traced
+        let _ = manager.logpoints.send(LogpointMessage {
+            request,
+            thread,
+            location: frame.location().clone(),
+            message,
+        });
+        false
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> EventRequestManager<VM> {
+    /// Creates the channel logpoint messages are sent over
+    pub(crate) fn logpoint_channel() -> tokio::sync::broadcast::Sender<LogpointMessage<VM>> {
+        tokio::sync::broadcast::Sender::new(LOGPOINT_CAPACITY)
+    }
+
+    /// Receives every message logged by a logpoint from now on. Messages are also traced at the
+    /// `info` level, with the target `jdi_rs::logpoint`.
+    pub fn logpoint_messages(&self) -> tokio::sync::broadcast::Receiver<LogpointMessage<VM>> {
+        self.logpoints.subscribe()
+    }
+
+    /// Evaluates the options of the breakpoints hit in a composite, removing the hits that
+    /// shouldn't stop.
+    ///
+    /// Returns `None` if nothing remains of the composite, in which case whatever it suspended
+    /// has been resumed.
+    pub(crate) async fn evaluate_breakpoints(&self, events: Events) -> io::Result<Option<Events>> {
+        let Events { policy, events } = events;
+        let mut remaining = vec![];
+        let mut ignored_thread = None;
+        for event in events {
+            let JdwpEvent::Breakpoint {
+                request_id, thread, ..
+            } = &event
+            else {
+                remaining.push(event);
+                continue;
+            };
+            let Some(request) = self.event_request(EventKind::Breakpoint, *request_id) else {
+                remaining.push(event);
+                continue;
+            };
+            let Some(applied) = request.state().breakpoint_options.clone() else {
+                remaining.push(event);
+                continue;
+            };
+            let thread = ThreadReference::new(*thread, &self.vm);
+            if applied
+                .should_stop(self, BreakpointRequest(request), thread.clone())
+                .await
+            {
+                remaining.push(event);
+            } else {
+                ignored_thread = Some(thread);
+            }
+        }
+
+        match ignored_thread {
+            Some(thread) if remaining.is_empty() => {
+                self.resume_events(policy, &thread).await?;
+                Ok(None)
+            }
+            _ => Ok(Some(Events {
+                policy,
+                events: remaining,
+            })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hit_condition() {
+        assert_eq!("5".parse(), Ok(HitCondition::Equal(5)));
+        assert_eq!(" == 5".parse(), Ok(HitCondition::Equal(5)));
+        assert_eq!(">= 5".parse(), Ok(HitCondition::AtLeast(5)));
+        assert_eq!(">4".parse(), Ok(HitCondition::AtLeast(5)));
+        assert_eq!("%2".parse(), Ok(HitCondition::Multiple(2)));
+        assert!("<3".parse::<HitCondition>().is_err());
+        assert!(HitCondition::Multiple(2).matches(4));
+        assert!(!HitCondition::Multiple(0).matches(0));
+    }
+
+    #[test]
+    fn test_parse_log_message() {
+        let message = LogMessage::parse("{{x}} = {x == }").unwrap_err();
+        assert_eq!(message.position(), 14);
+        assert!(LogMessage::parse("{x").is_err());
+        assert!(LogMessage::parse("x}").is_err());
+
+        let message = LogMessage::parse("{{x}} = {this.x}!").unwrap();
+        assert_eq!(
+            message.parts,
+            vec![
+                LogMessagePart::Text("{x} = ".to_string()),
+                LogMessagePart::Expression(Expression::parse("this.x").unwrap()),
+                LogMessagePart::Text("!".to_string()),
+            ]
+        );
+    }
+}
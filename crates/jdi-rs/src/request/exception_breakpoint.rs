@@ -0,0 +1,248 @@
+use crate::core::private::upgrade;
+use crate::request::{
+    matches_class_pattern, ClassPrepareRequest, EventRequestError, EventRequestManager,
+    ExceptionRequest,
+};
+use crate::{ReferenceType, VirtualMachine};
+use jdwp_types::{ReferenceTypeId, SuspendPolicy, TypeTag};
+use std::collections::HashSet;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::debug;
+
+/// The pattern that matches any exception
+const ANY_EXCEPTION: &str = "*";
+
+/// The signature of the class every exception extends
+const THROWABLE: &str = "Ljava/lang/Throwable;";
+
+/// A breakpoint on exceptions whose class name matches a pattern, such as
+/// `java.lang.IllegalStateException`, `com.acme.*` or `*` for any exception.
+///
+/// Created by [EventRequestManager::break_on_exception]. An [ExceptionRequest] is installed for
+/// every loaded exception class matching the pattern, which also reports its subclasses. Classes
+/// that haven't been loaded yet get their requests once they're prepared, while event sets are
+/// removed from an [EventQueue](crate::event::EventQueue).
+///
+/// This is a handle, and clones of it refer to the same breakpoint.
+pub struct ExceptionBreakpoint<VM: VirtualMachine + ?Sized> {
+    inner: Arc<ExceptionBreakpointInner<VM>>,
+}
+
+struct ExceptionBreakpointInner<VM: VirtualMachine + ?Sized> {
+    pattern: String,
+    caught: bool,
+    uncaught: bool,
+    /// The request used to find matching classes, or `None` when any exception is matched
+    class_prepare: Option<ClassPrepareRequest<VM>>,
+    state: Mutex<ExceptionBreakpointState<VM>>,
+}
+
+struct ExceptionBreakpointState<VM: VirtualMachine + ?Sized> {
+    resolved: HashSet<ReferenceTypeId>,
+    requests: Vec<ExceptionRequest<VM>>,
+}
+
+impl<VM: VirtualMachine + ?Sized> ExceptionBreakpoint<VM> {
+    /// Creates an exception breakpoint. Any exception is matched by a single request, otherwise
+    /// the request used to find matching classes as they're prepared is enabled.
+    async fn new(
+        manager: &EventRequestManager<VM>,
+        pattern: &str,
+        caught: bool,
+        uncaught: bool,
+    ) -> Result<Self, EventRequestError> {
+        let class_prepare = if pattern == ANY_EXCEPTION {
+            None
+        } else {
+            let class_prepare = manager.create_class_prepare_request();
+            class_prepare.add_class_pattern_filter(pattern)?;
+            class_prepare.set_suspend_policy(SuspendPolicy::EventThread)?;
+            class_prepare.enable().await?;
+            Some(class_prepare)
+        };
+        let mut requests = vec![];
+        if class_prepare.is_none() {
+            let request = manager.create_exception_request(None, caught, uncaught);
+            if let Err(error) = request.enable().await {
+                // the type isn't marked resolved, so it's tried again next time
+                manager.delete_event_request(request).await?;
+                return Err(error);
+            }
+            requests.push(request);
+        }
+        Ok(Self {
+            inner: Arc::new(ExceptionBreakpointInner {
+                pattern: pattern.to_string(),
+                caught,
+                uncaught,
+                class_prepare,
+                state: Mutex::new(ExceptionBreakpointState {
+                    resolved: HashSet::new(),
+                    requests,
+                }),
+            }),
+        })
+    }
+
+    fn state(&self) -> MutexGuard<'_, ExceptionBreakpointState<VM>> {
+        self.inner
+            .state
+            .lock()
+            .expect("exception breakpoint state poisoned")
+    }
+
+    /// Gets the pattern exception class names are matched against
+    pub fn pattern(&self) -> &str {
+        &self.inner.pattern
+    }
+
+    /// Whether caught exceptions are reported
+    pub fn notify_caught(&self) -> bool {
+        self.inner.caught
+    }
+
+    /// Whether uncaught exceptions are reported
+    pub fn notify_uncaught(&self) -> bool {
+        self.inner.uncaught
+    }
+
+    /// Gets the request used to find matching classes as they're prepared, or `None` if any
+    /// exception is matched
+    pub fn class_prepare_request(&self) -> Option<&ClassPrepareRequest<VM>> {
+        self.inner.class_prepare.as_ref()
+    }
+
+    /// Gets every exception request installed for this breakpoint so far
+    pub fn requests(&self) -> Vec<ExceptionRequest<VM>> {
+        self.state().requests.clone()
+    }
+
+    /// Whether any exception class matching the pattern has been loaded
+    pub fn is_resolved(&self) -> bool {
+        !self.state().requests.is_empty()
+    }
+
+    /// Installs an exception request for a type if it's an exception class matching the pattern,
+    /// unless one of its superclasses also matches since that request already reports it
+    pub(super) async fn resolve(
+        &self,
+        manager: &EventRequestManager<VM>,
+        ref_type: &ReferenceType<VM>,
+    ) -> Result<(), EventRequestError> {
+        if self.inner.class_prepare.is_none()
+            || ref_type.type_tag() != TypeTag::Class
+            || !matches_class_pattern(self.pattern(), &ref_type.name())
+            || self.state().resolved.contains(&ref_type.id())
+        {
+            return Ok(());
+        }
+        let supertypes = ref_type.all_supertypes().await?;
+        if !supertypes
+            .iter()
+            .any(|supertype| supertype.signature() == THROWABLE)
+        {
+            return Ok(());
+        }
+        if supertypes.iter().skip(1).any(|supertype| {
+            supertype.type_tag() == TypeTag::Class
+                && matches_class_pattern(self.pattern(), &supertype.name())
+        }) {
+            return Ok(());
+        }
+        let request = manager.create_exception_request(
+            Some(ref_type),
+            self.inner.caught,
+            self.inner.uncaught,
+        );
+        if let Err(error) = request.enable().await {
+            // the type isn't marked resolved, so it's tried again next time
+            manager.delete_event_request(request).await?;
+            return Err(error);
+        }
+        let mut state = self.state();
+        state.resolved.insert(ref_type.id());
+        state.requests.push(request);
+        debug!(
+            "resolved exception breakpoint {} in {}",
+            self.pattern(),
+            ref_type.name()
+        );
+        Ok(())
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> EventRequestManager<VM> {
+    /// Breaks when an exception is thrown whose class, or one of its superclasses, has a name
+    /// matching a restricted regular expression such as `java.lang.IllegalStateException` or
+    /// `com.acme.*`. The pattern `*` matches any exception.
+    ///
+    /// Requests are installed immediately for every loaded exception class matching the pattern.
+    /// Classes loaded later get theirs when they're prepared, so the breakpoint can be set before
+    /// the exception class has been loaded.
+    pub async fn break_on_exception(
+        &self,
+        pattern: &str,
+        caught: bool,
+        uncaught: bool,
+    ) -> Result<ExceptionBreakpoint<VM>, EventRequestError> {
+        let breakpoint = ExceptionBreakpoint::new(self, pattern, caught, uncaught).await?;
+        self.exception_breakpoint_list().push(breakpoint.clone());
+        if breakpoint.class_prepare_request().is_some() {
+            let vm = upgrade(&self.vm)?;
+            for ref_type in vm.all_classes().await? {
+                if ref_type.status().prepared() {
+                    breakpoint.resolve(self, &ref_type).await?;
+                }
+            }
+        }
+        Ok(breakpoint)
+    }
+
+    /// Gets every exception breakpoint that has been set and not yet deleted
+    pub fn exception_breakpoints(&self) -> Vec<ExceptionBreakpoint<VM>> {
+        self.exception_breakpoint_list().clone()
+    }
+
+    /// Deletes an exception breakpoint, along with every request it created
+    pub async fn delete_exception_breakpoint(
+        &self,
+        breakpoint: &ExceptionBreakpoint<VM>,
+    ) -> Result<(), EventRequestError> {
+        self.exception_breakpoint_list()
+            .retain(|other| other != breakpoint);
+        if let Some(class_prepare) = breakpoint.class_prepare_request() {
+            self.delete_event_request(class_prepare.clone()).await?;
+        }
+        let requests = std::mem::take(&mut breakpoint.state().requests);
+        self.delete_event_requests(requests).await
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for ExceptionBreakpoint<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> PartialEq for ExceptionBreakpoint<VM> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Eq for ExceptionBreakpoint<VM> {}
+
+impl<VM: VirtualMachine + ?Sized> Debug for ExceptionBreakpoint<VM> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExceptionBreakpoint")
+            .field("pattern", &self.inner.pattern)
+            .field("caught", &self.inner.caught)
+            .field("uncaught", &self.inner.uncaught)
+            .field("class_prepare", &self.inner.class_prepare)
+            .field("requests", &self.state().requests)
+            .finish()
+    }
+}
@@ -0,0 +1,334 @@
+use crate::core::private::upgrade;
+use crate::request::breakpoint_options::AppliedOptions;
+use crate::request::{
+    BreakpointOptions, BreakpointRequest, ClassPrepareRequest, EventRequestError,
+    EventRequestManager,
+};
+use crate::{ReferenceType, VirtualMachine};
+use jdwp_types::{Int, ReferenceTypeId, SuspendPolicy};
+use std::collections::HashSet;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::debug;
+
+/// A breakpoint on a line of a source file, such as `com/acme/Foo.java:123`.
+///
+/// Created by [EventRequestManager::set_breakpoint]. A [BreakpointRequest] is installed at the
+/// line in every class compiled from the source file, including nested, local and anonymous
+/// classes. Classes that haven't been loaded yet get their breakpoints once they're prepared,
+/// while event sets are removed from an [EventQueue](crate::event::EventQueue), so a debugger with
+/// line breakpoints must keep removing event sets from the queue.
+///
+/// This is a handle, and clones of it refer to the same breakpoint.
+pub struct LineBreakpoint<VM: VirtualMachine + ?Sized> {
+    inner: Arc<LineBreakpointInner<VM>>,
+}
+
+struct LineBreakpointInner<VM: VirtualMachine + ?Sized> {
+    source_path: String,
+    line: Int,
+    class_prepare: ClassPrepareRequest<VM>,
+    state: Mutex<LineBreakpointState<VM>>,
+}
+
+struct LineBreakpointState<VM: VirtualMachine + ?Sized> {
+    resolved: HashSet<ReferenceTypeId>,
+    /// Types whose breakpoints are being installed, so that they're only installed once when a
+    /// type is resolved by several tasks at the same time
+    resolving: HashSet<ReferenceTypeId>,
+    breakpoints: Vec<BreakpointRequest<VM>>,
+    /// Shared by every breakpoint request, so that hits are counted across all of them
+    options: Option<Arc<AppliedOptions>>,
+}
+
+impl<VM: VirtualMachine + ?Sized> LineBreakpoint<VM> {
+    /// Creates a line breakpoint, enabling the request used to find classes compiled from the
+    /// source file as they're prepared
+    async fn new(
+        manager: &EventRequestManager<VM>,
+        source_path: &str,
+        line: Int,
+        options: BreakpointOptions,
+    ) -> Result<Self, EventRequestError> {
+        let source_path = source_path
+            .replace('\\', "/")
+            .trim_start_matches('/')
+            .to_string();
+        let class_prepare = manager.create_class_prepare_request();
+        let breakpoint = Self {
+            inner: Arc::new(LineBreakpointInner {
+                source_path,
+                line,
+                class_prepare,
+                state: Mutex::new(LineBreakpointState {
+                    resolved: HashSet::new(),
+                    resolving: HashSet::new(),
+                    breakpoints: vec![],
+                    options: AppliedOptions::new(options),
+                }),
+            }),
+        };
+        let class_prepare = &breakpoint.inner.class_prepare;
+        class_prepare.add_source_name_filter(breakpoint.source_name())?;
+        if let Some(package) = breakpoint.package() {
+            class_prepare.add_class_pattern_filter(format!("{}.*", package.replace('/', ".")))?;
+        }
+        class_prepare.set_suspend_policy(SuspendPolicy::EventThread)?;
+        class_prepare.enable().await?;
+        Ok(breakpoint)
+    }
+
+    fn state(&self) -> MutexGuard<'_, LineBreakpointState<VM>> {
+        self.inner
+            .state
+            .lock()
+            .expect("line breakpoint state poisoned")
+    }
+
+    /// Gets the path of the source file, relative to its source root
+    pub fn source_path(&self) -> &str {
+        &self.inner.source_path
+    }
+
+    /// Gets the line of the source file this breakpoint is on
+    pub fn line(&self) -> Int {
+        self.inner.line
+    }
+
+    /// Gets the name of the source file, without any directories
+    fn source_name(&self) -> &str {
+        self.inner
+            .source_path
+            .rsplit_once('/')
+            .map_or(self.inner.source_path.as_str(), |(_, name)| name)
+    }
+
+    /// Gets the package the source file is in, separated by `/`, or `None` for the default package
+    fn package(&self) -> Option<&str> {
+        self.inner
+            .source_path
+            .rsplit_once('/')
+            .map(|(package, _)| package)
+    }
+
+    /// Gets the request used to find classes compiled from the source file as they're prepared
+    pub fn class_prepare_request(&self) -> &ClassPrepareRequest<VM> {
+        &self.inner.class_prepare
+    }
+
+    /// Gets every breakpoint request installed for this line so far
+    pub fn breakpoints(&self) -> Vec<BreakpointRequest<VM>> {
+        self.state().breakpoints.clone()
+    }
+
+    /// Whether any class compiled from the source file has code on this line
+    pub fn is_resolved(&self) -> bool {
+        !self.state().breakpoints.is_empty()
+    }
+
+    /// Gets the options deciding what this breakpoint does when it's hit
+    pub fn options(&self) -> BreakpointOptions {
+        self.state()
+            .options
+            .as_ref()
+            .map(|applied| applied.options().clone())
+            .unwrap_or_default()
+    }
+
+    /// Sets the options deciding what this breakpoint does when it's hit, for every breakpoint
+    /// request installed for the line, restarting its hit count
+    pub fn set_options(&self, options: BreakpointOptions) {
+        let mut state = self.state();
+        state.options = AppliedOptions::new(options);
+        for breakpoint in &state.breakpoints {
+            breakpoint.apply_options(state.options.clone());
+        }
+    }
+
+    /// Gets the number of times this line has been hit while the condition was true, in any of
+    /// its breakpoint requests, if it has any options
+    pub fn hit_count(&self) -> u64 {
+        self.state()
+            .options
+            .as_ref()
+            .map_or(0, |applied| applied.hits())
+    }
+
+    /// Installs breakpoints in a type if it was compiled from the source file
+    pub(super) async fn resolve(
+        &self,
+        manager: &EventRequestManager<VM>,
+        ref_type: &ReferenceType<VM>,
+    ) -> Result<(), EventRequestError> {
+        let signature = ref_type.signature();
+        let Some(class) = signature
+            .strip_prefix('L')
+            .and_then(|s| s.strip_suffix(';'))
+        else {
+            return Ok(());
+        };
+        let package = class.rsplit_once('/').map(|(package, _)| package);
+        if package != self.package() {
+            return Ok(());
+        }
+        let Some(_resolving) = self.start_resolving(ref_type.id()) else {
+            return Ok(());
+        };
+        if ref_type.source_name().await?.as_deref() != Some(self.source_name()) {
+            return Ok(());
+        }
+
+        let mut methods = HashSet::new();
+        let mut breakpoints = vec![];
+        for location in ref_type.locations_of_line(self.inner.line).await? {
+            // a line can be split over several parts of a method, only the first is needed
+            if !methods.insert(location.method_id()) {
+                continue;
+            }
+            let breakpoint = manager.create_breakpoint_request(&location);
+            breakpoint.apply_options(self.state().options.clone());
+            breakpoints.push(breakpoint.clone());
+            if let Err(error) = breakpoint.enable().await {
+                // the type isn't marked resolved, so it's tried again from scratch next time
+                manager.delete_event_requests(breakpoints).await?;
+                return Err(error);
+            }
+        }
+        let mut state = self.state();
+        state.resolved.insert(ref_type.id());
+        state.breakpoints.extend(breakpoints);
+        debug!(
+            "resolved {}:{} in {}",
+            self.source_path(),
+            self.line(),
+            ref_type.name()
+        );
+        Ok(())
+    }
+
+    /// Marks a type as being resolved until the returned guard is dropped, unless it's already
+    /// resolved or being resolved
+    fn start_resolving(&self, id: ReferenceTypeId) -> Option<Resolving<'_, VM>> {
+        let mut state = self.state();
+        if state.resolved.contains(&id) || !state.resolving.insert(id) {
+            return None;
+        }
+        Some(Resolving {
+            breakpoint: self,
+            id,
+        })
+    }
+}
+
+/// Keeps a type marked as being resolved by a line breakpoint, including when resolving it fails
+/// or is cancelled
+struct Resolving<'a, VM: VirtualMachine + ?Sized> {
+    breakpoint: &'a LineBreakpoint<VM>,
+    id: ReferenceTypeId,
+}
+
+impl<VM: VirtualMachine + ?Sized> Drop for Resolving<'_, VM> {
+    fn drop(&mut self) {
+        self.breakpoint.state().resolving.remove(&self.id);
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> EventRequestManager<VM> {
+    /// Sets a breakpoint on a line of a source file, given by its path relative to the source
+    /// root such as `com/acme/Foo.java`.
+    ///
+    /// Breakpoints are installed immediately in every loaded class compiled from the source file.
+    /// Classes loaded later get their breakpoints when they're prepared, so the breakpoint can be
+    /// set before the code it's in has been loaded.
+    ///
+    /// Those breakpoints are installed as event sets are removed from the
+    /// [EventQueue](crate::event::EventQueue): the thread preparing a class stays suspended until
+    /// the event set with its class prepare event is removed, so a debugger that never removes
+    /// event sets leaves it suspended for good.
+    pub async fn set_breakpoint(
+        &self,
+        source_path: &str,
+        line: Int,
+    ) -> Result<LineBreakpoint<VM>, EventRequestError> {
+        self.set_breakpoint_with(source_path, line, BreakpointOptions::default())
+            .await
+    }
+
+    /// Sets a breakpoint on a line of a source file like [set_breakpoint](Self::set_breakpoint),
+    /// with options such as a condition that are in effect from the first hit
+    pub async fn set_breakpoint_with(
+        &self,
+        source_path: &str,
+        line: Int,
+        options: BreakpointOptions,
+    ) -> Result<LineBreakpoint<VM>, EventRequestError> {
+        let breakpoint = LineBreakpoint::new(self, source_path, line, options).await?;
+        // listed before resolving, so that classes prepared meanwhile are resolved too
+        self.line_breakpoint_list().push(breakpoint.clone());
+        if let Err(error) = self.resolve_loaded(&breakpoint).await {
+            // the caller never gets the breakpoint, so nothing else could delete it
+            self.delete_line_breakpoint(&breakpoint).await?;
+            return Err(error);
+        }
+        Ok(breakpoint)
+    }
+
+    /// Installs a line breakpoint in every class that has already been prepared
+    async fn resolve_loaded(
+        &self,
+        breakpoint: &LineBreakpoint<VM>,
+    ) -> Result<(), EventRequestError> {
+        let vm = upgrade(&self.vm)?;
+        for ref_type in vm.all_classes().await? {
+            if ref_type.status().prepared() {
+                breakpoint.resolve(self, &ref_type).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets every line breakpoint that has been set and not yet deleted
+    pub fn line_breakpoints(&self) -> Vec<LineBreakpoint<VM>> {
+        self.line_breakpoint_list().clone()
+    }
+
+    /// Deletes a line breakpoint, along with every request it created
+    pub async fn delete_line_breakpoint(
+        &self,
+        breakpoint: &LineBreakpoint<VM>,
+    ) -> Result<(), EventRequestError> {
+        self.line_breakpoint_list()
+            .retain(|other| other != breakpoint);
+        self.delete_event_request(breakpoint.class_prepare_request().clone())
+            .await?;
+        let breakpoints = std::mem::take(&mut breakpoint.state().breakpoints);
+        self.delete_event_requests(breakpoints).await
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for LineBreakpoint<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> PartialEq for LineBreakpoint<VM> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Eq for LineBreakpoint<VM> {}
+
+impl<VM: VirtualMachine + ?Sized> Debug for LineBreakpoint<VM> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LineBreakpoint")
+            .field("source_path", &self.inner.source_path)
+            .field("line", &self.inner.line)
+            .field("class_prepare", &self.inner.class_prepare)
+            .field("breakpoints", &self.state().breakpoints)
+            .finish()
+    }
+}
@@ -0,0 +1,204 @@
+use crate::core::private::upgrade;
+use crate::event::{Event, StepEvent};
+use crate::request::{matches_class_pattern, EventRequestError, StepRequest};
+use crate::{Mirror, ThreadReference, VirtualMachine};
+use futures::StreamExt;
+use jdwp_client::events::Event as JdwpEvent;
+use jdwp_types::{StepDepth, StepSize, SuspendPolicy};
+use std::io;
+
+/// The classes stepped through without stopping unless other exclusions are given
+pub const DEFAULT_STEP_EXCLUSIONS: &[&str] = &["java.*", "sun.*", "jdk.internal.*"];
+
+/// Options controlling how a [ThreadReference] steps
+#[derive(Debug, Clone)]
+pub struct StepOptions {
+    exclusions: Vec<String>,
+}
+
+impl Default for StepOptions {
+    fn default() -> Self {
+        Self {
+            exclusions: DEFAULT_STEP_EXCLUSIONS
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl StepOptions {
+    /// Creates the default step options, which step through [DEFAULT_STEP_EXCLUSIONS]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the classes stepped through with the ones matching the given restricted regular
+    /// expressions, such as `java.*` or `*.Generated`
+    pub fn exclusions<S: Into<String>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.exclusions = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Also steps through classes matching a restricted regular expression
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclusions.push(pattern.into());
+        self
+    }
+
+    /// Gets the patterns of the classes stepped through
+    pub fn exclusion_patterns(&self) -> &[String] {
+        &self.exclusions
+    }
+
+    /// Whether a class, by its fully qualified name, is stepped through
+    pub fn is_excluded(&self, class_name: &str) -> bool {
+        self.exclusions
+            .iter()
+            .any(|pattern| matches_class_pattern(pattern, class_name))
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> ThreadReference<VM> {
+    /// Steps this thread, which must be suspended, through the [default exclusions](StepOptions)
+    /// and waits for the step to complete. See [step_with](Self::step_with).
+    pub async fn step(
+        &self,
+        size: StepSize,
+        depth: StepDepth,
+    ) -> Result<StepEvent<VM>, EventRequestError> {
+        self.step_with(size, depth, &StepOptions::default()).await
+    }
+
+    /// Creates a request for a single step of this thread through the given exclusions, which
+    /// ends after one step. Any step request left behind for the thread, such as one interrupted
+    /// by a breakpoint, is deleted first, since only one is allowed per thread.
+    ///
+    /// The request isn't enabled yet, so its suspend policy can still be set, and nothing waits
+    /// for the step: once the request is enabled and the thread resumed, the step's event is
+    /// removed from an [EventQueue](crate::event::EventQueue) like any other.
+    /// [step_with](Self::step_with) waits for the step instead.
+    pub async fn prepare_step(
+        &self,
+        size: StepSize,
+        depth: StepDepth,
+        options: &StepOptions,
+    ) -> Result<StepRequest<VM>, EventRequestError> {
+        let vm = upgrade(&self.virtual_machine())?;
+        let manager = vm.event_request_manager();
+        let stale = manager
+            .step_requests()
+            .into_iter()
+            .filter(|request| request.thread() == *self)
+            .collect::<Vec<_>>();
+        manager.delete_event_requests(stale).await?;
+        let request = manager.create_step_request(self, size, depth)?;
+        let filtered = (|| {
+            for pattern in options.exclusion_patterns() {
+                request.add_class_exclusion_filter(pattern.clone())?;
+            }
+            request.add_count_filter(1)
+        })();
+        if let Err(error) = filtered {
+            manager.delete_event_request(request).await?;
+            return Err(error);
+        }
+        Ok(request)
+    }
+
+    /// Steps this thread, which must be suspended, and waits for the step to complete.
+    ///
+    /// The thread is resumed for the step and is suspended again once it completes. The step's
+    /// event is returned here instead of being removed from an
+    /// [EventQueue](crate::event::EventQueue), and the request is deleted afterwards. If the
+    /// returned future is dropped before the step completes, the request is left for the
+    /// [EventRequestManager](crate::request::EventRequestManager) to delete.
+    pub async fn step_with(
+        &self,
+        size: StepSize,
+        depth: StepDepth,
+        options: &StepOptions,
+    ) -> Result<StepEvent<VM>, EventRequestError> {
+        let vm = upgrade(&self.virtual_machine())?;
+        let manager = vm.event_request_manager();
+        let request = self.prepare_step(size, depth, options).await?;
+        let stepped = async {
+            request.set_suspend_policy(SuspendPolicy::EventThread)?;
+            let mut events = vm.client().events();
+            request.enable().await?;
+            manager.await_event(&request);
+            self.resume().await?;
+            while let Some(composite) = events.next().await {
+                for event in composite.events {
+                    let JdwpEvent::SingleStep { request_id, .. } = &event else {
+                        continue;
+                    };
+                    if request.request_id() != Some(*request_id) {
+                        continue;
+                    }
+                    if let Some(Event::Step(step)) =
+                        Event::from_jdwp(event, manager, &self.virtual_machine())
+                    {
+                        return Ok(step);
+                    }
+                }
+            }
+            Err(EventRequestError::Io(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "the target VM disconnected while stepping",
+            )))
+        }
+        .await;
+        let deleted = manager.delete_event_request(request).await;
+        let step = stepped?;
+        deleted?;
+        Ok(step)
+    }
+
+    /// Steps into the method with the given name if it's called by the rest of the current line,
+    /// stepping over every other call made by the line.
+    ///
+    /// If the method isn't called, this stops wherever the line leads to, like a
+    /// [line step over](StepDepth::Over).
+    pub async fn step_into_method(
+        &self,
+        method_name: &str,
+        options: &StepOptions,
+    ) -> Result<StepEvent<VM>, EventRequestError> {
+        let depth = self.frame_count().await?;
+        loop {
+            let step = self
+                .step_with(StepSize::Line, StepDepth::Into, options)
+                .await?;
+            if self.frame_count().await? <= depth
+                || step.location().method().await?.name() == method_name
+            {
+                return Ok(step);
+            }
+            // back to just after the call, so the rest of the line is stepped into next
+            self.step_with(StepSize::Min, StepDepth::Out, options)
+                .await?;
+        }
+    }
+
+    /// Steps out of the current method, and keeps stepping out until reaching a class that isn't
+    /// excluded.
+    ///
+    /// Stepping out of a lambda usually returns into the library code that called it, such as
+    /// `java.util.ArrayList.forEach`, and the generated class implementing the lambda. Both are
+    /// stepped out of, so this stops in the code that passed the lambda to the library.
+    pub async fn smart_step_out(
+        &self,
+        options: &StepOptions,
+    ) -> Result<StepEvent<VM>, EventRequestError> {
+        loop {
+            let step = self
+                .step_with(StepSize::Line, StepDepth::Out, options)
+                .await?;
+            let class_name = step.location().declaring_type().await?.name();
+            if !options.is_excluded(&class_name) && !class_name.contains("$$Lambda") {
+                return Ok(step);
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::core::private::upgrade;
+use crate::request::{AccessWatchpointRequest, EventRequestError, ModificationWatchpointRequest};
+use crate::{Field, Mirror, ObjectReference, VirtualMachine};
+use jdwp_client::commands::CapabilitiesNew;
+
+impl<VM: VirtualMachine + ?Sized> Field<VM> {
+    /// Watches this field for reads, optionally only of the given object's field, and returns
+    /// the enabled request. Its events are removed from an
+    /// [EventQueue](crate::event::EventQueue) like any other.
+    ///
+    /// Fails with [Unsupported](EventRequestError::Unsupported) if the target VM can't watch
+    /// field access, or can't filter by instance when one is given.
+    pub async fn watch_access(
+        &self,
+        instance: Option<&ObjectReference<VM>>,
+    ) -> Result<AccessWatchpointRequest<VM>, EventRequestError> {
+        let vm = upgrade(&self.virtual_machine())?;
+        let capabilities = vm.client().send(CapabilitiesNew).await?;
+        if !capabilities.can_watch_field_access {
+            return Err(EventRequestError::Unsupported("watch field access"));
+        }
+        if instance.is_some() && !capabilities.can_use_instance_filters {
+            return Err(EventRequestError::Unsupported("filter events by instance"));
+        }
+        let request = vm
+            .event_request_manager()
+            .create_access_watchpoint_request(self);
+        if let Some(instance) = instance {
+            request.add_instance_filter(instance)?;
+        }
+        request.enable().await?;
+        Ok(request)
+    }
+
+    /// Watches this field for modifications, optionally only of the given object's field, and
+    /// returns the enabled request. Its events are removed from an
+    /// [EventQueue](crate::event::EventQueue) like any other.
+    ///
+    /// Fails with [Unsupported](EventRequestError::Unsupported) if the target VM can't watch
+    /// field modification, or can't filter by instance when one is given.
+    pub async fn watch_modification(
+        &self,
+        instance: Option<&ObjectReference<VM>>,
+    ) -> Result<ModificationWatchpointRequest<VM>, EventRequestError> {
+        let vm = upgrade(&self.virtual_machine())?;
+        let capabilities = vm.client().send(CapabilitiesNew).await?;
+        if !capabilities.can_watch_field_modification {
+            return Err(EventRequestError::Unsupported("watch field modification"));
+        }
+        if instance.is_some() && !capabilities.can_use_instance_filters {
+            return Err(EventRequestError::Unsupported("filter events by instance"));
+        }
+        let request = vm
+            .event_request_manager()
+            .create_modification_watchpoint_request(self);
+        if let Some(instance) = instance {
+            request.add_instance_filter(instance)?;
+        }
+        request.enable().await?;
+        Ok(request)
+    }
+}
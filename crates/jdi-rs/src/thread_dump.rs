@@ -0,0 +1,565 @@
+//! Dumping every thread of the target VM, like `jstack` does
+//!
+//! A [ThreadDump] is taken by [VirtualMachine::thread_dump]. It holds the status and call stack of
+//! every thread, along with the monitors each owns or is waiting for, and the deadlocks found in
+//! them. It's displayed as a report similar to `jstack`'s, and can be converted to JSON with
+//! [ThreadDump::to_json].
+
+use crate::{Location, ObjectReference, ThreadReference, VirtualMachine};
+use jdwp_types::{Int, MethodId, ReferenceTypeId, ThreadId, ThreadStatus};
+use serde_json::json;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::io;
+use tracing::debug;
+
+/// A snapshot of every thread in the target VM
+#[derive(Debug)]
+pub struct ThreadDump<VM: VirtualMachine + ?Sized> {
+    threads: Vec<ThreadInfo<VM>>,
+    deadlocks: Vec<Deadlock<VM>>,
+}
+
+/// A thread, as it was when it was dumped
+#[derive(Debug)]
+pub struct ThreadInfo<VM: VirtualMachine + ?Sized> {
+    thread: ThreadReference<VM>,
+    name: String,
+    status: ThreadStatus,
+    suspend_count: Int,
+    frames: Vec<FrameInfo>,
+    owned_monitors: Vec<LockedMonitor<VM>>,
+    contended_monitor: Option<Monitor<VM>>,
+}
+
+/// A frame on the call stack of a dumped thread
+#[derive(Debug, Clone)]
+pub struct FrameInfo {
+    class_name: String,
+    method_name: String,
+    source_name: Option<String>,
+    line_number: Option<Int>,
+}
+
+/// A monitor, as it was when its thread was dumped
+#[derive(Debug)]
+pub struct Monitor<VM: VirtualMachine + ?Sized> {
+    object: ObjectReference<VM>,
+    class_name: String,
+    owner: Option<ThreadReference<VM>>,
+    entry_count: Int,
+    waiters: Vec<ThreadReference<VM>>,
+}
+
+/// A monitor owned by a dumped thread, with the frame it was entered in
+#[derive(Debug)]
+pub struct LockedMonitor<VM: VirtualMachine + ?Sized> {
+    monitor: Monitor<VM>,
+    stack_depth: Option<Int>,
+}
+
+/// Threads that are each blocked entering a monitor owned by the next, the last one waiting for
+/// a monitor owned by the first
+#[derive(Debug)]
+pub struct Deadlock<VM: VirtualMachine + ?Sized> {
+    threads: Vec<ThreadReference<VM>>,
+}
+
+impl<VM: VirtualMachine + ?Sized> ThreadDump<VM> {
+    /// Dumps every thread of a virtual machine, which is suspended meanwhile so that the threads
+    /// are consistent with each other
+    pub(crate) async fn capture(vm: &VM) -> io::Result<Self> {
+        let threads = vm.all_threads().await?;
+        let capabilities = vm.capabilities().await?;
+        vm.suspend().await?;
+        let mut dumper = Dumper {
+            owned_monitors: capabilities.can_get_owned_monitor_info
+                && capabilities.can_get_monitor_frame_info,
+            contended_monitor: capabilities.can_get_current_contended_monitor,
+            monitor_info: capabilities.can_get_monitor_info,
+            types: HashMap::new(),
+            methods: HashMap::new(),
+        };
+        let dumped = async {
+            let mut dumped = Vec::with_capacity(threads.len());
+            for thread in threads {
+                dumped.push(dumper.thread(thread).await?);
+            }
+            Ok::<_, io::Error>(dumped)
+        }
+        .await;
+        // resumed even if dumping failed, so the target VM isn't left suspended
+        vm.resume().await?;
+        let threads = dumped?;
+        let deadlocks = find_deadlocks(&threads);
+        Ok(Self { threads, deadlocks })
+    }
+
+    /// Gets every dumped thread
+    pub fn threads(&self) -> &[ThreadInfo<VM>] {
+        &self.threads
+    }
+
+    /// Gets a dumped thread by its name, which might not be unique
+    pub fn thread_by_name(&self, name: &str) -> Option<&ThreadInfo<VM>> {
+        self.threads.iter().find(|info| info.name == name)
+    }
+
+    /// Gets a dumped thread
+    pub fn thread(&self, thread: ThreadId) -> Option<&ThreadInfo<VM>> {
+        self.threads.iter().find(|info| info.thread.id() == thread)
+    }
+
+    /// Gets the deadlocks found among the dumped threads
+    pub fn deadlocks(&self) -> &[Deadlock<VM>] {
+        &self.deadlocks
+    }
+
+    /// Converts the dump to JSON, as an object with a `threads` and a `deadlocks` array
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "threads": self.threads.iter().map(ThreadInfo::to_json).collect::<Vec<_>>(),
+            "deadlocks": self
+                .deadlocks
+                .iter()
+                .map(|deadlock| {
+                    deadlock
+                        .threads
+                        .iter()
+                        .map(|thread| json!(self.name_of(thread.id())))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    fn name_of(&self, thread: ThreadId) -> &str {
+        self.thread(thread).map_or("<unknown>", |info| &info.name)
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> ThreadInfo<VM> {
+    /// Gets the thread
+    pub fn thread(&self) -> &ThreadReference<VM> {
+        &self.thread
+    }
+
+    /// Gets the name of the thread
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the execution status of the thread
+    pub fn status(&self) -> ThreadStatus {
+        self.status
+    }
+
+    /// Gets how many times the thread was suspended, not counting the suspension for the dump
+    pub fn suspend_count(&self) -> Int {
+        self.suspend_count
+    }
+
+    /// Whether the thread was suspended, not counting the suspension for the dump
+    pub fn is_suspended(&self) -> bool {
+        self.suspend_count > 0
+    }
+
+    /// Gets the frames on the thread's call stack, starting from the currently executing one
+    pub fn frames(&self) -> &[FrameInfo] {
+        &self.frames
+    }
+
+    /// Gets the monitors the thread owns
+    pub fn owned_monitors(&self) -> &[LockedMonitor<VM>] {
+        &self.owned_monitors
+    }
+
+    /// Gets the monitor the thread is waiting to enter, or waiting on in `Object.wait`
+    pub fn contended_monitor(&self) -> Option<&Monitor<VM>> {
+        self.contended_monitor.as_ref()
+    }
+
+    /// Gets the monitor the thread is blocked entering, along with the thread owning it
+    fn blocked_on(&self) -> Option<(&Monitor<VM>, &ThreadReference<VM>)> {
+        if self.status != ThreadStatus::Monitor {
+            return None;
+        }
+        let monitor = self.contended_monitor.as_ref()?;
+        let owner = monitor.owner.as_ref()?;
+        (*owner != self.thread).then_some((monitor, owner))
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "id": u64::from(self.thread.id()),
+            "name": self.name,
+            "status": status_name(self.status),
+            "suspend_count": self.suspend_count,
+            "frames": self.frames.iter().map(FrameInfo::to_json).collect::<Vec<_>>(),
+            "owned_monitors": self
+                .owned_monitors
+                .iter()
+                .map(|locked| {
+                    let mut monitor = locked.monitor.to_json();
+                    monitor["stack_depth"] = json!(locked.stack_depth);
+                    monitor
+                })
+                .collect::<Vec<_>>(),
+            "contended_monitor": self.contended_monitor.as_ref().map(Monitor::to_json),
+        })
+    }
+}
+
+impl FrameInfo {
+    /// Gets the fully qualified name of the class declaring the frame's method
+    pub fn class_name(&self) -> &str {
+        &self.class_name
+    }
+
+    /// Gets the name of the frame's method
+    pub fn method_name(&self) -> &str {
+        &self.method_name
+    }
+
+    /// Gets the name of the source file declaring the frame's method, if it's known
+    pub fn source_name(&self) -> Option<&str> {
+        self.source_name.as_deref()
+    }
+
+    /// Gets the line being executed in the frame, if it's known
+    pub fn line_number(&self) -> Option<Int> {
+        self.line_number
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "class": self.class_name,
+            "method": self.method_name,
+            "source": self.source_name,
+            "line": self.line_number,
+        })
+    }
+}
+
+impl Display for FrameInfo {
+    /// Formats the frame the way a stack trace element is, such as
+    /// `com.acme.Foo.bar(Foo.java:12)`
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}(", self.class_name, self.method_name)?;
+        match (&self.source_name, self.line_number) {
+            (Some(source), Some(line)) => write!(f, "{source}:{line})"),
+            (Some(source), None) => write!(f, "{source})"),
+            (None, _) => write!(f, "Unknown Source)"),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Monitor<VM> {
+    /// Gets the object the monitor belongs to
+    pub fn object(&self) -> &ObjectReference<VM> {
+        &self.object
+    }
+
+    /// Gets the fully qualified name of the object's class
+    pub fn class_name(&self) -> &str {
+        &self.class_name
+    }
+
+    /// Gets the thread owning the monitor, if any
+    pub fn owner(&self) -> Option<&ThreadReference<VM>> {
+        self.owner.as_ref()
+    }
+
+    /// Gets how many times the owner has entered the monitor
+    pub fn entry_count(&self) -> Int {
+        self.entry_count
+    }
+
+    /// Gets the threads waiting on the monitor in `Object.wait`
+    pub fn waiters(&self) -> &[ThreadReference<VM>] {
+        &self.waiters
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "id": self.object.id().get(),
+            "class": self.class_name,
+            "owner": self.owner.as_ref().map(|owner| u64::from(owner.id())),
+            "entry_count": self.entry_count,
+            "waiters": self
+                .waiters
+                .iter()
+                .map(|waiter| u64::from(waiter.id()))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Display for Monitor<VM> {
+    /// Formats the monitor like `jstack` does, such as `<0x0000001a> (a java.lang.Object)`
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<{:#018x}> (a {})",
+            self.object.id().get(),
+            self.class_name
+        )
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> LockedMonitor<VM> {
+    /// Gets the monitor
+    pub fn monitor(&self) -> &Monitor<VM> {
+        &self.monitor
+    }
+
+    /// Gets the index of the frame the monitor was entered in, or `None` if it isn't known
+    pub fn stack_depth(&self) -> Option<Int> {
+        self.stack_depth
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Deadlock<VM> {
+    /// Gets the deadlocked threads, each waiting for a monitor owned by the next
+    pub fn threads(&self) -> &[ThreadReference<VM>] {
+        &self.threads
+    }
+}
+
+/// Names a thread status the way `java.lang.Thread.State` does
+fn status_name(status: ThreadStatus) -> &'static str {
+    match status {
+        ThreadStatus::Zombie => "TERMINATED",
+        ThreadStatus::Running => "RUNNABLE",
+        ThreadStatus::Sleeping => "TIMED_WAITING (sleeping)",
+        ThreadStatus::Monitor => "BLOCKED (on object monitor)",
+        ThreadStatus::Wait => "WAITING (on object monitor)",
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Display for ThreadDump<VM> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Full thread dump:")?;
+        for info in &self.threads {
+            writeln!(f)?;
+            write!(f, "\"{}\" #{}", info.name, info.thread.id().get())?;
+            if info.is_suspended() {
+                write!(f, " suspended={}", info.suspend_count)?;
+            }
+            writeln!(f)?;
+            writeln!(f, "   java.lang.Thread.State: {}", status_name(info.status))?;
+            for (depth, frame) in info.frames.iter().enumerate() {
+                writeln!(f, "\tat {frame}")?;
+                if depth == 0 {
+                    if let Some(monitor) = &info.contended_monitor {
+                        match info.status {
+                            ThreadStatus::Wait => writeln!(f, "\t- waiting on {monitor}")?,
+                            _ => {
+                                write!(f, "\t- waiting to lock {monitor}")?;
+                                if let Some(owner) = &monitor.owner {
+                                    write!(f, " owned by \"{}\"", self.name_of(owner.id()))?;
+                                }
+                                writeln!(f)?;
+                            }
+                        }
+                    }
+                }
+                for locked in &info.owned_monitors {
+                    if locked.stack_depth.is_some_and(|d| d as usize == depth) {
+                        write!(f, "\t- locked {}", locked.monitor)?;
+                        let waiters = &locked.monitor.waiters;
+                        if !waiters.is_empty() {
+                            let names = waiters
+                                .iter()
+                                .map(|waiter| format!("\"{}\"", self.name_of(waiter.id())))
+                                .collect::<Vec<_>>();
+                            write!(f, " waited on by {}", names.join(", "))?;
+                        }
+                        writeln!(f)?;
+                    }
+                }
+            }
+            for locked in &info.owned_monitors {
+                if locked.stack_depth.is_none() {
+                    writeln!(f, "\t- locked {} in an unknown frame", locked.monitor)?;
+                }
+            }
+        }
+        if self.deadlocks.is_empty() {
+            return Ok(());
+        }
+        writeln!(f)?;
+        match self.deadlocks.len() {
+            1 => writeln!(f, "Found one Java-level deadlock:")?,
+            n => writeln!(f, "Found {n} Java-level deadlocks:")?,
+        }
+        writeln!(f, "=============================")?;
+        for deadlock in &self.deadlocks {
+            for thread in &deadlock.threads {
+                let Some(info) = self.thread(thread.id()) else {
+                    continue;
+                };
+                let Some((monitor, owner)) = info.blocked_on() else {
+                    continue;
+                };
+                writeln!(f, "\"{}\":", info.name)?;
+                writeln!(f, "  waiting to lock monitor {monitor},")?;
+                writeln!(f, "  which is held by \"{}\"", self.name_of(owner.id()))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds the cycles in the graph of threads blocked entering a monitor owned by another thread.
+/// Every thread waits for at most one other, so each thread is in at most one cycle.
+fn find_deadlocks<VM: VirtualMachine + ?Sized>(threads: &[ThreadInfo<VM>]) -> Vec<Deadlock<VM>> {
+    let waits_for = threads
+        .iter()
+        .filter_map(|info| Some((info.thread.id(), info.blocked_on()?.1.clone())))
+        .collect::<HashMap<_, _>>();
+    let mut deadlocks = vec![];
+    let mut visited = HashSet::new();
+    for info in threads {
+        let mut path: Vec<ThreadReference<VM>> = vec![];
+        let mut current = info.thread.clone();
+        while visited.insert(current.id()) {
+            path.push(current.clone());
+            match waits_for.get(&current.id()) {
+                Some(next) => current = next.clone(),
+                None => break,
+            }
+        }
+        // the walk ended on a thread seen before, which closes a cycle if it's on this path
+        if let Some(start) = path.iter().position(|thread| *thread == current) {
+            if waits_for.contains_key(&current.id()) {
+                deadlocks.push(Deadlock {
+                    threads: path.split_off(start),
+                });
+            }
+        }
+    }
+    deadlocks
+}
+
+/// Reads the threads of a suspended virtual machine, caching what's shared between them
+struct Dumper {
+    owned_monitors: bool,
+    contended_monitor: bool,
+    monitor_info: bool,
+    /// The names and source names of the types frames were in
+    types: HashMap<ReferenceTypeId, (String, Option<String>)>,
+    methods: HashMap<(ReferenceTypeId, MethodId), String>,
+}
+
+impl Dumper {
+    async fn thread<VM: VirtualMachine + ?Sized>(
+        &mut self,
+        thread: ThreadReference<VM>,
+    ) -> io::Result<ThreadInfo<VM>> {
+        let name = thread.name().await?;
+        let status = thread.status().await?;
+        // the suspension for the dump isn't counted
+        let suspend_count = thread.suspend_count().await? - 1;
+        let mut info = ThreadInfo {
+            thread,
+            name,
+            status,
+            suspend_count,
+            frames: vec![],
+            owned_monitors: vec![],
+            contended_monitor: None,
+        };
+        // threads that haven't started or have terminated have no stack
+        if status == ThreadStatus::Zombie {
+            return Ok(info);
+        }
+        for frame in info.thread.frames().await? {
+            info.frames.push(self.frame(frame.location()).await?);
+        }
+        if self.owned_monitors {
+            for owned in info.thread.owned_monitors_and_frames().await? {
+                info.owned_monitors.push(LockedMonitor {
+                    monitor: self.monitor(owned.monitor().clone()).await?,
+                    stack_depth: owned.stack_depth(),
+                });
+            }
+        }
+        if self.contended_monitor {
+            if let Some(monitor) = info.thread.current_contended_monitor().await? {
+                info.contended_monitor = Some(self.monitor(monitor).await?);
+            }
+        }
+        Ok(info)
+    }
+
+    async fn frame<VM: VirtualMachine + ?Sized>(
+        &mut self,
+        location: &Location<VM>,
+    ) -> io::Result<FrameInfo> {
+        let (class_name, source_name) = match self.types.entry(location.declaring_type_id()) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => {
+                let declaring_type = location.declaring_type().await?;
+                let source_name = declaring_type.source_name().await?;
+                entry.insert((declaring_type.name(), source_name)).clone()
+            }
+        };
+        let method_key = (location.declaring_type_id(), location.method_id());
+        let method_name = match self.methods.entry(method_key) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => {
+                let method = location.method().await?;
+                entry.insert(method.name().to_string()).clone()
+            }
+        };
+        let line_number = match location.line_number().await {
+            Ok(line_number) => line_number,
+            Err(e) => {
+                debug!("could not read the line of a dumped frame: {e}");
+                None
+            }
+        };
+        Ok(FrameInfo {
+            class_name,
+            method_name,
+            source_name,
+            line_number,
+        })
+    }
+
+    async fn monitor<VM: VirtualMachine + ?Sized>(
+        &mut self,
+        object: ObjectReference<VM>,
+    ) -> io::Result<Monitor<VM>> {
+        let class_name = object.reference_type().await?.name();
+        let mut monitor = Monitor {
+            object,
+            class_name,
+            owner: None,
+            entry_count: 0,
+            waiters: vec![],
+        };
+        if self.monitor_info {
+            let info = monitor.object.monitor_info().await?;
+            monitor.owner = info.owner().cloned();
+            monitor.entry_count = info.entry_count();
+            monitor.waiters = info.waiters().to_vec();
+        }
+        Ok(monitor)
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for Monitor<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            object: self.object.clone(),
+            class_name: self.class_name.clone(),
+            owner: self.owner.clone(),
+            entry_count: self.entry_count,
+            waiters: self.waiters.clone(),
+        }
+    }
+}
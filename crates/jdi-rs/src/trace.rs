@@ -0,0 +1,685 @@
+//! Tracing the methods called in the target VM
+//!
+//! A [Tracer] requests method entry and exit events for the classes and threads it's given, and
+//! turns them into a [Stream] of [TraceEvent]s describing each call, along with the arguments it
+//! was passed and the value it returned. The calls made in each thread form a tree, which a
+//! [CallTree] puts together. Events can be exported as JSON lines with [write_json_lines], or in
+//! the Chrome trace event format, viewable in `chrome://tracing` or Perfetto, with a
+//! [ChromeTraceWriter].
+//!
+//! Each traced method suspends its thread twice, so tracing slows the target VM down
+//! considerably. Class patterns should be narrow.
+
+use crate::core::private::upgrade;
+use crate::expr::display_value;
+use crate::request::{EventRequest, EventRequestError, DEFAULT_STEP_EXCLUSIONS};
+use crate::{LocalVariable, Location, Method, ThreadReference, Value, VirtualMachine};
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use jdwp_client::events::{Event as JdwpEvent, EventStream};
+use jdwp_types::{EventKind, Int, MethodId, ReferenceTypeId, SuspendPolicy, ThreadId};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Debug, Display, Formatter};
+use std::io;
+use std::pin::Pin;
+use std::sync::Weak;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+pub use call_tree::{CallNode, CallTree};
+pub use export::{write_json_lines, ChromeTraceWriter};
+
+mod call_tree;
+mod export;
+
+/// Options controlling what a [Tracer] traces
+#[derive(Debug)]
+pub struct TraceOptions<VM: VirtualMachine + ?Sized> {
+    class_patterns: Vec<String>,
+    exclusions: Vec<String>,
+    threads: Vec<ThreadReference<VM>>,
+    arguments: bool,
+}
+
+impl<VM: VirtualMachine + ?Sized> Default for TraceOptions<VM> {
+    fn default() -> Self {
+        Self {
+            class_patterns: vec![],
+            exclusions: DEFAULT_STEP_EXCLUSIONS
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect(),
+            threads: vec![],
+            arguments: true,
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> TraceOptions<VM> {
+    /// Creates the default trace options, which trace every method in every thread except those
+    /// of the classes stepped through by default, and read their arguments
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also traces the methods of classes matching a restricted regular expression, such as
+    /// `com.acme.*`. Every class is traced if no patterns are given.
+    pub fn class_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.class_patterns.push(pattern.into());
+        self
+    }
+
+    /// Replaces the classes that aren't traced with the ones matching the given restricted
+    /// regular expressions
+    pub fn exclusions<S: Into<String>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.exclusions = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Also doesn't trace classes matching a restricted regular expression
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclusions.push(pattern.into());
+        self
+    }
+
+    /// Also traces the methods called in a thread. Every thread is traced if none are given.
+    pub fn thread(mut self, thread: &ThreadReference<VM>) -> Self {
+        self.threads.push(thread.clone());
+        self
+    }
+
+    /// Sets whether the arguments of each call are read from its frame, which needs the classes
+    /// to be compiled with debug information
+    pub fn arguments(mut self, arguments: bool) -> Self {
+        self.arguments = arguments;
+        self
+    }
+
+    /// Gets the patterns of the classes traced
+    pub fn class_patterns(&self) -> &[String] {
+        &self.class_patterns
+    }
+
+    /// Gets the patterns of the classes that aren't traced
+    pub fn exclusion_patterns(&self) -> &[String] {
+        &self.exclusions
+    }
+
+    /// Gets the threads traced
+    pub fn threads(&self) -> &[ThreadReference<VM>] {
+        &self.threads
+    }
+
+    /// Whether the arguments of each call are read
+    pub fn reads_arguments(&self) -> bool {
+        self.arguments
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for TraceOptions<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            class_patterns: self.class_patterns.clone(),
+            exclusions: self.exclusions.clone(),
+            threads: self.threads.clone(),
+            arguments: self.arguments,
+        }
+    }
+}
+
+/// A value read while tracing, along with how it's displayed, which for strings is their
+/// characters
+#[derive(Debug)]
+pub struct TracedValue<VM: VirtualMachine + ?Sized> {
+    value: Value<VM>,
+    display: String,
+}
+
+impl<VM: VirtualMachine + ?Sized> TracedValue<VM> {
+    async fn read(value: Value<VM>) -> io::Result<Self> {
+        let display = display_value(&value).await?;
+        Ok(Self { value, display })
+    }
+
+    /// Gets the value
+    pub fn value(&self) -> &Value<VM> {
+        &self.value
+    }
+
+    /// Gets how the value is displayed, the way string concatenation would
+    pub fn display(&self) -> &str {
+        &self.display
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for TracedValue<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            display: self.display.clone(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Display for TracedValue<VM> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.display)
+    }
+}
+
+/// A call to a traced method
+#[derive(Debug)]
+pub struct Call<VM: VirtualMachine + ?Sized> {
+    id: u64,
+    parent: Option<u64>,
+    depth: usize,
+    thread: ThreadReference<VM>,
+    thread_name: String,
+    class_name: String,
+    method: Method<VM>,
+    arguments: Vec<(String, TracedValue<VM>)>,
+    entered: Duration,
+}
+
+impl<VM: VirtualMachine + ?Sized> Call<VM> {
+    /// Gets the id of this call, which is unique within its trace
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Gets the id of the traced call this call was made in, if any
+    pub fn parent(&self) -> Option<u64> {
+        self.parent
+    }
+
+    /// Gets the number of traced calls this call was made in
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Gets the thread the call was made in
+    pub fn thread(&self) -> &ThreadReference<VM> {
+        &self.thread
+    }
+
+    /// Gets the name of the thread the call was made in, when it was first traced
+    pub fn thread_name(&self) -> &str {
+        &self.thread_name
+    }
+
+    /// Gets the fully qualified name of the class declaring the called method
+    pub fn class_name(&self) -> &str {
+        &self.class_name
+    }
+
+    /// Gets the called method
+    pub fn method(&self) -> &Method<VM> {
+        &self.method
+    }
+
+    /// Gets the names and values of the arguments the method was called with, which is empty if
+    /// they weren't read
+    pub fn arguments(&self) -> &[(String, TracedValue<VM>)] {
+        &self.arguments
+    }
+
+    /// Gets when the method was entered, relative to when tracing started
+    pub fn entered(&self) -> Duration {
+        self.entered
+    }
+
+    /// Gets the name of the called method qualified by its class, such as `com.acme.Foo.bar`
+    pub fn name(&self) -> String {
+        format!("{}.{}", self.class_name, self.method.name())
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for Call<VM> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            parent: self.parent,
+            depth: self.depth,
+            thread: self.thread.clone(),
+            thread_name: self.thread_name.clone(),
+            class_name: self.class_name.clone(),
+            method: self.method.clone(),
+            arguments: self.arguments.clone(),
+            entered: self.entered,
+        }
+    }
+}
+
+/// Something that happened to a traced call
+#[derive(Debug)]
+pub enum TraceEvent<VM: VirtualMachine + ?Sized> {
+    /// A traced method was entered
+    Enter {
+        /// The call that was made
+        call: Call<VM>,
+    },
+    /// A traced method returned, or was left by throwing an exception
+    Exit {
+        /// The call that ended
+        call: Call<VM>,
+        /// When the call ended, relative to when tracing started
+        exited: Duration,
+        /// The value returned, or `None` if the method is `void` or threw an exception
+        return_value: Option<TracedValue<VM>>,
+        /// Whether the method threw an exception rather than returning. This is only noticed
+        /// once a traced method in one of its callers is left or calls another, which is when
+        /// it's considered to have ended.
+        threw: bool,
+    },
+}
+
+impl<VM: VirtualMachine + ?Sized> TraceEvent<VM> {
+    /// Gets the call this event is about
+    pub fn call(&self) -> &Call<VM> {
+        match self {
+            TraceEvent::Enter { call } | TraceEvent::Exit { call, .. } => call,
+        }
+    }
+
+    /// Gets when this event happened, relative to when tracing started
+    pub fn timestamp(&self) -> Duration {
+        match self {
+            TraceEvent::Enter { call } => call.entered,
+            TraceEvent::Exit { exited, .. } => *exited,
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Clone for TraceEvent<VM> {
+    fn clone(&self) -> Self {
+        match self {
+            TraceEvent::Enter { call } => TraceEvent::Enter { call: call.clone() },
+            TraceEvent::Exit {
+                call,
+                exited,
+                return_value,
+                threw,
+            } => TraceEvent::Exit {
+                call: call.clone(),
+                exited: *exited,
+                return_value: return_value.clone(),
+                threw: *threw,
+            },
+        }
+    }
+}
+
+/// Traces the methods called in the target VM, as a [Stream] of [TraceEvent]s.
+///
+/// Created by [Tracer::start]. The method entry and exit events it requests are never removed
+/// from an [EventQueue](crate::event::EventQueue), and the threads they suspend are resumed by
+/// the tracer, so the stream must be polled for the target VM to make progress. Tracing goes on
+/// until the tracer is [stopped](Self::stop).
+pub struct Tracer<VM: VirtualMachine + ?Sized> {
+    requests: Vec<EventRequest<VM>>,
+    events: BoxStream<'static, TraceEvent<VM>>,
+    vm: Weak<VM>,
+}
+
+impl<VM: VirtualMachine + ?Sized> Tracer<VM> {
+    /// Starts tracing the methods called in a virtual machine.
+    ///
+    /// A method entry and a method exit request are created for every combination of class
+    /// pattern and thread in the options, since jdwp only lets a request have one of each.
+    pub async fn start(vm: &VM, options: TraceOptions<VM>) -> Result<Self, EventRequestError> {
+        let manager = vm.event_request_manager();
+        // subscribed before the requests are enabled, so that none of their events are missed
+        let source = vm.client().events();
+        let patterns = match options.class_patterns.as_slice() {
+            [] => vec![None],
+            patterns => patterns.iter().map(Some).collect(),
+        };
+        let threads = match options.threads.as_slice() {
+            [] => vec![None],
+            threads => threads.iter().map(Some).collect(),
+        };
+        let mut requests: Vec<EventRequest<VM>> = vec![];
+        let created = async {
+            for pattern in &patterns {
+                for thread in &threads {
+                    let entry = manager.create_method_entry_request();
+                    requests.push(entry.clone().into());
+                    let exit = manager.create_method_exit_with_return_value_request();
+                    requests.push(exit.clone().into());
+                    if let Some(pattern) = pattern {
+                        entry.add_class_pattern_filter(pattern.as_str())?;
+                        exit.add_class_pattern_filter(pattern.as_str())?;
+                    }
+                    for exclusion in &options.exclusions {
+                        entry.add_class_exclusion_filter(exclusion.as_str())?;
+                        exit.add_class_exclusion_filter(exclusion.as_str())?;
+                    }
+                    if let Some(thread) = thread {
+                        entry.add_thread_filter(thread)?;
+                        exit.add_thread_filter(thread)?;
+                    }
+                }
+            }
+            for request in &requests {
+                // the thread is suspended so its frames can be read
+                request.set_suspend_policy(SuspendPolicy::EventThread)?;
+                request.enable().await?;
+                manager.handle_events(request);
+            }
+            Ok::<_, EventRequestError>(())
+        }
+        .await;
+        if let Err(e) = created {
+            manager.delete_event_requests(requests).await?;
+            return Err(e);
+        }
+
+        let state = TraceState {
+            source,
+            requests: requests
+                .iter()
+                .filter_map(|request| Some((request.kind(), request.request_id()?)))
+                .collect(),
+            arguments: options.arguments,
+            started: Instant::now(),
+            next_call_id: 0,
+            threads: HashMap::new(),
+            methods: HashMap::new(),
+            pending: VecDeque::new(),
+            vm: vm.virtual_machine(),
+        };
+        let events = futures::stream::unfold(state, |mut state| async move {
+            let event = state.next().await?;
+            Some((event, state))
+        })
+        .boxed();
+        Ok(Self {
+            requests,
+            events,
+            vm: vm.virtual_machine(),
+        })
+    }
+
+    /// Gets the method entry and exit requests used for tracing
+    pub fn requests(&self) -> &[EventRequest<VM>] {
+        &self.requests
+    }
+
+    /// Stops tracing, deleting every request used for it
+    pub async fn stop(self) -> Result<(), EventRequestError> {
+        let vm = upgrade(&self.vm)?;
+        vm.event_request_manager()
+            .delete_event_requests(self.requests)
+            .await
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Stream for Tracer<VM> {
+    type Item = TraceEvent<VM>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_next_unpin(cx)
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> Debug for Tracer<VM> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tracer")
+            .field("requests", &self.requests)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A traced method, cached since every call of it needs the same information
+struct TracedMethod<VM: VirtualMachine + ?Sized> {
+    method: Method<VM>,
+    class_name: String,
+    /// The arguments of the method, read the first time they're needed
+    arguments: Option<Vec<LocalVariable<VM>>>,
+}
+
+/// A call that hasn't ended yet, with the number of frames its thread had when it was made
+struct OpenCall<VM: VirtualMachine + ?Sized> {
+    call: Call<VM>,
+    frame_count: Int,
+}
+
+/// The traced calls of a thread that haven't ended yet, outermost first
+struct ThreadTrace<VM: VirtualMachine + ?Sized> {
+    name: String,
+    calls: Vec<OpenCall<VM>>,
+}
+
+struct TraceState<VM: VirtualMachine + ?Sized> {
+    source: EventStream,
+    requests: HashSet<(EventKind, Int)>,
+    arguments: bool,
+    started: Instant,
+    next_call_id: u64,
+    threads: HashMap<ThreadId, ThreadTrace<VM>>,
+    methods: HashMap<(ReferenceTypeId, MethodId), TracedMethod<VM>>,
+    pending: VecDeque<TraceEvent<VM>>,
+    vm: Weak<VM>,
+}
+
+impl<VM: VirtualMachine + ?Sized> TraceState<VM> {
+    /// Waits for the next trace event, or `None` once the target VM disconnects
+    async fn next(&mut self) -> Option<TraceEvent<VM>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            let composite = self.source.next().await?;
+            let total = composite.events.len();
+            let ours = composite
+                .events
+                .into_iter()
+                .filter(|event| match event.request_id() {
+                    Some(request_id) => self.requests.contains(&(event.kind(), request_id)),
+                    None => false,
+                })
+                .collect::<Vec<_>>();
+            let mut suspended = None;
+            for event in &ours {
+                let (thread, result) = match event {
+                    JdwpEvent::MethodEntry {
+                        thread, location, ..
+                    } => (*thread, self.enter(*thread, *location).await),
+                    JdwpEvent::MethodExitWithReturnValue {
+                        thread,
+                        location,
+                        value,
+                        ..
+                    } => (*thread, self.exit(*thread, *location, value.clone()).await),
+                    _ => continue,
+                };
+                if let Err(e) = result {
+                    warn!("could not trace {event:?}: {e}");
+                }
+                suspended = Some(thread);
+            }
+            // anything else in the composite is left for an event queue, which resumes it
+            if let Some(thread) = suspended.filter(|_| ours.len() == total) {
+                if let Err(e) = ThreadReference::new(thread, &self.vm).resume().await {
+                    warn!("could not resume traced thread {thread:?}: {e}");
+                }
+            }
+        }
+    }
+
+    /// Records that a method was entered
+    async fn enter(
+        &mut self,
+        thread_id: ThreadId,
+        location: jdwp_types::Location,
+    ) -> io::Result<()> {
+        let entered = self.started.elapsed();
+        let thread = ThreadReference::new(thread_id, &self.vm);
+        let frame_count = thread.frame_count().await?;
+        self.thread_trace(&thread).await?;
+        // calls made at the same depth or deeper have been left by throwing an exception
+        self.unwind(thread_id, |open| open >= frame_count, entered);
+        let location = Location::new(location, &self.vm);
+        let arguments = if self.arguments {
+            match self.read_arguments(&thread, &location).await {
+                Ok(arguments) => arguments,
+                Err(e) => {
+                    debug!("could not read the arguments of a traced call: {e}");
+                    vec![]
+                }
+            }
+        } else {
+            vec![]
+        };
+        let method = self.method(&location).await?;
+        let (method, class_name) = (method.method.clone(), method.class_name.clone());
+        let id = self.next_call_id;
+        self.next_call_id += 1;
+        let trace = self
+            .threads
+            .get_mut(&thread_id)
+            .expect("thread trace was just created");
+        let call = Call {
+            id,
+            parent: trace.calls.last().map(|open| open.call.id),
+            depth: trace.calls.len(),
+            thread,
+            thread_name: trace.name.clone(),
+            class_name,
+            method,
+            arguments,
+            entered,
+        };
+        trace.calls.push(OpenCall {
+            call: call.clone(),
+            frame_count,
+        });
+        self.pending.push_back(TraceEvent::Enter { call });
+        Ok(())
+    }
+
+    /// Records that a method returned
+    async fn exit(
+        &mut self,
+        thread_id: ThreadId,
+        location: jdwp_types::Location,
+        value: jdwp_types::Value,
+    ) -> io::Result<()> {
+        let exited = self.started.elapsed();
+        let thread = ThreadReference::new(thread_id, &self.vm);
+        let frame_count = thread.frame_count().await?;
+        self.thread_trace(&thread).await?;
+        // calls made deeper have been left by throwing an exception
+        self.unwind(thread_id, |open| open > frame_count, exited);
+        let value = Value::new(value, &self.vm);
+        let return_value = match value {
+            Value::Void => None,
+            value => Some(TracedValue::read(value).await?),
+        };
+        let trace = self
+            .threads
+            .get_mut(&thread_id)
+            .expect("thread trace was just created");
+        match trace.calls.last() {
+            Some(open)
+                if open.frame_count == frame_count && open.call.method.id() == location.method =>
+            {
+                let open = trace.calls.pop().expect("there is a last call");
+                self.pending.push_back(TraceEvent::Exit {
+                    call: open.call,
+                    exited,
+                    return_value,
+                    threw: false,
+                });
+            }
+            _ => debug!("ignoring the exit of a call made before tracing started"),
+        }
+        Ok(())
+    }
+
+    /// Ends the open calls of a thread whose frame count matches, which were left by throwing an
+    /// exception
+    fn unwind(&mut self, thread_id: ThreadId, ended: impl Fn(Int) -> bool, exited: Duration) {
+        let Some(trace) = self.threads.get_mut(&thread_id) else {
+            return;
+        };
+        while trace
+            .calls
+            .last()
+            .is_some_and(|open| ended(open.frame_count))
+        {
+            let open = trace.calls.pop().expect("there is a last call");
+            self.pending.push_back(TraceEvent::Exit {
+                call: open.call,
+                exited,
+                return_value: None,
+                threw: true,
+            });
+        }
+    }
+
+    /// Starts tracing a thread if it hasn't been already
+    async fn thread_trace(&mut self, thread: &ThreadReference<VM>) -> io::Result<()> {
+        if let Entry::Vacant(entry) = self.threads.entry(thread.id()) {
+            let name = thread.name().await?;
+            entry.insert(ThreadTrace {
+                name,
+                calls: vec![],
+            });
+        }
+        Ok(())
+    }
+
+    /// Gets the method a location is in
+    async fn method(&mut self, location: &Location<VM>) -> io::Result<&mut TracedMethod<VM>> {
+        let key = (location.declaring_type_id(), location.method_id());
+        Ok(match self.methods.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let method = location.method().await?;
+                let class_name = location.declaring_type().await?.name();
+                entry.insert(TracedMethod {
+                    method,
+                    class_name,
+                    arguments: None,
+                })
+            }
+        })
+    }
+
+    /// Reads the arguments of the method a thread just entered from its frame
+    async fn read_arguments(
+        &mut self,
+        thread: &ThreadReference<VM>,
+        location: &Location<VM>,
+    ) -> io::Result<Vec<(String, TracedValue<VM>)>> {
+        let method = self.method(location).await?;
+        let variables = match &method.arguments {
+            Some(variables) => variables.clone(),
+            None => {
+                let variables = method
+                    .method
+                    .variables()
+                    .await?
+                    .into_iter()
+                    .filter(|variable| variable.is_argument() && variable.name() != "this")
+                    .collect::<Vec<_>>();
+                method.arguments = Some(variables.clone());
+                variables
+            }
+        };
+        if variables.is_empty() {
+            return Ok(vec![]);
+        }
+        let frame = thread.frame(0).await?;
+        let values = frame.get_values(&variables).await?;
+        let mut arguments = Vec::with_capacity(values.len());
+        for (variable, value) in variables.iter().zip(values) {
+            arguments.push((variable.name().to_string(), TracedValue::read(value).await?));
+        }
+        Ok(arguments)
+    }
+}
@@ -0,0 +1,189 @@
+//! Putting the calls of a trace together into a tree per thread
+
+use crate::trace::{Call, TraceEvent, TracedValue};
+use crate::VirtualMachine;
+use jdwp_types::ThreadId;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// A traced call, along with the traced calls made in it
+#[derive(Debug)]
+pub struct CallNode<VM: VirtualMachine + ?Sized> {
+    call: Call<VM>,
+    exited: Option<Duration>,
+    return_value: Option<TracedValue<VM>>,
+    threw: bool,
+    children: Vec<CallNode<VM>>,
+}
+
+impl<VM: VirtualMachine + ?Sized> CallNode<VM> {
+    /// Gets the call
+    pub fn call(&self) -> &Call<VM> {
+        &self.call
+    }
+
+    /// Gets when the call ended, relative to when tracing started, or `None` if it hasn't yet
+    pub fn exited(&self) -> Option<Duration> {
+        self.exited
+    }
+
+    /// Gets how long the call took, or `None` if it hasn't ended yet
+    pub fn duration(&self) -> Option<Duration> {
+        self.exited
+            .map(|exited| exited.saturating_sub(self.call.entered()))
+    }
+
+    /// Gets the value the call returned, or `None` if it hasn't returned one
+    pub fn return_value(&self) -> Option<&TracedValue<VM>> {
+        self.return_value.as_ref()
+    }
+
+    /// Whether the call was left by throwing an exception
+    pub fn threw(&self) -> bool {
+        self.threw
+    }
+
+    /// Gets the traced calls made in this call, in the order they were made
+    pub fn children(&self) -> &[CallNode<VM>] {
+        &self.children
+    }
+
+    fn fmt_indented(&self, f: &mut Formatter<'_>, indent: usize) -> std::fmt::Result {
+        let arguments = self
+            .call
+            .arguments()
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "{:indent$}{}({arguments})", "", self.call.name())?;
+        if self.threw {
+            write!(f, " threw")?;
+        } else if let Some(return_value) = &self.return_value {
+            write!(f, " = {return_value}")?;
+        }
+        if let Some(duration) = self.duration() {
+            write!(f, " [{duration:?}]")?;
+        }
+        writeln!(f)?;
+        for child in &self.children {
+            child.fmt_indented(f, indent + 2)?;
+        }
+        Ok(())
+    }
+}
+
+/// The calls of a trace as a tree per thread, built by [recording](Self::record) each
+/// [TraceEvent] in turn
+#[derive(Debug)]
+pub struct CallTree<VM: VirtualMachine + ?Sized> {
+    /// The threads in the order their first call was traced, with their calls
+    threads: Vec<(ThreadId, String, Vec<CallNode<VM>>)>,
+    /// The path of child indices to each open call, by thread
+    open: HashMap<ThreadId, Vec<usize>>,
+}
+
+impl<VM: VirtualMachine + ?Sized> Default for CallTree<VM> {
+    fn default() -> Self {
+        Self {
+            threads: vec![],
+            open: HashMap::new(),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> CallTree<VM> {
+    /// Creates an empty call tree
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a trace event to the tree
+    pub fn record(&mut self, event: &TraceEvent<VM>) {
+        let thread_id = event.call().thread().id();
+        let index = match self.threads.iter().position(|(id, ..)| *id == thread_id) {
+            Some(index) => index,
+            None => {
+                let name = event.call().thread_name().to_string();
+                self.threads.push((thread_id, name, vec![]));
+                self.threads.len() - 1
+            }
+        };
+        let roots = &mut self.threads[index].2;
+        let path = self.open.entry(thread_id).or_default();
+        match event {
+            TraceEvent::Enter { call } => {
+                let siblings = match node_at(roots, path) {
+                    Some(parent) => &mut parent.children,
+                    None => roots,
+                };
+                siblings.push(CallNode {
+                    call: call.clone(),
+                    exited: None,
+                    return_value: None,
+                    threw: false,
+                    children: vec![],
+                });
+                path.push(siblings.len() - 1);
+            }
+            TraceEvent::Exit {
+                call,
+                exited,
+                return_value,
+                threw,
+            } => {
+                let Some(node) = node_at(roots, path) else {
+                    return;
+                };
+                if node.call.id() != call.id() {
+                    return;
+                }
+                node.exited = Some(*exited);
+                node.return_value = return_value.clone();
+                node.threw = *threw;
+                path.pop();
+            }
+        }
+    }
+
+    /// Gets the names of the threads calls were traced in, in the order their first call was
+    pub fn threads(&self) -> impl Iterator<Item = (ThreadId, &str)> {
+        self.threads
+            .iter()
+            .map(|(id, name, _)| (*id, name.as_str()))
+    }
+
+    /// Gets the outermost traced calls made in a thread, in the order they were made
+    pub fn roots(&self, thread: ThreadId) -> &[CallNode<VM>] {
+        self.threads
+            .iter()
+            .find(|(id, ..)| *id == thread)
+            .map_or(&[], |(_, _, roots)| roots)
+    }
+}
+
+/// Follows a path of child indices from the roots, or gets `None` for an empty path
+fn node_at<'a, VM: VirtualMachine + ?Sized>(
+    roots: &'a mut [CallNode<VM>],
+    path: &[usize],
+) -> Option<&'a mut CallNode<VM>> {
+    let (first, rest) = path.split_first()?;
+    let mut node = &mut roots[*first];
+    for index in rest {
+        node = &mut node.children[*index];
+    }
+    Some(node)
+}
+
+impl<VM: VirtualMachine + ?Sized> Display for CallTree<VM> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (_, name, roots) in &self.threads {
+            writeln!(f, "{name}:")?;
+            for root in roots {
+                root.fmt_indented(f, 2)?;
+            }
+        }
+        Ok(())
+    }
+}
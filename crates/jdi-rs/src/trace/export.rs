@@ -0,0 +1,187 @@
+//! Exporting traces as JSON lines, or in the Chrome trace event format
+
+use crate::trace::{TraceEvent, TracedValue};
+use crate::{Value, VirtualMachine};
+use jdwp_types::ThreadId;
+use serde_json::{json, Map};
+use std::collections::HashSet;
+use std::io;
+use std::io::Write;
+
+impl<VM: VirtualMachine + ?Sized> TracedValue<VM> {
+    /// Converts the value to JSON. Primitives become numbers or booleans, `null` becomes `null`,
+    /// and objects become the way they're displayed.
+    pub fn to_json(&self) -> serde_json::Value {
+        match &self.value {
+            Value::Boolean(b) => json!(b),
+            Value::Byte(b) => json!(*b as i8),
+            Value::Char(c) => json!(char::from_u32(*c as u32)
+                .map(String::from)
+                .unwrap_or_else(|| self.display.clone())),
+            Value::Short(s) => json!(s),
+            Value::Int(i) => json!(i),
+            Value::Long(l) => json!(l),
+            // NaN and infinities aren't numbers in JSON, so become null
+            Value::Float(f) => json!(f),
+            Value::Double(d) => json!(d),
+            Value::Void | Value::Object(None) => serde_json::Value::Null,
+            Value::Object(Some(_)) => json!(self.display),
+        }
+    }
+}
+
+impl<VM: VirtualMachine + ?Sized> TraceEvent<VM> {
+    /// Converts the event to a JSON object, such as
+    /// `{"event":"exit","id":3,"parent":1,"depth":1,"thread":"main","method":"com.acme.Foo.bar",
+    /// "timestamp_us":1520,"duration_us":310,"return_value":42,"threw":false}`
+    pub fn to_json(&self) -> serde_json::Value {
+        let call = self.call();
+        let mut object = Map::new();
+        let kind = match self {
+            TraceEvent::Enter { .. } => "enter",
+            TraceEvent::Exit { .. } => "exit",
+        };
+        object.insert("event".to_string(), json!(kind));
+        object.insert("id".to_string(), json!(call.id()));
+        object.insert("parent".to_string(), json!(call.parent()));
+        object.insert("depth".to_string(), json!(call.depth()));
+        object.insert("thread".to_string(), json!(call.thread_name()));
+        object.insert("method".to_string(), json!(call.name()));
+        object.insert(
+            "timestamp_us".to_string(),
+            json!(self.timestamp().as_micros() as u64),
+        );
+        match self {
+            TraceEvent::Enter { call } => {
+                object.insert("arguments".to_string(), arguments(call.arguments()));
+            }
+            TraceEvent::Exit {
+                call,
+                exited,
+                return_value,
+                threw,
+            } => {
+                let duration = exited.saturating_sub(call.entered());
+                object.insert(
+                    "duration_us".to_string(),
+                    json!(duration.as_micros() as u64),
+                );
+                object.insert(
+                    "return_value".to_string(),
+                    return_value
+                        .as_ref()
+                        .map_or(serde_json::Value::Null, TracedValue::to_json),
+                );
+                object.insert("threw".to_string(), json!(threw));
+            }
+        }
+        serde_json::Value::Object(object)
+    }
+}
+
+fn arguments<VM: VirtualMachine + ?Sized>(
+    arguments: &[(String, TracedValue<VM>)],
+) -> serde_json::Value {
+    serde_json::Value::Object(
+        arguments
+            .iter()
+            .map(|(name, value)| (name.clone(), value.to_json()))
+            .collect(),
+    )
+}
+
+/// Writes trace events as JSON lines, one [JSON object](TraceEvent::to_json) per event
+pub fn write_json_lines<'a, VM: VirtualMachine + ?Sized + 'a>(
+    mut out: impl Write,
+    events: impl IntoIterator<Item = &'a TraceEvent<VM>>,
+) -> io::Result<()> {
+    for event in events {
+        serde_json::to_writer(&mut out, &event.to_json())?;
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Writes trace events in the Chrome trace event format, as a JSON array of duration events that
+/// `chrome://tracing` and Perfetto can open.
+///
+/// Every call becomes a begin and an end event on the track of its thread, which is named the
+/// first time a call is written for it. [finish](Self::finish) must be called to end the array.
+#[derive(Debug)]
+pub struct ChromeTraceWriter<W: Write> {
+    out: W,
+    first: bool,
+    threads: HashSet<ThreadId>,
+}
+
+impl<W: Write> ChromeTraceWriter<W> {
+    /// The process every event is written for, since there's only one target VM
+    const PID: u64 = 1;
+
+    /// Starts writing a trace
+    pub fn new(mut out: W) -> io::Result<Self> {
+        out.write_all(b"[\n")?;
+        Ok(Self {
+            out,
+            first: true,
+            threads: HashSet::new(),
+        })
+    }
+
+    /// Writes a trace event
+    pub fn write<VM: VirtualMachine + ?Sized>(&mut self, event: &TraceEvent<VM>) -> io::Result<()> {
+        let call = event.call();
+        let tid = u64::from(call.thread().id());
+        if self.threads.insert(call.thread().id()) {
+            self.write_value(json!({
+                "name": "thread_name",
+                "ph": "M",
+                "pid": Self::PID,
+                "tid": tid,
+                "args": { "name": call.thread_name() },
+            }))?;
+        }
+        let mut value = json!({
+            "name": call.name(),
+            "cat": call.class_name(),
+            "ph": match event {
+                TraceEvent::Enter { .. } => "B",
+                TraceEvent::Exit { .. } => "E",
+            },
+            "ts": event.timestamp().as_micros() as u64,
+            "pid": Self::PID,
+            "tid": tid,
+        });
+        let args = match event {
+            TraceEvent::Enter { call } => arguments(call.arguments()),
+            TraceEvent::Exit {
+                return_value,
+                threw,
+                ..
+            } => json!({
+                "return_value": return_value
+                    .as_ref()
+                    .map_or(serde_json::Value::Null, TracedValue::to_json),
+                "threw": threw,
+            }),
+        };
+        value["args"] = args;
+        self.write_value(value)
+    }
+
+    fn write_value(&mut self, value: serde_json::Value) -> io::Result<()> {
+        if !self.first {
+            self.out.write_all(b",\n")?;
+        }
+        self.first = false;
+        serde_json::to_writer(&mut self.out, &value)?;
+        Ok(())
+    }
+
+    /// Ends the trace, returning what it was written to
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(b"\n]\n")?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
@@ -0,0 +1,72 @@
+use jdb_test_fixtures::JavaInstance;
+use jdi_rs::event::Event;
+use jdi_rs::*;
+use std::time::Duration;
+use test_log::test;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SOURCE: &str = "com/acme/Lines.java";
+
+#[test(tokio::test)]
+async fn test_deferred_breakpoint_in_anonymous_class() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Lines").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let manager = vm.event_request_manager();
+    let queue = vm.event_queue();
+
+    let start = queue.remove_timeout(TIMEOUT).await?.expect("no vm start");
+    let breakpoint = manager.set_breakpoint(SOURCE, 17).await?;
+    assert!(!breakpoint.is_resolved());
+    assert_eq!(
+        manager.class_prepare_requests(),
+        vec![breakpoint.class_prepare_request().clone()]
+    );
+    start.resume().await?;
+
+    // the class prepare events used to install the breakpoint never reach the queue
+    let hit = queue
+        .remove_timeout(TIMEOUT)
+        .await?
+        .expect("no breakpoint hit");
+    let [Event::Breakpoint(event)] = hit.events() else {
+        panic!("expected a breakpoint event but got {hit:?}");
+    };
+    let installed = breakpoint.breakpoints();
+    assert_eq!(installed.len(), 1);
+    assert_eq!(event.request(), Some(&installed[0]));
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_breakpoint_in_loaded_class() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Lines").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let manager = vm.event_request_manager();
+    let queue = vm.event_queue();
+
+    let start = queue.remove_timeout(TIMEOUT).await?.expect("no vm start");
+    let deferred = manager.set_breakpoint(SOURCE, 20).await?;
+    start.resume().await?;
+    let hit = queue
+        .remove_timeout(TIMEOUT)
+        .await?
+        .expect("no breakpoint hit");
+    assert!(matches!(hit.events(), [Event::Breakpoint(_)]));
+
+    let loaded = manager.set_breakpoint(SOURCE, 9).await?;
+    assert!(loaded.is_resolved());
+    manager.delete_line_breakpoint(&deferred).await?;
+    assert_eq!(manager.line_breakpoints(), vec![loaded.clone()]);
+    assert_eq!(manager.class_prepare_requests().len(), 1);
+    hit.resume().await?;
+
+    let hit = queue
+        .remove_timeout(TIMEOUT)
+        .await?
+        .expect("no breakpoint hit");
+    let [Event::Breakpoint(event)] = hit.events() else {
+        panic!("expected a breakpoint event but got {hit:?}");
+    };
+    assert_eq!(event.request(), loaded.breakpoints().first());
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use jdb_test_fixtures::JavaInstance;
+use jdi_rs::event::{BreakpointEvent, Event, EventQueue};
+use jdi_rs::expr::{EvalError, Expression};
+use jdi_rs::request::{BreakpointOptions, HitCondition, LogMessage};
+use jdi_rs::*;
+use std::time::Duration;
+use test_log::test;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SOURCE: &str = "com/acme/Counter.java";
+const INCREMENT_LINE: i32 = 13;
+
+/// Resumes the target VM with a breakpoint in `Counter.increment`, and waits for it to stop there
+async fn run_to_increment<VM: VirtualMachine>(
+    vm: &VM,
+    queue: &EventQueue<VM>,
+    options: BreakpointOptions,
+) -> eyre::Result<BreakpointEvent<VM>> {
+    let start = queue.remove_timeout(TIMEOUT).await?.expect("no vm start");
+    vm.event_request_manager()
+        .set_breakpoint_with(SOURCE, INCREMENT_LINE, options)
+        .await?;
+    start.resume().await?;
+    let hit = queue
+        .remove_timeout(TIMEOUT)
+        .await?
+        .expect("no breakpoint hit");
+    let [Event::Breakpoint(event)] = hit.events() else {
+        panic!("expected a breakpoint event but got {hit:?}");
+    };
+    Ok(event.clone())
+}
+
+async fn evaluate<VM: VirtualMachine>(
+    frame: &StackFrame<VM>,
+    expression: &str,
+) -> Result<Value<VM>, EvalError> {
+    Expression::parse(expression)?.evaluate(frame).await
+}
+
+#[test(tokio::test)]
+async fn test_breakpoint_condition() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Counter").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let queue = vm.event_queue();
+    let options = BreakpointOptions::new().when(Expression::parse("by == 2 && this.count >= 4")?);
+    let hit = run_to_increment(&*vm, &queue, options).await?;
+
+    // 0 + 1 + 2 + 0 + 1 were added before the second time by is 2
+    let frame = hit.thread().frame(0).await?;
+    assert_eq!(evaluate(&frame, "by").await?, Value::Int(2));
+    assert_eq!(evaluate(&frame, "count").await?, Value::Int(4));
+    assert_eq!(
+        evaluate(&frame, r#"name == "clicks" && !(name != DEFAULT_NAME)"#).await?,
+        Value::Boolean(true)
+    );
+    assert_eq!(
+        evaluate(&frame, "count >= 4L && by < 2.5 && -by <= -2").await?,
+        Value::Boolean(true)
+    );
+    assert!(matches!(
+        evaluate(&frame, "missing").await,
+        Err(EvalError::UnknownName(name)) if name == "missing"
+    ));
+    assert!(matches!(
+        evaluate(&frame, "this.missing").await,
+        Err(EvalError::NoSuchField { .. })
+    ));
+    assert!(matches!(
+        evaluate(&frame, "by && true").await,
+        Err(EvalError::Type(_))
+    ));
+
+    let line_breakpoint = &vm.event_request_manager().line_breakpoints()[0];
+    assert_eq!(line_breakpoint.hit_count(), 1);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_hit_condition() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Counter").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let queue = vm.event_queue();
+    let options = BreakpointOptions::new()
+        .when(Expression::parse("by == 1")?)
+        .on_hit("== 3".parse()?);
+    assert_eq!(options.hit_condition(), Some(HitCondition::Equal(3)));
+    let hit = run_to_increment(&*vm, &queue, options).await?;
+
+    // by is 1 for the third time when i is 7, after 0 + 1 + 2 + 0 + 1 + 2 + 0 was added
+    let frame = hit.thread().frame(0).await?;
+    assert_eq!(evaluate(&frame, "count").await?, Value::Int(6));
+    let line_breakpoint = &vm.event_request_manager().line_breakpoints()[0];
+    assert_eq!(line_breakpoint.hit_count(), 3);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_logpoint() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Counter").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let manager = vm.event_request_manager();
+    let queue = vm.event_queue();
+    let mut messages = manager.logpoint_messages();
+
+    let start = queue.remove_timeout(TIMEOUT).await?.expect("no vm start");
+    let options = BreakpointOptions::new().log(LogMessage::parse(
+        "{{{name}}} count={count} by={by} {this}",
+    )?);
+    manager
+        .set_breakpoint_with(SOURCE, INCREMENT_LINE, options)
+        .await?;
+    start.resume().await?;
+
+    // logpoints never stop, but are only evaluated while events are being removed
+    assert!(queue
+        .remove_timeout(Duration::from_millis(500))
+        .await?
+        .is_none());
+    let mut logged = vec![];
+    for _ in 0..3 {
+        logged.push(messages.try_recv()?);
+    }
+    let (_, object) = logged[0].message().split_once("by=0 ").unwrap();
+    assert!(object.starts_with("instance of com.acme.Counter(id="));
+    assert_eq!(
+        logged
+            .iter()
+            .map(|logged| logged.message().to_string())
+            .collect::<Vec<_>>(),
+        vec![
+            format!("{{clicks}} count=0 by=0 {object}"),
+            format!("{{clicks}} count=0 by=1 {object}"),
+            format!("{{clicks}} count=1 by=2 {object}"),
+        ]
+    );
+    assert_eq!(
+        logged[0].location().line_number().await?,
+        Some(INCREMENT_LINE)
+    );
+    Ok(())
+}
@@ -1,7 +1,5 @@
 use jdb_test_fixtures::JavaInstance;
-use jdi_rs::connect::spi::TransportService;
-use jdi_rs::connect::{Connector, Transport};
-use jdi_rs::{VirtualMachine, VirtualMachineManager};
+use jdi_rs::VirtualMachineManager;
 use test_log::test;
 
 #[test(tokio::test)]
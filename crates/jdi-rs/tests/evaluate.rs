@@ -0,0 +1,252 @@
+use jdb_test_fixtures::JavaInstance;
+use jdi_rs::event::Event;
+use jdi_rs::expr::{EvalError, Expression};
+use jdi_rs::*;
+use std::sync::Arc;
+use std::time::Duration;
+use test_log::test;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SOURCE: &str = "com/acme/Orders.java";
+const SUMMARY_LINE: i32 = 75;
+
+/// Starts `com.acme.Orders` and waits for it to stop in `process`, once `first` and `count` are
+/// assigned
+async fn stop_in_process() -> eyre::Result<(
+    JavaInstance,
+    Arc<impl VirtualMachine>,
+    ThreadReference<impl VirtualMachine>,
+)> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Orders").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let queue = vm.event_queue();
+    let start = queue.remove_timeout(TIMEOUT).await?.expect("no vm start");
+    vm.event_request_manager()
+        .set_breakpoint(SOURCE, SUMMARY_LINE)
+        .await?;
+    start.resume().await?;
+    let hit = queue
+        .remove_timeout(TIMEOUT)
+        .await?
+        .expect("no breakpoint hit");
+    let [Event::Breakpoint(event)] = hit.events() else {
+        panic!("expected a breakpoint event but got {hit:?}");
+    };
+    Ok((jvm_instance, vm, event.thread().clone()))
+}
+
+/// Evaluates an expression in the top frame of a thread, which is fetched again every time since
+/// invoking methods invalidates it
+async fn evaluate<VM: VirtualMachine>(
+    thread: &ThreadReference<VM>,
+    expression: &str,
+) -> Result<Value<VM>, EvalError> {
+    thread.frame(0).await?.evaluate(expression).await
+}
+
+async fn string<VM: VirtualMachine>(
+    thread: &ThreadReference<VM>,
+    expression: &str,
+) -> eyre::Result<String> {
+    match evaluate(thread, expression).await? {
+        Value::Object(Some(object)) => Ok(object.string_value().await?),
+        other => panic!("{expression} evaluated to {other:?} rather than a string"),
+    }
+}
+
+#[test(tokio::test)]
+async fn test_evaluate() -> eyre::Result<()> {
+    let (_jvm_instance, _vm, thread) = stop_in_process().await?;
+
+    // method calls, and reading locals after a method was invoked
+    assert_eq!(
+        evaluate(&thread, "order.getItems().size() > 3").await?,
+        Value::Boolean(true)
+    );
+    assert_eq!(
+        evaluate(&thread, "order.getItems().size() + count").await?,
+        Value::Int(8)
+    );
+
+    // overload resolution
+    assert_eq!(string(&thread, "order.describe(count)").await?, "int");
+    assert_eq!(string(&thread, "order.describe('c')").await?, "int");
+    assert_eq!(string(&thread, "order.describe(1L)").await?, "long");
+    assert_eq!(string(&thread, r#"order.describe("x")"#).await?, "string");
+    assert_eq!(string(&thread, "order.describe(first)").await?, "object");
+    assert_eq!(
+        string(&thread, "order.describe(order.discount)").await?,
+        "object"
+    );
+    assert_eq!(evaluate(&thread, "twice(21)").await?, Value::Int(42));
+
+    // arrays
+    assert_eq!(evaluate(&thread, "order.ratings[1]").await?, Value::Int(3));
+    assert_eq!(
+        evaluate(&thread, "order.ratings.length").await?,
+        Value::Int(3)
+    );
+
+    // arithmetic and string concatenation
+    assert_eq!(evaluate(&thread, "count * 2 + 1").await?, Value::Int(9));
+    assert_eq!(evaluate(&thread, "7 / 2 - 7 % 2").await?, Value::Int(2));
+    assert_eq!(evaluate(&thread, "7 / 2.0").await?, Value::Double(3.5));
+    assert_eq!(evaluate(&thread, "1.5f + 1").await?, Value::Float(2.5));
+    assert_eq!(
+        evaluate(&thread, "order.discount + 1").await?,
+        Value::Long(11)
+    );
+    assert_eq!(
+        evaluate(&thread, "Integer.MAX_VALUE + 1").await?,
+        Value::Int(i32::MIN)
+    );
+    assert_eq!(
+        string(&thread, r#""n=" + count + ", " + first + ' ' + 1e10"#).await?,
+        "n=4, 3 x apple 1.0E10"
+    );
+    assert_eq!(
+        string(&thread, r#"count > 3 ? "many" : "few""#).await?,
+        "many"
+    );
+
+    // casts and instanceof
+    assert_eq!(evaluate(&thread, "(int) 3.9").await?, Value::Int(3));
+    assert_eq!(evaluate(&thread, "(long) count").await?, Value::Long(4));
+    assert_eq!(evaluate(&thread, "(char) 65").await?, Value::Char(65));
+    assert_eq!(
+        evaluate(&thread, "((Item) first).quantity").await?,
+        Value::Int(3)
+    );
+    assert_eq!(
+        evaluate(
+            &thread,
+            "first instanceof Orders.Item && !(first instanceof String)"
+        )
+        .await?,
+        Value::Boolean(true)
+    );
+    assert_eq!(
+        evaluate(
+            &thread,
+            "order.getItems() instanceof java.util.List && order.ratings instanceof int[]"
+        )
+        .await?,
+        Value::Boolean(true)
+    );
+
+    // static fields and methods
+    assert_eq!(evaluate(&thread, "created").await?, Value::Int(1));
+    assert_eq!(
+        evaluate(&thread, "com.acme.Orders.created").await?,
+        Value::Int(1)
+    );
+    assert_eq!(string(&thread, r#"label("run")"#).await?, "run1");
+    assert_eq!(
+        evaluate(&thread, r#"Integer.parseInt("12") + 1"#).await?,
+        Value::Int(13)
+    );
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_evaluate_errors() -> eyre::Result<()> {
+    let (_jvm_instance, _vm, thread) = stop_in_process().await?;
+
+    assert!(matches!(
+        evaluate(&thread, r#"order.fail("boom")"#).await,
+        Err(EvalError::Thrown { exception, message })
+            if exception == "java.lang.IllegalStateException" && message.as_deref() == Some("boom")
+    ));
+    assert!(matches!(
+        evaluate(&thread, "order.gift.name").await,
+        Err(EvalError::NullPointer(target)) if target == "order.gift"
+    ));
+    assert!(matches!(
+        evaluate(&thread, "order.ratings[3]").await,
+        Err(EvalError::IndexOutOfBounds {
+            index: 3,
+            length: 3
+        })
+    ));
+    assert!(matches!(
+        evaluate(&thread, "count / 0").await,
+        Err(EvalError::DivideByZero)
+    ));
+    assert!(matches!(
+        evaluate(&thread, "(String) first").await,
+        Err(EvalError::ClassCast { from, to }) if from == "com.acme.Orders$Item" && to == "String"
+    ));
+    assert!(matches!(
+        evaluate(&thread, "order.describe(null)").await,
+        Err(EvalError::AmbiguousMethod { .. })
+    ));
+    assert!(matches!(
+        evaluate(&thread, "order.describe(1, 2)").await,
+        Err(EvalError::NoSuchMethod { type_name, method })
+            if type_name == "com.acme.Orders$Order" && method == "describe(int, int)"
+    ));
+    assert!(matches!(
+        evaluate(&thread, "order.getItems(").await,
+        Err(EvalError::Parse(_))
+    ));
+
+    // the frame is still usable after a method threw
+    assert_eq!(evaluate(&thread, "count").await?, Value::Int(4));
+    Ok(())
+}
+
+/// Assigns the value of an expression in the top frame of a thread
+async fn assign<VM: VirtualMachine>(
+    thread: &ThreadReference<VM>,
+    variable: &str,
+    value: &str,
+) -> Result<Value<VM>, EvalError> {
+    let frame = thread.frame(0).await?;
+    Expression::parse(variable)?
+        .assign(&frame, &Expression::parse(value)?)
+        .await
+}
+
+#[test(tokio::test)]
+async fn test_assign() -> eyre::Result<()> {
+    let (_jvm_instance, _vm, thread) = stop_in_process().await?;
+
+    // locals, with the value evaluated before it's assigned
+    assert_eq!(assign(&thread, "count", "count * 2").await?, Value::Int(8));
+    assert_eq!(evaluate(&thread, "count").await?, Value::Int(8));
+    assign(&thread, "first", r#""plum""#).await?;
+    assert_eq!(string(&thread, "first").await?, "plum");
+
+    // static fields, instance fields and array components
+    assign(&thread, "created", "40 + 2").await?;
+    assert_eq!(evaluate(&thread, "Orders.created").await?, Value::Int(42));
+    assign(&thread, "order.gift", "order.getItems().get(1)").await?;
+    assert_eq!(string(&thread, "order.gift.name").await?, "pear");
+    assign(&thread, "order.ratings[2]", "'a'").await?;
+    assert_eq!(
+        evaluate(&thread, "order.ratings[2]").await?,
+        Value::Int('a' as i32)
+    );
+
+    assert!(matches!(
+        assign(&thread, "count", "true").await,
+        Err(EvalError::Type(message)) if message == "cannot assign boolean to int"
+    ));
+    assert!(matches!(
+        assign(&thread, "order.gift", r#""fig""#).await,
+        Err(EvalError::Type(_))
+    ));
+    assert!(matches!(
+        assign(&thread, "order.discount", "null").await,
+        Err(EvalError::Type(message)) if message == "cannot assign a value to final field discount"
+    ));
+    assert!(matches!(
+        assign(&thread, "order.ratings[3]", "1").await,
+        Err(EvalError::IndexOutOfBounds { .. })
+    ));
+    assert!(matches!(
+        assign(&thread, "count + 1", "1").await,
+        Err(EvalError::Type(_))
+    ));
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use futures::StreamExt;
+use jdb_test_fixtures::JavaInstance;
+use jdi_rs::event::Event;
+use jdi_rs::*;
+use jdwp_types::SuspendPolicy;
+use std::time::Duration;
+use test_log::test;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test(tokio::test)]
+async fn test_vm_start_then_class_prepare() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "BusyBeaver").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let queue = vm.event_queue();
+
+    let start = queue
+        .remove_timeout(TIMEOUT)
+        .await?
+        .expect("no vm start event");
+    assert_eq!(start.suspend_policy(), SuspendPolicy::All);
+    let [Event::VmStart(vm_start)] = start.events() else {
+        panic!("expected a vm start event but got {start:?}");
+    };
+    assert!(vm_start.request().is_none());
+
+    let request = vm.event_request_manager().create_class_prepare_request();
+    request.add_class_pattern_filter("BusyBeaver")?;
+    request.set_suspend_policy(SuspendPolicy::EventThread)?;
+    request.enable().await?;
+    start.resume().await?;
+
+    let prepared = queue
+        .remove_timeout(TIMEOUT)
+        .await?
+        .expect("no class prepare event");
+    assert_eq!(prepared.suspend_policy(), SuspendPolicy::EventThread);
+    let [Event::ClassPrepare(class_prepare)] = prepared.events() else {
+        panic!("expected a class prepare event but got {prepared:?}");
+    };
+    assert_eq!(class_prepare.reference_type().name(), "BusyBeaver");
+    assert_eq!(class_prepare.request(), Some(&request));
+    assert_eq!(prepared.events()[0].thread(), Some(class_prepare.thread()));
+    prepared.resume().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_event_queue_stream_ends_on_disconnect() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "BusyBeaver").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let mut queue = vm.event_queue();
+
+    let start = queue.next().await.expect("no vm start event");
+    assert!(matches!(start.events(), [Event::VmStart(_)]));
+    drop(jvm_instance);
+
+    let remaining = tokio::time::timeout(TIMEOUT, queue.by_ref().collect::<Vec<_>>()).await?;
+    let last = remaining.last().expect("no disconnect event");
+    assert!(matches!(last.events(), [Event::VmDisconnect(_)]));
+    assert!(vm.event_queue().remove().await.is_err());
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use jdb_test_fixtures::JavaInstance;
+use jdi_rs::request::EventRequestError;
+use jdi_rs::*;
+use jdwp_types::{StepDepth, StepSize, SuspendPolicy};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_class_prepare_request() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "BusyBeaver").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let manager = vm.event_request_manager();
+
+    let request = manager.create_class_prepare_request();
+    request.add_class_pattern_filter("BusyBeaver")?;
+    assert!(!request.is_enabled());
+    request.enable().await?;
+    assert!(request.is_enabled());
+    assert!(request.request_id().is_some());
+    assert!(matches!(
+        request.add_count_filter(1),
+        Err(EventRequestError::Enabled)
+    ));
+    assert_eq!(manager.class_prepare_requests(), vec![request.clone()]);
+
+    request.disable().await?;
+    assert!(!request.is_enabled());
+    manager.delete_event_request(request.clone()).await?;
+    assert!(manager.event_requests().is_empty());
+    assert!(matches!(
+        request.enable().await,
+        Err(EventRequestError::Deleted)
+    ));
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_breakpoint_request() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "BusyBeaver").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let manager = vm.event_request_manager();
+
+    let string_class = vm
+        .all_classes()
+        .await?
+        .into_iter()
+        .find(|r| r.name() == "java.lang.String")
+        .expect("no string class");
+    let length = string_class
+        .methods_by_name("length")
+        .await?
+        .into_iter()
+        .next()
+        .expect("no length method");
+    let location = length.location().await?.expect("no location for length");
+
+    let breakpoint = manager.create_breakpoint_request(&location);
+    breakpoint.add_count_filter(10)?;
+    breakpoint.enable().await?;
+    assert_eq!(breakpoint.location(), location);
+    assert_eq!(manager.breakpoint_requests().len(), 1);
+
+    let event_request = manager
+        .event_request(
+            breakpoint.kind(),
+            breakpoint
+                .request_id()
+                .expect("breakpoint should be enabled"),
+        )
+        .expect("could not find breakpoint by its id");
+    assert_eq!(event_request, breakpoint.clone().into());
+
+    manager.delete_all_breakpoints().await?;
+    assert!(manager.breakpoint_requests().is_empty());
+    assert!(!breakpoint.is_enabled());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_one_step_request_per_thread() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "BusyBeaver").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let manager = vm.event_request_manager();
+
+    let thread = vm
+        .all_threads()
+        .await?
+        .into_iter()
+        .next()
+        .expect("no threads");
+    let step = manager.create_step_request(&thread, StepSize::Line, StepDepth::Over)?;
+    step.add_class_exclusion_filter("java.*")?;
+    step.set_suspend_policy(SuspendPolicy::EventThread)?;
+    assert!(matches!(
+        manager.create_step_request(&thread, StepSize::Min, StepDepth::Into),
+        Err(EventRequestError::DuplicateStep(_))
+    ));
+
+    manager.delete_event_request(step).await?;
+    manager.create_step_request(&thread, StepSize::Min, StepDepth::Into)?;
+    Ok(())
+}
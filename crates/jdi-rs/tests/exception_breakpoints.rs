@@ -0,0 +1,127 @@
+use jdb_test_fixtures::JavaInstance;
+use jdi_rs::event::{Event, ExceptionEvent};
+use jdi_rs::*;
+use std::time::Duration;
+use test_log::test;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Removes the next event set, which must hold a single exception event, leaving it suspended
+async fn next_exception<VM: VirtualMachine>(
+    queue: &event::EventQueue<VM>,
+) -> eyre::Result<(event::EventSet<VM>, ExceptionEvent<VM>)> {
+    let set = queue.remove_timeout(TIMEOUT).await?.expect("no exception");
+    let [Event::Exception(event)] = set.events() else {
+        panic!("expected an exception event but got {set:?}");
+    };
+    let event = event.clone();
+    Ok((set, event))
+}
+
+#[test(tokio::test)]
+async fn test_break_on_caught_subclass() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Failures").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let queue = vm.event_queue();
+    let start = queue.remove_timeout(TIMEOUT).await?.expect("no vm start");
+
+    // the class isn't loaded yet
+    let breakpoint = vm
+        .event_request_manager()
+        .break_on_exception("com.acme.Failures$AppException", true, false)
+        .await?;
+    assert!(!breakpoint.is_resolved());
+    start.resume().await?;
+
+    let (set, event) = next_exception(&queue).await?;
+    assert!(breakpoint.is_resolved());
+    assert_eq!(
+        event.exception().reference_type().await?.name(),
+        "com.acme.Failures$RetryException"
+    );
+    assert_eq!(event.message().await?.as_deref(), Some("attempt 0"));
+    assert_eq!(event.location().method().await?.name(), "check");
+    let catch_location = event.catch_location().clone().expect("not caught");
+    assert_eq!(catch_location.method().await?.name(), "attempt");
+
+    let stack_trace = event.stack_trace().await?;
+    let frames = stack_trace
+        .iter()
+        .map(|element| element.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        frames,
+        [
+            "com.acme.Failures.check(Failures.java:18)",
+            "com.acme.Failures.attempt(Failures.java:25)",
+            "com.acme.Failures.main(Failures.java:37)",
+        ]
+    );
+    assert_eq!(stack_trace[0].file_name(), Some("Failures.java"));
+    set.resume().await?;
+
+    // odd attempts throw an IllegalStateException, which isn't an AppException
+    let (set, event) = next_exception(&queue).await?;
+    assert_eq!(event.message().await?.as_deref(), Some("attempt 2"));
+    set.resume().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_break_on_uncaught_pattern() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Failures").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let queue = vm.event_queue();
+    let start = queue.remove_timeout(TIMEOUT).await?.expect("no vm start");
+
+    // IllegalStateException is only ever caught, and both are loaded already
+    let manager = vm.event_request_manager();
+    let breakpoint = manager
+        .break_on_exception("java.lang.Illegal*", false, true)
+        .await?;
+    assert!(breakpoint.is_resolved());
+    start.resume().await?;
+
+    let (set, event) = next_exception(&queue).await?;
+    assert_eq!(
+        event.exception().reference_type().await?.name(),
+        "java.lang.IllegalArgumentException"
+    );
+    assert_eq!(event.message().await?.as_deref(), Some("worker failed"));
+    assert_eq!(event.thread().name().await?, "worker");
+    assert!(event.catch_location().is_none());
+    set.resume().await?;
+
+    manager.delete_exception_breakpoint(&breakpoint).await?;
+    assert!(manager.exception_breakpoints().is_empty());
+    assert!(manager.exception_requests().is_empty());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_break_on_any_exception() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Failures").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let queue = vm.event_queue();
+    let start = queue.remove_timeout(TIMEOUT).await?.expect("no vm start");
+
+    let breakpoint = vm
+        .event_request_manager()
+        .break_on_exception("*", true, true)
+        .await?;
+    assert!(breakpoint.class_prepare_request().is_none());
+    assert_eq!(breakpoint.requests().len(), 1);
+    assert!(breakpoint.requests()[0].exception().is_none());
+    start.resume().await?;
+
+    // the JVM throws exceptions of its own, so only the first of ours is checked
+    loop {
+        let (set, event) = next_exception(&queue).await?;
+        let message = event.message().await?;
+        set.resume().await?;
+        if message.as_deref() == Some("worker failed") {
+            break;
+        }
+    }
+    Ok(())
+}
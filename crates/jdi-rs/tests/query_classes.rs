@@ -17,3 +17,20 @@ async fn test_all_classes() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[test(tokio::test)]
+async fn test_classes_by_name() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "BusyBeaver").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+
+    let [string] = &vm.classes_by_name("java.lang.String").await?[..] else {
+        panic!("expected a single java.lang.String");
+    };
+    assert_eq!(string.signature(), "Ljava/lang/String;");
+    let [bytes] = &vm.classes_by_name("byte[]").await?[..] else {
+        panic!("expected a single byte[]");
+    };
+    assert_eq!(bytes.signature(), "[B");
+    assert!(vm.classes_by_name("com.acme.Missing").await?.is_empty());
+    Ok(())
+}
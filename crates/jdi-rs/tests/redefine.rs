@@ -0,0 +1,223 @@
+use futures::StreamExt;
+use jdb_test_fixtures::JavaInstance;
+use jdi_rs::redefine::{ClassFileWatcher, RedefineError, Rejection};
+use jdi_rs::*;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+use test_log::test;
+use tokio::io::AsyncBufReadExt;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const GREETER: &str = "Lcom/acme/Greeter;";
+
+/// The source of `com.acme.Greeter`, greeting with something else
+fn greeter_source(greeting: &str) -> String {
+    format!(
+        r#"package com.acme;
+
+public class Greeter {{
+    static String greeting() {{
+        return "{greeting}";
+    }}
+
+    public static void main(String[] args) throws InterruptedException {{
+        while (true) {{
+            System.out.println(greeting());
+            Thread.sleep(20);
+        }}
+    }}
+}}
+"#
+    )
+}
+
+/// Compiles a class in the `com.acme` package into `classes`, returning its class file
+fn compile(classes: &Path, name: &str, source: &str) -> eyre::Result<PathBuf> {
+    let sources = tempfile::tempdir()?;
+    let path = sources.path().join(format!("{name}.java"));
+    std::fs::write(&path, source)?;
+    let status = Command::new("javac")
+        .arg("-g")
+        .arg("-d")
+        .arg(classes)
+        .arg(&path)
+        .status()?;
+    eyre::ensure!(status.success(), "could not compile {name}");
+    Ok(classes.join("com/acme").join(format!("{name}.class")))
+}
+
+/// Reads the standard output of the java instance until it prints a line
+async fn wait_for_line(jvm_instance: &mut JavaInstance, expected: &str) -> eyre::Result<()> {
+    let printed = async {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if jvm_instance.stdout().read_line(&mut line).await? == 0 {
+                eyre::bail!("the java instance exited before printing {expected}");
+            }
+            if line.trim() == expected {
+                return Ok(());
+            }
+        }
+    };
+    tokio::time::timeout(TIMEOUT, printed).await?
+}
+
+/// Starts `com.acme.Greeter`, waiting for it to greet
+async fn start_greeter() -> eyre::Result<(JavaInstance, Arc<impl VirtualMachine>)> {
+    let mut jvm_instance = JavaInstance::new(0, "com.acme.Greeter").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let start = vm
+        .event_queue()
+        .remove_timeout(TIMEOUT)
+        .await?
+        .expect("no vm start");
+    start.resume().await?;
+    wait_for_line(&mut jvm_instance, "hello").await?;
+    Ok((jvm_instance, vm))
+}
+
+/// Finds the loaded `com.acme.Greeter` class
+async fn greeter<VM: VirtualMachine>(vm: &Arc<VM>) -> eyre::Result<ReferenceType<VM>> {
+    Ok(vm
+        .all_classes()
+        .await?
+        .into_iter()
+        .find(|class| class.signature() == GREETER)
+        .expect("greeter isn't loaded"))
+}
+
+#[test(tokio::test)]
+async fn test_redefine_classes() -> eyre::Result<()> {
+    let (mut jvm_instance, vm) = start_greeter().await?;
+    let greeter = greeter(&vm).await?;
+    let classes = tempfile::tempdir()?;
+
+    let class_file = compile(classes.path(), "Greeter", &greeter_source("bonjour"))?;
+    vm.redefine_classes(&[(greeter.clone(), std::fs::read(class_file)?)])
+        .await?;
+    wait_for_line(&mut jvm_instance, "bonjour").await?;
+
+    // adding a field changes the schema of the class
+    let with_field = greeter_source("hallo").replace(
+        "public class Greeter {",
+        "public class Greeter {\n    static int greeted;",
+    );
+    let class_file = compile(classes.path(), "Greeter", &with_field)?;
+    let result = vm
+        .redefine_classes(&[(greeter.clone(), std::fs::read(class_file)?)])
+        .await;
+    let capabilities = vm.capabilities().await?;
+    assert!(
+        matches!(
+            &result,
+            Err(RedefineError::Rejected {
+                classes,
+                reason: Rejection::SchemaChange,
+                missing_capability,
+            }) if classes == &["com.acme.Greeter"]
+                && *missing_capability == Rejection::SchemaChange.missing_capability(&capabilities)
+        ),
+        "{result:?}"
+    );
+
+    // adding a method needs a capability most virtual machines lack
+    let with_method = greeter_source("hallo").replace(
+        "public class Greeter {",
+        "public class Greeter {\n    static void wave() {}",
+    );
+    let class_file = compile(classes.path(), "Greeter", &with_method)?;
+    let result = vm
+        .redefine_classes(&[(greeter.clone(), std::fs::read(class_file)?)])
+        .await;
+    if capabilities.can_add_method || capabilities.can_unrestrictedly_redefine_classes {
+        result?;
+        wait_for_line(&mut jvm_instance, "hallo").await?;
+        let class_file = compile(classes.path(), "Greeter", &greeter_source("bonjour"))?;
+        vm.redefine_classes(&[(greeter.clone(), std::fs::read(class_file)?)])
+            .await?;
+    } else {
+        assert!(
+            matches!(
+                &result,
+                Err(RedefineError::Rejected {
+                    reason: Rejection::AddMethod,
+                    missing_capability: Some("canAddMethod"),
+                    ..
+                })
+            ),
+            "{result:?}"
+        );
+        let message = result.expect_err("adding a method was allowed").to_string();
+        assert!(message.contains("canAddMethod"), "{message}");
+    }
+
+    let class_file = compile(
+        classes.path(),
+        "Farewell",
+        "package com.acme;\n\npublic class Farewell {}\n",
+    )?;
+    let result = vm
+        .redefine_classes(&[(greeter.clone(), std::fs::read(class_file)?)])
+        .await;
+    assert!(
+        matches!(
+            result,
+            Err(RedefineError::Rejected {
+                reason: Rejection::NamesDontMatch,
+                ..
+            })
+        ),
+        "{result:?}"
+    );
+
+    let result = vm
+        .redefine_classes(&[(greeter, b"not a class".to_vec())])
+        .await;
+    assert!(
+        matches!(
+            result,
+            Err(RedefineError::Rejected {
+                reason: Rejection::InvalidClassFormat,
+                ..
+            })
+        ),
+        "{result:?}"
+    );
+
+    // nothing was redefined by the rejected class files
+    wait_for_line(&mut jvm_instance, "bonjour").await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_class_file_watcher() -> eyre::Result<()> {
+    let (mut jvm_instance, vm) = start_greeter().await?;
+    let classes = tempfile::tempdir()?;
+    compile(classes.path(), "Greeter", &greeter_source("hello"))?;
+    let mut watcher = ClassFileWatcher::new(vm.as_ref(), classes.path())?;
+
+    // classes that aren't loaded are ignored
+    compile(
+        classes.path(),
+        "Farewell",
+        "package com.acme;\n\npublic class Farewell {}\n",
+    )?;
+    let class_file = compile(classes.path(), "Greeter", &greeter_source("bonjour"))?;
+    let redefinition = tokio::time::timeout(TIMEOUT, watcher.next())
+        .await?
+        .expect("the watcher ended")?;
+    assert_eq!(redefinition.classes(), ["com.acme.Greeter"]);
+    assert_eq!(redefinition.class_files(), [class_file.canonicalize()?]);
+    wait_for_line(&mut jvm_instance, "bonjour").await?;
+
+    compile(classes.path(), "Greeter", &greeter_source("hola"))?;
+    let redefinition = tokio::time::timeout(TIMEOUT, watcher.next())
+        .await?
+        .expect("the watcher ended")?;
+    assert_eq!(redefinition.classes(), ["com.acme.Greeter"]);
+    wait_for_line(&mut jvm_instance, "hola").await?;
+    Ok(())
+}
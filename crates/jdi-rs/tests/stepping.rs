@@ -0,0 +1,110 @@
+use jdb_test_fixtures::JavaInstance;
+use jdi_rs::event::{BreakpointEvent, Event, EventQueue};
+use jdi_rs::request::StepOptions;
+use jdi_rs::*;
+use jdwp_types::{StepDepth, StepSize};
+use std::time::Duration;
+use test_log::test;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Resumes the target VM, then waits for it to hit a breakpoint on a line of a source file
+async fn run_to_line<VM: VirtualMachine>(
+    vm: &VM,
+    queue: &EventQueue<VM>,
+    source_path: &str,
+    line: i32,
+) -> eyre::Result<BreakpointEvent<VM>> {
+    let start = queue.remove_timeout(TIMEOUT).await?.expect("no vm start");
+    vm.event_request_manager()
+        .set_breakpoint(source_path, line)
+        .await?;
+    start.resume().await?;
+    let hit = queue
+        .remove_timeout(TIMEOUT)
+        .await?
+        .expect("no breakpoint hit");
+    let [Event::Breakpoint(event)] = hit.events() else {
+        panic!("expected a breakpoint event but got {hit:?}");
+    };
+    vm.event_request_manager().delete_all_breakpoints().await?;
+    Ok(event.clone())
+}
+
+#[test(tokio::test)]
+async fn test_step_over_and_into() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Lines").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let queue = vm.event_queue();
+    let hit = run_to_line(&*vm, &queue, "com/acme/Lines.java", 8).await?;
+    let thread = hit.thread();
+
+    let over =
+        tokio::time::timeout(TIMEOUT, thread.step(StepSize::Line, StepDepth::Over)).await??;
+    assert_eq!(over.location().line_number().await?, Some(9));
+    assert!(vm.event_request_manager().step_requests().is_empty());
+
+    // around the loop, back to the call to greet
+    tokio::time::timeout(TIMEOUT, thread.step(StepSize::Line, StepDepth::Over)).await??;
+    let into =
+        tokio::time::timeout(TIMEOUT, thread.step(StepSize::Line, StepDepth::Into)).await??;
+    assert_eq!(into.location().method().await?.name(), "greet");
+    assert_eq!(into.location().line_number().await?, Some(14));
+
+    let out = tokio::time::timeout(TIMEOUT, thread.step(StepSize::Line, StepDepth::Out)).await??;
+    assert_eq!(out.location().method().await?.name(), "main");
+
+    // none of the steps were removed from the queue
+    assert!(queue
+        .remove_timeout(Duration::from_millis(100))
+        .await?
+        .is_none());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_step_into_method() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Lines").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let queue = vm.event_queue();
+    let hit = run_to_line(&*vm, &queue, "com/acme/Lines.java", 20).await?;
+
+    let into = tokio::time::timeout(
+        TIMEOUT,
+        hit.thread().step_into_method("run", &StepOptions::new()),
+    )
+    .await??;
+    let location = into.location();
+    assert_eq!(location.method().await?.name(), "run");
+    assert_eq!(location.declaring_type().await?.name(), "com.acme.Lines$1");
+    assert_eq!(location.line_number().await?, Some(17));
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_smart_step_out_of_lambda() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Lambdas").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let queue = vm.event_queue();
+    let hit = run_to_line(&*vm, &queue, "com/acme/Lambdas.java", 11).await?;
+    assert!(hit.location().method().await?.name().starts_with("lambda$"));
+
+    let out =
+        tokio::time::timeout(TIMEOUT, hit.thread().smart_step_out(&StepOptions::new())).await??;
+    let location = out.location();
+    assert_eq!(location.declaring_type().await?.name(), "com.acme.Lambdas");
+    assert_eq!(location.method().await?.name(), "main");
+    Ok(())
+}
+
+#[test]
+fn test_step_exclusions() {
+    let options = StepOptions::new().exclude("*.Generated");
+    assert!(options.is_excluded("java.lang.String"));
+    assert!(options.is_excluded("jdk.internal.misc.Unsafe"));
+    assert!(options.is_excluded("com.acme.Generated"));
+    assert!(!options.is_excluded("com.acme.Lines"));
+    assert!(!StepOptions::new()
+        .exclusions(Vec::<String>::new())
+        .is_excluded("java.lang.String"));
+}
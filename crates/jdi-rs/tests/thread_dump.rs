@@ -0,0 +1,120 @@
+use jdb_test_fixtures::JavaInstance;
+use jdi_rs::thread_dump::ThreadDump;
+use jdi_rs::*;
+use jdwp_types::ThreadStatus;
+use std::time::Duration;
+use test_log::test;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const ACCOUNT: &str = "com.acme.Deadlock$Account";
+/// The line a thread blocked entering the inner monitor of `transfer` is reported at, which javac
+/// attributes to the first line of the block
+const INNER_LOCK_LINE: i32 = 20;
+
+/// Dumps the threads until the `left` and `right` threads are deadlocked
+async fn dump_deadlocked<VM: VirtualMachine>(vm: &VM) -> eyre::Result<ThreadDump<VM>> {
+    let deadlocked = async {
+        loop {
+            let dump = vm.thread_dump().await?;
+            if !dump.deadlocks().is_empty() {
+                return Ok::<_, eyre::Report>(dump);
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    };
+    tokio::time::timeout(TIMEOUT, deadlocked).await?
+}
+
+#[test(tokio::test)]
+async fn test_thread_dump_finds_deadlock() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Deadlock").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let start = vm
+        .event_queue()
+        .remove_timeout(TIMEOUT)
+        .await?
+        .expect("no vm start");
+    start.resume().await?;
+
+    let dump = dump_deadlocked(vm.as_ref()).await?;
+    let [deadlock] = dump.deadlocks() else {
+        panic!("expected a single deadlock but got {dump}");
+    };
+    let mut names = deadlock
+        .threads()
+        .iter()
+        .map(|thread| dump.thread(thread.id()).expect("not dumped").name())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["left", "right"]);
+
+    let left = dump.thread_by_name("left").expect("no left thread");
+    let right = dump.thread_by_name("right").expect("no right thread");
+    assert_eq!(left.status(), ThreadStatus::Monitor);
+    assert!(!left.is_suspended());
+    let top = &left.frames()[0];
+    assert_eq!(top.class_name(), "com.acme.Deadlock");
+    assert_eq!(top.method_name(), "transfer");
+    assert_eq!(top.source_name(), Some("Deadlock.java"));
+    assert_eq!(top.line_number(), Some(INNER_LOCK_LINE));
+
+    let contended = left.contended_monitor().expect("left isn't contending");
+    assert_eq!(contended.class_name(), ACCOUNT);
+    assert_eq!(contended.owner(), Some(right.thread()));
+    assert_eq!(contended.entry_count(), 1);
+    let [owned] = left.owned_monitors() else {
+        panic!("expected left to own a single monitor");
+    };
+    assert_eq!(owned.monitor().class_name(), ACCOUNT);
+    assert_eq!(owned.monitor().owner(), Some(left.thread()));
+    assert_eq!(owned.stack_depth(), Some(0));
+    assert_eq!(
+        right.contended_monitor().map(|monitor| monitor.object()),
+        Some(owned.monitor().object())
+    );
+
+    // a thread waiting on a monitor is reported, but isn't deadlocked
+    let waiter = dump.thread_by_name("waiter").expect("no waiter thread");
+    assert_eq!(waiter.status(), ThreadStatus::Wait);
+    let signal = waiter.contended_monitor().expect("waiter isn't waiting");
+    assert_eq!(signal.class_name(), "java.lang.Object");
+    assert_eq!(signal.waiters(), [waiter.thread().clone()]);
+    assert!(signal.owner().is_none());
+
+    let text = dump.to_string();
+    assert!(text.contains("Found one Java-level deadlock:"), "{text}");
+    assert!(
+        text.contains(&format!(
+            "\tat com.acme.Deadlock.transfer(Deadlock.java:{INNER_LOCK_LINE})"
+        )),
+        "{text}"
+    );
+    assert!(text.contains("owned by \"right\""), "{text}");
+    assert!(text.contains(&format!("\t- waiting on {signal}")), "{text}");
+    assert!(text.contains("which is held by \"left\""), "{text}");
+
+    let json = dump.to_json();
+    let mut deadlocked = json["deadlocks"][0]
+        .as_array()
+        .expect("no deadlock")
+        .iter()
+        .map(|name| name.as_str().expect("not a name"))
+        .collect::<Vec<_>>();
+    deadlocked.sort();
+    assert_eq!(deadlocked, ["left", "right"]);
+    let left_json = json["threads"]
+        .as_array()
+        .expect("no threads")
+        .iter()
+        .find(|thread| thread["name"] == "left")
+        .expect("left isn't in the json");
+    assert_eq!(left_json["status"], "BLOCKED (on object monitor)");
+    assert_eq!(left_json["frames"][0]["line"], INNER_LOCK_LINE);
+    assert_eq!(left_json["owned_monitors"][0]["stack_depth"], 0);
+    assert_eq!(left_json["contended_monitor"]["class"], ACCOUNT);
+
+    // the target VM is resumed once it's dumped
+    assert_eq!(left.thread().suspend_count().await?, 0);
+    assert!(!left.thread().is_suspended().await?);
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use futures::StreamExt;
+use jdb_test_fixtures::JavaInstance;
+use jdi_rs::trace::{
+    write_json_lines, CallTree, ChromeTraceWriter, TraceEvent, TraceOptions, Tracer,
+};
+use jdi_rs::*;
+use std::time::Duration;
+use test_log::test;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test(tokio::test)]
+async fn test_trace() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Orders").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let queue = vm.event_queue();
+    let start = queue.remove_timeout(TIMEOUT).await?.expect("no vm start");
+    let mut tracer =
+        Tracer::start(vm.as_ref(), TraceOptions::new().class_pattern("com.acme.*")).await?;
+    assert_eq!(tracer.requests().len(), 2);
+    start.resume().await?;
+
+    // collects events until `process` has returned twice
+    let mut events = vec![];
+    let mut processed = 0;
+    while processed < 2 {
+        let event = tokio::time::timeout(TIMEOUT, tracer.next())
+            .await?
+            .expect("tracing ended");
+        if let TraceEvent::Exit { call, .. } = &event {
+            if call.method().name() == "process" {
+                processed += 1;
+            }
+        }
+        events.push(event);
+    }
+
+    let mut tree = CallTree::new();
+    for event in &events {
+        tree.record(event);
+    }
+    let (main_thread, name) = tree.threads().next().expect("no thread traced");
+    assert_eq!(name, "main");
+    let [main] = tree.roots(main_thread) else {
+        panic!("expected main to be the only root but got {tree}");
+    };
+    assert_eq!(main.call().name(), "com.acme.Orders.main");
+    assert!(main.exited().is_none());
+    let children = main
+        .children()
+        .iter()
+        .map(|child| child.call().method().name())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        children,
+        ["<init>", "add", "add", "add", "add", "process", "process"],
+        "{tree}"
+    );
+
+    let add = &main.children()[1];
+    let arguments = add
+        .call()
+        .arguments()
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.display()))
+        .collect::<Vec<_>>();
+    assert_eq!(arguments, [("name", "apple"), ("quantity", "3")]);
+    assert_eq!(
+        add.children()[0].call().name(),
+        "com.acme.Orders$Item.<init>"
+    );
+
+    let process = &main.children()[5];
+    assert_eq!(process.call().depth(), 1);
+    assert_eq!(process.call().parent(), Some(main.call().id()));
+    assert!(!process.threw());
+    assert!(process.return_value().is_none());
+    let process_children = process
+        .children()
+        .iter()
+        .map(|child| child.call().name())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        process_children,
+        [
+            "com.acme.Orders$Order.getItems",
+            "com.acme.Orders$Order.getItems",
+            "com.acme.Orders$Item.toString",
+        ]
+    );
+    let to_string = &process.children()[2];
+    assert_eq!(
+        to_string.return_value().map(|value| value.display()),
+        Some("3 x apple")
+    );
+    assert!(to_string.duration().is_some());
+
+    let mut json_lines = vec![];
+    write_json_lines(&mut json_lines, &events)?;
+    let json_lines = String::from_utf8(json_lines)?;
+    assert_eq!(json_lines.lines().count(), events.len());
+    let exit = json_lines
+        .lines()
+        .map(serde_json::from_str::<serde_json::Value>)
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .find(|line| line["method"] == "com.acme.Orders$Item.toString" && line["event"] == "exit")
+        .expect("no exit from toString");
+    assert_eq!(exit["return_value"], "3 x apple");
+    assert_eq!(exit["thread"], "main");
+    assert_eq!(exit["threw"], false);
+
+    let mut chrome = ChromeTraceWriter::new(vec![])?;
+    for event in &events {
+        chrome.write(event)?;
+    }
+    let chrome: serde_json::Value = serde_json::from_slice(&chrome.finish()?)?;
+    let chrome = chrome.as_array().expect("not an array");
+    assert_eq!(chrome.len(), events.len() + 1);
+    assert_eq!(chrome[0]["ph"], "M");
+    assert_eq!(chrome[0]["args"]["name"], "main");
+    assert_eq!(
+        chrome.iter().filter(|event| event["ph"] == "B").count(),
+        events
+            .iter()
+            .filter(|event| matches!(event, TraceEvent::Enter { .. }))
+            .count()
+    );
+
+    // the traced events are handled by the tracer, rather than the event queue
+    assert!(queue
+        .remove_timeout(Duration::from_millis(200))
+        .await?
+        .is_none());
+
+    tracer.stop().await?;
+    assert!(vm
+        .event_request_manager()
+        .method_entry_requests()
+        .is_empty());
+    assert!(vm.event_request_manager().method_exit_requests().is_empty());
+    Ok(())
+}
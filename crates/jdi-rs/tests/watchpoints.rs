@@ -0,0 +1,112 @@
+use jdb_test_fixtures::JavaInstance;
+use jdi_rs::event::{Event, EventQueue, EventSet};
+use jdi_rs::*;
+use std::sync::Arc;
+use std::time::Duration;
+use test_log::test;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Waits for the `com.acme.Gauges` class to be prepared, leaving the target VM suspended there
+async fn prepare_gauges<VM: VirtualMachine>(
+    vm: &Arc<VM>,
+) -> eyre::Result<(ReferenceType<VM>, EventSet<VM>)> {
+    let queue = vm.event_queue();
+    let start = queue.remove_timeout(TIMEOUT).await?.expect("no vm start");
+    let request = vm.event_request_manager().create_class_prepare_request();
+    request.add_class_pattern_filter("com.acme.Gauges")?;
+    request.enable().await?;
+    start.resume().await?;
+    let prepared = queue
+        .remove_timeout(TIMEOUT)
+        .await?
+        .expect("no class prepare event");
+    let [Event::ClassPrepare(class_prepare)] = prepared.events() else {
+        panic!("expected a class prepare event but got {prepared:?}");
+    };
+    let gauges = class_prepare.reference_type().clone();
+    Ok((gauges, prepared))
+}
+
+/// Removes the next event set, which must only hold a single event, and resumes it
+async fn next_event<VM: VirtualMachine>(queue: &EventQueue<VM>) -> eyre::Result<Event<VM>> {
+    let set = queue.remove_timeout(TIMEOUT).await?.expect("no event");
+    let [event] = set.events() else {
+        panic!("expected a single event but got {set:?}");
+    };
+    let event = event.clone();
+    set.resume().await?;
+    Ok(event)
+}
+
+#[test(tokio::test)]
+async fn test_watch_static_modification() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Gauges").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let (gauges, prepared) = prepare_gauges(&vm).await?;
+    let readings = gauges
+        .field_by_name("readings")
+        .await?
+        .expect("no readings field");
+    let request = readings.watch_modification(None).await?;
+    assert!(request.is_enabled());
+    assert_eq!(request.field(), readings);
+    prepared.resume().await?;
+
+    let queue = vm.event_queue();
+    for count in 0..3 {
+        let Event::ModificationWatchpoint(event) = next_event(&queue).await? else {
+            panic!("expected a modification watchpoint event");
+        };
+        assert_eq!(event.field(), Some(readings.clone()));
+        assert!(event.object().is_none());
+        assert_eq!(event.current_value(), &Some(Value::Int(count)));
+        assert_eq!(event.value_to_be(), &Value::Int(count + 1));
+    }
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_watch_instance_access() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Gauges").await?;
+    let vm = VirtualMachineManager::attach(("127.0.0.1", jvm_instance.port())).await?;
+    let (gauges, prepared) = prepare_gauges(&vm).await?;
+    let level = gauges
+        .field_by_name("level")
+        .await?
+        .expect("no level field");
+    let modification = level.watch_modification(None).await?;
+    prepared.resume().await?;
+
+    // the first gauge set is the low one. The request is deleted before resuming so that the
+    // high one's modification isn't reported
+    let queue = vm.event_queue();
+    let set = queue.remove_timeout(TIMEOUT).await?.expect("no event");
+    let [Event::ModificationWatchpoint(event)] = set.events() else {
+        panic!("expected a modification watchpoint event but got {set:?}");
+    };
+    let low = event
+        .object()
+        .clone()
+        .expect("no object for an instance field");
+    assert_eq!(event.current_value(), &Some(Value::Int(0)));
+    assert_eq!(event.value_to_be(), &Value::Int(1));
+    vm.event_request_manager()
+        .delete_event_request(modification)
+        .await?;
+    set.resume().await?;
+
+    let access = level.watch_access(Some(&low)).await?;
+    for _ in 0..3 {
+        let Event::AccessWatchpoint(event) = next_event(&queue).await? else {
+            panic!("expected an access watchpoint event");
+        };
+        assert_eq!(event.request(), Some(&access));
+        assert_eq!(event.object().as_ref(), Some(&low));
+        assert!(matches!(
+            event.current_value(),
+            Some(Value::Int(value)) if *value < 100
+        ));
+    }
+    Ok(())
+}
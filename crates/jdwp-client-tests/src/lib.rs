@@ -3,6 +3,7 @@ use jdwp_client::JdwpClient;
 use std::io;
 use tokio::net::TcpStream;
 
+#[allow(async_fn_in_trait)]
 pub trait JdwpJavaInstanceExt {
     async fn connect(&self) -> io::Result<JdwpClient<TcpStream>>;
 }
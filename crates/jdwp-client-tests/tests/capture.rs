@@ -0,0 +1,68 @@
+use jdb_test_fixtures::JavaInstance;
+use jdwp_client::capture::{Capture, CaptureWriter};
+use jdwp_client::commands::{AllThreads, Version};
+use jdwp_client::packet::JdwpCommand;
+use jdwp_client::raw::packet::AnyRawPacket;
+use jdwp_client::record::{Direction, Recording, RecordingTransport};
+use jdwp_client::JdwpClient;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test_log::test(tokio::test)]
+async fn test_capture_session() -> io::Result<()> {
+    let directory = tempfile::tempdir()?;
+    let recorded = directory.path().join("session.jdwp");
+    let captured = directory.path().join("session.pcapng");
+    let java_instance = JavaInstance::new(0, "BusyBeaver").await?;
+    let stream = TcpStream::connect(("127.0.0.1", java_instance.port())).await?;
+    let transport = RecordingTransport::create(stream, &recorded)?;
+    let written = transport.recorded();
+    let client = JdwpClient::create(transport).await?;
+    let version = client.send(Version).await?;
+    let threads = client.send(AllThreads).await?;
+    client.dispose().await?;
+    drop(java_instance);
+    timeout(TIMEOUT, written.finish()).await?;
+
+    let debugger = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 50123);
+    let vm = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 5005);
+    let mut writer = CaptureWriter::create(&captured, debugger, vm)?;
+    writer.write_recording(&Recording::open(&recorded)?)?;
+    writer.finish()?;
+
+    for port in [None, Some(5005)] {
+        let capture = Capture::open(&captured, port).map_err(io::Error::other)?;
+        assert_eq!(capture.connections().len(), 1);
+        assert!(capture.connections()[0].id_sizes.is_some());
+        let packets = capture.packets();
+        assert_eq!(packets[0].direction, Direction::ToVm);
+
+        let reply = packets
+            .iter()
+            .find(|packet| packet.command == Some(Version::command_data()))
+            .expect("the version reply wasn't found");
+        assert!(matches!(reply.packet, AnyRawPacket::Reply(_)));
+        let captured = capture.reply::<Version>(reply).map_err(io::Error::other)?;
+        assert_eq!(captured.description, version.description);
+        assert_eq!(captured.major, version.major);
+
+        let reply = packets
+            .iter()
+            .find(|packet| packet.command == Some(AllThreads::command_data()))
+            .expect("the threads reply wasn't found");
+        let captured = capture
+            .reply::<AllThreads>(reply)
+            .map_err(io::Error::other)?;
+        assert_eq!(captured.threads, threads.threads);
+    }
+
+    // connections to other ports aren't read
+    let capture = Capture::open(&captured, Some(8000)).map_err(io::Error::other)?;
+    assert!(capture.packets().is_empty());
+    Ok(())
+}
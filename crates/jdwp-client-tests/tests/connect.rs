@@ -1,7 +1,6 @@
 use jdb_test_fixtures::JavaInstance;
 use jdwp_client_tests::JdwpJavaInstanceExt;
 use std::io;
-use std::time::Duration;
 use tracing::info;
 
 #[test_log::test(tokio::test)]
@@ -9,7 +8,7 @@ async fn test_connect() -> io::Result<()> {
     let java_instance = JavaInstance::new(0, "BusyBeaver").await?;
     println!("started java instance");
     let mut client = java_instance.connect().await?;
-    client
+    let _registration = client
         .on_event(|policy, e| async move {
             info!("event [policy: {policy:?}]: {e:?}");
             Ok(())
@@ -24,7 +23,7 @@ async fn test_disconnect() -> io::Result<()> {
     let java_instance = JavaInstance::new(0, "BusyBeaver").await?;
     println!("started java instance");
     let mut client = java_instance.connect().await?;
-    client
+    let _registration = client
         .on_event(|policy, e| async move {
             info!("event [policy: {policy:?}]: {e:?}");
             Ok(())
@@ -0,0 +1,209 @@
+use futures::StreamExt;
+use jdb_test_fixtures::JavaInstance;
+use jdwp_client::commands::event_request::{Set, SetReply};
+use jdwp_client::commands::Resume;
+use jdwp_client::events::{DeliveryMode, Event, Events, HandlerOptions};
+use jdwp_client::jdwp_types::{EventKind, SuspendPolicy};
+use jdwp_client::{ClientConfig, JdwpClient};
+use jdwp_client_tests::JdwpJavaInstanceExt;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::{sleep, timeout};
+
+const COMPOSITES: usize = 20;
+
+#[test_log::test(tokio::test)]
+async fn test_ordered_handlers_receive_events_in_order() -> io::Result<()> {
+    let java_instance = JavaInstance::new(0, "BusyBeaver").await?;
+    let mut client = java_instance.connect().await?;
+
+    let composites = Arc::new(Mutex::new(Vec::<String>::new()));
+    let events = Arc::new(Mutex::new(Vec::<String>::new()));
+    let _composites_registration = {
+        let composites = composites.clone();
+        client
+            .on_events(move |received: Events| async move {
+                // a slow handler must not let later composites overtake earlier ones
+                sleep(Duration::from_millis(5)).await;
+                composites.lock().unwrap().push(format!("{received:?}"));
+                Ok(())
+            })
+            .await
+    };
+    let _events_registration = {
+        let events = events.clone();
+        client
+            .on_event_with(
+                move |_, event| async move {
+                    events.lock().unwrap().push(format!("{event:?}"));
+                    Ok(())
+                },
+                HandlerOptions::new().delivery(DeliveryMode::Ordered),
+            )
+            .await
+    };
+    let mut stream = client.events();
+
+    prepare_every_class(&client).await?;
+
+    let expected = timeout(
+        Duration::from_secs(10),
+        stream.by_ref().take(COMPOSITES).collect::<Vec<_>>(),
+    )
+    .await?;
+    assert_eq!(expected.len(), COMPOSITES);
+
+    // the vm start event was held until the listeners were added, so every listener saw it
+    timeout(Duration::from_secs(10), async {
+        while composites.lock().unwrap().len() < COMPOSITES
+            || events.lock().unwrap().len() < COMPOSITES
+        {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await?;
+
+    let composites = composites.lock().unwrap();
+    let events = events.lock().unwrap();
+    assert_eq!(expected[0].events[0].kind(), EventKind::VmStart);
+    for (index, expected) in expected.iter().enumerate() {
+        assert_eq!(composites[index], format!("{expected:?}"));
+    }
+    let expected_events = expected
+        .iter()
+        .flat_map(|composite| &composite.events)
+        .map(|event| format!("{event:?}"))
+        .collect::<Vec<_>>();
+    assert_eq!(events[..expected_events.len()], expected_events[..]);
+    Ok(())
+}
+
+/// Requests class prepare events for every class without suspending, then resumes the target VM
+async fn prepare_every_class(client: &JdwpClient<TcpStream>) -> io::Result<SetReply> {
+    let reply = client
+        .send(Set {
+            event_kind: EventKind::ClassPrepare,
+            suspend_policy: SuspendPolicy::None,
+            modifiers: vec![],
+        })
+        .await?;
+    client.send(Resume).await?;
+    Ok(reply)
+}
+
+#[test_log::test(tokio::test)]
+async fn test_filtered_handler() -> io::Result<()> {
+    let java_instance = JavaInstance::new(0, "BusyBeaver").await?;
+    let mut client = java_instance.connect().await?;
+
+    let received = Arc::new(Mutex::new(Vec::<Event>::new()));
+    let registration = {
+        let received = received.clone();
+        client
+            .on_event_with(
+                move |_, event| async move {
+                    received.lock().unwrap().push(event);
+                    Ok(())
+                },
+                HandlerOptions::new().kind(EventKind::ClassPrepare),
+            )
+            .await
+    };
+    let mut stream = client.events();
+    let request_id = prepare_every_class(&client).await?.request_id;
+
+    timeout(
+        Duration::from_secs(10),
+        stream.by_ref().take(COMPOSITES).count(),
+    )
+    .await?;
+    registration.unregister();
+    let received = received.lock().unwrap();
+    assert!(!received.is_empty());
+    assert!(received.iter().all(|event| {
+        event.kind() == EventKind::ClassPrepare && event.request_id() == Some(request_id)
+    }));
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_handler_errors_reach_application() -> io::Result<()> {
+    let java_instance = JavaInstance::new(0, "BusyBeaver").await?;
+    let mut client = java_instance.connect().await?;
+
+    let registration = client
+        .on_event(|_, _| async move { Err(io::Error::other("failed to handle event")) })
+        .await;
+    assert!(registration.is_registered());
+    let mut errors = registration.errors();
+    prepare_every_class(&client).await?;
+
+    let error = timeout(Duration::from_secs(10), errors.next())
+        .await?
+        .expect("no handler error");
+    assert_eq!(error.handler(), registration.id());
+    assert_eq!(error.error().to_string(), "failed to handle event");
+    assert_eq!(error.events().events.len(), 1);
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_every_event_stream_receives_every_composite() -> io::Result<()> {
+    let java_instance = JavaInstance::new(0, "BusyBeaver").await?;
+    let client = java_instance.connect().await?;
+
+    let mut first = client.events();
+    let mut second = client.events();
+    prepare_every_class(&client).await?;
+
+    let (first, second) = timeout(
+        Duration::from_secs(10),
+        futures::future::join(
+            first.by_ref().take(COMPOSITES).collect::<Vec<_>>(),
+            second.by_ref().take(COMPOSITES).collect::<Vec<_>>(),
+        ),
+    )
+    .await?;
+    assert_eq!(first.len(), COMPOSITES);
+    assert_eq!(first, second);
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_lagging_event_stream_reports_missed_composites() -> io::Result<()> {
+    let java_instance = JavaInstance::new(0, "BusyBeaver").await?;
+    let tcp_stream = TcpStream::connect(("127.0.0.1", java_instance.port())).await?;
+    let client = JdwpClient::create_with(tcp_stream, ClientConfig::new().event_capacity(4)).await?;
+
+    let mut lagging = client.events();
+    let mut keeping_up = client.events();
+    // every composite suspends the target VM until it's been received, so that only the
+    // lagging stream misses any
+    client
+        .send(Set {
+            event_kind: EventKind::ClassPrepare,
+            suspend_policy: SuspendPolicy::All,
+            modifiers: vec![],
+        })
+        .await?;
+    client.send(Resume).await?;
+
+    timeout(Duration::from_secs(10), async {
+        for _ in 0..COMPOSITES {
+            keeping_up.next().await.expect("event stream ended");
+            client.send(Resume).await?;
+        }
+        io::Result::Ok(())
+    })
+    .await??;
+    let received = timeout(
+        Duration::from_secs(10),
+        lagging.by_ref().take(4).collect::<Vec<_>>(),
+    )
+    .await?;
+    assert_eq!(received.len(), 4);
+    assert!(lagging.missed() > 0);
+    Ok(())
+}
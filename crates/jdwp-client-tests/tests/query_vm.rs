@@ -1,5 +1,6 @@
 use jdb_test_fixtures::JavaInstance;
 use jdwp_client::commands::{AllClasses, AllThreads, ClassesBySignatures, Version};
+use jdwp_client_tests::JdwpJavaInstanceExt;
 use std::io;
 use tracing::info;
 
@@ -7,7 +8,7 @@ use tracing::info;
 async fn test_get_jvm_version() -> io::Result<()> {
     let java_instance = JavaInstance::new(0, "BusyBeaver").await?;
     println!("started java instance");
-    let mut client = java_instance.connect().await?;
+    let client = java_instance.connect().await?;
     let version = client.send(Version).await?;
     println!("got version: {version:#?}");
     assert!(version.major >= 8, "major is not >= 8: {}", version.major);
@@ -20,7 +21,7 @@ async fn test_get_jvm_version() -> io::Result<()> {
 async fn test_get_string_class() -> io::Result<()> {
     let java_instance = JavaInstance::new(0, "BusyBeaver").await?;
     println!("started java instance");
-    let mut client = java_instance.connect().await?;
+    let client = java_instance.connect().await?;
     let data = client
         .send(ClassesBySignatures {
             signature: "Ljava/lang/String;".to_string(),
@@ -35,7 +36,7 @@ async fn test_get_string_class() -> io::Result<()> {
 async fn test_get_all_classes() -> io::Result<()> {
     let java_instance = JavaInstance::new(0, "BusyBeaver").await?;
     println!("started java instance");
-    let mut client = java_instance.connect().await?;
+    let client = java_instance.connect().await?;
     let data = client.send(AllClasses).await?;
     info!("initialized:");
     for x in data.classes {
@@ -51,7 +52,7 @@ async fn test_get_all_classes() -> io::Result<()> {
 async fn test_get_all_threads() -> io::Result<()> {
     let java_instance = JavaInstance::new(0, "BusyBeaver").await?;
     println!("started java instance");
-    let mut client = java_instance.connect().await?;
+    let client = java_instance.connect().await?;
     let data = client.send(AllThreads).await?;
     println!("data: {data:#?}");
     client.dispose().await?;
@@ -63,7 +64,7 @@ async fn test_dispose() -> io::Result<()> {
     let java_instance = JavaInstance::new(0, "BusyBeaver").await?;
     println!("started java instance");
     let mut client = java_instance.connect().await?;
-    client
+    let _registration = client
         .on_event(|_, e| async move {
             println!("got event: {e:?}");
             Ok(())
@@ -0,0 +1,71 @@
+use jdb_test_fixtures::JavaInstance;
+use jdwp_client::commands::{ClassesBySignatures, Version};
+use jdwp_client::record::{Direction, Recording, RecordingTransport, ReplayError, ReplayTransport};
+use jdwp_client::JdwpClient;
+use std::io;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn string_class() -> ClassesBySignatures {
+    ClassesBySignatures {
+        signature: "Ljava/lang/String;".to_string(),
+    }
+}
+
+#[test_log::test(tokio::test)]
+async fn test_record_and_replay() -> io::Result<()> {
+    let directory = tempfile::tempdir()?;
+    let path = directory.path().join("session.jdwp");
+    let java_instance = JavaInstance::new(0, "BusyBeaver").await?;
+    let stream = TcpStream::connect(("127.0.0.1", java_instance.port())).await?;
+    let transport = RecordingTransport::create(stream, &path)?;
+    let recorded = transport.recorded();
+    let client = JdwpClient::create(transport).await?;
+    let version = client.send(Version).await?;
+    let classes = client.send(string_class()).await?;
+    client.dispose().await?;
+    drop(java_instance);
+    timeout(TIMEOUT, recorded.finish()).await?;
+
+    let recording = Recording::open(&path)?;
+    let to_vm = recording
+        .packets()
+        .iter()
+        .filter(|packet| packet.direction == Direction::ToVm)
+        .count();
+    // the id sizes asked for when connecting, then the commands sent here
+    assert_eq!(to_vm, 4);
+
+    let (transport, replay) = ReplayTransport::new(recording.clone());
+    let client = JdwpClient::create(transport).await?;
+    let replayed = client.send(Version).await?;
+    assert_eq!(replayed.description, version.description);
+    assert_eq!(replayed.major, version.major);
+    let replayed = client.send(string_class()).await?;
+    assert_eq!(replayed.classes.len(), classes.classes.len());
+    assert_eq!(replayed.classes[0].id, classes.classes[0].id);
+    client.dispose().await?;
+    timeout(TIMEOUT, replay.finish())
+        .await?
+        .map_err(io::Error::other)?;
+
+    let (transport, replay) = ReplayTransport::new(recording);
+    let client = JdwpClient::create(transport).await?;
+    let sent = timeout(
+        TIMEOUT,
+        client.send(ClassesBySignatures {
+            signature: "Ljava/lang/Object;".to_string(),
+        }),
+    )
+    .await?;
+    assert!(sent.is_err());
+    let error = timeout(TIMEOUT, replay.finish()).await?.unwrap_err();
+    assert!(
+        matches!(error, ReplayError::Mismatch { index, .. } if index > 0),
+        "{error}"
+    );
+    Ok(())
+}
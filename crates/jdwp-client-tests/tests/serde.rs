@@ -0,0 +1,113 @@
+use jdwp_client::commands::thread_reference::{FrameInfo, FramesReply};
+use jdwp_client::events::{Event, Events};
+use jdwp_client::jdwp_types::{
+    ClassId, ClassStatus, Id, Location, MethodId, ReferenceTypeId, SuspendPolicy, Tag,
+    TaggedObjectId, ThreadId, TypeTag, Value,
+};
+use serde_json::json;
+
+fn location() -> Location {
+    Location {
+        tag: TypeTag::Class,
+        class: ClassId::new(2),
+        method: MethodId::new(3),
+        offset: 4,
+    }
+}
+
+#[test]
+fn test_events_to_json() {
+    let events = Events {
+        policy: SuspendPolicy::EventThread,
+        events: vec![
+            Event::Breakpoint {
+                request_id: 9,
+                thread: ThreadId::new(1),
+                location: location(),
+            },
+            Event::ClassPrepare {
+                request_id: 0,
+                thread: ThreadId::new(1),
+                ref_type_tag: TypeTag::Interface,
+                type_id: ReferenceTypeId::new(6),
+                signature: "LA;".to_string(),
+                status: ClassStatus(7),
+            },
+            Event::MethodExitWithReturnValue {
+                request_id: 3,
+                thread: ThreadId::new(1),
+                location: location(),
+                value: Value::Int(-1),
+            },
+        ],
+    };
+    let location = json!({
+        "tag": "Class",
+        "class": { "kind": "Class", "id": 2 },
+        "method": { "kind": "Method", "id": 3 },
+        "offset": 4,
+    });
+    let thread = json!({ "kind": "Thread", "id": 1 });
+    let value = serde_json::to_value(&events).expect("could not serialize");
+    assert_eq!(
+        value,
+        json!({
+            "policy": "EventThread",
+            "events": [
+                {
+                    "kind": "Breakpoint",
+                    "request_id": 9,
+                    "thread": thread,
+                    "location": location,
+                },
+                {
+                    "kind": "ClassPrepare",
+                    "request_id": 0,
+                    "thread": thread,
+                    "ref_type_tag": "Interface",
+                    "type_id": { "kind": "ReferenceType", "id": 6 },
+                    "signature": "LA;",
+                    "status": 7,
+                },
+                {
+                    "kind": "MethodExitWithReturnValue",
+                    "request_id": 3,
+                    "thread": thread,
+                    "location": location,
+                    "value": { "Int": -1 },
+                },
+            ],
+        })
+    );
+    let deserialized: Events = serde_json::from_value(value).expect("could not deserialize");
+    assert_eq!(deserialized, events);
+}
+
+#[test]
+fn test_reply_round_trip() {
+    let reply = FramesReply {
+        frames: vec![FrameInfo {
+            frame_id: Id::new(11),
+            location: location(),
+        }],
+    };
+    let json = serde_json::to_string(&reply).expect("could not serialize");
+    let deserialized: FramesReply = serde_json::from_str(&json).expect("could not deserialize");
+    assert_eq!(deserialized.frames[0].frame_id, reply.frames[0].frame_id);
+    assert_eq!(deserialized.frames[0].location, reply.frames[0].location);
+}
+
+#[test]
+fn test_ids_keep_their_kind() {
+    let tagged = TaggedObjectId::new(Tag::Thread, Id::new(5));
+    let value = serde_json::to_value(tagged).expect("could not serialize");
+    assert_eq!(value, json!({ "tag": "Thread", "id": 5 }));
+    assert_eq!(
+        serde_json::from_value::<TaggedObjectId>(value).expect("could not deserialize"),
+        tagged
+    );
+
+    let thread = serde_json::to_value(ThreadId::new(5)).expect("could not serialize");
+    serde_json::from_value::<ThreadId>(thread.clone()).expect("could not deserialize");
+    serde_json::from_value::<ClassId>(thread).expect_err("a thread id isn't a class id");
+}
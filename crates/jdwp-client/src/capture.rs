@@ -0,0 +1,713 @@
+//! Writes jdwp traffic as packet captures, and finds the jdwp packets in captures taken with
+//! tcpdump or wireshark.
+//!
+//! A [CaptureWriter] writes packets, such as those of a [Recording](crate::record::Recording), to
+//! a pcapng file. Their TCP/IP framing is made up, as one connection between a debugger and a
+//! target VM, so the capture can be opened in wireshark.
+//!
+//! [Capture::read] reads pcap and pcapng files, reassembles the TCP streams in them and decodes the
+//! jdwp packets they carry. Connections are recognised by their handshake or, when the capture
+//! started after they connected, by the port of the target VM. Replies are matched to the commands
+//! they answer, and the id sizes the target VM replied with are kept so replies and events can be
+//! decoded into their types.
+
+use crate::codec::{JdwpCodec, JdwpDecodable, JdwpDecoder};
+use crate::commands::{self, IdSizesReply};
+use crate::connect::JDWP_HANDSHAKE;
+use crate::events::{to_events, Events};
+use crate::id_sizes::IdSizes;
+use crate::packet::{JdwpCommand, ReplyError};
+use crate::raw::codec::RawCodec;
+use crate::raw::packet::{AnyRawPacket, CommandData};
+use crate::record::{Direction, RecordedPacket, Recording};
+use crate::registry::{registry, Description, Node};
+use bytes::{Buf, BytesMut};
+use frame::{Outgoing, Segment, MAX_SEGMENT, TCP_ACK, TCP_FIN, TCP_PSH, TCP_SYN};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, SocketAddrV4};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+use tokio_util::codec::{Decoder, Encoder};
+use tracing::{debug, warn};
+
+mod file;
+mod frame;
+
+/// A capture couldn't be read, or a packet in it couldn't be decoded
+#[derive(Debug, Error)]
+pub enum CaptureError {
+    #[error("not a pcap or pcapng capture")]
+    NotACapture,
+    #[error("the capture ends part way through a block")]
+    Truncated,
+    #[error("frames with link type {0} can't be read")]
+    UnsupportedLinkType(u32),
+    #[error("a frame was captured on interface {0}, which wasn't described")]
+    UnknownInterface(usize),
+    /// The id sizes of the connection are needed to decode the packet, but the target VM didn't
+    /// reply with them in the capture
+    #[error("the id sizes of connection {0} weren't captured")]
+    UnknownIdSizes(usize),
+    #[error("the packet isn't a reply to {0}")]
+    NotAReplyTo(CommandData),
+    #[error(transparent)]
+    Reply(#[from] ReplyError),
+    #[error("could not decode the packet: {0}")]
+    Decode(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Writes packets to a pcapng file, as if they were sent over a TCP connection between a debugger
+/// and a target VM
+pub struct CaptureWriter<W: Write> {
+    output: W,
+    /// When the capture started, since the unix epoch
+    start: Duration,
+    /// When the last packet was sent, since the capture started
+    elapsed: Duration,
+    debugger: SocketAddrV4,
+    vm: SocketAddrV4,
+    /// The sequence number of the next byte the debugger sends
+    debugger_seq: u32,
+    /// The sequence number of the next byte the target VM sends
+    vm_seq: u32,
+    /// The identification of the next IP packet
+    id: u16,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    /// Starts a capture of a connection between addresses, writing it as far as the handshake
+    pub fn new(output: W, debugger: SocketAddrV4, vm: SocketAddrV4) -> io::Result<Self> {
+        let start = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut writer = Self {
+            output,
+            start,
+            elapsed: Duration::ZERO,
+            debugger,
+            vm,
+            debugger_seq: 0,
+            vm_seq: 0,
+            id: 0,
+        };
+        let mut header = BytesMut::new();
+        file::write_section_header(&mut header);
+        file::write_interface_description(&mut header, frame::LINKTYPE_ETHERNET);
+        writer.output.write_all(&header)?;
+        writer.segment(Direction::ToVm, TCP_SYN, &[])?;
+        writer.segment(Direction::FromVm, TCP_SYN | TCP_ACK, &[])?;
+        writer.segment(Direction::ToVm, TCP_ACK, &[])?;
+        writer.segment(Direction::ToVm, TCP_PSH | TCP_ACK, JDWP_HANDSHAKE)?;
+        writer.segment(Direction::FromVm, TCP_PSH | TCP_ACK, JDWP_HANDSHAKE)?;
+        Ok(writer)
+    }
+
+    /// Writes a packet, in as many segments as it takes
+    pub fn write(&mut self, packet: &RecordedPacket) -> io::Result<()> {
+        let mut bytes = BytesMut::new();
+        match &packet.packet {
+            AnyRawPacket::Command(command) => RawCodec.encode(command.clone(), &mut bytes)?,
+            AnyRawPacket::Reply(reply) => RawCodec.encode(reply.clone(), &mut bytes)?,
+        }
+        self.elapsed = self.elapsed.max(packet.elapsed);
+        for chunk in bytes.chunks(MAX_SEGMENT) {
+            self.segment(packet.direction, TCP_PSH | TCP_ACK, chunk)?;
+        }
+        Ok(())
+    }
+
+    /// Writes every packet of a recording
+    pub fn write_recording(&mut self, recording: &Recording) -> io::Result<()> {
+        recording
+            .packets()
+            .iter()
+            .try_for_each(|packet| self.write(packet))
+    }
+
+    /// Closes the connection, giving back the output
+    pub fn finish(mut self) -> io::Result<W> {
+        self.segment(Direction::ToVm, TCP_FIN | TCP_ACK, &[])?;
+        self.segment(Direction::FromVm, TCP_FIN | TCP_ACK, &[])?;
+        self.segment(Direction::ToVm, TCP_ACK, &[])?;
+        self.output.flush()?;
+        Ok(self.output)
+    }
+
+    fn segment(&mut self, direction: Direction, flags: u8, payload: &[u8]) -> io::Result<()> {
+        let (source, destination, seq, ack) = match direction {
+            Direction::ToVm => (self.debugger, self.vm, self.debugger_seq, self.vm_seq),
+            Direction::FromVm => (self.vm, self.debugger, self.vm_seq, self.debugger_seq),
+        };
+        let frame = frame::ethernet(&Outgoing {
+            source,
+            destination,
+            seq,
+            ack,
+            flags,
+            id: self.id,
+            payload,
+        });
+        self.id = self.id.wrapping_add(1);
+        // SYN and FIN take up a sequence number, like a byte would
+        let sent = payload.len() as u32 + u32::from(flags & (TCP_SYN | TCP_FIN) != 0);
+        match direction {
+            Direction::ToVm => self.debugger_seq = self.debugger_seq.wrapping_add(sent),
+            Direction::FromVm => self.vm_seq = self.vm_seq.wrapping_add(sent),
+        }
+        let mut block = BytesMut::new();
+        file::write_enhanced_packet(&mut block, self.start + self.elapsed, &frame);
+        self.output.write_all(&block)
+    }
+}
+
+impl CaptureWriter<File> {
+    /// Starts a capture of a connection between addresses in a new file
+    pub fn create(
+        path: impl AsRef<Path>,
+        debugger: SocketAddrV4,
+        vm: SocketAddrV4,
+    ) -> io::Result<Self> {
+        Self::new(File::create(path)?, debugger, vm)
+    }
+}
+
+/// A connection between a debugger and a target VM found in a capture
+#[derive(Debug, Clone)]
+pub struct CapturedConnection {
+    pub debugger: SocketAddr,
+    pub vm: SocketAddr,
+    /// The id sizes the target VM replied with, if the reply was captured
+    pub id_sizes: Option<IdSizes>,
+}
+
+/// A jdwp packet found in a capture
+#[derive(Debug, Clone)]
+pub struct CapturedPacket {
+    /// When the last of the packet was captured, since the unix epoch
+    pub timestamp: Duration,
+    /// The index of the connection the packet was sent over
+    pub connection: usize,
+    /// Which way the packet went
+    pub direction: Direction,
+    pub packet: AnyRawPacket,
+    /// The command a reply answers, if the command was captured
+    pub command: Option<CommandData>,
+}
+
+/// The jdwp packets of the connections in a capture, in the order they were captured
+#[derive(Debug, Clone, Default)]
+pub struct Capture {
+    connections: Vec<CapturedConnection>,
+    packets: Vec<CapturedPacket>,
+}
+
+impl Capture {
+    /// Reads a pcap or pcapng capture. If the port of the target VM is given, only connections to
+    /// it are read, and they're read even if their handshake wasn't captured.
+    pub fn read(mut reader: impl Read, vm_port: Option<u16>) -> Result<Self, CaptureError> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        let mut streams: Vec<Stream> = vec![];
+        let mut indices = HashMap::new();
+        for (index, frame) in file::read_frames(&bytes)?.iter().enumerate() {
+            let Some(segment) = frame::parse(frame.link_type, frame.data) else {
+                continue;
+            };
+            if let Some(port) = vm_port {
+                if segment.source.port() != port && segment.destination.port() != port {
+                    continue;
+                }
+            }
+            let stream = *indices
+                .entry((segment.source, segment.destination))
+                .or_insert_with(|| {
+                    streams.push(Stream::new(segment.source, segment.destination));
+                    streams.len() - 1
+                });
+            streams[stream].push(index, frame.timestamp, &segment);
+        }
+        for stream in &mut streams {
+            stream.finish();
+        }
+        Ok(Self::from_streams(streams, vm_port))
+    }
+
+    /// Opens a pcap or pcapng capture file
+    pub fn open(path: impl AsRef<Path>, vm_port: Option<u16>) -> Result<Self, CaptureError> {
+        Self::read(File::open(path)?, vm_port)
+    }
+
+    /// Pairs up the streams of each connection, and orders their packets
+    fn from_streams(mut streams: Vec<Stream>, vm_port: Option<u16>) -> Self {
+        let mut connections = vec![];
+        // the packets, with the frame they were completed by
+        let mut packets = vec![];
+        while let Some(stream) = streams.pop() {
+            let reverse = streams
+                .iter()
+                .position(|other| other.source == stream.destination)
+                .filter(|&i| streams[i].destination == stream.source)
+                .map(|i| streams.remove(i));
+            let pair = [Some(stream), reverse];
+            let handshake = pair
+                .iter()
+                .flatten()
+                .filter_map(|stream| stream.handshake.map(|frame| (frame, stream)))
+                .min_by_key(|&(frame, _)| frame);
+            let vm = match (vm_port, handshake) {
+                // the debugger sends its handshake first
+                (_, Some((_, stream))) => stream.destination,
+                (Some(port), None) => {
+                    let stream = pair.iter().flatten().next().expect("a stream");
+                    if stream.destination.port() == port {
+                        stream.destination
+                    } else {
+                        stream.source
+                    }
+                }
+                (None, None) => continue,
+            };
+            let debugger = pair.iter().flatten().next().map_or(vm, |stream| {
+                if stream.source == vm {
+                    stream.destination
+                } else {
+                    stream.source
+                }
+            });
+            let connection = connections.len();
+            debug!("found a connection from {debugger} to {vm}");
+            connections.push(CapturedConnection {
+                debugger,
+                vm,
+                id_sizes: None,
+            });
+            for stream in pair.into_iter().flatten() {
+                let direction = if stream.source == vm {
+                    Direction::FromVm
+                } else {
+                    Direction::ToVm
+                };
+                for (frame, timestamp, packet) in stream.packets {
+                    packets.push((
+                        frame,
+                        CapturedPacket {
+                            timestamp,
+                            connection,
+                            direction,
+                            packet,
+                            command: None,
+                        },
+                    ));
+                }
+            }
+        }
+        // streams are found in the order they started, but were paired up from the last
+        connections.reverse();
+        let last = connections.len().saturating_sub(1);
+        for (_, packet) in &mut packets {
+            packet.connection = last - packet.connection;
+        }
+        packets.sort_by_key(|(frame, _)| *frame);
+
+        let mut capture = Self {
+            connections,
+            packets: packets.into_iter().map(|(_, packet)| packet).collect(),
+        };
+        capture.match_replies();
+        capture
+    }
+
+    /// Finds the command each reply answers, keeping the id sizes the target VM replied with
+    fn match_replies(&mut self) {
+        let mut commands = HashMap::new();
+        for packet in &mut self.packets {
+            match &packet.packet {
+                AnyRawPacket::Command(command) => {
+                    commands.insert(
+                        (packet.connection, packet.direction, command.header().id()),
+                        command.header().command(),
+                    );
+                }
+                AnyRawPacket::Reply(reply) => {
+                    let asked = match packet.direction {
+                        Direction::ToVm => Direction::FromVm,
+                        Direction::FromVm => Direction::ToVm,
+                    };
+                    packet.command = commands
+                        .remove(&(packet.connection, asked, reply.header().id()))
+                        .or_else(|| {
+                            warn!("no command was captured for reply #{}", reply.header().id());
+                            None
+                        });
+                    if packet.command != Some(commands::IdSizes::command_data())
+                        || reply.header().error_code().code() != 0
+                    {
+                        continue;
+                    }
+                    let codec = JdwpCodec::default();
+                    let mut decoder = JdwpDecoder::new(&codec, reply.data().clone());
+                    match decoder.get::<IdSizesReply>() {
+                        Ok(sizes) => {
+                            self.connections[packet.connection].id_sizes = Some(IdSizes::new(
+                                sizes.object_id_size as usize,
+                                sizes.method_id_size as usize,
+                                sizes.field_id_size as usize,
+                                sizes.frame_id_size as usize,
+                            ))
+                        }
+                        Err(e) => warn!("could not decode the id sizes: {e}"),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Gets the connections found in the capture
+    pub fn connections(&self) -> &[CapturedConnection] {
+        &self.connections
+    }
+
+    /// Gets the jdwp packets found in the capture
+    pub fn packets(&self) -> &[CapturedPacket] {
+        &self.packets
+    }
+
+    /// Sets the id sizes of a connection, for captures that start after the target VM replied
+    /// with them
+    pub fn set_id_sizes(&mut self, connection: usize, id_sizes: IdSizes) {
+        self.connections[connection].id_sizes = Some(id_sizes);
+    }
+
+    /// Decodes the data of a packet
+    pub fn decode<T: JdwpDecodable>(&self, packet: &CapturedPacket) -> Result<T, CaptureError> {
+        let id_sizes = self.connections[packet.connection]
+            .id_sizes
+            .ok_or(CaptureError::UnknownIdSizes(packet.connection))?;
+        let codec = JdwpCodec::new(id_sizes);
+        let data = match &packet.packet {
+            AnyRawPacket::Command(command) => command.data().clone(),
+            AnyRawPacket::Reply(reply) => reply.data().clone(),
+        };
+        JdwpDecoder::new(&codec, data)
+            .get::<T>()
+            .map_err(|e| CaptureError::Decode(e.to_string()))
+    }
+
+    /// Decodes a reply to a command, failing if the command failed
+    pub fn reply<C: JdwpCommand>(&self, packet: &CapturedPacket) -> Result<C::Reply, CaptureError> {
+        let command = C::command_data();
+        match &packet.packet {
+            AnyRawPacket::Reply(reply) if packet.command == Some(command) => {
+                let code = reply.header().error_code().code();
+                if code != 0 {
+                    return Err(ReplyError::new(command, code).into());
+                }
+                self.decode::<C::Reply>(packet)
+            }
+            _ => Err(CaptureError::NotAReplyTo(command)),
+        }
+    }
+
+    /// Describes a packet, decoding it with the id sizes of its connection if they're known
+    pub fn describe(&self, packet: &CapturedPacket) -> Description {
+        let id_sizes = self.connections[packet.connection].id_sizes;
+        let codec = JdwpCodec::new(id_sizes.unwrap_or_default());
+        let mut description = registry().describe(&codec, &packet.packet, packet.command);
+        if id_sizes.is_none() && description.undecoded.is_none() {
+            // ids would have been decoded with the wrong sizes
+            description.data = match &packet.packet {
+                AnyRawPacket::Command(command) => Node::Bytes(command.data().clone()),
+                AnyRawPacket::Reply(reply) => Node::Bytes(reply.data().clone()),
+            };
+            description.undecoded =
+                Some(CaptureError::UnknownIdSizes(packet.connection).to_string());
+        }
+        description
+    }
+
+    /// Decodes the events of a composite event command
+    pub fn events(&self, packet: &CapturedPacket) -> Result<Events, CaptureError> {
+        let id_sizes = self.connections[packet.connection]
+            .id_sizes
+            .ok_or(CaptureError::UnknownIdSizes(packet.connection))?;
+        match &packet.packet {
+            AnyRawPacket::Command(command)
+                if command.header().command() == Events::command_data() =>
+            {
+                Ok(to_events(command.clone(), &JdwpCodec::new(id_sizes))?)
+            }
+            _ => Err(CaptureError::Decode(
+                "the packet isn't a composite event".to_string(),
+            )),
+        }
+    }
+}
+
+/// The bytes sent one way over a TCP connection, put back in order and decoded into packets
+#[derive(Debug)]
+struct Stream {
+    source: SocketAddr,
+    destination: SocketAddr,
+    /// The sequence number of the first byte of the stream
+    base: Option<u32>,
+    /// How far into the stream the bytes have been taken
+    taken: u64,
+    /// The payloads of segments received ahead of the bytes before them, by where they start
+    pending: BTreeMap<u64, Vec<u8>>,
+    buffer: BytesMut,
+    /// Whether the handshake has been looked for
+    checked_handshake: bool,
+    /// The frame that completed the handshake, if the stream started with one
+    handshake: Option<usize>,
+    /// Whether bytes are missing from the stream, so nothing more can be decoded
+    broken: bool,
+    /// The packets decoded, with the frame that completed each and when it was captured
+    packets: Vec<(usize, Duration, AnyRawPacket)>,
+}
+
+impl Stream {
+    fn new(source: SocketAddr, destination: SocketAddr) -> Self {
+        Self {
+            source,
+            destination,
+            base: None,
+            taken: 0,
+            pending: BTreeMap::new(),
+            buffer: BytesMut::new(),
+            checked_handshake: false,
+            handshake: None,
+            broken: false,
+            packets: vec![],
+        }
+    }
+
+    fn push(&mut self, frame: usize, timestamp: Duration, segment: &Segment) {
+        if segment.flags & TCP_SYN != 0 {
+            self.base = Some(segment.seq.wrapping_add(1));
+        }
+        if segment.payload.is_empty() {
+            return;
+        }
+        let base = *self.base.get_or_insert(segment.seq);
+        let offset = segment.seq.wrapping_sub(base);
+        // retransmissions of bytes from before the capture started
+        if offset > i32::MAX as u32 {
+            return;
+        }
+        let offset = u64::from(offset);
+        if offset + segment.payload.len() as u64 <= self.taken {
+            return;
+        }
+        let longer = self
+            .pending
+            .get(&offset)
+            .map_or(true, |pending| pending.len() < segment.payload.len());
+        if longer {
+            self.pending.insert(offset, segment.payload.to_vec());
+        }
+        self.take(frame, timestamp);
+    }
+
+    /// Takes the segments that continue the stream, now that a frame has been captured
+    fn take(&mut self, frame: usize, timestamp: Duration) {
+        while let Some(entry) = self.pending.first_entry() {
+            if *entry.key() > self.taken {
+                break;
+            }
+            let (offset, payload) = entry.remove_entry();
+            let skip = (self.taken - offset) as usize;
+            if skip < payload.len() {
+                self.buffer.extend_from_slice(&payload[skip..]);
+                self.taken = offset + payload.len() as u64;
+            }
+        }
+        self.decode(frame, timestamp);
+    }
+
+    fn decode(&mut self, frame: usize, timestamp: Duration) {
+        if self.broken {
+            return;
+        }
+        if !self.checked_handshake {
+            if self.buffer.len() < JDWP_HANDSHAKE.len() && JDWP_HANDSHAKE.starts_with(&self.buffer)
+            {
+                return;
+            }
+            self.checked_handshake = true;
+            if self.buffer.starts_with(JDWP_HANDSHAKE) {
+                self.buffer.advance(JDWP_HANDSHAKE.len());
+                self.handshake = Some(frame);
+            }
+        }
+        loop {
+            match RawCodec.decode(&mut self.buffer) {
+                Ok(Some(packet)) => self.packets.push((frame, timestamp, packet)),
+                Ok(None) => break,
+                Err(e) => {
+                    warn!(
+                        "could not decode a packet from {} to {}: {e}",
+                        self.source, self.destination
+                    );
+                    self.broken = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Gives up on any bytes that were never captured
+    fn finish(&mut self) {
+        if let Some((&offset, _)) = self.pending.first_key_value() {
+            warn!(
+                "{} bytes from {} to {} weren't captured, so the rest can't be decoded",
+                offset - self.taken,
+                self.source,
+                self.destination
+            );
+            self.broken = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::JdwpEncoder;
+    use crate::commands::{IdSizes as IdSizesCommand, Version};
+    use crate::events::Event;
+    use crate::raw::packet::{ErrorCode, RawCommandPacket, RawReplyPacket};
+    use bytes::Bytes;
+    use jdwp_types::{Id, Int, SuspendPolicy};
+    use std::net::Ipv4Addr;
+
+    fn packet(direction: Direction, packet: AnyRawPacket) -> RecordedPacket {
+        RecordedPacket {
+            direction,
+            elapsed: Duration::ZERO,
+            packet,
+        }
+    }
+
+    fn command(id: u32, command: CommandData, data: Bytes) -> RecordedPacket {
+        packet(
+            Direction::ToVm,
+            AnyRawPacket::Command(RawCommandPacket::new_command(id, command, data)),
+        )
+    }
+
+    fn reply(id: u32, code: u16, data: Bytes) -> RecordedPacket {
+        packet(
+            Direction::FromVm,
+            AnyRawPacket::Reply(RawReplyPacket::new_reply(id, ErrorCode::new(code), data)),
+        )
+    }
+
+    fn id_sizes_reply() -> Bytes {
+        let codec = JdwpCodec::default();
+        let mut encoder = JdwpEncoder::new(&codec);
+        for size in [8, 8, 8, 8, 8] {
+            encoder.put(&(size as Int));
+        }
+        encoder.into_bytes()
+    }
+
+    #[test]
+    fn test_capture_round_trip() {
+        let debugger = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 50123);
+        let vm = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 5005);
+        let mut writer = CaptureWriter::new(vec![], debugger, vm).unwrap();
+        let codec = JdwpCodec::new(IdSizes::new(8, 8, 8, 8));
+        let mut encoder = JdwpEncoder::new(&codec);
+        encoder.put(&Events {
+            policy: SuspendPolicy::All,
+            events: vec![Event::VmStart {
+                request_id: 0,
+                thread: Id::new(1),
+            }],
+        });
+        let events = encoder.into_bytes();
+        // bigger than a segment can carry
+        let large = Bytes::from(vec![7; MAX_SEGMENT * 2]);
+        for packet in [
+            command(1, IdSizesCommand::command_data(), Bytes::new()),
+            reply(1, 0, id_sizes_reply()),
+            packet(
+                Direction::FromVm,
+                AnyRawPacket::Command(RawCommandPacket::new_command(
+                    1,
+                    Events::command_data(),
+                    events,
+                )),
+            ),
+            command(2, Version::command_data(), Bytes::new()),
+            reply(2, 99, Bytes::new()),
+            command(3, CommandData::new(1, 1), large.clone()),
+        ] {
+            writer.write(&packet).unwrap();
+        }
+        let bytes = writer.finish().unwrap();
+
+        let capture = Capture::read(bytes.as_slice(), None).unwrap();
+        assert_eq!(capture.connections().len(), 1);
+        assert_eq!(capture.connections()[0].debugger, debugger.into());
+        assert_eq!(capture.connections()[0].vm, vm.into());
+        let packets = capture.packets();
+        assert_eq!(packets.len(), 6);
+        assert_eq!(packets[1].direction, Direction::FromVm);
+        assert_eq!(packets[1].command, Some(IdSizesCommand::command_data()));
+        let sizes = capture.reply::<IdSizesCommand>(&packets[1]).unwrap();
+        assert_eq!(sizes.object_id_size, 8);
+
+        let events = capture.events(&packets[2]).unwrap();
+        assert_eq!(events.policy, SuspendPolicy::All);
+        assert_eq!(events.events.len(), 1);
+
+        let error = capture.reply::<Version>(&packets[4]).unwrap_err();
+        assert!(matches!(error, CaptureError::Reply(e) if e.code() == 99));
+        assert!(matches!(
+            capture.reply::<Version>(&packets[1]),
+            Err(CaptureError::NotAReplyTo(_))
+        ));
+        assert!(matches!(&packets[5].packet, AnyRawPacket::Command(c) if *c.data() == large));
+
+        let description = capture.describe(&packets[1]);
+        assert_eq!(description.name.as_deref(), Some("VirtualMachine.IDSizes"));
+        assert_eq!(description.undecoded, None);
+    }
+
+    #[test]
+    fn test_reassemble_out_of_order() {
+        let source = "10.0.0.1:50123".parse().unwrap();
+        let destination = "10.0.0.2:5005".parse().unwrap();
+        let mut bytes = BytesMut::new();
+        let command = RawCommandPacket::new_command(1, CommandData::new(1, 1), Bytes::new());
+        RawCodec.encode(command.clone(), &mut bytes).unwrap();
+        let bytes = [JDWP_HANDSHAKE.as_slice(), &bytes].concat();
+        let segment = |seq: u32, range: std::ops::Range<usize>| Segment {
+            source,
+            destination,
+            seq,
+            flags: TCP_ACK,
+            payload: &bytes[range],
+        };
+
+        let mut stream = Stream::new(source, destination);
+        stream.push(0, Duration::ZERO, &segment(100, 0..0));
+        stream.push(1, Duration::ZERO, &segment(100, 0..5));
+        stream.push(2, Duration::ZERO, &segment(110, 10..bytes.len()));
+        assert!(stream.packets.is_empty());
+        // a retransmission overlapping what was already taken fills the gap
+        stream.push(3, Duration::ZERO, &segment(103, 3..10));
+        stream.finish();
+        assert_eq!(stream.handshake, Some(3));
+        assert!(!stream.broken);
+        assert_eq!(stream.packets.len(), 1);
+        assert!(matches!(&stream.packets[0].2, AnyRawPacket::Command(c) if *c == command));
+    }
+}
@@ -0,0 +1,318 @@
+//! Reads the frames of pcap and pcapng files, and writes pcapng files
+
+use super::frame::supported;
+use super::CaptureError;
+use bytes::{BufMut, BytesMut};
+use std::time::Duration;
+
+const SECTION_HEADER: [u8; 4] = [0x0a, 0x0d, 0x0d, 0x0a];
+const BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+const INTERFACE_DESCRIPTION: u32 = 1;
+const SIMPLE_PACKET: u32 = 3;
+const ENHANCED_PACKET: u32 = 6;
+/// The option of an interface description giving the resolution of its timestamps
+const IF_TSRESOL: u16 = 9;
+
+/// A frame captured on an interface
+#[derive(Debug)]
+pub(super) struct Frame<'a> {
+    /// The time since the unix epoch the frame was captured at
+    pub(super) timestamp: Duration,
+    pub(super) link_type: u32,
+    pub(super) data: &'a [u8],
+}
+
+/// The byte order of a file
+#[derive(Debug, Copy, Clone)]
+struct Endian {
+    little: bool,
+}
+
+impl Endian {
+    fn u16(self, bytes: &[u8], at: usize) -> Result<u16, CaptureError> {
+        let bytes = bytes
+            .get(at..at + 2)
+            .ok_or(CaptureError::Truncated)?
+            .try_into()
+            .expect("two bytes");
+        Ok(if self.little {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32(self, bytes: &[u8], at: usize) -> Result<u32, CaptureError> {
+        let bytes = bytes
+            .get(at..at + 4)
+            .ok_or(CaptureError::Truncated)?
+            .try_into()
+            .expect("four bytes");
+        Ok(if self.little {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+}
+
+/// The units of the timestamps of an interface
+#[derive(Debug, Copy, Clone)]
+enum Resolution {
+    /// Units of 10^-n seconds
+    Decimal(u32),
+    /// Units of 2^-n seconds
+    Binary(u32),
+}
+
+impl Resolution {
+    fn duration(self, timestamp: u64) -> Duration {
+        let nanos = match self {
+            Resolution::Decimal(exponent) if exponent <= 9 => {
+                u128::from(timestamp) * 10u128.pow(9 - exponent)
+            }
+            Resolution::Decimal(exponent) => {
+                u128::from(timestamp) / 10u128.pow(exponent.min(38) - 9)
+            }
+            Resolution::Binary(exponent) => {
+                (u128::from(timestamp) * 1_000_000_000) >> exponent.min(127)
+            }
+        };
+        Duration::from_nanos(nanos as u64)
+    }
+}
+
+/// Reads the frames of a pcap or pcapng file
+pub(super) fn read_frames(bytes: &[u8]) -> Result<Vec<Frame<'_>>, CaptureError> {
+    let magic = bytes.get(..4).ok_or(CaptureError::NotACapture)?;
+    let pcap = |little, resolution| read_pcap(bytes, Endian { little }, resolution);
+    match magic {
+        [0x0a, 0x0d, 0x0d, 0x0a] => read_pcapng(bytes),
+        [0xd4, 0xc3, 0xb2, 0xa1] => pcap(true, Resolution::Decimal(6)),
+        [0xa1, 0xb2, 0xc3, 0xd4] => pcap(false, Resolution::Decimal(6)),
+        [0x4d, 0x3c, 0xb2, 0xa1] => pcap(true, Resolution::Decimal(9)),
+        [0xa1, 0xb2, 0x3c, 0x4d] => pcap(false, Resolution::Decimal(9)),
+        _ => Err(CaptureError::NotACapture),
+    }
+}
+
+fn read_pcap(
+    bytes: &[u8],
+    endian: Endian,
+    resolution: Resolution,
+) -> Result<Vec<Frame<'_>>, CaptureError> {
+    // the upper bits of the link type can describe the frame check sequence
+    let link_type = endian.u32(bytes, 20)? & 0x0fff_ffff;
+    if !supported(link_type) {
+        return Err(CaptureError::UnsupportedLinkType(link_type));
+    }
+    let mut frames = vec![];
+    let mut offset = 24;
+    while offset < bytes.len() {
+        let seconds = endian.u32(bytes, offset)?;
+        let fraction = endian.u32(bytes, offset + 4)?;
+        let captured = endian.u32(bytes, offset + 8)? as usize;
+        let data = bytes
+            .get(offset + 16..offset + 16 + captured)
+            .ok_or(CaptureError::Truncated)?;
+        let timestamp =
+            Duration::from_secs(u64::from(seconds)) + resolution.duration(u64::from(fraction));
+        frames.push(Frame {
+            timestamp,
+            link_type,
+            data,
+        });
+        offset += 16 + captured;
+    }
+    Ok(frames)
+}
+
+fn read_pcapng(bytes: &[u8]) -> Result<Vec<Frame<'_>>, CaptureError> {
+    let mut frames = vec![];
+    let mut endian = Endian { little: true };
+    let mut interfaces = vec![];
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let block_type = bytes
+            .get(offset..offset + 4)
+            .ok_or(CaptureError::Truncated)?;
+        if block_type == SECTION_HEADER {
+            // each section has its own byte order and interfaces
+            endian = match bytes.get(offset + 8..offset + 12) {
+                Some([0x4d, 0x3c, 0x2b, 0x1a]) => Endian { little: true },
+                Some([0x1a, 0x2b, 0x3c, 0x4d]) => Endian { little: false },
+                Some(_) => return Err(CaptureError::NotACapture),
+                None => return Err(CaptureError::Truncated),
+            };
+            interfaces.clear();
+        }
+        let block_type = endian.u32(bytes, offset)?;
+        let length = endian.u32(bytes, offset + 4)? as usize;
+        if length < 12 || length % 4 != 0 {
+            return Err(CaptureError::Truncated);
+        }
+        let body = bytes
+            .get(offset + 8..offset + length - 4)
+            .ok_or(CaptureError::Truncated)?;
+        match block_type {
+            INTERFACE_DESCRIPTION => {
+                let link_type = u32::from(endian.u16(body, 0)?);
+                if !supported(link_type) {
+                    return Err(CaptureError::UnsupportedLinkType(link_type));
+                }
+                interfaces.push((link_type, resolution(body.get(8..).unwrap_or(&[]), endian)?));
+            }
+            ENHANCED_PACKET => {
+                let interface = endian.u32(body, 0)? as usize;
+                let &(link_type, resolution) = interfaces
+                    .get(interface)
+                    .ok_or(CaptureError::UnknownInterface(interface))?;
+                let timestamp =
+                    u64::from(endian.u32(body, 4)?) << 32 | u64::from(endian.u32(body, 8)?);
+                let captured = endian.u32(body, 12)? as usize;
+                let data = body.get(20..20 + captured).ok_or(CaptureError::Truncated)?;
+                frames.push(Frame {
+                    timestamp: resolution.duration(timestamp),
+                    link_type,
+                    data,
+                });
+            }
+            SIMPLE_PACKET => {
+                // simple packets are captured on the first interface, with no timestamp
+                let &(link_type, _) = interfaces
+                    .first()
+                    .ok_or(CaptureError::UnknownInterface(0))?;
+                let original = endian.u32(body, 0)? as usize;
+                let data = &body[4..(4 + original).min(body.len())];
+                frames.push(Frame {
+                    timestamp: Duration::ZERO,
+                    link_type,
+                    data,
+                });
+            }
+            _ => {}
+        }
+        offset += length;
+    }
+    Ok(frames)
+}
+
+/// Finds the resolution of timestamps in the options of an interface description
+fn resolution(mut options: &[u8], endian: Endian) -> Result<Resolution, CaptureError> {
+    while options.len() >= 4 {
+        let code = endian.u16(options, 0)?;
+        let length = usize::from(endian.u16(options, 2)?);
+        if code == 0 {
+            break;
+        }
+        if code == IF_TSRESOL && length >= 1 {
+            let value = *options.get(4).ok_or(CaptureError::Truncated)?;
+            let exponent = u32::from(value & 0x7f);
+            return Ok(if value & 0x80 == 0 {
+                Resolution::Decimal(exponent)
+            } else {
+                Resolution::Binary(exponent)
+            });
+        }
+        options = options.get(4 + length.next_multiple_of(4)..).unwrap_or(&[]);
+    }
+    Ok(Resolution::Decimal(6))
+}
+
+/// Writes the header of a pcapng section, in little-endian order like every block written
+pub(super) fn write_section_header(output: &mut BytesMut) {
+    output.put_slice(&SECTION_HEADER);
+    output.put_u32_le(28);
+    output.put_u32_le(BYTE_ORDER_MAGIC);
+    output.put_u16_le(1);
+    output.put_u16_le(0);
+    // the length of the section isn't known
+    output.put_i64_le(-1);
+    output.put_u32_le(28);
+}
+
+/// Writes the description of an interface, whose timestamps are in microseconds
+pub(super) fn write_interface_description(output: &mut BytesMut, link_type: u32) {
+    output.put_u32_le(INTERFACE_DESCRIPTION);
+    output.put_u32_le(20);
+    output.put_u16_le(link_type as u16);
+    output.put_u16_le(0);
+    // frames aren't cut short
+    output.put_u32_le(0);
+    output.put_u32_le(20);
+}
+
+/// Writes a frame captured on the first interface
+pub(super) fn write_enhanced_packet(output: &mut BytesMut, timestamp: Duration, frame: &[u8]) {
+    let padded = frame.len().next_multiple_of(4);
+    let length = (32 + padded) as u32;
+    let micros = timestamp.as_micros() as u64;
+    output.put_u32_le(ENHANCED_PACKET);
+    output.put_u32_le(length);
+    output.put_u32_le(0);
+    output.put_u32_le((micros >> 32) as u32);
+    output.put_u32_le(micros as u32);
+    output.put_u32_le(frame.len() as u32);
+    output.put_u32_le(frame.len() as u32);
+    output.put_slice(frame);
+    output.put_bytes(0, padded - frame.len());
+    output.put_u32_le(length);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::frame::LINKTYPE_ETHERNET;
+
+    #[test]
+    fn test_pcapng_round_trip() {
+        let mut output = BytesMut::new();
+        write_section_header(&mut output);
+        write_interface_description(&mut output, LINKTYPE_ETHERNET);
+        write_enhanced_packet(&mut output, Duration::from_micros(1_500_000), b"abcde");
+        let frames = read_frames(&output).expect("could not read");
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].timestamp, Duration::from_micros(1_500_000));
+        assert_eq!(frames[0].link_type, LINKTYPE_ETHERNET);
+        assert_eq!(frames[0].data, b"abcde");
+    }
+
+    #[test]
+    fn test_read_pcap() {
+        // big-endian with nanosecond timestamps, as some tcpdumps write
+        let mut bytes = vec![0xa1, 0xb2, 0x3c, 0x4d, 0, 2, 0, 4];
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&65535u32.to_be_bytes());
+        bytes.extend_from_slice(&113u32.to_be_bytes());
+        bytes.extend_from_slice(&2u32.to_be_bytes());
+        bytes.extend_from_slice(&5u32.to_be_bytes());
+        bytes.extend_from_slice(&3u32.to_be_bytes());
+        bytes.extend_from_slice(&3u32.to_be_bytes());
+        bytes.extend_from_slice(b"xyz");
+        let frames = read_frames(&bytes).expect("could not read");
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].timestamp, Duration::new(2, 5));
+        assert_eq!(frames[0].link_type, 113);
+        assert_eq!(frames[0].data, b"xyz");
+    }
+
+    #[test]
+    fn test_resolution() {
+        assert_eq!(
+            Resolution::Decimal(3).duration(1500),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(
+            Resolution::Binary(10).duration(1024),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn test_not_a_capture() {
+        assert!(matches!(
+            read_frames(b"JDWPREC1"),
+            Err(CaptureError::NotACapture)
+        ));
+    }
+}
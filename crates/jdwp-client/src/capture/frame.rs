@@ -0,0 +1,279 @@
+//! Frames the link layer, IP and TCP headers around segments, and finds the segments in frames
+
+use bytes::{BufMut, BytesMut};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4};
+
+/// Ethernet frames
+pub(super) const LINKTYPE_ETHERNET: u32 = 1;
+/// BSD loopback, with the address family in host byte order
+const LINKTYPE_NULL: u32 = 0;
+/// Raw IP packets
+const LINKTYPE_RAW: u32 = 101;
+/// Raw IP packets, as some systems number them
+const LINKTYPE_RAW_OPENBSD: u32 = 14;
+/// Linux cooked captures, as `tcpdump -i any` writes them
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+/// Linux cooked captures, version 2
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+
+const PROTOCOL_TCP: u8 = 6;
+
+pub(super) const TCP_FIN: u8 = 0x01;
+pub(super) const TCP_SYN: u8 = 0x02;
+pub(super) const TCP_PSH: u8 = 0x08;
+pub(super) const TCP_ACK: u8 = 0x10;
+
+const IPV4_HEADER: usize = 20;
+const TCP_HEADER: usize = 20;
+
+/// The most payload a synthesised segment carries, so its IP packet fits the 16-bit total length
+pub(super) const MAX_SEGMENT: usize = u16::MAX as usize - IPV4_HEADER - TCP_HEADER;
+
+/// Whether frames of a link type can be read
+pub(super) fn supported(link_type: u32) -> bool {
+    matches!(
+        link_type,
+        LINKTYPE_ETHERNET
+            | LINKTYPE_NULL
+            | LINKTYPE_RAW
+            | LINKTYPE_RAW_OPENBSD
+            | LINKTYPE_LINUX_SLL
+            | LINKTYPE_IPV4
+            | LINKTYPE_IPV6
+            | LINKTYPE_LINUX_SLL2
+    )
+}
+
+/// A TCP segment found in a frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Segment<'a> {
+    pub(super) source: SocketAddr,
+    pub(super) destination: SocketAddr,
+    pub(super) seq: u32,
+    pub(super) flags: u8,
+    pub(super) payload: &'a [u8],
+}
+
+/// Finds the TCP segment in a frame of a link type, if it holds one
+pub(super) fn parse(link_type: u32, frame: &[u8]) -> Option<Segment<'_>> {
+    let (ethertype, packet) = match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut ethertype = u16::from_be_bytes(frame.get(12..14)?.try_into().ok()?);
+            let mut offset = 14;
+            while ethertype == ETHERTYPE_VLAN {
+                ethertype = u16::from_be_bytes(frame.get(offset + 2..offset + 4)?.try_into().ok()?);
+                offset += 4;
+            }
+            (Some(ethertype), frame.get(offset..)?)
+        }
+        LINKTYPE_NULL => (None, frame.get(4..)?),
+        LINKTYPE_LINUX_SLL => {
+            let protocol = u16::from_be_bytes(frame.get(14..16)?.try_into().ok()?);
+            (Some(protocol), frame.get(16..)?)
+        }
+        LINKTYPE_LINUX_SLL2 => {
+            let protocol = u16::from_be_bytes(frame.get(0..2)?.try_into().ok()?);
+            (Some(protocol), frame.get(20..)?)
+        }
+        _ => (None, frame),
+    };
+    match (ethertype, packet.first()? >> 4) {
+        (Some(ETHERTYPE_IPV4) | None, 4) => parse_ipv4(packet),
+        (Some(ETHERTYPE_IPV6) | None, 6) => parse_ipv6(packet),
+        _ => None,
+    }
+}
+
+fn parse_ipv4(packet: &[u8]) -> Option<Segment<'_>> {
+    let header = usize::from(packet.first()? & 0x0f) * 4;
+    let total = usize::from(u16::from_be_bytes(packet.get(2..4)?.try_into().ok()?));
+    let fragment = u16::from_be_bytes(packet.get(6..8)?.try_into().ok()?);
+    // only the first fragment has the TCP header, and the rest can't be told apart from it
+    if packet.get(9)? != &PROTOCOL_TCP || fragment & 0x3fff != 0 {
+        return None;
+    }
+    let source = Ipv4Addr::from(<[u8; 4]>::try_from(packet.get(12..16)?).ok()?);
+    let destination = Ipv4Addr::from(<[u8; 4]>::try_from(packet.get(16..20)?).ok()?);
+    // frames can be padded past the end of the IP packet, or cut short by the snapshot length
+    let end = total.min(packet.len());
+    parse_tcp(source.into(), destination.into(), packet.get(header..end)?)
+}
+
+fn parse_ipv6(packet: &[u8]) -> Option<Segment<'_>> {
+    let payload = usize::from(u16::from_be_bytes(packet.get(4..6)?.try_into().ok()?));
+    if packet.get(6)? != &PROTOCOL_TCP {
+        return None;
+    }
+    let source = Ipv6Addr::from(<[u8; 16]>::try_from(packet.get(8..24)?).ok()?);
+    let destination = Ipv6Addr::from(<[u8; 16]>::try_from(packet.get(24..40)?).ok()?);
+    let end = (40 + payload).min(packet.len());
+    parse_tcp(source.into(), destination.into(), packet.get(40..end)?)
+}
+
+fn parse_tcp(source: IpAddr, destination: IpAddr, segment: &[u8]) -> Option<Segment<'_>> {
+    let source_port = u16::from_be_bytes(segment.get(0..2)?.try_into().ok()?);
+    let destination_port = u16::from_be_bytes(segment.get(2..4)?.try_into().ok()?);
+    let seq = u32::from_be_bytes(segment.get(4..8)?.try_into().ok()?);
+    let header = usize::from(segment.get(12)? >> 4) * 4;
+    let flags = *segment.get(13)?;
+    Some(Segment {
+        source: SocketAddr::new(source, source_port),
+        destination: SocketAddr::new(destination, destination_port),
+        seq,
+        flags,
+        payload: segment.get(header..)?,
+    })
+}
+
+/// The fields of a TCP segment to frame
+pub(super) struct Outgoing<'a> {
+    pub(super) source: SocketAddrV4,
+    pub(super) destination: SocketAddrV4,
+    pub(super) seq: u32,
+    pub(super) ack: u32,
+    pub(super) flags: u8,
+    /// The identification of the IP packet
+    pub(super) id: u16,
+    pub(super) payload: &'a [u8],
+}
+
+/// Frames a TCP segment in IPv4 and ethernet headers. The addresses of the ethernet header are
+/// made up from the IP addresses.
+pub(super) fn ethernet(segment: &Outgoing) -> BytesMut {
+    let total = IPV4_HEADER + TCP_HEADER + segment.payload.len();
+    let mut frame = BytesMut::with_capacity(14 + total);
+    frame.put_slice(&mac(*segment.destination.ip()));
+    frame.put_slice(&mac(*segment.source.ip()));
+    frame.put_u16(ETHERTYPE_IPV4);
+
+    let mut ip = BytesMut::with_capacity(IPV4_HEADER);
+    ip.put_u8(0x45);
+    ip.put_u8(0);
+    ip.put_u16(total as u16);
+    ip.put_u16(segment.id);
+    // don't fragment
+    ip.put_u16(0x4000);
+    ip.put_u8(64);
+    ip.put_u8(PROTOCOL_TCP);
+    ip.put_u16(0);
+    ip.put_slice(&segment.source.ip().octets());
+    ip.put_slice(&segment.destination.ip().octets());
+    let sum = checksum(&[&ip]);
+    ip[10..12].copy_from_slice(&sum.to_be_bytes());
+    frame.put(ip);
+
+    let mut tcp = BytesMut::with_capacity(TCP_HEADER + segment.payload.len());
+    tcp.put_u16(segment.source.port());
+    tcp.put_u16(segment.destination.port());
+    tcp.put_u32(segment.seq);
+    tcp.put_u32(segment.ack);
+    tcp.put_u8((TCP_HEADER as u8 / 4) << 4);
+    tcp.put_u8(segment.flags);
+    tcp.put_u16(u16::MAX);
+    tcp.put_u16(0);
+    tcp.put_u16(0);
+    tcp.put_slice(segment.payload);
+    let mut pseudo_header = BytesMut::with_capacity(12);
+    pseudo_header.put_slice(&segment.source.ip().octets());
+    pseudo_header.put_slice(&segment.destination.ip().octets());
+    pseudo_header.put_u8(0);
+    pseudo_header.put_u8(PROTOCOL_TCP);
+    pseudo_header.put_u16(tcp.len() as u16);
+    let sum = checksum(&[&pseudo_header, &tcp]);
+    tcp[16..18].copy_from_slice(&sum.to_be_bytes());
+    frame.put(tcp);
+    frame
+}
+
+/// A locally administered MAC address for an IP address
+fn mac(ip: Ipv4Addr) -> [u8; 6] {
+    let [a, b, c, d] = ip.octets();
+    [0x02, 0x00, a, b, c, d]
+}
+
+/// The internet checksum of some bytes, as if they were one run
+fn checksum(parts: &[&[u8]]) -> u16 {
+    let mut sum = 0u32;
+    let mut bytes = parts.iter().flat_map(|part| part.iter().copied());
+    while let Some(high) = bytes.next() {
+        let low = bytes.next().unwrap_or(0);
+        sum += u32::from(u16::from_be_bytes([high, low]));
+    }
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum() {
+        // the example of RFC 1071
+        let bytes = [0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7];
+        assert_eq!(checksum(&[&bytes]), !0xddf2);
+        assert_eq!(checksum(&[&bytes[..3], &bytes[3..]]), !0xddf2);
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let source = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 50123);
+        let destination = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), 5005);
+        let frame = ethernet(&Outgoing {
+            source,
+            destination,
+            seq: 7,
+            ack: 9,
+            flags: TCP_PSH | TCP_ACK,
+            id: 1,
+            payload: b"payload",
+        });
+        // a valid IP header sums to zero with its checksum in place
+        assert_eq!(checksum(&[&frame[14..34]]), 0);
+        let segment = parse(LINKTYPE_ETHERNET, &frame).expect("no segment");
+        assert_eq!(
+            segment,
+            Segment {
+                source: source.into(),
+                destination: destination.into(),
+                seq: 7,
+                flags: TCP_PSH | TCP_ACK,
+                payload: b"payload",
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_cooked_ipv6() {
+        let mut frame = vec![0u8; 16];
+        frame[14..16].copy_from_slice(&ETHERTYPE_IPV6.to_be_bytes());
+        let mut ip = vec![0x60, 0, 0, 0, 0, 23, PROTOCOL_TCP, 64];
+        ip.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        ip.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        frame.extend_from_slice(&ip);
+        let mut tcp = vec![0u8; 20];
+        tcp[0..2].copy_from_slice(&5005u16.to_be_bytes());
+        tcp[2..4].copy_from_slice(&40000u16.to_be_bytes());
+        tcp[4..8].copy_from_slice(&3u32.to_be_bytes());
+        tcp[12] = 5 << 4;
+        tcp[13] = TCP_ACK;
+        frame.extend_from_slice(&tcp);
+        frame.extend_from_slice(b"abc");
+        // padding past the end of the IP packet isn't payload
+        frame.extend_from_slice(&[0, 0]);
+
+        let segment = parse(LINKTYPE_LINUX_SLL, &frame).expect("no segment");
+        assert_eq!(segment.source, "[::1]:5005".parse().unwrap());
+        assert_eq!(segment.destination, "[::1]:40000".parse().unwrap());
+        assert_eq!(segment.seq, 3);
+        assert_eq!(segment.payload, b"abc");
+    }
+}
@@ -1,47 +1,81 @@
 use crate::codec::{JdwpCodec, JdwpDecoder, JdwpEncoder};
-use crate::events::{to_events, EventHandler, Events, NotAnEventError};
-use crate::events::{Event, OwnedEventHandler};
+use crate::events::{
+    to_events, DeliveryMode, EventHandler, EventStream, Events, EventsHandler, HandlerId,
+    HandlerOptions, HandlerRegistration, HandlerState, NotAnEventError,
+};
 use crate::id_sizes::IdSizes;
-use crate::packet::JdwpCommand;
-use crate::raw::codec::RawCodec;
+use crate::packet::{JdwpCommand, ReplyError};
 use crate::raw::packet::{AnyRawPacket, RawCommandPacket, RawReplyPacket};
 use crate::raw::{RawJdwpClient, RawPacketSink};
-use bytes::BytesMut;
+use crate::registry::registry;
+use futures::future::BoxFuture;
 use futures::{SinkExt, StreamExt};
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
-use std::future::Future;
 use std::io;
 use std::io::{Error, ErrorKind};
-use std::marker::PhantomData;
-use std::pin::Pin;
-use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
-use tokio::net::TcpStream;
-use tokio::sync::mpsc::error::TryRecvError;
-use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
-use tokio::sync::{Mutex, RwLock};
-use tokio::task::{JoinHandle, JoinSet};
-use tokio_util::codec::{FramedRead, FramedWrite};
-use tracing::{debug, error, error_span, instrument, trace, warn, Instrument, Span};
+use tokio::sync::{broadcast, mpsc, Mutex, Notify, RwLock};
+use tokio::task::JoinSet;
+use tracing::{debug, enabled, error, error_span, instrument, trace, warn, Level, Span};
 
 use crate::commands::{Dispose, IdSizes as IdSizesCommand};
-use crate::connect::JdwpTransport;
-use tokio::sync::oneshot::Receiver as OneshotReceiver;
+use crate::connect::{handshake, JdwpTransport};
 use tokio::sync::oneshot::Sender as OneshotSender;
 
-static JDWP_HANDSHAKE: &[u8; 14] = b"JDWP-Handshake";
+/// Turns a composite into the units of work a handler performs for it, along with the events
+/// each unit handles
+type Dispatch =
+    Box<dyn Fn(Events) -> Vec<(Events, BoxFuture<'static, io::Result<()>>)> + Send + Sync>;
+
+/// Configuration for a [JdwpClient]
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    event_capacity: usize,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            event_capacity: 1024,
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Creates the default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many composite events can be queued at each stage of delivery.
+    ///
+    /// Each handler has a queue of this size, and each [event stream](JdwpClient::events) can fall
+    /// this far behind before it starts missing composites. Once a handler's queue is full,
+    /// delivering composites to every listener pauses until it has room again. Receiving packets
+    /// from the target VM never waits on delivery, so replies to commands still arrive while
+    /// composites are held, including before anything listens and while a handler waits on a
+    /// reply itself.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn event_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "event capacity must be greater than zero");
+        self.event_capacity = capacity;
+        self
+    }
+}
 
 /// A non-blocking jdwp client
 pub struct JdwpClient<T: JdwpTransport> {
     tasks: JoinSet<()>,
-    event_handlers: Arc<RwLock<Vec<OwnedEventHandler<Error>>>>,
+    event_listeners: Arc<EventListeners>,
     raw_packet_sink: Mutex<RawPacketSink<T::Output>>,
     next_id: AtomicU32,
     codec: Arc<RwLock<JdwpCodec>>,
     one_shots: Arc<RwLock<HashMap<u32, OneshotSender<RawReplyPacket>>>>,
+    disconnected: Arc<AtomicBool>,
 }
 
 impl<T: JdwpTransport> Debug for JdwpClient<T> {
@@ -58,12 +92,112 @@ impl<T: JdwpTransport> Debug for JdwpClient<T> {
 impl<Tr: JdwpTransport> JdwpClient<Tr> {
     /// Creates a new jdwp client over a transport
     pub async fn create(transport: Tr) -> io::Result<Self> {
-        create_client(transport).await
+        Self::create_with(transport, ClientConfig::default()).await
+    }
+
+    /// Creates a new jdwp client over a transport with the given configuration
+    pub async fn create_with(transport: Tr, config: ClientConfig) -> io::Result<Self> {
+        create_client(transport, config).await
+    }
+
+    /// Add an event handler for when events are received from the targeted JVM.
+    ///
+    /// Events are delivered to the handler one at a time, in the order they were received. The
+    /// handler stays registered until the returned registration is dropped.
+    pub async fn on_event<E>(&mut self, event_handler: E) -> HandlerRegistration
+    where
+        E: EventHandler<Err = io::Error> + Sync,
+        E::Future: 'static,
+    {
+        self.on_event_with(event_handler, HandlerOptions::default())
+            .await
+    }
+
+    /// Add an event handler for when events are received from the targeted JVM, with options
+    /// controlling which events are delivered to it and how
+    pub async fn on_event_with<E>(
+        &mut self,
+        event_handler: E,
+        options: HandlerOptions,
+    ) -> HandlerRegistration
+    where
+        E: EventHandler<Err = io::Error> + Sync,
+        E::Future: 'static,
+    {
+        let dispatch: Dispatch = Box::new(move |events: Events| {
+            let policy = events.policy;
+            events
+                .events
+                .into_iter()
+                .map(|event| {
+                    let handled = Events {
+                        policy,
+                        events: vec![event.clone()],
+                    };
+                    let future = Box::pin(event_handler.clone().handle_event(policy, event))
+                        as BoxFuture<'static, io::Result<()>>;
+                    (handled, future)
+                })
+                .collect()
+        });
+        self.add_handler(dispatch, options)
+    }
+
+    /// Add a handler that receives every composite event from the targeted JVM whole.
+    ///
+    /// Composites are delivered to the handler one at a time, in the order they were received.
+    /// The handler stays registered until the returned registration is dropped.
+    pub async fn on_events<H>(&mut self, events_handler: H) -> HandlerRegistration
+    where
+        H: EventsHandler<Err = io::Error> + Sync,
+        H::Future: 'static,
+    {
+        self.on_events_with(events_handler, HandlerOptions::default())
+            .await
+    }
+
+    /// Add a handler that receives every composite event from the targeted JVM whole, with
+    /// options controlling which events are delivered to it and how. Events that don't pass the
+    /// filters are removed from each composite, and empty composites aren't delivered.
+    pub async fn on_events_with<H>(
+        &mut self,
+        events_handler: H,
+        options: HandlerOptions,
+    ) -> HandlerRegistration
+    where
+        H: EventsHandler<Err = io::Error> + Sync,
+        H::Future: 'static,
+    {
+        let dispatch: Dispatch = Box::new(move |events: Events| {
+            vec![(
+                events.clone(),
+                Box::pin(events_handler.clone().handle_events(events))
+                    as BoxFuture<'static, io::Result<()>>,
+            )]
+        });
+        self.add_handler(dispatch, options)
     }
-    /// Add an event handler for when events are received from the targeted JVM
-    pub async fn on_event<E: EventHandler<Err = io::Error> + Sync>(&mut self, event_handler: E) {
-        let mut event_handlers = self.event_handlers.write().await;
-        event_handlers.push(OwnedEventHandler::new(event_handler))
+
+    fn add_handler(&mut self, dispatch: Dispatch, options: HandlerOptions) -> HandlerRegistration {
+        let (id, rx) = self.event_listeners.add_handler();
+        let state = HandlerState::new(id);
+        self.tasks
+            .spawn(handler_loop(rx, dispatch, options, state.clone()));
+        let listeners = Arc::downgrade(&self.event_listeners);
+        HandlerRegistration::new(state, move |id| {
+            if let Some(listeners) = listeners.upgrade() {
+                listeners.remove(id);
+            }
+        })
+    }
+
+    /// Subscribes to every composite event received from the targeted JVM.
+    ///
+    /// Composites are delivered whole and in the order they were received, and every stream
+    /// receives its own copy of each. Events received before anything was listening are held
+    /// until the first subscriber or handler is added.
+    pub fn events(&self) -> EventStream {
+        EventStream::new(self.event_listeners.subscribe())
     }
 
     /// Send a command to the java virtual machine, receiving a future that eventually resolves to a reply
@@ -71,7 +205,7 @@ impl<Tr: JdwpTransport> JdwpClient<Tr> {
     pub async fn send<T: JdwpCommand>(&self, command: T) -> Result<T::Reply, io::Error> {
         let encoded = {
             let codec = self.codec.read().await;
-            let mut encoder = JdwpEncoder::new(&*codec);
+            let mut encoder = JdwpEncoder::new(&codec);
             command.encode(&mut encoder);
             encoder.data.freeze()
         };
@@ -81,15 +215,40 @@ impl<Tr: JdwpTransport> JdwpClient<Tr> {
         span.record("id", id);
         let raw = RawCommandPacket::new_command(id, T::command_data(), encoded);
         let (tx, rx) = tokio::sync::oneshot::channel::<RawReplyPacket>();
-        self.one_shots.write().await.insert(id, tx);
-        trace!("one-shot for command {id} is ready, sending raw command {raw:?}");
+        {
+            let mut one_shots = self.one_shots.write().await;
+            if self.disconnected.load(Ordering::SeqCst) {
+                return Err(Error::new(
+                    ErrorKind::NotConnected,
+                    "the target VM has disconnected",
+                ));
+            }
+            one_shots.insert(id, tx);
+        }
+        if enabled!(Level::TRACE) {
+            let packet = AnyRawPacket::Command(raw.clone());
+            let description = registry().describe(&*self.codec.read().await, &packet, None);
+            trace!(
+                "one-shot for command {id} is ready, sending {}",
+                description.to_json()
+            );
+        }
         self.raw_packet_sink.lock().await.send(raw).await?;
 
         let reply = rx.await.map_err(|e| Error::new(ErrorKind::BrokenPipe, e))?;
-        trace!("got raw reply packet: {reply:?}");
+        if enabled!(Level::TRACE) {
+            let packet = AnyRawPacket::Reply(reply.clone());
+            let description =
+                registry().describe(&*self.codec.read().await, &packet, Some(T::command_data()));
+            trace!("got reply {}", description.to_json());
+        }
+        let error_code = reply.header().error_code().code();
+        if error_code != 0 {
+            return Err(Error::other(ReplyError::new(T::command_data(), error_code)));
+        }
 
         let codec = self.codec.read().await;
-        let mut decoder = JdwpDecoder::new(&*codec, reply.data().clone());
+        let mut decoder = JdwpDecoder::new(&codec, reply.data().clone());
 
         let reply = decoder
             .get::<T::Reply>()
@@ -99,29 +258,35 @@ impl<Tr: JdwpTransport> JdwpClient<Tr> {
     }
 
     #[instrument(skip_all)]
-    pub async fn dispose(mut self) -> Result<(), Error> {
-        self.send(Dispose).await?;
-        trace!("successfully disposed of client");
-        Ok(())
+    pub async fn dispose(self) -> Result<(), Error> {
+        match self.send(Dispose).await {
+            Ok(_) => {
+                trace!("successfully disposed of client");
+                Ok(())
+            }
+            Err(e) if is_disconnect(&e) => {
+                trace!("target VM already disconnected, nothing to dispose");
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
     }
 }
 
 /// creates a client
-async fn create_client<T>(transport: T) -> io::Result<JdwpClient<T>>
+async fn create_client<T>(transport: T, config: ClientConfig) -> io::Result<JdwpClient<T>>
 where
     T: JdwpTransport,
 {
     let (mut input, mut output) = transport.split_transport();
     handshake(&mut input, &mut output).await?;
     let raw_client = RawJdwpClient::<T>::new(input, output);
-    let event_handlers = Arc::new(RwLock::new(Vec::<OwnedEventHandler<io::Error>>::new()));
+    let event_listeners = Arc::new(EventListeners::new(config.event_capacity));
 
     let mut join_set = JoinSet::<()>::new();
-    let (event_tx, event_rx) = unbounded_channel::<Events>();
-    {
-        let mut event_handlers = event_handlers.clone();
-        join_set.spawn(event_handling_loop(event_rx, event_handlers.clone()));
-    }
+    // unbounded, so that delivering events never holds up the replies received after them
+    let (event_tx, event_rx) = mpsc::unbounded_channel::<Events>();
+    join_set.spawn(dispatch_loop(event_rx, event_listeners.clone()));
 
     let (mut raw_stream, raw_sink) = raw_client.into_split();
     let codec = Arc::new(RwLock::new(JdwpCodec::default()));
@@ -129,51 +294,76 @@ where
         HashMap::<u32, OneshotSender<RawReplyPacket>>::new(),
     ));
 
+    let disconnected = Arc::new(AtomicBool::new(false));
+
     {
         let codec = codec.clone();
         let one_shots = one_shots.clone();
+        let disconnected = disconnected.clone();
         join_set.spawn(async move {
             let span = error_span!("packet-recv-loop");
             let _enter = span.enter();
             while let Some(raw_event) = raw_stream.next().await {
-                let Ok(raw_event) = raw_event else {
-                    one_shots.write().await.clear();
-                    panic!("getting next packet failed");
+                let raw_event = match raw_event {
+                    Ok(raw_event) => raw_event,
+                    Err(e) => {
+                        warn!("getting next packet failed: {e}");
+                        break;
+                    }
                 };
-                let codec = codec.read().await;
                 match raw_event {
                     AnyRawPacket::Command(command) => {
-                        trace!("got command {command:?} from JVM");
+                        let codec = codec.read().await;
+                        if enabled!(Level::TRACE) {
+                            let packet = AnyRawPacket::Command(command.clone());
+                            let description = registry().describe(&codec, &packet, None);
+                            trace!("got command {} from JVM", description.to_json());
+                        }
 
-                        match to_events(command, &*codec) {
+                        let events = to_events(command, &codec);
+                        drop(codec);
+                        match events {
                             Ok(events) => {
-                                event_tx.send(events).expect("event sender dropped");
+                                if event_tx.send(events).is_err() {
+                                    warn!("event dispatcher stopped, dropping events");
+                                }
                             }
-                            Err(e) => {
+                            Err(e) if e.get_ref().is_some_and(|e| e.is::<NotAnEventError>()) => {
                                 warn!("Received unexpected command from JVM: {e}")
                             }
+                            Err(e) => {
+                                warn!("Failed to decode events from JVM: {e}")
+                            }
                         }
                     }
                     AnyRawPacket::Reply(reply) => {
                         trace!("got reply {reply:?} from JVM");
                         let id = reply.header().id();
                         if let Some(sender) = one_shots.write().await.remove(&id) {
-                            sender.send(reply).expect("could not send");
+                            if sender.send(reply).is_err() {
+                                trace!("command {id} was cancelled before its reply arrived");
+                            }
                         }
                     }
                 }
                 trace!("waiting for next packet from JVM...");
             }
+            // dropping the senders of any in-flight commands wakes them with an error
+            let mut one_shots = one_shots.write().await;
+            disconnected.store(true, Ordering::SeqCst);
+            one_shots.clear();
+            debug!("target VM disconnected");
         });
     }
 
-    let mut client = JdwpClient {
+    let client = JdwpClient {
         tasks: join_set,
-        event_handlers,
+        event_listeners,
         raw_packet_sink: Mutex::from(raw_sink),
         next_id: AtomicU32::new(1),
         codec,
         one_shots,
+        disconnected,
     };
 
     let id_sizes = client.send(IdSizesCommand).await?;
@@ -191,77 +381,198 @@ where
     Ok(client)
 }
 
-fn event_handling_loop(
-    mut event_rx: UnboundedReceiver<Events>,
-    mut event_handlers: Arc<RwLock<Vec<OwnedEventHandler<io::Error>>>>,
-) -> impl Future<Output = ()> + Sized {
-    async move {
-        let mut buffered = VecDeque::<Events>::new();
-        loop {
-            if buffered.is_empty() {
-                let Some(events) = event_rx.recv().await else {
+/// Everything listening for composite events from the target VM
+#[derive(Debug)]
+struct EventListeners {
+    registry: std::sync::Mutex<ListenerRegistry>,
+    /// Notified when a listener is added or the listeners are closed
+    changed: Notify,
+    capacity: usize,
+}
+
+#[derive(Debug)]
+struct ListenerRegistry {
+    handlers: Vec<(HandlerId, mpsc::Sender<Events>)>,
+    next_id: u64,
+    /// Sends to every [EventStream], taken once the listeners are closed
+    broadcast: Option<broadcast::Sender<Events>>,
+}
+
+impl EventListeners {
+    fn new(capacity: usize) -> Self {
+        let (broadcast, _) = broadcast::channel(capacity);
+        Self {
+            registry: std::sync::Mutex::new(ListenerRegistry {
+                handlers: vec![],
+                next_id: 0,
+                broadcast: Some(broadcast),
+            }),
+            changed: Notify::new(),
+            capacity,
+        }
+    }
+
+    fn registry(&self) -> std::sync::MutexGuard<'_, ListenerRegistry> {
+        self.registry.lock().expect("event listeners poisoned")
+    }
+
+    /// Adds a handler's queue of composites
+    fn add_handler(&self) -> (HandlerId, mpsc::Receiver<Events>) {
+        let (tx, rx) = mpsc::channel(self.capacity);
+        let mut registry = self.registry();
+        let id = HandlerId(registry.next_id);
+        registry.next_id += 1;
+        if registry.broadcast.is_some() {
+            registry.handlers.push((id, tx));
+        }
+        drop(registry);
+        self.changed.notify_one();
+        (id, rx)
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<Events> {
+        let receiver = match &self.registry().broadcast {
+            Some(broadcast) => broadcast.subscribe(),
+            None => broadcast::channel(1).1,
+        };
+        self.changed.notify_one();
+        receiver
+    }
+
+    fn remove(&self, id: HandlerId) {
+        self.registry()
+            .handlers
+            .retain(|(handler_id, _)| *handler_id != id);
+    }
+
+    /// Whether composites have somewhere to go, or never will
+    fn ready(&self) -> bool {
+        let registry = self.registry();
+        match &registry.broadcast {
+            Some(broadcast) => !registry.handlers.is_empty() || broadcast.receiver_count() > 0,
+            None => true,
+        }
+    }
+
+    /// Delivers a composite to every listener, waiting until there's at least one and until every
+    /// handler's queue has room for it
+    async fn send(&self, events: Events) {
+        while !self.ready() {
+            self.changed.notified().await;
+        }
+        let handlers = {
+            let registry = self.registry();
+            if let Some(broadcast) = &registry.broadcast {
+                let _ = broadcast.send(events.clone());
+            }
+            registry.handlers.clone()
+        };
+        for (id, handler) in handlers {
+            if handler.send(events.clone()).await.is_err() {
+                self.remove(id);
+            }
+        }
+    }
+
+    /// Ends every listener's stream of events
+    fn close(&self) {
+        let mut registry = self.registry();
+        registry.handlers.clear();
+        registry.broadcast = None;
+        drop(registry);
+        self.changed.notify_one();
+    }
+}
+
+/// Delivers composites received from the target VM to its listeners, closing them once the target
+/// VM disconnects
+async fn dispatch_loop(
+    mut event_rx: mpsc::UnboundedReceiver<Events>,
+    listeners: Arc<EventListeners>,
+) {
+    while let Some(events) = event_rx.recv().await {
+        listeners.send(events).await;
+    }
+    listeners.close();
+}
+
+/// Delivers composites to a single handler
+async fn handler_loop(
+    mut event_rx: mpsc::Receiver<Events>,
+    dispatch: Dispatch,
+    options: HandlerOptions,
+    state: Arc<HandlerState>,
+) {
+    let mut running = JoinSet::new();
+    loop {
+        tokio::select! {
+            events = event_rx.recv() => {
+                let Some(events) = events else {
                     break;
                 };
-                buffered.push_back(events);
-            } else {
-                match event_rx.try_recv() {
-                    Ok(events) => {
-                        buffered.push_back(events);
-                    }
-                    Err(TryRecvError::Empty) => {}
-                    Err(TryRecvError::Disconnected) => {
-                        break;
-                    }
+                if !state.is_registered() {
+                    break;
                 }
-            }
-
-            let mut join_set = JoinSet::new();
-            let event_handlers = event_handlers.read().await;
-            if !event_handlers.is_empty() {
-                for buffered in buffered.drain(..) {
-                    for event_handler in &*event_handlers {
-                        for event in &buffered.events {
-                            join_set.spawn(
-                                event_handler
-                                    .clone()
-                                    .handle_event(buffered.policy, event.clone()),
-                            );
+                let Some(events) = options.filter(events) else {
+                    continue;
+                };
+                let work = dispatch(events);
+                match options.delivery_mode() {
+                    DeliveryMode::Ordered => {
+                        for (events, future) in work {
+                            if let Err(e) = future.await {
+                                report(&state, events, e);
+                            }
+                            if !state.is_registered() {
+                                break;
+                            }
+                        }
+                    }
+                    DeliveryMode::Concurrent => {
+                        for (events, future) in work {
+                            running.spawn(async move {
+                                future.await.map_err(|e| (events, e))
+                            });
                         }
                     }
                 }
             }
-            if let Err(e) = join_set
-                .join_all()
-                .await
-                .into_iter()
-                .collect::<Result<Vec<_>, _>>()
-            {
-                error!("error handling events: {}", e);
+            Some(result) = running.join_next(), if !running.is_empty() => {
+                report_joined(&state, result);
             }
         }
     }
+    while let Some(result) = running.join_next().await {
+        report_joined(&state, result);
+    }
+    state.unregistered();
 }
 
-#[instrument(skip_all, ok, err)]
-async fn handshake<I, O>(mut input: I, output: &mut O) -> io::Result<()>
-where
-    I: AsyncRead + Unpin,
-    O: AsyncWrite + Unpin,
-{
-    trace!("writing {JDWP_HANDSHAKE:?} to output stream");
-    output.write_all(JDWP_HANDSHAKE).await?;
-    let mut buf = [0u8; 14];
-    trace!("waiting to read {JDWP_HANDSHAKE:?} from input stream");
-    input.read_exact(&mut buf).await?;
-    trace!("read {buf:?} from input stream");
-    if &buf == JDWP_HANDSHAKE {
-        trace!("Handshake matched");
-        Ok(())
-    } else {
-        warn!("Handshake did not match");
-        Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "Expected JDWP handshake back in response",
-        ))
+fn report(state: &HandlerState, events: Events, error: io::Error) {
+    if let Err(error) = state.report(events, error) {
+        error!("{}", error);
+    }
+}
+
+fn report_joined(
+    state: &HandlerState,
+    result: Result<Result<(), (Events, io::Error)>, tokio::task::JoinError>,
+) {
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err((events, e))) => report(state, events, e),
+        Err(e) => error!("event handler {} panicked: {}", state.id(), e),
     }
 }
+
+/// Whether an error was caused by the connection to the target VM being closed
+fn is_disconnect(error: &Error) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::BrokenPipe
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected
+            | ErrorKind::UnexpectedEof
+    )
+}
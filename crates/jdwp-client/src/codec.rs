@@ -1,14 +1,16 @@
 //! The codec for encoding and decoding packets, using [tokio-util]'s [Encoder] and [Decoder] traits
+//!
+//! [tokio-util]: tokio_util
+//! [Encoder]: tokio_util::codec::Encoder
+//! [Decoder]: tokio_util::codec::Decoder
 
 use crate::id_sizes::IdSizes;
-use crate::packet::JdwpCommand;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
+pub use jdwp_derive::{JdwpDecode, JdwpEncode};
 use jdwp_types::*;
 use std::error::Error;
-use std::str::Utf8Error;
 use std::string::FromUtf8Error;
 use thiserror::Error;
-use tokio_util::codec::{Decoder, Encoder};
 use tracing::trace;
 
 /// A codec for encoding and decoding JDWP packets
@@ -34,7 +36,7 @@ impl JdwpCodec {
 /// Encodable into bytes
 pub trait JdwpEncodable {
     /// Encodes this into a buffer
-    fn encode(&self, encoder: &mut JdwpEncoder) {}
+    fn encode(&self, _encoder: &mut JdwpEncoder) {}
 }
 
 /// Decodable from a byte buffer
@@ -48,7 +50,7 @@ impl JdwpDecodable for Byte {
     type Err = DecodeJdwpDataError;
 
     fn decode(decoder: &mut JdwpDecoder) -> Result<Self, Self::Err> {
-        if decoder.data.len() < 1 {
+        if decoder.data.is_empty() {
             return Err(DecodeJdwpDataError::NotEnoughBytes);
         }
         Ok(decoder.data.get_u8())
@@ -60,6 +62,19 @@ impl JdwpEncodable for Byte {
     }
 }
 
+impl JdwpDecodable for bool {
+    type Err = DecodeJdwpDataError;
+
+    fn decode(decoder: &mut JdwpDecoder) -> Result<Self, Self::Err> {
+        Ok(decoder.get::<Byte>()? != 0)
+    }
+}
+impl JdwpEncodable for bool {
+    fn encode(&self, encoder: &mut JdwpEncoder) {
+        encoder.data.put_u8(*self as u8);
+    }
+}
+
 impl JdwpDecodable for Int {
     type Err = DecodeJdwpDataError;
 
@@ -83,6 +98,12 @@ impl JdwpDecodable for ClassStatus {
     }
 }
 
+impl JdwpEncodable for ClassStatus {
+    fn encode(&self, encoder: &mut JdwpEncoder) {
+        encoder.data.put_u32(self.0);
+    }
+}
+
 impl JdwpEncodable for Int {
     fn encode(&self, encoder: &mut JdwpEncoder) {
         encoder.data.put_i32(*self);
@@ -107,10 +128,50 @@ impl JdwpDecodable for TaggedObjectId {
     type Err = DecodeJdwpDataError;
 
     fn decode(decoder: &mut JdwpDecoder) -> Result<Self, Self::Err> {
-        todo!()
+        let tag = decoder.get::<Tag>()?;
+        let id = decoder.get::<ObjectId>()?;
+        Ok(TaggedObjectId::new(tag, Id::new(id.get())))
     }
 }
 
+impl JdwpEncodable for TaggedObjectId {
+    fn encode(&self, encoder: &mut JdwpEncoder) {
+        encoder.put(&self.tag());
+        encoder.put(&ObjectId::new(self.id().get()));
+    }
+}
+
+macro_rules! encdec_tagged {
+    ($($tagged:ty: $repr:ty),* $(,)?) => {
+        $(
+            impl JdwpDecodable for $tagged {
+                type Err = DecodeJdwpDataError;
+
+                fn decode(decoder: &mut JdwpDecoder) -> Result<Self, Self::Err> {
+                    let raw = decoder.get::<$repr>()?;
+                    Ok(<$tagged>::try_from(raw)?)
+                }
+            }
+
+            impl JdwpEncodable for $tagged {
+                fn encode(&self, encoder: &mut JdwpEncoder) {
+                    encoder.put(&<$repr>::from(*self));
+                }
+            }
+        )*
+    };
+}
+
+encdec_tagged! {
+    Tag: Byte,
+    TypeTag: Byte,
+    EventKind: Byte,
+    SuspendPolicy: Byte,
+    StepSize: Int,
+    StepDepth: Int,
+    ThreadStatus: Int,
+}
+
 impl JdwpDecodable for std::string::String {
     type Err = DecodeJdwpDataError;
 
@@ -145,31 +206,57 @@ impl JdwpDecodable for Location {
     }
 }
 
+impl JdwpEncodable for Location {
+    fn encode(&self, encoder: &mut JdwpEncoder) {
+        encoder.put(&self.tag);
+        encoder.put(&self.class);
+        encoder.put(&self.method);
+        encoder.put(&(self.offset as Long));
+    }
+}
+
 impl JdwpDecodable for Value {
     type Err = DecodeJdwpDataError;
 
     fn decode(decoder: &mut JdwpDecoder) -> Result<Self, Self::Err> {
-        let tag = decoder.get::<Byte>().and_then(|b| Ok(Tag::try_from(b)?))?;
-
-        let value = match tag {
-            Tag::Array => Value::Array(decoder.get()?),
-            Tag::Byte => Value::Byte(decoder.get()?),
-            Tag::Char => Value::Char(decoder.data.get_u16()),
-            Tag::Object => Value::Object(decoder.get()?),
-            Tag::Float => Value::Float(decoder.data.get_f32()),
-            Tag::Double => Value::Double(decoder.data.get_f64()),
-            Tag::Int => Value::Int(decoder.get()?),
-            Tag::Long => Value::Long(decoder.get()?),
-            Tag::Short => Value::Short(decoder.data.get_i16()),
-            Tag::Void => Value::Void,
-            Tag::Boolean => Value::Boolean(decoder.data.get_u8() != 0),
-            Tag::String => Value::String(decoder.get()?),
-            Tag::Thread => Value::Thread(decoder.get()?),
-            Tag::ThreadGroup => Value::ThreadGroup(decoder.get()?),
-            Tag::ClassLoader => Value::ClassLoader(decoder.get()?),
-            Tag::ClassObject => Value::ClassObject(decoder.get()?),
+        let tag = decoder.get::<Tag>()?;
+        decoder.get_untagged_value(tag)
+    }
+}
+
+impl JdwpEncodable for Value {
+    fn encode(&self, encoder: &mut JdwpEncoder) {
+        let tag = match self {
+            Value::Array(_) => Tag::Array,
+            Value::Byte(_) => Tag::Byte,
+            Value::Boolean(_) => Tag::Boolean,
+            Value::Char(_) => Tag::Char,
+            Value::Object(_) => Tag::Object,
+            Value::Float(_) => Tag::Float,
+            Value::Double(_) => Tag::Double,
+            Value::Int(_) => Tag::Int,
+            Value::Long(_) => Tag::Long,
+            Value::Short(_) => Tag::Short,
+            Value::Void => Tag::Void,
+            Value::String(_) => Tag::String,
+            Value::Thread(_) => Tag::Thread,
+            Value::ThreadGroup(_) => Tag::ThreadGroup,
+            Value::ClassLoader(_) => Tag::ClassLoader,
+            Value::ClassObject(_) => Tag::ClassObject,
         };
-        Ok(value)
+        encoder.put(&tag);
+        encoder.put_untagged_value(self);
+    }
+}
+
+/// A value encoded without its tag, since the type of the field or array component it's the new
+/// value of is already known
+#[derive(Debug, Clone)]
+pub struct UntaggedValue(pub Value);
+
+impl JdwpEncodable for UntaggedValue {
+    fn encode(&self, encoder: &mut JdwpEncoder) {
+        encoder.put_untagged_value(&self.0);
     }
 }
 
@@ -206,6 +293,22 @@ macro_rules! encdec_id {
                     }
 
                 }
+
+                /// A null id decodes to [None]
+                impl JdwpDecodable for Option<$id_type> {
+                    type Err = DecodeJdwpDataError;
+
+                    fn decode(decoder: &mut JdwpDecoder) -> Result<Self, Self::Err> {
+                        Ok(Some(decoder.get::<$id_type>()?).filter(|id| id.get() != 0))
+                    }
+                }
+
+                /// [None] encodes to a null id
+                impl JdwpEncodable for Option<$id_type> {
+                    fn encode(&self, encoder: &mut JdwpEncoder) {
+                        encoder.put(&self.unwrap_or(<$id_type>::new(0)));
+                    }
+                }
             )*
         )*
     };
@@ -237,6 +340,15 @@ impl<T: JdwpDecodable<Err = DecodeJdwpDataError>> JdwpDecodable for Vec<T> {
     }
 }
 
+impl<T: JdwpEncodable> JdwpEncodable for Vec<T> {
+    fn encode(&self, encoder: &mut JdwpEncoder) {
+        encoder.put(&(self.len() as Int));
+        for item in self {
+            encoder.put(item);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct JdwpDecoder<'a> {
     pub(crate) codec: &'a JdwpCodec,
@@ -246,16 +358,47 @@ pub struct JdwpDecoder<'a> {
 impl<'a> JdwpDecoder<'a> {
     /// Creates a new decoder with a given codec
     pub fn new(codec: &'a JdwpCodec, data: Bytes) -> Self {
-        Self {
-            codec,
-            data: Bytes::from(data),
-        }
+        Self { codec, data }
     }
 
     /// Decodes the next jdwp value
     pub fn get<T: JdwpDecodable>(&mut self) -> Result<T, T::Err> {
         T::decode(self)
     }
+
+    /// Decodes a value whose tag isn't part of the data, as with the primitive values of an array
+    /// region
+    pub fn get_untagged_value(&mut self, tag: Tag) -> Result<Value, DecodeJdwpDataError> {
+        let size = match tag {
+            Tag::Byte | Tag::Boolean => 1,
+            Tag::Char | Tag::Short => 2,
+            Tag::Float | Tag::Int => 4,
+            Tag::Double | Tag::Long => 8,
+            Tag::Void => 0,
+            _ => self.codec.id_sizes.object_id_size(),
+        };
+        if self.data.len() < size {
+            return Err(DecodeJdwpDataError::NotEnoughBytes);
+        }
+        Ok(match tag {
+            Tag::Array => Value::Array(self.get()?),
+            Tag::Byte => Value::Byte(self.get()?),
+            Tag::Char => Value::Char(self.data.get_u16()),
+            Tag::Object => Value::Object(self.get()?),
+            Tag::Float => Value::Float(self.data.get_f32()),
+            Tag::Double => Value::Double(self.data.get_f64()),
+            Tag::Int => Value::Int(self.get()?),
+            Tag::Long => Value::Long(self.get()?),
+            Tag::Short => Value::Short(self.data.get_i16()),
+            Tag::Void => Value::Void,
+            Tag::Boolean => Value::Boolean(self.data.get_u8() != 0),
+            Tag::String => Value::String(self.get()?),
+            Tag::Thread => Value::Thread(self.get()?),
+            Tag::ThreadGroup => Value::ThreadGroup(self.get()?),
+            Tag::ClassLoader => Value::ClassLoader(self.get()?),
+            Tag::ClassObject => Value::ClassObject(self.get()?),
+        })
+    }
 }
 
 #[derive(Debug, Error)]
@@ -267,7 +410,13 @@ pub enum DecodeJdwpDataError {
     #[error(transparent)]
     IllegalByteTag(#[from] UnknownTagError<u8>),
     #[error(transparent)]
+    IllegalIntTag(#[from] UnknownTagError<Int>),
+    #[error(transparent)]
     Utf8DecodeError(#[from] FromUtf8Error),
+    #[error("Unknown event request modifier kind: {0}")]
+    UnknownModifierKind(Byte),
+    #[error("Events of kind {0:?} can not be sent by the target VM")]
+    UnexpectedEventKind(EventKind),
 }
 
 #[derive(Debug)]
@@ -285,17 +434,47 @@ impl<'a> JdwpEncoder<'a> {
         }
     }
 
-    /// Decodes the next jdwp value
+    /// Encodes the next jdwp value
     pub fn put<T: JdwpEncodable>(&mut self, to_encoded: &T) {
         to_encoded.encode(self)
     }
+
+    /// Encodes a value without its tag, as the new values of fields and array components are
+    pub fn put_untagged_value(&mut self, value: &Value) {
+        match value {
+            Value::Array(id) => self.put(id),
+            Value::Byte(b) => self.put(b),
+            Value::Boolean(b) => self.put(b),
+            Value::Char(c) => self.data.put_u16(*c),
+            Value::Object(id) => self.put(id),
+            Value::Float(f) => self.data.put_f32(*f),
+            Value::Double(d) => self.data.put_f64(*d),
+            Value::Int(i) => self.put(i),
+            Value::Long(l) => self.put(l),
+            Value::Short(s) => self.data.put_i16(*s),
+            Value::Void => {}
+            Value::String(id) => self.put(id),
+            Value::Thread(id) => self.put(id),
+            Value::ThreadGroup(id) => self.put(id),
+            Value::ClassLoader(id) => self.put(id),
+            Value::ClassObject(id) => self.put(id),
+        }
+    }
+
+    /// Gets the bytes encoded so far
+    pub fn into_bytes(self) -> Bytes {
+        self.data.freeze()
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::codec::{JdwpCodec, JdwpDecoder, JdwpEncoder};
+    use crate::codec::{
+        DecodeJdwpDataError, JdwpCodec, JdwpDecode, JdwpDecoder, JdwpEncode, JdwpEncoder,
+        UntaggedValue,
+    };
     use crate::id_sizes::IdSizes;
-    use jdwp_types::{Id, Object, ObjectId};
+    use jdwp_types::{Byte, Id, Int, Object, ObjectId, Tag, TypeTag, Value};
 
     #[test]
     fn encode_special_ids() {
@@ -326,4 +505,203 @@ mod test {
         assert_ne!(decoded_id, id);
         assert_eq!(decoded_id, Id::new(!(0xFFFF << 48)));
     }
+
+    #[test]
+    fn encode_values() {
+        let codec = JdwpCodec::new(IdSizes::new(8, 8, 8, 8));
+        let values = vec![
+            Value::Int(-3),
+            Value::Char('x' as u16),
+            Value::Double(1.5),
+            Value::Boolean(true),
+            Value::String(Id::new(7)),
+            Value::Object(Id::new(0)),
+        ];
+        let mut encoder = JdwpEncoder::new(&codec);
+        encoder.put(&values);
+        assert_eq!(encoder.data.len(), 4 + 5 + 3 + 9 + 2 + 9 + 9);
+        let mut decoder = JdwpDecoder::new(&codec, encoder.data.freeze());
+        let decoded = decoder
+            .get::<Vec<Value>>()
+            .expect("could not decode values");
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    fn encode_untagged_values() {
+        let codec = JdwpCodec::new(IdSizes::new(8, 8, 8, 8));
+        let values = vec![
+            UntaggedValue(Value::Short(-2)),
+            UntaggedValue(Value::Long(9)),
+            UntaggedValue(Value::Array(Id::new(5))),
+        ];
+        let mut encoder = JdwpEncoder::new(&codec);
+        encoder.put(&values);
+        assert_eq!(encoder.data.len(), 4 + 2 + 8 + 8);
+        let mut decoder = JdwpDecoder::new(&codec, encoder.data.freeze());
+        assert_eq!(decoder.get::<Int>().expect("no length"), 3);
+        let decoded = [Tag::Short, Tag::Long, Tag::Array]
+            .into_iter()
+            .map(|tag| decoder.get_untagged_value(tag))
+            .collect::<Result<Vec<_>, _>>()
+            .expect("could not decode values");
+        assert_eq!(
+            decoded,
+            values.into_iter().map(|value| value.0).collect::<Vec<_>>()
+        );
+    }
+
+    #[derive(Debug, PartialEq, JdwpEncode, JdwpDecode)]
+    struct Derived {
+        #[jdwp(try_from = Byte)]
+        tag: TypeTag,
+        #[jdwp(len = Byte)]
+        ids: Vec<ObjectId>,
+        named: bool,
+        #[jdwp(when = *named)]
+        name: Option<String>,
+    }
+
+    #[test]
+    fn derive_struct() {
+        let codec = JdwpCodec::new(IdSizes::new(2, 2, 2, 2));
+        let derived = Derived {
+            tag: TypeTag::Interface,
+            ids: vec![Id::new(3), Id::new(4)],
+            named: true,
+            name: Some("a".to_string()),
+        };
+        let mut encoder = JdwpEncoder::new(&codec);
+        encoder.put(&derived);
+        assert_eq!(&encoder.data[..], &[2, 2, 0, 3, 0, 4, 1, 0, 0, 0, 1, b'a']);
+        let mut decoder = JdwpDecoder::new(&codec, encoder.data.freeze());
+        assert_eq!(decoder.get::<Derived>().expect("could not decode"), derived);
+
+        let mut decoder = JdwpDecoder::new(&codec, vec![1, 0, 0].into());
+        let derived = decoder.get::<Derived>().expect("could not decode");
+        assert_eq!(derived.name, None);
+    }
+
+    #[test]
+    fn derive_try_from() {
+        let codec = JdwpCodec::new(IdSizes::new(2, 2, 2, 2));
+        let mut decoder = JdwpDecoder::new(&codec, vec![9, 0, 0].into());
+        assert!(matches!(
+            decoder.get::<Derived>(),
+            Err(DecodeJdwpDataError::IllegalByteTag(_))
+        ));
+    }
+
+    #[derive(Debug, PartialEq, JdwpEncode, JdwpDecode)]
+    struct Lengths(#[jdwp(len = Int)] Vec<Byte>);
+
+    #[test]
+    fn derive_len() {
+        let codec = JdwpCodec::new(IdSizes::new(2, 2, 2, 2));
+        let mut encoder = JdwpEncoder::new(&codec);
+        encoder.put(&Lengths(vec![7, 8]));
+        assert_eq!(&encoder.data[..], &[0, 0, 0, 2, 7, 8]);
+        let mut decoder = JdwpDecoder::new(&codec, encoder.data.freeze());
+        assert_eq!(
+            decoder.get::<Lengths>().expect("could not decode"),
+            Lengths(vec![7, 8])
+        );
+
+        let mut decoder = JdwpDecoder::new(&codec, vec![0xff, 0xff, 0xff, 0xff].into());
+        assert!(matches!(
+            decoder.get::<Lengths>(),
+            Err(DecodeJdwpDataError::UnexpectedNegativeInt(-1))
+        ));
+    }
+
+    #[test]
+    fn derive_when() {
+        let codec = JdwpCodec::new(IdSizes::new(2, 2, 2, 2));
+        let derived = Derived {
+            tag: TypeTag::Class,
+            ids: vec![],
+            named: false,
+            name: Some("a".to_string()),
+        };
+        let mut encoder = JdwpEncoder::new(&codec);
+        encoder.put(&derived);
+        // the name is left out, as it wouldn't be decoded
+        assert_eq!(&encoder.data[..], &[1, 0, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "`name` must be set")]
+    fn derive_when_unset() {
+        let codec = JdwpCodec::new(IdSizes::new(2, 2, 2, 2));
+        let derived = Derived {
+            tag: TypeTag::Class,
+            ids: vec![],
+            named: true,
+            name: None,
+        };
+        JdwpEncoder::new(&codec).put(&derived);
+    }
+
+    #[derive(Debug, PartialEq, JdwpEncode, JdwpDecode)]
+    #[jdwp(tag = Byte, unknown = UnknownModifierKind)]
+    enum Tagged {
+        #[jdwp(tag = 1)]
+        Unit,
+        #[jdwp(tag = 2)]
+        Tuple(Int, Option<ObjectId>),
+        #[jdwp(tag = 4)]
+        Named {
+            present: bool,
+            #[jdwp(when = *present)]
+            id: Option<ObjectId>,
+        },
+        #[jdwp(skip)]
+        Skipped,
+    }
+
+    #[test]
+    fn derive_tagged_enum() {
+        let codec = JdwpCodec::new(IdSizes::new(4, 4, 4, 4));
+        let mut encoder = JdwpEncoder::new(&codec);
+        encoder.put(&vec![Tagged::Unit, Tagged::Tuple(3, None)]);
+        encoder.put(&Tagged::Skipped);
+        assert_eq!(
+            &encoder.data[..],
+            &[0, 0, 0, 2, 1, 2, 0, 0, 0, 3, 0, 0, 0, 0]
+        );
+        let mut decoder = JdwpDecoder::new(&codec, encoder.data.freeze());
+        assert_eq!(
+            decoder.get::<Vec<Tagged>>().expect("could not decode"),
+            vec![Tagged::Unit, Tagged::Tuple(3, None)]
+        );
+
+        let mut decoder = JdwpDecoder::new(&codec, vec![3].into());
+        assert!(matches!(
+            decoder.get::<Tagged>(),
+            Err(DecodeJdwpDataError::UnknownModifierKind(3))
+        ));
+    }
+
+    #[test]
+    fn derive_enum_variant_when() {
+        let codec = JdwpCodec::new(IdSizes::new(4, 4, 4, 4));
+        let values = vec![
+            Tagged::Named {
+                present: true,
+                id: Some(Id::new(5)),
+            },
+            Tagged::Named {
+                present: false,
+                id: None,
+            },
+        ];
+        let mut encoder = JdwpEncoder::new(&codec);
+        encoder.put(&values);
+        assert_eq!(&encoder.data[..], &[0, 0, 0, 2, 4, 1, 0, 0, 0, 5, 4, 0]);
+        let mut decoder = JdwpDecoder::new(&codec, encoder.data.freeze());
+        assert_eq!(
+            decoder.get::<Vec<Tagged>>().expect("could not decode"),
+            values
+        );
+    }
 }
@@ -1,216 +1,75 @@
 //! All JDB commands
 
-use crate::codec::{DecodeJdwpDataError, JdwpDecodable, JdwpDecoder, JdwpEncodable, JdwpEncoder};
-use crate::packet::JdwpCommand;
-use crate::raw::packet::CommandData;
-use bytes::BufMut;
-use jdwp_types::{Byte, ClassStatus, Int, ReferenceTypeId, ThreadGroupId, ThreadId, TypeTag};
-use tracing::instrument;
-
-macro_rules! command {
-    (
-        command_set: $command_set:expr;
-        command: $command:expr;
-        $(#[$meta:meta])*
-        $vis:vis struct $command_id:ident {
-            $($field_vis:vis $field:ident: $field_ty:ty),*
-            $(,)?
-        } -> {
-            $(
-                $reply_field_vis:vis $reply_field:ident: $reply_field_ty:ty
-            ),*
-            $(,)?
-        }
-    ) => {
-        paste::paste! {
-            $(#[$meta])*
-            $vis struct $command_id
-                {
-                    $(
-                        $field_vis $field: $field_ty
-                    )*
-                }
-
-
-            impl JdwpEncodable for $command_id {
-                fn encode(&self, encoder: &mut JdwpEncoder) {
-                    $(
-                        encoder.put(&self.$field);
-                    )*
-                }
-            }
-
-            impl JdwpCommand for $command_id {
-                type Reply = [<$command_id Reply>];
-
-                fn command_data() -> CommandData {
-                    CommandData::new($command_set, $command)
-                }
-            }
-
-            $(#[$meta])*
-            $vis struct [<$command_id Reply>] {
-            $(
-                $reply_field_vis $reply_field: $reply_field_ty,
-            )*
-            }
-
-            impl JdwpDecodable for [<$command_id Reply>] {
-                type Err = DecodeJdwpDataError;
-
-                fn decode(decoder: &mut JdwpDecoder) -> Result<Self, Self::Err> {
-                    Ok(Self {
-                        $(
-                            $reply_field: decoder.get()?
-                        ),*
-
-                    })
-                }
-            }
-        }
-    };
-    (
-        command_set: $command_set:expr;
-        command: $command:expr;
-        $(#[$meta:meta])*
-        $vis:vis struct $command_id:ident -> {
-            $(
-                $reply_field_vis:vis $reply_field:ident: $reply_field_ty:ty
-            ),*
-            $(,)?
-        }
-    ) => {
-        paste::paste! {
-            $(#[$meta])*
-            $vis struct $command_id;
-
-            impl JdwpEncodable for $command_id {
-
-            }
-
-            impl JdwpCommand for $command_id {
-                type Reply = [<$command_id Reply>];
-
-                fn command_data() -> CommandData {
-                    CommandData::new($command_set, $command)
-                }
-            }
-
-            $(#[$meta])*
-            $vis struct [<$command_id Reply>] {
-            $(
-                $reply_field_vis $reply_field: $reply_field_ty,
-            )*
-            }
-
-            impl JdwpDecodable for [<$command_id Reply>] {
-                type Err = DecodeJdwpDataError;
-
-                fn decode(decoder: &mut JdwpDecoder) -> Result<Self, Self::Err> {
-                    Ok(Self {
-                        $(
-                            $reply_field: decoder.get()?
-                        ),*
-
-                    })
-                }
-            }
-        }
-    };
-    (
-        command_set: $command_set:expr;
-        command: $command:expr;
-        $(#[$meta:meta])*
-        $vis:vis struct $command_id:ident;
-    ) => {
-        paste::paste! {
-            $(#[$meta])*
-            $vis struct $command_id;
-
-            impl JdwpEncodable for $command_id {
-
-            }
-
-            impl JdwpCommand for $command_id {
-                type Reply = [<$command_id Reply>];
-
-                fn command_data() -> CommandData {
-                    CommandData::new($command_set, $command)
-                }
-            }
-
-            $(#[$meta])*
-            $vis struct [<$command_id Reply>];
-
-            impl JdwpDecodable for [<$command_id Reply>] {
-                type Err = DecodeJdwpDataError;
-
-                fn decode(decoder: &mut JdwpDecoder) -> Result<Self, Self::Err> {
-                    Ok(Self)
-                }
-            }
-        }
-    };
+use crate::codec::{JdwpDecode, JdwpEncode};
+use crate::packet::jdwp_command;
+use crate::registry::Describe;
+use jdwp_types::signature::generic::ClassSignature;
+use jdwp_types::signature::{JavaType, SignatureError};
+use jdwp_types::{
+    Byte, ClassStatus, Int, ReferenceTypeId, StringId, ThreadGroupId, ThreadId, TypeTag,
+};
+use std::str::FromStr;
+
+pub mod array_reference;
+pub mod class_type;
+pub mod event_request;
+pub mod interface_type;
+pub mod method;
+pub mod object_reference;
+pub mod reference_type;
+pub mod stack_frame;
+pub mod string_reference;
+pub mod thread_reference;
+
+/// Gets the version of the JVM connected to
+#[jdwp_command(set = 1, cmd = 1)]
+#[derive(Debug, JdwpEncode, JdwpDecode, Describe)]
+pub struct Version;
+
+#[derive(Debug, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VersionReply {
+    pub description: String,
+    pub major: Int,
+    pub minor: Int,
+    pub version: String,
+    pub name: String,
 }
 
-command! {
-    command_set: 1;
-    command: 1;
-    /// Gets the version of the JVM connected to
-    #[derive(Debug)]
-    pub struct Version -> {
-        pub description: String,
-        pub major: Int,
-        pub minor: Int,
-        pub version: String,
-        pub name: String,
-    }
+/// Gets all classes by a given jni signature
+#[jdwp_command(set = 1, cmd = 2)]
+#[derive(Debug, JdwpEncode, JdwpDecode, Describe)]
+pub struct ClassesBySignatures {
+    pub signature: String,
 }
 
-command! {
-    command_set: 1;
-    command: 2;
-    /// Gets all classes by a given jni signature
-    #[derive(Debug)]
-    pub struct ClassesBySignatures {
-        pub signature: String
-    } -> {
-        pub classes: Vec<ClassReference>
-    }
+#[derive(Debug, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassesBySignaturesReply {
+    pub classes: Vec<ClassReference>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClassReference {
     pub type_tag: TypeTag,
     pub id: ReferenceTypeId,
     pub status: ClassStatus,
 }
 
-impl JdwpDecodable for ClassReference {
-    type Err = DecodeJdwpDataError;
-
-    fn decode(decoder: &mut JdwpDecoder) -> Result<Self, Self::Err> {
-        Ok(Self {
-            type_tag: decoder
-                .get::<Byte>()
-                .and_then(|i| Ok(TypeTag::try_from(i)?))?,
-            id: decoder.get()?,
-            status: decoder.get::<ClassStatus>()?,
-        })
-    }
-}
+/// Gets all classes by a given jni signature
+#[jdwp_command(set = 1, cmd = 3)]
+#[derive(Debug, JdwpEncode, JdwpDecode, Describe)]
+pub struct AllClasses;
 
-command! {
-    command_set: 1;
-    command: 3;
-    /// Gets all classes by a given jni signature
-    #[derive(Debug)]
-    pub struct AllClasses -> {
-        pub classes: Vec<ClassReferenceWithSignature>
-    }
+#[derive(Debug, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AllClassesReply {
+    pub classes: Vec<ClassReferenceWithSignature>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClassReferenceWithSignature {
     pub type_tag: TypeTag,
     pub id: ReferenceTypeId,
@@ -218,54 +77,177 @@ pub struct ClassReferenceWithSignature {
     pub status: ClassStatus,
 }
 
-impl JdwpDecodable for ClassReferenceWithSignature {
-    type Err = DecodeJdwpDataError;
-
-    fn decode(decoder: &mut JdwpDecoder) -> Result<Self, Self::Err> {
-        Ok(Self {
-            type_tag: decoder
-                .get::<Byte>()
-                .and_then(|type_tag_byte| Ok(TypeTag::try_from(type_tag_byte)?))?,
-            id: decoder.get()?,
-            signature: decoder.get()?,
-            status: decoder.get::<ClassStatus>()?,
-        })
+impl ClassReferenceWithSignature {
+    /// Parses the JNI signature of the class
+    pub fn java_type(&self) -> Result<JavaType, SignatureError> {
+        self.signature.parse()
     }
 }
 
-command! {
-    command_set: 1;
-    command: 4;
-    #[derive(Debug)]
-    pub struct AllThreads -> {
-        pub threads: Vec<ThreadId>
+/// Gets all loaded classes, with their generic signatures
+#[jdwp_command(set = 1, cmd = 20)]
+#[derive(Debug, JdwpEncode, JdwpDecode, Describe)]
+pub struct AllClassesWithGeneric;
+
+#[derive(Debug, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AllClassesWithGenericReply {
+    pub classes: Vec<ClassReferenceWithGeneric>,
+}
+
+#[derive(Debug, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassReferenceWithGeneric {
+    pub type_tag: TypeTag,
+    pub id: ReferenceTypeId,
+    pub signature: String,
+    /// The generic signature, empty if the class isn't generic
+    pub generic_signature: String,
+    pub status: ClassStatus,
+}
+
+impl ClassReferenceWithGeneric {
+    /// Parses the JNI signature of the class
+    pub fn java_type(&self) -> Result<JavaType, SignatureError> {
+        self.signature.parse()
+    }
+
+    /// Parses the generic signature of the class, if it has one
+    pub fn class_signature(&self) -> Result<Option<ClassSignature>, SignatureError> {
+        generic(&self.generic_signature)
     }
 }
 
-command! {
-    command_set: 1;
-    command: 5;
-    #[derive(Debug)]
-    pub struct TopLevelThreadGroups -> {
-        pub groups: Vec<ThreadGroupId>
+/// Parses a generic signature, which is sent empty when there is none
+fn generic<T: FromStr<Err = SignatureError>>(signature: &str) -> Result<Option<T>, SignatureError> {
+    match signature {
+        "" => Ok(None),
+        signature => signature.parse().map(Some),
     }
 }
 
-command! {
-    command_set: 1;
-    command: 6;
-    #[derive(Debug)]
-    pub struct Dispose;
+#[jdwp_command(set = 1, cmd = 4)]
+#[derive(Debug, JdwpEncode, JdwpDecode, Describe)]
+pub struct AllThreads;
+
+#[derive(Debug, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AllThreadsReply {
+    pub threads: Vec<ThreadId>,
 }
 
-command! {
-    command_set: 1;
-    command: 7;
-    pub struct IdSizes -> {
-        pub field_id_size: Int,
-        pub method_id_size: Int,
-        pub object_id_size: Int,
-        refernce_type_id_size: Int,
-        pub frame_id_size: Int
-    }
+#[jdwp_command(set = 1, cmd = 5)]
+#[derive(Debug, JdwpEncode, JdwpDecode, Describe)]
+pub struct TopLevelThreadGroups;
+
+#[derive(Debug, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TopLevelThreadGroupsReply {
+    pub groups: Vec<ThreadGroupId>,
+}
+
+#[jdwp_command(set = 1, cmd = 6)]
+#[derive(Debug, JdwpEncode, JdwpDecode, Describe)]
+pub struct Dispose;
+
+#[derive(Debug, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisposeReply;
+
+#[jdwp_command(set = 1, cmd = 7)]
+#[derive(JdwpEncode, JdwpDecode, Describe)]
+pub struct IdSizes;
+
+#[derive(JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IdSizesReply {
+    pub field_id_size: Int,
+    pub method_id_size: Int,
+    pub object_id_size: Int,
+    pub reference_type_id_size: Int,
+    pub frame_id_size: Int,
+}
+
+/// Suspends the execution of the application running in the target VM
+#[jdwp_command(set = 1, cmd = 8)]
+#[derive(Debug, JdwpEncode, JdwpDecode, Describe)]
+pub struct Suspend;
+
+#[derive(Debug, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuspendReply;
+
+/// Resumes execution of the application after a suspend command or an event has stopped it
+#[jdwp_command(set = 1, cmd = 9)]
+#[derive(Debug, JdwpEncode, JdwpDecode, Describe)]
+pub struct Resume;
+
+#[derive(Debug, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResumeReply;
+
+/// Creates a new string object in the target VM. It may be garbage collected at any time
+/// unless collection is disabled for it.
+#[jdwp_command(set = 1, cmd = 11)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct CreateString {
+    pub utf: String,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CreateStringReply {
+    pub string_object: StringId,
+}
+
+/// Gets the optional capabilities of the target VM. The reserved capabilities at the end of
+/// the reply aren't decoded.
+#[jdwp_command(set = 1, cmd = 17)]
+#[derive(Debug, Clone, Copy, JdwpEncode, JdwpDecode, Describe)]
+pub struct CapabilitiesNew;
+
+#[derive(Debug, Clone, Copy, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CapabilitiesNewReply {
+    pub can_watch_field_modification: bool,
+    pub can_watch_field_access: bool,
+    pub can_get_bytecodes: bool,
+    pub can_get_synthetic_attribute: bool,
+    pub can_get_owned_monitor_info: bool,
+    pub can_get_current_contended_monitor: bool,
+    pub can_get_monitor_info: bool,
+    pub can_redefine_classes: bool,
+    pub can_add_method: bool,
+    pub can_unrestrictedly_redefine_classes: bool,
+    pub can_pop_frames: bool,
+    pub can_use_instance_filters: bool,
+    pub can_get_source_debug_extension: bool,
+    pub can_request_vm_death_event: bool,
+    pub can_set_default_stratum: bool,
+    pub can_get_instance_info: bool,
+    pub can_request_monitor_events: bool,
+    pub can_get_monitor_frame_info: bool,
+    pub can_use_source_name_filters: bool,
+    pub can_get_constant_pool: bool,
+    pub can_force_early_return: bool,
+}
+
+/// Installs new class definitions. The classes are redefined atomically: either every class
+/// is redefined, or none is. Threads keep executing the old versions of the methods they're in,
+/// and the new versions are used by subsequent calls.
+#[jdwp_command(set = 1, cmd = 18)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct RedefineClasses {
+    pub classes: Vec<ClassDefinition>,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RedefineClassesReply;
+
+/// A new definition of a class, as the bytes of a class file
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct ClassDefinition {
+    pub ref_type: ReferenceTypeId,
+    pub class_file: Vec<Byte>,
 }
@@ -0,0 +1,90 @@
+//! Commands within the `ArrayReference` command set (13)
+
+use crate::codec::{
+    DecodeJdwpDataError, JdwpDecodable, JdwpDecode, JdwpDecoder, JdwpEncode, UntaggedValue,
+};
+use crate::packet::jdwp_command;
+use crate::registry::Describe;
+use jdwp_types::{ArrayId, Int, Tag, Value};
+
+/// Returns the number of components in an array.
+#[jdwp_command(set = 13, cmd = 1)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Length {
+    pub array_object: ArrayId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LengthReply {
+    pub array_length: Int,
+}
+
+/// Returns a range of components of an array.
+#[jdwp_command(set = 13, cmd = 2)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct GetValues {
+    pub array_object: ArrayId,
+    pub first_index: Int,
+    pub length: Int,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetValuesReply {
+    pub values: ArrayRegion,
+}
+
+/// Sets a range of components of an array, starting at the first index. Values of reference
+/// types must be assignable to the array's component type.
+#[jdwp_command(set = 13, cmd = 3)]
+#[derive(Debug, Clone, JdwpEncode, Describe)]
+pub struct SetValues {
+    pub array_object: ArrayId,
+    pub first_index: Int,
+    pub values: Vec<UntaggedValue>,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetValuesReply;
+
+/// Components of an array, all of the same type
+#[derive(Debug, Clone, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArrayRegion {
+    pub tag: Tag,
+    pub values: Vec<Value>,
+}
+
+impl JdwpDecodable for ArrayRegion {
+    type Err = DecodeJdwpDataError;
+
+    fn decode(decoder: &mut JdwpDecoder) -> Result<Self, Self::Err> {
+        let tag = decoder.get::<Tag>()?;
+        let len = decoder.get::<Int>()?;
+        if len < 0 {
+            return Err(DecodeJdwpDataError::UnexpectedNegativeInt(len));
+        }
+        // primitives are sent untagged, while objects carry the tag of their runtime type
+        let primitive = !matches!(
+            tag,
+            Tag::Array
+                | Tag::Object
+                | Tag::String
+                | Tag::Thread
+                | Tag::ThreadGroup
+                | Tag::ClassLoader
+                | Tag::ClassObject
+        );
+        let mut values = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            values.push(if primitive {
+                decoder.get_untagged_value(tag)?
+            } else {
+                decoder.get::<Value>()?
+            });
+        }
+        Ok(Self { tag, values })
+    }
+}
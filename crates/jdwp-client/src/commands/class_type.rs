@@ -0,0 +1,57 @@
+//! Commands within the `ClassType` command set (3)
+
+use crate::codec::{JdwpDecode, JdwpEncode};
+use crate::commands::object_reference::FieldValue;
+use crate::packet::jdwp_command;
+use crate::registry::Describe;
+use jdwp_types::{ClassId, Int, MethodId, TaggedObjectId, ThreadId, Value};
+
+/// Returns the immediate superclass of a class, or a null id for `java.lang.Object`.
+#[jdwp_command(set = 3, cmd = 1)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Superclass {
+    pub clazz: ClassId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuperclassReply {
+    pub superclass: ClassId,
+}
+
+/// Sets the value of one or more static fields, which must be members of the class or one of
+/// its supertypes. Final fields can't be set.
+#[jdwp_command(set = 3, cmd = 2)]
+#[derive(Debug, Clone, JdwpEncode, Describe)]
+pub struct SetValues {
+    pub clazz: ClassId,
+    pub values: Vec<FieldValue>,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetValuesReply;
+
+/// Invokes a static method of a class in a thread, which must have been suspended by an
+/// event. Returns either the method's return value, or the exception it threw.
+#[jdwp_command(set = 3, cmd = 3)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct InvokeMethod {
+    pub clazz: ClassId,
+    pub thread: ThreadId,
+    pub method_id: MethodId,
+    pub arguments: Vec<Value>,
+    pub options: Int,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InvokeMethodReply {
+    pub return_value: Value,
+    pub exception: TaggedObjectId,
+}
+
+/// Only resumes the invoking thread during an invocation, rather than every thread
+pub const INVOKE_SINGLE_THREADED: Int = 0x01;
+/// Invokes an instance method without looking up overrides in the object's runtime type
+pub const INVOKE_NONVIRTUAL: Int = 0x02;
@@ -0,0 +1,196 @@
+//! Commands within the `EventRequest` command set (15)
+
+use crate::codec::{JdwpDecode, JdwpEncode};
+use crate::packet::jdwp_command;
+use crate::registry::Describe;
+use jdwp_types::{
+    Byte, EventKind, FieldId, Int, Location, ObjectId, ReferenceTypeId, StepDepth, StepSize,
+    SuspendPolicy, ThreadId,
+};
+
+/// Set an event request. When the event described by this request occurs, an event is sent
+/// from the target VM.
+#[jdwp_command(set = 15, cmd = 1)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Set {
+    pub event_kind: EventKind,
+    pub suspend_policy: SuspendPolicy,
+    pub modifiers: Vec<Modifier>,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetReply {
+    pub request_id: Int,
+}
+
+/// Clear an event request
+#[jdwp_command(set = 15, cmd = 2)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Clear {
+    pub event_kind: EventKind,
+    pub request_id: Int,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClearReply;
+
+/// Removes all set breakpoints
+#[jdwp_command(set = 15, cmd = 3)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct ClearAllBreakpoints;
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClearAllBreakpointsReply;
+
+/// Constraints on an event request, limiting the events that are generated for it.
+///
+/// Modifiers are applied in the order they're given. For example, if a [Count](Modifier::Count)
+/// comes before a [ThreadOnly](Modifier::ThreadOnly), the count is decremented for every event
+/// regardless of the thread.
+#[derive(Debug, Clone, PartialEq, Eq, JdwpEncode, JdwpDecode)]
+#[jdwp(tag = Byte, unknown = UnknownModifierKind)]
+pub enum Modifier {
+    /// Limit the requested event to be reported at most once after a given number of occurrences
+    #[jdwp(tag = 1)]
+    Count(Int),
+    /// Conditional on expression. Reserved for future use and not supported by any target VM.
+    #[jdwp(tag = 2)]
+    Conditional(Int),
+    /// Restricts reported events to those in the given thread
+    #[jdwp(tag = 3)]
+    ThreadOnly(ThreadId),
+    /// Restricts reported events to those whose location is in the given reference type or any
+    /// of its subtypes
+    #[jdwp(tag = 4)]
+    ClassOnly(ReferenceTypeId),
+    /// Restricts reported events to those for classes whose name matches the given restricted
+    /// regular expression, such as `java.*` or `*.Foo`
+    #[jdwp(tag = 5)]
+    ClassMatch(String),
+    /// Restricts reported events to those for classes whose name does not match the given
+    /// restricted regular expression
+    #[jdwp(tag = 6)]
+    ClassExclude(String),
+    /// Restricts reported events to those that occur at the given location
+    #[jdwp(tag = 7)]
+    LocationOnly(Location),
+    /// Restricts reported exceptions by their class and whether they are caught or uncaught
+    #[jdwp(tag = 8)]
+    ExceptionOnly {
+        /// The exception type to report, or [None] for all exceptions
+        exception: Option<ReferenceTypeId>,
+        /// Report caught exceptions
+        caught: bool,
+        /// Report uncaught exceptions
+        uncaught: bool,
+    },
+    /// Restricts reported events to those that occur for a given field
+    #[jdwp(tag = 9)]
+    FieldOnly {
+        /// The type in which the field is declared
+        declaring: ReferenceTypeId,
+        /// The field
+        field: FieldId,
+    },
+    /// Restricts reported step events to those which satisfy depth and size constraints
+    #[jdwp(tag = 10)]
+    Step {
+        /// The thread in which to step
+        thread: ThreadId,
+        /// The size of each step
+        size: StepSize,
+        /// The relative call stack limit
+        depth: StepDepth,
+    },
+    /// Restricts reported events to those whose active `this` object is the given object
+    #[jdwp(tag = 11)]
+    InstanceOnly(ObjectId),
+    /// Restricts reported class prepare events to those for reference types which have a source
+    /// name that matches the given restricted regular expression
+    #[jdwp(tag = 12)]
+    SourceNameMatch(String),
+}
+
+impl Modifier {
+    /// Gets the `modKind` of this modifier
+    pub fn mod_kind(&self) -> Byte {
+        match self {
+            Modifier::Count(_) => 1,
+            Modifier::Conditional(_) => 2,
+            Modifier::ThreadOnly(_) => 3,
+            Modifier::ClassOnly(_) => 4,
+            Modifier::ClassMatch(_) => 5,
+            Modifier::ClassExclude(_) => 6,
+            Modifier::LocationOnly(_) => 7,
+            Modifier::ExceptionOnly { .. } => 8,
+            Modifier::FieldOnly { .. } => 9,
+            Modifier::Step { .. } => 10,
+            Modifier::InstanceOnly(_) => 11,
+            Modifier::SourceNameMatch(_) => 12,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{JdwpCodec, JdwpDecoder, JdwpEncoder};
+    use crate::id_sizes::IdSizes;
+    use jdwp_types::{ClassId, MethodId, TypeTag};
+
+    #[test]
+    fn encode_set_with_modifiers() {
+        let codec = JdwpCodec::new(IdSizes::new(4, 4, 4, 4));
+        let mut encoder = JdwpEncoder::new(&codec);
+        encoder.put(&Set {
+            event_kind: EventKind::Breakpoint,
+            suspend_policy: SuspendPolicy::EventThread,
+            modifiers: vec![
+                Modifier::Count(2),
+                Modifier::LocationOnly(Location {
+                    tag: TypeTag::Class,
+                    class: ClassId::new(7),
+                    method: MethodId::new(9),
+                    offset: 3,
+                }),
+            ],
+        });
+        assert_eq!(
+            &encoder.data[..],
+            &[
+                2, 1, // kind, policy
+                0, 0, 0, 2, // modifiers
+                1, 0, 0, 0, 2, // count
+                7, 1, 0, 0, 0, 7, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 3 // location only
+            ]
+        );
+    }
+
+    #[test]
+    fn modifiers_round_trip() {
+        let codec = JdwpCodec::new(IdSizes::new(8, 8, 8, 8));
+        let modifiers = vec![
+            Modifier::ClassMatch("java.*".to_string()),
+            Modifier::ExceptionOnly {
+                exception: None,
+                caught: true,
+                uncaught: false,
+            },
+            Modifier::Step {
+                thread: ThreadId::new(12),
+                size: StepSize::Line,
+                depth: StepDepth::Over,
+            },
+        ];
+        let mut encoder = JdwpEncoder::new(&codec);
+        encoder.put(&modifiers);
+        let mut decoder = JdwpDecoder::new(&codec, encoder.data.freeze());
+        let decoded = decoder
+            .get::<Vec<Modifier>>()
+            .expect("could not decode modifiers");
+        assert_eq!(decoded, modifiers);
+    }
+}
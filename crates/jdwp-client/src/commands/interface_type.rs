@@ -0,0 +1,25 @@
+//! Commands within the `InterfaceType` command set (5)
+
+use crate::codec::{JdwpDecode, JdwpEncode};
+use crate::packet::jdwp_command;
+use crate::registry::Describe;
+use jdwp_types::{Int, InterfaceId, MethodId, TaggedObjectId, ThreadId, Value};
+
+/// Invokes a static method of an interface in a thread, which must have been suspended by an
+/// event. Returns either the method's return value, or the exception it threw.
+#[jdwp_command(set = 5, cmd = 1)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct InvokeMethod {
+    pub clazz: InterfaceId,
+    pub thread: ThreadId,
+    pub method_id: MethodId,
+    pub arguments: Vec<Value>,
+    pub options: Int,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InvokeMethodReply {
+    pub return_value: Value,
+    pub exception: TaggedObjectId,
+}
@@ -0,0 +1,56 @@
+//! Commands within the `Method` command set (6)
+
+use crate::codec::{JdwpDecode, JdwpEncode};
+use crate::packet::jdwp_command;
+use crate::registry::Describe;
+use jdwp_types::{Int, Long, MethodId, ReferenceTypeId};
+
+/// Returns line number information for the method, if present.
+#[jdwp_command(set = 6, cmd = 1)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct LineTable {
+    pub ref_type: ReferenceTypeId,
+    pub method_id: MethodId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineTableReply {
+    pub start: Long,
+    pub end: Long,
+    pub lines: Vec<LineInfo>,
+}
+
+/// Maps a code index within a method to a line number in its source
+#[derive(Debug, Clone, Copy, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineInfo {
+    pub line_code_index: Long,
+    pub line_number: Int,
+}
+
+/// Returns variable information for the method, if the class was compiled with it.
+#[jdwp_command(set = 6, cmd = 2)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct VariableTable {
+    pub ref_type: ReferenceTypeId,
+    pub method_id: MethodId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VariableTableReply {
+    pub arg_cnt: Int,
+    pub slots: Vec<VariableInfo>,
+}
+
+/// A local variable, and the range of code it's in scope for
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VariableInfo {
+    pub code_index: Long,
+    pub name: String,
+    pub signature: String,
+    pub length: Int,
+    pub slot: Int,
+}
@@ -0,0 +1,97 @@
+//! Commands within the `ObjectReference` command set (9)
+
+use crate::codec::{JdwpDecode, JdwpEncode, UntaggedValue};
+use crate::packet::jdwp_command;
+use crate::registry::Describe;
+use jdwp_types::{
+    ClassId, FieldId, Int, MethodId, ObjectId, ReferenceTypeId, TaggedObjectId, ThreadId, TypeTag,
+    Value,
+};
+
+/// Returns the runtime type of the object.
+#[jdwp_command(set = 9, cmd = 1)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct ReferenceType {
+    pub object: ObjectId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReferenceTypeReply {
+    pub ref_type_tag: TypeTag,
+    pub type_id: ReferenceTypeId,
+}
+
+/// Returns the value of one or more instance fields of an object, which may be declared in its
+/// type or one of its superclasses.
+#[jdwp_command(set = 9, cmd = 2)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct GetValues {
+    pub object: ObjectId,
+    pub fields: Vec<FieldId>,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetValuesReply {
+    pub values: Vec<Value>,
+}
+
+/// Sets the value of one or more instance fields of an object, which may be declared in its
+/// type or one of its superclasses. Values of reference types must be assignable to their
+/// fields.
+#[jdwp_command(set = 9, cmd = 3)]
+#[derive(Debug, Clone, JdwpEncode, Describe)]
+pub struct SetValues {
+    pub object: ObjectId,
+    pub values: Vec<FieldValue>,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetValuesReply;
+
+/// A field to set, with its new value
+#[derive(Debug, Clone, JdwpEncode, Describe)]
+pub struct FieldValue {
+    pub field: FieldId,
+    pub value: UntaggedValue,
+}
+
+/// Returns monitor information for an object: the thread owning its monitor, or a null thread
+/// if none does, how many times the owner has entered it, and the threads waiting on it in
+/// `Object.wait`.
+#[jdwp_command(set = 9, cmd = 5)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct MonitorInfo {
+    pub object: ObjectId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MonitorInfoReply {
+    pub owner: ThreadId,
+    pub entry_count: Int,
+    pub waiters: Vec<ThreadId>,
+}
+
+/// Invokes an instance method of an object in a thread, which must have been suspended by an
+/// event. The method must be a member of the given class or one of its supertypes. Returns
+/// either the method's return value, or the exception it threw.
+#[jdwp_command(set = 9, cmd = 6)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct InvokeMethod {
+    pub object: ObjectId,
+    pub thread: ThreadId,
+    pub clazz: ClassId,
+    pub method_id: MethodId,
+    pub arguments: Vec<Value>,
+    pub options: Int,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InvokeMethodReply {
+    pub return_value: Value,
+    pub exception: TaggedObjectId,
+}
@@ -0,0 +1,243 @@
+//! Commands within the `ReferenceType` command set (2)
+
+use super::generic;
+use crate::codec::{JdwpDecode, JdwpEncode};
+use crate::packet::jdwp_command;
+use crate::registry::Describe;
+use jdwp_types::signature::generic::{ClassSignature, GenericMethodSignature, GenericType};
+use jdwp_types::signature::{JavaType, MethodSignature, SignatureError};
+use jdwp_types::{ClassStatus, FieldId, Int, InterfaceId, MethodId, ReferenceTypeId, Value};
+
+/// Returns the JNI signature of a reference type.
+#[jdwp_command(set = 2, cmd = 1)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Signature {
+    pub ref_type: ReferenceTypeId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignatureReply {
+    pub signature: String,
+}
+
+/// Returns information for each field in a reference type. Inherited fields are not included.
+#[jdwp_command(set = 2, cmd = 4)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Fields {
+    pub ref_type: ReferenceTypeId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldsReply {
+    pub declared: Vec<FieldInfo>,
+}
+
+/// Information about a field declared in a reference type
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldInfo {
+    pub field_id: FieldId,
+    pub name: String,
+    pub signature: String,
+    pub mod_bits: Int,
+}
+
+impl FieldInfo {
+    /// Parses the JNI signature of the field's type
+    pub fn java_type(&self) -> Result<JavaType, SignatureError> {
+        self.signature.parse()
+    }
+}
+
+/// Returns information for each method in a reference type. Inherited methods are not included.
+#[jdwp_command(set = 2, cmd = 5)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Methods {
+    pub ref_type: ReferenceTypeId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MethodsReply {
+    pub declared: Vec<MethodInfo>,
+}
+
+/// Information about a method declared in a reference type
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MethodInfo {
+    pub method_id: MethodId,
+    pub name: String,
+    pub signature: String,
+    pub mod_bits: Int,
+}
+
+impl MethodInfo {
+    /// Parses the JNI signature of the method
+    pub fn method_signature(&self) -> Result<MethodSignature, SignatureError> {
+        self.signature.parse()
+    }
+}
+
+/// Returns the value of one or more static fields of a reference type, which may be declared
+/// in the type or one of its superclasses or superinterfaces.
+#[jdwp_command(set = 2, cmd = 6)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct GetValues {
+    pub ref_type: ReferenceTypeId,
+    pub fields: Vec<FieldId>,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetValuesReply {
+    pub values: Vec<Value>,
+}
+
+/// Returns the name of the source file in which a reference type was declared, without any
+/// directories.
+#[jdwp_command(set = 2, cmd = 7)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct SourceFile {
+    pub ref_type: ReferenceTypeId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceFileReply {
+    pub source_file: String,
+}
+
+/// Returns the current status of a reference type.
+#[jdwp_command(set = 2, cmd = 9)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Status {
+    pub ref_type: ReferenceTypeId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatusReply {
+    pub status: ClassStatus,
+}
+
+/// Returns the interfaces directly implemented by a class, or directly extended by an
+/// interface.
+#[jdwp_command(set = 2, cmd = 10)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Interfaces {
+    pub ref_type: ReferenceTypeId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InterfacesReply {
+    pub interfaces: Vec<InterfaceId>,
+}
+
+/// Returns the JNI signature of a reference type, along with its generic signature.
+#[jdwp_command(set = 2, cmd = 13)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct SignatureWithGeneric {
+    pub ref_type: ReferenceTypeId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignatureWithGenericReply {
+    pub signature: String,
+    /// The generic signature, empty if the type isn't generic
+    pub generic_signature: String,
+}
+
+impl SignatureWithGenericReply {
+    /// Parses the JNI signature of the type
+    pub fn java_type(&self) -> Result<JavaType, SignatureError> {
+        self.signature.parse()
+    }
+
+    /// Parses the generic signature of the type, if it has one
+    pub fn class_signature(&self) -> Result<Option<ClassSignature>, SignatureError> {
+        generic(&self.generic_signature)
+    }
+}
+
+/// Returns information, including the generic signature, for each field in a reference type.
+/// Inherited fields are not included.
+#[jdwp_command(set = 2, cmd = 14)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct FieldsWithGeneric {
+    pub ref_type: ReferenceTypeId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldsWithGenericReply {
+    pub declared: Vec<FieldInfoWithGeneric>,
+}
+
+/// Information about a field declared in a reference type, with its generic signature
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldInfoWithGeneric {
+    pub field_id: FieldId,
+    pub name: String,
+    pub signature: String,
+    /// The generic signature, empty if the field's type isn't generic
+    pub generic_signature: String,
+    pub mod_bits: Int,
+}
+
+impl FieldInfoWithGeneric {
+    /// Parses the JNI signature of the field's type
+    pub fn java_type(&self) -> Result<JavaType, SignatureError> {
+        self.signature.parse()
+    }
+
+    /// Parses the generic signature of the field's type, if it has one
+    pub fn generic_type(&self) -> Result<Option<GenericType>, SignatureError> {
+        generic(&self.generic_signature)
+    }
+}
+
+/// Returns information, including the generic signature, for each method in a reference type.
+/// Inherited methods are not included.
+#[jdwp_command(set = 2, cmd = 15)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct MethodsWithGeneric {
+    pub ref_type: ReferenceTypeId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MethodsWithGenericReply {
+    pub declared: Vec<MethodInfoWithGeneric>,
+}
+
+/// Information about a method declared in a reference type, with its generic signature
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MethodInfoWithGeneric {
+    pub method_id: MethodId,
+    pub name: String,
+    pub signature: String,
+    /// The generic signature, empty if the method isn't generic
+    pub generic_signature: String,
+    pub mod_bits: Int,
+}
+
+impl MethodInfoWithGeneric {
+    /// Parses the JNI signature of the method
+    pub fn method_signature(&self) -> Result<MethodSignature, SignatureError> {
+        self.signature.parse()
+    }
+
+    /// Parses the generic signature of the method, if it has one
+    pub fn generic_method_signature(
+        &self,
+    ) -> Result<Option<GenericMethodSignature>, SignatureError> {
+        generic(&self.generic_signature)
+    }
+}
@@ -0,0 +1,64 @@
+//! Commands within the `StackFrame` command set (16)
+
+use crate::codec::{JdwpDecode, JdwpEncode};
+use crate::packet::jdwp_command;
+use crate::registry::Describe;
+use jdwp_types::{FrameId, Int, Tag, TaggedObjectId, ThreadId, Value};
+
+/// Returns the value of one or more local variables in a frame. The thread must be suspended.
+#[jdwp_command(set = 16, cmd = 1)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct GetValues {
+    pub thread: ThreadId,
+    pub frame: FrameId,
+    pub slots: Vec<SlotRequest>,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetValuesReply {
+    pub values: Vec<Value>,
+}
+
+/// A local variable to get the value of, by its slot and the tag of its type
+#[derive(Debug, Clone, Copy, JdwpEncode, JdwpDecode, Describe)]
+pub struct SlotRequest {
+    pub slot: Int,
+    pub sig_byte: Tag,
+}
+
+/// Sets the value of one or more local variables in a frame. The thread must be suspended, and
+/// values of reference types must be assignable to their variables.
+#[jdwp_command(set = 16, cmd = 2)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct SetValues {
+    pub thread: ThreadId,
+    pub frame: FrameId,
+    pub slot_values: Vec<SlotValue>,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetValuesReply;
+
+/// A local variable to set by its slot, with its new value
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct SlotValue {
+    pub slot: Int,
+    pub value: Value,
+}
+
+/// Returns the value of `this` in a frame, or a null id if the frame's method is static or
+/// native. The thread must be suspended.
+#[jdwp_command(set = 16, cmd = 3)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct ThisObject {
+    pub thread: ThreadId,
+    pub frame: FrameId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThisObjectReply {
+    pub object_this: TaggedObjectId,
+}
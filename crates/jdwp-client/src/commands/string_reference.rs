@@ -0,0 +1,19 @@
+//! Commands within the `StringReference` command set (10)
+
+use crate::codec::{JdwpDecode, JdwpEncode};
+use crate::packet::jdwp_command;
+use crate::registry::Describe;
+use jdwp_types::ObjectId;
+
+/// Returns the characters contained in a string.
+#[jdwp_command(set = 10, cmd = 1)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Value {
+    pub string_object: ObjectId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ValueReply {
+    pub string_value: String,
+}
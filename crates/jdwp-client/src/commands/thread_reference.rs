@@ -0,0 +1,145 @@
+//! Commands within the `ThreadReference` command set (11)
+
+use crate::codec::{JdwpDecode, JdwpEncode};
+use crate::packet::jdwp_command;
+use crate::registry::Describe;
+use jdwp_types::{FrameId, Int, Location, TaggedObjectId, ThreadId, ThreadStatus};
+
+/// Returns the thread name.
+#[jdwp_command(set = 11, cmd = 1)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Name {
+    pub thread: ThreadId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NameReply {
+    pub thread_name: String,
+}
+
+/// Suspends the thread.
+#[jdwp_command(set = 11, cmd = 2)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Suspend {
+    pub thread: ThreadId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuspendReply;
+
+/// Resumes the execution of a given thread.
+#[jdwp_command(set = 11, cmd = 3)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Resume {
+    pub thread: ThreadId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResumeReply;
+
+/// Returns the current status of a thread, and whether it's suspended. The suspend status is
+/// a bit set, where `SUSPEND_STATUS_SUSPENDED` (1) is the only bit defined.
+#[jdwp_command(set = 11, cmd = 4)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Status {
+    pub thread: ThreadId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatusReply {
+    pub thread_status: ThreadStatus,
+    pub suspend_status: Int,
+}
+
+/// The suspend status bit set when a thread is suspended
+pub const SUSPEND_STATUS_SUSPENDED: Int = 0x1;
+
+/// Returns the current call stack of a suspended thread, starting from the current frame. A
+/// length of -1 returns every remaining frame.
+#[jdwp_command(set = 11, cmd = 6)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct Frames {
+    pub thread: ThreadId,
+    pub start_frame: Int,
+    pub length: Int,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FramesReply {
+    pub frames: Vec<FrameInfo>,
+}
+
+/// A frame on the call stack of a thread
+#[derive(Debug, Clone, Copy, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrameInfo {
+    pub frame_id: FrameId,
+    pub location: Location,
+}
+
+/// Returns the count of frames on this thread's stack. The thread must be suspended.
+#[jdwp_command(set = 11, cmd = 7)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct FrameCount {
+    pub thread: ThreadId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrameCountReply {
+    pub frame_count: Int,
+}
+
+/// Returns the object whose monitor the thread is waiting to enter, or waiting to re-enter
+/// in `Object.wait`, or a null object if there is none. The thread must be suspended.
+#[jdwp_command(set = 11, cmd = 9)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct CurrentContendedMonitor {
+    pub thread: ThreadId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CurrentContendedMonitorReply {
+    pub monitor: TaggedObjectId,
+}
+
+/// Returns how many times a thread has been suspended without being resumed.
+#[jdwp_command(set = 11, cmd = 12)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct SuspendCount {
+    pub thread: ThreadId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuspendCountReply {
+    pub suspend_count: Int,
+}
+
+/// Returns the objects whose monitors the thread owns, along with the depth of the frame
+/// each was acquired in, or -1 if that isn't known. The thread must be suspended.
+#[jdwp_command(set = 11, cmd = 13)]
+#[derive(Debug, Clone, JdwpEncode, JdwpDecode, Describe)]
+pub struct OwnedMonitorsStackDepthInfo {
+    pub thread: ThreadId,
+}
+
+#[derive(Debug, Clone, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedMonitorsStackDepthInfoReply {
+    pub owned: Vec<MonitorStackDepth>,
+}
+
+/// A monitor owned by a thread, with the depth of the frame it was acquired in
+#[derive(Debug, Clone, Copy, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MonitorStackDepth {
+    pub monitor: TaggedObjectId,
+    pub stack_depth: Int,
+}
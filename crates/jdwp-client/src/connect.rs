@@ -1,8 +1,13 @@
 //! defines how a client can connect to a target jvm
 
-use tokio::io::{AsyncRead, AsyncWrite};
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::TcpStream;
+use tracing::{instrument, trace, warn};
+
+/// The handshake both sides of a connection send before any packets
+pub static JDWP_HANDSHAKE: &[u8; 14] = b"JDWP-Handshake";
 
 /// A type that can be used as a transport
 pub trait JdwpTransport {
@@ -25,3 +30,52 @@ impl JdwpTransport for TcpStream {
         self.into_split()
     }
 }
+
+/// Performs the handshake as the debugger, sending it first and waiting for the target VM to send
+/// it back
+#[instrument(skip_all, err)]
+pub async fn handshake<I, O>(mut input: I, output: &mut O) -> io::Result<()>
+where
+    I: AsyncRead + Unpin,
+    O: AsyncWrite + Unpin,
+{
+    trace!("writing {JDWP_HANDSHAKE:?} to output stream");
+    output.write_all(JDWP_HANDSHAKE).await?;
+    let mut buf = [0u8; 14];
+    trace!("waiting to read {JDWP_HANDSHAKE:?} from input stream");
+    input.read_exact(&mut buf).await?;
+    trace!("read {buf:?} from input stream");
+    if &buf == JDWP_HANDSHAKE {
+        trace!("Handshake matched");
+        Ok(())
+    } else {
+        warn!("Handshake did not match");
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Expected JDWP handshake back in response",
+        ))
+    }
+}
+
+/// Performs the handshake as the target VM, waiting for the debugger to send it first and sending
+/// it back
+#[instrument(skip_all, err)]
+pub async fn accept_handshake<I, O>(mut input: I, output: &mut O) -> io::Result<()>
+where
+    I: AsyncRead + Unpin,
+    O: AsyncWrite + Unpin,
+{
+    let mut buf = [0u8; 14];
+    trace!("waiting to read {JDWP_HANDSHAKE:?} from input stream");
+    input.read_exact(&mut buf).await?;
+    if &buf != JDWP_HANDSHAKE {
+        warn!("Handshake did not match");
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Expected JDWP handshake from the debugger",
+        ));
+    }
+    trace!("writing {JDWP_HANDSHAKE:?} to output stream");
+    output.write_all(JDWP_HANDSHAKE).await?;
+    output.flush().await
+}
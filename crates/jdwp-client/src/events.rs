@@ -2,7 +2,10 @@
 
 pub use event::*;
 pub use event_handler::*;
-use std::future::Future;
+pub use event_stream::*;
+pub use registration::*;
 
 mod event;
 mod event_handler;
+mod event_stream;
+mod registration;
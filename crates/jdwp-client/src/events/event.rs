@@ -1,34 +1,61 @@
-use crate::codec::{DecodeJdwpDataError, JdwpCodec, JdwpDecodable, JdwpDecoder};
-use crate::raw::packet::RawCommandPacket;
+use crate::codec::{JdwpCodec, JdwpDecode, JdwpDecoder, JdwpEncode};
+use crate::raw::packet::{CommandData, RawCommandPacket};
+use crate::registry::Describe;
 use jdwp_types::{
-    Boolean, Byte, ClassStatus, EventKind, FieldId, Int, JdwpValue, Location, Long, ObjectId,
-    ReferenceTypeId, SuspendPolicy, TaggedObjectId, ThreadId, TypeTag, Value,
+    Boolean, ClassStatus, EventKind, FieldId, Int, Location, Long, ReferenceTypeId, SuspendPolicy,
+    TaggedObjectId, ThreadId, TypeTag, Value,
 };
 use std::io;
 use std::io::ErrorKind;
 use thiserror::Error;
 use tracing::trace;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, JdwpEncode, JdwpDecode, Describe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Events {
     pub policy: SuspendPolicy,
     pub events: Vec<Event>,
 }
 
-/// Events, as received by the JVM
-#[derive(Debug, Clone)]
+impl Events {
+    /// Gets the command events are sent as, `Event.Composite`. It's sent by the target VM, and
+    /// never replied to.
+    pub const fn command_data() -> CommandData {
+        CommandData::new(64, 100)
+    }
+}
+
+/// Events, as received by the JVM. [Event::VmDisconnected] is never sent, and encodes to nothing.
+///
+/// The composite event command doesn't define a payload for [Event::FramePop],
+/// [Event::UserDefined], [Event::ClassLoad] and [Event::ExceptionCatch], so they can't be skipped
+/// over and fail to decode with [DecodeJdwpDataError::UnexpectedEventKind].
+///
+/// [DecodeJdwpDataError::UnexpectedEventKind]: crate::codec::DecodeJdwpDataError::UnexpectedEventKind
+#[derive(Debug, Clone, PartialEq, JdwpEncode, JdwpDecode)]
+#[jdwp(tag = EventKind, unknown = UnexpectedEventKind)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "kind")
+)]
 pub enum Event {
+    #[jdwp(tag = EventKind::SingleStep)]
     SingleStep {
         request_id: Int,
         thread: ThreadId,
         location: Location,
     },
+    #[jdwp(tag = EventKind::Breakpoint)]
     Breakpoint {
         request_id: Int,
         thread: ThreadId,
         location: Location,
     },
+    /// Not part of the composite command, so never decoded
+    #[jdwp(skip)]
     FramePop,
+    #[jdwp(tag = EventKind::Exception)]
     Exception {
         request_id: Int,
         thread: ThreadId,
@@ -36,15 +63,14 @@ pub enum Event {
         exception: TaggedObjectId,
         catch_location: Location,
     },
+    /// Not part of the composite command, so never decoded
+    #[jdwp(skip)]
     UserDefined,
-    ThreadStart {
-        request_id: Int,
-        thread: ThreadId,
-    },
-    ThreadDeath {
-        request_id: Int,
-        thread: ThreadId,
-    },
+    #[jdwp(tag = EventKind::ThreadStart)]
+    ThreadStart { request_id: Int, thread: ThreadId },
+    #[jdwp(tag = EventKind::ThreadDeath)]
+    ThreadDeath { request_id: Int, thread: ThreadId },
+    #[jdwp(tag = EventKind::ClassPrepare)]
     ClassPrepare {
         request_id: Int,
         thread: ThreadId,
@@ -53,57 +79,69 @@ pub enum Event {
         signature: String,
         status: ClassStatus,
     },
-    ClassUnload {
-        request_id: Int,
-        signature: String,
-    },
+    #[jdwp(tag = EventKind::ClassUnload)]
+    ClassUnload { request_id: Int, signature: String },
+    /// Not part of the composite command, so never decoded
+    #[jdwp(skip)]
     ClassLoad,
+    #[jdwp(tag = EventKind::FieldAccess)]
     FieldAccess {
         request_id: Int,
         thread: ThreadId,
+        location: Location,
         ref_type_tag: TypeTag,
         type_id: ReferenceTypeId,
         field_id: FieldId,
-        object: ObjectId,
+        object: TaggedObjectId,
     },
+    #[jdwp(tag = EventKind::FieldModification)]
     FieldModification {
         request_id: Int,
         thread: ThreadId,
+        location: Location,
         ref_type_tag: TypeTag,
         type_id: ReferenceTypeId,
         field_id: FieldId,
-        object: ObjectId,
+        object: TaggedObjectId,
         value_to_be: Value,
     },
+    /// Not part of the composite command, so never decoded
+    #[jdwp(skip)]
     ExceptionCatch,
+    #[jdwp(tag = EventKind::MethodEntry)]
     MethodEntry {
         request_id: Int,
         thread: ThreadId,
         location: Location,
     },
+    #[jdwp(tag = EventKind::MethodExit)]
     MethodExit {
         request_id: Int,
         thread: ThreadId,
         location: Location,
     },
+    #[jdwp(tag = EventKind::MethodExitWithReturnValue)]
     MethodExitWithReturnValue {
         request_id: Int,
         thread: ThreadId,
         location: Location,
         value: Value,
     },
+    #[jdwp(tag = EventKind::MonitorContendedEnter)]
     MonitorContendedEnter {
         request_id: Int,
         thread: ThreadId,
         object: TaggedObjectId,
         location: Location,
     },
+    #[jdwp(tag = EventKind::MonitorContendedEntered)]
     MonitorContendedEntered {
         request_id: Int,
         thread: ThreadId,
         object: TaggedObjectId,
         location: Location,
     },
+    #[jdwp(tag = EventKind::MonitorWait)]
     MonitorWait {
         request_id: Int,
         thread: ThreadId,
@@ -111,6 +149,7 @@ pub enum Event {
         location: Location,
         timeout: Long,
     },
+    #[jdwp(tag = EventKind::MonitorWaited)]
     MonitorWaited {
         request_id: Int,
         thread: ThreadId,
@@ -118,172 +157,450 @@ pub enum Event {
         location: Location,
         timed_out: Boolean,
     },
-    VmStart {
-        request_id: Int,
-        thread: ThreadId,
-    },
-    VmDeath {
-        request_id: Int,
-    },
+    #[jdwp(tag = EventKind::VmStart)]
+    VmStart { request_id: Int, thread: ThreadId },
+    #[jdwp(tag = EventKind::VmDeath)]
+    VmDeath { request_id: Int },
     /// Never sent across JDWP
+    #[jdwp(skip)]
     VmDisconnected,
 }
 
-pub(crate) fn to_events(
-    command: RawCommandPacket,
-    events_codec: &JdwpCodec,
-) -> Result<Events, io::Error> {
-    if !(command.header().command().command_set() == 64
-        && command.header().command().command() == 100)
-    {
+impl Event {
+    /// Gets the kind of this event
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::SingleStep { .. } => EventKind::SingleStep,
+            Event::Breakpoint { .. } => EventKind::Breakpoint,
+            Event::FramePop => EventKind::FramePop,
+            Event::Exception { .. } => EventKind::Exception,
+            Event::UserDefined => EventKind::UserDefined,
+            Event::ThreadStart { .. } => EventKind::ThreadStart,
+            Event::ThreadDeath { .. } => EventKind::ThreadDeath,
+            Event::ClassPrepare { .. } => EventKind::ClassPrepare,
+            Event::ClassUnload { .. } => EventKind::ClassUnload,
+            Event::ClassLoad => EventKind::ClassLoad,
+            Event::FieldAccess { .. } => EventKind::FieldAccess,
+            Event::FieldModification { .. } => EventKind::FieldModification,
+            Event::ExceptionCatch => EventKind::ExceptionCatch,
+            Event::MethodEntry { .. } => EventKind::MethodEntry,
+            Event::MethodExit { .. } => EventKind::MethodExit,
+            Event::MethodExitWithReturnValue { .. } => EventKind::MethodExitWithReturnValue,
+            Event::MonitorContendedEnter { .. } => EventKind::MonitorContendedEnter,
+            Event::MonitorContendedEntered { .. } => EventKind::MonitorContendedEntered,
+            Event::MonitorWait { .. } => EventKind::MonitorWait,
+            Event::MonitorWaited { .. } => EventKind::MonitorWaited,
+            Event::VmStart { .. } => EventKind::VmStart,
+            Event::VmDeath { .. } => EventKind::VmDeath,
+            Event::VmDisconnected => EventKind::VmDisconnected,
+        }
+    }
+
+    /// Gets the id of the request that generated this event, if any. Events sent automatically
+    /// by the target VM have a request id of `0`.
+    pub fn request_id(&self) -> Option<Int> {
+        match self {
+            Event::SingleStep { request_id, .. }
+            | Event::Breakpoint { request_id, .. }
+            | Event::Exception { request_id, .. }
+            | Event::ThreadStart { request_id, .. }
+            | Event::ThreadDeath { request_id, .. }
+            | Event::ClassPrepare { request_id, .. }
+            | Event::ClassUnload { request_id, .. }
+            | Event::FieldAccess { request_id, .. }
+            | Event::FieldModification { request_id, .. }
+            | Event::MethodEntry { request_id, .. }
+            | Event::MethodExit { request_id, .. }
+            | Event::MethodExitWithReturnValue { request_id, .. }
+            | Event::MonitorContendedEnter { request_id, .. }
+            | Event::MonitorContendedEntered { request_id, .. }
+            | Event::MonitorWait { request_id, .. }
+            | Event::MonitorWaited { request_id, .. }
+            | Event::VmStart { request_id, .. }
+            | Event::VmDeath { request_id } => Some(*request_id),
+            Event::FramePop
+            | Event::UserDefined
+            | Event::ClassLoad
+            | Event::ExceptionCatch
+            | Event::VmDisconnected => None,
+        }
+    }
+
+    /// Gets the thread this event occurred in, if any
+    pub fn thread(&self) -> Option<ThreadId> {
+        match self {
+            Event::SingleStep { thread, .. }
+            | Event::Breakpoint { thread, .. }
+            | Event::Exception { thread, .. }
+            | Event::ThreadStart { thread, .. }
+            | Event::ThreadDeath { thread, .. }
+            | Event::ClassPrepare { thread, .. }
+            | Event::FieldAccess { thread, .. }
+            | Event::FieldModification { thread, .. }
+            | Event::MethodEntry { thread, .. }
+            | Event::MethodExit { thread, .. }
+            | Event::MethodExitWithReturnValue { thread, .. }
+            | Event::MonitorContendedEnter { thread, .. }
+            | Event::MonitorContendedEntered { thread, .. }
+            | Event::MonitorWait { thread, .. }
+            | Event::MonitorWaited { thread, .. }
+            | Event::VmStart { thread, .. } => Some(*thread),
+            Event::FramePop
+            | Event::UserDefined
+            | Event::ClassUnload { .. }
+            | Event::ClassLoad
+            | Event::ExceptionCatch
+            | Event::VmDeath { .. }
+            | Event::VmDisconnected => None,
+        }
+    }
+}
+
+/// Decodes the events of a composite event command sent by the target VM. A command other than
+/// the composite fails with a [NotAnEventError], while a composite that can't be decoded fails with
+/// the [DecodeJdwpDataError](crate::codec::DecodeJdwpDataError) that caused it.
+pub fn to_events(command: RawCommandPacket, events_codec: &JdwpCodec) -> Result<Events, io::Error> {
+    if command.header().command() != Events::command_data() {
         return Err(io::Error::new(ErrorKind::InvalidData, NotAnEventError));
     }
     let mut decoder = JdwpDecoder::new(events_codec, command.data().clone());
-    let policy_raw = decoder
-        .get::<Byte>()
-        .map_err(|e| io::Error::new(ErrorKind::InvalidData, NotAnEventError))?;
-    let policy = SuspendPolicy::try_from(policy_raw)
-        .map_err(|e| io::Error::new(ErrorKind::InvalidData, NotAnEventError))?;
+    let policy = decoder
+        .get::<SuspendPolicy>()
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
 
     trace!("got events with policy: {policy:?}");
 
     let events = decoder
         .get::<Vec<Event>>()
-        .map_err(|e| io::Error::new(ErrorKind::InvalidData, NotAnEventError))?;
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
 
     Ok(Events { policy, events })
 }
 
-impl JdwpDecodable for Event {
-    type Err = DecodeJdwpDataError;
+#[derive(Debug, Error)]
+#[error("The given raw command packet is not an event")]
+pub struct NotAnEventError;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{DecodeJdwpDataError, JdwpEncoder};
+    use crate::id_sizes::IdSizes;
+    use bytes::Bytes;
+    use jdwp_types::{Byte, ClassId, FieldId, Id, MethodId, Tag};
+
+    const REQUEST: [u8; 4] = [0, 0, 0, 9];
+    const THREAD: [u8; 4] = [0, 0, 0, 1];
+    const LOCATION: [u8; 17] = [1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 4];
+    const OBJECT: [u8; 5] = [b'L', 0, 0, 0, 5];
+    const CLASS: [u8; 5] = [1, 0, 0, 0, 6];
+    const SIGNATURE: [u8; 7] = [0, 0, 0, 3, b'L', b'A', b';'];
+    const STATUS: [u8; 4] = [0, 0, 0, 7];
+    const FIELD: [u8; 4] = [0, 0, 0, 8];
+
+    fn codec() -> JdwpCodec {
+        JdwpCodec::new(IdSizes::new(4, 4, 4, 4))
+    }
+
+    fn golden(kind: u8, parts: &[&[u8]]) -> Vec<u8> {
+        let mut bytes = vec![kind];
+        for part in parts {
+            bytes.extend_from_slice(part);
+        }
+        bytes
+    }
+
+    fn location() -> Location {
+        Location {
+            tag: TypeTag::Class,
+            class: ClassId::new(2),
+            method: MethodId::new(3),
+            offset: 4,
+        }
+    }
+
+    fn object() -> TaggedObjectId {
+        TaggedObjectId::new(Tag::Object, Id::new(5))
+    }
+
+    fn status() -> ClassStatus {
+        ClassStatus(7)
+    }
+
+    /// Every kind of event that can be received, along with its encoding
+    fn goldens() -> Vec<(Vec<u8>, Event)> {
+        let thread = ThreadId::new(1);
+        vec![
+            (
+                golden(1, &[&REQUEST, &THREAD, &LOCATION]),
+                Event::SingleStep {
+                    request_id: 9,
+                    thread,
+                    location: location(),
+                },
+            ),
+            (
+                golden(2, &[&REQUEST, &THREAD, &LOCATION]),
+                Event::Breakpoint {
+                    request_id: 9,
+                    thread,
+                    location: location(),
+                },
+            ),
+            (
+                golden(4, &[&REQUEST, &THREAD, &LOCATION, &OBJECT, &LOCATION]),
+                Event::Exception {
+                    request_id: 9,
+                    thread,
+                    location: location(),
+                    exception: object(),
+                    catch_location: location(),
+                },
+            ),
+            (
+                golden(6, &[&REQUEST, &THREAD]),
+                Event::ThreadStart {
+                    request_id: 9,
+                    thread,
+                },
+            ),
+            (
+                golden(7, &[&REQUEST, &THREAD]),
+                Event::ThreadDeath {
+                    request_id: 9,
+                    thread,
+                },
+            ),
+            (
+                golden(8, &[&REQUEST, &THREAD, &CLASS, &SIGNATURE, &STATUS]),
+                Event::ClassPrepare {
+                    request_id: 9,
+                    thread,
+                    ref_type_tag: TypeTag::Class,
+                    type_id: ReferenceTypeId::new(6),
+                    signature: "LA;".to_string(),
+                    status: status(),
+                },
+            ),
+            (
+                golden(9, &[&REQUEST, &SIGNATURE]),
+                Event::ClassUnload {
+                    request_id: 9,
+                    signature: "LA;".to_string(),
+                },
+            ),
+            (
+                golden(20, &[&REQUEST, &THREAD, &LOCATION, &CLASS, &FIELD, &OBJECT]),
+                Event::FieldAccess {
+                    request_id: 9,
+                    thread,
+                    location: location(),
+                    ref_type_tag: TypeTag::Class,
+                    type_id: ReferenceTypeId::new(6),
+                    field_id: FieldId::new(8),
+                    object: object(),
+                },
+            ),
+            (
+                golden(
+                    21,
+                    &[
+                        &REQUEST,
+                        &THREAD,
+                        &LOCATION,
+                        &CLASS,
+                        &FIELD,
+                        &OBJECT,
+                        &[b'I', 0, 0, 0, 42],
+                    ],
+                ),
+                Event::FieldModification {
+                    request_id: 9,
+                    thread,
+                    location: location(),
+                    ref_type_tag: TypeTag::Class,
+                    type_id: ReferenceTypeId::new(6),
+                    field_id: FieldId::new(8),
+                    object: object(),
+                    value_to_be: Value::Int(42),
+                },
+            ),
+            (
+                golden(40, &[&REQUEST, &THREAD, &LOCATION]),
+                Event::MethodEntry {
+                    request_id: 9,
+                    thread,
+                    location: location(),
+                },
+            ),
+            (
+                golden(41, &[&REQUEST, &THREAD, &LOCATION]),
+                Event::MethodExit {
+                    request_id: 9,
+                    thread,
+                    location: location(),
+                },
+            ),
+            (
+                golden(42, &[&REQUEST, &THREAD, &LOCATION, &[b'Z', 1]]),
+                Event::MethodExitWithReturnValue {
+                    request_id: 9,
+                    thread,
+                    location: location(),
+                    value: Value::Boolean(true),
+                },
+            ),
+            (
+                golden(43, &[&REQUEST, &THREAD, &OBJECT, &LOCATION]),
+                Event::MonitorContendedEnter {
+                    request_id: 9,
+                    thread,
+                    object: object(),
+                    location: location(),
+                },
+            ),
+            (
+                golden(44, &[&REQUEST, &THREAD, &OBJECT, &LOCATION]),
+                Event::MonitorContendedEntered {
+                    request_id: 9,
+                    thread,
+                    object: object(),
+                    location: location(),
+                },
+            ),
+            (
+                golden(
+                    45,
+                    &[
+                        &REQUEST,
+                        &THREAD,
+                        &OBJECT,
+                        &LOCATION,
+                        &[0, 0, 0, 0, 0, 0, 3, 232],
+                    ],
+                ),
+                Event::MonitorWait {
+                    request_id: 9,
+                    thread,
+                    object: object(),
+                    location: location(),
+                    timeout: 1000,
+                },
+            ),
+            (
+                golden(46, &[&REQUEST, &THREAD, &OBJECT, &LOCATION, &[1]]),
+                Event::MonitorWaited {
+                    request_id: 9,
+                    thread,
+                    object: object(),
+                    location: location(),
+                    timed_out: 1,
+                },
+            ),
+            (
+                golden(90, &[&[0, 0, 0, 0], &THREAD]),
+                Event::VmStart {
+                    request_id: 0,
+                    thread,
+                },
+            ),
+            (golden(99, &[&REQUEST]), Event::VmDeath { request_id: 9 }),
+        ]
+    }
+
+    #[test]
+    fn decode_every_event_kind() {
+        let codec = codec();
+        for (bytes, expected) in goldens() {
+            let mut decoder = JdwpDecoder::new(&codec, Bytes::from(bytes));
+            let event = decoder
+                .get::<Event>()
+                .unwrap_or_else(|e| panic!("failed to decode {expected:?}: {e}"));
+            assert_eq!(event, expected);
+            assert!(
+                decoder.get::<Byte>().is_err(),
+                "payload of {expected:?} was not fully consumed"
+            );
+        }
+    }
+
+    #[test]
+    fn decode_composite_of_every_event_kind() {
+        let codec = codec();
+        let goldens = goldens();
+        let mut bytes = (goldens.len() as Int).to_be_bytes().to_vec();
+        for (golden, _) in &goldens {
+            bytes.extend_from_slice(golden);
+        }
+        let mut decoder = JdwpDecoder::new(&codec, Bytes::from(bytes));
+        let events = decoder.get::<Vec<Event>>().expect("failed to decode");
+        let expected = goldens
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect::<Vec<_>>();
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn encode_every_event_kind() {
+        let codec = codec();
+        for (bytes, event) in goldens() {
+            let mut encoder = JdwpEncoder::new(&codec);
+            encoder.put(&event);
+            assert_eq!(&encoder.into_bytes()[..], &bytes[..], "{event:?}");
+        }
+    }
 
-    fn decode(decoder: &mut JdwpDecoder) -> Result<Self, Self::Err> {
-        let event_kind = decoder
-            .get::<Byte>()
-            .and_then(|i| EventKind::try_from(i).map_err(|e| e.into()))?;
-        trace!("got event kind: {event_kind:?}");
-        let event: Event = match event_kind {
-            EventKind::SingleStep => Event::SingleStep {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-                location: decoder.get()?,
-            },
-            EventKind::Breakpoint => Event::Breakpoint {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-                location: decoder.get()?,
-            },
-            EventKind::FramePop => Event::FramePop,
-            EventKind::Exception => Event::Exception {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-                location: decoder.get()?,
-                exception: decoder.get()?,
-                catch_location: decoder.get()?,
-            },
-            EventKind::UserDefined => Event::UserDefined,
-            EventKind::ThreadStart => Event::ThreadStart {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-            },
-            EventKind::ThreadDeath => Event::ThreadDeath {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-            },
-            EventKind::ClassPrepare => Event::ClassPrepare {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-                ref_type_tag: decoder
-                    .get::<Byte>()
-                    .and_then(|b| Ok(TypeTag::try_from(b)?))?,
-                type_id: decoder.get()?,
-                signature: decoder.get()?,
-                status: decoder.get::<ClassStatus>()?,
-            },
-            EventKind::ClassUnload => Event::ClassUnload {
-                request_id: decoder.get()?,
-                signature: decoder.get()?,
-            },
-            EventKind::ClassLoad => Event::ClassLoad,
-            EventKind::FieldAccess => Event::FieldAccess {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-                ref_type_tag: decoder
-                    .get::<Byte>()
-                    .and_then(|b| Ok(TypeTag::try_from(b)?))?,
-                type_id: decoder.get()?,
-                field_id: decoder.get()?,
-                object: decoder.get()?,
-            },
-            EventKind::FieldModification => Event::FieldModification {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-                ref_type_tag: decoder
-                    .get::<Byte>()
-                    .and_then(|b| Ok(TypeTag::try_from(b)?))?,
-                type_id: decoder.get()?,
-                field_id: decoder.get()?,
-                object: decoder.get()?,
-                value_to_be: decoder.get()?,
-            },
-            EventKind::ExceptionCatch => Event::ExceptionCatch,
-            EventKind::MethodEntry => Event::MethodEntry {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-                location: decoder.get()?,
-            },
-            EventKind::MethodExit => Event::MethodExit {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-                location: decoder.get()?,
-            },
-            EventKind::MethodExitWithReturnValue => Event::MethodExitWithReturnValue {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-                location: decoder.get()?,
-                value: decoder.get()?,
-            },
-            EventKind::MonitorContendedEnter => Event::MonitorContendedEnter {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-                object: decoder.get()?,
-                location: decoder.get()?,
-            },
-            EventKind::MonitorContendedEntered => Event::MonitorContendedEnter {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-                object: decoder.get()?,
-                location: decoder.get()?,
-            },
-            EventKind::MonitorWait => Event::MonitorWait {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-                object: decoder.get()?,
-                location: decoder.get()?,
-                timeout: decoder.get()?,
-            },
-            EventKind::MonitorWaited => Event::MonitorWaited {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-                object: decoder.get()?,
-                location: decoder.get()?,
-                timed_out: decoder.get()?,
-            },
-            EventKind::VmStart => Event::VmStart {
-                request_id: decoder.get()?,
-                thread: decoder.get()?,
-            },
-            EventKind::VmDeath => Event::VmDeath {
-                request_id: decoder.get()?,
-            },
-            EventKind::VmDisconnected => {
-                unreachable!()
-            }
+    #[test]
+    fn encode_composite() {
+        let codec = codec();
+        let events = Events {
+            policy: SuspendPolicy::All,
+            events: vec![Event::VmDeath { request_id: 9 }],
         };
-        Ok(event)
+        let mut encoder = JdwpEncoder::new(&codec);
+        encoder.put(&events);
+        let packet = RawCommandPacket::new_command(1, Events::command_data(), encoder.into_bytes());
+        assert_eq!(to_events(packet, &codec).expect("not an event"), events);
     }
-}
 
-#[derive(Debug, Error)]
-#[error("The given raw command packet is not an event")]
-pub struct NotAnEventError;
+    #[test]
+    fn to_events_keeps_the_decode_error() {
+        let codec = codec();
+        let mut bytes = vec![Byte::from(SuspendPolicy::All), 0, 0, 0, 1];
+        bytes.extend(golden(Byte::from(EventKind::FramePop), &[&REQUEST]));
+        let packet = RawCommandPacket::new_command(1, Events::command_data(), bytes.into());
+        let error = to_events(packet, &codec).expect_err("frame pops can't be decoded");
+        assert!(matches!(
+            error
+                .get_ref()
+                .and_then(|e| e.downcast_ref::<DecodeJdwpDataError>()),
+            Some(DecodeJdwpDataError::UnexpectedEventKind(
+                EventKind::FramePop
+            ))
+        ));
+
+        let packet = RawCommandPacket::new_command(1, CommandData::new(1, 1), Bytes::new());
+        let error = to_events(packet, &codec).expect_err("not a composite");
+        assert!(error.get_ref().is_some_and(|e| e.is::<NotAnEventError>()));
+    }
+
+    #[test]
+    fn decode_events_that_can_not_be_received() {
+        let codec = codec();
+        for kind in [
+            EventKind::FramePop,
+            EventKind::UserDefined,
+            EventKind::ClassLoad,
+            EventKind::ExceptionCatch,
+            EventKind::VmDisconnected,
+        ] {
+            let bytes = golden(Byte::from(kind), &[&REQUEST]);
+            let mut decoder = JdwpDecoder::new(&codec, Bytes::from(bytes));
+            assert!(matches!(
+                decoder.get::<Event>(),
+                Err(DecodeJdwpDataError::UnexpectedEventKind(unexpected)) if unexpected == kind
+            ));
+        }
+    }
+}
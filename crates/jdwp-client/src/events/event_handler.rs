@@ -1,9 +1,8 @@
-use crate::events::Event;
-use jdwp_types::SuspendPolicy;
+use crate::events::{Event, Events};
+use jdwp_types::{EventKind, Int, SuspendPolicy, ThreadId};
 use pin_project::pin_project;
 use std::convert::Infallible;
 use std::future::Future;
-use std::ops::DerefMut;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
@@ -30,6 +29,109 @@ where
     }
 }
 
+/// Handles every event within a composite at once
+pub trait EventsHandler: Clone + Send + Sized + 'static {
+    type Err;
+    type Future: Future<Output = Result<(), Self::Err>> + Send;
+
+    fn handle_events(self, events: Events) -> Self::Future;
+}
+
+impl<F, Fut, Err> EventsHandler for F
+where
+    F: FnOnce(Events) -> Fut,
+    F: Clone + Send + 'static,
+    Fut: Future<Output = Result<(), Err>> + Send + 'static,
+{
+    type Err = Err;
+    type Future = Fut;
+
+    fn handle_events(self, events: Events) -> Self::Future {
+        self(events)
+    }
+}
+
+/// How events are delivered to a handler
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum DeliveryMode {
+    /// Events are handled one at a time, in the order they were received. A handler won't
+    /// receive the next event until it has finished handling the previous one.
+    #[default]
+    Ordered,
+    /// Events are handled as soon as they're received, possibly while previous events are still
+    /// being handled.
+    Concurrent,
+}
+
+/// Options for how an event handler is registered, and which events it receives.
+///
+/// Filters of different types must all match for an event to be delivered, while each filter
+/// matches if the event matches any of the values given to it. With no filters, a handler
+/// receives every event.
+#[derive(Debug, Default, Clone)]
+pub struct HandlerOptions {
+    delivery: DeliveryMode,
+    kinds: Vec<EventKind>,
+    request_ids: Vec<Int>,
+    threads: Vec<ThreadId>,
+}
+
+impl HandlerOptions {
+    /// Creates the default handler options, delivering events in order
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how events are delivered to the handler
+    pub fn delivery(mut self, delivery: DeliveryMode) -> Self {
+        self.delivery = delivery;
+        self
+    }
+
+    /// Gets how events are delivered to the handler
+    pub fn delivery_mode(&self) -> DeliveryMode {
+        self.delivery
+    }
+
+    /// Only deliver events of the given kind
+    pub fn kind(mut self, kind: EventKind) -> Self {
+        self.kinds.push(kind);
+        self
+    }
+
+    /// Only deliver events generated by the event request with the given id
+    pub fn request_id(mut self, request_id: Int) -> Self {
+        self.request_ids.push(request_id);
+        self
+    }
+
+    /// Only deliver events that occurred in the given thread
+    pub fn thread(mut self, thread: ThreadId) -> Self {
+        self.threads.push(thread);
+        self
+    }
+
+    /// Checks whether an event passes every filter
+    pub fn matches(&self, event: &Event) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&event.kind()))
+            && (self.request_ids.is_empty()
+                || event
+                    .request_id()
+                    .is_some_and(|id| self.request_ids.contains(&id)))
+            && (self.threads.is_empty()
+                || event
+                    .thread()
+                    .is_some_and(|thread| self.threads.contains(&thread)))
+    }
+
+    /// Removes every event that doesn't pass the filters from a composite, returning `None` if
+    /// none are left
+    pub(crate) fn filter(&self, mut events: Events) -> Option<Events> {
+        events.events.retain(|event| self.matches(event));
+        (!events.events.is_empty()).then_some(events)
+    }
+}
+
 type OwnedEventHandlerFn<E> = dyn Fn(SuspendPolicy, Event) -> Pin<Box<dyn Future<Output = Result<(), E>> + Send>>
     + Send
     + Sync;
@@ -153,17 +255,17 @@ where
 #[cfg(test)]
 mod tests {
     use crate::events::*;
-    use jdwp_types::SuspendPolicy;
+    use jdwp_types::{EventKind, SuspendPolicy, ThreadId};
     use std::convert::Infallible;
     use std::sync::Arc;
     use tokio::sync::Mutex;
 
     #[tokio::test]
     async fn test_async_handle_event() {
-        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<()>();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<()>();
 
         let rx = Arc::new(Mutex::new(rx));
-        let mut func = |policy: SuspendPolicy, event: Event| async move {
+        let func = |_policy: SuspendPolicy, _event: Event| async move {
             let mut guard = rx.lock().await;
             let _ = guard.recv().await;
             Result::<_, Infallible>::Ok(())
@@ -175,10 +277,10 @@ mod tests {
 
     #[tokio::test]
     async fn test_async_owned_handle_event() {
-        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<()>();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<()>();
 
         let rx = Arc::new(Mutex::new(rx));
-        let mut func = handle_event(|suspend_policy: SuspendPolicy, event: Event| async move {
+        let func = handle_event(|_suspend_policy: SuspendPolicy, _event: Event| async move {
             let mut guard = rx.lock().await;
             let _ = guard.recv().await;
             Result::<_, Infallible>::Ok(())
@@ -188,4 +290,51 @@ mod tests {
         let fut = func.handle_event(SuspendPolicy::All, Event::VmDisconnected);
         fut.await.expect("Failed to receive event");
     }
+
+    #[test]
+    fn test_handler_options_filter() {
+        let thread = ThreadId::new(1);
+        let events = Events {
+            policy: SuspendPolicy::All,
+            events: vec![
+                Event::VmStart {
+                    request_id: 0,
+                    thread,
+                },
+                Event::ThreadStart {
+                    request_id: 2,
+                    thread,
+                },
+                Event::ThreadStart {
+                    request_id: 3,
+                    thread: ThreadId::new(2),
+                },
+                Event::VmDeath { request_id: 2 },
+            ],
+        };
+
+        let everything = HandlerOptions::new();
+        assert_eq!(everything.filter(events.clone()).unwrap().events.len(), 4);
+
+        let thread_starts = HandlerOptions::new().kind(EventKind::ThreadStart);
+        assert_eq!(
+            thread_starts.filter(events.clone()).unwrap().events.len(),
+            2
+        );
+
+        let request = HandlerOptions::new().request_id(2);
+        assert_eq!(request.filter(events.clone()).unwrap().events.len(), 2);
+
+        let in_thread = HandlerOptions::new()
+            .kind(EventKind::ThreadStart)
+            .thread(thread);
+        let filtered = in_thread.filter(events.clone()).unwrap();
+        assert!(matches!(
+            filtered.events[..],
+            [Event::ThreadStart { request_id: 2, .. }]
+        ));
+
+        let none = HandlerOptions::new().kind(EventKind::Breakpoint);
+        assert!(none.filter(events).is_none());
+    }
 }
@@ -0,0 +1,52 @@
+use crate::events::Events;
+use futures::{ready, Stream, StreamExt};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::broadcast::Receiver;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tracing::warn;
+
+/// A stream of every composite event received from the target VM, in the order they were received.
+///
+/// Created by [`JdwpClient::events`](crate::client::JdwpClient::events). Every stream gets its own
+/// copy of each composite. A stream that falls more than the client's
+/// [event capacity](crate::client::ClientConfig::event_capacity) behind skips the oldest composites
+/// it hasn't received yet, which are counted by [`missed`](EventStream::missed). The stream ends
+/// once the target VM disconnects.
+#[derive(Debug)]
+pub struct EventStream {
+    inner: BroadcastStream<Events>,
+    missed: u64,
+}
+
+impl EventStream {
+    pub(crate) fn new(receiver: Receiver<Events>) -> Self {
+        Self {
+            inner: BroadcastStream::new(receiver),
+            missed: 0,
+        }
+    }
+
+    /// The number of composites skipped because this stream fell too far behind
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Events;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match ready!(self.inner.poll_next_unpin(cx)) {
+                Some(Ok(events)) => return Poll::Ready(Some(events)),
+                Some(Err(BroadcastStreamRecvError::Lagged(skipped))) => {
+                    warn!("event stream fell behind, skipped {skipped} composite events");
+                    self.missed += skipped;
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
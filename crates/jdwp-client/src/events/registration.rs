@@ -0,0 +1,181 @@
+use crate::events::Events;
+use futures::Stream;
+use std::fmt::{Debug, Display, Formatter};
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use thiserror::Error;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// Uniquely identifies a registered event handler within a client
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct HandlerId(pub(crate) u64);
+
+impl Display for HandlerId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// An event handler returned an error
+#[derive(Debug, Error)]
+#[error("event handler {handler} failed: {error}")]
+pub struct HandlerError {
+    handler: HandlerId,
+    events: Events,
+    #[source]
+    error: io::Error,
+}
+
+impl HandlerError {
+    /// Gets the handler that failed
+    pub fn handler(&self) -> HandlerId {
+        self.handler
+    }
+
+    /// Gets the events that were being handled. For handlers of single events, this composite
+    /// only contains the event that failed.
+    pub fn events(&self) -> &Events {
+        &self.events
+    }
+
+    /// Gets the error returned by the handler
+    pub fn error(&self) -> &io::Error {
+        &self.error
+    }
+
+    /// Converts this into the error returned by the handler
+    pub fn into_error(self) -> io::Error {
+        self.error
+    }
+}
+
+/// A stream of the errors returned by a registered handler
+#[derive(Debug)]
+pub struct HandlerErrors {
+    receiver: UnboundedReceiver<HandlerError>,
+}
+
+impl Stream for HandlerErrors {
+    type Item = HandlerError;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// State shared between a registration and the task delivering events to its handler
+#[derive(Debug)]
+pub(crate) struct HandlerState {
+    id: HandlerId,
+    registered: AtomicBool,
+    errors: Mutex<Option<UnboundedSender<HandlerError>>>,
+}
+
+impl HandlerState {
+    pub(crate) fn new(id: HandlerId) -> Arc<Self> {
+        Arc::new(Self {
+            id,
+            registered: AtomicBool::new(true),
+            errors: Mutex::new(None),
+        })
+    }
+
+    pub(crate) fn id(&self) -> HandlerId {
+        self.id
+    }
+
+    pub(crate) fn is_registered(&self) -> bool {
+        self.registered.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn unregistered(&self) {
+        self.registered.store(false, Ordering::SeqCst);
+    }
+
+    /// Reports an error returned by the handler, giving it back if nothing is listening for
+    /// errors
+    pub(crate) fn report(&self, events: Events, error: io::Error) -> Result<(), HandlerError> {
+        let error = HandlerError {
+            handler: self.id,
+            events,
+            error,
+        };
+        match &*self.errors.lock().expect("handler errors poisoned") {
+            Some(sender) => sender.send(error).map_err(|e| e.0),
+            None => Err(error),
+        }
+    }
+}
+
+/// A registered event handler, which is unregistered once this is dropped.
+///
+/// Events already being handled when the handler is unregistered are allowed to finish, but no
+/// more events are delivered to it afterward.
+#[must_use = "the handler is unregistered as soon as its registration is dropped"]
+pub struct HandlerRegistration {
+    state: Arc<HandlerState>,
+    remove: Option<Box<dyn FnOnce(HandlerId) + Send + Sync>>,
+}
+
+impl HandlerRegistration {
+    pub(crate) fn new(
+        state: Arc<HandlerState>,
+        remove: impl FnOnce(HandlerId) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            state,
+            remove: Some(Box::new(remove)),
+        }
+    }
+
+    /// Gets the id of the registered handler
+    pub fn id(&self) -> HandlerId {
+        self.state.id
+    }
+
+    /// Gets whether the handler is still registered. A handler is unregistered either explicitly
+    /// or once the target VM disconnects.
+    pub fn is_registered(&self) -> bool {
+        self.state.is_registered()
+    }
+
+    /// Gets a stream of the errors returned by the handler from now on, replacing any previously
+    /// returned stream. Errors that occur while nothing is listening are only logged.
+    pub fn errors(&self) -> HandlerErrors {
+        let (tx, rx) = unbounded_channel();
+        *self.state.errors.lock().expect("handler errors poisoned") = Some(tx);
+        HandlerErrors { receiver: rx }
+    }
+
+    /// Unregisters the handler
+    pub fn unregister(self) {
+        drop(self)
+    }
+
+    /// Keeps the handler registered for as long as the client exists, without needing to hold
+    /// on to this registration
+    pub fn detach(mut self) {
+        self.remove = None;
+    }
+}
+
+impl Debug for HandlerRegistration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HandlerRegistration")
+            .field("id", &self.state.id)
+            .field("registered", &self.is_registered())
+            .finish()
+    }
+}
+
+impl Drop for HandlerRegistration {
+    fn drop(&mut self) {
+        if let Some(remove) = self.remove.take() {
+            self.state.unregistered();
+            remove(self.state.id);
+        }
+    }
+}
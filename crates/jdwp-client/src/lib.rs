@@ -1,5 +1,9 @@
 //! A basic jdwp client, this is a raw jdwp implementation that matches the original spec
 
+// lets the code derived by jdwp-derive refer to this crate by name
+extern crate self as jdwp_client;
+
+pub mod capture;
 mod client;
 pub mod codec;
 pub mod commands;
@@ -7,8 +11,10 @@ pub mod connect;
 pub mod events;
 pub mod id_sizes;
 pub mod packet;
-mod raw;
+pub mod raw;
+pub mod record;
+pub mod registry;
 
-pub use client::JdwpClient;
+pub use client::{ClientConfig, JdwpClient};
 
 pub use jdwp_types;
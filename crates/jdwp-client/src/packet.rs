@@ -1,5 +1,8 @@
 use crate::codec::{JdwpDecodable, JdwpEncodable};
-use crate::raw::packet::CommandData;
+pub use crate::raw::packet::CommandData;
+pub use jdwp_derive::jdwp_command;
+use jdwp_types::ErrorConstant;
+use thiserror::Error;
 
 /// used for representing a JDWP command
 pub trait JdwpCommand: Sized + JdwpEncodable {
@@ -7,3 +10,66 @@ pub trait JdwpCommand: Sized + JdwpEncodable {
 
     fn command_data() -> CommandData;
 }
+
+/// An error code sent back by the target VM in reply to a command
+#[derive(Debug, Error)]
+#[error("command {command:?} failed with error code {code}{}", self.error_constant().map(|c| format!(" ({c:?})")).unwrap_or_default())]
+pub struct ReplyError {
+    command: CommandData,
+    code: u16,
+}
+
+impl ReplyError {
+    pub(crate) fn new(command: CommandData, code: u16) -> Self {
+        Self { command, code }
+    }
+
+    /// Gets the command that failed
+    pub fn command(&self) -> CommandData {
+        self.command
+    }
+
+    /// Gets the raw error code
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    /// Gets the error constant for this error code, if it's a known one
+    pub fn error_constant(&self) -> Option<ErrorConstant> {
+        ErrorConstant::try_from(self.code).ok()
+    }
+
+    /// Tries to get a reply error from an io error
+    pub fn from_io_error(error: &std::io::Error) -> Option<&ReplyError> {
+        error.get_ref()?.downcast_ref::<ReplyError>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{JdwpDecode, JdwpEncode};
+    use jdwp_types::Int;
+
+    #[jdwp_command(set = 64, cmd = 100)]
+    #[derive(Debug, JdwpEncode)]
+    struct Probe;
+
+    #[derive(Debug, JdwpDecode)]
+    struct ProbeReply;
+
+    #[jdwp_command(set = 64, cmd = 101, reply = ProbeReply)]
+    #[derive(Debug, JdwpEncode)]
+    struct Repeat(Int);
+
+    /// Gets the command data of a command replied to with a [ProbeReply]
+    fn command_data<C: JdwpCommand<Reply = ProbeReply>>() -> CommandData {
+        C::command_data()
+    }
+
+    #[test]
+    fn derive_command() {
+        assert_eq!(command_data::<Probe>(), CommandData::new(64, 100));
+        assert_eq!(command_data::<Repeat>(), CommandData::new(64, 101));
+    }
+}
@@ -5,20 +5,17 @@ use crate::raw::codec::RawCodec;
 use crate::raw::packet::{AnyRawPacket, RawCommandPacket};
 use futures::Sink;
 use futures::Stream;
-use pin_project::pin_project;
-use std::io;
+use pin_project::{pin_project, pinned_drop};
 use std::io::Error;
 use std::marker::PhantomData;
-use std::pin::{pin, Pin};
-use std::sync::Arc;
+use std::pin::Pin;
 use std::task::{Context, Poll};
-use tokio::io::{AsyncRead, AsyncWrite};
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
-use tokio::sync::mpsc::{unbounded_channel, Receiver, UnboundedReceiver};
+use tokio::io::AsyncWrite;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
 use tokio::task::JoinHandle;
 use tokio_stream::StreamExt;
 use tokio_util::codec::{FramedRead, FramedWrite};
-use tracing::{error_span, instrument, trace};
+use tracing::error_span;
 
 pub mod codec;
 pub mod packet;
@@ -31,36 +28,44 @@ pub struct RawPacketSink<I: AsyncWrite + Unpin>(#[pin] FramedWrite<I, RawCodec>)
 impl<I: AsyncWrite + Unpin> Sink<RawCommandPacket> for RawPacketSink<I> {
     type Error = Error;
 
-    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Sink::<RawCommandPacket>::poll_ready(self.project().0, cx)
     }
 
-    fn start_send(mut self: Pin<&mut Self>, item: RawCommandPacket) -> Result<(), Self::Error> {
+    fn start_send(self: Pin<&mut Self>, item: RawCommandPacket) -> Result<(), Self::Error> {
         Sink::<RawCommandPacket>::start_send(self.project().0, item)
     }
 
-    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Sink::<RawCommandPacket>::poll_flush(self.project().0, cx)
     }
 
-    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Sink::<RawCommandPacket>::poll_close(self.project().0, cx)
     }
 }
 
 /// A raw packet stream
 #[derive(Debug)]
-#[pin_project]
+#[pin_project(PinnedDrop)]
 pub struct RawPacketStream<O> {
     sender: UnboundedReceiver<Result<AnyRawPacket, Error>>,
     task: JoinHandle<()>,
     _phantom: PhantomData<O>,
 }
 
+#[pinned_drop]
+impl<O> PinnedDrop for RawPacketStream<O> {
+    fn drop(self: Pin<&mut Self>) {
+        // stops reading, so the input is closed along with the stream
+        self.task.abort();
+    }
+}
+
 impl<O> Stream for RawPacketStream<O> {
     type Item = Result<AnyRawPacket, Error>;
 
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         self.project().sender.poll_recv(cx)
     }
 }
@@ -78,7 +83,7 @@ where
 {
     /// Creates a new RawJdwpClient
     pub fn new(input: T::Input, output: T::Output) -> Self {
-        let codec = RawCodec::default();
+        let codec = RawCodec;
         let raw_sink = FramedWrite::new(output, codec);
         let mut raw_stream = FramedRead::new(input, codec);
 
@@ -1,6 +1,7 @@
 use bitfield::bitfield;
 use bytes::Bytes;
 use private::Sealed;
+use std::fmt::{Display, Formatter};
 
 pub const MAX_PACKET_LENGTH: usize = 1 << 22;
 pub const MIN_PACKET_LENGTH: usize = size_of::<u32>() * 2 + size_of::<u8>() + size_of::<u16>();
@@ -14,7 +15,7 @@ bitfield! {
     pub is_reply, set_is_reply: 7;
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct CommandData {
     command_set: u8,
     command: u8,
@@ -22,7 +23,7 @@ pub struct CommandData {
 
 impl CommandData {
     /// Creates a new command data struct
-    pub fn new(command_set: u8, command: u8) -> CommandData {
+    pub const fn new(command_set: u8, command: u8) -> CommandData {
         Self {
             command_set,
             command,
@@ -38,8 +39,148 @@ impl CommandData {
     pub fn command(&self) -> u8 {
         self.command
     }
+
+    /// Gets the name of the command set, such as `VirtualMachine`, if it's one defined by the
+    /// specification
+    pub fn command_set_name(&self) -> Option<&'static str> {
+        Some(match self.command_set {
+            1 => "VirtualMachine",
+            2 => "ReferenceType",
+            3 => "ClassType",
+            4 => "ArrayType",
+            5 => "InterfaceType",
+            6 => "Method",
+            8 => "Field",
+            9 => "ObjectReference",
+            10 => "StringReference",
+            11 => "ThreadReference",
+            12 => "ThreadGroupReference",
+            13 => "ArrayReference",
+            14 => "ClassLoaderReference",
+            15 => "EventRequest",
+            16 => "StackFrame",
+            17 => "ClassObjectReference",
+            18 => "ModuleReference",
+            64 => "Event",
+            _ => return None,
+        })
+    }
+
+    /// Gets the name of the command within its command set, such as `Version`, if it's one
+    /// defined by the specification
+    pub fn command_name(&self) -> Option<&'static str> {
+        Some(match (self.command_set, self.command) {
+            (1, 1) => "Version",
+            (1, 2) => "ClassesBySignature",
+            (1, 3) => "AllClasses",
+            (1, 4) => "AllThreads",
+            (1, 5) => "TopLevelThreadGroups",
+            (1, 6) => "Dispose",
+            (1, 7) => "IDSizes",
+            (1, 8) => "Suspend",
+            (1, 9) => "Resume",
+            (1, 10) => "Exit",
+            (1, 11) => "CreateString",
+            (1, 12) => "Capabilities",
+            (1, 13) => "ClassPaths",
+            (1, 14) => "DisposeObjects",
+            (1, 15) => "HoldEvents",
+            (1, 16) => "ReleaseEvents",
+            (1, 17) => "CapabilitiesNew",
+            (1, 18) => "RedefineClasses",
+            (1, 19) => "SetDefaultStratum",
+            (1, 20) => "AllClassesWithGeneric",
+            (1, 21) => "InstanceCounts",
+            (1, 22) => "AllModules",
+            (2, 1) => "Signature",
+            (2, 2) => "ClassLoader",
+            (2, 3) => "Modifiers",
+            (2, 4) => "Fields",
+            (2, 5) => "Methods",
+            (2, 6) => "GetValues",
+            (2, 7) => "SourceFile",
+            (2, 8) => "NestedTypes",
+            (2, 9) => "Status",
+            (2, 10) => "Interfaces",
+            (2, 11) => "ClassObject",
+            (2, 12) => "SourceDebugExtension",
+            (2, 13) => "SignatureWithGeneric",
+            (2, 14) => "FieldsWithGeneric",
+            (2, 15) => "MethodsWithGeneric",
+            (2, 16) => "Instances",
+            (2, 17) => "ClassFileVersion",
+            (2, 18) => "ConstantPool",
+            (2, 19) => "Module",
+            (3, 1) => "Superclass",
+            (3, 2) => "SetValues",
+            (3, 3) => "InvokeMethod",
+            (3, 4) => "NewInstance",
+            (4, 1) => "NewInstance",
+            (5, 1) => "InvokeMethod",
+            (6, 1) => "LineTable",
+            (6, 2) => "VariableTable",
+            (6, 3) => "Bytecodes",
+            (6, 4) => "IsObsolete",
+            (6, 5) => "VariableTableWithGeneric",
+            (9, 1) => "ReferenceType",
+            (9, 2) => "GetValues",
+            (9, 3) => "SetValues",
+            (9, 5) => "MonitorInfo",
+            (9, 6) => "InvokeMethod",
+            (9, 7) => "DisableCollection",
+            (9, 8) => "EnableCollection",
+            (9, 9) => "IsCollected",
+            (9, 10) => "ReferringObjects",
+            (10, 1) => "Value",
+            (11, 1) => "Name",
+            (11, 2) => "Suspend",
+            (11, 3) => "Resume",
+            (11, 4) => "Status",
+            (11, 5) => "ThreadGroup",
+            (11, 6) => "Frames",
+            (11, 7) => "FrameCount",
+            (11, 8) => "OwnedMonitors",
+            (11, 9) => "CurrentContendedMonitor",
+            (11, 10) => "Stop",
+            (11, 11) => "Interrupt",
+            (11, 12) => "SuspendCount",
+            (11, 13) => "OwnedMonitorsStackDepthInfo",
+            (11, 14) => "ForceEarlyReturn",
+            (11, 15) => "IsVirtual",
+            (12, 1) => "Name",
+            (12, 2) => "Parent",
+            (12, 3) => "Children",
+            (13, 1) => "Length",
+            (13, 2) => "GetValues",
+            (13, 3) => "SetValues",
+            (14, 1) => "VisibleClasses",
+            (15, 1) => "Set",
+            (15, 2) => "Clear",
+            (15, 3) => "ClearAllBreakpoints",
+            (16, 1) => "GetValues",
+            (16, 2) => "SetValues",
+            (16, 3) => "ThisObject",
+            (16, 4) => "PopFrames",
+            (17, 1) => "ReflectedType",
+            (18, 1) => "Name",
+            (18, 2) => "ClassLoader",
+            (64, 100) => "Composite",
+            _ => return None,
+        })
+    }
 }
 impl Sealed for CommandData {}
+
+/// Formats the command as `CommandSet.Command`, or by its numbers if it isn't known
+impl Display for CommandData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.command_set_name(), self.command_name()) {
+            (Some(set), Some(command)) => write!(f, "{set}.{command}"),
+            (Some(set), None) => write!(f, "{set}.{}", self.command),
+            _ => write!(f, "{}.{}", self.command_set, self.command),
+        }
+    }
+}
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct ErrorCode {
     code: u16,
@@ -196,7 +337,7 @@ impl HeaderVariableData for CommandData {
 
 impl HeaderVariableData for ErrorCode {
     fn from_u16(value: u16) -> Self {
-        Self { code: value }
+        Self::new(value)
     }
 
     fn to_u16(&self) -> u16 {
@@ -210,7 +351,15 @@ pub mod private {
 
 #[cfg(test)]
 mod tests {
-    use crate::raw::packet::Flags;
+    use crate::raw::packet::{CommandData, Flags};
+
+    #[test]
+    fn test_command_names() {
+        assert_eq!(CommandData::new(1, 7).to_string(), "VirtualMachine.IDSizes");
+        assert_eq!(CommandData::new(64, 100).to_string(), "Event.Composite");
+        assert_eq!(CommandData::new(9, 4).to_string(), "ObjectReference.4");
+        assert_eq!(CommandData::new(200, 1).to_string(), "200.1");
+    }
 
     #[test]
     fn test_flag_invariants() {
@@ -0,0 +1,600 @@
+//! Records the packets a client exchanges with a target VM, and replays them later in its place.
+//!
+//! A [RecordingTransport] wraps the transport of a real connection, writing every packet sent or
+//! received to a recording along with when it was. A [ReplayTransport] then plays that recording
+//! back as the target VM, checking the client sends the same commands in the same order and
+//! answering them with the recorded replies. A session seen against a real VM can then be repeated
+//! deterministically, without `java`.
+//!
+//! A recording starts with [MAGIC], followed by each packet as its direction (`0` for commands the
+//! client sent, `1` for packets the target VM sent), the microseconds since recording started as a
+//! big-endian `u64`, then the packet as it's sent over the wire.
+
+use crate::connect::{accept_handshake, JdwpTransport, JDWP_HANDSHAKE};
+use crate::raw::codec::RawCodec;
+use crate::raw::packet::{AnyRawPacket, CommandData, RawReplyPacket};
+use bytes::{Buf, BufMut, BytesMut};
+use futures::{SinkExt, StreamExt};
+use pin_project::pin_project;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::mpsc;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite, DuplexStream, ReadBuf, ReadHalf, WriteHalf};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite};
+use tracing::{trace, warn};
+
+/// The bytes every recording starts with
+pub const MAGIC: &[u8; 8] = b"JDWPREC1";
+
+/// The size of the buffer between a replay and its client
+const REPLAY_BUFFER: usize = 64 * 1024;
+
+/// Which way a packet went
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Sent by the client to the target VM
+    ToVm,
+    /// Sent by the target VM to the client
+    FromVm,
+}
+
+/// A packet in a recording
+#[derive(Debug, Clone)]
+pub struct RecordedPacket {
+    /// Which way the packet went
+    pub direction: Direction,
+    /// How long after recording started the packet was sent or received
+    pub elapsed: Duration,
+    pub packet: AnyRawPacket,
+}
+
+/// The packets of a recorded session, in the order they were sent or received
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    packets: Vec<RecordedPacket>,
+}
+
+impl Recording {
+    /// Reads a recording
+    pub fn read(mut reader: impl Read) -> io::Result<Self> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        if !bytes.starts_with(MAGIC) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "not a jdwp recording",
+            ));
+        }
+        let mut buffer = BytesMut::from(&bytes[MAGIC.len()..]);
+        let mut packets = vec![];
+        while !buffer.is_empty() {
+            if buffer.len() < 9 {
+                return Err(ErrorKind::UnexpectedEof.into());
+            }
+            let direction = match buffer.get_u8() {
+                0 => Direction::ToVm,
+                1 => Direction::FromVm,
+                other => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("invalid direction {other}"),
+                    ))
+                }
+            };
+            let elapsed = Duration::from_micros(buffer.get_u64());
+            let packet = RawCodec
+                .decode(&mut buffer)?
+                .ok_or_else(|| io::Error::from(ErrorKind::UnexpectedEof))?;
+            packets.push(RecordedPacket {
+                direction,
+                elapsed,
+                packet,
+            });
+        }
+        Ok(Self { packets })
+    }
+
+    /// Opens a recording file
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read(File::open(path)?)
+    }
+
+    /// Gets the recorded packets
+    pub fn packets(&self) -> &[RecordedPacket] {
+        &self.packets
+    }
+}
+
+/// Hands packets to the thread writing a recording as they're seen, so recording never blocks the
+/// transport on its output
+#[derive(Clone)]
+struct Recorder {
+    packets: mpsc::Sender<BytesMut>,
+    start: Instant,
+}
+
+impl Recorder {
+    fn new(output: impl Write + Send + 'static) -> io::Result<(Self, Recorded)> {
+        let (packets, received) = mpsc::channel();
+        let (done, finished) = watch::channel(());
+        thread::Builder::new()
+            .name("jdwp-recorder".to_string())
+            .spawn(move || {
+                write_packets(output, received);
+                drop(done);
+            })?;
+        let recorder = Self {
+            packets,
+            start: Instant::now(),
+        };
+        Ok((recorder, Recorded { finished }))
+    }
+
+    fn record(&self, direction: Direction, packet: AnyRawPacket) -> io::Result<()> {
+        let mut bytes = BytesMut::new();
+        bytes.put_u8(match direction {
+            Direction::ToVm => 0,
+            Direction::FromVm => 1,
+        });
+        bytes.put_u64(self.start.elapsed().as_micros() as u64);
+        match packet {
+            AnyRawPacket::Command(command) => RawCodec.encode(command, &mut bytes)?,
+            AnyRawPacket::Reply(reply) => RawCodec.encode(reply, &mut bytes)?,
+        }
+        self.packets
+            .send(bytes)
+            .map_err(|_| io::Error::from(ErrorKind::BrokenPipe))
+    }
+}
+
+/// Writes the packets of a recording until every [Recorder] sending them is dropped
+fn write_packets(mut output: impl Write, packets: mpsc::Receiver<BytesMut>) {
+    for bytes in packets {
+        // each packet is written whole, so the recording is complete up to the last packet seen
+        if let Err(e) = output.write_all(&bytes).and_then(|()| output.flush()) {
+            warn!("could not record a packet: {e}");
+        }
+    }
+}
+
+/// Waits for a [RecordingTransport] to finish writing its recording
+#[derive(Debug, Clone)]
+pub struct Recorded {
+    finished: watch::Receiver<()>,
+}
+
+impl Recorded {
+    /// Waits until every packet has been written, which is once the transport has been dropped
+    pub async fn finish(mut self) {
+        // the writer never sends, so this only returns once it's done
+        let _ = self.finished.changed().await;
+    }
+}
+
+/// Watches the bytes going one way, recording each packet once it's whole
+struct Tap {
+    direction: Direction,
+    recorder: Recorder,
+    /// How much of the handshake is left to skip
+    handshake: usize,
+    buffer: BytesMut,
+}
+
+impl Tap {
+    fn new(direction: Direction, recorder: Recorder) -> Self {
+        Self {
+            direction,
+            recorder,
+            handshake: JDWP_HANDSHAKE.len(),
+            buffer: BytesMut::new(),
+        }
+    }
+
+    fn observe(&mut self, mut bytes: &[u8]) {
+        let skipped = self.handshake.min(bytes.len());
+        self.handshake -= skipped;
+        bytes = &bytes[skipped..];
+        self.buffer.extend_from_slice(bytes);
+        loop {
+            match RawCodec.decode(&mut self.buffer) {
+                Ok(Some(packet)) => {
+                    trace!("recording {:?} packet {packet:?}", self.direction);
+                    if let Err(e) = self.recorder.record(self.direction, packet) {
+                        warn!("could not record a packet: {e}");
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("could not decode a packet to record: {e}");
+                    self.buffer.clear();
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// A transport that records the packets sent and received over another
+pub struct RecordingTransport<T> {
+    transport: T,
+    recorder: Recorder,
+    recorded: Recorded,
+}
+
+impl<T: JdwpTransport> RecordingTransport<T> {
+    /// Records the packets sent over a transport to an output
+    pub fn new(transport: T, mut output: impl Write + Send + 'static) -> io::Result<Self> {
+        output.write_all(MAGIC)?;
+        let (recorder, recorded) = Recorder::new(output)?;
+        Ok(Self {
+            transport,
+            recorder,
+            recorded,
+        })
+    }
+
+    /// Records the packets sent over a transport to a new file
+    pub fn create(transport: T, path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(transport, File::create(path)?)
+    }
+
+    /// Gets a handle to wait for the recording to be written with. The packets are written on
+    /// another thread, so a recording may not be complete until then.
+    pub fn recorded(&self) -> Recorded {
+        self.recorded.clone()
+    }
+}
+
+impl<T: JdwpTransport> JdwpTransport for RecordingTransport<T> {
+    type Input = RecordingInput<T::Input>;
+    type Output = RecordingOutput<T::Output>;
+
+    fn split_transport(self) -> (Self::Input, Self::Output)
+    where
+        Self: Sized,
+    {
+        let (input, output) = self.transport.split_transport();
+        (
+            RecordingInput {
+                input,
+                tap: Tap::new(Direction::FromVm, self.recorder.clone()),
+            },
+            RecordingOutput {
+                output,
+                tap: Tap::new(Direction::ToVm, self.recorder),
+            },
+        )
+    }
+}
+
+/// The input of a [RecordingTransport]
+#[pin_project]
+pub struct RecordingInput<I> {
+    #[pin]
+    input: I,
+    tap: Tap,
+}
+
+impl<I: AsyncRead> AsyncRead for RecordingInput<I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let filled = buf.filled().len();
+        let poll = this.input.poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            this.tap.observe(&buf.filled()[filled..]);
+        }
+        poll
+    }
+}
+
+/// The output of a [RecordingTransport]
+#[pin_project]
+pub struct RecordingOutput<O> {
+    #[pin]
+    output: O,
+    tap: Tap,
+}
+
+impl<O: AsyncWrite> AsyncWrite for RecordingOutput<O> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let poll = this.output.poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            this.tap.observe(&buf[..written]);
+        }
+        poll
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().output.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().output.poll_shutdown(cx)
+    }
+}
+
+/// The client didn't repeat the recorded session
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    /// The client sent a different command than the one recorded
+    #[error("command {index} of the recording is {expected}, but the client sent {actual}")]
+    Mismatch {
+        index: usize,
+        expected: CommandData,
+        actual: CommandData,
+    },
+    /// The client sent the recorded command, but with different data
+    #[error("command {index} of the recording ({command}) was sent with different data")]
+    DataMismatch { index: usize, command: CommandData },
+    /// The client disconnected before sending every recorded command
+    #[error("the client disconnected with {0} recorded commands left to send")]
+    Incomplete(usize),
+    /// The client sent a command after every recorded command
+    #[error("the client sent {0} after the end of the recording")]
+    Unrecorded(CommandData),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// A transport connected to a replay of a recording, rather than a target VM
+#[derive(Debug)]
+pub struct ReplayTransport {
+    stream: DuplexStream,
+}
+
+impl ReplayTransport {
+    /// Starts replaying a recording as the target VM. Once every recorded packet has been played,
+    /// the replay waits for the client to disconnect, failing if it sends any more commands.
+    pub fn new(recording: Recording) -> (Self, Replay) {
+        let (client, vm) = tokio::io::duplex(REPLAY_BUFFER);
+        let task = tokio::spawn(replay(vm, recording));
+        (Self { stream: client }, Replay { task })
+    }
+}
+
+impl JdwpTransport for ReplayTransport {
+    type Input = ReadHalf<DuplexStream>;
+    type Output = WriteHalf<DuplexStream>;
+
+    fn split_transport(self) -> (Self::Input, Self::Output)
+    where
+        Self: Sized,
+    {
+        tokio::io::split(self.stream)
+    }
+}
+
+/// A recording being replayed by a [ReplayTransport]
+#[derive(Debug)]
+pub struct Replay {
+    task: JoinHandle<Result<(), ReplayError>>,
+}
+
+impl Replay {
+    /// Waits for the replay to finish, failing if the client didn't repeat the recorded session
+    pub async fn finish(self) -> Result<(), ReplayError> {
+        self.task.await.map_err(io::Error::other)?
+    }
+}
+
+/// Plays the target VM's side of a recording
+async fn replay(vm: DuplexStream, recording: Recording) -> Result<(), ReplayError> {
+    let (mut input, mut output) = tokio::io::split(vm);
+    accept_handshake(&mut input, &mut output).await?;
+    let mut commands = FramedRead::new(input, RawCodec);
+    let mut sink = FramedWrite::new(output, RawCodec);
+    // the ids the client sent each recorded command with, as they needn't match the recording
+    let mut ids = HashMap::new();
+    let remaining = |from: usize| {
+        recording.packets[from..]
+            .iter()
+            .filter(|packet| packet.direction == Direction::ToVm)
+            .count()
+    };
+
+    for (index, recorded) in recording.packets.iter().enumerate() {
+        match (&recorded.direction, &recorded.packet) {
+            (Direction::ToVm, AnyRawPacket::Command(expected)) => {
+                let actual = match commands.next().await {
+                    Some(Ok(AnyRawPacket::Command(actual))) => actual,
+                    Some(Ok(AnyRawPacket::Reply(_))) => {
+                        return Err(io::Error::new(
+                            ErrorKind::InvalidData,
+                            "the client sent a reply",
+                        )
+                        .into())
+                    }
+                    Some(Err(e)) => return Err(e.into()),
+                    None => return Err(ReplayError::Incomplete(remaining(index))),
+                };
+                let command = expected.header().command();
+                if actual.header().command() != command {
+                    return Err(ReplayError::Mismatch {
+                        index,
+                        expected: command,
+                        actual: actual.header().command(),
+                    });
+                }
+                if actual.data() != expected.data() {
+                    return Err(ReplayError::DataMismatch { index, command });
+                }
+                ids.insert(expected.header().id(), actual.header().id());
+            }
+            (Direction::ToVm, AnyRawPacket::Reply(_)) => {
+                warn!("skipping recorded reply {index} sent by the client");
+            }
+            (Direction::FromVm, AnyRawPacket::Reply(reply)) => {
+                let id = ids
+                    .get(&reply.header().id())
+                    .copied()
+                    .unwrap_or(reply.header().id());
+                sink.send(RawReplyPacket::new_reply(
+                    id,
+                    reply.header().error_code(),
+                    reply.data().clone(),
+                ))
+                .await?;
+            }
+            (Direction::FromVm, AnyRawPacket::Command(command)) => {
+                sink.send(command.clone()).await?;
+            }
+        }
+    }
+    match commands.next().await {
+        None => Ok(()),
+        Some(Ok(AnyRawPacket::Command(command))) => {
+            Err(ReplayError::Unrecorded(command.header().command()))
+        }
+        Some(Ok(AnyRawPacket::Reply(_))) => {
+            Err(io::Error::new(ErrorKind::InvalidData, "the client sent a reply").into())
+        }
+        Some(Err(e)) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::Version;
+    use crate::packet::JdwpCommand;
+    use crate::raw::packet::{ErrorCode, RawCommandPacket};
+    use crate::JdwpClient;
+    use bytes::Bytes;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_recording_round_trip() {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(MAGIC);
+        let command = RawCommandPacket::new_command(1, CommandData::new(1, 1), Bytes::new());
+        let reply = RawReplyPacket::new_reply(1, ErrorCode::new(0), Bytes::from_static(&[1, 2]));
+        for (direction, packet) in [
+            (0, AnyRawPacket::Command(command.clone())),
+            (1, AnyRawPacket::Reply(reply.clone())),
+        ] {
+            bytes.push(direction);
+            bytes.extend_from_slice(&42u64.to_be_bytes());
+            let mut encoded = BytesMut::new();
+            match packet {
+                AnyRawPacket::Command(command) => RawCodec.encode(command, &mut encoded).unwrap(),
+                AnyRawPacket::Reply(reply) => RawCodec.encode(reply, &mut encoded).unwrap(),
+            }
+            bytes.extend_from_slice(&encoded);
+        }
+
+        let recording = Recording::read(bytes.as_slice()).unwrap();
+        let packets = recording.packets();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].direction, Direction::ToVm);
+        assert_eq!(packets[0].elapsed, Duration::from_micros(42));
+        assert!(matches!(&packets[0].packet, AnyRawPacket::Command(c) if *c == command));
+        assert_eq!(packets[1].direction, Direction::FromVm);
+        assert!(matches!(&packets[1].packet, AnyRawPacket::Reply(r) if *r == reply));
+
+        assert!(Recording::read(&b"JDWPREC0"[..]).is_err());
+        assert!(Recording::read(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tap_skips_handshake() {
+        let output = SharedBuffer::default();
+        let (recorder, recorded) = Recorder::new(output.clone()).unwrap();
+        let mut tap = Tap::new(Direction::ToVm, recorder);
+        let command = RawCommandPacket::new_command(7, CommandData::new(1, 7), Bytes::new());
+        let mut encoded = BytesMut::new();
+        RawCodec.encode(command.clone(), &mut encoded).unwrap();
+        let bytes = [JDWP_HANDSHAKE.as_slice(), &encoded].concat();
+        // the packet arrives split across reads
+        tap.observe(&bytes[..10]);
+        tap.observe(&bytes[10..20]);
+        tap.observe(&bytes[20..]);
+        drop(tap);
+        recorded.finish().await;
+
+        let mut recorded = MAGIC.to_vec();
+        recorded.extend_from_slice(&output.0.lock().unwrap());
+        let recording = Recording::read(recorded.as_slice()).unwrap();
+        assert_eq!(recording.packets().len(), 1);
+        assert!(
+            matches!(&recording.packets()[0].packet, AnyRawPacket::Command(c) if *c == command)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_fails_on_unrecorded_commands() {
+        let command = CommandData::new(1, 7);
+        let mut sizes = BytesMut::new();
+        for _ in 0..5 {
+            sizes.put_i32(8);
+        }
+        let packets = vec![
+            RecordedPacket {
+                direction: Direction::ToVm,
+                elapsed: Duration::ZERO,
+                packet: AnyRawPacket::Command(RawCommandPacket::new_command(
+                    1,
+                    command,
+                    Bytes::new(),
+                )),
+            },
+            RecordedPacket {
+                direction: Direction::FromVm,
+                elapsed: Duration::ZERO,
+                packet: AnyRawPacket::Reply(RawReplyPacket::new_reply(
+                    1,
+                    ErrorCode::new(0),
+                    sizes.freeze(),
+                )),
+            },
+        ];
+
+        let (transport, replay) = ReplayTransport::new(Recording {
+            packets: packets.clone(),
+        });
+        let client = JdwpClient::create(transport).await.unwrap();
+        drop(client);
+        replay.finish().await.unwrap();
+
+        let (transport, replay) = ReplayTransport::new(Recording { packets });
+        let client = JdwpClient::create(transport).await.unwrap();
+        assert!(client.send(Version).await.is_err());
+        let error = replay.finish().await.unwrap_err();
+        assert!(
+            matches!(error, ReplayError::Unrecorded(c) if c == Version::command_data()),
+            "{error}"
+        );
+    }
+
+    #[derive(Debug, Default, Clone)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,516 @@
+//! Knows every command by its command set and command, so packets can be decoded and described
+//! without knowing statically which command they're for.
+//!
+//! A [Registry] has an [Entry] for every command, with its name and how to decode its request and
+//! reply. [Registry::describe] uses them to decode any packet into a [Description], a tree of its
+//! fields that can be printed with `Debug` or as JSON, which is what proxies, recorders and logs
+//! want to show. Replies don't say which command they answer, so that has to be given.
+//!
+//! Only the commands this crate implements, and the composite event command, have an entry. The
+//! other commands of the spec are still described by their name, but their data is left as bytes
+//! and [Description::undecoded] says the registry doesn't decode them. Commands outside the spec
+//! are reported as unknown.
+
+use crate::codec::{DecodeJdwpDataError, JdwpCodec, JdwpDecodable, JdwpDecoder};
+use crate::commands::*;
+use crate::events::Events;
+use crate::packet::JdwpCommand;
+use crate::raw::packet::{AnyRawPacket, CommandData};
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+mod describe;
+
+pub use describe::{Describe, Node};
+pub use jdwp_derive::Describe;
+
+type Decode = fn(&mut JdwpDecoder) -> Result<Node, DecodeJdwpDataError>;
+
+/// The registry of every command
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+/// Gets the registry of every command
+pub fn registry() -> &'static Registry {
+    &REGISTRY
+}
+
+fn decode<T: JdwpDecodable<Err = DecodeJdwpDataError> + Describe>(
+    decoder: &mut JdwpDecoder,
+) -> Result<Node, DecodeJdwpDataError> {
+    Ok(decoder.get::<T>()?.describe())
+}
+
+/// What the registry knows of a command
+#[derive(Debug, Copy, Clone)]
+pub struct Entry {
+    command: CommandData,
+    request: Option<Decode>,
+    reply: Option<Decode>,
+}
+
+impl Entry {
+    /// A command whose request and reply can both be decoded
+    fn of<C>() -> Self
+    where
+        C: JdwpCommand + JdwpDecodable<Err = DecodeJdwpDataError> + Describe,
+        C::Reply: JdwpDecodable<Err = DecodeJdwpDataError> + Describe,
+    {
+        Self {
+            command: C::command_data(),
+            request: Some(decode::<C>),
+            reply: Some(decode::<C::Reply>),
+        }
+    }
+
+    /// A command whose request holds untagged values, so can't be decoded without knowing their
+    /// types
+    fn untagged<C>() -> Self
+    where
+        C: JdwpCommand,
+        C::Reply: JdwpDecodable<Err = DecodeJdwpDataError> + Describe,
+    {
+        Self {
+            command: C::command_data(),
+            request: None,
+            reply: Some(decode::<C::Reply>),
+        }
+    }
+
+    /// Gets the command set and command
+    pub fn command(&self) -> CommandData {
+        self.command
+    }
+
+    /// Gets the name of the command, such as `VirtualMachine.Version`
+    pub fn name(&self) -> String {
+        self.command.to_string()
+    }
+
+    /// Decodes the data of a command, unless it holds untagged values
+    pub fn decode_request(
+        &self,
+        codec: &JdwpCodec,
+        data: Bytes,
+    ) -> Option<Result<Node, DecodeJdwpDataError>> {
+        self.request
+            .map(|decode| decode(&mut JdwpDecoder::new(codec, data)))
+    }
+
+    /// Decodes the data of a reply, unless the command is never replied to
+    pub fn decode_reply(
+        &self,
+        codec: &JdwpCodec,
+        data: Bytes,
+    ) -> Option<Result<Node, DecodeJdwpDataError>> {
+        self.reply
+            .map(|decode| decode(&mut JdwpDecoder::new(codec, data)))
+    }
+}
+
+/// Whether a packet is a command or a reply
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PacketKind {
+    Command,
+    Reply,
+}
+
+/// A packet, decoded as far as it could be
+#[derive(Debug, Clone, PartialEq)]
+pub struct Description {
+    pub id: u32,
+    pub kind: PacketKind,
+    /// The command the packet is, or answers if it's known
+    pub command: Option<CommandData>,
+    /// The name of the command, if it's known
+    pub name: Option<String>,
+    /// The error code of a failed reply
+    pub error_code: Option<u16>,
+    /// The fields of the packet, or its bytes if it couldn't be decoded
+    pub data: Node,
+    /// Why the packet couldn't be decoded
+    pub undecoded: Option<String>,
+}
+
+impl Description {
+    /// Describes this as a tree itself
+    pub fn to_node(&self) -> Node {
+        let mut fields = vec![
+            ("id", Node::Int(i64::from(self.id))),
+            (
+                "kind",
+                Node::Constant(format!("{:?}", self.kind).to_lowercase()),
+            ),
+        ];
+        if let Some(command) = self.command {
+            fields.push(("command_set", Node::Int(i64::from(command.command_set()))));
+            fields.push(("command", Node::Int(i64::from(command.command()))));
+        }
+        if let Some(name) = &self.name {
+            fields.push(("name", Node::String(name.clone())));
+        }
+        if let Some(code) = self.error_code {
+            fields.push(("error_code", Node::Int(i64::from(code))));
+        }
+        fields.push(("data", self.data.clone()));
+        if let Some(undecoded) = &self.undecoded {
+            fields.push(("undecoded", Node::String(undecoded.clone())));
+        }
+        Node::Struct(fields)
+    }
+
+    /// Prints this as JSON
+    pub fn to_json(&self) -> String {
+        self.to_node().to_json()
+    }
+}
+
+/// The commands known, by their command set and command
+#[derive(Debug)]
+pub struct Registry {
+    entries: HashMap<CommandData, Entry>,
+}
+
+impl Registry {
+    fn new() -> Self {
+        use array_reference as ar;
+        use class_type as ct;
+        use event_request as er;
+        use interface_type as it;
+        use method as m;
+        use object_reference as or;
+        use reference_type as rt;
+        use stack_frame as sf;
+        use string_reference as sr;
+        use thread_reference as tr;
+
+        let entries = [
+            Entry::of::<Version>(),
+            Entry::of::<ClassesBySignatures>(),
+            Entry::of::<AllClasses>(),
+            Entry::of::<AllClassesWithGeneric>(),
+            Entry::of::<AllThreads>(),
+            Entry::of::<TopLevelThreadGroups>(),
+            Entry::of::<Dispose>(),
+            Entry::of::<IdSizes>(),
+            Entry::of::<Suspend>(),
+            Entry::of::<Resume>(),
+            Entry::of::<CreateString>(),
+            Entry::of::<CapabilitiesNew>(),
+            Entry::of::<RedefineClasses>(),
+            Entry::of::<rt::Signature>(),
+            Entry::of::<rt::Fields>(),
+            Entry::of::<rt::Methods>(),
+            Entry::of::<rt::GetValues>(),
+            Entry::of::<rt::SourceFile>(),
+            Entry::of::<rt::Status>(),
+            Entry::of::<rt::Interfaces>(),
+            Entry::of::<rt::SignatureWithGeneric>(),
+            Entry::of::<rt::FieldsWithGeneric>(),
+            Entry::of::<rt::MethodsWithGeneric>(),
+            Entry::of::<ct::Superclass>(),
+            Entry::untagged::<ct::SetValues>(),
+            Entry::of::<ct::InvokeMethod>(),
+            Entry::of::<it::InvokeMethod>(),
+            Entry::of::<m::LineTable>(),
+            Entry::of::<m::VariableTable>(),
+            Entry::of::<or::ReferenceType>(),
+            Entry::of::<or::GetValues>(),
+            Entry::untagged::<or::SetValues>(),
+            Entry::of::<or::MonitorInfo>(),
+            Entry::of::<or::InvokeMethod>(),
+            Entry::of::<sr::Value>(),
+            Entry::of::<tr::Name>(),
+            Entry::of::<tr::Suspend>(),
+            Entry::of::<tr::Resume>(),
+            Entry::of::<tr::Status>(),
+            Entry::of::<tr::Frames>(),
+            Entry::of::<tr::FrameCount>(),
+            Entry::of::<tr::CurrentContendedMonitor>(),
+            Entry::of::<tr::SuspendCount>(),
+            Entry::of::<tr::OwnedMonitorsStackDepthInfo>(),
+            Entry::of::<ar::Length>(),
+            Entry::of::<ar::GetValues>(),
+            Entry::untagged::<ar::SetValues>(),
+            Entry::of::<er::Set>(),
+            Entry::of::<er::Clear>(),
+            Entry::of::<er::ClearAllBreakpoints>(),
+            Entry::of::<sf::GetValues>(),
+            Entry::of::<sf::SetValues>(),
+            Entry::of::<sf::ThisObject>(),
+        ];
+        let mut entries: HashMap<_, _> = entries
+            .into_iter()
+            .map(|entry| (entry.command, entry))
+            .collect();
+        // events are sent as a command the debugger doesn't reply to
+        let composite = Events::command_data();
+        entries.insert(
+            composite,
+            Entry {
+                command: composite,
+                request: Some(decode::<Events>),
+                reply: None,
+            },
+        );
+        Self { entries }
+    }
+
+    /// Gets what's known of a command
+    pub fn get(&self, command: CommandData) -> Option<&Entry> {
+        self.entries.get(&command)
+    }
+
+    /// Gets every command known
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.values()
+    }
+
+    /// Describes a packet, decoding it with the id sizes of a codec. Replies are only decoded if
+    /// the command they answer is given.
+    pub fn describe(
+        &self,
+        codec: &JdwpCodec,
+        packet: &AnyRawPacket,
+        command: Option<CommandData>,
+    ) -> Description {
+        let (id, kind, command, error_code, data) = match packet {
+            AnyRawPacket::Command(packet) => (
+                packet.header().id(),
+                PacketKind::Command,
+                Some(packet.header().command()),
+                None,
+                packet.data(),
+            ),
+            AnyRawPacket::Reply(packet) => {
+                let code = packet.header().error_code().code();
+                (
+                    packet.header().id(),
+                    PacketKind::Reply,
+                    command,
+                    (code != 0).then_some(code),
+                    packet.data(),
+                )
+            }
+        };
+        let entry = command.and_then(|command| self.get(command));
+        let decoded = match (entry, kind) {
+            // failed replies have no data to decode
+            (_, PacketKind::Reply) if error_code.is_some() => Ok(Node::Bytes(data.clone())),
+            (None, PacketKind::Reply) if command.is_none() => {
+                Err("the command replied to isn't known".to_string())
+            }
+            (None, _) => match command {
+                Some(command) if command.command_name().is_some() => {
+                    Err(format!("{command} isn't decoded by the registry"))
+                }
+                _ => Err("the command isn't known".to_string()),
+            },
+            (Some(entry), PacketKind::Command) => entry
+                .decode_request(codec, data.clone())
+                .map(|decoded| decoded.map_err(|e| e.to_string()))
+                .unwrap_or_else(|| Err("the command holds untagged values".to_string())),
+            (Some(entry), PacketKind::Reply) => entry
+                .decode_reply(codec, data.clone())
+                .map(|decoded| decoded.map_err(|e| e.to_string()))
+                .unwrap_or_else(|| Err("the command isn't replied to".to_string())),
+        };
+        let (data, undecoded) = match decoded {
+            Ok(node) => (node, None),
+            Err(reason) => (Node::Bytes(data.clone()), Some(reason)),
+        };
+        Description {
+            id,
+            kind,
+            command,
+            // commands of the spec are named even when they can't be decoded
+            name: command
+                .filter(|command| command.command_name().is_some())
+                .map(|command| command.to_string()),
+            error_code,
+            data,
+            undecoded,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{JdwpEncodable, JdwpEncoder};
+    use crate::events::Event;
+    use crate::id_sizes::IdSizes;
+    use crate::raw::packet::{ErrorCode, RawCommandPacket, RawReplyPacket};
+    use jdwp_types::{Int, ObjectId, SuspendPolicy, ThreadId};
+
+    fn codec() -> JdwpCodec {
+        JdwpCodec::new(IdSizes::new(8, 8, 8, 8))
+    }
+
+    fn command<C: JdwpCommand>(id: u32, command: &C) -> AnyRawPacket {
+        let codec = codec();
+        let mut encoder = JdwpEncoder::new(&codec);
+        encoder.put(command);
+        AnyRawPacket::Command(RawCommandPacket::new_command(
+            id,
+            C::command_data(),
+            encoder.into_bytes(),
+        ))
+    }
+
+    fn reply(id: u32, code: u16, values: &[&dyn JdwpEncodable]) -> AnyRawPacket {
+        let codec = codec();
+        let mut encoder = JdwpEncoder::new(&codec);
+        for value in values {
+            value.encode(&mut encoder);
+        }
+        AnyRawPacket::Reply(RawReplyPacket::new_reply(
+            id,
+            ErrorCode::new(code),
+            encoder.into_bytes(),
+        ))
+    }
+
+    #[test]
+    fn test_every_command_is_named() {
+        for entry in registry().entries() {
+            assert!(
+                entry.command().command_name().is_some(),
+                "{} has no name",
+                entry.command()
+            );
+        }
+        let version = registry().get(Version::command_data()).expect("no entry");
+        assert_eq!(version.name(), "VirtualMachine.Version");
+    }
+
+    #[test]
+    fn test_describe_command_and_reply() {
+        let codec = codec();
+        let packet = command(
+            3,
+            &ClassesBySignatures {
+                signature: "Ljava/lang/String;".to_string(),
+            },
+        );
+        let description = registry().describe(&codec, &packet, None);
+        assert_eq!(description.kind, PacketKind::Command);
+        assert_eq!(
+            description.name.as_deref(),
+            Some("VirtualMachine.ClassesBySignature")
+        );
+        assert_eq!(
+            description.data,
+            Node::Struct(vec![(
+                "signature",
+                Node::String("Ljava/lang/String;".to_string())
+            )])
+        );
+
+        let packet = reply(3, 0, &[&"a VM".to_string(), &(17 as Int), &(0 as Int)]);
+        let undecoded = registry().describe(&codec, &packet, None);
+        assert!(matches!(undecoded.data, Node::Bytes(_)));
+        assert!(undecoded.undecoded.is_some());
+        let packet = reply(
+            4,
+            0,
+            &[
+                &"a VM".to_string(),
+                &(17 as Int),
+                &(0 as Int),
+                &"17".to_string(),
+                &"OpenJDK".to_string(),
+            ],
+        );
+        let description = registry().describe(&codec, &packet, Some(Version::command_data()));
+        assert_eq!(description.undecoded, None);
+        assert_eq!(
+            description.to_json(),
+            r#"{"id":4,"kind":"reply","command_set":1,"command":1,"name":"VirtualMachine.Version","data":{"description":"a VM","major":17,"minor":0,"version":"17","name":"OpenJDK"}}"#
+        );
+
+        let packet = reply(5, 20, &[]);
+        let failed = registry().describe(&codec, &packet, Some(Version::command_data()));
+        assert_eq!(failed.error_code, Some(20));
+        assert_eq!(failed.undecoded, None);
+    }
+
+    #[test]
+    fn test_describe_untagged_command() {
+        let codec = codec();
+        let packet = command(
+            1,
+            &object_reference::SetValues {
+                object: ObjectId::new(1),
+                values: vec![],
+            },
+        );
+        let description = registry().describe(&codec, &packet, None);
+        assert_eq!(
+            description.name.as_deref(),
+            Some("ObjectReference.SetValues")
+        );
+        assert!(matches!(description.data, Node::Bytes(_)));
+        assert_eq!(
+            description.undecoded.as_deref(),
+            Some("the command holds untagged values")
+        );
+    }
+
+    #[test]
+    fn test_describe_commands_not_in_the_registry() {
+        let codec = codec();
+        let hold_events = AnyRawPacket::Command(RawCommandPacket::new_command(
+            1,
+            CommandData::new(1, 15),
+            Bytes::new(),
+        ));
+        let description = registry().describe(&codec, &hold_events, None);
+        assert_eq!(
+            description.name.as_deref(),
+            Some("VirtualMachine.HoldEvents")
+        );
+        assert_eq!(
+            description.undecoded.as_deref(),
+            Some("VirtualMachine.HoldEvents isn't decoded by the registry")
+        );
+
+        let unknown = AnyRawPacket::Command(RawCommandPacket::new_command(
+            2,
+            CommandData::new(11, 99),
+            Bytes::from_static(&[1, 2]),
+        ));
+        let description = registry().describe(&codec, &unknown, None);
+        assert_eq!(description.name, None);
+        assert_eq!(description.data, Node::Bytes(Bytes::from_static(&[1, 2])));
+        assert_eq!(
+            description.undecoded.as_deref(),
+            Some("the command isn't known")
+        );
+    }
+
+    #[test]
+    fn test_describe_events() {
+        let codec = codec();
+        let mut encoder = JdwpEncoder::new(&codec);
+        encoder.put(&Events {
+            policy: SuspendPolicy::None,
+            events: vec![Event::ThreadDeath {
+                request_id: 2,
+                thread: ThreadId::new(9),
+            }],
+        });
+        let packet = AnyRawPacket::Command(RawCommandPacket::new_command(
+            1,
+            Events::command_data(),
+            encoder.into_bytes(),
+        ));
+        let description = registry().describe(&codec, &packet, None);
+        assert_eq!(description.name.as_deref(), Some("Event.Composite"));
+        let Node::Struct(fields) = description.data else {
+            panic!("events weren't decoded: {description:?}");
+        };
+        assert_eq!(fields[0], ("policy", Node::Constant("None".to_string())));
+        assert!(matches!(&fields[1].1, Node::List(events) if events.len() == 1));
+    }
+}
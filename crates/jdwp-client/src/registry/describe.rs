@@ -0,0 +1,248 @@
+//! Describes decoded jdwp values as trees
+
+use crate::codec::UntaggedValue;
+use crate::commands::event_request::Modifier;
+use crate::events::Event;
+use bytes::Bytes;
+use jdwp_types::{
+    ArrayId, ArrayTypeId, ClassId, ClassLoaderId, ClassObjectId, ClassStatus, EventKind, FieldId,
+    FrameId, InterfaceId, Location, MethodId, ObjectId, ReferenceTypeId, StepDepth, StepSize,
+    StringId, SuspendPolicy, Tag, TaggedObjectId, ThreadGroupId, ThreadId, ThreadStatus, TypeTag,
+    Value,
+};
+use std::fmt::Write;
+
+/// A described value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Id(u64),
+    String(String),
+    /// A constant, or a value described by its debug representation
+    Constant(String),
+    /// Bytes that weren't decoded
+    Bytes(Bytes),
+    List(Vec<Node>),
+    /// Named fields, in the order they're sent
+    Struct(Vec<(&'static str, Node)>),
+}
+
+impl Node {
+    /// Prints this as JSON. Constants are printed as strings, and bytes as a hex string.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        self.write_json(&mut json);
+        json
+    }
+
+    fn write_json(&self, json: &mut String) {
+        match self {
+            Node::Bool(value) => write!(json, "{value}").unwrap(),
+            Node::Int(value) => write!(json, "{value}").unwrap(),
+            // JSON has no infinities or NaN
+            Node::Float(value) if value.is_finite() => write!(json, "{value}").unwrap(),
+            Node::Float(value) => write_json_string(json, &value.to_string()),
+            Node::Id(value) => write!(json, "{value}").unwrap(),
+            Node::String(value) | Node::Constant(value) => write_json_string(json, value),
+            Node::Bytes(bytes) => {
+                let hex = bytes.iter().fold(String::new(), |mut hex, byte| {
+                    write!(hex, "{byte:02x}").unwrap();
+                    hex
+                });
+                write_json_string(json, &hex)
+            }
+            Node::List(items) => {
+                json.push('[');
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        json.push(',');
+                    }
+                    item.write_json(json);
+                }
+                json.push(']');
+            }
+            Node::Struct(fields) => {
+                json.push('{');
+                for (index, (name, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        json.push(',');
+                    }
+                    write_json_string(json, name);
+                    json.push(':');
+                    value.write_json(json);
+                }
+                json.push('}');
+            }
+        }
+    }
+}
+
+fn write_json_string(json: &mut String, value: &str) {
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// Describable as a tree
+pub trait Describe {
+    fn describe(&self) -> Node;
+}
+
+/// Describes types by their debug representation
+macro_rules! describe_debug {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Describe for $ty {
+                fn describe(&self) -> Node {
+                    Node::Constant(format!("{self:?}"))
+                }
+            }
+        )*
+    };
+}
+
+describe_debug! {
+    Tag,
+    TypeTag,
+    EventKind,
+    SuspendPolicy,
+    StepSize,
+    StepDepth,
+    ThreadStatus,
+    ClassStatus,
+    Modifier,
+    Event,
+}
+
+macro_rules! describe_int {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Describe for $ty {
+                fn describe(&self) -> Node {
+                    Node::Int(i64::from(*self))
+                }
+            }
+        )*
+    };
+}
+
+describe_int!(u8, i32, i64);
+
+impl Describe for bool {
+    fn describe(&self) -> Node {
+        Node::Bool(*self)
+    }
+}
+
+impl Describe for String {
+    fn describe(&self) -> Node {
+        Node::String(self.clone())
+    }
+}
+
+impl<T: Describe> Describe for Vec<T> {
+    fn describe(&self) -> Node {
+        Node::List(self.iter().map(Describe::describe).collect())
+    }
+}
+
+macro_rules! describe_id {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Describe for $ty {
+                fn describe(&self) -> Node {
+                    Node::Id(self.get())
+                }
+            }
+        )*
+    };
+}
+
+describe_id! {
+    ObjectId, ThreadId, ThreadGroupId, StringId, ClassLoaderId, ClassObjectId, ArrayId,
+    ReferenceTypeId, ClassId, InterfaceId, ArrayTypeId, MethodId, FieldId, FrameId,
+}
+
+impl Describe for Location {
+    fn describe(&self) -> Node {
+        Node::Struct(vec![
+            ("tag", self.tag.describe()),
+            ("class", self.class.describe()),
+            ("method", self.method.describe()),
+            ("offset", Node::Int(self.offset as i64)),
+        ])
+    }
+}
+
+impl Describe for Value {
+    fn describe(&self) -> Node {
+        let (tag, value) = match self {
+            Value::Array(id) => (Tag::Array, Some(id.describe())),
+            Value::Byte(value) => (Tag::Byte, Some(value.describe())),
+            Value::Boolean(value) => (Tag::Boolean, Some(value.describe())),
+            Value::Char(value) => (Tag::Char, Some(Node::Int(i64::from(*value)))),
+            Value::Object(id) => (Tag::Object, Some(id.describe())),
+            Value::Float(value) => (Tag::Float, Some(Node::Float(f64::from(*value)))),
+            Value::Double(value) => (Tag::Double, Some(Node::Float(*value))),
+            Value::Int(value) => (Tag::Int, Some(value.describe())),
+            Value::Long(value) => (Tag::Long, Some(value.describe())),
+            Value::Short(value) => (Tag::Short, Some(Node::Int(i64::from(*value)))),
+            Value::Void => (Tag::Void, None),
+            Value::String(id) => (Tag::String, Some(id.describe())),
+            Value::Thread(id) => (Tag::Thread, Some(id.describe())),
+            Value::ThreadGroup(id) => (Tag::ThreadGroup, Some(id.describe())),
+            Value::ClassLoader(id) => (Tag::ClassLoader, Some(id.describe())),
+            Value::ClassObject(id) => (Tag::ClassObject, Some(id.describe())),
+        };
+        let mut fields = vec![("tag", tag.describe())];
+        fields.extend(value.map(|value| ("value", value)));
+        Node::Struct(fields)
+    }
+}
+
+impl Describe for TaggedObjectId {
+    fn describe(&self) -> Node {
+        Node::Struct(vec![
+            ("tag", self.tag().describe()),
+            ("value", Node::Id(self.id().get())),
+        ])
+    }
+}
+
+impl Describe for UntaggedValue {
+    fn describe(&self) -> Node {
+        self.0.describe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() {
+        let node = Node::Struct(vec![
+            ("name", Node::String("say \"hi\"\n".to_string())),
+            ("thread", ThreadId::new(7).describe()),
+            ("values", vec![Value::Int(3), Value::Void].describe()),
+            ("data", Node::Bytes(Bytes::from_static(&[0x0a, 0xff]))),
+            ("ratio", Node::Float(f64::NAN)),
+        ]);
+        assert_eq!(
+            node.to_json(),
+            r#"{"name":"say \"hi\"\n","thread":7,"values":[{"tag":"Int","value":3},{"tag":"Void"}],"data":"0aff","ratio":"NaN"}"#
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Parses `#[jdwp(...)]` attributes
+
+use syn::meta::ParseNestedMeta;
+use syn::{Attribute, Expr, Ident, Result, Type};
+
+/// Parses every `#[jdwp(...)]` attribute with a parser for their items
+fn parse_jdwp(
+    attrs: &[Attribute],
+    mut item: impl FnMut(&ParseNestedMeta) -> Result<bool>,
+) -> Result<()> {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("jdwp")) {
+        attr.parse_nested_meta(|meta| {
+            if item(&meta)? {
+                Ok(())
+            } else {
+                Err(meta.error("unknown jdwp attribute"))
+            }
+        })?;
+    }
+    Ok(())
+}
+
+/// How a field is sent
+#[derive(Default)]
+pub(crate) struct FieldAttrs {
+    /// The type the field is sent as
+    pub(crate) try_from: Option<Type>,
+    /// The integer type the length of a vector is sent as
+    pub(crate) len: Option<Type>,
+    /// The condition an optional field is sent under
+    pub(crate) when: Option<Expr>,
+}
+
+impl FieldAttrs {
+    pub(crate) fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut parsed = FieldAttrs::default();
+        parse_jdwp(attrs, |meta| {
+            if meta.path.is_ident("try_from") {
+                parsed.try_from = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("len") {
+                parsed.len = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("when") {
+                parsed.when = Some(meta.value()?.parse()?);
+            } else {
+                return Ok(false);
+            }
+            if parsed.try_from.is_some() && parsed.len.is_some() {
+                return Err(meta.error("`try_from` and `len` can't both be used on a field"));
+            }
+            Ok(true)
+        })?;
+        Ok(parsed)
+    }
+}
+
+/// The tag of an enum
+pub(crate) struct EnumAttrs {
+    /// The type of the tag
+    pub(crate) tag: Type,
+    /// The variant of `DecodeJdwpDataError` returned for unknown tags
+    pub(crate) unknown: Ident,
+}
+
+impl EnumAttrs {
+    pub(crate) fn parse(ident: &Ident, attrs: &[Attribute]) -> Result<Self> {
+        let mut tag = None;
+        let mut unknown = None;
+        parse_jdwp(attrs, |meta| {
+            if meta.path.is_ident("tag") {
+                tag = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("unknown") {
+                unknown = Some(meta.value()?.parse()?);
+            } else {
+                return Ok(false);
+            }
+            Ok(true)
+        })?;
+        match (tag, unknown) {
+            (Some(tag), Some(unknown)) => Ok(Self { tag, unknown }),
+            _ => Err(syn::Error::new_spanned(
+                ident,
+                "enums need a `#[jdwp(tag = Type, unknown = Error)]` attribute",
+            )),
+        }
+    }
+}
+
+/// The tag of an enum's variant
+pub(crate) enum VariantAttrs {
+    Tag(Expr),
+    /// Never sent
+    Skip,
+}
+
+impl VariantAttrs {
+    pub(crate) fn parse(ident: &Ident, attrs: &[Attribute]) -> Result<Self> {
+        let mut parsed = None;
+        parse_jdwp(attrs, |meta| {
+            if meta.path.is_ident("tag") {
+                parsed = Some(VariantAttrs::Tag(meta.value()?.parse()?));
+            } else if meta.path.is_ident("skip") {
+                parsed = Some(VariantAttrs::Skip);
+            } else {
+                return Ok(false);
+            }
+            Ok(true)
+        })?;
+        parsed.ok_or_else(|| {
+            syn::Error::new_spanned(
+                ident,
+                "variants need a `#[jdwp(tag = ...)]` or `#[jdwp(skip)]` attribute",
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn error<T>(result: Result<T>) -> String {
+        match result {
+            Ok(_) => panic!("parsed invalid attributes"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn field_attrs() {
+        let attrs: Vec<Attribute> = vec![
+            parse_quote!(#[doc = "ignored"]),
+            parse_quote!(#[jdwp(len = Int)]),
+            parse_quote!(#[jdwp(when = *named)]),
+        ];
+        let parsed = FieldAttrs::parse(&attrs).unwrap();
+        assert!(parsed.try_from.is_none());
+        assert!(parsed.len.is_some());
+        assert!(parsed.when.is_some());
+
+        let attrs = [parse_quote!(#[jdwp(try_from = Byte, len = Int)])];
+        assert!(error(FieldAttrs::parse(&attrs)).contains("can't both be used"));
+        let attrs = [parse_quote!(#[jdwp(size = Int)])];
+        assert_eq!(error(FieldAttrs::parse(&attrs)), "unknown jdwp attribute");
+        let attrs = [parse_quote!(#[jdwp(when)])];
+        assert!(FieldAttrs::parse(&attrs).is_err());
+    }
+
+    #[test]
+    fn enum_attrs() {
+        let ident: Ident = parse_quote!(Kind);
+        let attrs = [parse_quote!(#[jdwp(tag = Byte, unknown = UnknownModifierKind)])];
+        let parsed = EnumAttrs::parse(&ident, &attrs).unwrap();
+        assert_eq!(parsed.unknown, "UnknownModifierKind");
+
+        let attrs = [parse_quote!(#[jdwp(tag = Byte)])];
+        assert!(error(EnumAttrs::parse(&ident, &attrs)).starts_with("enums need"));
+        assert!(error(EnumAttrs::parse(&ident, &[])).starts_with("enums need"));
+    }
+
+    #[test]
+    fn variant_attrs() {
+        let ident: Ident = parse_quote!(Variant);
+        let attrs = [parse_quote!(#[jdwp(tag = 1)])];
+        assert!(matches!(
+            VariantAttrs::parse(&ident, &attrs),
+            Ok(VariantAttrs::Tag(_))
+        ));
+        let attrs = [parse_quote!(#[jdwp(skip)])];
+        assert!(matches!(
+            VariantAttrs::parse(&ident, &attrs),
+            Ok(VariantAttrs::Skip)
+        ));
+
+        assert!(error(VariantAttrs::parse(&ident, &[])).starts_with("variants need"));
+        let attrs = [parse_quote!(#[jdwp(tag = 1, unknown = Error)])];
+        assert_eq!(
+            error(VariantAttrs::parse(&ident, &attrs)),
+            "unknown jdwp attribute"
+        );
+    }
+}
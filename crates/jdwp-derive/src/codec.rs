@@ -0,0 +1,314 @@
+//! Derives `JdwpEncodable` and `JdwpDecodable`
+
+use crate::attr::{EnumAttrs, FieldAttrs, VariantAttrs};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Ident, Result, Type};
+
+/// A field, and the name it's bound to while encoding or decoding
+struct Field<'a> {
+    binding: Ident,
+    ty: &'a Type,
+    attrs: FieldAttrs,
+}
+
+fn fields(fields: &Fields) -> Result<Vec<Field<'_>>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let binding = match &field.ident {
+                Some(ident) => ident.clone(),
+                None => format_ident!("__{index}"),
+            };
+            Ok(Field {
+                binding,
+                ty: &field.ty,
+                attrs: FieldAttrs::parse(&field.attrs)?,
+            })
+        })
+        .collect()
+}
+
+/// The pattern binding every field of a struct or variant, or the expression creating it from
+/// its bindings
+fn shape(path: TokenStream, kind: &Fields, fields: &[Field]) -> TokenStream {
+    // named fields are bound to their own names
+    let bindings = fields.iter().map(|field| &field.binding);
+    match kind {
+        Fields::Named(_) => quote!(#path { #(#bindings),* }),
+        Fields::Unnamed(_) => quote!(#path(#(#bindings),*)),
+        Fields::Unit => path,
+    }
+}
+
+/// Encodes a field from its binding, a reference to its value
+fn encode_field(field: &Field) -> TokenStream {
+    let put = |value: TokenStream| match (&field.attrs.try_from, &field.attrs.len) {
+        (Some(repr), _) => quote! {
+            __encoder.put(&<#repr>::from(::core::clone::Clone::clone(#value)));
+        },
+        (None, Some(len)) => quote! {
+            __encoder.put(&((#value).len() as #len));
+            for __item in #value {
+                __encoder.put(__item);
+            }
+        },
+        (None, None) => quote!(__encoder.put(#value);),
+    };
+    let binding = &field.binding;
+    match &field.attrs.when {
+        // the field is sent exactly when it would be decoded
+        Some(condition) => {
+            let put = put(quote!(__value));
+            let message = format!("`{binding}` must be set when `{}`", quote!(#condition));
+            quote! {
+                if #condition {
+                    match #binding {
+                        ::core::option::Option::Some(__value) => {
+                            #put
+                        }
+                        ::core::option::Option::None => ::core::panic!("{}", #message),
+                    }
+                }
+            }
+        }
+        None => put(quote!(#binding)),
+    }
+}
+
+/// Decodes a field into its binding, given the fields decoded before it
+fn decode_field(field: &Field, before: &[Field]) -> TokenStream {
+    let value = match (&field.attrs.try_from, &field.attrs.len) {
+        (Some(repr), _) => quote! {
+            ::core::convert::TryInto::try_into(__decoder.get::<#repr>()?)?
+        },
+        (None, Some(len)) => quote! {
+            {
+                let __len = i64::from(__decoder.get::<#len>()?);
+                if __len < 0 {
+                    return ::core::result::Result::Err(
+                        ::jdwp_client::codec::DecodeJdwpDataError::UnexpectedNegativeInt(
+                            __len as i32,
+                        ),
+                    );
+                }
+                let mut __items = ::std::vec::Vec::with_capacity(__len as usize);
+                for _ in 0..__len {
+                    __items.push(__decoder.get()?);
+                }
+                __items
+            }
+        },
+        (None, None) => quote!(__decoder.get()?),
+    };
+    let value = match &field.attrs.when {
+        Some(condition) => {
+            // the condition sees the fields by reference, as it does when encoding
+            let before = before.iter().map(|field| &field.binding);
+            quote! {
+                if { #(let #before = &#before;)* #condition } {
+                    ::core::option::Option::Some(#value)
+                } else {
+                    ::core::option::Option::None
+                }
+            }
+        }
+        None => value,
+    };
+    let binding = &field.binding;
+    let ty = field.ty;
+    quote!(let #binding: #ty = #value;)
+}
+
+/// Decodes every field into its binding
+fn decode_fields(fields: &[Field]) -> TokenStream {
+    let gets = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| decode_field(field, &fields[..index]));
+    quote!(#(#gets)*)
+}
+
+pub(crate) fn encode(input: &DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let fields = fields(&data.fields)?;
+            let pattern = shape(quote!(Self), &data.fields, &fields);
+            let puts = fields.iter().map(encode_field);
+            quote! {
+                let #pattern = self;
+                #(#puts)*
+            }
+        }
+        Data::Enum(data) => {
+            let attrs = EnumAttrs::parse(ident, &input.attrs)?;
+            let tag_ty = &attrs.tag;
+            let arms = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let name = &variant.ident;
+                    let fields = fields(&variant.fields)?;
+                    let pattern = shape(quote!(Self::#name), &variant.fields, &fields);
+                    Ok(match VariantAttrs::parse(name, &variant.attrs)? {
+                        VariantAttrs::Tag(tag) => {
+                            let puts = fields.iter().map(encode_field);
+                            quote! {
+                                #pattern => {
+                                    __encoder.put::<#tag_ty>(&(#tag));
+                                    #(#puts)*
+                                }
+                            }
+                        }
+                        VariantAttrs::Skip => quote!(#pattern => {}),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "unions can't be encoded",
+            ))
+        }
+    };
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::jdwp_client::codec::JdwpEncodable for #ident #ty_generics
+            #where_clause
+        {
+            #[allow(unused_variables)]
+            fn encode(&self, __encoder: &mut ::jdwp_client::codec::JdwpEncoder) {
+                #body
+            }
+        }
+    })
+}
+
+pub(crate) fn decode(input: &DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let fields = fields(&data.fields)?;
+            let gets = decode_fields(&fields);
+            let value = shape(quote!(Self), &data.fields, &fields);
+            quote! {
+                #gets
+                ::core::result::Result::Ok(#value)
+            }
+        }
+        Data::Enum(data) => {
+            let attrs = EnumAttrs::parse(ident, &input.attrs)?;
+            let tag_ty = &attrs.tag;
+            let unknown = &attrs.unknown;
+            let mut arms = vec![];
+            for variant in &data.variants {
+                let name = &variant.ident;
+                let fields = fields(&variant.fields)?;
+                if let VariantAttrs::Tag(tag) = VariantAttrs::parse(name, &variant.attrs)? {
+                    let gets = decode_fields(&fields);
+                    let value = shape(quote!(Self::#name), &variant.fields, &fields);
+                    arms.push(quote! {
+                        #tag => {
+                            #gets
+                            ::core::result::Result::Ok(#value)
+                        }
+                    });
+                }
+            }
+            quote! {
+                let __tag = __decoder.get::<#tag_ty>()?;
+                match __tag {
+                    #(#arms)*
+                    _ => ::core::result::Result::Err(
+                        ::jdwp_client::codec::DecodeJdwpDataError::#unknown(__tag),
+                    ),
+                }
+            }
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "unions can't be decoded",
+            ))
+        }
+    };
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::jdwp_client::codec::JdwpDecodable for #ident #ty_generics
+            #where_clause
+        {
+            type Err = ::jdwp_client::codec::DecodeJdwpDataError;
+
+            #[allow(unused_variables, unreachable_patterns)]
+            fn decode(
+                __decoder: &mut ::jdwp_client::codec::JdwpDecoder,
+            ) -> ::core::result::Result<Self, Self::Err> {
+                #body
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn rejects_unions() {
+        let input: DeriveInput = parse_quote!(
+            union Either {
+                a: u8,
+                b: u16,
+            }
+        );
+        assert_eq!(
+            encode(&input).unwrap_err().to_string(),
+            "unions can't be encoded"
+        );
+        assert_eq!(
+            decode(&input).unwrap_err().to_string(),
+            "unions can't be decoded"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_enums() {
+        let input: DeriveInput = parse_quote!(
+            #[jdwp(tag = Byte, unknown = UnknownModifierKind)]
+            enum Kind {
+                #[jdwp(tag = 1)]
+                Tagged,
+                Untagged,
+            }
+        );
+        assert!(encode(&input).is_err());
+        assert!(decode(&input).is_err());
+    }
+
+    #[test]
+    fn encodes_conditional_fields_under_their_condition() {
+        let input: DeriveInput = parse_quote!(
+            struct Named {
+                named: bool,
+                #[jdwp(when = *named)]
+                name: Option<String>,
+            }
+        );
+        let encoded = encode(&input).unwrap().to_string();
+        assert!(encoded.contains("if * named"));
+        assert!(encoded.contains("`name` must be set when `* named`"));
+        let decoded = decode(&input).unwrap().to_string();
+        assert!(decoded.contains("let named = & named ; * named"));
+    }
+}
@@ -0,0 +1,94 @@
+//! Implements `JdwpCommand` for the structs given to `#[jdwp_command(...)]`
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::meta::ParseNestedMeta;
+use syn::{ItemStruct, LitInt, Result, Type};
+
+#[derive(Default)]
+pub(crate) struct CommandArgs {
+    set: Option<LitInt>,
+    cmd: Option<LitInt>,
+    reply: Option<Type>,
+}
+
+impl CommandArgs {
+    pub(crate) fn parse(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        if meta.path.is_ident("set") {
+            self.set = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("cmd") {
+            self.cmd = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("reply") {
+            self.reply = Some(meta.value()?.parse()?);
+        } else {
+            return Err(meta.error("expected `set`, `cmd` or `reply`"));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn expand(self, item: &ItemStruct) -> Result<TokenStream> {
+        let (Some(set), Some(cmd)) = (self.set, self.cmd) else {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "commands need both a `set` and a `cmd`",
+            ));
+        };
+        let ident = &item.ident;
+        let reply = match self.reply {
+            Some(reply) => quote!(#reply),
+            None => {
+                let reply = format_ident!("{ident}Reply");
+                quote!(#reply)
+            }
+        };
+        let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+        Ok(quote! {
+            #item
+
+            impl #impl_generics ::jdwp_client::packet::JdwpCommand for #ident #ty_generics
+                #where_clause
+            {
+                type Reply = #reply;
+
+                fn command_data() -> ::jdwp_client::packet::CommandData {
+                    ::jdwp_client::packet::CommandData::new(#set, #cmd)
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse::Parser;
+    use syn::parse_quote;
+
+    fn expand(args: TokenStream) -> Result<TokenStream> {
+        let mut command = CommandArgs::default();
+        syn::meta::parser(|meta| command.parse(meta)).parse2(args)?;
+        command.expand(&parse_quote!(
+            struct Version;
+        ))
+    }
+
+    #[test]
+    fn expands_command() {
+        let expanded = expand(quote!(set = 1, cmd = 1)).unwrap().to_string();
+        assert!(expanded.contains("type Reply = VersionReply"));
+        assert!(expanded.contains("CommandData :: new (1 , 1)"));
+        let expanded = expand(quote!(set = 1, cmd = 1, reply = Other))
+            .unwrap()
+            .to_string();
+        assert!(expanded.contains("type Reply = Other"));
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        let error = expand(quote!(set = 1)).unwrap_err();
+        assert_eq!(error.to_string(), "commands need both a `set` and a `cmd`");
+        let error = expand(quote!(set = 1, cmd = 1, id = 2)).unwrap_err();
+        assert_eq!(error.to_string(), "expected `set`, `cmd` or `reply`");
+        assert!(expand(quote!(set = "1", cmd = 1)).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+//! Derives `Describe`
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Member, Result};
+
+pub(crate) fn describe(input: &DeriveInput) -> Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "only structs can be described by their fields",
+        ));
+    };
+    let fields = data.fields.iter().enumerate().map(|(index, field)| {
+        let (name, member) = match &field.ident {
+            Some(ident) => (ident.to_string(), Member::Named(ident.clone())),
+            None => (index.to_string(), Member::Unnamed(index.into())),
+        };
+        quote! {
+            (#name, ::jdwp_client::registry::Describe::describe(&self.#member))
+        }
+    });
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::jdwp_client::registry::Describe for #ident #ty_generics
+            #where_clause
+        {
+            fn describe(&self) -> ::jdwp_client::registry::Node {
+                ::jdwp_client::registry::Node::Struct(::std::vec![#(#fields),*])
+            }
+        }
+    })
+}
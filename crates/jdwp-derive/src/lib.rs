@@ -0,0 +1,73 @@
+//! # `jdwp-derive`
+//! Derive macros for the encoding and decoding traits of `jdwp-client`, and an attribute for
+//! declaring jdwp commands. These are re-exported by `jdwp-client`, and the generated code refers
+//! to it as `::jdwp_client`.
+//!
+//! Fields are encoded and decoded in the order they're declared. Their encoding can be changed
+//! with `#[jdwp(...)]` attributes:
+//! - `#[jdwp(try_from = Byte)]` sends the field as another type, converting it with [TryFrom]
+//!   when decoded and [From] when encoded
+//! - `#[jdwp(len = Int)]` sends a vector prefixed by its length, as the given integer type
+//! - `#[jdwp(when = expr)]` only sends an [Option] field when the expression, which may refer to
+//!   references to the fields before it, is true. Encoding panics if the field is [None] while
+//!   the expression is true, and leaves the field out while it's false, even if it's set.
+//!
+//! Enums are sent as a tag followed by the fields of their variant. The enum is given the type of
+//! its tag and the variant of `DecodeJdwpDataError` returned for unknown tags with
+//! `#[jdwp(tag = Byte, unknown = UnknownModifierKind)]`, and each variant its tag with
+//! `#[jdwp(tag = 1)]`. Variants marked `#[jdwp(skip)]` encode to nothing, and are never decoded.
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput, ItemStruct};
+
+mod attr;
+mod codec;
+mod command;
+mod describe;
+
+/// Derives `JdwpEncodable`
+#[proc_macro_derive(JdwpEncode, attributes(jdwp))]
+pub fn derive_encode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    codec::encode(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `JdwpDecodable`, failing with a `DecodeJdwpDataError`
+#[proc_macro_derive(JdwpDecode, attributes(jdwp))]
+pub fn derive_decode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    codec::decode(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `Describe` for a struct, describing it by its fields
+#[proc_macro_derive(Describe)]
+pub fn derive_describe(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    describe::describe(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `JdwpCommand` for a struct, given its command set and command. The reply is the
+/// struct's name suffixed with `Reply`, unless given with `reply = Type`.
+///
+/// ```ignore
+/// #[jdwp_command(set = 1, cmd = 1)]
+/// #[derive(Debug, JdwpEncode, JdwpDecode, Describe)]
+/// pub struct Version;
+/// ```
+#[proc_macro_attribute]
+pub fn jdwp_command(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut command = command::CommandArgs::default();
+    let parser = syn::meta::parser(|meta| command.parse(meta));
+    parse_macro_input!(args with parser);
+    let item = parse_macro_input!(item as ItemStruct);
+    command
+        .expand(&item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
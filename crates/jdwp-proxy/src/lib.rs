@@ -0,0 +1,479 @@
+//! # `jdwp-proxy`
+//!
+//! A proxy between debuggers and a java virtual machine, which records a human-readable
+//! [transcript] of the packets passing through it.
+//!
+//! The proxy performs the handshake with each debugger that connects to it, and connects to the
+//! target VM when the first one does. Several debuggers can share the target VM: the ids of their
+//! commands are rewritten so they can't collide, and each composite event is sent to the debuggers
+//! that requested its events. Events nobody requested, such as `VMStart`, are sent to every
+//! debugger.
+//!
+//! Debuggers sharing a VM still share its threads, so a thread one of them resumes is resumed for
+//! all of them.
+
+use bytes::{BufMut, BytesMut};
+use futures::{SinkExt, StreamExt};
+use jdwp_client::codec::{JdwpCodec, JdwpDecoder};
+use jdwp_client::commands::{self, event_request, Dispose, IdSizesReply};
+use jdwp_client::connect::{accept_handshake, handshake};
+use jdwp_client::events::{to_events, Events};
+use jdwp_client::id_sizes::IdSizes;
+use jdwp_client::packet::JdwpCommand;
+use jdwp_client::raw::codec::RawCodec;
+use jdwp_client::raw::packet::{
+    AnyRawPacket, CommandData, ErrorCode, RawCommandPacket, RawReplyPacket,
+};
+use jdwp_types::Int;
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+use std::net::SocketAddr;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio_util::codec::{FramedRead, FramedWrite};
+use tracing::{debug, info, warn};
+use transcript::{describe_command, describe_reply, Direction};
+
+pub mod transcript;
+
+type PacketSink = FramedWrite<OwnedWriteHalf, RawCodec>;
+
+/// What the connections of the proxy send to it
+enum Message {
+    /// A debugger finished its handshake
+    Connected(usize, PacketSink),
+    /// A debugger sent a packet
+    FromDebugger(usize, AnyRawPacket),
+    /// A debugger closed its connection
+    Disconnected(usize),
+    /// The target VM sent a packet over the connection with the given generation
+    FromVm(u64, AnyRawPacket),
+    /// The target VM closed the connection with the given generation
+    VmClosed(u64),
+}
+
+/// A command sent to the target VM, waiting for its reply
+struct Pending {
+    /// The debugger that sent it and its id there, or `None` for commands sent by the proxy
+    debugger: Option<(usize, u32)>,
+    command: CommandData,
+    /// The event kind of an `EventRequest.Set` command
+    event_kind: Option<u8>,
+}
+
+/// The connection to the target VM, shared by every connected debugger
+struct VmConnection {
+    generation: u64,
+    sink: PacketSink,
+    /// The codec for the id sizes of the target VM, once a debugger asks for them
+    codec: Option<JdwpCodec>,
+    next_id: u32,
+    pending: HashMap<u32, Pending>,
+    /// The debugger and event kind of each event request, by its id
+    requests: HashMap<Int, (usize, u8)>,
+}
+
+/// A proxy between debuggers and a java virtual machine
+#[derive(Debug)]
+pub struct Proxy {
+    listener: TcpListener,
+    target: String,
+    transcript: Option<UnboundedSender<String>>,
+}
+
+impl Proxy {
+    /// Listens for debuggers at an address, proxying them to the target VM at another
+    pub async fn bind(address: impl ToSocketAddrs, target: impl Into<String>) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(address).await?,
+            target: target.into(),
+            transcript: None,
+        })
+    }
+
+    /// Gets the address debuggers connect to
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Sends a line describing each packet passing through the proxy
+    pub fn transcript(mut self, lines: UnboundedSender<String>) -> Self {
+        self.transcript = Some(lines);
+        self
+    }
+
+    /// Proxies debuggers until accepting a connection fails
+    pub async fn run(self) -> io::Result<()> {
+        let Proxy {
+            listener,
+            target,
+            transcript,
+        } = self;
+        let (messages, mut received) = mpsc::unbounded_channel();
+        let mut router = Router {
+            target,
+            transcript,
+            messages: messages.clone(),
+            debuggers: HashMap::new(),
+            vm: None,
+            generation: 0,
+        };
+        let mut next_debugger = 1;
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let (stream, address) = accepted?;
+                    info!("debugger {next_debugger} connected from {address}");
+                    tokio::spawn(accept(next_debugger, stream, messages.clone()));
+                    next_debugger += 1;
+                }
+                Some(message) = received.recv() => router.handle(message).await,
+            }
+        }
+    }
+}
+
+/// Performs the handshake with a debugger, then forwards its packets to the router
+async fn accept(debugger: usize, stream: TcpStream, messages: UnboundedSender<Message>) {
+    let (mut input, mut output) = stream.into_split();
+    if let Err(e) = accept_handshake(&mut input, &mut output).await {
+        warn!("handshake with debugger {debugger} failed: {e}");
+        return;
+    }
+    let _ = messages.send(Message::Connected(
+        debugger,
+        FramedWrite::new(output, RawCodec),
+    ));
+    forward(input, messages, move |packet| match packet {
+        Some(packet) => Message::FromDebugger(debugger, packet),
+        None => Message::Disconnected(debugger),
+    })
+    .await;
+}
+
+/// Forwards the packets read from a connection to the router, until it's closed
+async fn forward(
+    input: OwnedReadHalf,
+    messages: UnboundedSender<Message>,
+    message: impl Fn(Option<AnyRawPacket>) -> Message,
+) {
+    let mut packets = FramedRead::new(input, RawCodec);
+    while let Some(packet) = packets.next().await {
+        match packet {
+            Ok(packet) => {
+                if messages.send(message(Some(packet))).is_err() {
+                    return;
+                }
+            }
+            Err(e) => {
+                warn!("could not read a packet: {e}");
+                break;
+            }
+        }
+    }
+    let _ = messages.send(message(None));
+}
+
+/// Routes packets between the debuggers and the target VM
+struct Router {
+    target: String,
+    transcript: Option<UnboundedSender<String>>,
+    messages: UnboundedSender<Message>,
+    debuggers: HashMap<usize, PacketSink>,
+    vm: Option<VmConnection>,
+    generation: u64,
+}
+
+impl Router {
+    async fn handle(&mut self, message: Message) {
+        match message {
+            Message::Connected(debugger, sink) => {
+                self.debuggers.insert(debugger, sink);
+                if self.vm.is_none() {
+                    if let Err(e) = self.connect().await {
+                        warn!("could not connect to {}: {e}", self.target);
+                        self.debuggers.clear();
+                    }
+                }
+            }
+            Message::FromDebugger(debugger, AnyRawPacket::Command(command)) => {
+                self.command(debugger, command).await;
+            }
+            Message::FromDebugger(debugger, AnyRawPacket::Reply(_)) => {
+                warn!("debugger {debugger} sent a reply, but the target VM sends no commands to reply to");
+            }
+            Message::Disconnected(debugger) => self.disconnected(debugger).await,
+            Message::FromVm(generation, packet) if self.is_current(generation) => match packet {
+                AnyRawPacket::Command(command) => self.event(command).await,
+                AnyRawPacket::Reply(reply) => self.reply(reply).await,
+            },
+            Message::VmClosed(generation) if self.is_current(generation) => {
+                info!("the target VM closed the connection");
+                self.vm = None;
+                // dropping the sinks closes the connections of the debuggers
+                self.debuggers.clear();
+            }
+            Message::FromVm(..) | Message::VmClosed(_) => {}
+        }
+    }
+
+    fn is_current(&self, generation: u64) -> bool {
+        self.vm
+            .as_ref()
+            .is_some_and(|vm| vm.generation == generation)
+    }
+
+    fn record(&self, line: String) {
+        debug!("{line}");
+        if let Some(transcript) = &self.transcript {
+            let _ = transcript.send(line);
+        }
+    }
+
+    /// Connects to the target VM
+    async fn connect(&mut self) -> io::Result<()> {
+        let stream = TcpStream::connect(&self.target).await?;
+        let (mut input, mut output) = stream.into_split();
+        handshake(&mut input, &mut output).await?;
+        info!("connected to {}", self.target);
+        self.generation += 1;
+        let generation = self.generation;
+        tokio::spawn(forward(
+            input,
+            self.messages.clone(),
+            move |packet| match packet {
+                Some(packet) => Message::FromVm(generation, packet),
+                None => Message::VmClosed(generation),
+            },
+        ));
+        self.vm = Some(VmConnection {
+            generation,
+            sink: FramedWrite::new(output, RawCodec),
+            codec: None,
+            next_id: 1,
+            pending: HashMap::new(),
+            requests: HashMap::new(),
+        });
+        Ok(())
+    }
+
+    /// Sends a packet to a debugger, forgetting the debugger if its connection fails
+    async fn send_to_debugger(&mut self, debugger: usize, packet: AnyRawPacket) {
+        let Some(sink) = self.debuggers.get_mut(&debugger) else {
+            return;
+        };
+        let sent = match packet {
+            AnyRawPacket::Command(command) => sink.send(command).await,
+            AnyRawPacket::Reply(reply) => sink.send(reply).await,
+        };
+        if let Err(e) = sent {
+            warn!("could not send to debugger {debugger}: {e}");
+            self.debuggers.remove(&debugger);
+        }
+    }
+
+    /// Sends a command to the target VM under an id of the proxy's own
+    async fn send_to_vm(
+        &mut self,
+        debugger: Option<(usize, u32)>,
+        command: CommandData,
+        data: bytes::Bytes,
+    ) {
+        let Some(vm) = &mut self.vm else {
+            return;
+        };
+        let id = vm.next_id;
+        vm.next_id = vm.next_id.wrapping_add(1);
+        let event_kind = (command == event_request::Set::command_data())
+            .then(|| data.first().copied())
+            .flatten();
+        vm.pending.insert(
+            id,
+            Pending {
+                debugger,
+                command,
+                event_kind,
+            },
+        );
+        if let Err(e) = vm
+            .sink
+            .send(RawCommandPacket::new_command(id, command, data))
+            .await
+        {
+            warn!("could not send to the target VM: {e}");
+        }
+    }
+
+    /// Forwards a command from a debugger to the target VM
+    async fn command(&mut self, debugger: usize, packet: RawCommandPacket) {
+        self.record(describe_command(
+            Direction::ToVm(debugger),
+            &packet,
+            self.vm.as_ref().and_then(|vm| vm.codec.as_ref()),
+        ));
+        let command = packet.header().command();
+        let id = packet.header().id();
+        if command == Dispose::command_data() && self.debuggers.len() > 1 {
+            // the other debuggers are still using the target VM
+            let reply = RawReplyPacket::new_reply(id, ErrorCode::new(0), Default::default());
+            self.record(describe_reply(
+                Direction::ToDebugger(debugger),
+                &reply,
+                command,
+                self.vm.as_ref().and_then(|vm| vm.codec.as_ref()),
+            ));
+            self.send_to_debugger(debugger, AnyRawPacket::Reply(reply))
+                .await;
+            return;
+        }
+        if command == event_request::Clear::command_data() {
+            if let (Some(vm), Some(request)) =
+                (&mut self.vm, packet.data().get(1..).and_then(request_id))
+            {
+                vm.requests.remove(&request);
+            }
+        }
+        self.send_to_vm(Some((debugger, id)), command, packet.data().clone())
+            .await;
+    }
+
+    /// Forwards a reply from the target VM to the debugger that sent its command
+    async fn reply(&mut self, reply: RawReplyPacket) {
+        let Some(vm) = &mut self.vm else {
+            return;
+        };
+        let Some(pending) = vm.pending.remove(&reply.header().id()) else {
+            warn!(
+                "the target VM replied to unknown command {}",
+                reply.header().id()
+            );
+            return;
+        };
+        let succeeded = reply.header().error_code().code() == 0;
+        if succeeded && pending.command == commands::IdSizes::command_data() {
+            let codec = JdwpCodec::new(IdSizes::new(8, 8, 8, 8));
+            if let Ok(sizes) = JdwpDecoder::new(&codec, reply.data().clone()).get::<IdSizesReply>()
+            {
+                vm.codec = Some(JdwpCodec::new(IdSizes::new(
+                    sizes.object_id_size as usize,
+                    sizes.method_id_size as usize,
+                    sizes.field_id_size as usize,
+                    sizes.frame_id_size as usize,
+                )));
+            }
+        }
+        let Some((debugger, id)) = pending.debugger else {
+            return;
+        };
+        if succeeded {
+            if let (Some(kind), Some(request)) = (pending.event_kind, request_id(reply.data())) {
+                vm.requests.insert(request, (debugger, kind));
+            }
+        }
+        let reply =
+            RawReplyPacket::new_reply(id, reply.header().error_code(), reply.data().clone());
+        let codec = vm
+            .codec
+            .as_ref()
+            .map(|codec| JdwpCodec::new(codec.id_sizes()));
+        self.record(describe_reply(
+            Direction::ToDebugger(debugger),
+            &reply,
+            pending.command,
+            codec.as_ref(),
+        ));
+        self.send_to_debugger(debugger, AnyRawPacket::Reply(reply))
+            .await;
+    }
+
+    /// Sends a composite event to the debuggers that requested its events
+    async fn event(&mut self, packet: RawCommandPacket) {
+        let Some(vm) = &self.vm else {
+            return;
+        };
+        let mut owners = BTreeSet::new();
+        let mut everyone = true;
+        if packet.header().command() == Events::command_data() {
+            if let Some(events) = vm
+                .codec
+                .as_ref()
+                .and_then(|codec| to_events(packet.clone(), codec).ok())
+            {
+                everyone = false;
+                for event in &events.events {
+                    match event
+                        .request_id()
+                        .and_then(|request| vm.requests.get(&request))
+                    {
+                        Some((debugger, _)) => {
+                            owners.insert(*debugger);
+                        }
+                        None => everyone = true,
+                    }
+                }
+            }
+        }
+        let codec = vm
+            .codec
+            .as_ref()
+            .map(|codec| JdwpCodec::new(codec.id_sizes()));
+        let targets = if everyone {
+            self.record(describe_command(
+                Direction::ToDebuggers,
+                &packet,
+                codec.as_ref(),
+            ));
+            self.debuggers.keys().copied().collect()
+        } else {
+            for debugger in &owners {
+                self.record(describe_command(
+                    Direction::ToDebugger(*debugger),
+                    &packet,
+                    codec.as_ref(),
+                ));
+            }
+            owners
+        };
+        for debugger in targets {
+            self.send_to_debugger(debugger, AnyRawPacket::Command(packet.clone()))
+                .await;
+        }
+    }
+
+    /// Clears the event requests of a debugger that disconnected, or disconnects from the target
+    /// VM once every debugger has
+    async fn disconnected(&mut self, debugger: usize) {
+        if self.debuggers.remove(&debugger).is_none() {
+            return;
+        }
+        info!("debugger {debugger} disconnected");
+        if self.debuggers.is_empty() {
+            // the target VM cleans up after the last debugger itself
+            self.vm = None;
+            return;
+        }
+        let Some(vm) = &mut self.vm else {
+            return;
+        };
+        let owned = vm
+            .requests
+            .iter()
+            .filter(|(_, (owner, _))| *owner == debugger)
+            .map(|(request, (_, kind))| (*request, *kind))
+            .collect::<Vec<_>>();
+        vm.requests.retain(|_, (owner, _)| *owner != debugger);
+        for (request, kind) in owned {
+            let mut data = BytesMut::new();
+            data.put_u8(kind);
+            data.put_i32(request);
+            self.send_to_vm(None, event_request::Clear::command_data(), data.freeze())
+                .await;
+        }
+    }
+}
+
+/// Reads the request id at the start of some data
+fn request_id(data: &[u8]) -> Option<Int> {
+    Some(Int::from_be_bytes(data.get(..4)?.try_into().ok()?))
+}
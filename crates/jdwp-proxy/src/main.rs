@@ -0,0 +1,53 @@
+//! The `jdwp-proxy` command, which prints the transcript of the debuggers it proxies
+
+use clap::Parser;
+use jdwp_proxy::Proxy;
+use std::path::PathBuf;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+/// Proxies debuggers to a java virtual machine, recording the packets they exchange
+#[derive(Debug, Parser)]
+#[command(name = "jdwp-proxy", version)]
+struct Options {
+    /// The address debuggers connect to
+    #[arg(long, default_value = "127.0.0.1:8000")]
+    listen: String,
+    /// Writes the transcript to a file rather than standard output
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// The address the target VM listens at, such as `localhost:5005` or `5005`
+    target: String,
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    let options = Options::parse();
+    let target = match options.target.parse::<u16>() {
+        Ok(port) => format!("localhost:{port}"),
+        Err(_) => options.target,
+    };
+    let mut output: Box<dyn AsyncWrite + Unpin + Send> = match &options.output {
+        Some(path) => Box::new(tokio::fs::File::create(path).await?),
+        None => Box::new(tokio::io::stdout()),
+    };
+    let (lines, mut transcript) = mpsc::unbounded_channel::<String>();
+    let proxy = Proxy::bind(&options.listen, target)
+        .await?
+        .transcript(lines);
+    eprintln!("Listening for debuggers at {}", proxy.local_addr()?);
+    tokio::spawn(async move {
+        while let Some(line) = transcript.recv().await {
+            if output
+                .write_all(format!("{line}\n").as_bytes())
+                .await
+                .is_err()
+            {
+                break;
+            }
+            let _ = output.flush().await;
+        }
+    });
+    proxy.run().await?;
+    Ok(())
+}
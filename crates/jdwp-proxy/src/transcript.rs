@@ -0,0 +1,214 @@
+//! Describes the packets passing through the proxy, one line each. Their data is decoded into
+//! typed fields by the command [registry], and shown as bytes when it can't be.
+
+use jdwp_client::codec::JdwpCodec;
+use jdwp_client::raw::packet::{AnyRawPacket, CommandData, RawCommandPacket, RawReplyPacket};
+use jdwp_client::registry::{registry, Description};
+use jdwp_types::ErrorConstant;
+use std::fmt::{Display, Formatter, Write};
+
+/// How many bytes of a packet's data are shown when it can't be decoded
+const SHOWN_BYTES: usize = 32;
+
+/// Where a packet is going
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Direction {
+    /// From a debugger, identified by the order it connected in, to the target VM
+    ToVm(usize),
+    /// From the target VM to a debugger
+    ToDebugger(usize),
+    /// From the target VM to every debugger
+    ToDebuggers,
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Direction::ToVm(debugger) => write!(f, "debugger {debugger} -> vm"),
+            Direction::ToDebugger(debugger) => write!(f, "vm -> debugger {debugger}"),
+            Direction::ToDebuggers => write!(f, "vm -> debuggers"),
+        }
+    }
+}
+
+/// Describes a command, decoding its data with the [registry] once the id sizes of the target VM
+/// are known
+pub fn describe_command(
+    direction: Direction,
+    packet: &RawCommandPacket,
+    codec: Option<&JdwpCodec>,
+) -> String {
+    let command = packet.header().command();
+    let mut line = format!("{direction}: command #{} {command}", packet.header().id());
+    let description =
+        codec.map(|codec| registry().describe(codec, &AnyRawPacket::Command(packet.clone()), None));
+    write_description(&mut line, description, packet.data());
+    line
+}
+
+/// Describes the reply to a command, decoding its data with the [registry] once the id sizes of
+/// the target VM are known
+pub fn describe_reply(
+    direction: Direction,
+    packet: &RawReplyPacket,
+    command: CommandData,
+    codec: Option<&JdwpCodec>,
+) -> String {
+    let code = packet.header().error_code().code();
+    let mut line = format!("{direction}: reply #{} {command}", packet.header().id());
+    if code != 0 {
+        let _ = write!(line, " failed with error code {code}");
+        if let Ok(constant) = ErrorConstant::try_from(code) {
+            let _ = write!(line, " ({constant:?})");
+        }
+        write_data(&mut line, packet.data());
+        return line;
+    }
+    let description = codec.map(|codec| {
+        registry().describe(codec, &AnyRawPacket::Reply(packet.clone()), Some(command))
+    });
+    write_description(&mut line, description, packet.data());
+    line
+}
+
+/// Writes the fields of a packet the registry decoded, or its bytes and why it couldn't be decoded
+fn write_description(line: &mut String, description: Option<Description>, data: &[u8]) {
+    match description {
+        Some(Description {
+            data,
+            undecoded: None,
+            ..
+        }) => {
+            let _ = write!(line, " {}", data.to_json());
+        }
+        Some(Description {
+            undecoded: Some(reason),
+            ..
+        }) => {
+            write_data(line, data);
+            let _ = write!(line, " ({reason})");
+        }
+        None => write_data(line, data),
+    }
+}
+
+/// Writes the length of a packet's data and its first bytes
+fn write_data(line: &mut String, data: &[u8]) {
+    let _ = write!(line, ", {} bytes", data.len());
+    if data.is_empty() {
+        return;
+    }
+    line.push(':');
+    for byte in data.iter().take(SHOWN_BYTES) {
+        let _ = write!(line, " {byte:02x}");
+    }
+    if data.len() > SHOWN_BYTES {
+        line.push_str(" ...");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use jdwp_client::commands::event_request::Set;
+    use jdwp_client::commands::object_reference::SetValues;
+    use jdwp_client::commands::thread_reference::Name;
+    use jdwp_client::commands::{ClassesBySignatures, Version};
+    use jdwp_client::events::Events;
+    use jdwp_client::id_sizes::IdSizes;
+    use jdwp_client::packet::JdwpCommand;
+    use jdwp_client::raw::packet::ErrorCode;
+
+    fn codec() -> JdwpCodec {
+        JdwpCodec::new(IdSizes::new(8, 8, 8, 8))
+    }
+
+    #[test]
+    fn test_describe_command() {
+        let packet = RawCommandPacket::new_command(3, Version::command_data(), Bytes::new());
+        assert_eq!(
+            describe_command(Direction::ToVm(1), &packet, None),
+            "debugger 1 -> vm: command #3 VirtualMachine.Version, 0 bytes"
+        );
+
+        let signature = b"Ljava/lang/String;";
+        let data = [&(signature.len() as u32).to_be_bytes()[..], signature].concat();
+        let packet = RawCommandPacket::new_command(
+            4,
+            ClassesBySignatures::command_data(),
+            Bytes::from(data),
+        );
+        assert_eq!(
+            describe_command(Direction::ToVm(1), &packet, Some(&codec())),
+            r#"debugger 1 -> vm: command #4 VirtualMachine.ClassesBySignature {"signature":"Ljava/lang/String;"}"#
+        );
+    }
+
+    #[test]
+    fn test_describe_undecoded_command() {
+        let data = [vec![0u8; 40], vec![1]].concat();
+        let packet = RawCommandPacket::new_command(4, CommandData::new(11, 99), Bytes::from(data));
+        let line = describe_command(Direction::ToVm(2), &packet, Some(&codec()));
+        assert!(
+            line.starts_with("debugger 2 -> vm: command #4 ThreadReference.99, 41 bytes: 00 00"),
+            "{line}"
+        );
+        assert!(
+            line.ends_with(" 00 ... (the command isn't known)"),
+            "{line}"
+        );
+
+        // an object id and no values
+        let packet = RawCommandPacket::new_command(
+            5,
+            SetValues::command_data(),
+            Bytes::from_static(&[0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0]),
+        );
+        let line = describe_command(Direction::ToVm(1), &packet, Some(&codec()));
+        assert!(
+            line.ends_with("(the command holds untagged values)"),
+            "{line}"
+        );
+    }
+
+    #[test]
+    fn test_describe_events() {
+        // suspend all, one VM death event for request 0
+        let data = Bytes::from_static(&[2, 0, 0, 0, 1, 99, 0, 0, 0, 0]);
+        let packet = RawCommandPacket::new_command(1, Events::command_data(), data);
+        assert_eq!(
+            describe_command(Direction::ToDebuggers, &packet, Some(&codec())),
+            r#"vm -> debuggers: command #1 Event.Composite {"policy":"All","events":["VmDeath { request_id: 0 }"]}"#
+        );
+    }
+
+    #[test]
+    fn test_describe_reply() {
+        let packet =
+            RawReplyPacket::new_reply(5, ErrorCode::new(0), Bytes::from_static(&[0, 0, 0, 1]));
+        assert_eq!(
+            describe_reply(
+                Direction::ToDebugger(1),
+                &packet,
+                Set::command_data(),
+                Some(&codec())
+            ),
+            r#"vm -> debugger 1: reply #5 EventRequest.Set {"request_id":1}"#
+        );
+        assert_eq!(
+            describe_reply(Direction::ToDebugger(1), &packet, Set::command_data(), None),
+            "vm -> debugger 1: reply #5 EventRequest.Set, 4 bytes: 00 00 00 01"
+        );
+        let packet = RawReplyPacket::new_reply(6, ErrorCode::new(10), Bytes::new());
+        assert_eq!(
+            describe_reply(
+                Direction::ToDebugger(1),
+                &packet,
+                Name::command_data(),
+                Some(&codec())
+            ),
+            "vm -> debugger 1: reply #6 ThreadReference.Name failed with error code 10 (InvalidThread), 0 bytes"
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use futures::StreamExt;
+use jdb_test_fixtures::JavaInstance;
+use jdi_rs::event::Event;
+use jdi_rs::*;
+use jdwp_client::commands::Version;
+use jdwp_client::JdwpClient;
+use jdwp_proxy::Proxy;
+use std::time::Duration;
+use test_log::test;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test(tokio::test)]
+async fn test_shared_vm() -> eyre::Result<()> {
+    let jvm_instance = JavaInstance::new(0, "com.acme.Orders").await?;
+    let (lines, mut transcript) = mpsc::unbounded_channel();
+    let proxy = Proxy::bind("127.0.0.1:0", format!("127.0.0.1:{}", jvm_instance.port()))
+        .await?
+        .transcript(lines);
+    let address = proxy.local_addr()?;
+    tokio::spawn(proxy.run());
+
+    let vm = VirtualMachineManager::attach(address).await?;
+    let start = vm
+        .event_queue()
+        .remove_timeout(TIMEOUT)
+        .await?
+        .expect("no event");
+    assert!(matches!(start.events()[0], Event::VmStart(_)));
+    let manager = vm.event_request_manager();
+    manager.set_breakpoint("com/acme/Orders.java", 75).await?;
+
+    let client = JdwpClient::create(TcpStream::connect(address).await?).await?;
+    let mut other_events = client.events();
+    let version = client.send(Version).await?;
+    assert!(!version.version.is_empty());
+
+    start.resume().await?;
+    let hit = loop {
+        let set = vm
+            .event_queue()
+            .remove_timeout(TIMEOUT)
+            .await?
+            .expect("no event");
+        if let Some(Event::Breakpoint(hit)) = set.events().first() {
+            break hit.clone();
+        }
+        set.resume().await?;
+    };
+    assert_eq!(hit.location().line_number().await?, Some(75));
+    // the breakpoint was requested by the first debugger alone
+    assert!(timeout(Duration::from_millis(200), other_events.next())
+        .await
+        .is_err());
+
+    // disposing one debugger leaves the target VM to the other
+    client.dispose().await?;
+    assert!(!vm.all_threads().await?.is_empty());
+
+    let mut lines = vec![];
+    while let Ok(line) = transcript.try_recv() {
+        lines.push(line);
+    }
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("debugger 1 -> vm: command #")
+                && line.contains(" VirtualMachine.IDSizes")),
+        "{lines:#?}"
+    );
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("debugger 2 -> vm: command #")
+                && line.contains(" VirtualMachine.Version")),
+        "{lines:#?}"
+    );
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("vm -> debugger 2: reply #")
+                && line.contains(" VirtualMachine.Dispose")),
+        "{lines:#?}"
+    );
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("vm -> debugger 1: command #")
+                && line.contains(r#""events":["Breakpoint {"#)),
+        "{lines:#?}"
+    );
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("vm -> debugger 1: reply #")
+                && line.contains(r#" VirtualMachine.IDSizes {"field_id_size":"#)),
+        "{lines:#?}"
+    );
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("vm -> debuggers: command #")
+                && line.contains(" Event.Composite")),
+        "{lines:#?}"
+    );
+    Ok(())
+}
@@ -0,0 +1,576 @@
+//! Answers the commands debuggers send from the model of the target VM
+
+use crate::model::{EventRequest, MockObject, Model, ObjectKind, ThreadState};
+use bytes::Bytes;
+use jdwp_client::codec::{JdwpCodec, JdwpDecodable, JdwpDecoder, JdwpEncodable, JdwpEncoder};
+use jdwp_client::commands::event_request::Modifier;
+use jdwp_types::{
+    Byte, ErrorConstant, EventKind, FieldId, FrameId, Id, Int, MethodId, ObjectId, ReferenceTypeId,
+    SuspendPolicy, Tag, TaggedObjectId, ThreadId, TypeTag,
+};
+
+/// The suspend status bit of suspended threads
+const SUSPEND_STATUS_SUSPENDED: Int = 0x1;
+
+/// How many capabilities `VirtualMachine.CapabilitiesNew` replies with, including the reserved
+/// ones
+const CAPABILITIES: usize = 32;
+
+type Reply = Result<(), ErrorConstant>;
+
+/// The arguments of a command and the data of its reply
+struct Exchange<'a> {
+    args: JdwpDecoder<'a>,
+    reply: JdwpEncoder<'a>,
+}
+
+impl Exchange<'_> {
+    fn arg<T: JdwpDecodable>(&mut self) -> Result<T, ErrorConstant> {
+        self.args
+            .get::<T>()
+            .map_err(|_| ErrorConstant::IllegalArgument)
+    }
+
+    fn put<T: JdwpEncodable>(&mut self, value: &T) {
+        self.reply.put(value);
+    }
+
+    /// Puts the length of a list that follows
+    fn put_len(&mut self, len: usize) {
+        self.reply.put(&(len as Int));
+    }
+}
+
+/// Answers a command, giving the data of the reply or the error it failed with
+pub(crate) fn handle(
+    model: &mut Model,
+    codec: &JdwpCodec,
+    command_set: u8,
+    command: u8,
+    data: Bytes,
+) -> Result<Bytes, ErrorConstant> {
+    let mut exchange = Exchange {
+        args: JdwpDecoder::new(codec, data),
+        reply: JdwpEncoder::new(codec),
+    };
+    let ex = &mut exchange;
+    match command_set {
+        1 => virtual_machine(model, command, ex, codec),
+        2 => reference_type(model, command, ex),
+        3 => class_type(model, command, ex),
+        6 => method(model, command, ex),
+        9 => object_reference(model, command, ex),
+        10 => string_reference(model, command, ex),
+        11 => thread_reference(model, command, ex),
+        13 => array_reference(model, command, ex),
+        15 => event_request(model, command, ex),
+        16 => stack_frame(model, command, ex),
+        _ => Err(ErrorConstant::NotImplemented),
+    }?;
+    Ok(exchange.reply.into_bytes())
+}
+
+fn virtual_machine(model: &mut Model, command: u8, ex: &mut Exchange, codec: &JdwpCodec) -> Reply {
+    match command {
+        // Version
+        1 => {
+            let version = &model.version;
+            ex.put(&version.description);
+            ex.put(&version.major);
+            ex.put(&version.minor);
+            ex.put(&version.version);
+            ex.put(&version.name);
+        }
+        // ClassesBySignature
+        2 => {
+            let signature = ex.arg::<String>()?;
+            let classes = model
+                .classes
+                .iter()
+                .filter(|(_, class)| class.signature == signature)
+                .collect::<Vec<_>>();
+            ex.put_len(classes.len());
+            for (id, class) in classes {
+                ex.put(&class.tag);
+                ex.put(&ReferenceTypeId::new(*id));
+                ex.put(&class.status);
+            }
+        }
+        // AllClasses
+        3 => {
+            ex.put_len(model.classes.len());
+            for (id, class) in &model.classes {
+                ex.put(&class.tag);
+                ex.put(&ReferenceTypeId::new(*id));
+                ex.put(&class.signature);
+                ex.put(&class.status);
+            }
+        }
+        // AllThreads
+        4 => {
+            ex.put_len(model.threads.len());
+            for thread in &model.threads {
+                ex.put(&ThreadId::new(*thread));
+            }
+        }
+        // TopLevelThreadGroups
+        5 => ex.put_len(0),
+        // Dispose: the target VM forgets the debugger's requests and resumes its threads
+        6 => {
+            model.requests.clear();
+            for thread in model.threads.clone() {
+                if let Some(state) = model.thread_mut(thread) {
+                    state.suspend_count = 0;
+                }
+            }
+        }
+        // IDSizes
+        7 => {
+            let id_sizes = codec.id_sizes();
+            ex.put(&(id_sizes.field_id_size() as Int));
+            ex.put(&(id_sizes.method_id_size() as Int));
+            ex.put(&(id_sizes.object_id_size() as Int));
+            ex.put(&(id_sizes.object_id_size() as Int));
+            ex.put(&(id_sizes.frame_id_size() as Int));
+        }
+        // Suspend
+        8 => model.suspend_all(),
+        // Resume
+        9 => model.resume_all(),
+        // CreateString
+        11 => {
+            let value = ex.arg::<String>()?;
+            let id = model.add_string(value);
+            ex.put(&ObjectId::new(id));
+        }
+        // CapabilitiesNew
+        17 => {
+            let c = model.capabilities;
+            let capabilities = [
+                c.can_watch_field_modification,
+                c.can_watch_field_access,
+                c.can_get_bytecodes,
+                c.can_get_synthetic_attribute,
+                c.can_get_owned_monitor_info,
+                c.can_get_current_contended_monitor,
+                c.can_get_monitor_info,
+                c.can_redefine_classes,
+                c.can_add_method,
+                c.can_unrestrictedly_redefine_classes,
+                c.can_pop_frames,
+                c.can_use_instance_filters,
+                c.can_get_source_debug_extension,
+                c.can_request_vm_death_event,
+                c.can_set_default_stratum,
+                c.can_get_instance_info,
+                c.can_request_monitor_events,
+                c.can_get_monitor_frame_info,
+                c.can_use_source_name_filters,
+                c.can_get_constant_pool,
+                c.can_force_early_return,
+            ];
+            for capability in capabilities {
+                ex.put(&capability);
+            }
+            for _ in capabilities.len()..CAPABILITIES {
+                ex.put(&false);
+            }
+        }
+        // AllClassesWithGeneric
+        20 => {
+            ex.put_len(model.classes.len());
+            for (id, class) in &model.classes {
+                ex.put(&class.tag);
+                ex.put(&ReferenceTypeId::new(*id));
+                ex.put(&class.signature);
+                ex.put(&class.generic_signature.clone().unwrap_or_default());
+                ex.put(&class.status);
+            }
+        }
+        _ => return Err(ErrorConstant::NotImplemented),
+    }
+    Ok(())
+}
+
+fn reference_type(model: &mut Model, command: u8, ex: &mut Exchange) -> Reply {
+    let ref_type = ex.arg::<ReferenceTypeId>()?;
+    let class = model
+        .classes
+        .get(&ref_type.get())
+        .ok_or(ErrorConstant::InvalidClass)?;
+    match command {
+        // Signature
+        1 => ex.put(&class.signature),
+        // Fields
+        4 => {
+            ex.put_len(class.fields.len());
+            for (id, field) in &class.fields {
+                ex.put(id);
+                ex.put(&field.name);
+                ex.put(&field.signature);
+                ex.put(&field.mod_bits);
+            }
+        }
+        // Methods
+        5 => {
+            ex.put_len(class.methods.len());
+            for (id, method) in &class.methods {
+                ex.put(id);
+                ex.put(&method.name);
+                ex.put(&method.signature);
+                ex.put(&method.mod_bits);
+            }
+        }
+        // GetValues of static fields
+        6 => {
+            let fields = ex.arg::<Vec<FieldId>>()?;
+            let mut values = vec![];
+            for field in fields {
+                let value = class
+                    .fields
+                    .iter()
+                    .find(|(id, _)| *id == field)
+                    .and_then(|(_, field)| field.value.clone())
+                    .ok_or(ErrorConstant::InvalidFieldId)?;
+                values.push(value);
+            }
+            ex.put(&values);
+        }
+        // SourceFile
+        7 => {
+            let source_file = class
+                .source_file
+                .as_ref()
+                .ok_or(ErrorConstant::AbsentInformation)?;
+            ex.put(source_file);
+        }
+        // Status
+        9 => ex.put(&class.status),
+        // Interfaces
+        10 => ex.put(&class.interfaces),
+        // SignatureWithGeneric
+        13 => {
+            ex.put(&class.signature);
+            ex.put(&class.generic_signature.clone().unwrap_or_default());
+        }
+        // FieldsWithGeneric
+        14 => {
+            ex.put_len(class.fields.len());
+            for (id, field) in &class.fields {
+                ex.put(id);
+                ex.put(&field.name);
+                ex.put(&field.signature);
+                ex.put(&field.generic_signature.clone().unwrap_or_default());
+                ex.put(&field.mod_bits);
+            }
+        }
+        // MethodsWithGeneric
+        15 => {
+            ex.put_len(class.methods.len());
+            for (id, method) in &class.methods {
+                ex.put(id);
+                ex.put(&method.name);
+                ex.put(&method.signature);
+                ex.put(&method.generic_signature.clone().unwrap_or_default());
+                ex.put(&method.mod_bits);
+            }
+        }
+        _ => return Err(ErrorConstant::NotImplemented),
+    }
+    Ok(())
+}
+
+fn class_type(model: &mut Model, command: u8, ex: &mut Exchange) -> Reply {
+    match command {
+        // Superclass
+        1 => {
+            let class = ex.arg::<ReferenceTypeId>()?;
+            let class = model
+                .classes
+                .get(&class.get())
+                .filter(|class| class.tag == TypeTag::Class)
+                .ok_or(ErrorConstant::InvalidClass)?;
+            let superclass = class.superclass.unwrap_or(ReferenceTypeId::new(0));
+            ex.put(&superclass);
+        }
+        _ => return Err(ErrorConstant::NotImplemented),
+    }
+    Ok(())
+}
+
+fn method(model: &mut Model, command: u8, ex: &mut Exchange) -> Reply {
+    let ref_type = ex.arg::<ReferenceTypeId>()?;
+    let method_id = ex.arg::<MethodId>()?;
+    let class = model
+        .classes
+        .get(&ref_type.get())
+        .ok_or(ErrorConstant::InvalidClass)?;
+    let (_, method) = class
+        .methods
+        .iter()
+        .find(|(id, _)| *id == method_id)
+        .ok_or(ErrorConstant::InvalidMethodId)?;
+    let (start, end) = method.code_range();
+    match command {
+        // LineTable
+        1 => {
+            ex.put(&start);
+            ex.put(&end);
+            ex.put_len(method.lines.len());
+            for (code_index, line) in &method.lines {
+                ex.put(code_index);
+                ex.put(line);
+            }
+        }
+        // VariableTable
+        2 => {
+            if method.variables.is_empty() {
+                return Err(ErrorConstant::AbsentInformation);
+            }
+            ex.put(&method.arg_count);
+            ex.put_len(method.variables.len());
+            for variable in &method.variables {
+                let (code_index, length) =
+                    variable.scope.unwrap_or((start, (end - start + 1) as Int));
+                ex.put(&code_index);
+                ex.put(&variable.name);
+                ex.put(&variable.signature);
+                ex.put(&length);
+                ex.put(&variable.slot);
+            }
+        }
+        _ => return Err(ErrorConstant::NotImplemented),
+    }
+    Ok(())
+}
+
+fn object(model: &Model, object: ObjectId) -> Result<&MockObject, ErrorConstant> {
+    model
+        .objects
+        .get(&object.get())
+        .ok_or(ErrorConstant::InvalidObject)
+}
+
+fn object_reference(model: &mut Model, command: u8, ex: &mut Exchange) -> Reply {
+    let object = object(model, ex.arg::<ObjectId>()?)?;
+    match command {
+        // ReferenceType
+        1 => {
+            let class = &model.classes[&object.class];
+            ex.put(&class.tag);
+            ex.put(&ReferenceTypeId::new(object.class));
+        }
+        // GetValues
+        2 => {
+            let fields = ex.arg::<Vec<FieldId>>()?;
+            let ObjectKind::Instance(values) = &object.kind else {
+                return Err(ErrorConstant::InvalidFieldId);
+            };
+            let mut found = vec![];
+            for field in fields {
+                found.push(
+                    values
+                        .get(&field)
+                        .cloned()
+                        .ok_or(ErrorConstant::InvalidFieldId)?,
+                );
+            }
+            ex.put(&found);
+        }
+        _ => return Err(ErrorConstant::NotImplemented),
+    }
+    Ok(())
+}
+
+fn string_reference(model: &mut Model, command: u8, ex: &mut Exchange) -> Reply {
+    let object = object(model, ex.arg::<ObjectId>()?)?;
+    match command {
+        // Value
+        1 => {
+            let ObjectKind::String(value) = &object.kind else {
+                return Err(ErrorConstant::InvalidString);
+            };
+            ex.put(value);
+        }
+        _ => return Err(ErrorConstant::NotImplemented),
+    }
+    Ok(())
+}
+
+fn thread(model: &mut Model, thread: ThreadId) -> Result<&mut ThreadState, ErrorConstant> {
+    model
+        .thread_mut(thread.get())
+        .ok_or(ErrorConstant::InvalidThread)
+}
+
+/// Gets a thread whose frames can be inspected
+fn suspended(model: &mut Model, thread_id: ThreadId) -> Result<&mut ThreadState, ErrorConstant> {
+    let state = thread(model, thread_id)?;
+    if state.suspend_count == 0 {
+        return Err(ErrorConstant::ThreadNotSuspended);
+    }
+    Ok(state)
+}
+
+fn thread_reference(model: &mut Model, command: u8, ex: &mut Exchange) -> Reply {
+    let thread_id = ex.arg::<ThreadId>()?;
+    match command {
+        // Name
+        1 => ex.put(&thread(model, thread_id)?.name),
+        // Suspend
+        2 => thread(model, thread_id)?.suspend_count += 1,
+        // Resume
+        3 => {
+            let state = thread(model, thread_id)?;
+            state.suspend_count = (state.suspend_count - 1).max(0);
+        }
+        // Status
+        4 => {
+            let state = thread(model, thread_id)?;
+            ex.put(&state.status);
+            let suspended = if state.suspend_count > 0 {
+                SUSPEND_STATUS_SUSPENDED
+            } else {
+                0
+            };
+            ex.put(&suspended);
+        }
+        // Frames
+        6 => {
+            let start = ex.arg::<Int>()?;
+            let length = ex.arg::<Int>()?;
+            let state = suspended(model, thread_id)?;
+            let total = state.frames.len();
+            let start = usize::try_from(start)
+                .ok()
+                .filter(|&start| start <= total)
+                .ok_or(ErrorConstant::InvalidIndex)?;
+            let end = if length == -1 {
+                total
+            } else {
+                usize::try_from(length)
+                    .ok()
+                    .map(|length| start + length)
+                    .filter(|&end| end <= total)
+                    .ok_or(ErrorConstant::InvalidLength)?
+            };
+            let frames = &state.frames[start..end];
+            ex.put_len(frames.len());
+            for (id, frame) in frames {
+                ex.put(id);
+                ex.put(&frame.location);
+            }
+        }
+        // FrameCount
+        7 => {
+            let state = suspended(model, thread_id)?;
+            ex.put(&(state.frames.len() as Int));
+        }
+        // SuspendCount
+        12 => ex.put(&thread(model, thread_id)?.suspend_count),
+        _ => return Err(ErrorConstant::NotImplemented),
+    }
+    Ok(())
+}
+
+fn array_reference(model: &mut Model, command: u8, ex: &mut Exchange) -> Reply {
+    let object = object(model, ex.arg::<ObjectId>()?)?;
+    let ObjectKind::Array { component, values } = &object.kind else {
+        return Err(ErrorConstant::InvalidArray);
+    };
+    match command {
+        // Length
+        1 => ex.put(&(values.len() as Int)),
+        // GetValues
+        2 => {
+            let first = ex.arg::<Int>()?;
+            let length = ex.arg::<Int>()?;
+            let region = usize::try_from(first)
+                .ok()
+                .zip(usize::try_from(length).ok())
+                .and_then(|(first, length)| values.get(first..first + length))
+                .ok_or(ErrorConstant::InvalidLength)?;
+            ex.put(component);
+            ex.put_len(region.len());
+            let primitive = !matches!(component, Tag::Array | Tag::Object);
+            for value in region {
+                if primitive {
+                    ex.reply.put_untagged_value(value);
+                } else {
+                    ex.put(value);
+                }
+            }
+        }
+        _ => return Err(ErrorConstant::NotImplemented),
+    }
+    Ok(())
+}
+
+fn event_request(model: &mut Model, command: u8, ex: &mut Exchange) -> Reply {
+    match command {
+        // Set
+        1 => {
+            let event_kind = ex.arg::<EventKind>()?;
+            let suspend_policy = ex.arg::<SuspendPolicy>()?;
+            let modifiers = ex.arg::<Vec<Modifier>>()?;
+            let id = model.next_request();
+            model.requests.push(EventRequest {
+                id,
+                event_kind,
+                suspend_policy,
+                modifiers,
+            });
+            ex.put(&id);
+        }
+        // Clear, which ignores requests that don't exist
+        2 => {
+            let event_kind = ex.arg::<EventKind>()?;
+            let request_id = ex.arg::<Int>()?;
+            model
+                .requests
+                .retain(|request| !(request.event_kind == event_kind && request.id == request_id));
+        }
+        // ClearAllBreakpoints
+        3 => model
+            .requests
+            .retain(|request| request.event_kind != EventKind::Breakpoint),
+        _ => return Err(ErrorConstant::NotImplemented),
+    }
+    Ok(())
+}
+
+fn stack_frame(model: &mut Model, command: u8, ex: &mut Exchange) -> Reply {
+    let thread_id = ex.arg::<ThreadId>()?;
+    let frame_id = ex.arg::<FrameId>()?;
+    let state = suspended(model, thread_id)?;
+    let (_, frame) = state
+        .frames
+        .iter()
+        .find(|(id, _)| *id == frame_id)
+        .ok_or(ErrorConstant::InvalidFrameId)?;
+    match command {
+        // GetValues
+        1 => {
+            let slots = ex.arg::<Int>()?;
+            let mut values = vec![];
+            for _ in 0..slots {
+                let slot = ex.arg::<Int>()?;
+                let _sig_byte = ex.arg::<Byte>()?;
+                let value = frame
+                    .locals
+                    .get(&slot)
+                    .cloned()
+                    .ok_or(ErrorConstant::InvalidSlot)?;
+                values.push(value);
+            }
+            ex.put(&values);
+        }
+        // ThisObject
+        3 => {
+            let this = frame.this.map_or(0, |this| this.get());
+            ex.put(&TaggedObjectId::new(Tag::Object, Id::new(this)));
+        }
+        _ => return Err(ErrorConstant::NotImplemented),
+    }
+    Ok(())
+}
@@ -0,0 +1,216 @@
+//! # `jdwp-server`
+//!
+//! The target VM side of jdwp, answering debuggers from a scriptable [model](MockVm) of classes,
+//! threads, frames and objects instead of a java virtual machine. Debuggers and the libraries
+//! they're built on can be tested with it quickly, and without a JDK.
+//!
+//! A test loads classes and starts threads in a [MockVm], then lets a debugger connect, either
+//! over TCP through a [JdwpServer] or in memory through [MockVm::connect]. The [Connection] it
+//! gets back emits composite events to the debugger, and the model records the event requests the
+//! debugger makes so the test can answer them.
+//!
+//! Threads are suspended the way a java virtual machine suspends them, by events and suspend
+//! commands, and their frames can only be inspected while they're suspended. Commands the mock
+//! doesn't implement fail with `NOT_IMPLEMENTED`.
+
+use crate::model::Model;
+use futures::{SinkExt, StreamExt};
+use jdwp_client::codec::{JdwpCodec, JdwpEncoder};
+use jdwp_client::commands::Dispose;
+use jdwp_client::connect::{accept_handshake, JdwpTransport};
+use jdwp_client::events::{Event, Events};
+use jdwp_client::id_sizes::IdSizes;
+use jdwp_client::packet::JdwpCommand;
+use jdwp_client::raw::codec::RawCodec;
+use jdwp_client::raw::packet::{AnyRawPacket, ErrorCode, RawCommandPacket, RawReplyPacket};
+use jdwp_types::SuspendPolicy;
+use std::collections::BTreeSet;
+use std::io;
+use std::net::SocketAddr;
+use tokio::io::{DuplexStream, ReadHalf, WriteHalf};
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+use tokio_util::codec::{FramedRead, FramedWrite};
+use tracing::{debug, trace};
+
+pub use model::*;
+
+mod handler;
+mod model;
+
+/// The size of the buffer of in-memory connections
+const BUFFER: usize = 64 * 1024;
+
+/// The size of every id the mock gives out
+const ID_SIZE: usize = 8;
+
+/// Accepts debuggers over TCP
+#[derive(Debug)]
+pub struct JdwpServer {
+    listener: TcpListener,
+    vm: MockVm,
+}
+
+impl JdwpServer {
+    /// Listens for debuggers at an address, answering them from a model
+    pub async fn bind(address: impl ToSocketAddrs, vm: MockVm) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(address).await?,
+            vm,
+        })
+    }
+
+    /// Gets the address debuggers attach to
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Waits for the next debugger to connect
+    pub async fn accept(&self) -> io::Result<Connection> {
+        let (stream, address) = self.listener.accept().await?;
+        debug!("debugger connected from {address}");
+        Ok(Connection::spawn(self.vm.clone(), stream))
+    }
+}
+
+impl MockVm {
+    /// Connects a debugger in memory. The connection is served in the background, so this must be
+    /// called within a tokio runtime.
+    pub fn connect(&self) -> (MockTransport, Connection) {
+        let (debugger, vm) = tokio::io::duplex(BUFFER);
+        let connection = Connection::spawn(self.clone(), MockTransport { stream: vm });
+        (MockTransport { stream: debugger }, connection)
+    }
+}
+
+/// One end of an in-memory connection between a debugger and a [MockVm]
+#[derive(Debug)]
+pub struct MockTransport {
+    stream: DuplexStream,
+}
+
+impl JdwpTransport for MockTransport {
+    type Input = ReadHalf<DuplexStream>;
+    type Output = WriteHalf<DuplexStream>;
+
+    fn split_transport(self) -> (Self::Input, Self::Output)
+    where
+        Self: Sized,
+    {
+        tokio::io::split(self.stream)
+    }
+}
+
+/// A debugger connected to a [MockVm]
+#[derive(Debug)]
+pub struct Connection {
+    vm: MockVm,
+    events: UnboundedSender<Events>,
+    task: JoinHandle<io::Result<()>>,
+}
+
+impl Connection {
+    fn spawn<T: JdwpTransport + Send + 'static>(vm: MockVm, transport: T) -> Self {
+        let (events, received) = mpsc::unbounded_channel();
+        let task = tokio::spawn(serve(vm.clone(), transport, received));
+        Self { vm, events, task }
+    }
+
+    /// Sends events to the debugger in one composite command, first suspending the threads the
+    /// suspend policy says to
+    pub fn emit(&self, policy: SuspendPolicy, events: Vec<Event>) -> io::Result<()> {
+        {
+            let mut model = self.vm.lock();
+            match policy {
+                SuspendPolicy::None => {}
+                SuspendPolicy::EventThread => {
+                    let threads = events.iter().filter_map(Event::thread);
+                    for thread in threads.collect::<BTreeSet<_>>() {
+                        if let Some(state) = model.thread_mut(thread.get()) {
+                            state.suspend_count += 1;
+                        }
+                    }
+                }
+                SuspendPolicy::All => model.suspend_all(),
+            }
+        }
+        self.events
+            .send(Events { policy, events })
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "the debugger disconnected"))
+    }
+
+    /// Waits for the debugger to dispose of the connection or disconnect
+    pub async fn closed(self) -> io::Result<()> {
+        self.task.await.map_err(io::Error::other)?
+    }
+}
+
+/// Answers the commands of a debugger, and sends it the events emitted for it, until it
+/// disconnects
+async fn serve<T: JdwpTransport>(
+    vm: MockVm,
+    transport: T,
+    mut events: UnboundedReceiver<Events>,
+) -> io::Result<()> {
+    let (mut input, mut output) = transport.split_transport();
+    accept_handshake(&mut input, &mut output).await?;
+    let codec = JdwpCodec::new(IdSizes::new(ID_SIZE, ID_SIZE, ID_SIZE, ID_SIZE));
+    let mut packets = FramedRead::new(input, RawCodec);
+    let mut sink = FramedWrite::new(output, RawCodec);
+    let mut next_id = 1;
+    loop {
+        tokio::select! {
+            packet = packets.next() => match packet {
+                Some(Ok(AnyRawPacket::Command(command))) => {
+                    let disposed = command.header().command() == Dispose::command_data();
+                    let reply = answer(&mut vm.lock(), &codec, command);
+                    sink.send(reply).await?;
+                    if disposed {
+                        // the target VM closes the connection once it's disposed
+                        debug!("debugger disposed of the connection");
+                        return Ok(());
+                    }
+                }
+                // the mock never sends commands that are replied to
+                Some(Ok(AnyRawPacket::Reply(_))) => {}
+                Some(Err(e)) => return Err(e),
+                None => {
+                    debug!("debugger disconnected");
+                    return Ok(());
+                }
+            },
+            Some(events) = events.recv() => {
+                let mut encoder = JdwpEncoder::new(&codec);
+                encoder.put(&events);
+                let command = RawCommandPacket::new_command(next_id, Events::command_data(), encoder.into_bytes());
+                next_id += 1;
+                sink.send(command).await?;
+            }
+        }
+    }
+}
+
+/// Answers a command from the model
+fn answer(model: &mut Model, codec: &JdwpCodec, command: RawCommandPacket) -> RawReplyPacket {
+    let id = command.header().id();
+    let command_data = command.header().command();
+    let handled = handler::handle(
+        model,
+        codec,
+        command_data.command_set(),
+        command_data.command(),
+        command.data().clone(),
+    );
+    match handled {
+        Ok(data) => {
+            trace!("answered {command_data} #{id}");
+            RawReplyPacket::new_reply(id, ErrorCode::new(0), data)
+        }
+        Err(error) => {
+            debug!("{command_data} #{id} failed with {error:?}");
+            let code = ErrorCode::new(u16::from(error));
+            RawReplyPacket::new_reply(id, code, Default::default())
+        }
+    }
+}
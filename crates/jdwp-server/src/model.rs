@@ -0,0 +1,686 @@
+//! The scriptable model of the target VM: its classes, threads, frames and objects
+
+use jdwp_client::commands::event_request::Modifier;
+use jdwp_client::commands::CapabilitiesNewReply;
+use jdwp_types::{
+    ArrayId, ClassId, ClassStatus, EventKind, FieldId, FrameId, Int, Location, Long, MethodId,
+    ObjectId, ReferenceTypeId, StringId, SuspendPolicy, Tag, ThreadId, ThreadStatus, TypeTag,
+    Value,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The modifier bit of static fields and methods
+pub const ACC_STATIC: Int = 0x0008;
+
+/// The status of a class that has been verified, prepared and initialized
+const INITIALIZED: ClassStatus = ClassStatus(7);
+
+const OBJECT: &str = "Ljava/lang/Object;";
+const STRING: &str = "Ljava/lang/String;";
+const THREAD: &str = "Ljava/lang/Thread;";
+
+/// The version a [MockVm] reports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionInfo {
+    pub description: String,
+    pub major: Int,
+    pub minor: Int,
+    pub version: String,
+    pub name: String,
+}
+
+impl Default for VersionInfo {
+    fn default() -> Self {
+        Self {
+            description: "Mock JVM debug interface".to_string(),
+            major: 17,
+            minor: 0,
+            version: "17.0.0".to_string(),
+            name: "jdwp-server".to_string(),
+        }
+    }
+}
+
+/// A class or interface to load into a [MockVm]
+#[derive(Debug, Clone)]
+pub struct MockClass {
+    pub(crate) signature: String,
+    pub(crate) generic_signature: Option<String>,
+    pub(crate) tag: TypeTag,
+    pub(crate) status: ClassStatus,
+    pub(crate) source_file: Option<String>,
+    pub(crate) superclass: Option<ReferenceTypeId>,
+    pub(crate) interfaces: Vec<ReferenceTypeId>,
+    pub(crate) fields: Vec<(FieldId, MockField)>,
+    pub(crate) methods: Vec<(MethodId, MockMethod)>,
+}
+
+impl MockClass {
+    /// Creates a class with a jni signature such as `Lcom/acme/Orders;`. Its superclass is
+    /// `java.lang.Object` unless another is given.
+    pub fn new(signature: impl Into<String>) -> Self {
+        Self {
+            signature: signature.into(),
+            generic_signature: None,
+            tag: TypeTag::Class,
+            status: INITIALIZED,
+            source_file: None,
+            superclass: None,
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+        }
+    }
+
+    /// Sets the generic signature of this class, such as `<T:Ljava/lang/Object;>Ljava/lang/Object;`
+    pub fn generic_signature(mut self, generic_signature: impl Into<String>) -> Self {
+        self.generic_signature = Some(generic_signature.into());
+        self
+    }
+
+    /// Makes this an interface
+    pub fn interface(mut self) -> Self {
+        self.tag = TypeTag::Interface;
+        self
+    }
+
+    /// Sets the status of this class, which is initialized by default
+    pub fn status(mut self, status: ClassStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Sets the name of the source file this class was compiled from, such as `Orders.java`
+    pub fn source_file(mut self, source_file: impl Into<String>) -> Self {
+        self.source_file = Some(source_file.into());
+        self
+    }
+
+    /// Sets the superclass of this class
+    pub fn superclass(mut self, superclass: ReferenceTypeId) -> Self {
+        self.superclass = Some(superclass);
+        self
+    }
+
+    /// Adds an interface this class directly implements
+    pub fn implements(mut self, interface: ReferenceTypeId) -> Self {
+        self.interfaces.push(interface);
+        self
+    }
+
+    /// Declares a field
+    pub fn field(mut self, field: MockField) -> Self {
+        self.fields.push((FieldId::new(0), field));
+        self
+    }
+
+    /// Declares a method
+    pub fn method(mut self, method: MockMethod) -> Self {
+        self.methods.push((MethodId::new(0), method));
+        self
+    }
+}
+
+/// A field declared by a [MockClass]
+#[derive(Debug, Clone)]
+pub struct MockField {
+    pub(crate) name: String,
+    pub(crate) signature: String,
+    pub(crate) generic_signature: Option<String>,
+    pub(crate) mod_bits: Int,
+    pub(crate) value: Option<Value>,
+}
+
+impl MockField {
+    /// Creates an instance field with a jni signature such as `I`
+    pub fn new(name: impl Into<String>, signature: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            signature: signature.into(),
+            generic_signature: None,
+            mod_bits: 0,
+            value: None,
+        }
+    }
+
+    /// Sets the generic signature of this field's type, such as `Ljava/util/List<TT;>;`
+    pub fn generic_signature(mut self, generic_signature: impl Into<String>) -> Self {
+        self.generic_signature = Some(generic_signature.into());
+        self
+    }
+
+    /// Sets the modifier bits of this field
+    pub fn modifiers(mut self, mod_bits: Int) -> Self {
+        self.mod_bits = mod_bits;
+        self
+    }
+
+    /// Makes this a static field with a value
+    pub fn static_value(mut self, value: Value) -> Self {
+        self.mod_bits |= ACC_STATIC;
+        self.value = Some(value);
+        self
+    }
+}
+
+/// A method declared by a [MockClass]
+#[derive(Debug, Clone)]
+pub struct MockMethod {
+    pub(crate) name: String,
+    pub(crate) signature: String,
+    pub(crate) generic_signature: Option<String>,
+    pub(crate) mod_bits: Int,
+    pub(crate) arg_count: Int,
+    pub(crate) lines: Vec<(Long, Int)>,
+    pub(crate) variables: Vec<MockVariable>,
+}
+
+impl MockMethod {
+    /// Creates a method with a jni signature such as `(I)V`
+    pub fn new(name: impl Into<String>, signature: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            signature: signature.into(),
+            generic_signature: None,
+            mod_bits: 0,
+            arg_count: 0,
+            lines: vec![],
+            variables: vec![],
+        }
+    }
+
+    /// Sets the generic signature of this method, such as `<T:Ljava/lang/Object;>(TT;)V`
+    pub fn generic_signature(mut self, generic_signature: impl Into<String>) -> Self {
+        self.generic_signature = Some(generic_signature.into());
+        self
+    }
+
+    /// Sets the modifier bits of this method
+    pub fn modifiers(mut self, mod_bits: Int) -> Self {
+        self.mod_bits = mod_bits;
+        self
+    }
+
+    /// Sets how many slots the arguments of this method take, including `this`
+    pub fn arguments(mut self, arg_count: Int) -> Self {
+        self.arg_count = arg_count;
+        self
+    }
+
+    /// Adds an entry to the line table, mapping a code index to the line it starts
+    pub fn line(mut self, code_index: Long, line: Int) -> Self {
+        self.lines.push((code_index, line));
+        self.lines.sort();
+        self
+    }
+
+    /// Adds a local variable. Methods without variables have no variable table.
+    pub fn variable(mut self, variable: MockVariable) -> Self {
+        self.variables.push(variable);
+        self
+    }
+
+    /// The first and last code index of this method
+    pub(crate) fn code_range(&self) -> (Long, Long) {
+        let start = self.lines.first().map_or(0, |&(index, _)| index);
+        let end = self.lines.last().map_or(0, |&(index, _)| index);
+        (start, end)
+    }
+}
+
+/// A local variable of a [MockMethod]
+#[derive(Debug, Clone)]
+pub struct MockVariable {
+    pub(crate) name: String,
+    pub(crate) signature: String,
+    pub(crate) slot: Int,
+    pub(crate) scope: Option<(Long, Int)>,
+}
+
+impl MockVariable {
+    /// Creates a variable in a slot, visible in the whole method
+    pub fn new(name: impl Into<String>, signature: impl Into<String>, slot: Int) -> Self {
+        Self {
+            name: name.into(),
+            signature: signature.into(),
+            slot,
+            scope: None,
+        }
+    }
+
+    /// Limits the variable to the code indices starting at `code_index`
+    pub fn scope(mut self, code_index: Long, length: Int) -> Self {
+        self.scope = Some((code_index, length));
+        self
+    }
+}
+
+/// A thread to start in a [MockVm]
+#[derive(Debug, Clone)]
+pub struct MockThread {
+    pub(crate) name: String,
+    pub(crate) status: ThreadStatus,
+    pub(crate) frames: Vec<MockFrame>,
+}
+
+impl MockThread {
+    /// Creates a running thread with no frames
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: ThreadStatus::Running,
+            frames: vec![],
+        }
+    }
+
+    /// Sets the status of this thread
+    pub fn status(mut self, status: ThreadStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Adds a frame below the frames given so far, so the first frame given is the current frame
+    pub fn frame(mut self, frame: MockFrame) -> Self {
+        self.frames.push(frame);
+        self
+    }
+}
+
+/// A frame on the call stack of a [MockThread]
+#[derive(Debug, Clone)]
+pub struct MockFrame {
+    pub(crate) location: Location,
+    pub(crate) this: Option<ObjectId>,
+    pub(crate) locals: BTreeMap<Int, Value>,
+}
+
+impl MockFrame {
+    /// Creates a frame executing at a location
+    pub fn new(location: Location) -> Self {
+        Self {
+            location,
+            this: None,
+            locals: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the object the frame's method was invoked on
+    pub fn this(mut self, this: ObjectId) -> Self {
+        self.this = Some(this);
+        self
+    }
+
+    /// Sets the value of a local variable slot
+    pub fn local(mut self, slot: Int, value: Value) -> Self {
+        self.locals.insert(slot, value);
+        self
+    }
+}
+
+/// An event request a debugger made with `EventRequest.Set`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventRequest {
+    pub id: Int,
+    pub event_kind: EventKind,
+    pub suspend_policy: SuspendPolicy,
+    pub modifiers: Vec<Modifier>,
+}
+
+impl EventRequest {
+    /// Gets the location of a `LocationOnly` modifier, as breakpoints have
+    pub fn location(&self) -> Option<Location> {
+        self.modifiers.iter().find_map(|modifier| match modifier {
+            Modifier::LocationOnly(location) => Some(*location),
+            _ => None,
+        })
+    }
+}
+
+/// A scriptable model of a java virtual machine.
+///
+/// Handles are cheap to clone and share the model, so a test can keep changing it while
+/// debuggers are connected.
+#[derive(Debug, Clone)]
+pub struct MockVm {
+    model: Arc<Mutex<Model>>,
+}
+
+impl Default for MockVm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockVm {
+    /// Creates a VM with `java.lang.Object`, `java.lang.String` and `java.lang.Thread` loaded, and
+    /// no threads
+    pub fn new() -> Self {
+        let vm = Self {
+            model: Arc::new(Mutex::new(Model {
+                version: VersionInfo::default(),
+                capabilities: default_capabilities(),
+                next_id: 1,
+                next_request: 1,
+                classes: BTreeMap::new(),
+                objects: HashMap::new(),
+                threads: vec![],
+                requests: vec![],
+            })),
+        };
+        let object = vm.add_class(MockClass::new(OBJECT).source_file("Object.java"));
+        vm.add_class(
+            MockClass::new(STRING)
+                .source_file("String.java")
+                .superclass(object),
+        );
+        vm.add_class(
+            MockClass::new(THREAD)
+                .source_file("Thread.java")
+                .superclass(object),
+        );
+        vm
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, Model> {
+        self.model.lock().expect("mock vm poisoned")
+    }
+
+    /// Sets the version this VM reports
+    pub fn set_version(&self, version: VersionInfo) {
+        self.lock().version = version;
+    }
+
+    /// Sets the optional capabilities this VM reports
+    pub fn set_capabilities(&self, capabilities: CapabilitiesNewReply) {
+        self.lock().capabilities = capabilities;
+    }
+
+    /// Loads a class, giving ids to it and its fields and methods
+    pub fn add_class(&self, mut class: MockClass) -> ReferenceTypeId {
+        let mut model = self.lock();
+        if class.superclass.is_none() && class.tag == TypeTag::Class && class.signature != OBJECT {
+            class.superclass = model.class_by_signature(OBJECT);
+        }
+        for (id, _) in &mut class.fields {
+            *id = FieldId::new(model.next_id());
+        }
+        for (id, _) in &mut class.methods {
+            *id = MethodId::new(model.next_id());
+        }
+        let id = model.next_id();
+        model.classes.insert(id, class);
+        ReferenceTypeId::new(id)
+    }
+
+    /// Gets a loaded class by its jni signature
+    pub fn class_by_signature(&self, signature: &str) -> Option<ReferenceTypeId> {
+        self.lock().class_by_signature(signature)
+    }
+
+    /// Gets the id of a field of a class by its name
+    pub fn field(&self, class: ReferenceTypeId, name: &str) -> Option<FieldId> {
+        let model = self.lock();
+        let class = model.classes.get(&class.get())?;
+        class
+            .fields
+            .iter()
+            .find(|(_, field)| field.name == name)
+            .map(|(id, _)| *id)
+    }
+
+    /// Gets the id of a method of a class by its name
+    pub fn method(&self, class: ReferenceTypeId, name: &str) -> Option<MethodId> {
+        let model = self.lock();
+        let class = model.classes.get(&class.get())?;
+        class
+            .methods
+            .iter()
+            .find(|(_, method)| method.name == name)
+            .map(|(id, _)| *id)
+    }
+
+    /// Gets the location of the start of a line within a method of a class
+    pub fn location(&self, class: ReferenceTypeId, method: &str, line: Int) -> Option<Location> {
+        let model = self.lock();
+        let mock_class = model.classes.get(&class.get())?;
+        let (method_id, mock_method) = mock_class
+            .methods
+            .iter()
+            .find(|(_, mock_method)| mock_method.name == method)?;
+        let (code_index, _) = mock_method
+            .lines
+            .iter()
+            .find(|&&(_, number)| number == line)?;
+        Some(Location {
+            tag: mock_class.tag,
+            class: ClassId::new(class.get()),
+            method: *method_id,
+            offset: *code_index as u64,
+        })
+    }
+
+    /// Creates an instance of a class with values for its instance fields
+    pub fn add_object(
+        &self,
+        class: ReferenceTypeId,
+        values: impl IntoIterator<Item = (FieldId, Value)>,
+    ) -> ObjectId {
+        let kind = ObjectKind::Instance(values.into_iter().collect());
+        ObjectId::new(self.lock().add_object(class.get(), kind))
+    }
+
+    /// Creates a string
+    pub fn add_string(&self, value: impl Into<String>) -> StringId {
+        StringId::new(self.lock().add_string(value.into()))
+    }
+
+    /// Creates an array with a jni signature such as `[I`, loading its array type if needed
+    pub fn add_array(&self, signature: &str, values: Vec<Value>) -> ArrayId {
+        let mut model = self.lock();
+        let class = match model.class_by_signature(signature) {
+            Some(class) => class.get(),
+            None => {
+                let id = model.next_id();
+                let mut class = MockClass::new(signature);
+                class.tag = TypeTag::Array;
+                model.classes.insert(id, class);
+                id
+            }
+        };
+        let component = signature
+            .as_bytes()
+            .get(1)
+            .and_then(|&tag| Tag::try_from(tag).ok())
+            .unwrap_or(Tag::Object);
+        ArrayId::new(model.add_object(class, ObjectKind::Array { component, values }))
+    }
+
+    /// Starts a thread, giving ids to its frames
+    pub fn add_thread(&self, thread: MockThread) -> ThreadId {
+        let mut model = self.lock();
+        let class = model
+            .class_by_signature(THREAD)
+            .expect("java.lang.Thread is always loaded")
+            .get();
+        let frames = model.frames(thread.frames);
+        let state = ThreadState {
+            name: thread.name,
+            status: thread.status,
+            suspend_count: 0,
+            frames,
+        };
+        let id = model.add_object(class, ObjectKind::Thread(state));
+        model.threads.push(id);
+        ThreadId::new(id)
+    }
+
+    /// Replaces the call stack of a thread, starting from the current frame, as the thread has
+    /// moved on. The frames get new ids.
+    pub fn set_frames(&self, thread: ThreadId, frames: Vec<MockFrame>) {
+        let mut model = self.lock();
+        let frames = model.frames(frames);
+        if let Some(state) = model.thread_mut(thread.get()) {
+            state.frames = frames;
+        }
+    }
+
+    /// Gets the ids of the frames on the call stack of a thread, starting from the current frame
+    pub fn frames(&self, thread: ThreadId) -> Vec<FrameId> {
+        let mut model = self.lock();
+        model
+            .thread_mut(thread.get())
+            .map(|state| state.frames.iter().map(|(id, _)| *id).collect())
+            .unwrap_or_default()
+    }
+
+    /// Gets how many times a thread has been suspended without being resumed
+    pub fn suspend_count(&self, thread: ThreadId) -> Option<Int> {
+        let mut model = self.lock();
+        model
+            .thread_mut(thread.get())
+            .map(|state| state.suspend_count)
+    }
+
+    /// Gets the event requests debuggers have made and not cleared
+    pub fn requests(&self) -> Vec<EventRequest> {
+        self.lock().requests.clone()
+    }
+}
+
+/// The state behind a [MockVm]
+#[derive(Debug)]
+pub(crate) struct Model {
+    pub(crate) version: VersionInfo,
+    pub(crate) capabilities: CapabilitiesNewReply,
+    next_id: u64,
+    next_request: Int,
+    /// Every loaded class, by its id
+    pub(crate) classes: BTreeMap<u64, MockClass>,
+    pub(crate) objects: HashMap<u64, MockObject>,
+    /// The ids of the threads, in the order they were started
+    pub(crate) threads: Vec<u64>,
+    pub(crate) requests: Vec<EventRequest>,
+}
+
+/// An object in the heap of the target VM
+#[derive(Debug)]
+pub(crate) struct MockObject {
+    pub(crate) class: u64,
+    pub(crate) kind: ObjectKind,
+}
+
+#[derive(Debug)]
+pub(crate) enum ObjectKind {
+    Instance(HashMap<FieldId, Value>),
+    String(String),
+    Array { component: Tag, values: Vec<Value> },
+    Thread(ThreadState),
+}
+
+/// A started thread, with its frames starting from the current frame
+#[derive(Debug)]
+pub(crate) struct ThreadState {
+    pub(crate) name: String,
+    pub(crate) status: ThreadStatus,
+    pub(crate) suspend_count: Int,
+    pub(crate) frames: Vec<(FrameId, MockFrame)>,
+}
+
+impl Model {
+    fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    pub(crate) fn next_request(&mut self) -> Int {
+        let id = self.next_request;
+        self.next_request += 1;
+        id
+    }
+
+    pub(crate) fn class_by_signature(&self, signature: &str) -> Option<ReferenceTypeId> {
+        self.classes
+            .iter()
+            .find(|(_, class)| class.signature == signature)
+            .map(|(id, _)| ReferenceTypeId::new(*id))
+    }
+
+    fn add_object(&mut self, class: u64, kind: ObjectKind) -> u64 {
+        let id = self.next_id();
+        self.objects.insert(id, MockObject { class, kind });
+        id
+    }
+
+    pub(crate) fn add_string(&mut self, value: String) -> u64 {
+        let class = self
+            .class_by_signature(STRING)
+            .expect("java.lang.String is always loaded")
+            .get();
+        self.add_object(class, ObjectKind::String(value))
+    }
+
+    fn frames(&mut self, frames: Vec<MockFrame>) -> Vec<(FrameId, MockFrame)> {
+        frames
+            .into_iter()
+            .map(|frame| (FrameId::new(self.next_id()), frame))
+            .collect()
+    }
+
+    pub(crate) fn thread_mut(&mut self, thread: u64) -> Option<&mut ThreadState> {
+        match self.objects.get_mut(&thread) {
+            Some(MockObject {
+                kind: ObjectKind::Thread(state),
+                ..
+            }) => Some(state),
+            _ => None,
+        }
+    }
+
+    /// Suspends every thread, as `VirtualMachine.Suspend` and events with the `All` policy do
+    pub(crate) fn suspend_all(&mut self) {
+        for object in self.objects.values_mut() {
+            if let ObjectKind::Thread(state) = &mut object.kind {
+                state.suspend_count += 1;
+            }
+        }
+    }
+
+    /// Resumes every suspended thread once
+    pub(crate) fn resume_all(&mut self) {
+        for object in self.objects.values_mut() {
+            if let ObjectKind::Thread(state) = &mut object.kind {
+                state.suspend_count = (state.suspend_count - 1).max(0);
+            }
+        }
+    }
+}
+
+/// The capabilities of a [MockVm] unless others are set: the events it can be asked for, since
+/// it only records event requests
+fn default_capabilities() -> CapabilitiesNewReply {
+    CapabilitiesNewReply {
+        can_watch_field_modification: true,
+        can_watch_field_access: true,
+        can_get_bytecodes: false,
+        can_get_synthetic_attribute: false,
+        can_get_owned_monitor_info: false,
+        can_get_current_contended_monitor: false,
+        can_get_monitor_info: false,
+        can_redefine_classes: false,
+        can_add_method: false,
+        can_unrestrictedly_redefine_classes: false,
+        can_pop_frames: false,
+        can_use_instance_filters: true,
+        can_get_source_debug_extension: false,
+        can_request_vm_death_event: true,
+        can_set_default_stratum: false,
+        can_get_instance_info: false,
+        can_request_monitor_events: false,
+        can_get_monitor_frame_info: false,
+        can_use_source_name_filters: true,
+        can_get_constant_pool: false,
+        can_force_early_return: false,
+    }
+}
@@ -0,0 +1,299 @@
+use futures::StreamExt;
+use jdwp_client::commands::event_request::{Modifier, Set};
+use jdwp_client::commands::method::LineTable;
+use jdwp_client::commands::reference_type::{
+    FieldsWithGeneric, GetValues, MethodsWithGeneric, SignatureWithGeneric, SourceFile,
+};
+use jdwp_client::commands::stack_frame::{self, SlotRequest};
+use jdwp_client::commands::thread_reference::{Frames, Name};
+use jdwp_client::commands::{
+    AllClassesWithGeneric, ClassesBySignatures, RedefineClasses, Suspend, Version,
+};
+use jdwp_client::events::{Event, Events};
+use jdwp_client::packet::ReplyError;
+use jdwp_client::{ClientConfig, JdwpClient};
+use jdwp_server::*;
+use jdwp_types::signature::generic::GenericType;
+use jdwp_types::{ErrorConstant, EventKind, ReferenceTypeId, SuspendPolicy, Tag, Value};
+use std::io;
+use std::time::Duration;
+use test_log::test;
+use tokio::time::{sleep, timeout};
+
+fn orders(vm: &MockVm) -> ReferenceTypeId {
+    vm.add_class(
+        MockClass::new("Lcom/acme/Orders;")
+            .source_file("Orders.java")
+            .field(MockField::new("processed", "I").static_value(Value::Int(4)))
+            .method(
+                MockMethod::new("process", "()V")
+                    .modifiers(ACC_STATIC)
+                    .line(0, 72)
+                    .line(4, 73)
+                    .line(9, 75)
+                    .variable(MockVariable::new("count", "I", 0)),
+            )
+            .method(
+                MockMethod::new("main", "([Ljava/lang/String;)V")
+                    .modifiers(ACC_STATIC)
+                    .arguments(1)
+                    .line(0, 86),
+            ),
+    )
+}
+
+fn error_constant(error: &io::Error) -> Option<ErrorConstant> {
+    ReplyError::from_io_error(error).and_then(ReplyError::error_constant)
+}
+
+#[test(tokio::test)]
+async fn test_query_vm() -> eyre::Result<()> {
+    let vm = MockVm::new();
+    let orders = orders(&vm);
+    let summary = vm.location(orders, "process", 75).expect("no line 75");
+    let call = vm.location(orders, "main", 86).expect("no line 86");
+    let main = vm.add_thread(
+        MockThread::new("main")
+            .frame(MockFrame::new(summary).local(0, Value::Int(4)))
+            .frame(MockFrame::new(call)),
+    );
+    let (transport, connection) = vm.connect();
+    let client = JdwpClient::create(transport).await?;
+
+    let version = client.send(Version).await?;
+    assert_eq!(version.name, "jdwp-server");
+    let classes = client
+        .send(ClassesBySignatures {
+            signature: "Lcom/acme/Orders;".to_string(),
+        })
+        .await?;
+    assert_eq!(classes.classes.len(), 1);
+    assert_eq!(classes.classes[0].id, orders);
+    assert!(classes.classes[0].status.prepared());
+    let source = client.send(SourceFile { ref_type: orders }).await?;
+    assert_eq!(source.source_file, "Orders.java");
+    let processed = vm.field(orders, "processed").expect("no field");
+    let statics = client
+        .send(GetValues {
+            ref_type: orders,
+            fields: vec![processed],
+        })
+        .await?;
+    assert_eq!(statics.values, vec![Value::Int(4)]);
+    let lines = client
+        .send(LineTable {
+            ref_type: orders,
+            method_id: summary.method,
+        })
+        .await?;
+    assert_eq!(
+        lines
+            .lines
+            .iter()
+            .map(|line| line.line_number)
+            .collect::<Vec<_>>(),
+        vec![72, 73, 75]
+    );
+    assert_eq!(
+        client.send(Name { thread: main }).await?.thread_name,
+        "main"
+    );
+
+    // frames can only be inspected while the thread is suspended
+    let frames = Frames {
+        thread: main,
+        start_frame: 0,
+        length: -1,
+    };
+    let error = client
+        .send(frames.clone())
+        .await
+        .expect_err("not suspended");
+    assert_eq!(
+        error_constant(&error),
+        Some(ErrorConstant::ThreadNotSuspended)
+    );
+    client.send(Suspend).await?;
+    let frames = client.send(frames).await?.frames;
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].location, summary);
+    assert_eq!(frames[1].location, call);
+    let locals = client
+        .send(stack_frame::GetValues {
+            thread: main,
+            frame: frames[0].frame_id,
+            slots: vec![SlotRequest {
+                slot: 0,
+                sig_byte: Tag::Int,
+            }],
+        })
+        .await?;
+    assert_eq!(locals.values, vec![Value::Int(4)]);
+
+    let error = client
+        .send(RedefineClasses { classes: vec![] })
+        .await
+        .expect_err("redefining classes is not implemented");
+    assert_eq!(error_constant(&error), Some(ErrorConstant::NotImplemented));
+
+    client.dispose().await?;
+    assert_eq!(vm.suspend_count(main), Some(0));
+    connection.closed().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_query_generic_signatures() -> eyre::Result<()> {
+    let vm = MockVm::new();
+    let orders = orders(&vm);
+    let boxed = vm.add_class(
+        MockClass::new("Lcom/acme/Box;")
+            .generic_signature("<T:Ljava/lang/Object;>Ljava/lang/Object;")
+            .field(
+                MockField::new("items", "Ljava/util/List;")
+                    .generic_signature("Ljava/util/List<TT;>;"),
+            )
+            .field(MockField::new("count", "I"))
+            .method(MockMethod::new("put", "(Ljava/lang/Object;)V").generic_signature("(TT;)V"))
+            .method(MockMethod::new("size", "()I")),
+    );
+    let (transport, _connection) = vm.connect();
+    let client = JdwpClient::create(transport).await?;
+
+    let classes = client.send(AllClassesWithGeneric).await?.classes;
+    let generic = |id| {
+        classes
+            .iter()
+            .find(|class| class.id == id)
+            .expect("class not found")
+            .class_signature()
+    };
+    assert_eq!(generic(orders)?, None);
+    let class = generic(boxed)?.expect("no generic signature");
+    assert_eq!(class.type_parameters[0].name, "T");
+
+    let signature = client
+        .send(SignatureWithGeneric { ref_type: boxed })
+        .await?;
+    assert_eq!(signature.java_type()?.source_name(), "com.acme.Box");
+    assert_eq!(signature.class_signature()?, Some(class));
+
+    let fields = client.send(FieldsWithGeneric { ref_type: boxed }).await?;
+    let types = fields
+        .declared
+        .iter()
+        .map(|field| Ok(field.generic_type()?.map(|ty| ty.source_name())))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    assert_eq!(types, vec![Some("java.util.List<T>".to_string()), None]);
+
+    let methods = client.send(MethodsWithGeneric { ref_type: boxed }).await?;
+    let put = methods.declared[0]
+        .generic_method_signature()?
+        .expect("no generic signature");
+    assert_eq!(put.parameters, vec![GenericType::Variable("T".to_string())]);
+    assert_eq!(put.return_type, None);
+    assert_eq!(methods.declared[1].generic_method_signature()?, None);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_emit_events() -> eyre::Result<()> {
+    let vm = MockVm::new();
+    let orders = orders(&vm);
+    let summary = vm.location(orders, "process", 75).expect("no line 75");
+    let main = vm.add_thread(MockThread::new("main").frame(MockFrame::new(summary)));
+    let (transport, connection) = vm.connect();
+    let client = JdwpClient::create(transport).await?;
+    let mut events = client.events();
+
+    let start = vec![Event::VmStart {
+        request_id: 0,
+        thread: main,
+    }];
+    connection.emit(SuspendPolicy::All, start.clone())?;
+    let received = timeout(Duration::from_secs(5), events.next()).await?;
+    assert_eq!(
+        received,
+        Some(Events {
+            policy: SuspendPolicy::All,
+            events: start,
+        })
+    );
+    assert_eq!(vm.suspend_count(main), Some(1));
+
+    let set = client
+        .send(Set {
+            event_kind: EventKind::Breakpoint,
+            suspend_policy: SuspendPolicy::EventThread,
+            modifiers: vec![Modifier::LocationOnly(summary)],
+        })
+        .await?;
+    let requests = vm.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].id, set.request_id);
+    assert_eq!(requests[0].location(), Some(summary));
+
+    connection.emit(
+        SuspendPolicy::EventThread,
+        vec![Event::Breakpoint {
+            request_id: set.request_id,
+            thread: main,
+            location: summary,
+        }],
+    )?;
+    let received = timeout(Duration::from_secs(5), events.next())
+        .await?
+        .expect("no events");
+    assert_eq!(received.events[0].request_id(), Some(set.request_id));
+    assert_eq!(vm.suspend_count(main), Some(2));
+    Ok(())
+}
+
+async fn starts(vm: &MockVm, connection: &Connection, composites: usize) -> io::Result<()> {
+    let main = vm.add_thread(MockThread::new("main"));
+    for _ in 0..composites {
+        connection.emit(
+            SuspendPolicy::None,
+            vec![Event::VmStart {
+                request_id: 0,
+                thread: main,
+            }],
+        )?;
+    }
+    // let the mock send every composite before any later reply
+    sleep(Duration::from_millis(100)).await;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_replies_arrive_while_no_one_listens() -> eyre::Result<()> {
+    let vm = MockVm::new();
+    let (transport, connection) = vm.connect();
+    let client = JdwpClient::create_with(transport, ClientConfig::new().event_capacity(2)).await?;
+
+    starts(&vm, &connection, 64).await?;
+    let version = timeout(Duration::from_secs(5), client.send(Version)).await??;
+    assert_eq!(version.name, "jdwp-server");
+
+    // the composites were held for the first listener
+    let mut events = client.events();
+    let received = timeout(Duration::from_secs(5), events.next()).await?;
+    assert!(received.is_some());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_replies_arrive_while_a_handler_is_stuck() -> eyre::Result<()> {
+    let vm = MockVm::new();
+    let (transport, connection) = vm.connect();
+    let mut client =
+        JdwpClient::create_with(transport, ClientConfig::new().event_capacity(2)).await?;
+    let _registration = client
+        .on_event(|_, _| async move { std::future::pending::<io::Result<()>>().await })
+        .await;
+
+    starts(&vm, &connection, 64).await?;
+    let version = timeout(Duration::from_secs(5), client.send(Version)).await??;
+    assert_eq!(version.name, "jdwp-server");
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use jdi_rs::event::Event;
+use jdi_rs::*;
+use jdwp_client::events;
+use jdwp_server::*;
+use jdwp_types::SuspendPolicy;
+use std::time::Duration;
+use test_log::test;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test(tokio::test)]
+async fn test_breakpoint() -> eyre::Result<()> {
+    let vm = MockVm::new();
+    let orders = vm.add_class(
+        MockClass::new("Lcom/acme/Orders;")
+            .source_file("Orders.java")
+            .method(
+                MockMethod::new("process", "()V")
+                    .modifiers(ACC_STATIC)
+                    .line(0, 72)
+                    .line(9, 75),
+            ),
+    );
+    let summary = vm.location(orders, "process", 75).expect("no line 75");
+    let main = vm.add_thread(MockThread::new("main").frame(MockFrame::new(summary)));
+    let server = JdwpServer::bind("127.0.0.1:0", vm.clone()).await?;
+
+    let address = server.local_addr()?;
+    let (connection, debugger) =
+        tokio::join!(server.accept(), VirtualMachineManager::attach(address));
+    let connection = connection?;
+    let debugger = debugger?;
+    connection.emit(
+        SuspendPolicy::All,
+        vec![events::Event::VmStart {
+            request_id: 0,
+            thread: main,
+        }],
+    )?;
+    let start = debugger
+        .event_queue()
+        .remove_timeout(TIMEOUT)
+        .await?
+        .expect("no event");
+    assert!(matches!(start.events()[0], Event::VmStart(_)));
+
+    debugger
+        .event_request_manager()
+        .set_breakpoint("com/acme/Orders.java", 75)
+        .await?;
+    let request = vm
+        .requests()
+        .into_iter()
+        .find(|request| request.location() == Some(summary))
+        .expect("the breakpoint was not requested");
+    start.resume().await?;
+
+    connection.emit(
+        request.suspend_policy,
+        vec![events::Event::Breakpoint {
+            request_id: request.id,
+            thread: main,
+            location: summary,
+        }],
+    )?;
+    let set = debugger
+        .event_queue()
+        .remove_timeout(TIMEOUT)
+        .await?
+        .expect("no event");
+    let Some(Event::Breakpoint(hit)) = set.events().first() else {
+        panic!("expected a breakpoint, got {:?}", set.events());
+    };
+    assert_eq!(hit.location().line_number().await?, Some(75));
+    assert_eq!(hit.thread().name().await?, "main");
+    let frames = hit.thread().frames().await?;
+    assert_eq!(frames.len(), 1);
+    Ok(())
+}
@@ -188,42 +188,118 @@ tagged_type! {
     }
 }
 
+tagged_type! {
+    repr: i32;
+    /// The granularity of a step
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+    pub enum StepSize {
+        /// Step by the minimum possible amount (often a bytecode instruction).
+        Min = 0,
+        /// Step to the next source line unless there is no line number information in which case a MIN step is done instead.
+        Line = 1,
+    }
+}
+
+tagged_type! {
+    repr: i32;
+    /// How a step treats method calls and returns
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+    pub enum StepDepth {
+        /// Step into any method calls that occur before the end of the step.
+        Into = 0,
+        /// Step over any method calls that occur before the end of the step.
+        Over = 1,
+        /// Step out of the current method.
+        Out = 2,
+    }
+}
+
+tagged_type! {
+    repr: i32;
+    /// The execution status of a thread
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+    pub enum ThreadStatus {
+        /// The thread has terminated, or hasn't been started yet
+        Zombie = 0,
+        /// The thread is runnable
+        Running = 1,
+        /// The thread is sleeping, in `Thread.sleep`
+        Sleeping = 2,
+        /// The thread is blocked waiting to enter a monitor
+        Monitor = 3,
+        /// The thread is waiting, in `Object.wait`
+        Wait = 4,
+    }
+}
+
 bitfield! {
-    #[derive(Clone, Copy)]
+    /// The current status of a reference type
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "serde",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(transparent)
+    )]
     pub struct ClassStatus(u32);
     impl Debug;
 
+    /// Whether the class has been verified
     pub verified, _: 0;
+    /// Whether the class has been prepared
     pub prepared, _: 1;
+    /// Whether the class has been initialized
     pub initialized, _: 2;
+    /// Whether an error occurred while preparing, verifying or initializing the class
     pub error, _: 3;
 }
 
 tagged_type! {
-    /// Suspension policy for the event
+    /// The kind of event
     #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
     pub enum EventKind {
+        /// A step has completed
         SingleStep = 1,
+        /// A breakpoint has been hit
         Breakpoint = 2,
+        /// A frame has been popped
         FramePop = 3,
+        /// An exception has been thrown
         Exception = 4,
+        /// A user defined event
         UserDefined = 5,
+        /// A thread has started
         ThreadStart = 6,
+        /// A thread has ended
         ThreadDeath = 7,
+        /// A class has been prepared
         ClassPrepare = 8,
+        /// A class has been unloaded
         ClassUnload = 9,
+        /// A class has been loaded
         ClassLoad = 10,
+        /// A watched field has been accessed
         FieldAccess = 20,
+        /// A watched field has been modified
         FieldModification = 21,
+        /// An exception has been caught
         ExceptionCatch = 30,
+        /// A method has been entered
         MethodEntry = 40,
+        /// A method has been exited
         MethodExit = 41,
+        /// A method has been exited, along with the value it returned
         MethodExitWithReturnValue = 42,
+        /// A thread is attempting to enter a monitor already acquired by another thread
         MonitorContendedEnter = 43,
+        /// A thread has entered a monitor after waiting for it to be released by another thread
         MonitorContendedEntered = 44,
+        /// A thread is about to wait on a monitor
         MonitorWait = 45,
+        /// A thread has finished waiting on a monitor
         MonitorWaited = 46,
+        /// The virtual machine has started
         VmStart = 90,
+        /// The virtual machine has terminated
         VmDeath = 99,
         /// Never sent across JDWP
         VmDisconnected = 100,
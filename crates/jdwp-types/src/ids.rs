@@ -1,6 +1,6 @@
 use std::any::type_name;
 use std::cmp::Ordering;
-use std::fmt::{Debug, Formatter, Pointer};
+use std::fmt::{Debug, Formatter};
 use std::marker::PhantomData;
 use thiserror::Error;
 
@@ -76,11 +76,24 @@ pub enum TaggedObjectConversionError {
 
 /// A tagged object Id
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        from = "serde_impls::TaggedObjectIdRepr",
+        into = "serde_impls::TaggedObjectIdRepr"
+    )
+)]
 pub struct TaggedObjectId(Tag, Id<Unknown>);
 
 impl JdwpValue for TaggedObjectId {}
 
 impl TaggedObjectId {
+    /// Creates a new tagged object id from a tag and an id of unknown type
+    pub const fn new(tag: Tag, id: Id<Unknown>) -> Self {
+        Self(tag, id)
+    }
+
     /// Gets the tag for this object id
     pub fn tag(&self) -> Tag {
         self.0
@@ -170,6 +183,73 @@ type_to_tagged!(
     ArrayId: Tag::Array
 );
 
+/// Ids are serialized along with their kind, which must match when they're deserialized
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use super::{Id, TaggedObjectId};
+    use crate::constants::Tag;
+    use crate::private::Identifiable;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::borrow::Cow;
+    use std::marker::PhantomData;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename = "Id")]
+    struct IdRepr<'a> {
+        #[serde(borrow)]
+        kind: Cow<'a, str>,
+        id: u64,
+    }
+
+    impl<T: Identifiable> Serialize for Id<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            IdRepr {
+                kind: Cow::Borrowed(T::KIND),
+                id: self.0,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, T: Identifiable> Deserialize<'de> for Id<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let repr = IdRepr::deserialize(deserializer)?;
+            if repr.kind != T::KIND {
+                return Err(D::Error::custom(format!(
+                    "expected an id of kind {}, got {}",
+                    T::KIND,
+                    repr.kind
+                )));
+            }
+            Ok(Id(repr.id, PhantomData))
+        }
+    }
+
+    /// A tagged object id, whose id has no kind but its tag
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename = "TaggedObjectId")]
+    pub(super) struct TaggedObjectIdRepr {
+        tag: Tag,
+        id: u64,
+    }
+
+    impl From<TaggedObjectId> for TaggedObjectIdRepr {
+        fn from(value: TaggedObjectId) -> Self {
+            Self {
+                tag: value.0,
+                id: value.1.get(),
+            }
+        }
+    }
+
+    impl From<TaggedObjectIdRepr> for TaggedObjectId {
+        fn from(value: TaggedObjectIdRepr) -> Self {
+            TaggedObjectId(value.tag, Id::new(value.id))
+        }
+    }
+}
+
 use crate::constants::Tag;
 use crate::private::Identifiable;
 use crate::{JdwpValue, UnknownTagError};
@@ -184,7 +264,9 @@ mod identifiable_types {
                 /// An identifiable type
                 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
                 pub enum $ty {}
-                impl Identifiable for $ty {}
+                impl Identifiable for $ty {
+                    const KIND: &'static str = stringify!($ty);
+                }
             )*
         };
     }
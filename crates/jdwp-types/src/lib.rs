@@ -9,12 +9,12 @@
 use crate::private::Repr;
 pub use constants::*;
 pub use ids::*;
-use std::ffi::c_double;
 use thiserror::Error;
 
 mod constants;
 mod ids;
 mod macros;
+pub mod signature;
 
 /// A byte value
 pub type Byte = u8;
@@ -35,7 +35,7 @@ impl JdwpValue for Long {}
 ///  - The index of the start location for the method is less than all other locations in the method.
 ///  - The index of the end location for the method is greater than all other locations in the method.
 ///  - If a line number table exists for a method, locations that belong to a particular line must
-///     fall between the line's location index and the location index of the next line in the table.
+///    fall between the line's location index and the location index of the next line in the table.
 ///
 /// Index values within a method are monotonically increasing from the first executable point in the
 /// method to the last. For many implementations, each byte-code instruction in the method has its
@@ -45,6 +45,7 @@ impl JdwpValue for Long {}
 /// interface. Almost all locations are within classes, but it is possible to have executable code
 /// in the static initializer of an interface.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Location {
     /// Type tag
     pub tag: TypeTag,
@@ -60,7 +61,8 @@ pub struct Location {
 pub trait JdwpValue {}
 
 /// Any value
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum Value {
     Array(ArrayId),
@@ -89,11 +91,15 @@ pub struct UnknownTagError<T: Repr>(T);
 mod private {
     use std::fmt::Display;
 
-    pub trait Identifiable {}
+    pub trait Identifiable {
+        /// The name of the kind of thing identified
+        const KIND: &'static str;
+    }
     pub trait Repr: Display {}
 
     impl Repr for u8 {}
     impl Repr for u16 {}
+    impl Repr for i32 {}
 }
 
 #[cfg(test)]
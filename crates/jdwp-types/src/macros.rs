@@ -12,6 +12,7 @@ macro_rules! tagged_type {
     ) => {
         #[repr($repr_ty)]
         $(#[$attr])*
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         $vis enum $name {
             $(
                 $(#[$id_attr])*
@@ -0,0 +1,580 @@
+//! JNI type signatures, such as `Ljava/lang/String;`, `[I` and `(ILjava/util/List;)V`, parsed into
+//! the types they describe. The [generic] signatures sent by the `*WithGeneric` commands have their
+//! own grammar, with type parameters, type variables and wildcards.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+
+pub mod generic;
+
+/// A primitive type
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[allow(missing_docs)]
+pub enum PrimitiveType {
+    Boolean,
+    Byte,
+    Char,
+    Short,
+    Int,
+    Long,
+    Float,
+    Double,
+}
+
+impl PrimitiveType {
+    /// Every primitive type
+    pub const ALL: [PrimitiveType; 8] = [
+        PrimitiveType::Boolean,
+        PrimitiveType::Byte,
+        PrimitiveType::Char,
+        PrimitiveType::Short,
+        PrimitiveType::Int,
+        PrimitiveType::Long,
+        PrimitiveType::Float,
+        PrimitiveType::Double,
+    ];
+
+    /// Gets the character this type is written as in signatures, such as `I` for `int`
+    pub const fn descriptor(self) -> char {
+        match self {
+            PrimitiveType::Boolean => 'Z',
+            PrimitiveType::Byte => 'B',
+            PrimitiveType::Char => 'C',
+            PrimitiveType::Short => 'S',
+            PrimitiveType::Int => 'I',
+            PrimitiveType::Long => 'J',
+            PrimitiveType::Float => 'F',
+            PrimitiveType::Double => 'D',
+        }
+    }
+
+    /// Gets the primitive type written as a character in signatures
+    pub fn from_descriptor(descriptor: char) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|primitive| primitive.descriptor() == descriptor)
+    }
+
+    /// Gets the name of this type in source, such as `int`
+    pub const fn source_name(self) -> &'static str {
+        match self {
+            PrimitiveType::Boolean => "boolean",
+            PrimitiveType::Byte => "byte",
+            PrimitiveType::Char => "char",
+            PrimitiveType::Short => "short",
+            PrimitiveType::Int => "int",
+            PrimitiveType::Long => "long",
+            PrimitiveType::Float => "float",
+            PrimitiveType::Double => "double",
+        }
+    }
+
+    /// Gets the primitive type with a name in source
+    pub fn from_source_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|primitive| primitive.source_name() == name)
+    }
+}
+
+/// A type, as described by a JNI signature
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum JavaType {
+    /// A primitive type
+    Primitive(PrimitiveType),
+    /// A class or interface, by its binary name with `/` separating its packages, such as
+    /// `java/lang/String` or `java/util/Map$Entry`
+    Class(String),
+    /// An array, by the type of its elements, which are never arrays themselves, and its number of
+    /// dimensions
+    Array {
+        /// The type of the elements
+        element: Box<JavaType>,
+        /// The number of dimensions, at least one
+        dimensions: usize,
+    },
+}
+
+impl JavaType {
+    /// Creates the type of an array with the given number of dimensions. Arrays of arrays are
+    /// flattened into a single array with more dimensions, and zero dimensions is the element type.
+    pub fn array(element: JavaType, dimensions: usize) -> JavaType {
+        match element {
+            element if dimensions == 0 => element,
+            JavaType::Array {
+                element,
+                dimensions: inner,
+            } => JavaType::Array {
+                element,
+                dimensions: inner + dimensions,
+            },
+            element => JavaType::Array {
+                element: Box::new(element),
+                dimensions,
+            },
+        }
+    }
+
+    /// Parses a type from its name in source, such as `int` or `java.lang.String[]`. Nested
+    /// classes are named by their binary name, such as `java.util.Map$Entry`.
+    pub fn from_source_name(name: &str) -> Result<Self, SignatureError> {
+        let element = name.trim_end_matches("[]");
+        let dimensions = (name.len() - element.len()) / 2;
+        let error = |position: usize, expected| SignatureError {
+            signature: name.to_string(),
+            position,
+            expected,
+        };
+        let element = match PrimitiveType::from_source_name(element) {
+            Some(primitive) => JavaType::Primitive(primitive),
+            None => {
+                let mut position = 0;
+                for part in element.split('.') {
+                    if let Some(index) = invalid_identifier(part) {
+                        return Err(error(position + index, "an identifier"));
+                    }
+                    position += part.len() + 1;
+                }
+                JavaType::Class(element.replace('.', "/"))
+            }
+        };
+        Ok(JavaType::array(element, dimensions))
+    }
+
+    /// Gets the name of this type in source, such as `int[]` for `[I` or `java.lang.String` for
+    /// `Ljava/lang/String;`
+    pub fn source_name(&self) -> String {
+        match self {
+            JavaType::Primitive(primitive) => primitive.source_name().to_string(),
+            JavaType::Class(name) => name.replace('/', "."),
+            JavaType::Array {
+                element,
+                dimensions,
+            } => element.source_name() + &"[]".repeat(*dimensions),
+        }
+    }
+}
+
+/// The keywords and literals that are never identifiers, including `void`, which is only a return
+/// type, and the names of the primitive types
+const RESERVED: [&str; 53] = [
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "try",
+    "void",
+    "volatile",
+    "while",
+    "true",
+    "false",
+    "null",
+];
+
+/// Finds where a name stops being a Java identifier, if it isn't one
+fn invalid_identifier(name: &str) -> Option<usize> {
+    if RESERVED.contains(&name) {
+        return Some(0);
+    }
+    let mut chars = name.char_indices();
+    match chars.next() {
+        Some((_, first)) if first.is_alphabetic() || first == '_' || first == '$' => {}
+        _ => return Some(0),
+    }
+    chars
+        .find(|&(_, c)| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .map(|(index, _)| index)
+}
+
+impl FromStr for JavaType {
+    type Err = SignatureError;
+
+    fn from_str(signature: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(signature);
+        let java_type = parser.java_type()?;
+        parser.finish()?;
+        Ok(java_type)
+    }
+}
+
+/// Writes the JNI signature of the type
+impl Display for JavaType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JavaType::Primitive(primitive) => write!(f, "{}", primitive.descriptor()),
+            JavaType::Class(name) => write!(f, "L{name};"),
+            JavaType::Array {
+                element,
+                dimensions,
+            } => write!(f, "{}{element}", "[".repeat(*dimensions)),
+        }
+    }
+}
+
+/// The signature of a method: the types of its parameters, and the type it returns
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct MethodSignature {
+    /// The types of the parameters
+    pub parameters: Vec<JavaType>,
+    /// The type returned, or [None] for `void`
+    pub return_type: Option<JavaType>,
+}
+
+impl FromStr for MethodSignature {
+    type Err = SignatureError;
+
+    fn from_str(signature: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(signature);
+        parser.expect('(', "`(`")?;
+        let mut parameters = vec![];
+        while !parser.eat(')') {
+            parameters.push(parser.java_type()?);
+        }
+        let return_type = parser.return_type(Parser::java_type)?;
+        parser.finish()?;
+        Ok(Self {
+            parameters,
+            return_type,
+        })
+    }
+}
+
+/// Writes the JNI signature of the method
+impl Display for MethodSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(")?;
+        for parameter in &self.parameters {
+            write!(f, "{parameter}")?;
+        }
+        match &self.return_type {
+            Some(return_type) => write!(f, "){return_type}"),
+            None => write!(f, ")V"),
+        }
+    }
+}
+
+/// A signature couldn't be parsed
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+#[error("Invalid signature {signature:?}: expected {expected} at {position}")]
+pub struct SignatureError {
+    signature: String,
+    position: usize,
+    expected: &'static str,
+}
+
+impl SignatureError {
+    /// Gets the signature that couldn't be parsed
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    /// Gets the byte offset into the signature of the error
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+/// Reads a signature one character at a time
+struct Parser<'a> {
+    signature: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(signature: &'a str) -> Self {
+        Self {
+            signature,
+            position: 0,
+        }
+    }
+
+    fn error(&self, expected: &'static str) -> SignatureError {
+        SignatureError {
+            signature: self.signature.to_string(),
+            position: self.position,
+            expected,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.signature[self.position..].chars().next()
+    }
+
+    fn next(&mut self, expected: &'static str) -> Result<char, SignatureError> {
+        let c = self.peek().ok_or_else(|| self.error(expected))?;
+        self.position += c.len_utf8();
+        Ok(c)
+    }
+
+    /// Consumes a character, if it's next
+    fn eat(&mut self, c: char) -> bool {
+        let next = self.peek() == Some(c);
+        if next {
+            self.position += c.len_utf8();
+        }
+        next
+    }
+
+    fn expect(&mut self, c: char, expected: &'static str) -> Result<(), SignatureError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    /// Checks the whole signature was read
+    fn finish(&self) -> Result<(), SignatureError> {
+        match self.peek() {
+            Some(_) => Err(self.error("the end of the signature")),
+            None => Ok(()),
+        }
+    }
+
+    /// Reads a name up to one of the characters that can't be in it
+    fn identifier(&mut self, delimiters: &[char]) -> Result<&'a str, SignatureError> {
+        let rest = &self.signature[self.position..];
+        let length = rest.find(delimiters).unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.error("a name"));
+        }
+        self.position += length;
+        Ok(&rest[..length])
+    }
+
+    /// Reads the dimensions of an array
+    fn dimensions(&mut self) -> usize {
+        let mut dimensions = 0;
+        while self.eat('[') {
+            dimensions += 1;
+        }
+        dimensions
+    }
+
+    fn java_type(&mut self) -> Result<JavaType, SignatureError> {
+        let dimensions = self.dimensions();
+        let element = match self.next("a type")? {
+            'L' => {
+                let start = self.position;
+                loop {
+                    self.identifier(&['.', ';', '[', '/'])?;
+                    if !self.eat('/') {
+                        break;
+                    }
+                }
+                let name = &self.signature[start..self.position];
+                self.expect(';', "`;`")?;
+                JavaType::Class(name.to_string())
+            }
+            c => match PrimitiveType::from_descriptor(c) {
+                Some(primitive) => JavaType::Primitive(primitive),
+                None => {
+                    self.position -= c.len_utf8();
+                    return Err(self.error("a type"));
+                }
+            },
+        };
+        Ok(JavaType::array(element, dimensions))
+    }
+
+    /// Reads the type a method returns, which is [None] for `void`
+    fn return_type<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, SignatureError>,
+    ) -> Result<Option<T>, SignatureError> {
+        if self.eat('V') {
+            Ok(None)
+        } else {
+            parse(self).map(Some)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_parse_types() {
+        let string = JavaType::Class("java/lang/String".to_string());
+        assert_eq!("Ljava/lang/String;".parse(), Ok(string.clone()));
+        assert_eq!(
+            "[[I".parse(),
+            Ok(JavaType::array(JavaType::Primitive(PrimitiveType::Int), 2))
+        );
+        assert_eq!(
+            "(ILjava/util/List;)V".parse(),
+            Ok(MethodSignature {
+                parameters: vec![
+                    JavaType::Primitive(PrimitiveType::Int),
+                    JavaType::Class("java/util/List".to_string()),
+                ],
+                return_type: None,
+            })
+        );
+        assert_eq!(
+            "()[Ljava/lang/String;".parse(),
+            Ok(MethodSignature {
+                parameters: vec![],
+                return_type: Some(JavaType::array(string, 1)),
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_signatures() {
+        for (signature, position) in [
+            ("", 0),
+            ("Ljava/lang/String", 17),
+            ("Ljava//String;", 6),
+            ("[", 1),
+            ("II", 1),
+            ("V", 0),
+            ("Q", 0),
+        ] {
+            let error = signature
+                .parse::<JavaType>()
+                .expect_err("signature should be invalid");
+            assert_eq!(error.position(), position, "{error}");
+        }
+        for signature in ["(I", "I)V", "(V)V", "()"] {
+            signature
+                .parse::<MethodSignature>()
+                .expect_err("signature should be invalid");
+        }
+    }
+
+    #[test]
+    fn test_source_names() {
+        let strings = JavaType::from_source_name("java.lang.String[][]").expect("a valid name");
+        assert_eq!(strings.to_string(), "[[Ljava/lang/String;");
+        assert_eq!(strings.source_name(), "java.lang.String[][]");
+        assert_eq!(
+            JavaType::from_source_name("int"),
+            Ok(JavaType::Primitive(PrimitiveType::Int))
+        );
+        for name in [
+            "",
+            "java..String",
+            "[]",
+            "java.lang.String[",
+            "java/lang/String",
+            "void",
+            "void[]",
+            "java.lang. String",
+            "java.lang.String ",
+            "java.lang.Str-ing",
+            "java.1lang.String",
+            "java.lang.int",
+        ] {
+            JavaType::from_source_name(name).expect_err("name should be invalid");
+        }
+    }
+
+    pub(super) fn identifier() -> impl Strategy<Value = String> {
+        "[a-zA-Z_$][a-zA-Z0-9_$]{0,6}"
+            .prop_filter("a keyword", |name| !RESERVED.contains(&name.as_str()))
+    }
+
+    pub(super) fn primitive() -> impl Strategy<Value = PrimitiveType> {
+        proptest::sample::select(PrimitiveType::ALL.to_vec())
+    }
+
+    fn java_type() -> impl Strategy<Value = JavaType> {
+        let class = proptest::collection::vec(identifier(), 1..4)
+            .prop_map(|names| JavaType::Class(names.join("/")));
+        let element = prop_oneof![primitive().prop_map(JavaType::Primitive), class];
+        (element, 0..4usize).prop_map(|(element, dimensions)| JavaType::array(element, dimensions))
+    }
+
+    proptest! {
+        #[test]
+        fn types_round_trip(java_type in java_type()) {
+            prop_assert_eq!(java_type.to_string().parse::<JavaType>(), Ok(java_type.clone()));
+            prop_assert_eq!(JavaType::from_source_name(&java_type.source_name()), Ok(java_type));
+        }
+
+        #[test]
+        fn names_with_invalid_characters_are_rejected(
+            names in proptest::collection::vec(identifier(), 1..4),
+            index in any::<proptest::sample::Index>(),
+            invalid in "[ \t;/\\[\\]()<>+*-]",
+        ) {
+            let mut name = names.join(".");
+            name.insert_str(index.index(name.len() + 1), &invalid);
+            prop_assert!(JavaType::from_source_name(&name).is_err(), "{} should be invalid", name);
+        }
+
+        #[test]
+        fn names_with_invalid_identifiers_are_rejected(
+            mut names in proptest::collection::vec(identifier(), 1..4),
+            index in any::<proptest::sample::Index>(),
+            invalid in prop_oneof![
+                // a primitive name on its own is a valid type
+                proptest::sample::select(RESERVED.to_vec())
+                    .prop_filter("a primitive", |name| PrimitiveType::from_source_name(name).is_none())
+                    .prop_map(String::from),
+                ("[0-9]", identifier()).prop_map(|(digit, name)| digit + &name),
+            ],
+            dimensions in 0..3usize,
+        ) {
+            let segment = index.index(names.len());
+            names[segment] = invalid;
+            let name = names.join(".") + &"[]".repeat(dimensions);
+            prop_assert!(JavaType::from_source_name(&name).is_err(), "{} should be invalid", name);
+        }
+
+        #[test]
+        fn method_signatures_round_trip(
+            parameters in proptest::collection::vec(java_type(), 0..5),
+            return_type in proptest::option::of(java_type()),
+        ) {
+            let signature = MethodSignature { parameters, return_type };
+            prop_assert_eq!(signature.to_string().parse::<MethodSignature>(), Ok(signature));
+        }
+    }
+}
@@ -0,0 +1,651 @@
+//! Generic signatures, as sent by the `*WithGeneric` commands for the classes, methods, fields and
+//! local variables declared with generic types, such as `Ljava/util/List<TT;>;` or
+//! `<T:Ljava/lang/Object;>(TT;)V`
+
+use super::{JavaType, Parser, PrimitiveType, SignatureError};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The characters that end a name in a generic signature
+const DELIMITERS: [char; 8] = ['.', ';', '[', '/', '<', '>', ':', '^'];
+
+/// A type in a generic signature
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum GenericType {
+    /// A primitive type
+    Primitive(PrimitiveType),
+    /// A class or interface, with its type arguments
+    Class(ClassTypeSignature),
+    /// A type variable, by its name, such as `T`
+    Variable(String),
+    /// An array, by the type of its elements, which are never arrays themselves, and its number of
+    /// dimensions
+    Array {
+        /// The type of the elements
+        element: Box<GenericType>,
+        /// The number of dimensions, at least one
+        dimensions: usize,
+    },
+}
+
+impl GenericType {
+    /// Creates the type of an array with the given number of dimensions. Arrays of arrays are
+    /// flattened into a single array with more dimensions, and zero dimensions is the element type.
+    pub fn array(element: GenericType, dimensions: usize) -> GenericType {
+        match element {
+            element if dimensions == 0 => element,
+            GenericType::Array {
+                element,
+                dimensions: inner,
+            } => GenericType::Array {
+                element,
+                dimensions: inner + dimensions,
+            },
+            element => GenericType::Array {
+                element: Box::new(element),
+                dimensions,
+            },
+        }
+    }
+
+    /// Gets the name of this type in source, such as `java.util.List<? extends T>[]`
+    pub fn source_name(&self) -> String {
+        match self {
+            GenericType::Primitive(primitive) => primitive.source_name().to_string(),
+            GenericType::Class(class) => class.source_name(),
+            GenericType::Variable(name) => name.clone(),
+            GenericType::Array {
+                element,
+                dimensions,
+            } => element.source_name() + &"[]".repeat(*dimensions),
+        }
+    }
+}
+
+impl FromStr for GenericType {
+    type Err = SignatureError;
+
+    fn from_str(signature: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(signature);
+        let generic_type = parser.generic_type()?;
+        parser.finish()?;
+        Ok(generic_type)
+    }
+}
+
+/// Writes the generic signature of the type
+impl Display for GenericType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenericType::Primitive(primitive) => write!(f, "{}", primitive.descriptor()),
+            GenericType::Class(class) => write!(f, "{class}"),
+            GenericType::Variable(name) => write!(f, "T{name};"),
+            GenericType::Array {
+                element,
+                dimensions,
+            } => write!(f, "{}{element}", "[".repeat(*dimensions)),
+        }
+    }
+}
+
+/// A class or interface, with the type arguments of it and the classes enclosing it
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ClassTypeSignature {
+    /// The package of the class with `/` separating its parts, such as `java/util`, or empty for
+    /// the default package
+    pub package: String,
+    /// The outermost class, followed by the classes nested in it down to this class
+    pub classes: Vec<SimpleClassType>,
+}
+
+impl ClassTypeSignature {
+    /// Gets the class without its type arguments
+    pub fn erasure(&self) -> JavaType {
+        let names = self
+            .classes
+            .iter()
+            .map(|class| class.name.as_str())
+            .collect::<Vec<_>>()
+            .join("$");
+        match self.package.as_str() {
+            "" => JavaType::Class(names),
+            package => JavaType::Class(format!("{package}/{names}")),
+        }
+    }
+
+    /// Gets the name of this class in source, such as `java.util.Map<K, V>`
+    pub fn source_name(&self) -> String {
+        let mut name = self.package.replace('/', ".");
+        for (index, class) in self.classes.iter().enumerate() {
+            if index > 0 || !name.is_empty() {
+                name.push('.');
+            }
+            name.push_str(&class.name);
+            if !class.arguments.is_empty() {
+                let arguments = class
+                    .arguments
+                    .iter()
+                    .map(TypeArgument::source_name)
+                    .collect::<Vec<_>>();
+                name.push_str(&format!("<{}>", arguments.join(", ")));
+            }
+        }
+        name
+    }
+}
+
+/// Writes the generic signature of the class
+impl Display for ClassTypeSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "L")?;
+        if !self.package.is_empty() {
+            write!(f, "{}/", self.package)?;
+        }
+        for (index, class) in self.classes.iter().enumerate() {
+            if index > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{}", class.name)?;
+            if !class.arguments.is_empty() {
+                write!(f, "<")?;
+                for argument in &class.arguments {
+                    write!(f, "{argument}")?;
+                }
+                write!(f, ">")?;
+            }
+        }
+        write!(f, ";")
+    }
+}
+
+/// A class, by its simple name, with its type arguments
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct SimpleClassType {
+    /// The name of the class, without its package or the classes enclosing it
+    pub name: String,
+    /// The type arguments, empty if there are none
+    pub arguments: Vec<TypeArgument>,
+}
+
+/// An argument for a type parameter
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum TypeArgument {
+    /// The unbounded wildcard `?`
+    Any,
+    /// A type
+    Exact(GenericType),
+    /// A wildcard bounded from above, `? extends T`
+    Extends(GenericType),
+    /// A wildcard bounded from below, `? super T`
+    Super(GenericType),
+}
+
+impl TypeArgument {
+    /// Gets the argument as it's written in source, such as `? extends T`
+    pub fn source_name(&self) -> String {
+        match self {
+            TypeArgument::Any => "?".to_string(),
+            TypeArgument::Exact(bound) => bound.source_name(),
+            TypeArgument::Extends(bound) => format!("? extends {}", bound.source_name()),
+            TypeArgument::Super(bound) => format!("? super {}", bound.source_name()),
+        }
+    }
+}
+
+/// Writes the generic signature of the argument
+impl Display for TypeArgument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeArgument::Any => write!(f, "*"),
+            TypeArgument::Exact(bound) => write!(f, "{bound}"),
+            TypeArgument::Extends(bound) => write!(f, "+{bound}"),
+            TypeArgument::Super(bound) => write!(f, "-{bound}"),
+        }
+    }
+}
+
+/// A type parameter declared by a class or method, such as `T extends Comparable<? super T>`
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct TypeParameter {
+    /// The name of the parameter
+    pub name: String,
+    /// The class bounding the parameter, if any
+    pub class_bound: Option<GenericType>,
+    /// The interfaces bounding the parameter
+    pub interface_bounds: Vec<GenericType>,
+}
+
+/// Writes the generic signature of the parameter
+impl Display for TypeParameter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:", self.name)?;
+        if let Some(bound) = &self.class_bound {
+            write!(f, "{bound}")?;
+        }
+        for bound in &self.interface_bounds {
+            write!(f, ":{bound}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a list of type parameters, if there are any
+fn write_type_parameters(f: &mut Formatter<'_>, parameters: &[TypeParameter]) -> std::fmt::Result {
+    if !parameters.is_empty() {
+        write!(f, "<")?;
+        for parameter in parameters {
+            write!(f, "{parameter}")?;
+        }
+        write!(f, ">")?;
+    }
+    Ok(())
+}
+
+/// The generic signature of a class: its type parameters, and the classes it extends and
+/// implements
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ClassSignature {
+    /// The type parameters, empty if there are none
+    pub type_parameters: Vec<TypeParameter>,
+    /// The superclass
+    pub superclass: ClassTypeSignature,
+    /// The interfaces implemented
+    pub interfaces: Vec<ClassTypeSignature>,
+}
+
+impl FromStr for ClassSignature {
+    type Err = SignatureError;
+
+    fn from_str(signature: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(signature);
+        let type_parameters = parser.type_parameters()?;
+        let superclass = parser.class_type()?;
+        let mut interfaces = vec![];
+        while parser.peek().is_some() {
+            interfaces.push(parser.class_type()?);
+        }
+        Ok(Self {
+            type_parameters,
+            superclass,
+            interfaces,
+        })
+    }
+}
+
+/// Writes the generic signature of the class
+impl Display for ClassSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_type_parameters(f, &self.type_parameters)?;
+        write!(f, "{}", self.superclass)?;
+        for interface in &self.interfaces {
+            write!(f, "{interface}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The generic signature of a method: its type parameters, the types of its parameters, the type it
+/// returns and the exceptions it throws
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct GenericMethodSignature {
+    /// The type parameters, empty if there are none
+    pub type_parameters: Vec<TypeParameter>,
+    /// The types of the parameters
+    pub parameters: Vec<GenericType>,
+    /// The type returned, or [None] for `void`
+    pub return_type: Option<GenericType>,
+    /// The exceptions thrown, which are classes or type variables
+    pub throws: Vec<GenericType>,
+}
+
+impl FromStr for GenericMethodSignature {
+    type Err = SignatureError;
+
+    fn from_str(signature: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(signature);
+        let type_parameters = parser.type_parameters()?;
+        parser.expect('(', "`(`")?;
+        let mut parameters = vec![];
+        while !parser.eat(')') {
+            parameters.push(parser.generic_type()?);
+        }
+        let return_type = parser.return_type(Parser::generic_type)?;
+        let mut throws = vec![];
+        while parser.eat('^') {
+            throws.push(match parser.peek() {
+                Some('T') => parser.generic_type()?,
+                _ => GenericType::Class(parser.class_type()?),
+            });
+        }
+        parser.finish()?;
+        Ok(Self {
+            type_parameters,
+            parameters,
+            return_type,
+            throws,
+        })
+    }
+}
+
+/// Writes the generic signature of the method
+impl Display for GenericMethodSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_type_parameters(f, &self.type_parameters)?;
+        write!(f, "(")?;
+        for parameter in &self.parameters {
+            write!(f, "{parameter}")?;
+        }
+        match &self.return_type {
+            Some(return_type) => write!(f, "){return_type}")?,
+            None => write!(f, ")V")?,
+        }
+        for throws in &self.throws {
+            write!(f, "^{throws}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Parser<'_> {
+    fn generic_type(&mut self) -> Result<GenericType, SignatureError> {
+        let dimensions = self.dimensions();
+        let element = match self.peek() {
+            Some('L') => GenericType::Class(self.class_type()?),
+            Some('T') => {
+                self.next("a type")?;
+                let name = self.identifier(&DELIMITERS)?;
+                self.expect(';', "`;`")?;
+                GenericType::Variable(name.to_string())
+            }
+            Some(c) => match PrimitiveType::from_descriptor(c) {
+                Some(primitive) => {
+                    self.next("a type")?;
+                    GenericType::Primitive(primitive)
+                }
+                None => return Err(self.error("a type")),
+            },
+            None => return Err(self.error("a type")),
+        };
+        Ok(GenericType::array(element, dimensions))
+    }
+
+    /// Reads a type that isn't primitive, as type arguments and bounds are
+    fn reference_type(&mut self) -> Result<GenericType, SignatureError> {
+        let start = self.position;
+        match self.generic_type()? {
+            GenericType::Primitive(_) => {
+                self.position = start;
+                Err(self.error("a reference type"))
+            }
+            reference => Ok(reference),
+        }
+    }
+
+    fn class_type(&mut self) -> Result<ClassTypeSignature, SignatureError> {
+        self.expect('L', "`L`")?;
+        let mut package = vec![];
+        let mut name = self.identifier(&DELIMITERS)?;
+        while self.eat('/') {
+            package.push(name);
+            name = self.identifier(&DELIMITERS)?;
+        }
+        let mut classes = vec![];
+        loop {
+            let mut arguments = vec![];
+            if self.eat('<') {
+                while !self.eat('>') {
+                    arguments.push(self.type_argument()?);
+                }
+                if arguments.is_empty() {
+                    return Err(self.error("a type argument"));
+                }
+            }
+            classes.push(SimpleClassType {
+                name: name.to_string(),
+                arguments,
+            });
+            if !self.eat('.') {
+                break;
+            }
+            name = self.identifier(&DELIMITERS)?;
+        }
+        self.expect(';', "`;`")?;
+        Ok(ClassTypeSignature {
+            package: package.join("/"),
+            classes,
+        })
+    }
+
+    fn type_argument(&mut self) -> Result<TypeArgument, SignatureError> {
+        if self.eat('*') {
+            Ok(TypeArgument::Any)
+        } else if self.eat('+') {
+            self.reference_type().map(TypeArgument::Extends)
+        } else if self.eat('-') {
+            self.reference_type().map(TypeArgument::Super)
+        } else {
+            self.reference_type().map(TypeArgument::Exact)
+        }
+    }
+
+    /// Reads the type parameters of a class or method, if there are any
+    fn type_parameters(&mut self) -> Result<Vec<TypeParameter>, SignatureError> {
+        let mut parameters = vec![];
+        if self.eat('<') {
+            while !self.eat('>') {
+                let name = self.identifier(&DELIMITERS)?.to_string();
+                self.expect(':', "`:`")?;
+                let class_bound = match self.peek() {
+                    Some('L' | 'T' | '[') => Some(self.reference_type()?),
+                    _ => None,
+                };
+                let mut interface_bounds = vec![];
+                while self.eat(':') {
+                    interface_bounds.push(self.reference_type()?);
+                }
+                parameters.push(TypeParameter {
+                    name,
+                    class_bound,
+                    interface_bounds,
+                });
+            }
+            if parameters.is_empty() {
+                return Err(self.error("a type parameter"));
+            }
+        }
+        Ok(parameters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{identifier, primitive};
+    use super::*;
+    use proptest::prelude::*;
+
+    fn class(package: &str, name: &str, arguments: Vec<TypeArgument>) -> GenericType {
+        GenericType::Class(ClassTypeSignature {
+            package: package.to_string(),
+            classes: vec![SimpleClassType {
+                name: name.to_string(),
+                arguments,
+            }],
+        })
+    }
+
+    fn variable(name: &str) -> GenericType {
+        GenericType::Variable(name.to_string())
+    }
+
+    #[test]
+    fn test_parse_method() {
+        let signature = "<T::Ljava/lang/Comparable<-TT;>;>(TT;Ljava/util/List<+TT;>;[I)TT;^Ljava/io/IOException;";
+        let parsed: GenericMethodSignature = signature.parse().expect("a valid signature");
+        assert_eq!(
+            parsed,
+            GenericMethodSignature {
+                type_parameters: vec![TypeParameter {
+                    name: "T".to_string(),
+                    class_bound: None,
+                    interface_bounds: vec![class(
+                        "java/lang",
+                        "Comparable",
+                        vec![TypeArgument::Super(variable("T"))]
+                    )],
+                }],
+                parameters: vec![
+                    variable("T"),
+                    class(
+                        "java/util",
+                        "List",
+                        vec![TypeArgument::Extends(variable("T"))]
+                    ),
+                    GenericType::array(GenericType::Primitive(PrimitiveType::Int), 1),
+                ],
+                return_type: Some(variable("T")),
+                throws: vec![class("java/io", "IOException", vec![])],
+            }
+        );
+        assert_eq!(parsed.to_string(), signature);
+        assert_eq!(
+            parsed.parameters[1].source_name(),
+            "java.util.List<? extends T>"
+        );
+    }
+
+    #[test]
+    fn test_parse_class() {
+        let signature = "<K:Ljava/lang/Object;V:Ljava/lang/Object;>Ljava/util/AbstractMap<TK;TV;>;Ljava/util/Map<TK;TV;>;";
+        let parsed: ClassSignature = signature.parse().expect("a valid signature");
+        assert_eq!(parsed.type_parameters.len(), 2);
+        assert_eq!(parsed.interfaces.len(), 1);
+        assert_eq!(
+            parsed.superclass.source_name(),
+            "java.util.AbstractMap<K, V>"
+        );
+        assert_eq!(parsed.to_string(), signature);
+    }
+
+    #[test]
+    fn test_nested_classes() {
+        let parsed: GenericType = "Lcom/example/Outer<TT;>.Inner<*>;"
+            .parse()
+            .expect("a valid signature");
+        let GenericType::Class(class) = &parsed else {
+            panic!("expected a class, got {parsed:?}");
+        };
+        assert_eq!(class.source_name(), "com.example.Outer<T>.Inner<?>");
+        assert_eq!(
+            class.erasure(),
+            JavaType::Class("com/example/Outer$Inner".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_signatures() {
+        for signature in ["Ljava/util/List<>;", "Ljava/util/List<I>;", "TT", "T;", "Q"] {
+            signature
+                .parse::<GenericType>()
+                .expect_err("signature should be invalid");
+        }
+        for signature in ["<>()V", "<T>()V", "<T:I>()V", "()V^I", "()V^"] {
+            signature
+                .parse::<GenericMethodSignature>()
+                .expect_err("signature should be invalid");
+        }
+    }
+
+    fn class_type(
+        argument: impl Strategy<Value = TypeArgument> + Clone,
+    ) -> impl Strategy<Value = ClassTypeSignature> {
+        let simple = (identifier(), proptest::collection::vec(argument, 0..3))
+            .prop_map(|(name, arguments)| SimpleClassType { name, arguments });
+        (
+            proptest::collection::vec(identifier(), 0..3),
+            proptest::collection::vec(simple, 1..3),
+        )
+            .prop_map(|(package, classes)| ClassTypeSignature {
+                package: package.join("/"),
+                classes,
+            })
+    }
+
+    fn reference_type() -> impl Strategy<Value = GenericType> {
+        let leaf = prop_oneof![
+            identifier().prop_map(GenericType::Variable),
+            class_type(Just(TypeArgument::Any)).prop_map(GenericType::Class),
+        ];
+        leaf.prop_recursive(3, 16, 3, |inner| {
+            let argument = prop_oneof![
+                Just(TypeArgument::Any),
+                inner.clone().prop_map(TypeArgument::Exact),
+                inner.clone().prop_map(TypeArgument::Extends),
+                inner.clone().prop_map(TypeArgument::Super),
+            ];
+            prop_oneof![
+                class_type(argument).prop_map(GenericType::Class),
+                (inner, 1..3usize)
+                    .prop_map(|(element, dimensions)| { GenericType::array(element, dimensions) }),
+                (primitive(), 1..3usize).prop_map(|(primitive, dimensions)| {
+                    GenericType::array(GenericType::Primitive(primitive), dimensions)
+                }),
+            ]
+        })
+    }
+
+    fn generic_type() -> impl Strategy<Value = GenericType> {
+        prop_oneof![
+            primitive().prop_map(GenericType::Primitive),
+            reference_type(),
+        ]
+    }
+
+    fn type_parameters() -> impl Strategy<Value = Vec<TypeParameter>> {
+        let parameter = (
+            identifier(),
+            proptest::option::of(reference_type()),
+            proptest::collection::vec(reference_type(), 0..2),
+        )
+            .prop_map(|(name, class_bound, interface_bounds)| TypeParameter {
+                name,
+                // a parameter without bounds can run into the next one, so javac always gives one
+                class_bound: match (class_bound, interface_bounds.is_empty()) {
+                    (None, true) => Some(class("java/lang", "Object", vec![])),
+                    (class_bound, _) => class_bound,
+                },
+                interface_bounds,
+            });
+        proptest::collection::vec(parameter, 0..3)
+    }
+
+    proptest! {
+        #[test]
+        fn types_round_trip(generic_type in generic_type()) {
+            prop_assert_eq!(generic_type.to_string().parse::<GenericType>(), Ok(generic_type));
+        }
+
+        #[test]
+        fn class_signatures_round_trip(
+            type_parameters in type_parameters(),
+            superclass in class_type(Just(TypeArgument::Any)),
+            interfaces in proptest::collection::vec(class_type(Just(TypeArgument::Any)), 0..3),
+        ) {
+            let signature = ClassSignature { type_parameters, superclass, interfaces };
+            prop_assert_eq!(signature.to_string().parse::<ClassSignature>(), Ok(signature));
+        }
+
+        #[test]
+        fn method_signatures_round_trip(
+            type_parameters in type_parameters(),
+            parameters in proptest::collection::vec(generic_type(), 0..4),
+            return_type in proptest::option::of(generic_type()),
+            throws in proptest::collection::vec(prop_oneof![
+                identifier().prop_map(GenericType::Variable),
+                class_type(Just(TypeArgument::Any)).prop_map(GenericType::Class),
+            ], 0..3),
+        ) {
+            let signature = GenericMethodSignature { type_parameters, parameters, return_type, throws };
+            prop_assert_eq!(signature.to_string().parse::<GenericMethodSignature>(), Ok(signature));
+        }
+    }
+}